@@ -0,0 +1,219 @@
+//! Batch command - runs the prompts of a file without TUI.
+//!
+//! Each line of the input file is one prompt: either a JSON object with a
+//! `prompt` and optional `id` and `model`, or plain text. Every prompt runs in
+//! a new session through the same agentic loop as `opencode prompt`, and one
+//! result object is written per input, in input order.
+
+use super::prompt::{self, PromptOutcome};
+use crate::provider;
+use anyhow::{Context, Result};
+use futures::stream::{self, StreamExt};
+use serde::Deserialize;
+use std::path::Path;
+
+/// One prompt of the input file
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct BatchInput {
+    /// Caller's identifier, echoed in the result
+    #[serde(default)]
+    pub id: Option<serde_json::Value>,
+    pub prompt: String,
+    /// Model for this prompt, overriding `--model`
+    #[serde(default)]
+    pub model: Option<String>,
+}
+
+/// Parse the input file, skipping blank lines
+///
+/// Lines starting with `{` must be JSON objects; any other line is taken as
+/// the prompt text.
+pub fn parse_inputs(content: &str) -> Result<Vec<BatchInput>> {
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            if line.trim_start().starts_with('{') {
+                serde_json::from_str(line).with_context(|| format!("line {}", i + 1))
+            } else {
+                Ok(BatchInput {
+                    id: None,
+                    prompt: line.to_string(),
+                    model: None,
+                })
+            }
+        })
+        .collect()
+}
+
+/// Result object of one prompt
+fn result_json(
+    index: usize,
+    input: &BatchInput,
+    outcome: &Result<PromptOutcome>,
+) -> serde_json::Value {
+    let mut result = serde_json::json!({
+        "index": index,
+        "id": input.id,
+        "ok": outcome.is_ok(),
+    });
+    match outcome {
+        Ok(outcome) => {
+            result["session_id"] = outcome.session_id.clone().into();
+            result["model"] = outcome.model.clone().into();
+            result["steps"] = outcome.steps.into();
+            result["output"] = provider::structured::last_assistant_text(&outcome.messages).into();
+            if let Some(value) = &outcome.structured_output {
+                result["structured_output"] = value.clone();
+            }
+        }
+        Err(e) => result["error"] = format!("{:#}", e).into(),
+    }
+    result
+}
+
+/// Run the prompts of a file and print one result per prompt
+///
+/// `format` is `ndjson` (a line per result, printed as results come in) or
+/// `json` (one array at the end). Up to `concurrency` prompts run at once. The
+/// first failure stops the batch unless `continue_on_error` is set; either
+/// way the command fails when any prompt did.
+pub async fn execute(
+    file: &Path,
+    model: Option<&str>,
+    model_fallbacks: &[String],
+    format: &str,
+    json_schema: Option<&Path>,
+    concurrency: usize,
+    continue_on_error: bool,
+) -> Result<()> {
+    if !matches!(format, "ndjson" | "json") {
+        anyhow::bail!("Unknown batch format '{}'; use ndjson or json", format);
+    }
+    let content = tokio::fs::read_to_string(file)
+        .await
+        .with_context(|| format!("Failed to read {}", file.display()))?;
+    let inputs =
+        parse_inputs(&content).with_context(|| format!("Invalid prompt in {}", file.display()))?;
+
+    let (config, json_schema) = prompt::prepare(json_schema).await?;
+    let (config, json_schema) = (&config, json_schema.as_ref());
+
+    let mut outcomes = stream::iter(inputs.iter().enumerate())
+        .map(|(index, input)| async move {
+            let model = input.model.as_deref().or(model);
+            let outcome = prompt::run(
+                config,
+                &input.prompt,
+                model,
+                model_fallbacks,
+                format,
+                json_schema,
+            )
+            .await;
+            (index, input, outcome)
+        })
+        .buffered(concurrency.max(1));
+
+    let mut results = Vec::new();
+    let (mut finished, mut failed) = (0, 0);
+    while let Some((index, input, outcome)) = outcomes.next().await {
+        finished += 1;
+        let result = result_json(index, input, &outcome);
+        if format == "ndjson" {
+            println!("{}", result);
+        } else {
+            results.push(result);
+        }
+        if outcome.is_err() {
+            failed += 1;
+            if !continue_on_error {
+                break;
+            }
+        }
+    }
+
+    if format == "json" {
+        println!("{}", serde_json::to_string_pretty(&results)?);
+    }
+    if finished < inputs.len() {
+        anyhow::bail!(
+            "Stopped at a failed prompt; {} of {} prompts did not finish",
+            inputs.len() - finished,
+            inputs.len()
+        );
+    }
+    if failed > 0 {
+        anyhow::bail!("{} of {} prompts failed", failed, inputs.len());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_inputs() {
+        let content = r#"{"id": 1, "prompt": "Fix the build", "model": "anthropic/claude-sonnet-4"}
+
+Explain {this} file
+{"prompt": "Add tests"}
+"#;
+        let inputs = parse_inputs(content).unwrap();
+        assert_eq!(inputs.len(), 3);
+        assert_eq!(inputs[0].id, Some(serde_json::json!(1)));
+        assert_eq!(
+            inputs[0].model.as_deref(),
+            Some("anthropic/claude-sonnet-4")
+        );
+        assert_eq!(inputs[1].prompt, "Explain {this} file");
+        assert_eq!(inputs[2].id, None);
+
+        let err = parse_inputs("ok\n{\"id\": 2}").unwrap_err();
+        assert_eq!(err.to_string(), "line 2");
+    }
+
+    #[test]
+    fn test_result_json() {
+        let input = BatchInput {
+            id: Some(serde_json::json!("a")),
+            prompt: "hi".to_string(),
+            model: None,
+        };
+        let outcome = Ok(PromptOutcome {
+            session_id: "ses_1".to_string(),
+            model: "openai/gpt-4o".to_string(),
+            messages: vec![provider::ChatMessage {
+                role: "assistant".to_string(),
+                content: provider::ChatContent::Text("hello".to_string()),
+            }],
+            steps: 1,
+            structured_output: None,
+        });
+        assert_eq!(
+            result_json(0, &input, &outcome),
+            serde_json::json!({
+                "index": 0,
+                "id": "a",
+                "ok": true,
+                "session_id": "ses_1",
+                "model": "openai/gpt-4o",
+                "steps": 1,
+                "output": "hello",
+            })
+        );
+
+        let failed = Err(anyhow::anyhow!("No API key for provider: openai"));
+        assert_eq!(
+            result_json(3, &input, &failed),
+            serde_json::json!({
+                "index": 3,
+                "id": "a",
+                "ok": false,
+                "error": "No API key for provider: openai",
+            })
+        );
+    }
+}
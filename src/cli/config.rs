@@ -1,6 +1,10 @@
 //! Config management CLI commands.
 
 use crate::config::Config;
+use crate::lint::{self, Warning};
+use crate::provider;
+use crate::redact::Redactor;
+use crate::slash_command::loader;
 use anyhow::Result;
 
 /// Show current configuration
@@ -75,3 +79,107 @@ pub async fn init() -> Result<()> {
     );
     Ok(())
 }
+
+/// Set a key of the global config file
+///
+/// The value is parsed as JSON, so numbers, booleans and objects keep their
+/// type; anything else is taken as a string.
+pub async fn set(key: &str, value: &str) -> Result<()> {
+    let value = serde_json::from_str(value)
+        .unwrap_or_else(|_| serde_json::Value::String(value.to_string()));
+    let config_path = Config::set_global_value(key, value).await?;
+    println!("Set {} in {}", key, config_path.display());
+    Ok(())
+}
+
+/// Print the effective value of a key
+///
+/// Strings are printed as they are, so the output can be used in scripts;
+/// other values are printed as JSON.
+pub async fn get(key: &str) -> Result<()> {
+    let config = Config::load().await?;
+    match config.value_at(key) {
+        Some(serde_json::Value::String(value)) => println!("{}", value),
+        Some(value) => println!("{}", serde_json::to_string_pretty(&value)?),
+        None => anyhow::bail!("{} is not set", key),
+    }
+    Ok(())
+}
+
+/// Remove a key from the global config file
+pub async fn unset(key: &str) -> Result<()> {
+    let config_path = Config::unset_global_value(key).await?;
+    println!("Unset {} in {}", key, config_path.display());
+    Ok(())
+}
+
+/// Print the effective definition of a model as a config snippet
+pub async fn export_model(model: &str) -> Result<()> {
+    let config = Config::load().await?;
+    provider::registry().initialize(&config).await?;
+
+    let (provider_id, model_id) = provider::parse_model_string(model)
+        .ok_or_else(|| anyhow::anyhow!("Invalid model '{}', expected provider/model", model))?;
+    let provider = provider::registry()
+        .get(&provider_id)
+        .await
+        .ok_or_else(|| anyhow::anyhow!("Provider not found: {}", provider_id))?;
+    let model = provider
+        .models
+        .get(&model_id)
+        .ok_or_else(|| anyhow::anyhow!("Model not found: {}/{}", provider_id, model_id))?;
+
+    let custom = config
+        .provider
+        .as_ref()
+        .and_then(|providers| providers.get(&provider_id))
+        .and_then(|cfg| cfg.source.as_deref())
+        == Some("custom");
+    let mut snippet = provider::model_config(&provider, model, custom);
+    Redactor::from_config(&config).0.redact_value(&mut snippet);
+
+    println!("{}", serde_json::to_string_pretty(&snippet)?);
+    Ok(())
+}
+
+/// Check custom definitions and print the problems found
+///
+/// Fails when there are any, so the check can gate CI.
+pub async fn lint() -> Result<()> {
+    let config = Config::load().await?;
+    provider::registry().initialize(&config).await?;
+    let catalog: lint::Catalog = provider::registry()
+        .list()
+        .await
+        .into_iter()
+        .map(|p| (p.id, p.models.into_keys().collect()))
+        .collect();
+
+    let mut warnings = Vec::new();
+    let mut command_files = Vec::new();
+    for dir in loader::find_opencode_directories().await? {
+        let Some(base_path) = dir.parent() else {
+            continue;
+        };
+        for (command_dir, path) in loader::command_files(base_path) {
+            let location = path.display().to_string();
+            match loader::read_command_file(&command_dir, &path).await {
+                Ok((_, command)) => command_files.push((location, command)),
+                Err(e) => warnings.push(Warning {
+                    location,
+                    message: format!("{:#}", e),
+                }),
+            }
+        }
+    }
+    warnings.extend(lint::lint(&config, &command_files, &catalog));
+
+    if warnings.is_empty() {
+        println!("No problems found");
+        return Ok(());
+    }
+    for warning in &warnings {
+        println!("{}", warning);
+    }
+    anyhow::bail!("{} problem(s) found", warnings.len())
+}
@@ -0,0 +1,312 @@
+//! Doctor command - diagnoses common setup problems.
+//!
+//! Checks the configuration, the storage directory, provider credentials,
+//! network access to the providers and the terminal, and prints one line per
+//! check with a hint on how to fix what failed.
+
+use crate::config::Config;
+use crate::provider::{self, Provider};
+use anyhow::Result;
+use std::fmt;
+use std::io::IsTerminal;
+use std::path::Path;
+use std::time::Duration;
+
+/// How long to wait for a provider to answer
+const NETWORK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Smallest terminal the TUI is usable in, in columns and rows
+const MIN_TERMINAL_SIZE: (u16, u16) = (60, 15);
+
+/// Outcome of a check
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Pass,
+    Warn,
+    Fail,
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Status::Pass => "PASS",
+            Status::Warn => "WARN",
+            Status::Fail => "FAIL",
+        })
+    }
+}
+
+/// Result of one check
+#[derive(Debug, Clone, PartialEq)]
+pub struct Check {
+    pub name: String,
+    pub status: Status,
+    pub detail: String,
+    /// What to do about a warning or failure
+    pub hint: Option<String>,
+}
+
+impl Check {
+    fn pass(name: &str, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            status: Status::Pass,
+            detail: detail.into(),
+            hint: None,
+        }
+    }
+
+    fn warn(name: &str, detail: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self {
+            status: Status::Warn,
+            hint: Some(hint.into()),
+            ..Self::pass(name, detail)
+        }
+    }
+
+    fn fail(name: &str, detail: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self {
+            status: Status::Fail,
+            ..Self::warn(name, detail, hint)
+        }
+    }
+}
+
+impl fmt::Display for Check {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}  {}: {}", self.status, self.name, self.detail)?;
+        if let Some(hint) = &self.hint {
+            write!(f, "\n      hint: {}", hint)?;
+        }
+        Ok(())
+    }
+}
+
+/// Run all checks and print the results
+///
+/// Fails when any check failed, so the command can be scripted.
+pub async fn execute() -> Result<()> {
+    let mut checks = Vec::new();
+    let config = match Config::load().await {
+        Ok(config) => {
+            checks.push(check_config_file().await);
+            Some(config)
+        }
+        Err(e) => {
+            checks.push(Check::fail(
+                "config",
+                format!("{:#}", e),
+                "fix the file's syntax; `opencode config path` shows where it is",
+            ));
+            None
+        }
+    };
+    checks.push(check_storage(crate::storage::global().base_path()).await);
+
+    match &config {
+        Some(config) => match provider::registry().initialize(config).await {
+            Ok(()) => {
+                let mut available = provider::registry().list_available().await;
+                available.sort_by(|a, b| a.id.cmp(&b.id));
+                checks.push(check_credentials(&available));
+                checks.extend(check_network(&available).await);
+            }
+            Err(e) => checks.push(Check::fail(
+                "providers",
+                format!("{:#}", e),
+                "run `opencode models refresh` or check the `provider` section of the config",
+            )),
+        },
+        None => checks.push(Check::warn(
+            "providers",
+            "skipped, the config did not load",
+            "fix the config first",
+        )),
+    }
+
+    let size = crossterm::terminal::size().ok();
+    let term = std::env::var("TERM").ok();
+    checks.push(check_terminal(
+        std::io::stdout().is_terminal(),
+        term.as_deref(),
+        size,
+    ));
+
+    for check in &checks {
+        println!("{}", check);
+    }
+    let failed = checks.iter().filter(|c| c.status == Status::Fail).count();
+    if failed > 0 {
+        anyhow::bail!("{} check(s) failed", failed);
+    }
+    Ok(())
+}
+
+async fn check_config_file() -> Check {
+    if Config::file_exists().await {
+        return Check::pass("config", "loaded");
+    }
+    Check::warn(
+        "config",
+        "no config file, using defaults and environment variables",
+        "run `opencode config init` to create one",
+    )
+}
+
+/// Whether files can be created in the storage root
+async fn check_storage(base: &Path) -> Check {
+    let hint =
+        "make the directory writable, or point OPENCODE_DATA_DIR or `storage.path` elsewhere";
+    if let Err(e) = tokio::fs::create_dir_all(base).await {
+        return Check::fail("storage", format!("{}: {}", base.display(), e), hint);
+    }
+    let probe = base.join(".doctor-probe");
+    let written = tokio::fs::write(&probe, b"ok").await;
+    let _ = tokio::fs::remove_file(&probe).await;
+    match written {
+        Ok(()) => Check::pass("storage", format!("{} is writable", base.display())),
+        Err(e) => Check::fail("storage", format!("{}: {}", base.display(), e), hint),
+    }
+}
+
+fn check_credentials(available: &[Provider]) -> Check {
+    if available.is_empty() {
+        return Check::fail(
+            "credentials",
+            "no provider has an API key",
+            "set ANTHROPIC_API_KEY or OPENAI_API_KEY, or connect a provider with `/connect`",
+        );
+    }
+    let ids: Vec<&str> = available.iter().map(|p| p.id.as_str()).collect();
+    Check::pass("credentials", ids.join(", "))
+}
+
+/// URL requests to a provider go to, if it is one opencode can talk to
+fn endpoint(provider: &Provider) -> Option<String> {
+    match provider.id.as_str() {
+        "anthropic" => return Some("https://api.anthropic.com".to_string()),
+        "copilot" => return Some("https://api.githubcopilot.com".to_string()),
+        _ => {}
+    }
+    let configured = provider
+        .models
+        .values()
+        .filter_map(|m| m.api.url.clone())
+        .min();
+    configured
+        .or_else(|| (provider.id == "openai").then(|| "https://api.openai.com/v1".to_string()))
+}
+
+/// Whether each provider with credentials answers at all
+///
+/// Any HTTP response counts; only failing to connect is a problem.
+async fn check_network(available: &[Provider]) -> Vec<Check> {
+    if provider::offline::is_offline() {
+        return vec![Check::warn(
+            "network",
+            "skipped in offline mode",
+            "drop --offline or OPENCODE_OFFLINE to check the providers",
+        )];
+    }
+    let checks = available.iter().filter_map(|provider| {
+        let url = endpoint(provider)?;
+        Some(async move {
+            let name = format!("network {}", provider.id);
+            let client = reqwest::Client::builder()
+                .timeout(NETWORK_TIMEOUT)
+                .tls_danger_accept_invalid_certs(provider.insecure_skip_verify)
+                .build();
+            let response = match client {
+                Ok(client) => client.get(&url).send().await,
+                Err(e) => return Check::fail(&name, format!("{:#}", e), "report this as a bug"),
+            };
+            match response {
+                Ok(_) => Check::pass(&name, format!("{} is reachable", url)),
+                Err(e) => Check::fail(
+                    &name,
+                    format!("{}: {}", url, e),
+                    "check the connection, proxy settings (HTTPS_PROXY) and the provider's `base_url`",
+                ),
+            }
+        })
+    });
+    futures::future::join_all(checks).await
+}
+
+fn check_terminal(is_tty: bool, term: Option<&str>, size: Option<(u16, u16)>) -> Check {
+    if !is_tty {
+        return Check::warn(
+            "terminal",
+            "output is not a terminal",
+            "run `opencode` from a terminal for the TUI; `opencode prompt` works without one",
+        );
+    }
+    let term = term.unwrap_or("");
+    if term.is_empty() || term == "dumb" {
+        return Check::warn(
+            "terminal",
+            format!("TERM is '{}'", term),
+            "set TERM to your terminal's type, e.g. xterm-256color",
+        );
+    }
+    let Some((columns, rows)) = size else {
+        return Check::warn(
+            "terminal",
+            "could not read the terminal size",
+            "see Troubleshooting in the README",
+        );
+    };
+    let (min_columns, min_rows) = MIN_TERMINAL_SIZE;
+    if columns < min_columns || rows < min_rows {
+        return Check::warn(
+            "terminal",
+            format!("{}x{} is small for the TUI", columns, rows),
+            format!("use at least {}x{}", min_columns, min_rows),
+        );
+    }
+    Check::pass("terminal", format!("{}, {}x{}", term, columns, rows))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_terminal() {
+        let check = check_terminal(true, Some("xterm-256color"), Some((120, 40)));
+        assert_eq!(check.to_string(), "PASS  terminal: xterm-256color, 120x40");
+
+        let check = check_terminal(true, Some("dumb"), Some((120, 40)));
+        assert_eq!(
+            check.to_string(),
+            "WARN  terminal: TERM is 'dumb'\n      hint: set TERM to your terminal's type, e.g. xterm-256color"
+        );
+        assert_eq!(
+            check_terminal(true, Some("xterm"), Some((40, 10))).status,
+            Status::Warn
+        );
+        assert_eq!(check_terminal(false, None, None).status, Status::Warn);
+    }
+
+    #[tokio::test]
+    async fn test_check_storage() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = dir.path().join("data");
+        let check = check_storage(&base).await;
+        assert_eq!(check.status, Status::Pass);
+        assert!(base.is_dir());
+        assert_eq!(std::fs::read_dir(&base).unwrap().count(), 0);
+
+        // A file where the directory should be
+        let file = dir.path().join("file");
+        std::fs::write(&file, "").unwrap();
+        let check = check_storage(&file.join("data")).await;
+        assert_eq!(check.status, Status::Fail);
+        assert!(check.hint.unwrap().contains("OPENCODE_DATA_DIR"));
+    }
+
+    #[test]
+    fn test_check_credentials() {
+        assert_eq!(check_credentials(&[]).status, Status::Fail);
+    }
+}
@@ -1,7 +1,12 @@
 //! CLI command implementations.
 
+pub mod batch;
 pub mod config;
+pub mod doctor;
+pub mod models;
 pub mod prompt;
 pub mod run;
 pub mod serve;
 pub mod session;
+pub mod storage;
+pub mod tools;
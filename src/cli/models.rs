@@ -0,0 +1,38 @@
+//! Models CLI commands.
+
+use crate::config::Config;
+use crate::provider;
+use anyhow::Result;
+
+/// Re-fetch models.dev, update the cache and reload the provider registry
+pub async fn refresh(provider_id: Option<&str>) -> Result<()> {
+    let changes = provider::refresh_now(provider_id).await?;
+
+    let config = Config::load().await?;
+    provider::registry().reload(&config).await?;
+
+    if changes.is_empty() {
+        println!("Models are up to date.");
+    }
+
+    for change in &changes {
+        println!("{}:", change.provider_id);
+        for model in &change.added {
+            println!("  + {}", model);
+        }
+        for model in &change.removed {
+            println!("  - {}", model);
+        }
+    }
+
+    let mut providers = provider::registry().list().await;
+    providers.sort_by(|a, b| a.id.cmp(&b.id));
+    for p in providers
+        .iter()
+        .filter(|p| provider_id.is_none_or(|id| id == p.id))
+    {
+        println!("{}: {} models available", p.id, p.models.len());
+    }
+
+    Ok(())
+}
@@ -3,13 +3,20 @@
 use crate::config::Config;
 use crate::permission::PermissionChecker;
 use crate::provider::{
-    self, ChatContent, ChatMessage, ContentPart, OpenAIRequest, StreamEvent, ToolDefinition,
+    self, AnthropicRequest, ChatContent, ChatMessage, ContentPart, FinishReason, Model, ModelCost,
+    ModelOverloaded, OpenAIRequest, RequestRefused, StreamEvent, ToolDefinition,
+};
+use crate::session::{
+    self, CreateSessionOptions, Message, MessageError, ModelRef, Session, TokenUsage,
 };
-use crate::session::{CreateSessionOptions, ModelRef, Session};
 use crate::tool::{self, DoomLoopDetector, PendingToolCall, ToolCallTracker, ToolContext};
 use anyhow::Result;
+use std::path::Path;
 use tokio::sync::mpsc;
 
+/// Formats `opencode prompt` can print its result in
+const OUTPUT_FORMATS: &[&str] = &["text", "json", "markdown"];
+
 /// Context for prompt execution
 struct PromptContext {
     provider_id: String,
@@ -17,12 +24,28 @@ struct PromptContext {
     api_key: String,
     model_api_id: String,
     model_api_url: Option<String>,
+    /// Whether the model is served over the OpenAI chat completions API
+    openai_compatible: bool,
+    /// Whether the model accepts images; others get a text note instead
+    accepts_images: bool,
     max_tokens: u64,
+    /// Request field for `max_tokens` on OpenAI-compatible models
+    max_tokens_field: &'static str,
+    /// Prices of the current model, for per-step cost
+    model_cost: ModelCost,
+    /// Request options of the provider and model config
+    request_options: serde_json::Map<String, serde_json::Value>,
     tool_defs: Vec<ToolDefinition>,
     tool_ctx: ToolContext,
     permission_checker: PermissionChecker,
     format: String,
     system_prompt: String,
+    json_schema: Option<serde_json::Value>,
+    client: provider::StreamingClient,
+    /// Max output tokens override from the config
+    max_output: Option<u64>,
+    /// Models to try when the current one is overloaded
+    fallbacks: std::vec::IntoIter<(String, String)>,
 }
 
 /// Result of processing a stream
@@ -30,12 +53,123 @@ struct StreamResult {
     response_text: String,
     pending_calls: Vec<PendingToolCall>,
     finish_reason: String,
+    input_tokens: u64,
+    output_tokens: u64,
+    /// Reason the provider refused the request or filtered the response
+    refusal: Option<String>,
+}
+
+/// Result of running a prompt through the agentic loop
+pub struct PromptOutcome {
+    pub session_id: String,
+    /// Model the prompt was sent to; a fallback may have answered instead
+    pub model: String,
+    pub messages: Vec<ChatMessage>,
+    pub steps: i32,
+    /// Final answer validated against the JSON schema, when one was given
+    pub structured_output: Option<serde_json::Value>,
 }
 
 /// Execute a single prompt without TUI (with agentic loop)
-pub async fn execute(prompt: &str, model: Option<&str>, format: &str) -> Result<()> {
+///
+/// `model_fallbacks` replaces the configured `model_fallbacks` when not empty.
+/// Without a `format`, the config's `output.default_format` is used, else
+/// `text`.
+pub async fn execute(
+    prompt: &str,
+    model: Option<&str>,
+    model_fallbacks: &[String],
+    format: Option<&str>,
+    json_schema: Option<&Path>,
+) -> Result<()> {
+    // Check the format before the providers are set up, which may take a
+    // network round trip
+    let config = Config::load().await?;
+    let format = format
+        .or(config
+            .output
+            .as_ref()
+            .and_then(|output| output.default_format.as_deref()))
+        .unwrap_or("text")
+        .to_string();
+    if !OUTPUT_FORMATS.contains(&format.as_str()) {
+        anyhow::bail!(
+            "Unknown output format '{}'; use {}",
+            format,
+            OUTPUT_FORMATS.join(", ")
+        );
+    }
+
+    let json_schema = prepare_config(&config, json_schema).await?;
+    let outcome = run(
+        &config,
+        prompt,
+        model,
+        model_fallbacks,
+        &format,
+        json_schema.as_ref(),
+    )
+    .await?;
+
+    output_result(
+        &outcome.messages,
+        outcome.steps,
+        &format,
+        outcome.structured_output.as_ref(),
+    );
+    Ok(())
+}
+
+/// Load the config and JSON schema and initialize the providers, once for
+/// all the prompts of an invocation
+pub async fn prepare(json_schema: Option<&Path>) -> Result<(Config, Option<serde_json::Value>)> {
+    let config = Config::load().await?;
+    let json_schema = prepare_config(&config, json_schema).await?;
+    Ok((config, json_schema))
+}
+
+/// Load the JSON schema and initialize the providers for a loaded config
+async fn prepare_config(
+    config: &Config,
+    json_schema: Option<&Path>,
+) -> Result<Option<serde_json::Value>> {
+    // Load the schema up front so a bad file fails before any request is made
+    let json_schema = match json_schema {
+        Some(path) => Some(provider::structured::load_schema(path).await?),
+        None => None,
+    };
+
+    provider::registry().initialize(config).await?;
+    tool::registry().initialize(config).await;
+    Ok(json_schema)
+}
+
+/// Run a prompt in a new session through the agentic loop
+///
+/// Progress and the streamed reply are printed only in the `text` format.
+pub async fn run(
+    config: &Config,
+    prompt: &str,
+    model: Option<&str>,
+    model_fallbacks: &[String],
+    format: &str,
+    json_schema: Option<&serde_json::Value>,
+) -> Result<PromptOutcome> {
+    // Expand prompt macros such as `!file:` before anything is sent or saved
+    let prompt = &session::macros::expand(prompt, config, &std::env::current_dir()?).await?;
+
     // Initialize context
-    let (ctx, mut session) = initialize_context(model, format).await?;
+    let (mut ctx, mut session) = initialize_context(
+        config,
+        prompt,
+        model,
+        model_fallbacks,
+        format,
+        json_schema.cloned(),
+    )
+    .await?;
+    // A fallback only serves this prompt; the session keeps the chosen model
+    let (provider_id, model_id) = (ctx.provider_id.clone(), ctx.model_id.clone());
 
     // Initialize conversation history
     let mut messages: Vec<ChatMessage> = vec![ChatMessage {
@@ -43,13 +177,13 @@ pub async fn execute(prompt: &str, model: Option<&str>, format: &str) -> Result<
         content: ChatContent::Text(prompt.to_string()),
     }];
 
-    // Create streaming client
-    let client = provider::StreamingClient::new();
-
     // Agentic loop
     let mut step = 0;
     let max_steps = 20; // Increased from 10 to allow more complex workflows
     let mut doom_detector = DoomLoopDetector::new();
+    let mut prefill = None;
+    let mut json_retries = 0;
+    let mut structured_output = None;
 
     loop {
         step += 1;
@@ -67,81 +201,131 @@ pub async fn execute(prompt: &str, model: Option<&str>, format: &str) -> Result<
             eprintln!("\n[Agentic step {}/{}]", step, max_steps);
         }
 
-        // Stream the response
-        let rx = create_provider_stream(&client, &ctx, &messages).await?;
-
-        if ctx.format == "text" && step == 1 {
-            // Don't print anything for first step
-        }
-
-        // Process the stream
-        let result = process_stream(rx, &ctx.format).await?;
+        tool::pair_tool_results(&mut messages);
+        ctx.start_step().await;
+
+        // Stream the response (structured output is printed once validated),
+        // moving down the fallback chain while the model is overloaded
+        let result = loop {
+            let rx = create_provider_stream(&ctx.client, &ctx, &messages, prefill.clone()).await?;
+            match process_stream(rx, &ctx.format, ctx.json_schema.is_none()).await {
+                Ok(result) => break result,
+                Err(e) => match e.downcast::<ModelOverloaded>() {
+                    Ok(overloaded) => ctx.fall_back(overloaded).await?,
+                    Err(e) => return Err(e),
+                },
+            }
+        };
+        prefill = None;
+        ctx.finish_step(&result).await;
 
         // Handle the result
         let should_continue =
             handle_stream_result(&ctx, &mut messages, result, &mut doom_detector).await?;
 
-        if ctx.format == "text" && !should_continue {
-            eprintln!("[Agentic loop complete]");
+        if should_continue {
+            continue;
         }
 
-        if !should_continue {
-            break;
+        if let Some(schema) = &ctx.json_schema {
+            match provider::structured::check_output(&messages, schema) {
+                Ok(value) => structured_output = Some(value),
+                Err(e) if json_retries < provider::structured::MAX_RETRIES => {
+                    json_retries += 1;
+                    if ctx.format == "text" {
+                        eprintln!("[Invalid structured output, retrying: {}]", e);
+                    }
+                    messages.push(provider::structured::correction_message(&e));
+                    prefill = provider::structured::retry_prefill(&ctx.provider_id);
+                    continue;
+                }
+                Err(e) => {
+                    return Err(anyhow::anyhow!(
+                        "Output does not match the JSON schema: {}",
+                        e
+                    ))
+                }
+            }
+        }
+
+        if ctx.format == "text" {
+            eprintln!("[Agentic loop complete]");
         }
+        break;
     }
 
-    // Output and save
-    output_result(&messages, step, format);
-    save_model_to_session(&mut session, &ctx.provider_id, &ctx.model_id).await;
+    if ctx.json_schema.is_some() && structured_output.is_none() {
+        return Err(anyhow::anyhow!(
+            "No structured output produced within {} steps",
+            max_steps
+        ));
+    }
 
-    Ok(())
+    save_model_to_session(&mut session, &provider_id, &model_id).await;
+
+    Ok(PromptOutcome {
+        session_id: session.id,
+        model: format!("{}/{}", provider_id, model_id),
+        messages,
+        steps: step,
+        structured_output,
+    })
 }
 
 /// Initialize the prompt context with config, provider, and tools
-async fn initialize_context(model: Option<&str>, format: &str) -> Result<(PromptContext, Session)> {
-    // Load configuration
-    let config = Config::load().await?;
-
-    // Initialize provider registry
-    provider::registry().initialize(&config).await?;
-
+async fn initialize_context(
+    config: &Config,
+    prompt: &str,
+    model: Option<&str>,
+    model_fallbacks: &[String],
+    format: &str,
+    json_schema: Option<serde_json::Value>,
+) -> Result<(PromptContext, Session)> {
     // Create a session
     let session = Session::create(CreateSessionOptions::default()).await?;
 
     // Resolve model
-    let (provider_id, model_id) = resolve_model(model, &session, &config).await?;
+    let (provider_id, model_id) = resolve_model(model, &session, config).await?;
 
-    // Get model info
-    let model_info = provider::registry()
-        .get_model(&provider_id, &model_id)
-        .await
-        .ok_or_else(|| anyhow::anyhow!("Model not found: {}/{}", provider_id, model_id))?;
+    if json_schema.is_some() && !provider::structured::is_supported(&provider_id) {
+        anyhow::bail!(
+            "Structured output is not supported by provider: {}",
+            provider_id
+        );
+    }
 
-    // Get API key
-    let provider_info = provider::registry()
-        .get(&provider_id)
-        .await
-        .ok_or_else(|| anyhow::anyhow!("Provider not found: {}", provider_id))?;
+    let (model_info, api_key, client, request_options) = connect(&provider_id, &model_id).await?;
+    let openai_compatible = provider::is_openai_compatible(&provider_id, &model_info);
 
-    let api_key = provider_info
-        .key
-        .ok_or_else(|| anyhow::anyhow!("No API key for provider: {}", provider_id))?;
+    let fallbacks = if model_fallbacks.is_empty() {
+        config.model_fallbacks.clone().unwrap_or_default()
+    } else {
+        model_fallbacks.to_vec()
+    };
+    let fallbacks = provider::fallback_chain(&fallbacks, &provider_id, &model_id);
 
     // Create permission checker
-    let permission_checker = PermissionChecker::from_config(&config);
+    let permission_checker =
+        PermissionChecker::from_config(config).with_session(session.permission.as_ref());
 
     // Create CLI permission handler using shared implementation
-    let permission_handler = crate::permission_state::create_cli_permission_handler();
+    let permission_handler =
+        crate::permission_state::create_cli_permission_handler(permission_checker.clone());
 
-    // Create tool context
+    // Persist the prompt so tool calls are recorded against this session
     let cwd = std::env::current_dir()?.to_string_lossy().to_string();
-    let tool_ctx = ToolContext::new("cli-session", "msg-1")
+    let message_id =
+        Message::save_prompt(&session.id, prompt, &provider_id, &model_id, &cwd).await?;
+
+    // Create tool context
+    let tool_ctx = ToolContext::new(&session.id, &message_id)
         .with_cwd(cwd.clone())
         .with_root(cwd.clone())
-        .with_permission_handler(permission_handler);
+        .with_permission_handler(permission_handler)
+        .with_permission_checker(permission_checker.clone());
 
     // Generate system prompt
-    let system_prompt = crate::session::system::generate(&cwd, &provider_id, &model_id);
+    let system_prompt = crate::session::system::load(config, &cwd, &provider_id, &model_id)?;
 
     // Get tool definitions
     let tools = tool::registry().definitions().await;
@@ -161,27 +345,164 @@ async fn initialize_context(model: Option<&str>, format: &str) -> Result<(Prompt
             api_key,
             model_api_id: model_info.api.id.clone(),
             model_api_url: model_info.api.url.clone(),
-            max_tokens: model_info.limit.output,
+            openai_compatible,
+            accepts_images: model_info
+                .capabilities
+                .supports(provider::Capability::ImageInput),
+            max_tokens_field: provider::openai_max_tokens_field(&model_info),
+            model_cost: model_info.cost.clone(),
+            request_options,
+            max_tokens: provider::max_output_tokens(&model_info, config.max_output_tokens),
             tool_defs,
             tool_ctx,
             permission_checker,
             format: format.to_string(),
             system_prompt,
+            json_schema,
+            client,
+            max_output: config.max_output_tokens,
+            fallbacks: fallbacks.into_iter(),
         },
         session,
     ))
 }
 
+/// Look up a model and create a client for its provider
+async fn connect(
+    provider_id: &str,
+    model_id: &str,
+) -> Result<(
+    Model,
+    String,
+    provider::StreamingClient,
+    serde_json::Map<String, serde_json::Value>,
+)> {
+    // Get model info
+    let model_info = provider::registry()
+        .get_model(provider_id, model_id)
+        .await
+        .ok_or_else(|| anyhow::anyhow!("Model not found: {}/{}", provider_id, model_id))?;
+
+    // Get API key
+    let provider_info = provider::registry()
+        .get(provider_id)
+        .await
+        .ok_or_else(|| anyhow::anyhow!("Provider not found: {}", provider_id))?;
+
+    // Create streaming client (honors per-provider TLS options)
+    let client = provider::StreamingClient::for_provider(&provider_info)?;
+
+    let request_options = provider::options::merged(&provider_info.options, &model_info.options);
+    let api_key = provider_info
+        .key
+        .ok_or_else(|| anyhow::anyhow!("No API key for provider: {}", provider_id))?;
+
+    Ok((model_info, api_key, client, request_options))
+}
+
+impl PromptContext {
+    /// Record the start of a step on the assistant message
+    async fn start_step(&self) {
+        let (session_id, message_id) = (&self.tool_ctx.session_id, &self.tool_ctx.message_id);
+        if let Err(e) = session::record_step_start(session_id, message_id).await {
+            tracing::warn!("Failed to persist step start: {}", e);
+        }
+    }
+
+    /// Record the finish of a step with its usage, priced by the serving model
+    async fn finish_step(&self, result: &StreamResult) {
+        let (session_id, message_id) = (&self.tool_ctx.session_id, &self.tool_ctx.message_id);
+        let cost =
+            provider::usage_cost(&self.model_cost, result.input_tokens, result.output_tokens);
+        let tokens = TokenUsage {
+            input: result.input_tokens,
+            output: result.output_tokens,
+            ..Default::default()
+        };
+        if let Err(e) =
+            session::record_step_finish(session_id, message_id, &result.finish_reason, tokens, cost)
+                .await
+        {
+            tracing::warn!("Failed to persist step finish: {}", e);
+        }
+    }
+
+    /// Save a refusal on the assistant message, so it is not taken for an
+    /// empty answer
+    async fn record_refusal(&self, error: &anyhow::Error) {
+        let Some(refused) = error.downcast_ref::<RequestRefused>() else {
+            return;
+        };
+        let (session_id, message_id) = (&self.tool_ctx.session_id, &self.tool_ctx.message_id);
+        let error = MessageError::Refused {
+            message: refused.0.clone(),
+        };
+        if let Err(e) = session::record_message_error(session_id, message_id, error).await {
+            tracing::warn!("Failed to persist refusal: {}", e);
+        }
+    }
+
+    /// Switch to the next usable fallback model after an overload
+    ///
+    /// Fails with the overload error once the chain is exhausted.
+    async fn fall_back(&mut self, overloaded: ModelOverloaded) -> Result<()> {
+        for (provider_id, model_id) in self.fallbacks.by_ref() {
+            let (model_info, api_key, client, request_options) =
+                match connect(&provider_id, &model_id).await {
+                    Ok(connection) => connection,
+                    Err(e) => {
+                        tracing::warn!(
+                            "Skipping fallback model {}/{}: {}",
+                            provider_id,
+                            model_id,
+                            e
+                        );
+                        continue;
+                    }
+                };
+
+            if self.format == "text" {
+                eprintln!(
+                    "\n[{}; falling back to {}/{}]",
+                    overloaded, provider_id, model_id
+                );
+            }
+            self.model_api_id = model_info.api.id.clone();
+            self.model_api_url = model_info.api.url.clone();
+            self.openai_compatible = provider::is_openai_compatible(&provider_id, &model_info);
+            self.accepts_images = model_info
+                .capabilities
+                .supports(provider::Capability::ImageInput);
+            self.max_tokens = provider::max_output_tokens(&model_info, self.max_output);
+            self.max_tokens_field = provider::openai_max_tokens_field(&model_info);
+            self.model_cost = model_info.cost.clone();
+            self.request_options = request_options;
+            self.api_key = api_key;
+            self.client = client;
+            self.provider_id = provider_id;
+            self.model_id = model_id;
+            return Ok(());
+        }
+
+        eprintln!("\nError: {}", overloaded);
+        Err(overloaded.into())
+    }
+}
+
 /// Resolve which model to use based on priority
-async fn resolve_model(
+pub async fn resolve_model(
     model: Option<&str>,
     session: &Session,
     config: &Config,
 ) -> Result<(String, String)> {
     if let Some(m) = model {
         // CLI argument takes highest priority
-        return provider::parse_model_string(m)
-            .ok_or_else(|| anyhow::anyhow!("Invalid model format. Use 'provider/model'"));
+        return provider::parse_model_string(m).ok_or_else(|| {
+            anyhow::anyhow!(
+                "Invalid model '{}'. Use 'provider/model' or a model alias",
+                m
+            )
+        });
     }
 
     if let Some(session_model) = session.get_model().await {
@@ -213,44 +534,63 @@ async fn create_provider_stream(
     client: &provider::StreamingClient,
     ctx: &PromptContext,
     messages: &[ChatMessage],
+    prefill: Option<String>,
 ) -> Result<mpsc::Receiver<StreamEvent>> {
+    let mut messages = messages.to_vec();
+    if !ctx.accepts_images {
+        provider::replace_images(&mut messages);
+    }
+
     match ctx.provider_id.as_str() {
         "anthropic" => {
+            let request = AnthropicRequest {
+                messages,
+                system: Some(ctx.system_prompt.clone()),
+                tools: ctx.tool_defs.clone(),
+                max_tokens: ctx.max_tokens,
+                prefill,
+                json_schema: ctx.json_schema.clone(),
+                temperature: None,
+                options: ctx.request_options.clone(),
+            };
+            client
+                .stream_anthropic(&ctx.api_key, &ctx.model_api_id, request)
+                .await
+        }
+        "copilot" => {
             client
-                .stream_anthropic(
+                .stream_copilot(
                     &ctx.api_key,
                     &ctx.model_api_id,
-                    messages.to_vec(),
+                    messages,
                     Some(ctx.system_prompt.clone()),
                     ctx.tool_defs.clone(),
                     ctx.max_tokens,
                 )
                 .await
         }
-        "openai" => {
+        _ if ctx.openai_compatible => {
             let base_url = ctx
                 .model_api_url
                 .as_deref()
                 .unwrap_or("https://api.openai.com/v1");
             let request = OpenAIRequest {
-                messages: messages.to_vec(),
+                messages,
                 system: Some(ctx.system_prompt.clone()),
                 tools: ctx.tool_defs.clone(),
                 max_tokens: ctx.max_tokens,
+                max_tokens_field: ctx.max_tokens_field,
+                json_schema: ctx.json_schema.clone(),
+                temperature: None,
+                options: ctx.request_options.clone(),
             };
             client
-                .stream_openai(&ctx.api_key, base_url, &ctx.model_api_id, request)
-                .await
-        }
-        "copilot" => {
-            client
-                .stream_copilot(
+                .stream_openai(
+                    &ctx.provider_id,
                     &ctx.api_key,
+                    base_url,
                     &ctx.model_api_id,
-                    messages.to_vec(),
-                    Some(ctx.system_prompt.clone()),
-                    ctx.tool_defs.clone(),
-                    ctx.max_tokens,
+                    request,
                 )
                 .await
         }
@@ -259,16 +599,22 @@ async fn create_provider_stream(
 }
 
 /// Process stream events and collect results
-async fn process_stream(mut rx: mpsc::Receiver<StreamEvent>, format: &str) -> Result<StreamResult> {
+async fn process_stream(
+    mut rx: mpsc::Receiver<StreamEvent>,
+    format: &str,
+    echo_text: bool,
+) -> Result<StreamResult> {
     let mut response_text = String::new();
     let mut tool_tracker = ToolCallTracker::new();
     let mut finish_reason = String::new();
+    let (mut input_total, mut output_total) = (0, 0);
+    let mut refusal = None;
     let mut last_printed_newline = false;
 
     while let Some(event) = rx.recv().await {
         match event {
             StreamEvent::TextDelta(text) => {
-                handle_text_delta(&text, format, &mut response_text);
+                handle_text_delta(&text, format == "text" && echo_text, &mut response_text);
                 last_printed_newline = false;
             }
             StreamEvent::ReasoningDelta(text) => {
@@ -301,6 +647,8 @@ async fn process_stream(mut rx: mpsc::Receiver<StreamEvent>, format: &str) -> Re
                 input_tokens,
                 output_tokens,
             } => {
+                input_total = input_total.max(input_tokens);
+                output_total = output_total.max(output_tokens);
                 if format == "text" {
                     if !last_printed_newline {
                         println!();
@@ -312,12 +660,20 @@ async fn process_stream(mut rx: mpsc::Receiver<StreamEvent>, format: &str) -> Re
             StreamEvent::Done {
                 finish_reason: reason,
             } => {
-                finish_reason = reason;
+                // OpenAI's `[DONE]` and Anthropic's `message_stop` follow the
+                // actual reason as a plain "stop"
+                if finish_reason.is_empty() {
+                    finish_reason = reason;
+                }
             }
-            StreamEvent::Error(err) => {
+            StreamEvent::Error(err) | StreamEvent::AuthError { message: err, .. } => {
                 eprintln!("\nError: {}", err);
                 return Err(anyhow::anyhow!(err));
             }
+            // Usage may still follow, so the stream is read to its end
+            StreamEvent::Refused(reason) => refusal = Some(reason),
+            // Reported by the caller, which may fall back to another model
+            StreamEvent::Overloaded(message) => return Err(ModelOverloaded(message).into()),
         }
     }
 
@@ -325,12 +681,15 @@ async fn process_stream(mut rx: mpsc::Receiver<StreamEvent>, format: &str) -> Re
         response_text,
         pending_calls: tool_tracker.get_all_calls(),
         finish_reason,
+        input_tokens: input_total,
+        output_tokens: output_total,
+        refusal,
     })
 }
 
 /// Handle text delta event
-fn handle_text_delta(text: &str, format: &str, response_text: &mut String) {
-    if format == "text" {
+fn handle_text_delta(text: &str, echo: bool, response_text: &mut String) {
+    if echo {
         print!("{}", text);
         use std::io::Write;
         let _ = std::io::stdout().flush();
@@ -363,6 +722,18 @@ async fn handle_stream_result(
         });
     }
 
+    let refusal = result.refusal.clone().or_else(|| {
+        FinishReason::parse(&result.finish_reason)
+            .refusal()
+            .map(str::to_string)
+    });
+    if let Some(reason) = refusal {
+        let error = anyhow::Error::from(RequestRefused(reason));
+        eprintln!("\nError: {}", error);
+        ctx.record_refusal(&error).await;
+        return Err(error);
+    }
+
     // Check if there are tool calls
     // Anthropic may return "end_turn" or "stop" even when tool calls are present
     // So we should execute tools whenever they are present, regardless of finish_reason
@@ -374,7 +745,7 @@ async fn handle_stream_result(
         handle_tool_calls(ctx, messages, result, assistant_parts, doom_detector).await
     } else {
         // No tool calls or final response - add assistant message and exit
-        handle_final_response(messages, result, assistant_parts)
+        handle_final_response(&ctx.format, messages, result, assistant_parts)
     }
 }
 
@@ -534,6 +905,7 @@ fn build_chat_content(parts: Vec<ContentPart>) -> ChatContent {
 
 /// Handle final response when no tool calls
 fn handle_final_response(
+    format: &str,
     messages: &mut Vec<ChatMessage>,
     result: StreamResult,
     assistant_parts: Vec<ContentPart>,
@@ -551,24 +923,40 @@ fn handle_final_response(
         });
     }
 
-    if result.finish_reason == "tool_calls" || result.finish_reason == "tool_use" {
+    if FinishReason::parse(&result.finish_reason) == FinishReason::ToolCalls {
         eprintln!("[Warning: LLM indicated tool_calls but no tools found or executed]");
     }
 
-    println!();
+    // End the streamed reply
+    if format == "text" {
+        println!();
+    }
     Ok(false)
 }
 
 /// Output result in requested format
-fn output_result(messages: &[ChatMessage], step: i32, format: &str) {
+fn output_result(
+    messages: &[ChatMessage],
+    step: i32,
+    format: &str,
+    structured_output: Option<&serde_json::Value>,
+) {
     if format == "json" {
-        let output = serde_json::json!({
+        let mut output = serde_json::json!({
             "messages": messages,
             "steps": step,
         });
+        if let Some(value) = structured_output {
+            output["output"] = value.clone();
+        }
         if let Ok(json) = serde_json::to_string_pretty(&output) {
             println!("{}", json);
         }
+    } else if let Some(value) = structured_output {
+        // Streamed text was suppressed, so print the validated value instead
+        if let Ok(json) = serde_json::to_string_pretty(value) {
+            println!("{}", json);
+        }
     }
     // For "text" and "markdown", output was already printed during streaming
 }
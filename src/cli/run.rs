@@ -3,7 +3,11 @@
 use anyhow::Result;
 
 /// Execute the run command (starts TUI)
-pub async fn execute(prompt: Option<String>, model: Option<String>) -> Result<()> {
+pub async fn execute(
+    prompt: Option<String>,
+    model: Option<String>,
+    append_system: Option<String>,
+) -> Result<()> {
     // Initialize configuration
     let config = crate::config::Config::load().await?;
 
@@ -11,5 +15,5 @@ pub async fn execute(prompt: Option<String>, model: Option<String>) -> Result<()
     crate::provider::registry().initialize(&config).await?;
 
     // Start TUI
-    crate::tui::run(prompt, model).await
+    crate::tui::run(prompt, model, append_system).await
 }
@@ -1,21 +1,16 @@
 //! Serve command - starts the HTTP server.
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 
 /// Execute the serve command
 pub async fn execute(host: &str, port: u16) -> Result<()> {
-    println!("Starting server on {}:{}", host, port);
-    println!("Note: HTTP server not yet implemented in Rust version");
+    let (config, _) = super::prompt::prepare(None).await?;
+    let listener = tokio::net::TcpListener::bind((host, port))
+        .await
+        .with_context(|| format!("Failed to listen on {}:{}", host, port))?;
+    println!("Listening on http://{}", listener.local_addr()?);
 
-    // TODO: Implement HTTP server using axum or actix-web
-    // The server should expose:
-    // - Session management APIs
-    // - Streaming chat endpoints
-    // - Tool execution endpoints
-    // - SSE for real-time updates
-
-    // For now, just keep the process running
-    tokio::signal::ctrl_c().await?;
+    crate::server::serve(listener, config).await?;
     println!("\nShutting down...");
 
     Ok(())
@@ -1,12 +1,22 @@
 //! Session management CLI commands.
 
-use crate::session::Session;
+use crate::config::Config;
+use crate::session::{
+    compare, diff as file_diff, tool_log, Message, Part, Session, DEFAULT_TRASH_RETENTION_DAYS,
+};
+use crate::storage::migrate::{self, RecordKind};
 use anyhow::Result;
 use chrono::{TimeZone, Utc};
+use serde::de::DeserializeOwned;
 
 /// List all sessions
-pub async fn list() -> Result<()> {
+pub async fn list(trash: bool) -> Result<()> {
+    if trash {
+        return list_trash().await;
+    }
+
     let sessions = Session::list("default").await?;
+    report_skipped();
 
     if sessions.is_empty() {
         println!("No sessions found.");
@@ -23,14 +33,12 @@ pub async fn list() -> Result<()> {
             .map(|t| t.format("%Y-%m-%d %H:%M").to_string())
             .unwrap_or_else(|| "Unknown".to_string());
 
-        // Truncate title if too long
-        let title = if session.title.len() > 38 {
-            format!("{}...", &session.title[..35])
-        } else {
-            session.title.clone()
-        };
-
-        println!("{:<30} {:<40} {:<20}", session.id, title, created);
+        println!(
+            "{:<30} {:<40} {:<20}",
+            session.id,
+            short_title(&session.title),
+            created
+        );
     }
 
     Ok(())
@@ -70,8 +78,7 @@ pub async fn show(id: &str) -> Result<()> {
     }
 
     // Show message count
-    let messages = session.messages().await?;
-    println!("\nMessages: {}", messages.len());
+    println!("\nMessages: {}", session.message_count().await?);
 
     // Show summary if available
     if let Some(summary) = &session.summary {
@@ -85,17 +92,382 @@ pub async fn show(id: &str) -> Result<()> {
 }
 
 /// Delete a session
-pub async fn delete(id: &str) -> Result<()> {
+///
+/// By default the session is moved to the trash and can be restored with
+/// `session restore`. `purge` removes it permanently.
+pub async fn delete(id: &str, yes: bool, purge: bool) -> Result<()> {
     // Check if session exists
-    let session = Session::get("default", id)
+    let Some(session) = Session::get("default", id).await? else {
+        if purge {
+            return purge_trashed(id, yes).await;
+        }
+        anyhow::bail!("Session not found: {}", id);
+    };
+
+    if !yes
+        && !confirm(&format!(
+            "Delete session '{}' ({})?",
+            session.title, session.id
+        ))?
+    {
+        println!("Aborted.");
+        return Ok(());
+    }
+
+    println!("Deleting session: {} ({})", session.title, session.id);
+
+    if purge {
+        Session::delete("default", id).await?;
+        println!("Session permanently deleted.");
+    } else {
+        Session::trash("default", id).await?;
+        println!(
+            "Session moved to trash. Restore it with: opencode session restore {}",
+            id
+        );
+    }
+
+    purge_expired_trash().await;
+
+    Ok(())
+}
+
+/// Permanently remove a session that is already in the trash
+async fn purge_trashed(id: &str, yes: bool) -> Result<()> {
+    let in_trash = Session::list_trash("default")
+        .await?
+        .iter()
+        .any(|entry| entry.session.id == id);
+    if !in_trash {
+        anyhow::bail!("Session not found: {}", id);
+    }
+
+    if !yes && !confirm(&format!("Permanently delete trashed session {}?", id))? {
+        println!("Aborted.");
+        return Ok(());
+    }
+
+    Session::purge("default", id).await?;
+    println!("Session permanently deleted.");
+
+    Ok(())
+}
+
+/// Restore a session from the trash
+pub async fn restore(id: &str) -> Result<()> {
+    purge_expired_trash().await;
+
+    let session = Session::restore("default", id).await?;
+    println!("Session restored: {} ({})", session.title, session.id);
+
+    Ok(())
+}
+
+/// List sessions in the trash
+pub async fn list_trash() -> Result<()> {
+    purge_expired_trash().await;
+
+    let trashed = Session::list_trash("default").await?;
+
+    if trashed.is_empty() {
+        println!("Trash is empty.");
+        return Ok(());
+    }
+
+    println!("{:<30} {:<40} {:<20}", "ID", "Title", "Deleted");
+    println!("{}", "-".repeat(90));
+
+    for entry in trashed {
+        let deleted = Utc
+            .timestamp_millis_opt(entry.deleted_at)
+            .single()
+            .map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_else(|| "Unknown".to_string());
+
+        println!(
+            "{:<30} {:<40} {:<20}",
+            entry.session.id,
+            short_title(&entry.session.title),
+            deleted
+        );
+    }
+
+    Ok(())
+}
+
+/// Title truncated to fit the listing column
+fn short_title(title: &str) -> String {
+    if title.chars().count() > 38 {
+        format!("{}...", title.chars().take(35).collect::<String>())
+    } else {
+        title.to_string()
+    }
+}
+
+/// Purge trashed sessions past the configured retention period
+async fn purge_expired_trash() {
+    let retention_days = Config::load()
+        .await
+        .ok()
+        .and_then(|c| c.session)
+        .and_then(|s| s.trash_retention_days)
+        .unwrap_or(DEFAULT_TRASH_RETENTION_DAYS);
+
+    match Session::purge_expired("default", retention_days).await {
+        Ok(0) => {}
+        Ok(count) => tracing::info!("Purged {} expired session(s) from trash", count),
+        Err(e) => tracing::warn!("Failed to purge expired sessions: {}", e),
+    }
+}
+
+/// Ask the user for a yes/no confirmation on stdin
+fn confirm(question: &str) -> Result<bool> {
+    use std::io::Write;
+
+    print!("{} [y/N] ", question);
+    std::io::stdout().flush()?;
+
+    let mut input = String::new();
+    std::io::stdin().read_line(&mut input)?;
+
+    Ok(matches!(input.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+/// List tool executions recorded for a session
+pub async fn tools(id: &str, filter: Option<&str>, format: &str) -> Result<()> {
+    Session::get("default", id)
         .await?
         .ok_or_else(|| anyhow::anyhow!("Session not found: {}", id))?;
 
-    println!("Deleting session: {} ({})", session.title, session.id);
+    let executions = tool_log::list(id, filter).await?;
+    report_skipped();
 
-    Session::delete("default", id).await?;
+    if format == "json" {
+        println!("{}", serde_json::to_string_pretty(&executions)?);
+        return Ok(());
+    }
+
+    if executions.is_empty() {
+        println!("No tool executions found.");
+        return Ok(());
+    }
+
+    println!(
+        "{:<20} {:<12} {:<10} {:>8} {:>5}  Arguments",
+        "Time", "Tool", "Status", "Duration", "Exit"
+    );
+    println!("{}", "-".repeat(90));
+
+    for exec in executions {
+        let time = exec
+            .started
+            .and_then(|ms| Utc.timestamp_millis_opt(ms).single())
+            .map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string())
+            .unwrap_or_else(|| "-".to_string());
+        let duration = exec
+            .duration_ms
+            .map(|ms| format!("{}ms", ms))
+            .unwrap_or_else(|| "-".to_string());
+        let exit = exec
+            .exit_code
+            .map(|c| c.to_string())
+            .unwrap_or_else(|| "-".to_string());
+
+        // Truncate arguments to keep one line per call
+        let args = exec.input.to_string();
+        let args = if args.chars().count() > 60 {
+            format!("{}...", args.chars().take(57).collect::<String>())
+        } else {
+            args
+        };
 
-    println!("Session deleted.");
+        println!(
+            "{:<20} {:<12} {:<10} {:>8} {:>5}  {}",
+            time, exec.tool, exec.status, duration, exit, args
+        );
 
+        for file in &exec.files {
+            println!("{:<20} file: {}", "", file);
+        }
+        if let Some(error) = &exec.error {
+            println!("{:<20} error: {}", "", error);
+        }
+    }
+
+    Ok(())
+}
+
+/// Print a session's tool calls as a shell script (bash calls as commands,
+/// other calls as comments)
+pub async fn export_script(id: &str) -> Result<()> {
+    Session::get("default", id)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Session not found: {}", id))?;
+
+    let executions = tool_log::list(id, None).await?;
+    print!("{}", tool_log::to_script(id, &executions));
     Ok(())
 }
+
+/// Compare two sessions: their assistant replies, aligned by position, as
+/// unified diffs, then the files each one changed
+pub async fn diff(left: &str, right: &str) -> Result<()> {
+    for id in [left, right] {
+        Session::get("default", id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Session not found: {}", id))?;
+    }
+
+    let left_replies = compare::replies(left).await?;
+    let right_replies = compare::replies(right).await?;
+    let count = left_replies.len().max(right_replies.len());
+    for index in 0..count {
+        println!("Reply {}:", index + 1);
+        match (left_replies.get(index), right_replies.get(index)) {
+            (Some(old), Some(new)) => match compare::reply_diff(old, new, left, right) {
+                Some(diff) => print!("{}", diff),
+                None => println!("  (identical)"),
+            },
+            (Some(_), None) => println!("  (only in {})", left),
+            (None, Some(_)) => println!("  (only in {})", right),
+            (None, None) => {}
+        }
+        println!();
+    }
+    if count == 0 {
+        println!("No assistant replies in either session.\n");
+    }
+
+    let changes = compare::file_changes(
+        &file_diff::list(left).await?,
+        &file_diff::list(right).await?,
+    );
+    report_skipped();
+    if changes.is_empty() {
+        println!("No files changed in either session.");
+        return Ok(());
+    }
+
+    let counts = |counts: Option<(u32, u32)>| match counts {
+        Some((additions, deletions)) => format!("+{} -{}", additions, deletions),
+        None => "-".to_string(),
+    };
+    println!("Files changed:");
+    println!("{:<50} {:>12} {:>12}", "Path", "Left", "Right");
+    for change in changes {
+        println!(
+            "{:<50} {:>12} {:>12}",
+            change.path,
+            counts(change.left),
+            counts(change.right)
+        );
+    }
+    Ok(())
+}
+
+/// Note on stderr how many corrupt records were skipped while reading
+fn report_skipped() {
+    let skipped = crate::storage::global().skipped();
+    if skipped > 0 {
+        eprintln!(
+            "Skipped {} corrupt record(s); inspect them with `opencode session dump`",
+            skipped
+        );
+    }
+}
+
+/// Print a session's stored session, message and part records as JSON
+///
+/// Records are printed exactly as stored. A record that is not valid JSON or
+/// does not deserialize into its type is listed under `errors` instead of
+/// aborting the dump.
+pub async fn dump(id: &str, compact: bool) -> Result<()> {
+    let storage = crate::storage::global();
+    let mut errors = Vec::new();
+
+    let session = read_record::<Session>(&["session", "default", id], &mut errors).await;
+    if session.is_none() && errors.is_empty() {
+        anyhow::bail!("Session not found: {}", id);
+    }
+
+    let mut messages = Vec::new();
+    for key in storage.list(&["message", id]).await? {
+        let message_id = key.last().map(String::as_str).unwrap_or_default();
+        let message = read_record::<Message>(&["message", id, message_id], &mut errors).await;
+
+        let mut parts = Vec::new();
+        for part_key in storage.list(&["part", message_id]).await? {
+            let part_id = part_key.last().map(String::as_str).unwrap_or_default();
+            if let Some(part) =
+                read_record::<Part>(&["part", message_id, part_id], &mut errors).await
+            {
+                parts.push(part);
+            }
+        }
+
+        messages.push(serde_json::json!({
+            "id": message_id,
+            "message": message,
+            "parts": parts,
+        }));
+    }
+
+    let dump = serde_json::json!({
+        "session": session,
+        "messages": messages,
+        "errors": errors,
+    });
+    if compact {
+        println!("{}", serde_json::to_string(&dump)?);
+    } else {
+        println!("{}", serde_json::to_string_pretty(&dump)?);
+    }
+    Ok(())
+}
+
+/// Read a stored record as raw JSON
+///
+/// Adds an entry to `errors` when the record is not valid JSON (and returns
+/// `None`) or does not deserialize into `T` after schema upgrades (and still
+/// returns it as stored).
+async fn read_record<T: DeserializeOwned>(
+    key: &[&str],
+    errors: &mut Vec<serde_json::Value>,
+) -> Option<serde_json::Value> {
+    let mut report = |error: String| {
+        errors.push(serde_json::json!({ "key": key.join("/"), "error": error }));
+    };
+
+    match crate::storage::global().read_raw(key).await {
+        Ok(Some(value)) => {
+            let mut upgraded = value.clone();
+            if let Some(kind) = RecordKind::of_key(key) {
+                migrate::upgrade(kind, &mut upgraded);
+            }
+            if let Err(e) = serde_json::from_value::<T>(upgraded) {
+                report(e.to_string());
+            }
+            Some(value)
+        }
+        Ok(None) => None,
+        Err(e) => {
+            report(format!("{:#}", e));
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_short_title_multibyte() {
+        assert_eq!(short_title("short"), "short");
+        let title = "日本語".repeat(15);
+        assert_eq!(
+            short_title(&title),
+            format!("{}...", title.chars().take(35).collect::<String>())
+        );
+    }
+}
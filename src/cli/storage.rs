@@ -0,0 +1,23 @@
+//! Storage maintenance CLI commands.
+
+use anyhow::Result;
+
+/// Upgrade every stored session record to the current schema
+pub async fn migrate() -> Result<()> {
+    let report = crate::storage::global().migrate_all().await?;
+
+    println!(
+        "Upgraded {} of {} records to schema version {}.",
+        report.upgraded,
+        report.checked,
+        crate::storage::migrate::SCHEMA_VERSION
+    );
+    if report.skipped > 0 {
+        println!(
+            "Skipped {} records that are not valid JSON; inspect them with `opencode session dump`.",
+            report.skipped
+        );
+    }
+
+    Ok(())
+}
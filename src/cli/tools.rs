@@ -0,0 +1,84 @@
+//! Tools CLI commands.
+
+use crate::config::{Config, PermissionAction};
+use crate::permission::PermissionChecker;
+use crate::tool::{ToolDefinition, ToolRegistry};
+use anyhow::Result;
+use serde::Serialize;
+
+/// A built-in tool and whether the agent is offered it
+#[derive(Serialize)]
+struct ToolEntry {
+    #[serde(flatten)]
+    definition: ToolDefinition,
+    enabled: bool,
+    /// What the config's `permission` rules do with a call: ask, allow or deny
+    permission: PermissionAction,
+    #[serde(skip)]
+    disabled_by: Option<&'static str>,
+}
+
+/// List the built-in tools with their descriptions and parameter schemas
+///
+/// Tools turned off by `disabled_tools` or `tools` are listed too, marked as
+/// disabled. Each tool shows what the config's `permission` rules do with its
+/// calls; rules set on an agent are not applied to tool calls, so they are
+/// not reflected.
+pub async fn list(json: bool) -> Result<()> {
+    let config = Config::load().await?;
+    let checker = PermissionChecker::from_config(&config);
+
+    let mut definitions = ToolRegistry::with_defaults().definitions().await;
+    definitions.sort_by(|a, b| a.name.cmp(&b.name));
+    let entries: Vec<ToolEntry> = definitions
+        .into_iter()
+        .map(|definition| {
+            let disabled_by = disabled_by(&config, &definition.name);
+            ToolEntry {
+                enabled: disabled_by.is_none(),
+                permission: checker.config_action(&definition.name),
+                disabled_by,
+                definition,
+            }
+        })
+        .collect();
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&entries)?);
+        return Ok(());
+    }
+
+    for (i, entry) in entries.iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        let status = match (entry.disabled_by, &entry.permission) {
+            (Some(setting), _) => format!("disabled by {}", setting),
+            (None, PermissionAction::Allow) => "enabled, allowed".to_string(),
+            (None, PermissionAction::Ask) => "enabled, asks first".to_string(),
+            (None, PermissionAction::Deny) => "enabled, denied by permission".to_string(),
+        };
+        println!("{} ({})", entry.definition.name, status);
+        for line in entry.definition.description.trim().lines() {
+            println!("  {}", line);
+        }
+        println!("  Parameters:");
+        let schema = serde_json::to_string_pretty(&entry.definition.parameters)?;
+        for line in schema.lines() {
+            println!("    {}", line);
+        }
+    }
+
+    Ok(())
+}
+
+/// The setting that keeps a tool from being offered, if any
+fn disabled_by(config: &Config, name: &str) -> Option<&'static str> {
+    if config.disabled_tools.iter().flatten().any(|id| id == name) {
+        Some("disabled_tools")
+    } else if config.tools.as_ref().and_then(|tools| tools.get(name)) == Some(&false) {
+        Some("tools")
+    } else {
+        None
+    }
+}
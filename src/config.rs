@@ -13,6 +13,8 @@ use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use tokio::fs;
 
+mod edit;
+
 /// Parser state for JSONC comment stripping
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum JsoncParseState {
@@ -46,6 +48,29 @@ pub struct Config {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub small_model: Option<String>,
 
+    /// Name new sessions after their first exchange (default true)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auto_title: Option<bool>,
+
+    /// Models in provider/model format to try, in order, when the current
+    /// model is overloaded or rate limited
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model_fallbacks: Option<Vec<String>>,
+
+    /// Short names for models, e.g. `"sonnet": "anthropic/claude-sonnet-4-20250514"`,
+    /// usable wherever a provider/model string is
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub aliases: Option<HashMap<String, String>>,
+
+    /// Max output tokens per request (capped at the model's output limit)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_output_tokens: Option<u64>,
+
+    /// Largest file, in bytes, the read tool loads whole; bigger ones can
+    /// only be read by line range (default 1 MiB)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_read_bytes: Option<u64>,
+
     /// Default agent name
     #[serde(skip_serializing_if = "Option::is_none")]
     pub default_agent: Option<String>,
@@ -66,10 +91,22 @@ pub struct Config {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub enabled_providers: Option<Vec<String>>,
 
+    /// Built-in tools that are neither registered nor offered to the model
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub disabled_tools: Option<Vec<String>>,
+
     /// Share settings
     #[serde(skip_serializing_if = "Option::is_none")]
     pub share: Option<ShareMode>,
 
+    /// Share server settings
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub share_server: Option<ShareServerConfig>,
+
+    /// Secret redaction for shares, exports and copies
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub redact: Option<RedactConfig>,
+
     /// Auto-update settings
     #[serde(skip_serializing_if = "Option::is_none")]
     pub autoupdate: Option<AutoUpdate>,
@@ -94,9 +131,9 @@ pub struct Config {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub permission: Option<HashMap<String, PermissionRule>>,
 
-    /// Keybind configurations
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub keybinds: Option<KeybindsConfig>,
+    /// Keybindings: action name to comma-separated key chords
+    #[serde(alias = "keybindings", skip_serializing_if = "Option::is_none")]
+    pub keybinds: Option<HashMap<String, String>>,
 
     /// TUI settings
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -110,14 +147,43 @@ pub struct Config {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub compaction: Option<CompactionConfig>,
 
+    /// Session settings
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session: Option<SessionConfig>,
+
+    /// Storage location settings
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub storage: Option<StorageConfig>,
+
     /// Additional instructions files
     #[serde(skip_serializing_if = "Option::is_none")]
     pub instructions: Option<Vec<String>>,
 
+    /// File replacing the default system prompt, relative to the working
+    /// directory; overridden by `--system-file`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub system_file: Option<String>,
+
     /// Plugin list
     #[serde(skip_serializing_if = "Option::is_none")]
     pub plugin: Option<Vec<String>>,
 
+    /// Shell that runs bash tool commands (default: `$SHELL`, `cmd` on Windows)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shell: Option<String>,
+
+    /// Test tool settings
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub test: Option<TestConfig>,
+
+    /// Output settings of the non-interactive commands
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output: Option<OutputConfig>,
+
+    /// Prompt macros such as `!file:` (see `session::macros`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub macros: Option<MacroConfig>,
+
     /// Tool configurations (enable/disable tools)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tools: Option<HashMap<String, bool>>,
@@ -135,6 +201,63 @@ pub enum ShareMode {
     Disabled,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct ShareServerConfig {
+    /// Base URL of the share server
+    pub url: Option<String>,
+    /// Payload sent to the server
+    pub format: Option<ShareFormat>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct RedactConfig {
+    /// Set to false to turn redaction off (same as `--no-redact`)
+    pub enabled: Option<bool>,
+    /// Extra regex patterns to redact, on top of the built-in ones
+    pub patterns: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct MacroConfig {
+    /// Directives to expand (`file`, `sh`, `clip`); `file` and `clip` when
+    /// unset, since `sh` runs commands typed into prompts
+    pub enabled: Option<Vec<String>>,
+    /// Seconds a `!sh:` command may run
+    pub sh_timeout: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct TestConfig {
+    /// Command the test tool runs, e.g. `cargo test --workspace`;
+    /// detected from the project files when unset
+    pub command: Option<String>,
+    /// Timeout in milliseconds
+    pub timeout: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct OutputConfig {
+    /// Format of `opencode prompt` when `--format` is not given (text, json,
+    /// markdown)
+    pub default_format: Option<String>,
+}
+
+/// Share payload format
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ShareFormat {
+    /// Session, messages and parts as JSON
+    #[default]
+    Json,
+    /// Rendered markdown transcript
+    Markdown,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum AutoUpdate {
@@ -145,14 +268,24 @@ pub enum AutoUpdate {
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(default)]
 pub struct ProviderConfig {
+    /// `custom` defines a new OpenAI-compatible provider from `base_url`,
+    /// `api_key_env` and `models`
+    pub source: Option<String>,
     pub name: Option<String>,
     pub api: Option<String>,
     pub npm: Option<String>,
     pub env: Option<Vec<String>>,
+    /// Environment variable holding the API key of a custom provider
+    pub api_key_env: Option<String>,
     pub options: Option<HashMap<String, serde_json::Value>>,
+    /// API base URL for all of this provider's models, e.g. a gateway or proxy
+    pub base_url: Option<String>,
     pub models: Option<HashMap<String, ModelConfig>>,
     pub whitelist: Option<Vec<String>>,
     pub blacklist: Option<Vec<String>>,
+    /// DANGEROUS: skip TLS certificate verification for this provider's requests.
+    /// Only intended for local gateways with self-signed certificates.
+    pub insecure_skip_verify: Option<bool>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -213,6 +346,8 @@ pub struct AgentConfig {
     pub temperature: Option<f64>,
     pub top_p: Option<f64>,
     pub prompt: Option<String>,
+    /// File with the agent's prompt, used instead of `prompt`
+    pub system_file: Option<String>,
     pub description: Option<String>,
     pub mode: Option<AgentMode>,
     pub hidden: Option<bool>,
@@ -245,9 +380,11 @@ pub struct CommandConfig {
 pub enum PermissionRule {
     Action(PermissionAction),
     Object(HashMap<String, PermissionAction>),
+    /// Tool names, used by `auto_allow`
+    List(Vec<String>),
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum PermissionAction {
     Ask,
@@ -257,30 +394,30 @@ pub enum PermissionAction {
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(default)]
-pub struct KeybindsConfig {
-    pub leader: Option<String>,
-    pub app_exit: Option<String>,
-    pub editor_open: Option<String>,
-    pub theme_list: Option<String>,
-    pub sidebar_toggle: Option<String>,
-    pub session_new: Option<String>,
-    pub session_list: Option<String>,
-    pub model_list: Option<String>,
-    pub command_list: Option<String>,
-    pub agent_list: Option<String>,
-    pub input_submit: Option<String>,
-    pub input_newline: Option<String>,
-    pub input_clear: Option<String>,
-    pub input_paste: Option<String>,
-    pub session_interrupt: Option<String>,
-    pub session_compact: Option<String>,
+pub struct TuiConfig {
+    pub scroll_speed: Option<f64>,
+    /// Enter inserts a newline and the newline keys submit
+    pub multiline_input: Option<bool>,
+    pub diff_style: Option<DiffStyle>,
+    /// Max characters of a collapsed tool result preview
+    pub tool_preview_chars: Option<usize>,
+    /// Bell and desktop notifications when a turn needs attention
+    pub notify: Option<NotifyConfig>,
+    /// Seconds between autosaves of the unsent input (0 = off)
+    pub autosave_interval: Option<u64>,
+    /// Milliseconds between redraws, which also batch streamed text
+    pub tick_interval: Option<u64>,
+    /// Capture the mouse for scrolling and clicks (disables native selection)
+    pub mouse: Option<bool>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(default)]
-pub struct TuiConfig {
-    pub scroll_speed: Option<f64>,
-    pub diff_style: Option<DiffStyle>,
+pub struct NotifyConfig {
+    /// Ring the terminal bell
+    pub bell: Option<bool>,
+    /// Show a desktop notification (`notify-send` or `osascript`)
+    pub desktop: Option<bool>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -297,6 +434,10 @@ pub struct ServerConfig {
     pub hostname: Option<String>,
     pub mdns: Option<bool>,
     pub cors: Option<Vec<String>>,
+    /// Turns run at once for prompt requests
+    pub max_turns: Option<usize>,
+    /// Prompt requests waiting for a turn before new ones are turned away
+    pub max_queued: Option<usize>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -306,6 +447,24 @@ pub struct CompactionConfig {
     pub prune: Option<bool>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct SessionConfig {
+    /// Days to keep deleted sessions in the trash before purging them
+    pub trash_retention_days: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct StorageConfig {
+    /// Storage root directory (overridden by `OPENCODE_DATA_DIR`)
+    pub path: Option<String>,
+    /// Keep data in the project's `.opencode/storage` directory
+    pub project: Option<bool>,
+    /// Fail listings on a corrupt record instead of skipping it
+    pub strict: Option<bool>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(default)]
 pub struct ExperimentalConfig {
@@ -515,23 +674,36 @@ impl Config {
             theme,
             model,
             small_model,
+            auto_title,
+            model_fallbacks,
+            max_output_tokens,
+            max_read_bytes,
             default_agent,
             username,
             log_level,
             disabled_providers,
             enabled_providers,
+            disabled_tools,
             share,
+            share_server,
+            redact,
             autoupdate,
-            keybinds,
             tui,
             server,
             compaction,
+            session,
+            storage,
             instructions,
+            system_file,
             plugin,
+            shell,
+            test,
+            output,
+            macros,
             experimental,
         );
 
-        merge_map!(provider, mcp, agent, command, permission, tools);
+        merge_map!(provider, mcp, agent, command, permission, tools, keybinds, aliases);
 
         self
     }
@@ -555,6 +727,26 @@ impl Config {
 
     /// Create a default config file if it doesn't exist
     pub async fn init() -> Result<PathBuf> {
+        Self::write_init(Self::default_init_config()).await
+    }
+
+    /// Initialize the global configuration file with a default model
+    pub async fn init_with_model(model: &str) -> Result<PathBuf> {
+        let config = Self {
+            model: Some(model.to_string()),
+            ..Self::default_init_config()
+        };
+        Self::write_init(config).await
+    }
+
+    /// Whether a global or project configuration file exists
+    pub async fn file_exists() -> bool {
+        Self::global_config_path().is_some_and(|path| path.exists())
+            || matches!(Self::find_project_config().await, Ok(Some(_)))
+    }
+
+    /// Write the global configuration file unless it already exists
+    async fn write_init(config: Self) -> Result<PathBuf> {
         let config_dir = Self::global_config_dir()
             .ok_or_else(|| anyhow::anyhow!("Could not determine config directory"))?;
 
@@ -570,8 +762,7 @@ impl Config {
             .unwrap_or_else(|_| std::fs::symlink_metadata(&config_path).is_ok());
 
         if !exists {
-            let default_config = Self::default_init_config();
-            let content = serde_json::to_string_pretty(&default_config)?;
+            let content = serde_json::to_string_pretty(&config)?;
             fs::write(&config_path, content)
                 .await
                 .context("Failed to write default config file")?;
@@ -580,6 +771,83 @@ impl Config {
         Ok(config_path)
     }
 
+    /// Set a dotted key of the global config file, e.g.
+    /// `output.default_format`, creating the file if needed
+    ///
+    /// Only the key's text changes, so the rest of the file keeps its
+    /// comments and layout. The file must still be a valid config afterwards
+    /// and the key one the config knows. Returns the file's path.
+    pub async fn set_global_value(key: &str, value: serde_json::Value) -> Result<PathBuf> {
+        let (config_path, content) = Self::read_global_file().await?;
+        let content = if content.trim().is_empty() {
+            "{}\n".to_string()
+        } else {
+            content
+        };
+        let edited = edit::set(&content, key, &value)?;
+        Self::check_edit(&edited, Some(key))?;
+        write_atomic(&config_path, &edited).await?;
+        Ok(config_path)
+    }
+
+    /// Remove a dotted key from the global config file
+    ///
+    /// Fails when the key is not set there. Returns the file's path.
+    pub async fn unset_global_value(key: &str) -> Result<PathBuf> {
+        let (config_path, content) = Self::read_global_file().await?;
+        let edited = match content.trim() {
+            "" => None,
+            _ => edit::unset(&content, key)?,
+        };
+        let edited = edited
+            .ok_or_else(|| anyhow::anyhow!("{} is not set in {}", key, config_path.display()))?;
+        Self::check_edit(&edited, None)?;
+        write_atomic(&config_path, &edited).await?;
+        Ok(config_path)
+    }
+
+    /// Value of a dotted key, or `None` when it is not set
+    pub fn value_at(&self, key: &str) -> Option<serde_json::Value> {
+        let value = serde_json::to_value(self).ok()?;
+        get_dotted(&value, key)
+            .filter(|value| !value.is_null())
+            .cloned()
+    }
+
+    /// Path and content of the global config file, empty when it is missing
+    async fn read_global_file() -> Result<(PathBuf, String)> {
+        let config_path = Self::global_config_path()
+            .ok_or_else(|| anyhow::anyhow!("Could not determine config directory"))?;
+        let content = match fs::read_to_string(&config_path).await {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => {
+                return Err(e)
+                    .with_context(|| format!("Failed to read config file: {:?}", config_path))
+            }
+        };
+        Ok((config_path, content))
+    }
+
+    /// Check that edited config file content is still a valid config and,
+    /// when given, that `key` is one the config knows
+    ///
+    /// Unknown keys are dropped when a config is deserialized, so a key missing
+    /// after the round trip is a typo.
+    fn check_edit(content: &str, key: Option<&str>) -> Result<()> {
+        let content = Self::strip_trailing_commas(&Self::strip_jsonc_comments(content));
+        let config: Config = serde_json::from_str(&content).with_context(|| match key {
+            Some(key) => format!("Invalid value for {}", key),
+            None => "Config would be invalid".to_string(),
+        })?;
+        if let Some(key) = key {
+            if get_dotted(&serde_json::to_value(&config)?, key).is_none() {
+                anyhow::bail!("Unknown config key: {}", key);
+            }
+        }
+        Ok(())
+    }
+
     /// Create a default configuration for initialization
     fn default_init_config() -> Self {
         Self {
@@ -591,7 +859,13 @@ impl Config {
             provider: Some(HashMap::new()),
             tui: Some(TuiConfig {
                 scroll_speed: Some(3.0),
+                multiline_input: None,
                 diff_style: Some(DiffStyle::Auto),
+                tool_preview_chars: None,
+                notify: None,
+                autosave_interval: None,
+                tick_interval: None,
+                mouse: None,
             }),
             server: Some(ServerConfig {
                 port: Some(19876),
@@ -608,6 +882,30 @@ impl Config {
     }
 }
 
+/// Value at a dotted key such as `tui.scroll_speed`
+fn get_dotted<'a>(root: &'a serde_json::Value, key: &str) -> Option<&'a serde_json::Value> {
+    key.split('.').try_fold(root, |value, part| value.get(part))
+}
+
+/// Replace a file through a temporary file next to it, so an interrupted
+/// write never leaves it half written
+async fn write_atomic(path: &Path, content: &str) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .await
+            .context("Failed to create config directory")?;
+    }
+    let mut temp_name = path.file_name().unwrap_or_default().to_os_string();
+    temp_name.push(".tmp");
+    let temp_path = path.with_file_name(temp_name);
+    fs::write(&temp_path, content)
+        .await
+        .with_context(|| format!("Failed to write config file: {:?}", temp_path))?;
+    fs::rename(&temp_path, path)
+        .await
+        .with_context(|| format!("Failed to replace config file: {:?}", path))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -675,6 +973,18 @@ mod tests {
         assert!(parsed.is_ok());
     }
 
+    #[test]
+    fn test_provider_insecure_skip_verify() {
+        let config: Config = serde_json::from_str(
+            r#"{"provider": {"local": {"insecure_skip_verify": true}, "openai": {}}}"#,
+        )
+        .unwrap();
+
+        let providers = config.provider.unwrap();
+        assert_eq!(providers["local"].insecure_skip_verify, Some(true));
+        assert_eq!(providers["openai"].insecure_skip_verify, None);
+    }
+
     #[test]
     fn test_empty_config() {
         let empty_content = "";
@@ -684,4 +994,23 @@ mod tests {
         assert!(empty_content.trim().is_empty());
         assert!(whitespace_content.trim().is_empty());
     }
+
+    #[test]
+    fn test_check_edit() {
+        let edited = edit::set(
+            "{\n  // comment\n  \"model\": \"openai/gpt-4o\",\n}\n",
+            "output.default_format",
+            &serde_json::json!("json"),
+        )
+        .unwrap();
+        assert!(Config::check_edit(&edited, Some("output.default_format")).is_ok());
+
+        let edited = edit::set("{}", "output.defualt_format", &"json".into()).unwrap();
+        let err = Config::check_edit(&edited, Some("output.defualt_format")).unwrap_err();
+        assert_eq!(err.to_string(), "Unknown config key: output.defualt_format");
+
+        let edited = edit::set("{}", "output", &serde_json::json!(3)).unwrap();
+        let err = Config::check_edit(&edited, Some("output")).unwrap_err();
+        assert_eq!(err.to_string(), "Invalid value for output");
+    }
 }
@@ -0,0 +1,392 @@
+//! Edits of config file text that keep its formatting.
+//!
+//! `opencode config set` and `config unset` change one key of a JSONC file.
+//! Rather than parsing the file and writing it out again, which would drop
+//! its comments and layout, the file is scanned for the spans of the objects
+//! on the key's path and only the affected member's text is replaced, added
+//! or removed.
+
+use anyhow::Result;
+use serde_json::Value;
+use std::ops::Range;
+
+/// A `"key": value` member of an object, as byte offsets into the text
+#[derive(Debug)]
+struct Member {
+    key: String,
+    key_start: usize,
+    value: Range<usize>,
+    /// Offset of the comma after the value, if there is one
+    comma: Option<usize>,
+}
+
+/// An object's braces and members, as byte offsets into the text
+#[derive(Debug)]
+struct Object {
+    open: usize,
+    close: usize,
+    members: Vec<Member>,
+}
+
+struct Scanner<'a> {
+    text: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Scanner<'a> {
+    fn new(text: &'a str, pos: usize) -> Self {
+        Self {
+            text: text.as_bytes(),
+            pos,
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.text.get(self.pos).copied()
+    }
+
+    fn error(&self, expected: &str) -> anyhow::Error {
+        let line = self.text[..self.pos.min(self.text.len())]
+            .iter()
+            .filter(|&&b| b == b'\n')
+            .count()
+            + 1;
+        anyhow::anyhow!(
+            "Invalid config file: expected {} on line {}",
+            expected,
+            line
+        )
+    }
+
+    /// Skip whitespace and comments
+    fn skip_trivia(&mut self) {
+        loop {
+            match (self.peek(), self.text.get(self.pos + 1)) {
+                (Some(b), _) if b.is_ascii_whitespace() => self.pos += 1,
+                (Some(b'/'), Some(b'/')) => {
+                    while !matches!(self.peek(), None | Some(b'\n')) {
+                        self.pos += 1;
+                    }
+                }
+                (Some(b'/'), Some(b'*')) => {
+                    self.pos += 2;
+                    while self.pos < self.text.len() && !self.text[self.pos..].starts_with(b"*/") {
+                        self.pos += 1;
+                    }
+                    self.pos = (self.pos + 2).min(self.text.len());
+                }
+                _ => return,
+            }
+        }
+    }
+
+    /// Read a string starting at the current `"`
+    fn string(&mut self) -> Result<String> {
+        let start = self.pos;
+        self.pos += 1;
+        loop {
+            match self.peek() {
+                None => return Err(self.error("a closing quote")),
+                Some(b'\\') => self.pos += 2,
+                Some(b'"') => break,
+                Some(_) => self.pos += 1,
+            }
+        }
+        self.pos += 1;
+        let literal = std::str::from_utf8(&self.text[start..self.pos])?;
+        Ok(serde_json::from_str(literal)?)
+    }
+
+    /// Skip the value starting at the current position
+    fn value(&mut self) -> Result<()> {
+        match self.peek() {
+            Some(b'"') => {
+                self.string()?;
+            }
+            Some(b'{') => {
+                let object = self.object()?;
+                self.pos = object.close + 1;
+            }
+            Some(b'[') => {
+                self.pos += 1;
+                loop {
+                    self.skip_trivia();
+                    match self.peek() {
+                        Some(b']') => break,
+                        Some(b',') => self.pos += 1,
+                        Some(_) => self.value()?,
+                        None => return Err(self.error("`]`")),
+                    }
+                }
+                self.pos += 1;
+            }
+            Some(b) if !b"}],:".contains(&b) && !b.is_ascii_whitespace() => {
+                while self
+                    .peek()
+                    .is_some_and(|b| !b",}]/".contains(&b) && !b.is_ascii_whitespace())
+                {
+                    self.pos += 1;
+                }
+            }
+            _ => return Err(self.error("a value")),
+        }
+        Ok(())
+    }
+
+    /// Read the members of the object starting at the current `{`
+    fn object(&mut self) -> Result<Object> {
+        let open = self.pos;
+        self.pos += 1;
+        let mut members = Vec::new();
+        loop {
+            self.skip_trivia();
+            match self.peek() {
+                Some(b'}') => break,
+                Some(b'"') => {}
+                _ => return Err(self.error("a key or `}`")),
+            }
+            let key_start = self.pos;
+            let key = self.string()?;
+            self.skip_trivia();
+            if self.peek() != Some(b':') {
+                return Err(self.error("`:`"));
+            }
+            self.pos += 1;
+            self.skip_trivia();
+            let value_start = self.pos;
+            self.value()?;
+            let value = value_start..self.pos;
+            self.skip_trivia();
+            let comma = (self.peek() == Some(b',')).then_some(self.pos);
+            if comma.is_some() {
+                self.pos += 1;
+            } else if self.peek() != Some(b'}') {
+                return Err(self.error("`,` or `}`"));
+            }
+            members.push(Member {
+                key,
+                key_start,
+                value,
+                comma,
+            });
+        }
+        Ok(Object {
+            open,
+            close: self.pos,
+            members,
+        })
+    }
+}
+
+/// The top-level object of a config file
+fn root(text: &str) -> Result<Object> {
+    let mut scanner = Scanner::new(text, 0);
+    scanner.skip_trivia();
+    if scanner.peek() != Some(b'{') {
+        return Err(scanner.error("a JSON object"));
+    }
+    scanner.object()
+}
+
+fn object_at(text: &str, open: usize) -> Result<Object> {
+    Scanner::new(text, open).object()
+}
+
+fn key_parts(key: &str) -> Result<Vec<&str>> {
+    let parts: Vec<&str> = key.split('.').collect();
+    if parts.iter().any(|part| part.is_empty()) {
+        anyhow::bail!("Invalid config key: {}", key);
+    }
+    Ok(parts)
+}
+
+/// Offset of the start of the line `pos` is on
+fn line_start(text: &str, pos: usize) -> usize {
+    text[..pos].rfind('\n').map_or(0, |i| i + 1)
+}
+
+/// Leading whitespace of the line `pos` is on
+fn line_indent(text: &str, pos: usize) -> &str {
+    let start = line_start(text, pos);
+    let line = &text[start..];
+    &line[..line.len() - line.trim_start_matches([' ', '\t']).len()]
+}
+
+/// A value as pretty JSON, with its continuation lines indented by `indent`
+fn render(value: &Value, indent: &str) -> String {
+    let json = serde_json::to_string_pretty(value).unwrap_or_else(|_| value.to_string());
+    json.replace('\n', &format!("\n{}", indent))
+}
+
+/// Set a dotted key, replacing its value or adding it and the objects on the
+/// way to it
+pub fn set(text: &str, key: &str, value: &Value) -> Result<String> {
+    let parts = key_parts(key)?;
+    let mut object = root(text)?;
+    for (i, part) in parts.iter().enumerate() {
+        let Some(member) = object.members.iter().rev().find(|m| m.key == *part) else {
+            let nested = parts[i + 1..].iter().rev().fold(
+                value.clone(),
+                |value, part| serde_json::json!({ *part: value }),
+            );
+            return Ok(insert(text, &object, part, &nested));
+        };
+        if i == parts.len() - 1 {
+            let indent = line_indent(text, member.key_start);
+            let mut edited = text.to_string();
+            edited.replace_range(member.value.clone(), &render(value, indent));
+            return Ok(edited);
+        }
+        if !text[member.value.clone()].starts_with('{') {
+            anyhow::bail!(
+                "Cannot set {}: {} is not an object",
+                key,
+                parts[..=i].join(".")
+            );
+        }
+        object = object_at(text, member.value.start)?;
+    }
+    unreachable!("a key has at least one part")
+}
+
+/// Add a member at the end of an object
+fn insert(text: &str, object: &Object, key: &str, value: &Value) -> String {
+    let key = Value::String(key.to_string());
+    let mut edited = text.to_string();
+    match object.members.last() {
+        Some(last) => {
+            let indent = line_indent(text, last.key_start);
+            let member = format!(",\n{}{}: {}", indent, key, render(value, indent));
+            edited.insert_str(last.value.end, &member);
+        }
+        None => {
+            let close_indent = line_indent(text, object.close);
+            let indent = format!("{}  ", close_indent);
+            let member = format!(
+                "\n{}{}: {}\n{}",
+                indent,
+                key,
+                render(value, &indent),
+                close_indent
+            );
+            let inside = object.open + 1..object.close;
+            if text[inside.clone()].trim().is_empty() {
+                edited.replace_range(inside, &member);
+            } else {
+                edited.insert_str(object.open + 1, &member);
+            }
+        }
+    }
+    edited
+}
+
+/// Remove a dotted key, or `None` when it is not set
+pub fn unset(text: &str, key: &str) -> Result<Option<String>> {
+    let parts = key_parts(key)?;
+    let mut object = root(text)?;
+    for (i, part) in parts.iter().enumerate() {
+        let Some(index) = object.members.iter().rposition(|m| m.key == *part) else {
+            return Ok(None);
+        };
+        let member = &object.members[index];
+        if i < parts.len() - 1 {
+            if !text[member.value.clone()].starts_with('{') {
+                return Ok(None);
+            }
+            object = object_at(text, member.value.start)?;
+            continue;
+        }
+
+        let range = match (object.members.get(index + 1), member.comma) {
+            // Through the comma and the rest of its line, or up to the next key
+            (Some(next), Some(comma)) => {
+                let rest = &text[comma + 1..];
+                let blank = rest.len() - rest.trim_start_matches([' ', '\t', '\r']).len();
+                let start = line_start(text, member.key_start);
+                if rest[blank..].starts_with('\n')
+                    && text[start..member.key_start].trim().is_empty()
+                {
+                    start..comma + 1 + blank + 1
+                } else {
+                    member.key_start..next.key_start
+                }
+            }
+            // The last member takes the comma before it
+            _ if index > 0 => {
+                let previous = &object.members[index - 1];
+                let end = member.comma.map_or(member.value.end, |comma| comma + 1);
+                previous.comma.unwrap_or(previous.value.end)..end
+            }
+            _ => object.open + 1..object.close,
+        };
+        let mut edited = text.to_string();
+        edited.replace_range(range, "");
+        return Ok(Some(edited));
+    }
+    unreachable!("a key has at least one part")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const CONFIG: &str = r#"{
+  // Default model
+  "model": "openai/gpt-4o",
+  "tui": {
+    "scroll_speed": 3, /* lines */
+  },
+  "share": "disabled"
+}
+"#;
+
+    #[test]
+    fn test_set_keeps_comments() {
+        let edited = set(CONFIG, "model", &json!("anthropic/claude-sonnet-4")).unwrap();
+        assert_eq!(
+            edited,
+            CONFIG.replace("openai/gpt-4o", "anthropic/claude-sonnet-4")
+        );
+
+        let edited = set(CONFIG, "tui.diff_style", &json!("stacked")).unwrap();
+        assert!(edited.contains(
+            "    \"scroll_speed\": 3,\n    \"diff_style\": \"stacked\", /* lines */\n  },"
+        ));
+
+        let edited = set(CONFIG, "output.default_format", &json!("json")).unwrap();
+        assert!(edited.starts_with("{\n  // Default model\n"));
+        assert!(edited.ends_with(
+            "  \"share\": \"disabled\",\n  \"output\": {\n    \"default_format\": \"json\"\n  }\n}\n"
+        ));
+
+        let edited = set("{}", "model", &json!("openai/gpt-4o")).unwrap();
+        assert_eq!(edited, "{\n  \"model\": \"openai/gpt-4o\"\n}");
+
+        let err = set(CONFIG, "model.name", &json!("x")).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Cannot set model.name: model is not an object"
+        );
+        assert!(set("[1]", "model", &json!("x")).is_err());
+        assert!(set(CONFIG, "tui..x", &json!(1)).is_err());
+    }
+
+    #[test]
+    fn test_unset() {
+        let edited = unset(CONFIG, "model").unwrap().unwrap();
+        assert_eq!(
+            edited,
+            CONFIG.replace("  \"model\": \"openai/gpt-4o\",\n", "")
+        );
+
+        let edited = unset(CONFIG, "share").unwrap().unwrap();
+        assert!(edited.ends_with("    \"scroll_speed\": 3, /* lines */\n  }\n}\n"));
+
+        let edited = unset(CONFIG, "tui.scroll_speed").unwrap().unwrap();
+        assert!(edited.contains("  \"tui\": {},\n"));
+
+        assert!(unset(CONFIG, "tui.notify").unwrap().is_none());
+        assert!(unset(CONFIG, "model.name").unwrap().is_none());
+    }
+}
@@ -5,11 +5,14 @@ pub mod bus;
 pub mod cli;
 pub mod config;
 pub mod id;
+pub mod lint;
 pub mod oauth;
 pub mod permission;
 pub mod permission_state;
 pub mod provider;
 pub mod question_state;
+pub mod redact;
+pub mod server;
 pub mod session;
 pub mod slash_command;
 pub mod storage;
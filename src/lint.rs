@@ -0,0 +1,293 @@
+//! Checks of custom definitions in the config and command files.
+//!
+//! `opencode config lint` reports mistakes that would otherwise only show up
+//! at runtime, often as confusing behavior: placeholders a command template
+//! leaves as written, models and agents that do not exist, and custom
+//! providers without a usable URL.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use crate::config::{CommandConfig, Config, ProviderConfig};
+use crate::provider;
+use crate::slash_command::parser::template_problems;
+
+/// A problem found in a definition
+#[derive(Debug, Clone, PartialEq)]
+pub struct Warning {
+    /// Where the definition is, e.g. `command.review` or a command file path
+    pub location: String,
+    pub message: String,
+}
+
+impl Warning {
+    fn new(location: &str, message: impl Into<String>) -> Self {
+        Self {
+            location: location.to_string(),
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.location, self.message)
+    }
+}
+
+/// Model IDs per provider ID, to check model strings against
+pub type Catalog = HashMap<String, HashSet<String>>;
+
+/// Check the config and the commands loaded from markdown files
+///
+/// `command_files` pairs each file's location with its definition; commands
+/// defined in the config are taken from `config.command`.
+pub fn lint(
+    config: &Config,
+    command_files: &[(String, CommandConfig)],
+    catalog: &Catalog,
+) -> Vec<Warning> {
+    let mut warnings = Vec::new();
+    let agents: HashSet<&str> = config
+        .agent
+        .iter()
+        .flat_map(|agents| agents.keys().map(String::as_str))
+        .collect();
+
+    let mut config_commands: Vec<_> = config.command.iter().flatten().collect();
+    config_commands.sort_by_key(|(name, _)| name.as_str());
+    let config_commands = config_commands
+        .into_iter()
+        .map(|(name, command)| (format!("command.{}", name), command));
+    let files = command_files
+        .iter()
+        .map(|(location, command)| (location.clone(), command));
+    for (location, command) in config_commands.chain(files) {
+        lint_command(&location, command, &agents, catalog, &mut warnings);
+    }
+
+    let models = [
+        ("model", &config.model),
+        ("small_model", &config.small_model),
+    ];
+    for (field, model) in models {
+        if let Some(model) = model {
+            lint_model(field, model, catalog, &mut warnings);
+        }
+    }
+    for (i, model) in config.model_fallbacks.iter().flatten().enumerate() {
+        lint_model(
+            &format!("model_fallbacks[{}]", i),
+            model,
+            catalog,
+            &mut warnings,
+        );
+    }
+    let mut aliases: Vec<_> = config.aliases.iter().flatten().collect();
+    aliases.sort();
+    for (alias, model) in aliases {
+        lint_model(&format!("aliases.{}", alias), model, catalog, &mut warnings);
+    }
+    if let Some(agent) = &config.default_agent {
+        lint_agent("default_agent", agent, &agents, &mut warnings);
+    }
+
+    let mut agent_configs: Vec<_> = config.agent.iter().flatten().collect();
+    agent_configs.sort_by_key(|(name, _)| name.as_str());
+    for (name, agent) in agent_configs {
+        if let Some(model) = &agent.model {
+            let location = format!("agent.{}.model", name);
+            lint_model(&location, model, catalog, &mut warnings);
+        }
+    }
+
+    let mut providers: Vec<_> = config.provider.iter().flatten().collect();
+    providers.sort_by_key(|(id, _)| id.as_str());
+    for (id, provider) in providers {
+        lint_provider(id, provider, catalog, &mut warnings);
+    }
+
+    warnings
+}
+
+fn lint_command(
+    location: &str,
+    command: &CommandConfig,
+    agents: &HashSet<&str>,
+    catalog: &Catalog,
+    warnings: &mut Vec<Warning>,
+) {
+    if command.template.trim().is_empty() {
+        warnings.push(Warning::new(location, "template is empty"));
+    }
+    for problem in template_problems(&command.template) {
+        warnings.push(Warning::new(location, problem));
+    }
+    if let Some(agent) = &command.agent {
+        lint_agent(location, agent, agents, warnings);
+    }
+    if let Some(model) = &command.model {
+        lint_model(location, model, catalog, warnings);
+    }
+}
+
+fn lint_agent(location: &str, agent: &str, agents: &HashSet<&str>, warnings: &mut Vec<Warning>) {
+    if !agents.contains(agent) {
+        warnings.push(Warning::new(
+            location,
+            format!("agent '{}' is not defined under `agent`", agent),
+        ));
+    }
+}
+
+fn lint_model(location: &str, model: &str, catalog: &Catalog, warnings: &mut Vec<Warning>) {
+    let Some((provider_id, model_id)) = provider::parse_model_string(model) else {
+        warnings.push(Warning::new(
+            location,
+            format!("model '{}' is not in provider/model format", model),
+        ));
+        return;
+    };
+    let message = match catalog.get(&provider_id) {
+        None => format!("unknown provider '{}' in model '{}'", provider_id, model),
+        Some(models) if !models.contains(&model_id) => format!(
+            "unknown model '{}'; `opencode models` lists the available ones",
+            model
+        ),
+        Some(_) => return,
+    };
+    warnings.push(Warning::new(location, message));
+}
+
+fn lint_provider(
+    id: &str,
+    provider: &ProviderConfig,
+    catalog: &Catalog,
+    warnings: &mut Vec<Warning>,
+) {
+    let location = format!("provider.{}", id);
+    let custom = provider.source.as_deref() == Some("custom");
+    if !custom && !catalog.contains_key(id) {
+        warnings.push(Warning::new(
+            &location,
+            "unknown provider; set `\"source\": \"custom\"` to define an OpenAI-compatible one",
+        ));
+    }
+
+    match provider.base_url.as_deref().filter(|url| !url.is_empty()) {
+        Some(url) => {
+            let scheme = reqwest::Url::parse(url).map(|url| url.scheme().to_string());
+            if !matches!(scheme.as_deref(), Ok("http" | "https")) {
+                warnings.push(Warning::new(
+                    &location,
+                    format!("`base_url` '{}' is not an http(s) URL", url),
+                ));
+            }
+        }
+        None if custom => warnings.push(Warning::new(
+            &location,
+            "custom provider has no `base_url`, so it is skipped",
+        )),
+        None => {}
+    }
+
+    if custom && provider.models.as_ref().is_none_or(HashMap::is_empty) {
+        warnings.push(Warning::new(
+            &location,
+            "custom provider defines no `models`",
+        ));
+    }
+    if let Some(var) = &provider.api_key_env {
+        if std::env::var_os(var).is_none() {
+            warnings.push(Warning::new(
+                &location,
+                format!("`api_key_env` variable {} is not set", var),
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn catalog() -> Catalog {
+        HashMap::from([(
+            "anthropic".to_string(),
+            HashSet::from(["claude-sonnet-4".to_string()]),
+        )])
+    }
+
+    fn messages(warnings: &[Warning]) -> Vec<String> {
+        warnings.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn test_clean_config() {
+        let config: Config = serde_json::from_value(serde_json::json!({
+            "model": "anthropic/claude-sonnet-4",
+            "agent": {"reviewer": {"model": "anthropic/claude-sonnet-4"}},
+            "command": {
+                "review": {"template": "Review $1", "agent": "reviewer"}
+            }
+        }))
+        .unwrap();
+        assert_eq!(lint(&config, &[], &catalog()), vec![]);
+    }
+
+    #[test]
+    fn test_command_problems() {
+        let config: Config = serde_json::from_value(serde_json::json!({
+            "command": {
+                "explain": {"template": "Explain $ARGS", "agent": "missing"}
+            }
+        }))
+        .unwrap();
+        let file = CommandConfig {
+            template: "Fix $1".to_string(),
+            description: None,
+            agent: None,
+            model: Some("anthropic/claude-nope".to_string()),
+            subtask: None,
+        };
+        let warnings = lint(&config, &[("fix.md".to_string(), file)], &catalog());
+        assert_eq!(
+            messages(&warnings),
+            vec![
+                "command.explain: unknown placeholder `$ARGS`; use `$1`, `$2`, ... or `$ARGUMENTS`",
+                "command.explain: agent 'missing' is not defined under `agent`",
+                "fix.md: unknown model 'anthropic/claude-nope'; `opencode models` lists the available ones",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_provider_problems() {
+        let config: Config = serde_json::from_value(serde_json::json!({
+            "model": "sonnet",
+            "model_fallbacks": ["local/qwen"],
+            "aliases": {"opus": "anthropic/claude-opus-4"},
+            "provider": {
+                "local": {
+                    "source": "custom",
+                    "base_url": "localhost:8000",
+                    "api_key_env": "OPENCODE_LINT_TEST_UNSET_KEY"
+                },
+                "typo": {}
+            }
+        }))
+        .unwrap();
+        assert_eq!(
+            messages(&lint(&config, &[], &catalog())),
+            vec![
+                "model: model 'sonnet' is not in provider/model format",
+                "model_fallbacks[0]: unknown provider 'local' in model 'local/qwen'",
+                "aliases.opus: unknown model 'anthropic/claude-opus-4'; `opencode models` lists the available ones",
+                "provider.local: `base_url` 'localhost:8000' is not an http(s) URL",
+                "provider.local: custom provider defines no `models`",
+                "provider.local: `api_key_env` variable OPENCODE_LINT_TEST_UNSET_KEY is not set",
+                "provider.typo: unknown provider; set `\"source\": \"custom\"` to define an OpenAI-compatible one",
+            ]
+        );
+    }
+}
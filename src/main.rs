@@ -1,5 +1,6 @@
 use anyhow::Result;
 use clap::{Parser, Subcommand};
+use std::path::PathBuf;
 use tracing_subscriber::EnvFilter;
 
 mod auth;
@@ -7,11 +8,14 @@ mod bus;
 mod cli;
 mod config;
 mod id;
+mod lint;
 mod oauth;
 mod permission;
 mod permission_state;
 mod provider;
 mod question_state;
+mod redact;
+mod server;
 mod session;
 mod slash_command;
 mod storage;
@@ -31,6 +35,18 @@ struct Cli {
     #[arg(short = 'C', long, global = true)]
     directory: Option<String>,
 
+    /// Do not redact secrets from shares, exports and clipboard copies
+    #[arg(long, global = true)]
+    no_redact: bool,
+
+    /// Make no network calls: use the cached model catalog and refuse prompts
+    #[arg(long, global = true)]
+    offline: bool,
+
+    /// Load the system prompt from a file (replaces the configured system_file)
+    #[arg(long, global = true, value_name = "FILE")]
+    system_file: Option<PathBuf>,
+
     #[command(subcommand)]
     command: Option<Commands>,
 }
@@ -44,9 +60,13 @@ enum Commands {
         #[arg(short, long)]
         prompt: Option<String>,
 
-        /// Model to use (provider/model format)
+        /// Model to use (provider/model format, or a model alias)
         #[arg(short, long)]
         model: Option<String>,
+
+        /// Text to append to the session's system prompt
+        #[arg(long, value_name = "TEXT")]
+        append_system: Option<String>,
     },
 
     /// Run a single prompt without TUI
@@ -55,13 +75,55 @@ enum Commands {
         /// The prompt to send
         prompt: String,
 
-        /// Model to use (provider/model format)
+        /// Model to use (provider/model format, or a model alias)
         #[arg(short, long)]
         model: Option<String>,
 
-        /// Output format (text, json, markdown)
-        #[arg(short, long, default_value = "text")]
+        /// Model to try when the model is overloaded (repeatable, in order;
+        /// replaces the configured model_fallbacks)
+        #[arg(long = "model-fallback", value_name = "PROVIDER/MODEL")]
+        model_fallbacks: Vec<String>,
+
+        /// Output format (text, json, markdown); defaults to the config's
+        /// `output.default_format`, else text
+        #[arg(short, long)]
+        format: Option<String>,
+
+        /// JSON schema file the final answer must conform to
+        #[arg(long, value_name = "FILE")]
+        json_schema: Option<PathBuf>,
+    },
+
+    /// Run the prompts of a file without TUI, one result per prompt
+    Batch {
+        /// File with one prompt per line: a JSON object with `prompt` (and
+        /// optional `id` and `model`) or plain text
+        file: PathBuf,
+
+        /// Model to use (provider/model format, or a model alias)
+        #[arg(short, long)]
+        model: Option<String>,
+
+        /// Model to try when the model is overloaded (repeatable, in order;
+        /// replaces the configured model_fallbacks)
+        #[arg(long = "model-fallback", value_name = "PROVIDER/MODEL")]
+        model_fallbacks: Vec<String>,
+
+        /// Output format (ndjson, json)
+        #[arg(short, long, default_value = "ndjson")]
         format: String,
+
+        /// JSON schema file the final answers must conform to
+        #[arg(long, value_name = "FILE")]
+        json_schema: Option<PathBuf>,
+
+        /// Number of prompts to run at once
+        #[arg(short = 'j', long, default_value = "1")]
+        concurrency: usize,
+
+        /// Keep running the remaining prompts after one fails
+        #[arg(long)]
+        continue_on_error: bool,
     },
 
     /// Start the HTTP server
@@ -87,6 +149,27 @@ enum Commands {
         command: ConfigCommands,
     },
 
+    /// Manage the models catalog
+    Models {
+        #[command(subcommand)]
+        command: ModelsCommands,
+    },
+
+    /// Maintain stored data
+    Storage {
+        #[command(subcommand)]
+        command: StorageCommands,
+    },
+
+    /// Inspect the tools the agent can call
+    Tools {
+        #[command(subcommand)]
+        command: ToolsCommands,
+    },
+
+    /// Check the setup and suggest fixes for what is wrong
+    Doctor,
+
     /// Show version information
     Version,
 }
@@ -94,16 +177,99 @@ enum Commands {
 #[derive(Subcommand)]
 enum SessionCommands {
     /// List all sessions
-    List,
+    List {
+        /// List sessions in the trash instead
+        #[arg(long)]
+        trash: bool,
+    },
     /// Show session details
     Show {
         /// Session ID
         id: String,
     },
-    /// Delete a session
+    /// Delete a session (moves it to the trash unless --purge is given)
     Delete {
         /// Session ID
         id: String,
+
+        /// Skip the confirmation prompt
+        #[arg(short, long)]
+        yes: bool,
+
+        /// Permanently delete instead of moving to the trash
+        #[arg(long)]
+        purge: bool,
+    },
+    /// Restore a deleted session from the trash
+    Restore {
+        /// Session ID
+        id: String,
+    },
+    /// List tool executions in a session
+    Tools {
+        /// Session ID
+        id: String,
+
+        /// Only show calls to this tool (e.g. bash)
+        #[arg(long)]
+        filter: Option<String>,
+
+        /// Output format (text, json)
+        #[arg(short, long, default_value = "text")]
+        format: String,
+    },
+    /// Print a session's tool calls as a shell script
+    ExportScript {
+        /// Session ID
+        id: String,
+    },
+    /// Print a session's stored records as raw JSON, for debugging
+    Dump {
+        /// Session ID
+        id: String,
+
+        /// Pretty-print the JSON (default)
+        #[arg(long, conflicts_with = "compact")]
+        pretty: bool,
+
+        /// Print the JSON on one line
+        #[arg(long)]
+        compact: bool,
+    },
+    /// Compare the assistant replies and changed files of two sessions
+    Diff {
+        /// Session ID of the first (old) side
+        left: String,
+
+        /// Session ID of the second (new) side
+        right: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum ModelsCommands {
+    /// Re-fetch models from models.dev and update the local cache
+    Refresh {
+        /// Only refresh this provider (e.g. anthropic)
+        #[arg(long)]
+        provider: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum StorageCommands {
+    /// Upgrade stored sessions, messages and parts to the current schema
+    Migrate,
+}
+
+#[derive(Subcommand)]
+enum ToolsCommands {
+    /// List the built-in tools, their parameters, whether they are enabled and
+    /// what the config's permission rules do with their calls
+    List {
+        /// Print the tool definitions as JSON
+        #[arg(long)]
+        json: bool,
     },
 }
 
@@ -115,6 +281,30 @@ enum ConfigCommands {
     Path,
     /// Initialize configuration file with defaults
     Init,
+    /// Print a model's effective definition as a config snippet
+    ExportModel {
+        /// Model to export (provider/model)
+        model: String,
+    },
+    /// Check custom commands, models, agents and providers for mistakes
+    Lint,
+    /// Set a key of the global config file, e.g. `output.default_format json`
+    Set {
+        /// Dotted key to set
+        key: String,
+        /// Value, as JSON or else a plain string
+        value: String,
+    },
+    /// Print the effective value of a key, e.g. `output.default_format`
+    Get {
+        /// Dotted key to print
+        key: String,
+    },
+    /// Remove a key from the global config file
+    Unset {
+        /// Dotted key to remove
+        key: String,
+    },
 }
 
 #[tokio::main]
@@ -133,39 +323,114 @@ async fn main() -> Result<()> {
         .with_target(false)
         .init();
 
+    if cli.no_redact {
+        redact::disable();
+    }
+
+    if cli.offline {
+        provider::offline::enable();
+    }
+
+    // Resolved before changing directory, so the path is relative to where
+    // the command was run
+    if let Some(path) = &cli.system_file {
+        let path = std::path::absolute(path)?;
+        if !path.is_file() {
+            anyhow::bail!("System prompt file not found: {}", path.display());
+        }
+        session::system::set_file_override(path);
+    }
+
     // Change directory if specified
     if let Some(dir) = &cli.directory {
         std::env::set_current_dir(dir)?;
     }
 
+    // Point storage at the configured root before anything reads from it
+    match config::Config::load().await {
+        Ok(config) => {
+            let cwd = std::env::current_dir()?;
+            storage::init(storage::StorageConfig::from_config(&config, &cwd));
+        }
+        Err(e) => tracing::warn!("Failed to load config for storage location: {}", e),
+    }
+
     // Initialize permission state (load saved rules)
     if let Err(e) = permission_state::initialize().await {
         tracing::warn!("Failed to initialize permission state: {}", e);
     }
 
     match cli.command {
-        Some(Commands::Run { prompt, model }) => {
-            cli::run::execute(prompt, model).await?;
+        Some(Commands::Run {
+            prompt,
+            model,
+            append_system,
+        }) => {
+            cli::run::execute(prompt, model, append_system).await?;
         }
         Some(Commands::Prompt {
             prompt,
             model,
+            model_fallbacks,
             format,
+            json_schema,
         }) => {
-            cli::prompt::execute(&prompt, model.as_deref(), &format).await?;
+            cli::prompt::execute(
+                &prompt,
+                model.as_deref(),
+                &model_fallbacks,
+                format.as_deref(),
+                json_schema.as_deref(),
+            )
+            .await?;
+        }
+        Some(Commands::Batch {
+            file,
+            model,
+            model_fallbacks,
+            format,
+            json_schema,
+            concurrency,
+            continue_on_error,
+        }) => {
+            cli::batch::execute(
+                &file,
+                model.as_deref(),
+                &model_fallbacks,
+                &format,
+                json_schema.as_deref(),
+                concurrency,
+                continue_on_error,
+            )
+            .await?;
         }
         Some(Commands::Serve { port, host }) => {
             cli::serve::execute(&host, port).await?;
         }
         Some(Commands::Session { command }) => match command {
-            SessionCommands::List => {
-                cli::session::list().await?;
+            SessionCommands::List { trash } => {
+                cli::session::list(trash).await?;
             }
             SessionCommands::Show { id } => {
                 cli::session::show(&id).await?;
             }
-            SessionCommands::Delete { id } => {
-                cli::session::delete(&id).await?;
+            SessionCommands::Delete { id, yes, purge } => {
+                cli::session::delete(&id, yes, purge).await?;
+            }
+            SessionCommands::Restore { id } => {
+                cli::session::restore(&id).await?;
+            }
+            SessionCommands::Tools { id, filter, format } => {
+                cli::session::tools(&id, filter.as_deref(), &format).await?;
+            }
+            SessionCommands::ExportScript { id } => {
+                cli::session::export_script(&id).await?;
+            }
+            SessionCommands::Dump { id, compact, .. } => {
+                cli::session::dump(&id, compact).await?;
+            }
+            SessionCommands::Diff { left, right } => {
+                cli::session::diff(&left, &right).await?;
             }
         },
         Some(Commands::Config { command }) => match command {
@@ -178,13 +443,46 @@ async fn main() -> Result<()> {
             ConfigCommands::Init => {
                 cli::config::init().await?;
             }
+            ConfigCommands::ExportModel { model } => {
+                cli::config::export_model(&model).await?;
+            }
+            ConfigCommands::Lint => {
+                cli::config::lint().await?;
+            }
+            ConfigCommands::Set { key, value } => {
+                cli::config::set(&key, &value).await?;
+            }
+            ConfigCommands::Get { key } => {
+                cli::config::get(&key).await?;
+            }
+            ConfigCommands::Unset { key } => {
+                cli::config::unset(&key).await?;
+            }
+        },
+        Some(Commands::Models { command }) => match command {
+            ModelsCommands::Refresh { provider } => {
+                cli::models::refresh(provider.as_deref()).await?;
+            }
+        },
+        Some(Commands::Storage { command }) => match command {
+            StorageCommands::Migrate => {
+                cli::storage::migrate().await?;
+            }
         },
+        Some(Commands::Tools { command }) => match command {
+            ToolsCommands::List { json } => {
+                cli::tools::list(json).await?;
+            }
+        },
+        Some(Commands::Doctor) => {
+            cli::doctor::execute().await?;
+        }
         Some(Commands::Version) => {
             println!("opencode {}", env!("CARGO_PKG_VERSION"));
         }
         None => {
             // Default: start TUI
-            cli::run::execute(None, None).await?;
+            cli::run::execute(None, None, None).await?;
         }
     }
 
@@ -126,6 +126,87 @@ pub async fn copilot_poll_for_token(device_code: &str, interval: u64) -> Result<
     }
 }
 
+// ============================================================================
+// GitHub Copilot API Token Exchange
+// ============================================================================
+
+const COPILOT_TOKEN_URL: &str = "https://api.github.com/copilot_internal/v2/token";
+
+/// Refresh the Copilot API token this many seconds before it expires
+const COPILOT_REFRESH_MARGIN_SECS: i64 = 60;
+
+/// Copilot API token exchanged from the GitHub token, kept for this process
+static COPILOT_TOKEN: LazyLock<tokio::sync::Mutex<Option<OAuthTokenInfo>>> =
+    LazyLock::new(|| tokio::sync::Mutex::new(None));
+
+/// Response from the Copilot token endpoint
+#[derive(Debug, Deserialize)]
+struct CopilotTokenResponse {
+    token: String,
+    /// Expiry as a Unix timestamp (seconds)
+    expires_at: i64,
+}
+
+/// Get a Copilot API token for a GitHub token
+///
+/// The device flow yields a long-lived GitHub token, but the Copilot API only
+/// accepts short-lived tokens exchanged from it. The exchanged token is cached
+/// in memory and exchanged again shortly before it expires.
+pub async fn copilot_api_token(github_token: &str) -> Result<String> {
+    let mut cached = COPILOT_TOKEN.lock().await;
+    if let Some(token) = cached
+        .as_ref()
+        .filter(|t| t.refresh == github_token && !t.is_expired())
+    {
+        return Ok(token.access.clone());
+    }
+
+    let token = copilot_exchange_token(github_token).await?;
+    let access = token.access.clone();
+    *cached = Some(token);
+    Ok(access)
+}
+
+/// Exchange a GitHub token for a Copilot API token
+async fn copilot_exchange_token(github_token: &str) -> Result<OAuthTokenInfo> {
+    let response = HTTP_CLIENT
+        .get(COPILOT_TOKEN_URL)
+        .header("Accept", "application/json")
+        .header("Authorization", format!("token {}", github_token))
+        .header("User-Agent", "opencode-rs/0.1.0")
+        .header("editor-version", "opencode/0.1.0")
+        .send()
+        .await
+        .context("Failed to exchange GitHub token for a Copilot token")?;
+
+    let status = response.status();
+    if status == reqwest::StatusCode::UNAUTHORIZED {
+        anyhow::bail!(
+            "GitHub token was rejected. Please reauthenticate with the copilot provider."
+        );
+    }
+    if status == reqwest::StatusCode::FORBIDDEN || status == reqwest::StatusCode::NOT_FOUND {
+        anyhow::bail!("This GitHub account does not have access to GitHub Copilot.");
+    }
+    if !status.is_success() {
+        let error = response
+            .text()
+            .await
+            .unwrap_or_else(|_| "Unknown error".to_string());
+        anyhow::bail!("Copilot token exchange failed ({}): {}", status, error);
+    }
+
+    let exchanged: CopilotTokenResponse = response
+        .json()
+        .await
+        .context("Failed to parse Copilot token response")?;
+
+    Ok(OAuthTokenInfo::new_copilot_api(
+        github_token.to_string(),
+        exchanged,
+    ))
+}
+
 // ============================================================================
 // OpenAI Codex OAuth PKCE Flow
 // ============================================================================
@@ -331,7 +412,20 @@ impl OAuthTokenInfo {
             auth_type: "oauth".to_string(),
             refresh: access_token.clone(),
             access: access_token,
-            expires: 0, // Copilot tokens don't expire in traditional sense
+            // The GitHub token does not expire; the Copilot API token exchanged
+            // from it does, see `copilot_api_token`
+            expires: 0,
+            account_id: None,
+        }
+    }
+
+    /// Exchanged Copilot API token, with the GitHub token it came from
+    fn new_copilot_api(github_token: String, exchanged: CopilotTokenResponse) -> Self {
+        Self {
+            auth_type: "oauth".to_string(),
+            refresh: github_token,
+            access: exchanged.token,
+            expires: exchanged.expires_at - COPILOT_REFRESH_MARGIN_SECS,
             account_id: None,
         }
     }
@@ -358,3 +452,32 @@ impl OAuthTokenInfo {
         chrono::Utc::now().timestamp() > self.expires
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_copilot_api_token_expires_before_deadline() {
+        let now = chrono::Utc::now().timestamp();
+        let token = OAuthTokenInfo::new_copilot_api(
+            "gho_github".to_string(),
+            CopilotTokenResponse {
+                token: "tid=abc".to_string(),
+                expires_at: now + COPILOT_REFRESH_MARGIN_SECS / 2,
+            },
+        );
+        assert_eq!(token.refresh, "gho_github");
+        assert_eq!(token.access, "tid=abc");
+        assert!(token.is_expired());
+
+        let token = OAuthTokenInfo::new_copilot_api(
+            "gho_github".to_string(),
+            CopilotTokenResponse {
+                token: "tid=abc".to_string(),
+                expires_at: now + 30 * 60,
+            },
+        );
+        assert!(!token.is_expired());
+    }
+}
@@ -3,6 +3,16 @@
 //! This module provides:
 //! - Doom loop permission checking for CLI mode
 //! - Configuration-based permission rules
+//!
+//! Tools listed in `permission.auto_allow` run without asking; the default
+//! list holds only read-only tools. Every other permission asks unless the
+//! config sets an action for it, e.g. `"permission": {"bash": "deny"}`.
+//!
+//! A session may carry its own rules in the same format, where `*` matches
+//! every permission. They are layered over the config, so a session can be
+//! more or less restrictive than the default. Precedence, highest first:
+//! session rules, remembered workspace approvals, remembered global
+//! approvals, then the config.
 
 use std::collections::HashMap;
 use std::io::{self, Write};
@@ -11,46 +21,110 @@ use anyhow::Result;
 
 use crate::config::{Config, PermissionAction, PermissionRule};
 
-/// Default permission rules for tools
-const DEFAULT_PERMISSIONS: &[(&str, PermissionAction)] = &[
-    ("read", PermissionAction::Allow),
-    ("write", PermissionAction::Ask),
-    ("edit", PermissionAction::Ask),
-    ("bash", PermissionAction::Ask),
-    ("glob", PermissionAction::Allow),
-    ("grep", PermissionAction::Allow),
-    ("question", PermissionAction::Allow),
-    ("todowrite", PermissionAction::Allow),
-    ("todoread", PermissionAction::Allow),
-    ("webfetch", PermissionAction::Ask),
-    ("doom_loop", PermissionAction::Ask),
-];
-
-/// Permission checker for tools (primarily used for doom loop detection in CLI mode)
+/// Config key holding the tools that never ask for permission
+const AUTO_ALLOW_KEY: &str = "auto_allow";
+
+/// Session rule key matching every permission
+const WILDCARD_KEY: &str = "*";
+
+/// Read-only tools allowed without asking, unless `permission.auto_allow` is set
+const DEFAULT_AUTO_ALLOW: &[&str] = &["read", "glob", "grep", "tree", "ls", "git"];
+
+/// Permission checker for tools
+#[derive(Debug, Clone)]
 pub struct PermissionChecker {
     rules: HashMap<String, PermissionAction>,
+    auto_allow: Vec<String>,
+    session_rules: HashMap<String, PermissionAction>,
 }
 
 impl PermissionChecker {
     /// Create a new permission checker from config
     pub fn from_config(config: &Config) -> Self {
-        let mut rules = Self::default_rules();
+        let mut checker = Self::default();
 
         if let Some(permissions) = &config.permission {
             for (key, rule) in permissions {
-                let action = match rule {
-                    PermissionRule::Action(action) => action.clone(),
-                    PermissionRule::Object(obj) => obj
-                        .values()
-                        .next()
-                        .cloned()
-                        .unwrap_or(PermissionAction::Ask),
-                };
-                rules.insert(key.clone(), action);
+                match rule {
+                    PermissionRule::List(tools) if key == AUTO_ALLOW_KEY => {
+                        checker.auto_allow = tools.clone();
+                    }
+                    _ => {
+                        if let Some(action) = rule_action(key, rule) {
+                            checker.rules.insert(key.clone(), action);
+                        }
+                    }
+                }
             }
         }
 
-        Self { rules }
+        checker
+    }
+
+    /// Layer the rules stored on a session (`Session::permission`) over the
+    /// config
+    ///
+    /// The value has the config's format. Tools in its `auto_allow` list are
+    /// allowed in addition to the configured ones.
+    pub fn with_session(mut self, permission: Option<&serde_json::Value>) -> Self {
+        let Some(permission) = permission else {
+            return self;
+        };
+        let permissions: HashMap<String, PermissionRule> =
+            match serde_json::from_value(permission.clone()) {
+                Ok(permissions) => permissions,
+                Err(e) => {
+                    tracing::warn!("Ignoring session permission rules: {}", e);
+                    return self;
+                }
+            };
+
+        for (key, rule) in &permissions {
+            match rule {
+                PermissionRule::List(tools) if key == AUTO_ALLOW_KEY => {
+                    for tool in tools {
+                        self.session_rules
+                            .insert(tool.clone(), PermissionAction::Allow);
+                    }
+                }
+                _ => {
+                    if let Some(action) = rule_action(key, rule) {
+                        self.session_rules.insert(key.clone(), action);
+                    }
+                }
+            }
+        }
+        self
+    }
+
+    /// Action the session rules set for a permission, if any
+    pub fn session_action(&self, permission: &str) -> Option<PermissionAction> {
+        self.session_rules
+            .get(permission)
+            .or_else(|| self.session_rules.get(WILDCARD_KEY))
+            .cloned()
+    }
+
+    /// Action the config sets for a permission: its configured rule, else
+    /// `Allow` for auto-allowed tools, else `Ask`
+    pub fn config_action(&self, permission: &str) -> PermissionAction {
+        if let Some(action) = self.rules.get(permission) {
+            return action.clone();
+        }
+        if self.auto_allow.iter().any(|tool| tool == permission) {
+            PermissionAction::Allow
+        } else {
+            PermissionAction::Ask
+        }
+    }
+
+    /// Action for a permission: its session rule, else the config's action
+    ///
+    /// Remembered approvals are matched against patterns, so they are applied
+    /// by the permission handlers (see `permission_state`).
+    pub fn action(&self, permission: &str) -> PermissionAction {
+        self.session_action(permission)
+            .unwrap_or_else(|| self.config_action(permission))
     }
 
     /// Check doom loop permission and prompt user if needed
@@ -59,13 +133,7 @@ impl PermissionChecker {
         tool_name: &str,
         _arguments: &str,
     ) -> Result<bool> {
-        let action = self
-            .rules
-            .get("doom_loop")
-            .cloned()
-            .unwrap_or(PermissionAction::Ask);
-
-        match action {
+        match self.action("doom_loop") {
             PermissionAction::Allow => Ok(true),
             PermissionAction::Deny => Ok(false),
             PermissionAction::Ask => {
@@ -86,19 +154,31 @@ impl PermissionChecker {
             }
         }
     }
-
-    fn default_rules() -> HashMap<String, PermissionAction> {
-        DEFAULT_PERMISSIONS
-            .iter()
-            .map(|(k, v)| (k.to_string(), v.clone()))
-            .collect()
-    }
 }
 
 impl Default for PermissionChecker {
     fn default() -> Self {
         Self {
-            rules: Self::default_rules(),
+            rules: HashMap::new(),
+            auto_allow: DEFAULT_AUTO_ALLOW.iter().map(|t| t.to_string()).collect(),
+            session_rules: HashMap::new(),
+        }
+    }
+}
+
+/// Action of a single rule, or `None` (with a warning) if it holds none
+fn rule_action(key: &str, rule: &PermissionRule) -> Option<PermissionAction> {
+    match rule {
+        PermissionRule::Action(action) => Some(action.clone()),
+        PermissionRule::Object(obj) => Some(
+            obj.values()
+                .next()
+                .cloned()
+                .unwrap_or(PermissionAction::Ask),
+        ),
+        PermissionRule::List(_) => {
+            tracing::warn!("Ignoring permission.{}: expected an action", key);
+            None
         }
     }
 }
@@ -110,12 +190,62 @@ mod tests {
     #[test]
     fn test_default_permissions() {
         let checker = PermissionChecker::default();
-        let action = checker
-            .rules
-            .get("doom_loop")
-            .cloned()
-            .unwrap_or(PermissionAction::Deny);
+        assert!(matches!(checker.action("doom_loop"), PermissionAction::Ask));
+        assert!(matches!(checker.action("read"), PermissionAction::Allow));
+        assert!(matches!(checker.action("git"), PermissionAction::Allow));
+        assert!(matches!(checker.action("bash"), PermissionAction::Ask));
+        assert!(matches!(checker.action("webfetch"), PermissionAction::Ask));
+    }
+
+    #[test]
+    fn test_auto_allow_from_config() {
+        let config: Config = serde_json::from_value(serde_json::json!({
+            "permission": {
+                "auto_allow": ["read", "todowrite"],
+                "bash": "deny",
+                "read": "ask"
+            }
+        }))
+        .unwrap();
+        let checker = PermissionChecker::from_config(&config);
+
+        assert!(matches!(
+            checker.action("todowrite"),
+            PermissionAction::Allow
+        ));
+        assert!(matches!(checker.action("grep"), PermissionAction::Ask));
+        assert!(matches!(checker.action("bash"), PermissionAction::Deny));
+        // An explicit rule wins over the auto-allow list
+        assert!(matches!(checker.action("read"), PermissionAction::Ask));
+    }
 
-        assert!(matches!(action, PermissionAction::Ask));
+    #[test]
+    fn test_session_rules_over_config() {
+        let config: Config = serde_json::from_value(serde_json::json!({
+            "permission": {"bash": "deny", "webfetch": "allow"}
+        }))
+        .unwrap();
+        let checker = PermissionChecker::from_config(&config).with_session(Some(
+            &serde_json::json!({"bash": "allow", "webfetch": "ask", "auto_allow": ["edit"]}),
+        ));
+
+        assert!(matches!(checker.action("bash"), PermissionAction::Allow));
+        assert!(matches!(checker.action("webfetch"), PermissionAction::Ask));
+        assert!(matches!(checker.action("edit"), PermissionAction::Allow));
+        assert!(matches!(checker.action("read"), PermissionAction::Allow));
+        assert!(checker.session_action("read").is_none());
+        assert!(matches!(
+            checker.config_action("bash"),
+            PermissionAction::Deny
+        ));
+
+        let throwaway = PermissionChecker::from_config(&config)
+            .with_session(Some(&serde_json::json!({"*": "allow", "bash": "ask"})));
+        assert!(matches!(throwaway.action("write"), PermissionAction::Allow));
+        assert!(matches!(throwaway.action("bash"), PermissionAction::Ask));
+
+        let invalid = PermissionChecker::from_config(&config)
+            .with_session(Some(&serde_json::json!(["bash"])));
+        assert!(matches!(invalid.action("bash"), PermissionAction::Deny));
     }
 }
@@ -8,12 +8,15 @@
 //! - Batch approval when "always" is selected
 
 use std::collections::HashMap;
+use std::path::Path;
 use std::sync::{Arc, LazyLock};
 
 use serde::{Deserialize, Serialize};
 use tokio::sync::oneshot;
 use tokio::sync::Mutex;
 
+use crate::config::PermissionAction;
+use crate::permission::PermissionChecker;
 use crate::tool::{self, PermissionScope};
 
 // =============================================================================
@@ -59,6 +62,9 @@ static GLOBAL_RULES: LazyLock<Arc<Mutex<Vec<PermissionRule>>>> =
 static PENDING_REQUESTS: LazyLock<Arc<Mutex<PendingRequestMap>>> =
     LazyLock::new(|| Arc::new(Mutex::new(HashMap::new())));
 
+/// Held while a CLI prompt waits for its answer on stdin
+static CLI_PROMPT: Mutex<()> = Mutex::const_new(());
+
 // =============================================================================
 // Initialization
 // =============================================================================
@@ -93,6 +99,90 @@ pub async fn store_pending_request(request: PermissionRequestInfo) {
         .insert(request.id.clone(), request);
 }
 
+/// Programs whose every use can destroy data, remembered only as the exact
+/// command
+const DESTRUCTIVE_COMMANDS: &[&str] = &[
+    "rm", "rmdir", "mv", "dd", "shred", "truncate", "chmod", "chown", "kill", "pkill", "killall",
+    "mkfs", "sudo", "doas",
+];
+
+/// Derive the pattern remembered when a request is allowed "always"
+///
+/// Bash commands are keyed by the program and its subcommand (e.g.
+/// `git status *`), so a later `git status -s` is allowed but `git push` is
+/// not. A command whose first argument is a flag, such as `ls -la`, or whose
+/// program is destructive, such as `rm build`, is kept exactly, so approving
+/// it does not approve every use of the program. Other tools are keyed by the
+/// exact pattern (file path, glob, ...).
+pub fn always_pattern(permission: &str, pattern: &str) -> String {
+    if permission != "bash" {
+        return pattern.to_string();
+    }
+
+    let mut words = pattern.split_whitespace();
+    let Some(program) = words.next() else {
+        return pattern.to_string();
+    };
+    if DESTRUCTIVE_COMMANDS.contains(&program) {
+        return pattern.to_string();
+    }
+
+    match words.next() {
+        Some(flag) if flag.starts_with('-') => pattern.to_string(),
+        Some(sub) if is_plain_word(sub) => format!("{} {} *", program, sub),
+        _ => format!("{} *", program),
+    }
+}
+
+/// Whether a command word is a plain subcommand (not a path, operator, etc.)
+fn is_plain_word(word: &str) -> bool {
+    word.chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == ':')
+}
+
+/// Answer a request without prompting, if the permission layers decide it
+///
+/// Layers are checked highest first: the session's rules, remembered
+/// approvals (workspace, then global), then the config. A session rule of
+/// `ask` always prompts, even when a lower layer would allow the request.
+pub async fn decided_response(
+    checker: &PermissionChecker,
+    request: &tool::PermissionRequest,
+) -> Option<tool::PermissionResponse> {
+    match checker.session_action(&request.permission) {
+        Some(PermissionAction::Ask) => return None,
+        Some(action) => return action_response(&action, request),
+        None => {}
+    }
+    if check_auto_approve(request).await {
+        return Some(tool::PermissionResponse {
+            id: request.id.clone(),
+            allow: true,
+            scope: PermissionScope::Session,
+            individually: false,
+        });
+    }
+    action_response(&checker.config_action(&request.permission), request)
+}
+
+/// Response for an `allow`/`deny` action; `None` for `ask`
+fn action_response(
+    action: &PermissionAction,
+    request: &tool::PermissionRequest,
+) -> Option<tool::PermissionResponse> {
+    let allow = match action {
+        PermissionAction::Allow => true,
+        PermissionAction::Deny => false,
+        PermissionAction::Ask => return None,
+    };
+    Some(tool::PermissionResponse {
+        id: request.id.clone(),
+        allow,
+        scope: PermissionScope::Once,
+        individually: false,
+    })
+}
+
 /// Check if a request should be auto-approved based on approved rules
 pub async fn check_auto_approve(request: &tool::PermissionRequest) -> bool {
     check_patterns_against_rules(&request.permission, &request.patterns).await
@@ -128,21 +218,41 @@ pub async fn send_permission_response(id: String, allow: bool, scope: Permission
     auto_approve_pending_requests().await;
 }
 
+/// Send the decision on a batch request to the waiting executor
+///
+/// Batch decisions apply to the listed calls only; no rules are stored.
+pub async fn send_batch_response(id: String, decision: tool::BatchDecision) {
+    deliver_response(tool::PermissionResponse {
+        id,
+        allow: decision == tool::BatchDecision::ApproveAll,
+        scope: PermissionScope::Once,
+        individually: decision == tool::BatchDecision::Individually,
+    })
+    .await;
+}
+
 /// Create a CLI permission handler that prompts the user in the terminal
-pub fn create_cli_permission_handler() -> tool::PermissionHandler {
+///
+/// Prompts share stdin, so requests made at the same time (e.g. by a batch
+/// run with `--concurrency`) are asked one after another.
+pub fn create_cli_permission_handler(checker: PermissionChecker) -> tool::PermissionHandler {
     use std::io::{self, Write};
 
     Arc::new(move |request| {
+        let checker = checker.clone();
         let (response_tx, response_rx) = oneshot::channel();
         let request_clone = request.clone();
 
         tokio::spawn(async move {
-            if check_auto_approve(&request_clone).await {
-                let _ = response_tx.send(tool::PermissionResponse {
-                    id: request_clone.id.clone(),
-                    allow: true,
-                    scope: PermissionScope::Session,
-                });
+            if let Some(response) = decided_response(&checker, &request_clone).await {
+                let _ = response_tx.send(response);
+                return;
+            }
+
+            let _prompt = CLI_PROMPT.lock().await;
+            // An answer given while this request waited may cover it
+            if let Some(response) = decided_response(&checker, &request_clone).await {
+                let _ = response_tx.send(response);
                 return;
             }
 
@@ -155,25 +265,34 @@ pub fn create_cli_permission_handler() -> tool::PermissionHandler {
             })
             .await;
 
-            let request_for_blocking = request_clone.clone();
-            let (user_tx, user_rx) = oneshot::channel();
+            if !request_clone.calls.is_empty() {
+                let id = request_clone.id.clone();
+                let decision = tokio::task::spawn_blocking(move || {
+                    print_batch_prompt(&request_clone);
+                    let _ = io::stderr().flush();
+
+                    let mut input = String::new();
+                    let _ = io::stdin().read_line(&mut input);
+                    parse_batch_choice(input.trim())
+                })
+                .await
+                .unwrap_or(tool::BatchDecision::DenyAll);
+                send_batch_response(id, decision).await;
+                return;
+            }
 
-            tokio::task::spawn_blocking(move || {
-                print_permission_prompt(&request_for_blocking);
+            let id = request_clone.id.clone();
+            let (allow, scope) = tokio::task::spawn_blocking(move || {
+                print_permission_prompt(&request_clone);
                 let _ = io::stderr().flush();
 
                 let mut input = String::new();
                 let _ = io::stdin().read_line(&mut input);
-
-                let (allow, scope) = parse_permission_choice(input.trim());
-                let _ = user_tx.send((request_for_blocking.id, allow, scope));
-            });
-
-            tokio::spawn(async move {
-                if let Ok((id, allow, scope)) = user_rx.await {
-                    send_permission_response(id, allow, scope).await;
-                }
-            });
+                parse_permission_choice(input.trim())
+            })
+            .await
+            .unwrap_or((false, PermissionScope::Once));
+            send_permission_response(id, allow, scope).await;
         });
 
         response_rx
@@ -182,20 +301,18 @@ pub fn create_cli_permission_handler() -> tool::PermissionHandler {
 
 /// Create a TUI permission handler that sends requests via event channel
 pub fn create_tui_permission_handler(
+    checker: PermissionChecker,
     event_tx: tokio::sync::mpsc::Sender<crate::tui::AppEvent>,
 ) -> tool::PermissionHandler {
     Arc::new(move |request| {
         let event_tx = event_tx.clone();
+        let checker = checker.clone();
         let (response_tx, response_rx) = oneshot::channel();
         let request_clone = request.clone();
 
         tokio::spawn(async move {
-            if check_auto_approve(&request_clone).await {
-                let _ = response_tx.send(tool::PermissionResponse {
-                    id: request_clone.id.clone(),
-                    allow: true,
-                    scope: PermissionScope::Session,
-                });
+            if let Some(response) = decided_response(&checker, &request_clone).await {
+                let _ = response_tx.send(response);
                 return;
             }
 
@@ -213,7 +330,9 @@ pub fn create_tui_permission_handler(
                     id: request_clone.id,
                     permission: request_clone.permission,
                     patterns: request_clone.patterns,
+                    always: request_clone.always,
                     metadata: request_clone.metadata,
+                    calls: request_clone.calls,
                 },
             ));
         });
@@ -238,20 +357,26 @@ async fn check_patterns_against_rules(permission: &str, patterns: &[String]) ->
         .collect();
 
     patterns.iter().all(|pattern| {
-        all_rules
-            .iter()
-            .any(|rule| rule.permission == permission && wildcard_match(&rule.pattern, pattern))
+        all_rules.iter().any(|rule| {
+            rule.permission == permission && rule_matches(permission, &rule.pattern, pattern)
+        })
     })
 }
 
 async fn send_response(id: &str, allow: bool, scope: PermissionScope) {
+    let response = tool::PermissionResponse {
+        id: id.to_string(),
+        allow,
+        scope,
+        individually: false,
+    };
+    deliver_response(response).await;
+}
+
+async fn deliver_response(response: tool::PermissionResponse) {
+    let id = response.id.clone();
     let mut responses = PERMISSION_RESPONSES.lock().await;
-    if let Some(tx) = responses.remove(id) {
-        let response = tool::PermissionResponse {
-            id: id.to_string(),
-            allow,
-            scope,
-        };
+    if let Some(tx) = responses.remove(&id) {
         if tx.send(response).is_err() {
             eprintln!(
                 "Warning: Permission response receiver dropped for request {}",
@@ -261,22 +386,22 @@ async fn send_response(id: &str, allow: bool, scope: PermissionScope) {
     }
     drop(responses);
 
-    PENDING_REQUESTS.lock().await.remove(id);
+    PENDING_REQUESTS.lock().await.remove(&id);
 }
 
 async fn store_rules(rules: Vec<PermissionRule>, scope: PermissionScope) {
     match scope {
         PermissionScope::Session => {
-            SESSION_RULES.lock().await.extend(rules);
+            add_rules(&mut *SESSION_RULES.lock().await, rules);
         }
         PermissionScope::Workspace => {
-            WORKSPACE_RULES.lock().await.extend(rules);
+            add_rules(&mut *WORKSPACE_RULES.lock().await, rules);
             if let Err(e) = save_workspace_rules().await {
                 eprintln!("Warning: Failed to save workspace permission rules: {}", e);
             }
         }
         PermissionScope::Global => {
-            GLOBAL_RULES.lock().await.extend(rules);
+            add_rules(&mut *GLOBAL_RULES.lock().await, rules);
             if let Err(e) = save_global_rules().await {
                 eprintln!("Warning: Failed to save global permission rules: {}", e);
             }
@@ -285,6 +410,18 @@ async fn store_rules(rules: Vec<PermissionRule>, scope: PermissionScope) {
     }
 }
 
+/// Add rules, skipping ones that are already present
+fn add_rules(existing: &mut Vec<PermissionRule>, rules: Vec<PermissionRule>) {
+    for rule in rules {
+        let duplicate = existing
+            .iter()
+            .any(|r| r.permission == rule.permission && r.pattern == rule.pattern);
+        if !duplicate {
+            existing.push(rule);
+        }
+    }
+}
+
 async fn auto_approve_pending_requests() {
     let pending_ids: Vec<String> = PENDING_REQUESTS.lock().await.keys().cloned().collect();
 
@@ -318,6 +455,7 @@ async fn auto_approve_pending_requests() {
                 id: id.clone(),
                 allow: true,
                 scope: PermissionScope::Once,
+                individually: false,
             };
             if tx.send(response).is_err() {
                 eprintln!(
@@ -337,27 +475,43 @@ async fn auto_approve_pending_requests() {
 async fn save_workspace_rules() -> Result<(), Box<dyn std::error::Error>> {
     let rules = WORKSPACE_RULES.lock().await.clone();
     let cwd = std::env::current_dir()?;
-    let dir = cwd.join(".opencode");
+    write_workspace_rules(&cwd, &rules).await
+}
+
+async fn load_workspace_rules() -> Result<(), Box<dyn std::error::Error>> {
+    let cwd = std::env::current_dir()?;
+    if let Some(rules) = read_workspace_rules(&cwd).await? {
+        *WORKSPACE_RULES.lock().await = rules;
+    }
+    Ok(())
+}
+
+/// Write workspace rules to `<root>/.opencode/permissions.json`
+async fn write_workspace_rules(
+    root: &Path,
+    rules: &[PermissionRule],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let dir = root.join(".opencode");
 
     tokio::fs::create_dir_all(&dir).await?;
-    let json = serde_json::to_string_pretty(&rules)?;
+    let json = serde_json::to_string_pretty(rules)?;
     tokio::fs::write(dir.join("permissions.json"), json).await?;
 
     Ok(())
 }
 
-async fn load_workspace_rules() -> Result<(), Box<dyn std::error::Error>> {
-    let path = std::env::current_dir()?.join(".opencode/permissions.json");
+/// Read workspace rules from `<root>/.opencode/permissions.json`, if present
+async fn read_workspace_rules(
+    root: &Path,
+) -> Result<Option<Vec<PermissionRule>>, Box<dyn std::error::Error>> {
+    let path = root.join(".opencode/permissions.json");
 
     if !path.exists() {
-        return Ok(());
+        return Ok(None);
     }
 
     let json = tokio::fs::read_to_string(&path).await?;
-    let rules: Vec<PermissionRule> = serde_json::from_str(&json)?;
-    *WORKSPACE_RULES.lock().await = rules;
-
-    Ok(())
+    Ok(Some(serde_json::from_str(&json)?))
 }
 
 async fn save_global_rules() -> Result<(), Box<dyn std::error::Error>> {
@@ -386,6 +540,7 @@ fn print_permission_prompt(request: &tool::PermissionRequest) {
     eprintln!("\n[Permission Required]");
     eprintln!("Tool: {}", request.permission);
     eprintln!("Patterns: {:?}", request.patterns);
+    eprintln!("Always allows: {}", request.always.join(", "));
     eprintln!(
         "Action: Execute with arguments: {}",
         serde_json::to_string(&request.metadata).unwrap_or_default()
@@ -400,6 +555,28 @@ fn print_permission_prompt(request: &tool::PermissionRequest) {
     eprint!("\nChoice [Y/s/w/g/n]: ");
 }
 
+fn print_batch_prompt(request: &tool::PermissionRequest) {
+    eprintln!("\n[Permission Required]");
+    eprintln!("The model wants to run {} tool calls:", request.calls.len());
+    for call in &request.calls {
+        eprintln!("  {:<10} {}", call.tool, call.preview);
+    }
+    eprintln!();
+    eprintln!("Options:");
+    eprintln!("  a/all      - Approve all of these calls");
+    eprintln!("  e/each     - Decide for each call separately");
+    eprintln!("  n/no       - Deny all of these calls");
+    eprint!("\nChoice [A/e/n]: ");
+}
+
+fn parse_batch_choice(answer: &str) -> tool::BatchDecision {
+    match answer.to_lowercase().as_str() {
+        "" | "a" | "all" | "y" | "yes" => tool::BatchDecision::ApproveAll,
+        "e" | "each" => tool::BatchDecision::Individually,
+        _ => tool::BatchDecision::DenyAll,
+    }
+}
+
 fn parse_permission_choice(answer: &str) -> (bool, PermissionScope) {
     let answer = answer.to_lowercase();
     match answer.as_str() {
@@ -415,6 +592,22 @@ fn parse_permission_choice(answer: &str) -> (bool, PermissionScope) {
 // Wildcard Matching
 // =============================================================================
 
+/// Shell syntax that runs further commands after or inside the first one, or
+/// redirects input and output (`>`, `>>`, `<(`, `>(`, ...)
+const SHELL_CHAINING: &[&str] = &[";", "&", "|", "$(", "`", "\n", ">", "<"];
+
+/// Whether a remembered pattern covers a request's pattern
+///
+/// A bash wildcard only covers a single command: `git status *` must not
+/// allow `git status; rm -rf ~` or `git status > src/main.rs`, so commands
+/// that chain, substitute or redirect are only covered by an exact match.
+fn rule_matches(permission: &str, rule_pattern: &str, pattern: &str) -> bool {
+    if permission == "bash" && SHELL_CHAINING.iter().any(|op| pattern.contains(op)) {
+        return rule_pattern == pattern;
+    }
+    wildcard_match(rule_pattern, pattern)
+}
+
 /// Wildcard matching supporting multiple asterisks
 fn wildcard_match(pattern: &str, text: &str) -> bool {
     if pattern == text || pattern == "*" {
@@ -425,6 +618,11 @@ fn wildcard_match(pattern: &str, text: &str) -> bool {
         return false;
     }
 
+    // "git status *" also matches the bare command "git status"
+    if pattern.strip_suffix(" *") == Some(text) {
+        return true;
+    }
+
     // Security: Reject overly broad patterns for absolute paths
     if pattern == "/*" && !text.starts_with("./") && !text.starts_with("../") {
         return false;
@@ -466,3 +664,134 @@ fn wildcard_match(pattern: &str, text: &str) -> bool {
 
     true
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(permission: &str, pattern: &str, scope: PermissionScope) -> PermissionRule {
+        PermissionRule {
+            permission: permission.to_string(),
+            pattern: pattern.to_string(),
+            scope,
+        }
+    }
+
+    mod always_pattern {
+        use super::*;
+
+        #[test]
+        fn test_bash_keeps_subcommand() {
+            assert_eq!(always_pattern("bash", "git status -s"), "git status *");
+            assert_eq!(always_pattern("bash", "cargo test --lib"), "cargo test *");
+        }
+
+        #[test]
+        fn test_bash_without_subcommand() {
+            assert_eq!(always_pattern("bash", "cat src/main.rs"), "cat *");
+            assert_eq!(always_pattern("bash", "pwd"), "pwd *");
+        }
+
+        #[test]
+        fn test_bash_keeps_flagged_and_destructive_commands_exact() {
+            assert_eq!(always_pattern("bash", "ls -la"), "ls -la");
+            assert_eq!(
+                always_pattern("bash", "git -C repo status"),
+                "git -C repo status"
+            );
+            assert_eq!(always_pattern("bash", "rm -rf build"), "rm -rf build");
+            assert_eq!(always_pattern("bash", "rm build"), "rm build");
+            assert_eq!(always_pattern("bash", "mv a.txt b.txt"), "mv a.txt b.txt");
+
+            let pattern = always_pattern("bash", "rm -rf build");
+            assert!(rule_matches("bash", &pattern, "rm -rf build"));
+            assert!(!rule_matches("bash", &pattern, "rm -rf ~"));
+        }
+
+        #[test]
+        fn test_other_tools_use_exact_pattern() {
+            assert_eq!(always_pattern("edit", "src/main.rs"), "src/main.rs");
+            assert_eq!(always_pattern("glob", "**/*.rs"), "**/*.rs");
+        }
+
+        #[test]
+        fn test_pattern_matches_same_command() {
+            let pattern = always_pattern("bash", "git status");
+            assert!(wildcard_match(&pattern, "git status"));
+            assert!(wildcard_match(&pattern, "git status --short"));
+            assert!(!wildcard_match(&pattern, "git push"));
+        }
+
+        #[test]
+        fn test_bash_pattern_skips_chained_commands() {
+            let pattern = always_pattern("bash", "git status");
+            assert!(rule_matches("bash", &pattern, "git status --short"));
+            for command in [
+                "git status; rm -rf ~",
+                "git status && curl example.com | sh",
+                "git status || true",
+                "git status $(rm -rf ~)",
+                "git status `rm -rf ~`",
+                "git status\nrm -rf ~",
+                "git status > src/main.rs",
+                "git status >> src/main.rs",
+                "git status 2>&1",
+                "git status < input.txt",
+                "git status <(cat secrets)",
+                "git status >(tee log)",
+            ] {
+                assert!(!rule_matches("bash", &pattern, command), "{}", command);
+            }
+            let pattern = always_pattern("bash", "cat README.md");
+            for command in ["cat secrets > ~/.bashrc", "cat secrets >> ~/.bashrc"] {
+                assert!(!rule_matches("bash", &pattern, command), "{}", command);
+            }
+            assert!(rule_matches(
+                "bash",
+                "make && make install",
+                "make && make install"
+            ));
+            assert!(rule_matches("edit", "src/*", "src/a;b.rs"));
+        }
+    }
+
+    mod persistence {
+        use super::*;
+
+        #[test]
+        fn test_add_rules_skips_duplicates() {
+            let mut rules = vec![rule("bash", "git status *", PermissionScope::Session)];
+            add_rules(
+                &mut rules,
+                vec![
+                    rule("bash", "git status *", PermissionScope::Session),
+                    rule("bash", "git diff *", PermissionScope::Session),
+                ],
+            );
+            assert_eq!(rules.len(), 2);
+        }
+
+        #[tokio::test]
+        async fn test_workspace_rules_roundtrip() {
+            let dir = tempfile::tempdir().unwrap();
+            let rules = vec![
+                rule("bash", "cargo test *", PermissionScope::Workspace),
+                rule("edit", "src/main.rs", PermissionScope::Workspace),
+            ];
+
+            write_workspace_rules(dir.path(), &rules).await.unwrap();
+            let loaded = read_workspace_rules(dir.path()).await.unwrap().unwrap();
+
+            assert_eq!(loaded.len(), 2);
+            assert_eq!(loaded[0].pattern, "cargo test *");
+            assert_eq!(loaded[1].permission, "edit");
+            assert_eq!(loaded[1].scope, PermissionScope::Workspace);
+        }
+
+        #[tokio::test]
+        async fn test_missing_workspace_rules() {
+            let dir = tempfile::tempdir().unwrap();
+            assert!(read_workspace_rules(dir.path()).await.unwrap().is_none());
+        }
+    }
+}
@@ -5,10 +5,15 @@
 
 mod models;
 mod models_dev;
+pub mod offline;
+pub mod options;
 mod parsers;
 mod registry;
 mod stream_types;
 mod streaming;
+pub mod structured;
+pub mod tokenizer;
+mod tool_schema;
 mod types;
 
 pub use models::*;
@@ -1,7 +1,64 @@
 //! Model-related utilities.
 
-/// Parse a model string in the format "provider/model"
+use std::collections::{BTreeMap, HashMap};
+use std::sync::RwLock;
+
+use super::{Model, ModelCost};
+
+/// Model aliases from the config, by alias
+static ALIASES: RwLock<BTreeMap<String, String>> = RwLock::new(BTreeMap::new());
+
+/// SDK package marking models served over an OpenAI-compatible API
+pub const OPENAI_COMPATIBLE_NPM: &str = "@ai-sdk/openai-compatible";
+
+/// Whether requests for a model go through the OpenAI chat completions API
+///
+/// True for OpenAI itself and for custom OpenAI-compatible providers.
+pub fn is_openai_compatible(provider_id: &str, model: &Model) -> bool {
+    provider_id == "openai" || model.api.npm.as_deref() == Some(OPENAI_COMPATIBLE_NPM)
+}
+
+/// Whether a model ID names an OpenAI o-series reasoning model (`o1`,
+/// `o3-mini`, ...), ignoring any routing prefix such as `openai/`
+pub fn is_o_series(model_id: &str) -> bool {
+    let name = model_id.rsplit('/').next().unwrap_or(model_id);
+    let mut chars = name.chars();
+    matches!(
+        (chars.next(), chars.next()),
+        (Some('o' | 'O'), Some(c)) if c.is_ascii_digit()
+    )
+}
+
+/// Replace the model aliases with those of the `aliases` config
+pub fn set_aliases(aliases: &HashMap<String, String>) {
+    let mut current = ALIASES.write().unwrap_or_else(|e| e.into_inner());
+    *current = aliases
+        .iter()
+        .map(|(alias, model)| (alias.clone(), model.clone()))
+        .collect();
+}
+
+/// Configured aliases and the models they stand for, sorted by alias
+pub fn aliases() -> Vec<(String, String)> {
+    let aliases = ALIASES.read().unwrap_or_else(|e| e.into_inner());
+    aliases
+        .iter()
+        .map(|(alias, model)| (alias.clone(), model.clone()))
+        .collect()
+}
+
+/// Parse a model string in the format "provider/model", or a model alias
 pub fn parse_model_string(model: &str) -> Option<(String, String)> {
+    let aliases = ALIASES.read().unwrap_or_else(|e| e.into_inner());
+    split_model_string(resolve_alias(&aliases, model))
+}
+
+/// The model `model` stands for if it is an alias, else `model` itself
+fn resolve_alias<'a>(aliases: &'a BTreeMap<String, String>, model: &'a str) -> &'a str {
+    aliases.get(model).map(String::as_str).unwrap_or(model)
+}
+
+fn split_model_string(model: &str) -> Option<(String, String)> {
     let parts: Vec<&str> = model.splitn(2, '/').collect();
     if parts.len() == 2 {
         Some((parts[0].to_string(), parts[1].to_string()))
@@ -10,9 +67,91 @@ pub fn parse_model_string(model: &str) -> Option<(String, String)> {
     }
 }
 
+/// Highest sampling temperature a provider accepts; the lowest is 0
+pub fn max_temperature(provider_id: &str) -> f64 {
+    match provider_id {
+        "anthropic" => 1.0,
+        _ => 2.0,
+    }
+}
+
+/// Fallback models to try after `provider_id/model_id`, in order
+///
+/// Entries that are not in provider/model format and the current model itself
+/// are skipped.
+pub fn fallback_chain(
+    fallbacks: &[String],
+    provider_id: &str,
+    model_id: &str,
+) -> Vec<(String, String)> {
+    fallbacks
+        .iter()
+        .filter_map(|entry| parse_model_string(entry.trim()))
+        .filter(|(p, m)| !(p == provider_id && m == model_id))
+        .collect()
+}
+
+/// Model option overriding the field that carries the output token limit
+const MAX_TOKENS_FIELD_OPTION: &str = "max_tokens_field";
+
+/// Request field carrying the output token limit for an OpenAI-compatible model
+///
+/// Reasoning models (o-series, gpt-5) reject `max_tokens` in favor of
+/// `max_completion_tokens`. The `max_tokens_field` model option overrides the
+/// choice, which is otherwise inferred from the model ID or family.
+pub fn openai_max_tokens_field(model: &Model) -> &'static str {
+    match model
+        .options
+        .get(MAX_TOKENS_FIELD_OPTION)
+        .and_then(|v| v.as_str())
+    {
+        Some("max_tokens") => return "max_tokens",
+        Some("max_completion_tokens") => return "max_completion_tokens",
+        Some(other) => tracing::warn!(
+            "Unknown {} '{}' for model {}; inferring it instead",
+            MAX_TOKENS_FIELD_OPTION,
+            other,
+            model.id
+        ),
+        None => {}
+    }
+
+    let is_reasoning_model = |name: &str| {
+        // Strip any organization prefix, e.g. "openai/gpt-5"
+        let base = name.rsplit('/').next().unwrap_or(name).to_lowercase();
+        is_o_series(name) || base.starts_with("gpt-5")
+    };
+    if is_reasoning_model(&model.api.id) || model.family.as_deref().is_some_and(is_reasoning_model)
+    {
+        "max_completion_tokens"
+    } else {
+        "max_tokens"
+    }
+}
+
+/// Resolve the max output tokens for a request
+///
+/// An override is capped at the model's output limit. Models with an unknown
+/// limit (0) use the override as-is.
+pub fn max_output_tokens(model: &Model, max_output: Option<u64>) -> u64 {
+    let limit = model.limit.output;
+    match max_output {
+        Some(max) if limit == 0 => max,
+        Some(max) => max.min(limit),
+        None => limit,
+    }
+}
+
+/// Cost in USD of a request, from the model's per-million-token prices
+pub fn usage_cost(cost: &ModelCost, input_tokens: u64, output_tokens: u64) -> f64 {
+    (input_tokens as f64 * cost.input + output_tokens as f64 * cost.output) / 1_000_000.0
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::provider::{ModelApi, ModelCapabilities, ModelCost, ModelLimit, ModelStatus};
+    use std::collections::HashMap;
 
     #[test]
     fn test_parse_model_string() {
@@ -26,4 +165,124 @@ mod tests {
             Some(("provider".to_string(), "model/with/slashes".to_string()))
         );
     }
+
+    #[test]
+    fn test_resolve_alias() {
+        let aliases = BTreeMap::from([(
+            "sonnet".to_string(),
+            "anthropic/claude-sonnet-4-20250514".to_string(),
+        )]);
+        assert_eq!(
+            split_model_string(resolve_alias(&aliases, "sonnet")),
+            Some((
+                "anthropic".to_string(),
+                "claude-sonnet-4-20250514".to_string()
+            ))
+        );
+        assert_eq!(resolve_alias(&aliases, "openai/gpt-4o"), "openai/gpt-4o");
+        assert_eq!(resolve_alias(&aliases, "opus"), "opus");
+    }
+
+    #[test]
+    fn test_max_temperature() {
+        assert_eq!(max_temperature("anthropic"), 1.0);
+        assert_eq!(max_temperature("openai"), 2.0);
+    }
+
+    #[test]
+    fn test_fallback_chain_skips_current_and_invalid_entries() {
+        let fallbacks = vec![
+            "anthropic/claude-sonnet-4".to_string(),
+            "gpt-4o".to_string(),
+            " openai/gpt-4o ".to_string(),
+        ];
+        assert_eq!(
+            fallback_chain(&fallbacks, "anthropic", "claude-sonnet-4"),
+            vec![("openai".to_string(), "gpt-4o".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_is_o_series() {
+        for id in ["o1", "o3-mini", "O4-mini", "openai/o3"] {
+            assert!(is_o_series(id), "{}", id);
+        }
+        for id in ["gpt-4o", "omni-moderation", "o", "openai/gpt-5"] {
+            assert!(!is_o_series(id), "{}", id);
+        }
+    }
+
+    #[test]
+    fn test_openai_max_tokens_field() {
+        let mut model = model_with_output_limit(0);
+        model.api.id = "gpt-4o".to_string();
+        assert_eq!(openai_max_tokens_field(&model), "max_tokens");
+
+        for id in ["o1", "o3-mini", "o4-mini", "gpt-5", "openai/o3"] {
+            model.api.id = id.to_string();
+            assert_eq!(openai_max_tokens_field(&model), "max_completion_tokens");
+        }
+
+        model.options.insert(
+            "max_tokens_field".to_string(),
+            serde_json::json!("max_tokens"),
+        );
+        assert_eq!(openai_max_tokens_field(&model), "max_tokens");
+    }
+
+    fn model_with_output_limit(output: u64) -> Model {
+        Model {
+            id: "test".to_string(),
+            provider_id: "test".to_string(),
+            name: "Test".to_string(),
+            family: None,
+            api: ModelApi {
+                id: "test".to_string(),
+                url: None,
+                npm: None,
+            },
+            capabilities: ModelCapabilities::default(),
+            cost: ModelCost::default(),
+            limit: ModelLimit {
+                context: 200_000,
+                input: None,
+                output,
+            },
+            status: ModelStatus::Active,
+            options: HashMap::new(),
+            headers: HashMap::new(),
+            release_date: None,
+            variants: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_max_output_tokens_defaults_to_model_limit() {
+        let model = model_with_output_limit(100_000);
+        assert_eq!(max_output_tokens(&model, None), 100_000);
+    }
+
+    #[test]
+    fn test_max_output_tokens_override_is_capped() {
+        let model = model_with_output_limit(8192);
+        assert_eq!(max_output_tokens(&model, Some(4096)), 4096);
+        assert_eq!(max_output_tokens(&model, Some(50_000)), 8192);
+    }
+
+    #[test]
+    fn test_max_output_tokens_unknown_limit() {
+        let model = model_with_output_limit(0);
+        assert_eq!(max_output_tokens(&model, Some(4096)), 4096);
+    }
+
+    #[test]
+    fn test_usage_cost() {
+        let cost = ModelCost {
+            input: 3.0,
+            output: 15.0,
+            ..Default::default()
+        };
+        assert_eq!(usage_cost(&cost, 1_000_000, 0), 3.0);
+        assert_eq!(usage_cost(&cost, 200_000, 100_000), 2.1);
+    }
 }
@@ -15,6 +15,9 @@ const MODELS_DEV_API: &str = "https://models.dev/api.json";
 const CACHE_FILENAME: &str = "models.json";
 const CACHE_MAX_AGE: Duration = Duration::from_secs(60 * 60); // 1 hour
 
+/// How long `get` waits for models.dev before falling back to the cache
+const GET_FETCH_TIMEOUT: Duration = Duration::from_secs(3);
+
 /// Models.dev model definition
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ModelsDevModel {
@@ -197,12 +200,13 @@ fn save_to_cache(providers: &HashMap<String, ModelsDevProvider>) -> Result<()> {
     Ok(())
 }
 
-/// Check if models fetch is disabled via environment variable
+/// Check if models fetch is disabled via environment variable or offline mode
 pub fn is_fetch_disabled() -> bool {
-    std::env::var("OPENCODE_DISABLE_MODELS_FETCH")
-        .ok()
-        .and_then(|v| v.parse::<bool>().ok())
-        .unwrap_or(false)
+    super::offline::is_offline()
+        || std::env::var("OPENCODE_DISABLE_MODELS_FETCH")
+            .ok()
+            .and_then(|v| v.parse::<bool>().ok())
+            .unwrap_or(false)
         || std::env::var("OPENCODE_DISABLE_MODELS_FETCH")
             .ok()
             .map(|v| v == "1" || v.to_lowercase() == "true")
@@ -215,7 +219,8 @@ pub fn is_fetch_disabled() -> bool {
 /// 1. If OPENCODE_DISABLE_MODELS_FETCH is set, only use cache
 /// 2. If cache is fresh (< 1 hour), use cache
 /// 3. Otherwise, try to fetch from API and update cache
-/// 4. If API fetch fails, fall back to cache
+/// 4. If API fetch fails or takes longer than `GET_FETCH_TIMEOUT`, fall back
+///    to cache; a slow fetch still updates the cache when it completes
 pub async fn get() -> Result<HashMap<String, ModelsDevProvider>> {
     // If fetch is disabled, only use cache
     if is_fetch_disabled() {
@@ -229,18 +234,31 @@ pub async fn get() -> Result<HashMap<String, ModelsDevProvider>> {
         }
     }
 
-    // Try to fetch from API
-    match fetch_from_api().await {
-        Ok(providers) => {
-            // Save to cache (ignore errors)
-            let _ = save_to_cache(&providers);
-            Ok(providers)
-        }
-        Err(e) => {
+    // Try to fetch from API; the task keeps running past the timeout
+    let fetch = tokio::spawn(async {
+        let providers = fetch_from_api().await?;
+        // Save to cache (ignore errors)
+        let _ = save_to_cache(&providers);
+        Ok::<_, anyhow::Error>(providers)
+    });
+    match tokio::time::timeout(GET_FETCH_TIMEOUT, fetch).await {
+        Ok(Ok(Ok(providers))) => Ok(providers),
+        Ok(Ok(Err(e))) => {
             // Fall back to cache if API fetch fails
             tracing::warn!("Failed to fetch from models.dev API: {}, using cache", e);
             load_from_cache()
         }
+        Ok(Err(e)) => {
+            tracing::warn!("models.dev fetch task failed: {}, using cache", e);
+            load_from_cache()
+        }
+        Err(_) => {
+            tracing::warn!(
+                "models.dev did not respond within {}s, using cache",
+                GET_FETCH_TIMEOUT.as_secs()
+            );
+            load_from_cache()
+        }
     }
 }
 
@@ -258,6 +276,97 @@ pub async fn refresh() {
     }
 }
 
+/// Models added to and removed from a provider by a refresh
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ModelsDiff {
+    pub provider_id: String,
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+impl ModelsDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// Compare two models.dev snapshots
+///
+/// Only providers with changes are returned, sorted by provider ID.
+/// When `provider_id` is set, only that provider is compared.
+pub fn diff_models(
+    old: &HashMap<String, ModelsDevProvider>,
+    new: &HashMap<String, ModelsDevProvider>,
+    provider_id: Option<&str>,
+) -> Vec<ModelsDiff> {
+    let mut ids: Vec<&String> = old.keys().chain(new.keys()).collect();
+    ids.sort();
+    ids.dedup();
+
+    ids.into_iter()
+        .filter(|id| provider_id.is_none_or(|p| p == id.as_str()))
+        .filter_map(|id| {
+            let old_models = old.get(id).map(|p| &p.models);
+            let new_models = new.get(id).map(|p| &p.models);
+            let contains = |models: Option<&HashMap<String, ModelsDevModel>>, model_id: &String| {
+                models.is_some_and(|m| m.contains_key(model_id))
+            };
+
+            let mut added: Vec<String> = new_models
+                .into_iter()
+                .flat_map(|m| m.keys())
+                .filter(|m| !contains(old_models, m))
+                .cloned()
+                .collect();
+            let mut removed: Vec<String> = old_models
+                .into_iter()
+                .flat_map(|m| m.keys())
+                .filter(|m| !contains(new_models, m))
+                .cloned()
+                .collect();
+            added.sort();
+            removed.sort();
+
+            let entry = ModelsDiff {
+                provider_id: id.clone(),
+                added,
+                removed,
+            };
+            (!entry.is_empty()).then_some(entry)
+        })
+        .collect()
+}
+
+/// Re-fetch models from models.dev and update the cache, ignoring its age
+///
+/// When `provider_id` is set, only that provider's entry in the cache is
+/// replaced. Returns the changes compared to the previous cache.
+pub async fn refresh_now(provider_id: Option<&str>) -> Result<Vec<ModelsDiff>> {
+    if is_fetch_disabled() {
+        anyhow::bail!(
+            "Models fetch is disabled (offline mode or OPENCODE_DISABLE_MODELS_FETCH is set)"
+        );
+    }
+
+    let old = load_from_cache().unwrap_or_default();
+    let mut fetched = fetch_from_api().await?;
+
+    let new = match provider_id {
+        Some(id) => {
+            let provider = fetched
+                .remove(id)
+                .ok_or_else(|| anyhow::anyhow!("Provider not found in models.dev: {}", id))?;
+            let mut merged = old.clone();
+            merged.insert(id.to_string(), provider);
+            merged
+        }
+        None => fetched,
+    };
+
+    save_to_cache(&new)?;
+    Ok(diff_models(&old, &new, provider_id))
+}
+
 /// Convert models.dev model to our Model struct
 pub fn to_model(provider: &ModelsDevProvider, model: &ModelsDevModel) -> Model {
     let status = match model.status.as_deref() {
@@ -351,3 +460,80 @@ pub fn to_model(provider: &ModelsDevProvider, model: &ModelsDevModel) -> Model {
         variants: model.variants.clone(),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn provider(id: &str, model_ids: &[&str]) -> ModelsDevProvider {
+        let models = model_ids
+            .iter()
+            .map(|m| {
+                let model: ModelsDevModel = serde_json::from_value(serde_json::json!({
+                    "id": m,
+                    "name": m,
+                    "release_date": "2025-01-01",
+                    "limit": { "context": 1000, "output": 100 }
+                }))
+                .unwrap();
+                (m.to_string(), model)
+            })
+            .collect();
+
+        ModelsDevProvider {
+            id: id.to_string(),
+            name: id.to_string(),
+            api: None,
+            env: Vec::new(),
+            npm: None,
+            doc: None,
+            models,
+        }
+    }
+
+    fn snapshot(providers: &[ModelsDevProvider]) -> HashMap<String, ModelsDevProvider> {
+        providers
+            .iter()
+            .map(|p| (p.id.clone(), p.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn test_diff_added_and_removed() {
+        let old = snapshot(&[provider("anthropic", &["a", "b"])]);
+        let new = snapshot(&[provider("anthropic", &["b", "c"])]);
+
+        let changes = diff_models(&old, &new, None);
+        assert_eq!(
+            changes,
+            vec![ModelsDiff {
+                provider_id: "anthropic".to_string(),
+                added: vec!["c".to_string()],
+                removed: vec!["a".to_string()],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_diff_skips_unchanged_providers() {
+        let old = snapshot(&[provider("anthropic", &["a"]), provider("openai", &["x"])]);
+        let new = snapshot(&[
+            provider("anthropic", &["a"]),
+            provider("openai", &["x", "y"]),
+        ]);
+
+        let changes = diff_models(&old, &new, None);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].provider_id, "openai");
+    }
+
+    #[test]
+    fn test_diff_with_provider_filter() {
+        let old = snapshot(&[]);
+        let new = snapshot(&[provider("anthropic", &["a"]), provider("openai", &["x"])]);
+
+        let changes = diff_models(&old, &new, Some("openai"));
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].added, vec!["x".to_string()]);
+    }
+}
@@ -0,0 +1,50 @@
+//! Offline mode: no network calls to providers or models.dev.
+//!
+//! Turned on with `--offline` or `OPENCODE_OFFLINE=1`. The model catalog is
+//! read from the cache only and prompts are refused, while sessions and
+//! configuration can still be browsed.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use anyhow::Result;
+
+/// Set by `--offline`
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Turn offline mode on for the rest of the process
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+/// Whether offline mode is on, by flag or `OPENCODE_OFFLINE`
+pub fn is_offline() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+        || std::env::var("OPENCODE_OFFLINE").is_ok_and(|v| is_truthy(&v))
+}
+
+/// Fail with a clear message when offline mode is on
+pub fn ensure_online() -> Result<()> {
+    if is_offline() {
+        anyhow::bail!(
+            "Offline mode is on (--offline or OPENCODE_OFFLINE): prompts need a network connection"
+        );
+    }
+    Ok(())
+}
+
+fn is_truthy(value: &str) -> bool {
+    value == "1" || value.eq_ignore_ascii_case("true")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_truthy() {
+        assert!(is_truthy("1"));
+        assert!(is_truthy("TRUE"));
+        assert!(!is_truthy("0"));
+        assert!(!is_truthy(""));
+    }
+}
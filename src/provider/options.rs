@@ -0,0 +1,76 @@
+//! Request options from the provider and model config.
+//!
+//! The `options` of a provider and of its models can carry request
+//! parameters, e.g. `service_tier` to pick Anthropic's or OpenAI's priority or
+//! flex processing. Those an API is known to accept are copied into its
+//! request body. The rest are not sent: options also hold settings for
+//! opencode itself, such as `max_tokens_field`, and an unknown parameter would
+//! fail the request.
+
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+/// Options sent to the Anthropic Messages API
+pub const ANTHROPIC: &[&str] = &["service_tier", "top_k", "top_p"];
+
+/// Options sent to OpenAI-compatible chat completions APIs
+pub const OPENAI: &[&str] = &["service_tier", "reasoning_effort", "top_p", "seed"];
+
+/// Provider options, overridden by the model's
+pub fn merged(
+    provider: &HashMap<String, Value>,
+    model: &HashMap<String, Value>,
+) -> Map<String, Value> {
+    // Later entries replace earlier ones with the same key
+    provider
+        .iter()
+        .chain(model)
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect()
+}
+
+/// Copy the options in `accepted` into a request body
+///
+/// Other options are skipped with a debug log, as is a `null` value, which
+/// lets a model unset an option of its provider.
+pub fn apply(body: &mut Value, options: &Map<String, Value>, accepted: &[&str], provider_id: &str) {
+    for (key, value) in options {
+        if !accepted.contains(&key.as_str()) {
+            tracing::debug!(
+                "Not sending option '{}' to {}: not a known request parameter",
+                key,
+                provider_id
+            );
+        } else if !value.is_null() {
+            body[key] = value.clone();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_apply_merged_options() {
+        let provider = HashMap::from([
+            ("service_tier".to_string(), json!("auto")),
+            ("top_p".to_string(), json!(0.9)),
+            ("baseURL".to_string(), json!("https://example.com")),
+        ]);
+        let model = HashMap::from([
+            ("service_tier".to_string(), json!("standard_only")),
+            ("top_p".to_string(), Value::Null),
+            ("max_tokens_field".to_string(), json!("max_tokens")),
+        ]);
+        let options = merged(&provider, &model);
+
+        let mut body = json!({"model": "claude-sonnet-4", "stream": true});
+        apply(&mut body, &options, ANTHROPIC, "anthropic");
+        assert_eq!(
+            body,
+            json!({"model": "claude-sonnet-4", "stream": true, "service_tier": "standard_only"})
+        );
+    }
+}
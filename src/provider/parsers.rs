@@ -3,7 +3,7 @@
 //! This module contains parser functions for handling streaming responses
 //! from different LLM providers (Anthropic, OpenAI).
 
-use super::stream_types::StreamEvent;
+use super::stream_types::{StreamEvent, CONTENT_FILTERED, MODEL_DECLINED};
 use serde_json::Value;
 use std::collections::HashMap;
 
@@ -29,6 +29,10 @@ fn parse_usage(input_key: &str, output_key: &str, usage: &Value) -> StreamEvent
 #[derive(Debug, Default)]
 pub struct AnthropicParser {
     index_to_id: HashMap<usize, String>,
+    /// Prefilled assistant text, emitted when the message starts
+    prefill: Option<String>,
+    /// Stop reason that came with the usage, reported at `message_stop`
+    stop_reason: Option<String>,
 }
 
 impl AnthropicParser {
@@ -36,17 +40,31 @@ impl AnthropicParser {
         Self::default()
     }
 
+    /// Create a parser that prepends prefilled text to the response
+    pub fn with_prefill(prefill: Option<String>) -> Self {
+        Self {
+            prefill,
+            ..Self::new()
+        }
+    }
+
     pub fn parse(&mut self, event: &str) -> Option<StreamEvent> {
         let (event_type, data) = parse_sse_event(event)?;
 
         match event_type.as_str() {
+            "message_start" => self.prefill.take().map(StreamEvent::TextDelta),
             "content_block_delta" => self.parse_content_delta(&data),
             "content_block_start" => self.parse_block_start(&data),
             "content_block_stop" => self.parse_block_stop(&data),
-            "message_delta" => Self::parse_message_delta(&data),
-            "message_stop" => Some(StreamEvent::Done {
-                finish_reason: "stop".to_string(),
-            }),
+            "message_delta" => self.parse_message_delta(&data),
+            "message_stop" => match self.stop_reason.take() {
+                Some(reason) if reason == "refusal" => {
+                    Some(StreamEvent::Refused(MODEL_DECLINED.to_string()))
+                }
+                reason => Some(StreamEvent::Done {
+                    finish_reason: reason.unwrap_or_else(|| "stop".to_string()),
+                }),
+            },
             "error" => Self::parse_error(&data),
             _ => None,
         }
@@ -96,14 +114,23 @@ impl AnthropicParser {
         })
     }
 
-    fn parse_message_delta(data: &str) -> Option<StreamEvent> {
+    fn parse_message_delta(&mut self, data: &str) -> Option<StreamEvent> {
         let parsed: Value = serde_json::from_str(data).ok()?;
+        let stop_reason = parsed
+            .get("delta")
+            .and_then(|delta| get_str(delta, "stop_reason"));
 
+        // A refusal coming with usage is reported at `message_stop`, so the
+        // usage is counted too
         if let Some(usage) = parsed.get("usage") {
+            self.stop_reason = stop_reason.map(str::to_string);
             return Some(parse_usage("input_tokens", "output_tokens", usage));
         }
 
-        get_str(parsed.get("delta")?, "stop_reason").map(|r| StreamEvent::Done {
+        if stop_reason == Some("refusal") {
+            return Some(StreamEvent::Refused(MODEL_DECLINED.to_string()));
+        }
+        stop_reason.map(|r| StreamEvent::Done {
             finish_reason: r.to_string(),
         })
     }
@@ -137,6 +164,8 @@ fn parse_sse_event(event: &str) -> Option<(String, String)> {
 #[derive(Debug, Default)]
 pub struct OpenAIParser {
     index_to_id: HashMap<usize, String>,
+    /// Refusal text streamed instead of content
+    refusal: String,
 }
 
 impl OpenAIParser {
@@ -166,7 +195,17 @@ impl OpenAIParser {
         let choice = parsed.get("choices")?.as_array()?.first()?;
         let delta = choice.get("delta")?;
 
+        if let Some(refusal) = get_str(delta, "refusal") {
+            self.refusal.push_str(refusal);
+        }
+
         if let Some(reason) = get_str(choice, "finish_reason") {
+            if reason == "content_filter" {
+                return Some(StreamEvent::Refused(CONTENT_FILTERED.to_string()));
+            }
+            if !self.refusal.is_empty() {
+                return Some(StreamEvent::Refused(std::mem::take(&mut self.refusal)));
+            }
             if reason != "null" {
                 return Some(StreamEvent::Done {
                     finish_reason: reason.to_string(),
@@ -301,6 +340,53 @@ data: {"type":"message_delta","delta":{"stop_reason":"end_turn"}}"#;
             ));
         }
 
+        #[test]
+        fn test_stop_reason_with_usage() {
+            let delta = r#"event: message_delta
+data: {"type":"message_delta","delta":{"stop_reason":"tool_use"},"usage":{"output_tokens":50}}"#;
+            let stop = r#"event: message_stop
+data: {}"#;
+
+            let mut parser = AnthropicParser::new();
+            assert!(matches!(
+                parser.parse(delta),
+                Some(StreamEvent::Usage { .. })
+            ));
+            assert!(matches!(
+                parser.parse(stop),
+                Some(StreamEvent::Done { finish_reason }) if finish_reason == "tool_use"
+            ));
+        }
+
+        #[test]
+        fn test_refusal() {
+            let event = r#"event: message_delta
+data: {"type":"message_delta","delta":{"stop_reason":"refusal"},"usage":{"output_tokens":5}}"#;
+            let stop = r#"event: message_stop
+data: {}"#;
+
+            let mut parser = AnthropicParser::new();
+            assert!(matches!(
+                parser.parse(event),
+                Some(StreamEvent::Usage {
+                    output_tokens: 5,
+                    ..
+                })
+            ));
+            assert!(matches!(
+                parser.parse(stop),
+                Some(StreamEvent::Refused(reason)) if reason == MODEL_DECLINED
+            ));
+
+            let event = r#"event: message_delta
+data: {"type":"message_delta","delta":{"stop_reason":"refusal"}}"#;
+            let mut parser = AnthropicParser::new();
+            assert!(matches!(
+                parser.parse(event),
+                Some(StreamEvent::Refused(reason)) if reason == MODEL_DECLINED
+            ));
+        }
+
         #[test]
         fn test_usage() {
             let event = r#"event: message_delta
@@ -337,6 +423,17 @@ data: {}"#;
             let result = parser.parse(event);
             assert!(result.is_none());
         }
+
+        #[test]
+        fn test_prefill_emitted_once_on_message_start() {
+            let event = r#"event: message_start
+data: {"type":"message_start","message":{"id":"msg_1"}}"#;
+
+            let mut parser = AnthropicParser::with_prefill(Some("{".to_string()));
+            let result = parser.parse(event);
+            assert!(matches!(result, Some(StreamEvent::TextDelta(text)) if text == "{"));
+            assert!(parser.parse(event).is_none());
+        }
     }
 
     mod parse_openai_sse {
@@ -372,6 +469,31 @@ data: {}"#;
             ));
         }
 
+        #[test]
+        fn test_content_filter_and_refusal() {
+            let line =
+                r#"data: {"choices":[{"delta":{},"finish_reason":"content_filter","index":0}]}"#;
+            let mut parser = OpenAIParser::new();
+            assert!(matches!(
+                parser.parse(line),
+                Some(StreamEvent::Refused(reason)) if reason == CONTENT_FILTERED
+            ));
+
+            let mut parser = OpenAIParser::new();
+            for refusal in ["I can't ", "help with that."] {
+                let line = format!(
+                    r#"data: {{"choices":[{{"delta":{{"refusal":"{}"}},"index":0}}]}}"#,
+                    refusal
+                );
+                assert!(parser.parse(&line).is_none());
+            }
+            let line = r#"data: {"choices":[{"delta":{},"finish_reason":"stop","index":0}]}"#;
+            assert!(matches!(
+                parser.parse(line),
+                Some(StreamEvent::Refused(reason)) if reason == "I can't help with that."
+            ));
+        }
+
         #[test]
         fn test_tool_call_start() {
             let line = r#"data: {"choices":[{"delta":{"tool_calls":[{"id":"call_abc123","index":0,"function":{"name":"bash"}}]},"index":0}]}"#;
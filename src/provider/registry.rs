@@ -3,9 +3,13 @@
 //! This module contains the `ProviderRegistry` which manages provider initialization,
 //! configuration, and model loading from various sources.
 
+use super::models::OPENAI_COMPATIBLE_NPM;
 use super::models_dev;
-use super::types::{Model, Provider, ProviderSource};
-use crate::config::Config;
+use super::types::{
+    Modalities, Model, ModelApi, ModelCapabilities, ModelCost, ModelLimit, ModelStatus, Provider,
+    ProviderSource,
+};
+use crate::config::{Config, ModelConfig, ProviderConfig};
 use anyhow::Result;
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -65,21 +69,75 @@ impl ProviderRegistry {
 
     /// Initialize the registry with built-in and configured providers
     pub async fn initialize(&self, config: &Config) -> Result<()> {
+        self.reload(config).await?;
+
+        Self::start_background_refresh();
+        Ok(())
+    }
+
+    /// Rebuild the provider list from the models cache and configuration
+    pub async fn reload(&self, config: &Config) -> Result<()> {
+        // Fetch the model catalog and read stored keys concurrently, before
+        // taking the lock
+        let (catalog, auth) = tokio::join!(models_dev::get(), crate::auth::AuthStorage::load());
+        let catalog = catalog
+            .map_err(|e| tracing::error!("Failed to load models from models.dev: {}", e))
+            .ok();
+
         let mut providers = self.providers.write().await;
+        providers.clear();
 
-        self.add_builtin_providers(&mut providers).await;
+        self.add_builtin_providers(&mut providers, catalog.as_ref());
+        self.add_custom_providers(&mut providers, config);
         self.apply_config_overrides(&mut providers, config);
-        self.load_auth_keys(&mut providers).await;
+        if let Ok(auth) = &auth {
+            self.load_auth_keys(&mut providers, auth);
+        }
         self.load_env_keys(&mut providers);
         self.apply_provider_filters(&mut providers, config);
+        super::set_aliases(config.aliases.as_ref().unwrap_or(&HashMap::new()));
 
-        Self::start_background_refresh();
         Ok(())
     }
 
-    async fn add_builtin_providers(&self, providers: &mut HashMap<String, Provider>) {
+    /// Re-read API keys from the auth storage and environment
+    ///
+    /// Only the keys of the registered providers change; their models are
+    /// kept, so unlike `initialize` this makes no models.dev round-trip.
+    pub async fn reload_credentials(&self) {
+        let auth = crate::auth::AuthStorage::load().await.ok();
+        self.reload_credentials_from(auth.as_ref()).await;
+    }
+
+    /// Re-read API keys from the given auth storage and the environment
+    async fn reload_credentials_from(&self, auth: Option<&crate::auth::AuthStorage>) {
+        let mut providers = self.providers.write().await;
+        for provider in providers.values_mut() {
+            provider.key = None;
+            provider.source = ProviderSource::Custom;
+        }
+        if let Some(auth) = auth {
+            self.load_auth_keys(&mut providers, auth);
+        }
+        self.load_env_keys(&mut providers);
+    }
+
+    /// Add the built-in providers, with their models from the catalog
+    ///
+    /// Without a catalog the providers are still added, with no models.
+    fn add_builtin_providers(
+        &self,
+        providers: &mut HashMap<String, Provider>,
+        catalog: Option<&HashMap<String, models_dev::ModelsDevProvider>>,
+    ) {
         for def in BUILTIN_PROVIDERS {
-            let models = load_models(def.models_dev_id, def.fallback_ids).await;
+            let models = match catalog {
+                Some(catalog) => load_models(catalog, def.models_dev_id, def.fallback_ids),
+                None => {
+                    tracing::warn!("{} models unavailable", def.id);
+                    HashMap::new()
+                }
+            };
             providers.insert(
                 def.id.to_string(),
                 Provider {
@@ -89,12 +147,39 @@ impl ProviderRegistry {
                     env: def.env_vars.iter().map(|s| s.to_string()).collect(),
                     key: None,
                     options: HashMap::new(),
+                    insecure_skip_verify: false,
                     models,
                 },
             );
         }
     }
 
+    /// Add OpenAI-compatible providers defined with `"source": "custom"`
+    fn add_custom_providers(&self, providers: &mut HashMap<String, Provider>, config: &Config) {
+        let Some(provider_config) = &config.provider else {
+            return;
+        };
+
+        for (id, cfg) in provider_config {
+            if cfg.source.as_deref() != Some("custom") {
+                continue;
+            }
+            if providers.contains_key(id) {
+                tracing::warn!(
+                    "Custom provider '{}' conflicts with a built-in provider",
+                    id
+                );
+                continue;
+            }
+            match custom_provider(id, cfg) {
+                Some(provider) => {
+                    providers.insert(id.clone(), provider);
+                }
+                None => tracing::warn!("Custom provider '{}' has no base_url; skipping", id),
+            }
+        }
+    }
+
     fn apply_config_overrides(&self, providers: &mut HashMap<String, Provider>, config: &Config) {
         let Some(provider_config) = &config.provider else {
             return;
@@ -109,23 +194,37 @@ impl ProviderRegistry {
                 provider.options.extend(options.clone());
             }
 
+            if let Some(insecure) = cfg.insecure_skip_verify {
+                provider.insecure_skip_verify = insecure;
+            }
+
+            if let Some(base_url) = cfg.base_url.as_deref().filter(|url| !url.is_empty()) {
+                let base_url = base_url.trim_end_matches('/');
+                for model in provider.models.values_mut() {
+                    model.api.url = Some(base_url.to_string());
+                }
+            }
+
             if let Some(models) = &cfg.models {
                 for (model_id, model_cfg) in models {
                     if let Some(model) = provider.models.get_mut(model_id) {
                         if let Some(name) = &model_cfg.name {
                             model.name = name.clone();
                         }
+                        if let Some(options) = &model_cfg.options {
+                            model.options.extend(options.clone());
+                        }
                     }
                 }
             }
         }
     }
 
-    async fn load_auth_keys(&self, providers: &mut HashMap<String, Provider>) {
-        let Ok(auth) = crate::auth::AuthStorage::load().await else {
-            return;
-        };
-
+    fn load_auth_keys(
+        &self,
+        providers: &mut HashMap<String, Provider>,
+        auth: &crate::auth::AuthStorage,
+    ) {
         for (provider_id, api_key) in &auth.api_keys {
             if let Some(p) = providers.get_mut(provider_id) {
                 if p.key.is_none() {
@@ -223,42 +322,284 @@ impl Default for ProviderRegistry {
     }
 }
 
-async fn load_models(primary_id: &str, fallback_ids: &[&str]) -> HashMap<String, Model> {
-    match models_dev::get().await {
-        Ok(providers) => {
-            let provider = providers
-                .get(primary_id)
-                .or_else(|| fallback_ids.iter().find_map(|id| providers.get(*id)));
-
-            match provider {
-                Some(p) => {
-                    tracing::info!(
-                        "Loaded {} {} models from models.dev",
-                        p.models.len(),
-                        p.name
-                    );
-                    p.models
-                        .iter()
-                        .map(|(id, m)| (id.clone(), models_dev::to_model(p, m)))
-                        .collect()
-                }
-                None => {
-                    tracing::warn!("{} provider not found in models.dev", primary_id);
-                    HashMap::new()
-                }
-            }
+/// Models of a built-in provider from the models.dev catalog
+fn load_models(
+    catalog: &HashMap<String, models_dev::ModelsDevProvider>,
+    primary_id: &str,
+    fallback_ids: &[&str],
+) -> HashMap<String, Model> {
+    let provider = catalog
+        .get(primary_id)
+        .or_else(|| fallback_ids.iter().find_map(|id| catalog.get(*id)));
+
+    match provider {
+        Some(p) => {
+            tracing::info!(
+                "Loaded {} {} models from models.dev",
+                p.models.len(),
+                p.name
+            );
+            p.models
+                .iter()
+                .map(|(id, m)| (id.clone(), models_dev::to_model(p, m)))
+                .collect()
         }
-        Err(e) => {
-            tracing::error!("Failed to load models from models.dev: {}", e);
-            tracing::warn!("{} models unavailable", primary_id);
+        None => {
+            tracing::warn!("{} provider not found in models.dev", primary_id);
             HashMap::new()
         }
     }
 }
 
+/// Build a custom OpenAI-compatible provider from its config
+///
+/// The base URL is applied to the models by `apply_config_overrides`, like
+/// for any provider. Returns `None` without a base URL.
+fn custom_provider(id: &str, cfg: &ProviderConfig) -> Option<Provider> {
+    cfg.base_url.as_deref().filter(|url| !url.is_empty())?;
+
+    let env = cfg
+        .api_key_env
+        .iter()
+        .chain(cfg.env.iter().flatten())
+        .cloned()
+        .collect();
+    let models = cfg
+        .models
+        .iter()
+        .flatten()
+        .map(|(model_id, model_cfg)| (model_id.clone(), custom_model(id, model_id, model_cfg)))
+        .collect();
+
+    Some(Provider {
+        id: id.to_string(),
+        name: cfg.name.clone().unwrap_or_else(|| id.to_string()),
+        source: ProviderSource::Custom,
+        env,
+        key: None,
+        options: HashMap::new(),
+        insecure_skip_verify: false,
+        models,
+    })
+}
+
+/// Build a model of a custom provider; unset capabilities assume a chat model
+/// with tool calling
+fn custom_model(provider_id: &str, model_id: &str, cfg: &ModelConfig) -> Model {
+    let limit = cfg.limit.as_ref();
+    let cost = cfg.cost.as_ref();
+    let text_only = Modalities {
+        text: true,
+        ..Default::default()
+    };
+
+    Model {
+        id: model_id.to_string(),
+        provider_id: provider_id.to_string(),
+        name: cfg.name.clone().unwrap_or_else(|| model_id.to_string()),
+        family: None,
+        api: ModelApi {
+            id: cfg.id.clone().unwrap_or_else(|| model_id.to_string()),
+            url: None,
+            npm: Some(OPENAI_COMPATIBLE_NPM.to_string()),
+        },
+        capabilities: ModelCapabilities {
+            temperature: cfg.temperature.unwrap_or(true),
+            reasoning: cfg.reasoning.unwrap_or(false),
+            attachment: cfg.attachment.unwrap_or(false),
+            toolcall: cfg.tool_call.unwrap_or(true),
+            input: text_only.clone(),
+            output: text_only,
+            interleaved: Default::default(),
+        },
+        cost: ModelCost {
+            input: cost.and_then(|c| c.input).unwrap_or(0.0),
+            output: cost.and_then(|c| c.output).unwrap_or(0.0),
+            cache_read: cost.and_then(|c| c.cache_read).unwrap_or(0.0),
+            cache_write: cost.and_then(|c| c.cache_write).unwrap_or(0.0),
+        },
+        limit: ModelLimit {
+            context: limit.and_then(|l| l.context).unwrap_or(0),
+            input: limit.and_then(|l| l.input),
+            output: limit.and_then(|l| l.output).unwrap_or(0),
+        },
+        status: ModelStatus::Active,
+        options: cfg.options.clone().unwrap_or_default(),
+        headers: cfg.headers.clone().unwrap_or_default(),
+        release_date: None,
+        variants: HashMap::new(),
+    }
+}
+
+/// Config snippet that reproduces a model: its provider's base URL and
+/// options, and the model's definition
+///
+/// `custom` marks the provider as a custom OpenAI-compatible one. API keys are
+/// left out; a custom provider lists the environment variable holding its key.
+pub fn model_config(provider: &Provider, model: &Model, custom: bool) -> serde_json::Value {
+    let capabilities = &model.capabilities;
+    let mut model_cfg = serde_json::json!({
+        "id": model.api.id,
+        "name": model.name,
+        "temperature": capabilities.temperature,
+        "reasoning": capabilities.reasoning,
+        "attachment": capabilities.attachment,
+        "tool_call": capabilities.toolcall,
+        "cost": model.cost,
+        "limit": model.limit,
+    });
+    if !model.options.is_empty() {
+        model_cfg["options"] = serde_json::json!(model.options);
+    }
+    if !model.headers.is_empty() {
+        model_cfg["headers"] = serde_json::json!(model.headers);
+    }
+
+    let mut provider_cfg = serde_json::json!({
+        "name": provider.name,
+        "models": { model.id.clone(): model_cfg },
+    });
+    if custom {
+        provider_cfg["source"] = "custom".into();
+        if let Some(env) = provider.env.first() {
+            provider_cfg["api_key_env"] = env.as_str().into();
+        }
+    }
+    if let Some(url) = &model.api.url {
+        provider_cfg["base_url"] = url.as_str().into();
+    }
+    if !provider.options.is_empty() {
+        provider_cfg["options"] = serde_json::json!(provider.options);
+    }
+    if provider.insecure_skip_verify {
+        provider_cfg["insecure_skip_verify"] = true.into();
+    }
+
+    serde_json::json!({ "provider": { provider.id.clone(): provider_cfg } })
+}
+
 static GLOBAL_REGISTRY: std::sync::LazyLock<Arc<ProviderRegistry>> =
     std::sync::LazyLock::new(|| Arc::new(ProviderRegistry::new()));
 
 pub fn registry() -> Arc<ProviderRegistry> {
     GLOBAL_REGISTRY.clone()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn custom_config() -> ProviderConfig {
+        serde_json::from_value(serde_json::json!({
+            "source": "custom",
+            "name": "Local LLM",
+            "base_url": "http://localhost:8000/v1/",
+            "api_key_env": "LOCAL_LLM_KEY",
+            "models": {
+                "qwen": {"id": "Qwen/Qwen2.5-Coder-32B", "limit": {"context": 32768, "output": 8192}}
+            }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_custom_provider_is_openai_compatible() {
+        let provider = custom_provider("local", &custom_config()).unwrap();
+        assert_eq!(provider.name, "Local LLM");
+        assert_eq!(provider.env, vec!["LOCAL_LLM_KEY"]);
+
+        let model = &provider.models["qwen"];
+        assert_eq!(model.api.id, "Qwen/Qwen2.5-Coder-32B");
+        assert_eq!(model.limit.output, 8192);
+        assert!(model.capabilities.toolcall);
+        assert!(crate::provider::is_openai_compatible("local", model));
+    }
+
+    #[test]
+    fn test_custom_provider_gets_base_url_from_overrides() {
+        let config = Config {
+            provider: Some(HashMap::from([("local".to_string(), custom_config())])),
+            ..Default::default()
+        };
+        let registry = ProviderRegistry::new();
+        let mut providers = HashMap::new();
+        registry.add_custom_providers(&mut providers, &config);
+        registry.apply_config_overrides(&mut providers, &config);
+
+        let model = &providers["local"].models["qwen"];
+        assert_eq!(model.api.url.as_deref(), Some("http://localhost:8000/v1"));
+    }
+
+    #[tokio::test]
+    async fn test_reload_credentials_keeps_models() {
+        let registry = ProviderRegistry::new();
+        registry.providers.write().await.insert(
+            "local".to_string(),
+            custom_provider("local", &custom_config()).unwrap(),
+        );
+
+        // Credentials come from the auth storage, so no environment variable
+        // is set while other tests read them
+        let auth = crate::auth::AuthStorage {
+            api_keys: HashMap::from([("local".to_string(), "sk-test".to_string())]),
+            ..Default::default()
+        };
+        registry.reload_credentials_from(Some(&auth)).await;
+        let provider = registry.get("local").await.unwrap();
+        assert_eq!(provider.key.as_deref(), Some("sk-test"));
+        assert!(matches!(provider.source, ProviderSource::Config));
+        assert!(provider.models.contains_key("qwen"));
+
+        // A key removed from the storage is dropped
+        registry
+            .reload_credentials_from(Some(&crate::auth::AuthStorage::default()))
+            .await;
+        let provider = registry.get("local").await.unwrap();
+        assert_eq!(provider.key, None);
+        assert!(provider.models.contains_key("qwen"));
+    }
+
+    #[test]
+    fn test_builtin_providers_without_catalog() {
+        let registry = ProviderRegistry::new();
+        let mut providers = HashMap::new();
+        registry.add_builtin_providers(&mut providers, None);
+
+        assert_eq!(providers.len(), BUILTIN_PROVIDERS.len());
+        assert!(providers["anthropic"].models.is_empty());
+    }
+
+    #[test]
+    fn test_model_config_round_trips() {
+        let config = Config {
+            provider: Some(HashMap::from([("local".to_string(), custom_config())])),
+            ..Default::default()
+        };
+        let registry = ProviderRegistry::new();
+        let mut providers = HashMap::new();
+        registry.add_custom_providers(&mut providers, &config);
+        registry.apply_config_overrides(&mut providers, &config);
+        let provider = &providers["local"];
+
+        let snippet = model_config(provider, &provider.models["qwen"], true);
+        assert_eq!(snippet["provider"]["local"]["api_key_env"], "LOCAL_LLM_KEY");
+
+        let exported: ProviderConfig =
+            serde_json::from_value(snippet["provider"]["local"].clone()).unwrap();
+        assert_eq!(
+            exported.base_url.as_deref(),
+            Some("http://localhost:8000/v1")
+        );
+        let model = &custom_provider("local", &exported).unwrap().models["qwen"];
+        assert_eq!(model.api.id, "Qwen/Qwen2.5-Coder-32B");
+        assert_eq!(model.limit.context, 32768);
+        assert_eq!(model.limit.output, 8192);
+    }
+
+    #[test]
+    fn test_custom_provider_requires_base_url() {
+        let mut config = custom_config();
+        config.base_url = None;
+        assert!(custom_provider("local", &config).is_none());
+    }
+}
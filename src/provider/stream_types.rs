@@ -4,6 +4,9 @@
 //! including message formats and tool definitions.
 
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use super::tool_schema;
 
 /// Stream event from LLM
 #[derive(Debug, Clone)]
@@ -27,6 +30,69 @@ pub enum StreamEvent {
     Done { finish_reason: String },
     /// Error occurred
     Error(String),
+    /// The provider refused the request or filtered the response, with the
+    /// reason
+    Refused(String),
+    /// The model is overloaded or rate limited (HTTP 429/529)
+    Overloaded(String),
+    /// The provider rejected the credentials (HTTP 401/403)
+    AuthError {
+        provider_id: String,
+        message: String,
+    },
+}
+
+/// What a stream's finish reason means for the agentic loop
+///
+/// Providers name the same outcomes differently: Anthropic ends a turn with
+/// `end_turn` or `tool_use`, OpenAI with `stop` or `tool_calls`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FinishReason {
+    /// The model finished its answer
+    Stop,
+    /// The model waits for the results of its tool calls
+    ToolCalls,
+    /// The output token limit was reached
+    Length,
+    /// The provider's content filter cut the response off
+    ContentFilter,
+    /// The model declined to answer
+    Refusal,
+    /// Missing or not one of the above
+    Other,
+}
+
+impl FinishReason {
+    pub fn parse(reason: &str) -> Self {
+        match reason {
+            "stop" | "end_turn" | "stop_sequence" => Self::Stop,
+            "tool_calls" | "tool_use" | "function_call" => Self::ToolCalls,
+            "length" | "max_tokens" => Self::Length,
+            "content_filter" => Self::ContentFilter,
+            "refusal" => Self::Refusal,
+            _ => Self::Other,
+        }
+    }
+
+    /// Refusal reason, if the provider refused or filtered the response
+    pub fn refusal(self) -> Option<&'static str> {
+        match self {
+            Self::ContentFilter => Some(CONTENT_FILTERED),
+            Self::Refusal => Some(MODEL_DECLINED),
+            _ => None,
+        }
+    }
+}
+
+/// Refusal reason of a response stopped by a content filter
+pub const CONTENT_FILTERED: &str = "the response was flagged by the content filter";
+
+/// Refusal reason of a model that declined to answer
+pub const MODEL_DECLINED: &str = "the model declined to answer";
+
+/// Message shown for a refused or filtered request
+pub fn refusal_message(reason: &str) -> String {
+    format!("The provider refused or filtered this request: {}", reason)
 }
 
 /// Message format for API requests
@@ -80,21 +146,34 @@ pub struct ToolDefinition {
     pub input_schema: serde_json::Value,
 }
 
+/// Role used for the system prompt of an OpenAI model
+///
+/// Reasoning (o-series) models take instructions as a "developer" message
+/// instead of a "system" message.
+pub fn openai_system_role(model: &str) -> &'static str {
+    if super::is_o_series(model) {
+        "developer"
+    } else {
+        "system"
+    }
+}
+
 /// Convert messages to OpenAI format with optional system prompt
 /// OpenAI expects:
-/// - System message as the first message with role="system"
+/// - System message as the first message with role="system" (or "developer")
 /// - Tool calls as tool_calls array in assistant messages
 /// - Tool results as separate messages with role="tool"
 pub fn convert_messages_to_openai_with_system(
     messages: Vec<ChatMessage>,
     system: Option<String>,
+    system_role: &str,
 ) -> Vec<serde_json::Value> {
     let mut result = Vec::new();
 
     // Add system message first if provided
     if let Some(system_prompt) = system {
         result.push(serde_json::json!({
-            "role": "system",
+            "role": system_role,
             "content": system_prompt,
         }));
     }
@@ -163,6 +242,10 @@ pub fn convert_messages_to_openai(messages: Vec<ChatMessage>) -> Vec<serde_json:
                     }
                 }
 
+                // Tool results must directly follow the assistant's tool calls,
+                // so they go ahead of any text or images of the message
+                result.extend(tool_results);
+
                 // Add main message if it has content or tool calls
                 if !text_parts.is_empty() || !tool_calls.is_empty() {
                     let mut message = serde_json::json!({
@@ -190,9 +273,6 @@ pub fn convert_messages_to_openai(messages: Vec<ChatMessage>) -> Vec<serde_json:
 
                     result.push(message);
                 }
-
-                // Add tool result messages
-                result.extend(tool_results);
             }
         }
     }
@@ -200,10 +280,241 @@ pub fn convert_messages_to_openai(messages: Vec<ChatMessage>) -> Vec<serde_json:
     result
 }
 
+/// Convert messages to Anthropic format
+///
+/// Images become `image` blocks: data URLs are sent as base64 sources and
+/// other URLs as URL sources.
+pub fn convert_messages_to_anthropic(messages: &[ChatMessage]) -> Vec<serde_json::Value> {
+    messages
+        .iter()
+        .map(|msg| {
+            let content = match &msg.content {
+                ChatContent::Text(text) => serde_json::json!(text),
+                ChatContent::Parts(parts) => parts
+                    .iter()
+                    .map(|part| match part {
+                        ContentPart::ImageUrl { image_url } => anthropic_image(&image_url.url),
+                        _ => serde_json::to_value(part).unwrap_or_default(),
+                    })
+                    .collect(),
+            };
+            serde_json::json!({
+                "role": msg.role,
+                "content": content,
+            })
+        })
+        .collect()
+}
+
+/// Anthropic image block for an image URL
+fn anthropic_image(url: &str) -> serde_json::Value {
+    let data = url
+        .strip_prefix("data:")
+        .and_then(|rest| rest.split_once(";base64,"));
+    let source = match data {
+        Some((media_type, data)) => serde_json::json!({
+            "type": "base64",
+            "media_type": media_type,
+            "data": data,
+        }),
+        None => serde_json::json!({"type": "url", "url": url}),
+    };
+    serde_json::json!({"type": "image", "source": source})
+}
+
+/// Convert messages and the system prompt to the `contents` and
+/// `systemInstruction` fields of a Gemini request
+///
+/// Gemini expects:
+/// - Role "model" instead of "assistant"
+/// - The system prompt, and the text of any "system" messages, in
+///   `systemInstruction` rather than in `contents`, which only takes "user"
+///   and "model" turns
+/// - Tool calls as `functionCall` parts with the arguments as an object
+/// - Tool results as `functionResponse` parts naming the function, with the
+///   result wrapped in an object
+// Not called yet: the Gemini stream will build its request with these
+#[allow(dead_code)]
+pub fn convert_messages_to_gemini(
+    messages: &[ChatMessage],
+    system: Option<String>,
+) -> serde_json::Value {
+    // functionResponse names the function, but tool results only carry the call ID
+    let mut call_names = HashMap::new();
+    let mut instructions: Vec<String> = system.into_iter().collect();
+    let mut contents = Vec::new();
+
+    for msg in messages {
+        let role = match msg.role.as_str() {
+            "system" => {
+                instructions.push(gemini_instruction(&msg.content));
+                continue;
+            }
+            "assistant" => "model",
+            _ => "user",
+        };
+        let parts: Vec<serde_json::Value> = match &msg.content {
+            ChatContent::Text(text) => vec![serde_json::json!({"text": text})],
+            ChatContent::Parts(parts) => {
+                let mut responses = Vec::new();
+                let mut other = Vec::new();
+                for part in parts {
+                    match part {
+                        ContentPart::Text { text } => {
+                            other.push(serde_json::json!({"text": text}));
+                        }
+                        ContentPart::ImageUrl { image_url } => {
+                            other.push(gemini_image(&image_url.url));
+                        }
+                        ContentPart::ToolUse { id, name, input } => {
+                            call_names.insert(id.as_str(), name.as_str());
+                            other.push(serde_json::json!({
+                                "functionCall": {"name": name, "args": input}
+                            }));
+                        }
+                        ContentPart::ToolResult {
+                            tool_use_id,
+                            content,
+                            is_error,
+                        } => {
+                            let key = if is_error.unwrap_or(false) {
+                                "error"
+                            } else {
+                                "output"
+                            };
+                            let name = call_names.get(tool_use_id.as_str()).copied();
+                            responses.push(serde_json::json!({
+                                "functionResponse": {
+                                    "name": name.unwrap_or(tool_use_id.as_str()),
+                                    "response": {key: content},
+                                }
+                            }));
+                        }
+                    }
+                }
+                // Function responses must directly follow the model's calls
+                responses.extend(other);
+                responses
+            }
+        };
+
+        if !parts.is_empty() {
+            contents.push(serde_json::json!({"role": role, "parts": parts}));
+        }
+    }
+
+    let mut request = serde_json::json!({"contents": contents});
+    instructions.retain(|text| !text.is_empty());
+    if !instructions.is_empty() {
+        request["systemInstruction"] =
+            serde_json::json!({"parts": [{"text": instructions.join("\n\n")}]});
+    }
+    request
+}
+
+/// Text of a system message; `systemInstruction` only takes text
+fn gemini_instruction(content: &ChatContent) -> String {
+    match content {
+        ChatContent::Text(text) => text.clone(),
+        ChatContent::Parts(parts) => parts
+            .iter()
+            .filter_map(|part| match part {
+                ContentPart::Text { text } => Some(text.as_str()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+    }
+}
+
+/// Gemini `tools` declaring the tool definitions
+///
+/// The schemas are reduced to the subset Gemini accepts (see `tool_schema`).
+/// Empty when there are no tools, since Gemini rejects an empty declaration
+/// list.
+#[allow(dead_code)]
+pub fn convert_tools_to_gemini(tools: &[ToolDefinition]) -> Vec<serde_json::Value> {
+    if tools.is_empty() {
+        return Vec::new();
+    }
+    let profile = tool_schema::profile("google", "");
+    let declarations: Vec<_> = tools
+        .iter()
+        .map(|t| {
+            serde_json::json!({
+                "name": t.name,
+                "description": t.description,
+                "parameters": tool_schema::sanitize(&t.input_schema, &profile),
+            })
+        })
+        .collect();
+    vec![serde_json::json!({"functionDeclarations": declarations})]
+}
+
+/// Gemini part for an image URL
+///
+/// Data URLs are sent inline; other URLs as file references, with the MIME
+/// type guessed from the extension.
+fn gemini_image(url: &str) -> serde_json::Value {
+    let data = url
+        .strip_prefix("data:")
+        .and_then(|rest| rest.split_once(";base64,"));
+    match data {
+        Some((mime_type, data)) => serde_json::json!({
+            "inlineData": {"mimeType": mime_type, "data": data}
+        }),
+        None => {
+            let extension = url.rsplit('.').next().unwrap_or_default();
+            let mime_type = match extension.to_ascii_lowercase().as_str() {
+                "png" => "image/png",
+                "gif" => "image/gif",
+                "webp" => "image/webp",
+                _ => "image/jpeg",
+            };
+            serde_json::json!({
+                "fileData": {"mimeType": mime_type, "fileUri": url}
+            })
+        }
+    }
+}
+
+/// Stands in for an image sent to a model without image input
+const IMAGE_PLACEHOLDER: &str = "[Image omitted: the current model does not accept image input]";
+
+/// Replace images with a text note, for models without image input
+pub fn replace_images(messages: &mut [ChatMessage]) {
+    for msg in messages {
+        let ChatContent::Parts(parts) = &mut msg.content else {
+            continue;
+        };
+        for part in parts.iter_mut() {
+            if matches!(part, ContentPart::ImageUrl { .. }) {
+                *part = ContentPart::Text {
+                    text: IMAGE_PLACEHOLDER.to_string(),
+                };
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_finish_reason() {
+        assert_eq!(FinishReason::parse("tool_calls"), FinishReason::ToolCalls);
+        assert_eq!(FinishReason::parse("tool_use"), FinishReason::ToolCalls);
+        assert_eq!(FinishReason::parse("end_turn"), FinishReason::Stop);
+        assert_eq!(FinishReason::parse("max_tokens"), FinishReason::Length);
+        assert_eq!(FinishReason::parse(""), FinishReason::Other);
+        assert_eq!(
+            FinishReason::parse("content_filter").refusal(),
+            Some(CONTENT_FILTERED)
+        );
+        assert!(FinishReason::parse("stop").refusal().is_none());
+    }
+
     mod convert_messages_to_openai {
         use super::*;
 
@@ -283,4 +594,218 @@ mod tests {
             assert!(result[0]["content"].is_array());
         }
     }
+
+    mod images {
+        use super::*;
+
+        fn tool_result_with_image() -> Vec<ChatMessage> {
+            vec![ChatMessage {
+                role: "user".to_string(),
+                content: ChatContent::Parts(vec![
+                    ContentPart::ToolResult {
+                        tool_use_id: "call_1".to_string(),
+                        content: "screenshot taken".to_string(),
+                        is_error: Some(false),
+                    },
+                    ContentPart::ImageUrl {
+                        image_url: ImageUrl {
+                            url: "data:image/png;base64,abc".to_string(),
+                            detail: None,
+                        },
+                    },
+                ]),
+            }]
+        }
+
+        #[test]
+        fn test_openai_tool_results_precede_images() {
+            let result = convert_messages_to_openai(tool_result_with_image());
+            assert_eq!(result.len(), 2);
+            assert_eq!(result[0]["role"], "tool");
+            assert_eq!(result[1]["role"], "user");
+            assert_eq!(result[1]["content"][0]["type"], "image_url");
+        }
+
+        #[test]
+        fn test_anthropic_image_blocks() {
+            let result = convert_messages_to_anthropic(&tool_result_with_image());
+            let content = &result[0]["content"];
+            assert_eq!(content[0]["type"], "tool_result");
+            assert_eq!(
+                content[1],
+                serde_json::json!({
+                    "type": "image",
+                    "source": {"type": "base64", "media_type": "image/png", "data": "abc"},
+                })
+            );
+            assert_eq!(
+                anthropic_image("https://example.com/chart.png")["source"]["type"],
+                "url"
+            );
+        }
+
+        #[test]
+        fn test_replace_images() {
+            let mut messages = tool_result_with_image();
+            replace_images(&mut messages);
+            let ChatContent::Parts(parts) = &messages[0].content else {
+                panic!("expected parts");
+            };
+            assert!(matches!(parts[0], ContentPart::ToolResult { .. }));
+            assert!(matches!(&parts[1], ContentPart::Text { text } if text == IMAGE_PLACEHOLDER));
+        }
+    }
+
+    mod system_prompt {
+        use super::*;
+
+        #[test]
+        fn test_system_role_by_model() {
+            assert_eq!(openai_system_role("gpt-4o"), "system");
+            assert_eq!(openai_system_role("o3-mini"), "developer");
+            assert_eq!(openai_system_role("o1"), "developer");
+            assert_eq!(openai_system_role("openai/o4-mini"), "developer");
+            assert_eq!(openai_system_role("omni-moderation"), "system");
+        }
+
+        #[test]
+        fn test_system_prompt_prepended() {
+            let messages = vec![ChatMessage {
+                role: "user".to_string(),
+                content: ChatContent::Text("Hi".to_string()),
+            }];
+
+            let result = convert_messages_to_openai_with_system(
+                messages,
+                Some("Be brief".to_string()),
+                "developer",
+            );
+            assert_eq!(result.len(), 2);
+            assert_eq!(result[0]["role"], "developer");
+            assert_eq!(result[0]["content"], "Be brief");
+            assert_eq!(result[1]["role"], "user");
+        }
+
+        #[test]
+        fn test_no_system_prompt() {
+            let result = convert_messages_to_openai_with_system(Vec::new(), None, "system");
+            assert!(result.is_empty());
+        }
+    }
+
+    mod gemini {
+        use super::*;
+
+        #[test]
+        fn test_tool_call_round_trip() {
+            let messages = vec![
+                ChatMessage {
+                    role: "system".to_string(),
+                    content: ChatContent::Text("Answer briefly".to_string()),
+                },
+                ChatMessage {
+                    role: "user".to_string(),
+                    content: ChatContent::Text("List files".to_string()),
+                },
+                ChatMessage {
+                    role: "assistant".to_string(),
+                    content: ChatContent::Parts(vec![
+                        ContentPart::Text {
+                            text: "Listing".to_string(),
+                        },
+                        ContentPart::ToolUse {
+                            id: "call_1".to_string(),
+                            name: "bash".to_string(),
+                            input: serde_json::json!({"command": "ls"}),
+                        },
+                    ]),
+                },
+                ChatMessage {
+                    role: "user".to_string(),
+                    content: ChatContent::Parts(vec![
+                        ContentPart::Text {
+                            text: "Go on".to_string(),
+                        },
+                        ContentPart::ToolResult {
+                            tool_use_id: "call_1".to_string(),
+                            content: "a.txt".to_string(),
+                            is_error: Some(true),
+                        },
+                    ]),
+                },
+            ];
+
+            assert_eq!(
+                convert_messages_to_gemini(&messages, Some("You are a coder".to_string())),
+                serde_json::json!({
+                    "contents": [
+                        {"role": "user", "parts": [{"text": "List files"}]},
+                        {"role": "model", "parts": [
+                            {"text": "Listing"},
+                            {"functionCall": {"name": "bash", "args": {"command": "ls"}}}
+                        ]},
+                        {"role": "user", "parts": [
+                            {"functionResponse": {"name": "bash", "response": {"error": "a.txt"}}},
+                            {"text": "Go on"}
+                        ]},
+                    ],
+                    "systemInstruction": {
+                        "parts": [{"text": "You are a coder\n\nAnswer briefly"}]
+                    },
+                })
+            );
+        }
+
+        #[test]
+        fn test_images() {
+            let messages = vec![ChatMessage {
+                role: "user".to_string(),
+                content: ChatContent::Parts(vec![
+                    ContentPart::ImageUrl {
+                        image_url: ImageUrl {
+                            url: "data:image/png;base64,AAAA".to_string(),
+                            detail: None,
+                        },
+                    },
+                    ContentPart::ImageUrl {
+                        image_url: ImageUrl {
+                            url: "https://example.com/chart.webp".to_string(),
+                            detail: None,
+                        },
+                    },
+                ]),
+            }];
+            let request = convert_messages_to_gemini(&messages, None);
+            assert!(request.get("systemInstruction").is_none());
+            let parts = &request["contents"][0]["parts"];
+            assert_eq!(
+                parts[0],
+                serde_json::json!({"inlineData": {"mimeType": "image/png", "data": "AAAA"}})
+            );
+            assert_eq!(parts[1]["fileData"]["mimeType"], "image/webp");
+        }
+
+        #[test]
+        fn test_tools() {
+            assert!(convert_tools_to_gemini(&[]).is_empty());
+
+            let tools = vec![ToolDefinition {
+                name: "read".to_string(),
+                description: "Read a file".to_string(),
+                input_schema: serde_json::json!({
+                    "type": "object",
+                    "additionalProperties": false,
+                    "properties": {"path": {"type": "string"}}
+                }),
+            }];
+            assert_eq!(
+                convert_tools_to_gemini(&tools),
+                vec![serde_json::json!({"functionDeclarations": [{
+                    "name": "read",
+                    "description": "Read a file",
+                    "parameters": {"type": "object", "properties": {"path": {"type": "string"}}}
+                }]})]
+            );
+        }
+    }
 }
@@ -3,13 +3,18 @@
 //! This module provides the `StreamingClient` for streaming responses from
 //! different LLM providers (Anthropic, OpenAI, GitHub Copilot).
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use futures::StreamExt;
 use reqwest::{Client, Response};
+use std::time::Duration;
 use tokio::sync::mpsc;
 
+use super::options;
 pub use super::parsers::{AnthropicParser, OpenAIParser};
 pub use super::stream_types::*;
+use super::structured;
+use super::tool_schema;
+use super::types::Provider;
 
 /// Request parameters for OpenAI-compatible API calls
 #[derive(Debug, Clone)]
@@ -18,6 +23,33 @@ pub struct OpenAIRequest {
     pub system: Option<String>,
     pub tools: Vec<ToolDefinition>,
     pub max_tokens: u64,
+    /// Request field for `max_tokens`, see [`super::openai_max_tokens_field`]
+    pub max_tokens_field: &'static str,
+    /// JSON schema the final reply must conform to
+    pub json_schema: Option<serde_json::Value>,
+    /// Sampling temperature (None = provider default)
+    pub temperature: Option<f64>,
+    /// Provider and model options; those the API accepts are sent, see
+    /// [`super::options`]
+    pub options: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Request parameters for Anthropic API calls
+#[derive(Debug, Clone)]
+pub struct AnthropicRequest {
+    pub messages: Vec<ChatMessage>,
+    pub system: Option<String>,
+    pub tools: Vec<ToolDefinition>,
+    pub max_tokens: u64,
+    /// Text the assistant reply is seeded with
+    pub prefill: Option<String>,
+    /// JSON schema the final reply must conform to
+    pub json_schema: Option<serde_json::Value>,
+    /// Sampling temperature (None = provider default)
+    pub temperature: Option<f64>,
+    /// Provider and model options; those the API accepts are sent, see
+    /// [`super::options`]
+    pub options: serde_json::Map<String, serde_json::Value>,
 }
 
 type RequestModifier =
@@ -25,6 +57,7 @@ type RequestModifier =
 
 /// Internal parameters for OpenAI-compatible streaming
 struct OpenAIParams {
+    provider_id: String,
     api_key: String,
     base_url: String,
     model: String,
@@ -32,6 +65,10 @@ struct OpenAIParams {
     system: Option<String>,
     tools: Vec<ToolDefinition>,
     max_tokens: u64,
+    max_tokens_field: &'static str,
+    response_format: Option<serde_json::Value>,
+    temperature: Option<f64>,
+    options: serde_json::Map<String, serde_json::Value>,
     request_modifier: RequestModifier,
 }
 
@@ -137,6 +174,31 @@ impl StreamingClient {
         }
     }
 
+    /// Create a client configured for a specific provider
+    ///
+    /// Honors the provider's `insecure_skip_verify` option, which disables TLS
+    /// certificate verification for that provider's requests only. Fails in
+    /// offline mode.
+    pub fn for_provider(provider: &Provider) -> Result<Self> {
+        super::offline::ensure_online()?;
+        if !provider.insecure_skip_verify {
+            return Ok(Self::new());
+        }
+
+        tracing::warn!(
+            "TLS certificate verification is DISABLED for provider '{}' (insecure_skip_verify). \
+            Connections to this provider are vulnerable to interception.",
+            provider.id
+        );
+
+        let client = Client::builder()
+            .tls_danger_accept_invalid_certs(true)
+            .build()
+            .context("Failed to build HTTP client")?;
+
+        Ok(Self { client })
+    }
+
     /// Generic SSE stream processor
     async fn process_sse_stream<P: SseParser>(
         response: Response,
@@ -173,7 +235,12 @@ impl StreamingClient {
     }
 
     /// Handle HTTP response with optional error customization
+    ///
+    /// Rejected credentials are reported as [`StreamEvent::AuthError`] and
+    /// overload or rate limiting as [`StreamEvent::Overloaded`], rather than
+    /// the raw response body.
     async fn handle_response<P: SseParser>(
+        provider_id: &str,
         result: Result<Response, reqwest::Error>,
         tx: mpsc::Sender<StreamEvent>,
         parser: P,
@@ -187,8 +254,17 @@ impl StreamingClient {
                         .text()
                         .await
                         .unwrap_or_else(|_| "Unknown error".to_string());
-                    let error = error_handler.map_or(text.clone(), |h| h(status, &text));
-                    let _ = tx.send(StreamEvent::Error(error)).await;
+                    let event = if is_auth_failure(status, &text) {
+                        StreamEvent::AuthError {
+                            provider_id: provider_id.to_string(),
+                            message: auth_error_message(provider_id, &text),
+                        }
+                    } else if is_overloaded(status) {
+                        StreamEvent::Overloaded(overloaded_message(provider_id, status, &text))
+                    } else {
+                        StreamEvent::Error(error_handler.map_or(text.clone(), |h| h(status, &text)))
+                    };
+                    let _ = tx.send(event).await;
                     return;
                 }
                 Self::process_sse_stream(response, tx, parser).await;
@@ -200,29 +276,64 @@ impl StreamingClient {
     }
 
     /// Stream from Anthropic API
+    ///
+    /// A prefill is sent as a trailing assistant message, and echoed at the
+    /// start of the streamed text so the captured output is complete.
     pub async fn stream_anthropic(
         &self,
         api_key: &str,
         model: &str,
-        messages: Vec<ChatMessage>,
-        system: Option<String>,
-        tools: Vec<ToolDefinition>,
-        max_tokens: u64,
+        request: AnthropicRequest,
     ) -> Result<mpsc::Receiver<StreamEvent>> {
         let (tx, rx) = mpsc::channel(100);
 
-        let request_body = serde_json::json!({
+        // The API rejects a final assistant message ending in whitespace
+        let prefill = request
+            .prefill
+            .map(|p| p.trim_end().to_string())
+            .filter(|p| !p.is_empty());
+        let mut messages = request.messages;
+        if let Some(prefill) = &prefill {
+            messages.push(ChatMessage {
+                role: "assistant".to_string(),
+                content: ChatContent::Text(prefill.clone()),
+            });
+        }
+
+        // No native structured output, so describe the schema in the system prompt
+        let system = match &request.json_schema {
+            Some(schema) => {
+                let instruction = structured::schema_instruction(schema);
+                Some(match request.system {
+                    Some(system) => format!("{}\n\n{}", system, instruction),
+                    None => instruction,
+                })
+            }
+            None => request.system,
+        };
+
+        let schema_profile = tool_schema::profile("anthropic", model);
+        let mut request_body = serde_json::json!({
             "model": model,
-            "max_tokens": max_tokens,
-            "messages": messages,
+            "max_tokens": request.max_tokens,
+            "messages": convert_messages_to_anthropic(&messages),
             "system": system,
-            "tools": tools.iter().map(|t| serde_json::json!({
+            "tools": request.tools.iter().map(|t| serde_json::json!({
                 "name": t.name,
                 "description": t.description,
-                "input_schema": t.input_schema,
+                "input_schema": tool_schema::sanitize(&t.input_schema, &schema_profile),
             })).collect::<Vec<_>>(),
             "stream": true,
         });
+        if let Some(temperature) = request.temperature {
+            request_body["temperature"] = serde_json::json!(temperature);
+        }
+        options::apply(
+            &mut request_body,
+            &request.options,
+            options::ANTHROPIC,
+            "anthropic",
+        );
 
         if let Some(error) = check_request_size(&request_body) {
             spawn_error(tx, error);
@@ -233,7 +344,7 @@ impl StreamingClient {
         let api_key = api_key.to_string();
 
         tokio::spawn(async move {
-            let result = client
+            let builder = client
                 .post("https://api.anthropic.com/v1/messages")
                 .header("x-api-key", &api_key)
                 .header("anthropic-version", "2023-06-01")
@@ -242,25 +353,30 @@ impl StreamingClient {
                     "claude-code-20250219,interleaved-thinking-2025-05-14",
                 )
                 .header("content-type", "application/json")
-                .json(&request_body)
-                .send()
-                .await;
+                .json(&request_body);
+            let result = send_with_retry(builder).await;
 
-            Self::handle_response(result, tx, AnthropicParser::new(), None).await;
+            let parser = AnthropicParser::with_prefill(prefill);
+            Self::handle_response("anthropic", result, tx, parser, None).await;
         });
 
         Ok(rx)
     }
 
     /// Stream from OpenAI-compatible API
+    ///
+    /// Used for OpenAI and for custom OpenAI-compatible providers;
+    /// `provider_id` names the provider in errors.
     pub async fn stream_openai(
         &self,
+        provider_id: &str,
         api_key: &str,
         base_url: &str,
         model: &str,
         request: OpenAIRequest,
     ) -> Result<mpsc::Receiver<StreamEvent>> {
         self.stream_openai_impl(OpenAIParams {
+            provider_id: provider_id.to_string(),
             api_key: api_key.to_string(),
             base_url: base_url.to_string(),
             model: model.to_string(),
@@ -268,12 +384,22 @@ impl StreamingClient {
             system: request.system,
             tools: request.tools,
             max_tokens: request.max_tokens,
+            max_tokens_field: request.max_tokens_field,
+            response_format: request
+                .json_schema
+                .as_ref()
+                .map(structured::openai_response_format),
+            temperature: request.temperature,
+            options: request.options,
             request_modifier: None,
         })
         .await
     }
 
     /// Stream from GitHub Copilot API (OpenAI-compatible)
+    ///
+    /// `token` is the GitHub token; it is exchanged for a short-lived Copilot
+    /// API token first, reusing the previous one until it expires.
     pub async fn stream_copilot(
         &self,
         token: &str,
@@ -283,14 +409,20 @@ impl StreamingClient {
         tools: Vec<ToolDefinition>,
         max_tokens: u64,
     ) -> Result<mpsc::Receiver<StreamEvent>> {
+        let api_token = crate::oauth::copilot_api_token(token).await?;
         self.stream_openai_impl(OpenAIParams {
-            api_key: token.to_string(),
+            provider_id: "copilot".to_string(),
+            api_key: api_token,
             base_url: "https://api.githubcopilot.com".to_string(),
             model: model.to_string(),
             messages,
             system,
             tools,
             max_tokens,
+            max_tokens_field: "max_tokens",
+            response_format: None,
+            temperature: None,
+            options: serde_json::Map::new(),
             request_modifier: Some(Box::new(|b| {
                 b.header("editor-version", "opencode/0.1.0")
                     .header("copilot-integration-id", "vscode-chat")
@@ -306,6 +438,7 @@ impl StreamingClient {
     ) -> Result<mpsc::Receiver<StreamEvent>> {
         let (tx, rx) = mpsc::channel(100);
 
+        let schema_profile = tool_schema::profile(&params.provider_id, &params.model);
         let openai_tools: Vec<_> = params
             .tools
             .iter()
@@ -315,23 +448,42 @@ impl StreamingClient {
                     "function": {
                         "name": t.name,
                         "description": t.description,
-                        "parameters": t.input_schema,
+                        "parameters": tool_schema::sanitize(&t.input_schema, &schema_profile),
                     }
                 })
             })
             .collect();
 
-        let openai_messages =
-            convert_messages_to_openai_with_system(params.messages, params.system);
+        let openai_messages = convert_messages_to_openai_with_system(
+            params.messages,
+            params.system,
+            openai_system_role(&params.model),
+        );
         let is_copilot = params.base_url.contains("githubcopilot.com");
 
         let mut request_body = serde_json::json!({
             "model": params.model,
-            "max_tokens": params.max_tokens,
             "messages": openai_messages,
-            "tools": openai_tools,
             "stream": true,
         });
+        // An empty tool list is rejected rather than ignored
+        if !openai_tools.is_empty() {
+            request_body["tools"] = serde_json::json!(openai_tools);
+        }
+        request_body[params.max_tokens_field] = serde_json::json!(params.max_tokens);
+
+        if let Some(response_format) = params.response_format {
+            request_body["response_format"] = response_format;
+        }
+        if let Some(temperature) = params.temperature {
+            request_body["temperature"] = serde_json::json!(temperature);
+        }
+        options::apply(
+            &mut request_body,
+            &params.options,
+            options::OPENAI,
+            &params.provider_id,
+        );
 
         if !is_copilot {
             request_body["stream_options"] = serde_json::json!({"include_usage": true});
@@ -362,25 +514,163 @@ impl StreamingClient {
                 None
             };
 
-            Self::handle_response(builder.send().await, tx, OpenAIParser::new(), error_handler)
-                .await;
+            let result = send_with_retry(builder).await;
+            let parser = OpenAIParser::new();
+            Self::handle_response(&params.provider_id, result, tx, parser, error_handler).await;
         });
 
         Ok(rx)
     }
 }
 
-fn copilot_error_handler(status: u16, text: &str) -> String {
+/// Whether an error response means the credentials were rejected
+///
+/// Copilot also answers 403 for models that are not enabled, which the
+/// Copilot error handler explains instead.
+fn is_auth_failure(status: u16, body: &str) -> bool {
+    matches!(status, 401 | 403) && !body.contains("The requested model is not supported")
+}
+
+/// Error for a stream that ended in [`StreamEvent::Overloaded`]
+///
+/// Lets the agentic loops tell an overload apart from other failures and try
+/// a fallback model.
+#[derive(Debug)]
+pub struct ModelOverloaded(pub String);
+
+impl std::fmt::Display for ModelOverloaded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for ModelOverloaded {}
+
+/// Error for a stream that ended in [`StreamEvent::Refused`], holding the
+/// reason
+#[derive(Debug)]
+pub struct RequestRefused(pub String);
+
+impl std::fmt::Display for RequestRefused {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&refusal_message(&self.0))
+    }
+}
+
+impl std::error::Error for RequestRefused {}
+
+/// Whether an error response means the model is overloaded or rate limited
+fn is_overloaded(status: u16) -> bool {
+    matches!(status, 429 | 529)
+}
+
+/// Retries of an overloaded or rate limited request before giving up
+const MAX_RETRIES: u32 = 3;
+
+/// Wait before the first retry, doubled for each further one
+const RETRY_BASE_DELAY: Duration = Duration::from_secs(1);
+
+/// Longest wait for a retry; a longer `retry-after` gives up instead
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// Send a request, retrying with backoff while the model is overloaded
+///
+/// The provider's `retry-after` header is honoured when present. Once the
+/// retries are used up, or the provider asks for a longer wait than
+/// [`MAX_RETRY_DELAY`], the overloaded response is returned so the caller
+/// can fall back to another model.
+async fn send_with_retry(builder: reqwest::RequestBuilder) -> Result<Response, reqwest::Error> {
+    let mut attempt = 0;
+    loop {
+        // A JSON body can always be cloned; without a clone, send once
+        let Some(retry) = builder.try_clone() else {
+            return builder.send().await;
+        };
+        let response = retry.send().await?;
+        let status = response.status().as_u16();
+        if !is_overloaded(status) {
+            return Ok(response);
+        }
+        let retry_after = response
+            .headers()
+            .get("retry-after")
+            .and_then(|v| v.to_str().ok());
+        let Some(delay) = retry_delay(attempt, retry_after) else {
+            return Ok(response);
+        };
+        tracing::warn!(
+            "HTTP {}, retrying in {:.1}s ({}/{})",
+            status,
+            delay.as_secs_f64(),
+            attempt + 1,
+            MAX_RETRIES
+        );
+        tokio::time::sleep(delay).await;
+        attempt += 1;
+    }
+}
+
+/// Wait before retry number `attempt` (from 0), or None to give up
+fn retry_delay(attempt: u32, retry_after: Option<&str>) -> Option<Duration> {
+    if attempt >= MAX_RETRIES {
+        return None;
+    }
+    let delay = match retry_after.and_then(|v| v.trim().parse::<f64>().ok()) {
+        Some(secs) if secs.is_finite() && secs >= 0.0 => Duration::from_secs_f64(secs),
+        _ => RETRY_BASE_DELAY * 2u32.pow(attempt),
+    };
+    (delay <= MAX_RETRY_DELAY).then_some(delay)
+}
+
+/// Error message from a provider's JSON error body
+fn error_reason(body: &str) -> Option<String> {
+    serde_json::from_str::<serde_json::Value>(body)
+        .ok()
+        .and_then(|v| {
+            v.pointer("/error/message")
+                .or_else(|| v.get("message"))
+                .and_then(|m| m.as_str())
+                .map(str::to_string)
+        })
+        .filter(|r| !r.is_empty())
+}
+
+/// User-facing message for an overloaded or rate limited model
+fn overloaded_message(provider_id: &str, status: u16, body: &str) -> String {
+    let kind = if status == 429 {
+        "rate limited"
+    } else {
+        "overloaded"
+    };
+    let mut message = format!("{} is {} (HTTP {})", provider_id, kind, status);
+    if let Some(reason) = error_reason(body) {
+        message.push_str(&format!(": {}", reason));
+    }
+    message
+}
+
+/// User-facing message for rejected credentials, with the provider's reason
+fn auth_error_message(provider_id: &str, body: &str) -> String {
+    let reason = error_reason(body);
+
+    let mut message = format!(
+        "Your API key/token for {} appears invalid or expired. \
+        Run /connect to reconnect, or update the key in your config.",
+        provider_id
+    );
+    if let Some(reason) = reason {
+        message.push_str(&format!(" ({})", reason));
+    }
+    message
+}
+
+fn copilot_error_handler(_status: u16, text: &str) -> String {
     if text.contains("The requested model is not supported") {
         format!(
             "{}\n\nMake sure the model is enabled in your copilot settings: \
             https://github.com/settings/copilot/features",
             text
         )
-    } else if status == 403 {
-        "Please reauthenticate with the copilot provider to ensure your credentials \
-        work properly with opencode-rs."
-            .to_string()
     } else {
         text.to_string()
     }
@@ -391,3 +681,67 @@ impl Default for StreamingClient {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_auth_failures_are_detected() {
+        assert!(is_auth_failure(401, "{}"));
+        assert!(is_auth_failure(403, "forbidden"));
+        assert!(!is_auth_failure(
+            403,
+            "The requested model is not supported"
+        ));
+        assert!(!is_auth_failure(429, "{}"));
+    }
+
+    #[test]
+    fn test_auth_error_message_includes_provider_reason() {
+        let body = r#"{"type":"error","error":{"type":"authentication_error","message":"invalid x-api-key"}}"#;
+        let message = auth_error_message("anthropic", body);
+        assert!(message.starts_with("Your API key/token for anthropic appears invalid"));
+        assert!(message.ends_with("(invalid x-api-key)"));
+
+        let message = auth_error_message("openai", "<html>Unauthorized</html>");
+        assert!(message.ends_with("update the key in your config."));
+    }
+
+    #[test]
+    fn test_retry_delay() {
+        assert_eq!(retry_delay(0, None), Some(Duration::from_secs(1)));
+        assert_eq!(retry_delay(2, None), Some(Duration::from_secs(4)));
+        assert_eq!(retry_delay(MAX_RETRIES, None), None);
+
+        // retry-after wins, unless it asks for too long a wait
+        assert_eq!(retry_delay(0, Some("7")), Some(Duration::from_secs(7)));
+        assert_eq!(
+            retry_delay(0, Some("0.5")),
+            Some(Duration::from_millis(500))
+        );
+        assert_eq!(retry_delay(0, Some("120")), None);
+        // An HTTP date is not parsed; the backoff applies
+        assert_eq!(
+            retry_delay(1, Some("Wed, 21 Oct 2026 07:28:00 GMT")),
+            Some(Duration::from_secs(2))
+        );
+    }
+
+    #[test]
+    fn test_overload_responses_are_detected() {
+        assert!(is_overloaded(429));
+        assert!(is_overloaded(529));
+        assert!(!is_overloaded(500));
+
+        let body = r#"{"type":"error","error":{"type":"overloaded_error","message":"Overloaded"}}"#;
+        assert_eq!(
+            overloaded_message("anthropic", 529, body),
+            "anthropic is overloaded (HTTP 529): Overloaded"
+        );
+        assert_eq!(
+            overloaded_message("openai", 429, ""),
+            "openai is rate limited (HTTP 429)"
+        );
+    }
+}
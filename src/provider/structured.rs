@@ -0,0 +1,353 @@
+//! Structured (JSON schema) output support.
+//!
+//! OpenAI-compatible providers receive the schema as a `response_format`.
+//! Anthropic has no native equivalent, so the schema is described in the
+//! system prompt and replies can be seeded with `{` via prefill. Either way
+//! the final text is validated here before it is handed back to the caller.
+
+use super::{ChatContent, ChatMessage, ContentPart};
+use anyhow::{Context, Result};
+use serde_json::Value;
+use std::path::Path;
+
+/// Prefill used to coax Anthropic models into answering with a JSON object
+pub const JSON_PREFILL: &str = "{";
+
+/// How many times an invalid reply is sent back to the model for correction
+pub const MAX_RETRIES: usize = 2;
+
+/// Whether structured output can be requested from a provider
+pub fn is_supported(provider_id: &str) -> bool {
+    matches!(provider_id, "anthropic" | "openai")
+}
+
+/// Load a JSON schema from a file
+pub async fn load_schema(path: &Path) -> Result<Value> {
+    let content = tokio::fs::read_to_string(path)
+        .await
+        .with_context(|| format!("Failed to read JSON schema: {}", path.display()))?;
+    let schema: Value = serde_json::from_str(&content)
+        .with_context(|| format!("Invalid JSON schema: {}", path.display()))?;
+    if !schema.is_object() {
+        anyhow::bail!("JSON schema must be an object: {}", path.display());
+    }
+    Ok(schema)
+}
+
+/// Build the OpenAI `response_format` value for a schema
+pub fn openai_response_format(schema: &Value) -> Value {
+    let name = schema
+        .get("title")
+        .and_then(|t| t.as_str())
+        .map(sanitize_schema_name)
+        .filter(|n| !n.is_empty())
+        .unwrap_or_else(|| "response".to_string());
+
+    serde_json::json!({
+        "type": "json_schema",
+        "json_schema": {
+            "name": name,
+            "schema": schema,
+        }
+    })
+}
+
+/// OpenAI only accepts `[a-zA-Z0-9_-]` in schema names
+fn sanitize_schema_name(title: &str) -> String {
+    title
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .take(64)
+        .collect()
+}
+
+/// System prompt addition for providers without native structured output
+pub fn schema_instruction(schema: &Value) -> String {
+    let schema_str = serde_json::to_string_pretty(schema).unwrap_or_default();
+    format!(
+        "When you give your final answer, reply with a single JSON value that \
+        conforms to the following JSON schema. Do not wrap it in a code block \
+        or add any other text.\n\n{}",
+        schema_str
+    )
+}
+
+/// Parse model output as JSON and validate it against a schema
+///
+/// Tolerates a surrounding markdown code fence, which models often add even
+/// when told not to.
+pub fn parse_and_validate(text: &str, schema: &Value) -> Result<Value, String> {
+    let json_text = strip_code_fence(text.trim());
+    let value: Value =
+        serde_json::from_str(json_text).map_err(|e| format!("Invalid JSON: {}", e))?;
+    validate(&value, schema, "$")?;
+    Ok(value)
+}
+
+/// Text of the final assistant message, if the conversation ends with one
+pub fn last_assistant_text(messages: &[ChatMessage]) -> Option<String> {
+    let message = messages.last().filter(|m| m.role == "assistant")?;
+    match &message.content {
+        ChatContent::Text(text) => Some(text.clone()),
+        ChatContent::Parts(parts) => {
+            let text: String = parts
+                .iter()
+                .filter_map(|p| match p {
+                    ContentPart::Text { text } => Some(text.as_str()),
+                    _ => None,
+                })
+                .collect();
+            Some(text)
+        }
+    }
+}
+
+/// Validate the final assistant reply of a conversation against a schema
+pub fn check_output(messages: &[ChatMessage], schema: &Value) -> Result<Value, String> {
+    let text =
+        last_assistant_text(messages).ok_or_else(|| "no final answer was given".to_string())?;
+    parse_and_validate(&text, schema)
+}
+
+/// User message asking the model to fix a reply that failed validation
+pub fn correction_message(error: &str) -> ChatMessage {
+    ChatMessage {
+        role: "user".to_string(),
+        content: ChatContent::Text(format!(
+            "Your reply did not match the required JSON schema ({}). \
+            Reply again with only the corrected JSON.",
+            error
+        )),
+    }
+}
+
+/// Prefill for a correction attempt, for providers that support it
+pub fn retry_prefill(provider_id: &str) -> Option<String> {
+    (provider_id == "anthropic").then(|| JSON_PREFILL.to_string())
+}
+
+fn strip_code_fence(text: &str) -> &str {
+    let Some(rest) = text.strip_prefix("```") else {
+        return text;
+    };
+    let Some(body) = rest.strip_suffix("```") else {
+        return text;
+    };
+    // Drop the language tag line, e.g. ```json
+    body.split_once('\n').map_or(body, |(_, b)| b).trim()
+}
+
+/// Validate a value against the commonly used subset of JSON schema
+///
+/// Supports `type`, `enum`, `const`, `properties`, `required`,
+/// `additionalProperties: false`, `items` and `anyOf`/`oneOf`. Other keywords
+/// are ignored.
+pub fn validate(value: &Value, schema: &Value, path: &str) -> Result<(), String> {
+    let Some(schema) = schema.as_object() else {
+        // `true` accepts anything, `false` rejects everything
+        return match schema {
+            Value::Bool(false) => Err(format!("{}: no value is allowed here", path)),
+            _ => Ok(()),
+        };
+    };
+
+    if let Some(expected) = schema.get("type") {
+        check_type(value, expected, path)?;
+    }
+
+    if let Some(allowed) = schema.get("enum").and_then(|e| e.as_array()) {
+        if !allowed.contains(value) {
+            return Err(format!(
+                "{}: {} is not one of {}",
+                path,
+                value,
+                Value::from(allowed.clone())
+            ));
+        }
+    }
+
+    if let Some(expected) = schema.get("const") {
+        if value != expected {
+            return Err(format!("{}: expected {}, got {}", path, expected, value));
+        }
+    }
+
+    if let Some(variants) = schema
+        .get("anyOf")
+        .or_else(|| schema.get("oneOf"))
+        .and_then(|v| v.as_array())
+    {
+        if !variants.iter().any(|v| validate(value, v, path).is_ok()) {
+            return Err(format!("{}: does not match any allowed schema", path));
+        }
+    }
+
+    if let Some(object) = value.as_object() {
+        validate_object(object, schema, path)?;
+    }
+
+    if let (Some(items), Some(item_schema)) = (value.as_array(), schema.get("items")) {
+        for (i, item) in items.iter().enumerate() {
+            validate(item, item_schema, &format!("{}[{}]", path, i))?;
+        }
+    }
+
+    Ok(())
+}
+
+fn validate_object(
+    object: &serde_json::Map<String, Value>,
+    schema: &serde_json::Map<String, Value>,
+    path: &str,
+) -> Result<(), String> {
+    if let Some(required) = schema.get("required").and_then(|r| r.as_array()) {
+        for key in required.iter().filter_map(|k| k.as_str()) {
+            if !object.contains_key(key) {
+                return Err(format!("{}: missing required property '{}'", path, key));
+            }
+        }
+    }
+
+    let properties = schema.get("properties").and_then(|p| p.as_object());
+    let additional_allowed = schema.get("additionalProperties") != Some(&Value::Bool(false));
+
+    for (key, field) in object {
+        let field_path = format!("{}.{}", path, key);
+        match properties.and_then(|p| p.get(key)) {
+            Some(field_schema) => validate(field, field_schema, &field_path)?,
+            None if !additional_allowed => {
+                return Err(format!("{}: unexpected property", field_path));
+            }
+            None => {}
+        }
+    }
+
+    Ok(())
+}
+
+fn check_type(value: &Value, expected: &Value, path: &str) -> Result<(), String> {
+    let matches = |type_name: &str| match type_name {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        _ => true,
+    };
+
+    let ok = match expected {
+        Value::String(t) => matches(t),
+        Value::Array(types) => types.iter().filter_map(|t| t.as_str()).any(matches),
+        _ => true,
+    };
+
+    if ok {
+        Ok(())
+    } else {
+        Err(format!(
+            "{}: expected type {}, got {}",
+            path, expected, value
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn person_schema() -> Value {
+        json!({
+            "title": "Person record",
+            "type": "object",
+            "properties": {
+                "name": {"type": "string"},
+                "age": {"type": "integer"},
+                "tags": {"type": "array", "items": {"type": "string"}}
+            },
+            "required": ["name"],
+            "additionalProperties": false
+        })
+    }
+
+    #[test]
+    fn test_valid_output_is_accepted() {
+        let value = parse_and_validate(r#"{"name":"a","age":3,"tags":["x"]}"#, &person_schema());
+        assert_eq!(value.unwrap()["age"], 3);
+    }
+
+    #[test]
+    fn test_code_fence_is_stripped() {
+        let text = "```json\n{\"name\":\"a\"}\n```";
+        assert!(parse_and_validate(text, &person_schema()).is_ok());
+    }
+
+    #[test]
+    fn test_invalid_json_is_rejected() {
+        let err = parse_and_validate("not json", &person_schema()).unwrap_err();
+        assert!(err.starts_with("Invalid JSON"));
+    }
+
+    #[test]
+    fn test_schema_violations_are_reported_with_path() {
+        let schema = person_schema();
+        let err = parse_and_validate(r#"{"age":3}"#, &schema).unwrap_err();
+        assert!(err.contains("missing required property 'name'"));
+
+        let err = parse_and_validate(r#"{"name":"a","tags":[1]}"#, &schema).unwrap_err();
+        assert!(err.starts_with("$.tags[0]"));
+
+        let err = parse_and_validate(r#"{"name":"a","extra":true}"#, &schema).unwrap_err();
+        assert!(err.contains("unexpected property"));
+    }
+
+    #[test]
+    fn test_last_assistant_text_requires_trailing_assistant_message() {
+        let mut messages = vec![ChatMessage {
+            role: "user".to_string(),
+            content: ChatContent::Text("hi".to_string()),
+        }];
+        assert_eq!(last_assistant_text(&messages), None);
+
+        messages.push(ChatMessage {
+            role: "assistant".to_string(),
+            content: ChatContent::Parts(vec![ContentPart::Text {
+                text: "{}".to_string(),
+            }]),
+        });
+        assert_eq!(last_assistant_text(&messages), Some("{}".to_string()));
+    }
+
+    #[test]
+    fn test_check_output() {
+        let user = ChatMessage {
+            role: "user".to_string(),
+            content: ChatContent::Text("hi".to_string()),
+        };
+        let err = check_output(std::slice::from_ref(&user), &person_schema()).unwrap_err();
+        assert_eq!(err, "no final answer was given");
+
+        let reply = ChatMessage {
+            role: "assistant".to_string(),
+            content: ChatContent::Text(r#"{"name":"a"}"#.to_string()),
+        };
+        let value = check_output(&[user, reply], &person_schema()).unwrap();
+        assert_eq!(value["name"], "a");
+    }
+
+    #[test]
+    fn test_openai_response_format_uses_sanitized_title() {
+        let format = openai_response_format(&person_schema());
+        assert_eq!(format["type"], "json_schema");
+        assert_eq!(format["json_schema"]["name"], "Person_record");
+        assert_eq!(format["json_schema"]["schema"]["type"], "object");
+    }
+}
@@ -0,0 +1,120 @@
+//! Tokenizer selection for token counts.
+//!
+//! Counts such as the size of hidden reasoning are estimated locally. OpenAI
+//! models are counted exactly with their tiktoken encoding; other families get
+//! an estimate tuned to them, and unknown models fall back to four characters
+//! per token.
+
+/// Rough characters per token when no tokenizer is known
+const CHARS_PER_TOKEN: f64 = 4.0;
+
+/// Characters per token of Claude models, which have no public tokenizer
+const CLAUDE_CHARS_PER_TOKEN: f64 = 3.5;
+
+/// A way of counting the tokens of a text
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tokenizer {
+    /// tiktoken `cl100k_base`: GPT-4, GPT-3.5 and older embeddings
+    Cl100k,
+    /// tiktoken `o200k_base`: GPT-4o, GPT-4.1, GPT-5 and the o-series
+    O200k,
+    /// Estimate for Anthropic models
+    Claude,
+    /// Characters divided by four
+    Heuristic,
+}
+
+/// Model ID prefixes of each family, checked in order after the o-series
+const FAMILIES: &[(&str, Tokenizer)] = &[
+    ("gpt-4o", Tokenizer::O200k),
+    ("gpt-4.1", Tokenizer::O200k),
+    ("gpt-4.5", Tokenizer::O200k),
+    ("gpt-5", Tokenizer::O200k),
+    ("chatgpt-4o", Tokenizer::O200k),
+    ("codex", Tokenizer::O200k),
+    ("gpt-4", Tokenizer::Cl100k),
+    ("gpt-3.5", Tokenizer::Cl100k),
+    ("text-embedding-3", Tokenizer::Cl100k),
+    ("text-embedding-ada", Tokenizer::Cl100k),
+    ("claude", Tokenizer::Claude),
+];
+
+impl Tokenizer {
+    /// Name shown in `/status`
+    pub fn name(self) -> &'static str {
+        match self {
+            Tokenizer::Cl100k => "tiktoken cl100k_base",
+            Tokenizer::O200k => "tiktoken o200k_base",
+            Tokenizer::Claude => "estimate (3.5 chars/token)",
+            Tokenizer::Heuristic => "estimate (4 chars/token)",
+        }
+    }
+
+    /// Number of tokens in `text`
+    pub fn count(self, text: &str) -> usize {
+        match self {
+            Tokenizer::Cl100k => tiktoken_rs::cl100k_base_singleton()
+                .encode_ordinary(text)
+                .len(),
+            Tokenizer::O200k => tiktoken_rs::o200k_base_singleton()
+                .encode_ordinary(text)
+                .len(),
+            Tokenizer::Claude => estimate(text, CLAUDE_CHARS_PER_TOKEN),
+            Tokenizer::Heuristic => estimate(text, CHARS_PER_TOKEN),
+        }
+    }
+}
+
+/// Tokenizer of a model, by its family
+///
+/// Routed IDs such as `openai/gpt-4o` on OpenRouter are matched by the part
+/// after the last `/`. Models of the `anthropic` provider use the Claude
+/// estimate whatever their ID.
+pub fn for_model(provider_id: &str, model_id: &str) -> Tokenizer {
+    if super::is_o_series(model_id) {
+        return Tokenizer::O200k;
+    }
+    let model = model_id.rsplit('/').next().unwrap_or(model_id);
+    let model = model.to_ascii_lowercase();
+    FAMILIES
+        .iter()
+        .find(|(prefix, _)| model.starts_with(prefix))
+        .map(|(_, tokenizer)| *tokenizer)
+        .unwrap_or(match provider_id {
+            "anthropic" => Tokenizer::Claude,
+            _ => Tokenizer::Heuristic,
+        })
+}
+
+fn estimate(text: &str, chars_per_token: f64) -> usize {
+    (text.chars().count() as f64 / chars_per_token).ceil() as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_for_model() {
+        assert_eq!(for_model("openai", "gpt-4o-mini"), Tokenizer::O200k);
+        assert_eq!(for_model("openai", "o3-mini"), Tokenizer::O200k);
+        assert_eq!(for_model("openai", "o5"), Tokenizer::O200k);
+        assert_eq!(for_model("openai", "gpt-4-turbo"), Tokenizer::Cl100k);
+        assert_eq!(for_model("openrouter", "openai/gpt-5"), Tokenizer::O200k);
+        assert_eq!(
+            for_model("bedrock", "anthropic/Claude-Sonnet-4"),
+            Tokenizer::Claude
+        );
+        assert_eq!(for_model("anthropic", "future-model"), Tokenizer::Claude);
+        assert_eq!(for_model("google", "gemini-2.5-pro"), Tokenizer::Heuristic);
+    }
+
+    #[test]
+    fn test_count() {
+        assert_eq!(Tokenizer::Heuristic.count("The answer."), 3);
+        assert_eq!(Tokenizer::Claude.count("The answer."), 4);
+        assert_eq!(Tokenizer::Heuristic.count(""), 0);
+        assert_eq!(Tokenizer::O200k.count("hello world"), 2);
+        assert_eq!(Tokenizer::Cl100k.count("hello world"), 2);
+    }
+}
@@ -0,0 +1,406 @@
+//! Provider-specific rewriting of tool input schemas.
+//!
+//! Tool schemas, in particular those of MCP servers, may use JSON Schema
+//! features a provider rejects with a 400. Before tools are sent, each schema
+//! is rewritten to the subset the provider's profile accepts: `$ref`s are
+//! inlined, unions are flattened where the variants allow it, and unsupported
+//! keywords and string formats are dropped.
+
+use serde_json::{Map, Value};
+
+/// Max nesting of inlined `$ref`s, which ends recursive schemas
+const MAX_REF_DEPTH: usize = 8;
+
+/// Max rounds of union flattening on one schema, for unions nested in unions
+const MAX_FLATTEN_ROUNDS: usize = 8;
+
+/// Union keywords, flattened for providers without union support
+const UNION_KEYWORDS: [&str; 3] = ["allOf", "anyOf", "oneOf"];
+
+/// JSON Schema features a provider accepts in tool input schemas
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SchemaProfile {
+    /// `$ref` into `$defs` or `definitions`
+    pub refs: bool,
+    /// `allOf`/`anyOf`/`oneOf`, `const` and `type` arrays
+    pub unions: bool,
+    /// Accepted string formats (None = any)
+    pub formats: Option<&'static [&'static str]>,
+    /// Keywords removed from every schema node
+    pub unsupported: &'static [&'static str],
+}
+
+/// Anthropic accepts most of JSON Schema; `$ref`s are inlined to be safe
+const ANTHROPIC: SchemaProfile = SchemaProfile {
+    refs: false,
+    unions: true,
+    formats: None,
+    unsupported: &[],
+};
+
+const OPENAI: SchemaProfile = SchemaProfile {
+    refs: true,
+    unions: true,
+    formats: None,
+    unsupported: &[],
+};
+
+/// Gemini takes an OpenAPI-style subset of JSON Schema
+const GEMINI: SchemaProfile = SchemaProfile {
+    refs: false,
+    unions: false,
+    formats: Some(&["enum", "date-time"]),
+    unsupported: &[
+        "$schema",
+        "$id",
+        "$comment",
+        "additionalProperties",
+        "patternProperties",
+        "default",
+        "examples",
+    ],
+};
+
+/// Other OpenAI-compatible servers vary, so only `$ref`s are inlined
+const DEFAULT: SchemaProfile = SchemaProfile {
+    refs: false,
+    unions: true,
+    formats: None,
+    unsupported: &[],
+};
+
+/// Schema profile of a provider and model
+///
+/// Gemini models are matched by ID too, since proxies such as Copilot serve
+/// them under another provider.
+pub fn profile(provider_id: &str, model_id: &str) -> SchemaProfile {
+    match provider_id {
+        _ if provider_id == "google" || model_id.contains("gemini") => GEMINI,
+        "anthropic" => ANTHROPIC,
+        "openai" => OPENAI,
+        _ => DEFAULT,
+    }
+}
+
+/// Rewrite a tool input schema to what the profile accepts
+pub fn sanitize(schema: &Value, profile: &SchemaProfile) -> Value {
+    let mut schema = if profile.refs {
+        schema.clone()
+    } else {
+        let mut inlined = inline_refs(schema, schema, 0);
+        if let Some(root) = inlined.as_object_mut() {
+            root.remove("$defs");
+            root.remove("definitions");
+        }
+        inlined
+    };
+    rewrite(&mut schema, profile);
+    schema
+}
+
+/// Replace each local `$ref` with a copy of the schema it points to
+///
+/// Unresolvable references and those nested too deep become `{}`, which
+/// accepts any value.
+fn inline_refs(node: &Value, root: &Value, depth: usize) -> Value {
+    match node {
+        Value::Object(map) => {
+            let Some(reference) = map.get("$ref").and_then(Value::as_str) else {
+                return Value::Object(
+                    map.iter()
+                        .map(|(key, value)| (key.clone(), inline_refs(value, root, depth)))
+                        .collect(),
+                );
+            };
+            let target = reference
+                .strip_prefix('#')
+                .and_then(|pointer| root.pointer(pointer))
+                .filter(|_| depth < MAX_REF_DEPTH);
+            let mut resolved = match target {
+                Some(target) => inline_refs(target, root, depth + 1),
+                None => Value::Object(Map::new()),
+            };
+            // Keywords next to the `$ref`, such as a description, win
+            if let Value::Object(resolved) = &mut resolved {
+                for (key, value) in map.iter().filter(|(key, _)| *key != "$ref") {
+                    resolved.insert(key.clone(), inline_refs(value, root, depth));
+                }
+            }
+            resolved
+        }
+        Value::Array(items) => Value::Array(
+            items
+                .iter()
+                .map(|item| inline_refs(item, root, depth))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+/// Apply the profile to a schema node and its subschemas
+fn rewrite(node: &mut Value, profile: &SchemaProfile) {
+    let Value::Object(map) = node else {
+        return;
+    };
+    for keyword in profile.unsupported {
+        map.remove(*keyword);
+    }
+    if !profile.unions {
+        flatten_unions(map);
+    }
+    if let Some(formats) = profile.formats {
+        let format = map.get("format").and_then(Value::as_str);
+        if format.is_some_and(|format| !formats.contains(&format)) {
+            map.remove("format");
+        }
+    }
+
+    for key in ["items", "additionalProperties", "not"] {
+        if let Some(child) = map.get_mut(key) {
+            rewrite(child, profile);
+        }
+    }
+    for key in ["properties", "patternProperties", "$defs", "definitions"] {
+        if let Some(Value::Object(children)) = map.get_mut(key) {
+            children
+                .values_mut()
+                .for_each(|child| rewrite(child, profile));
+        }
+    }
+    for key in UNION_KEYWORDS.into_iter().chain(["prefixItems"]) {
+        if let Some(Value::Array(children)) = map.get_mut(key) {
+            children
+                .iter_mut()
+                .for_each(|child| rewrite(child, profile));
+        }
+    }
+}
+
+/// Rewrite unions into a single schema
+///
+/// - `type` arrays keep their first non-null type
+/// - `allOf` parts are merged
+/// - `anyOf`/`oneOf` of literals become an `enum`, of objects a single object
+///   with every variant's properties, and otherwise their first variant
+/// - `const` becomes a one-value `enum`
+fn flatten_unions(map: &mut Map<String, Value>) {
+    for _ in 0..MAX_FLATTEN_ROUNDS {
+        if !UNION_KEYWORDS
+            .iter()
+            .any(|keyword| map.contains_key(*keyword))
+        {
+            break;
+        }
+        if let Some(Value::Array(parts)) = map.remove("allOf") {
+            for part in parts {
+                merge(map, part, true);
+            }
+        }
+        for keyword in ["anyOf", "oneOf"] {
+            let Some(Value::Array(variants)) = map.remove(keyword) else {
+                continue;
+            };
+            let variants: Vec<Value> = variants
+                .into_iter()
+                .filter(|variant| variant.get("type").and_then(Value::as_str) != Some("null"))
+                .collect();
+
+            if let Some(values) = literal_values(&variants) {
+                if let Some(ty) = variants.iter().find_map(|variant| variant.get("type")) {
+                    map.entry("type").or_insert_with(|| ty.clone());
+                }
+                map.insert("enum".to_string(), Value::Array(values));
+            } else if !variants.is_empty() && variants.iter().all(is_object_schema) {
+                for variant in variants {
+                    merge(map, variant, false);
+                }
+            } else if let Some(first) = variants.into_iter().next() {
+                merge(map, first, true);
+            }
+        }
+    }
+
+    if let Some(Value::Array(types)) = map.get("type") {
+        let ty = types
+            .iter()
+            .find(|ty| ty.as_str() != Some("null"))
+            .or(types.first())
+            .cloned();
+        match ty {
+            Some(ty) => map.insert("type".to_string(), ty),
+            None => map.remove("type"),
+        };
+    }
+    if let Some(value) = map.remove("const") {
+        map.insert("enum".to_string(), Value::Array(vec![value]));
+    }
+}
+
+/// Values of union variants that are all `const` or `enum` literals
+fn literal_values(variants: &[Value]) -> Option<Vec<Value>> {
+    let mut values = Vec::new();
+    for variant in variants {
+        match (variant.get("const"), variant.get("enum")) {
+            (Some(value), _) => values.push(value.clone()),
+            (None, Some(Value::Array(literals))) => values.extend(literals.iter().cloned()),
+            _ => return None,
+        }
+    }
+    (!values.is_empty()).then_some(values)
+}
+
+fn is_object_schema(schema: &Value) -> bool {
+    schema.get("type").and_then(Value::as_str) == Some("object")
+        || schema.get("properties").is_some()
+}
+
+/// Merge a subschema into a schema; the schema's own keywords win
+///
+/// Properties are combined. `required` is only merged when `required` is
+/// set: a property required by one union variant is optional in the union.
+fn merge(map: &mut Map<String, Value>, part: Value, required: bool) {
+    let Value::Object(part) = part else {
+        return;
+    };
+    for (key, value) in part {
+        match (key.as_str(), value) {
+            ("properties", Value::Object(properties)) => {
+                let existing = map
+                    .entry("properties")
+                    .or_insert_with(|| Value::Object(Map::new()));
+                if let Value::Object(existing) = existing {
+                    for (name, schema) in properties {
+                        existing.entry(name).or_insert(schema);
+                    }
+                }
+            }
+            ("required", Value::Array(names)) => {
+                if !required {
+                    continue;
+                }
+                let existing = map
+                    .entry("required")
+                    .or_insert_with(|| Value::Array(Vec::new()));
+                if let Value::Array(existing) = existing {
+                    for name in names {
+                        if !existing.contains(&name) {
+                            existing.push(name);
+                        }
+                    }
+                }
+            }
+            (_, value) => {
+                map.entry(key).or_insert(value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_inline_refs() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "point": {"$ref": "#/$defs/point", "description": "Where"},
+                "missing": {"$ref": "#/$defs/nope"}
+            },
+            "$defs": {
+                "point": {"type": "object", "properties": {"x": {"type": "number"}}}
+            }
+        });
+        assert_eq!(
+            sanitize(&schema, &ANTHROPIC),
+            json!({
+                "type": "object",
+                "properties": {
+                    "point": {
+                        "type": "object",
+                        "properties": {"x": {"type": "number"}},
+                        "description": "Where"
+                    },
+                    "missing": {}
+                }
+            })
+        );
+        // OpenAI resolves references itself
+        assert_eq!(sanitize(&schema, &OPENAI), schema);
+    }
+
+    #[test]
+    fn test_recursive_ref_ends() {
+        let schema = json!({
+            "type": "object",
+            "properties": {"child": {"$ref": "#"}}
+        });
+        let sanitized = sanitize(&schema, &DEFAULT);
+        let mut node = &sanitized;
+        for _ in 0..MAX_REF_DEPTH {
+            node = &node["properties"]["child"];
+        }
+        assert_eq!(node["properties"]["child"], json!({}));
+    }
+
+    #[test]
+    fn test_flatten_unions_for_gemini() {
+        let schema = json!({
+            "type": "object",
+            "additionalProperties": false,
+            "properties": {
+                "mode": {"oneOf": [
+                    {"const": "fast", "type": "string"},
+                    {"const": "slow", "type": "string"}
+                ]},
+                "limit": {"type": ["integer", "null"], "default": 10},
+                "when": {"type": "string", "format": "date-time"},
+                "email": {"type": "string", "format": "email"},
+                "target": {"anyOf": [
+                    {"type": "object", "properties": {"path": {"type": "string"}}, "required": ["path"]},
+                    {"type": "object", "properties": {"url": {"type": "string"}}, "required": ["url"]}
+                ]},
+                "base": {"allOf": [
+                    {"properties": {"a": {"type": "string"}}, "required": ["a"]},
+                    {"properties": {"b": {"type": "string"}}}
+                ]}
+            }
+        });
+        assert_eq!(
+            sanitize(&schema, &profile("google", "gemini-2.5-pro")),
+            json!({
+                "type": "object",
+                "properties": {
+                    "mode": {"type": "string", "enum": ["fast", "slow"]},
+                    "limit": {"type": "integer"},
+                    "when": {"type": "string", "format": "date-time"},
+                    "email": {"type": "string"},
+                    "target": {
+                        "type": "object",
+                        "properties": {"path": {"type": "string"}, "url": {"type": "string"}}
+                    },
+                    "base": {
+                        "properties": {"a": {"type": "string"}, "b": {"type": "string"}},
+                        "required": ["a"]
+                    }
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn test_mixed_union_keeps_first_variant() {
+        let schema = json!({"anyOf": [{"type": "string"}, {"type": "number"}]});
+        assert_eq!(sanitize(&schema, &GEMINI), json!({"type": "string"}));
+    }
+
+    #[test]
+    fn test_profile() {
+        assert_eq!(profile("anthropic", "claude-sonnet-4"), ANTHROPIC);
+        assert_eq!(profile("copilot", "gemini-2.0-flash"), GEMINI);
+        assert_eq!(profile("my-proxy", "llama-3"), DEFAULT);
+    }
+}
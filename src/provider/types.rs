@@ -23,6 +23,9 @@ pub struct Provider {
     /// Provider-specific options
     #[serde(default)]
     pub options: HashMap<String, serde_json::Value>,
+    /// Skip TLS certificate verification (opt-in, for local gateways only)
+    #[serde(default)]
+    pub insecure_skip_verify: bool,
     /// Available models
     pub models: HashMap<String, Model>,
 }
@@ -103,6 +106,58 @@ pub struct ModelCapabilities {
     pub interleaved: InterleavedSupport,
 }
 
+impl ModelCapabilities {
+    /// Whether the model has a capability
+    pub fn supports(&self, capability: Capability) -> bool {
+        match capability {
+            Capability::ToolCall => self.toolcall,
+            Capability::Reasoning => self.reasoning,
+            Capability::ImageInput => self.input.image,
+        }
+    }
+
+    /// The capabilities in `required` the model lacks
+    pub fn missing(&self, required: &[Capability]) -> Vec<Capability> {
+        required
+            .iter()
+            .copied()
+            .filter(|capability| !self.supports(*capability))
+            .collect()
+    }
+}
+
+/// A model capability a session can depend on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Capability {
+    ToolCall,
+    Reasoning,
+    ImageInput,
+}
+
+impl Capability {
+    pub const ALL: [Capability; 3] = [
+        Capability::ToolCall,
+        Capability::Reasoning,
+        Capability::ImageInput,
+    ];
+
+    /// Name of the capability, following the models.dev fields
+    pub fn name(self) -> &'static str {
+        match self {
+            Capability::ToolCall => "toolcall",
+            Capability::Reasoning => "reasoning",
+            Capability::ImageInput => "input.image",
+        }
+    }
+
+    /// Parse a capability from its name
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|capability| capability.name() == name)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct Modalities {
     pub text: bool,
@@ -159,3 +214,26 @@ pub enum ModelStatus {
     Active,
     Deprecated,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_missing_capabilities() {
+        let capabilities = ModelCapabilities {
+            toolcall: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            capabilities.missing(&Capability::ALL),
+            vec![Capability::Reasoning, Capability::ImageInput]
+        );
+        assert!(capabilities.missing(&[Capability::ToolCall]).is_empty());
+        assert_eq!(
+            Capability::parse("input.image"),
+            Some(Capability::ImageInput)
+        );
+        assert_eq!(Capability::parse("vision"), None);
+    }
+}
@@ -2,20 +2,28 @@
 //!
 //! This module provides a shared question/answer system that works across
 //! both CLI and TUI modes. It handles:
-//! - Storing pending question requests
+//! - Storing pending question requests, persisted per session so they
+//!   survive a restart
 //! - Managing response channels for waiting tools
 //! - Creating handlers for different UIs
 
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
-use crate::tool::QuestionResponse;
+use crate::tool::{QuestionRequest, QuestionResponse};
+
+/// Storage key prefix of pending questions, followed by session and request ID
+const QUESTION_KEY: &str = "question";
 
 /// Question request information
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct QuestionRequestInfo {
     pub id: String,
+    /// Session the question was asked in (empty when not persisted)
+    pub session_id: String,
+    pub request: QuestionRequest,
 }
 
 // Global question state
@@ -41,13 +49,65 @@ pub async fn store_response_channel(
     map.insert(id, tx);
 }
 
-/// Store a pending question request
+/// Store a pending question request, persisting it under its session
 pub async fn store_pending_request(request: QuestionRequestInfo) {
+    if !request.session_id.is_empty() {
+        if let Err(e) = crate::storage::global()
+            .write(&[QUESTION_KEY, &request.session_id, &request.id], &request)
+            .await
+        {
+            tracing::warn!("Failed to persist question {}: {}", request.id, e);
+        }
+    }
+
     let mut map = PENDING_QUESTIONS.lock().await;
     map.insert(request.id.clone(), request);
 }
 
+/// Restore the questions still pending in a session, e.g. after a restart
+/// or when switching back to it
+///
+/// Questions a running tool is waiting on are skipped. The restored ones are
+/// tracked as pending, so answering them removes the stored copy.
+pub async fn restore(session_id: &str) -> Vec<QuestionRequest> {
+    let stored: Vec<QuestionRequestInfo> = match crate::storage::global()
+        .list_values(&[QUESTION_KEY, session_id])
+        .await
+    {
+        Ok(stored) => stored,
+        Err(e) => {
+            tracing::warn!("Failed to load pending questions: {}", e);
+            return Vec::new();
+        }
+    };
+
+    let live = QUESTION_RESPONSES.lock().await;
+    let mut pending = PENDING_QUESTIONS.lock().await;
+    stored
+        .into_iter()
+        .filter(|info| !live.contains_key(&info.id))
+        .map(|info| {
+            let request = info.request.clone();
+            pending.insert(info.id.clone(), info);
+            request
+        })
+        .collect()
+}
+
+/// Prompt relaying the answers to a question whose tool is no longer waiting
+pub fn answers_prompt(request: &QuestionRequest, answers: &QuestionResponse) -> String {
+    let mut prompt = "Answers to your earlier question:".to_string();
+    for (question, answer) in request.questions.iter().zip(answers) {
+        if !answer.is_empty() {
+            prompt.push_str(&format!("\n- {}: {}", question.question, answer.join(", ")));
+        }
+    }
+    prompt
+}
+
 /// Send question response to waiting tool
+///
+/// The pending request is dropped from memory and storage.
 pub async fn send_question_response(id: String, answers: QuestionResponse) {
     let mut response_map = QUESTION_RESPONSES.lock().await;
     if let Some(tx) = response_map.remove(&id) {
@@ -60,16 +120,61 @@ pub async fn send_question_response(id: String, answers: QuestionResponse) {
     }
     drop(response_map);
 
+    let removed = PENDING_QUESTIONS.lock().await.remove(&id);
+    if let Some(info) = removed {
+        forget(&info).await;
+    }
+}
+
+/// Drop the questions of a session that a running turn waits on, e.g. when
+/// the turn is aborted
+///
+/// Their tools stop waiting and the stored copies are removed. Questions
+/// restored from storage have no waiting tool and are kept.
+pub async fn cancel_waiting(session_id: &str) {
+    let mut responses = QUESTION_RESPONSES.lock().await;
     let mut pending = PENDING_QUESTIONS.lock().await;
-    pending.remove(&id);
+    let ids: Vec<String> = pending
+        .values()
+        .filter(|info| info.session_id == session_id && responses.contains_key(&info.id))
+        .map(|info| info.id.clone())
+        .collect();
+    let mut cancelled = Vec::new();
+    for id in ids {
+        responses.remove(&id);
+        cancelled.extend(pending.remove(&id));
+    }
+    drop(pending);
+    drop(responses);
+
+    for info in cancelled {
+        forget(&info).await;
+    }
+}
+
+/// Remove the stored copy of a question
+async fn forget(info: &QuestionRequestInfo) {
+    if info.session_id.is_empty() {
+        return;
+    }
+    if let Err(e) = crate::storage::global()
+        .remove(&[QUESTION_KEY, &info.session_id, &info.id])
+        .await
+    {
+        tracing::warn!("Failed to remove question {}: {}", info.id, e);
+    }
 }
 
 /// Create a TUI question handler that sends requests via event channel
+///
+/// Requests are persisted under `session_id` until answered.
 pub fn create_tui_question_handler(
+    session_id: String,
     event_tx: tokio::sync::mpsc::Sender<crate::tui::AppEvent>,
 ) -> crate::tool::QuestionHandler {
     std::sync::Arc::new(move |request| {
         let event_tx = event_tx.clone();
+        let session_id = session_id.clone();
         let (response_tx, response_rx) = tokio::sync::oneshot::channel();
 
         let request_clone = request.clone();
@@ -80,6 +185,8 @@ pub fn create_tui_question_handler(
             // Store pending request
             store_pending_request(QuestionRequestInfo {
                 id: request_clone.id.clone(),
+                session_id,
+                request: request_clone.clone(),
             })
             .await;
 
@@ -91,3 +198,29 @@ pub fn create_tui_question_handler(
         response_rx
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tool::QuestionInfo;
+
+    #[test]
+    fn test_answers_prompt() {
+        let question = |text: &str| QuestionInfo {
+            question: text.to_string(),
+            header: String::new(),
+            options: Vec::new(),
+            multiple: false,
+            custom: true,
+        };
+        let request = QuestionRequest {
+            id: "q1".to_string(),
+            questions: vec![question("Which framework?"), question("Add tests?")],
+        };
+        let answers = vec![vec!["React".to_string()], Vec::new()];
+        assert_eq!(
+            answers_prompt(&request, &answers),
+            "Answers to your earlier question:\n- Which framework?: React"
+        );
+    }
+}
@@ -0,0 +1,151 @@
+//! Admission control for prompt requests.
+//!
+//! At most `max_turns` turns run at once and at most `max_queued` more wait
+//! for a slot; a request beyond that is turned away, to be retried later.
+//! Turns of one session run one at a time, so a second prompt for a busy
+//! session waits in the queue behind the running one.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::{OwnedMutexGuard, OwnedSemaphorePermit, Semaphore};
+
+/// Turns run at once when `server.max_turns` is unset
+pub const DEFAULT_MAX_TURNS: usize = 4;
+
+/// Requests waiting for a turn when `server.max_queued` is unset
+pub const DEFAULT_MAX_QUEUED: usize = 16;
+
+/// Limits on the turns run for prompt requests
+pub struct Admission {
+    running: Arc<Semaphore>,
+    /// Requests running or waiting to run
+    admitted: Arc<AtomicUsize>,
+    capacity: usize,
+    sessions: Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>,
+}
+
+/// A place in the queue, given up when dropped
+pub struct Reservation {
+    admitted: Arc<AtomicUsize>,
+}
+
+/// The right to run a turn, held until the turn ends
+pub struct Turn {
+    _running: OwnedSemaphorePermit,
+    _session: OwnedMutexGuard<()>,
+    _reservation: Reservation,
+}
+
+impl Admission {
+    pub fn new(max_turns: usize, max_queued: usize) -> Self {
+        let max_turns = max_turns.max(1);
+        Self {
+            running: Arc::new(Semaphore::new(max_turns)),
+            admitted: Arc::new(AtomicUsize::new(0)),
+            capacity: max_turns + max_queued,
+            sessions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Limits from the `server` config
+    pub fn from_config(config: &crate::config::Config) -> Self {
+        let server = config.server.as_ref();
+        Self::new(
+            server
+                .and_then(|s| s.max_turns)
+                .unwrap_or(DEFAULT_MAX_TURNS),
+            server
+                .and_then(|s| s.max_queued)
+                .unwrap_or(DEFAULT_MAX_QUEUED),
+        )
+    }
+
+    /// Take a place in the queue, or None when it is full
+    pub fn try_reserve(&self) -> Option<Reservation> {
+        self.admitted
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |n| {
+                (n < self.capacity).then_some(n + 1)
+            })
+            .ok()?;
+        Some(Reservation {
+            admitted: self.admitted.clone(),
+        })
+    }
+
+    /// Wait until the session is idle and a turn slot is free
+    pub async fn admit(&self, reservation: Reservation, session_id: &str) -> Turn {
+        let session = self.session_lock(session_id).lock_owned().await;
+        let running = self
+            .running
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("admission semaphore is never closed");
+        Turn {
+            _running: running,
+            _session: session,
+            _reservation: reservation,
+        }
+    }
+
+    /// The lock serializing the turns of a session
+    fn session_lock(&self, session_id: &str) -> Arc<tokio::sync::Mutex<()>> {
+        let mut sessions = self.sessions.lock().unwrap();
+        // Locks no request holds or waits for are no longer needed
+        sessions.retain(|_, lock| Arc::strong_count(lock) > 1);
+        sessions.entry(session_id.to_string()).or_default().clone()
+    }
+}
+
+impl Drop for Reservation {
+    fn drop(&mut self) {
+        self.admitted.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_queue_is_bounded() {
+        let admission = Admission::new(1, 1);
+        let first = admission.try_reserve().unwrap();
+        let _second = admission.try_reserve().unwrap();
+        assert!(admission.try_reserve().is_none());
+
+        drop(first);
+        assert!(admission.try_reserve().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_one_turn_per_session() {
+        let admission = Arc::new(Admission::new(2, 2));
+        let turn = admission.admit(admission.try_reserve().unwrap(), "a").await;
+
+        // Another session gets the second slot
+        let other = admission.try_reserve().unwrap();
+        let other = tokio::time::timeout(Duration::from_secs(1), admission.admit(other, "b"))
+            .await
+            .unwrap();
+
+        // The same session waits for the running turn
+        let waiting = {
+            let admission = admission.clone();
+            let reservation = admission.try_reserve().unwrap();
+            tokio::spawn(async move { admission.admit(reservation, "a").await })
+        };
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!waiting.is_finished());
+
+        drop(turn);
+        drop(other);
+        tokio::time::timeout(Duration::from_secs(1), waiting)
+            .await
+            .unwrap()
+            .unwrap();
+    }
+}
@@ -0,0 +1,328 @@
+//! HTTP server started by `opencode serve`.
+//!
+//! `POST /prompt` runs a turn and streams its events as server-sent events:
+//! `session` first, then `delta`, `reasoning`, `tool_call`, `tool_input`,
+//! `tool_output`, `tool_result`, `step`, `fallback` and `permission_denied`
+//! as they happen, ending with `done` or `error`. Nobody can answer a
+//! permission prompt or a question here, so tool calls needing a prompt are
+//! denied and questions dismissed; allow tools with permission rules instead.
+//!
+//! A client that disconnects ends its turn: the abort signal cancels pending
+//! tool calls and the turn's task is dropped along with its provider request.
+//!
+//! Prompts go through admission control (see [`admission`]); a request that
+//! finds the queue full gets `429 Too Many Requests` with a `Retry-After`.
+
+pub mod admission;
+
+use std::convert::Infallible;
+use std::sync::Arc;
+
+use anyhow::Result;
+use axum::extract::State;
+use axum::http::{header, StatusCode};
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
+use axum::routing::post;
+use axum::{Json, Router};
+use futures::{Stream, StreamExt};
+use serde::Deserialize;
+use serde_json::json;
+use tokio::sync::{mpsc, watch};
+
+use crate::config::Config;
+use crate::session::{CreateSessionOptions, Session};
+use crate::tool::{BatchDecision, PermissionScope};
+use crate::tui::{stream_response_agentic, AppEvent, StreamOptions};
+use admission::Admission;
+
+/// Seconds a client turned away by a full queue is asked to wait
+const RETRY_AFTER_SECS: u64 = 5;
+
+struct ServerState {
+    config: Config,
+    admission: Admission,
+}
+
+/// Body of `POST /prompt`
+#[derive(Debug, Deserialize)]
+struct PromptRequest {
+    prompt: String,
+    /// Session to continue; a new one is created when unset
+    #[serde(default)]
+    session_id: Option<String>,
+    /// Model in provider/model format; the session's or configured one when unset
+    #[serde(default)]
+    model: Option<String>,
+}
+
+/// Routes of the server
+pub fn router(config: Config) -> Router {
+    let state = Arc::new(ServerState {
+        admission: Admission::from_config(&config),
+        config,
+    });
+    Router::new()
+        .route("/prompt", post(prompt))
+        .with_state(state)
+}
+
+/// Serve requests on `listener` until Ctrl+C
+pub async fn serve(listener: tokio::net::TcpListener, config: Config) -> Result<()> {
+    axum::serve(listener, router(config))
+        .with_graceful_shutdown(async {
+            let _ = tokio::signal::ctrl_c().await;
+        })
+        .await?;
+    Ok(())
+}
+
+async fn prompt(
+    State(state): State<Arc<ServerState>>,
+    Json(request): Json<PromptRequest>,
+) -> Response {
+    let Some(reservation) = state.admission.try_reserve() else {
+        return (
+            StatusCode::TOO_MANY_REQUESTS,
+            [(header::RETRY_AFTER, RETRY_AFTER_SECS.to_string())],
+            "Too many prompts are queued; retry later",
+        )
+            .into_response();
+    };
+
+    let session = match request.session_id.as_deref() {
+        Some(id) => Session::get("default", id).await,
+        None => Session::create(CreateSessionOptions::default())
+            .await
+            .map(Some),
+    };
+    let session = match session {
+        Ok(Some(session)) => session,
+        Ok(None) => return (StatusCode::NOT_FOUND, "Session not found").into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, format!("{:#}", e)).into_response(),
+    };
+    let model =
+        crate::cli::prompt::resolve_model(request.model.as_deref(), &session, &state.config);
+    let (provider_id, model_id) = match model.await {
+        Ok(model) => model,
+        Err(e) => return (StatusCode::BAD_REQUEST, format!("{:#}", e)).into_response(),
+    };
+
+    let turn = state.admission.admit(reservation, &session.id).await;
+    let (event_tx, event_rx) = mpsc::channel(100);
+    let (abort_tx, abort_rx) = watch::channel(false);
+    let options = StreamOptions {
+        abort: Some(abort_rx),
+        system: session.system.clone(),
+        pinned: session.pinned.clone(),
+        permission: session.permission.clone(),
+        ..Default::default()
+    };
+    let session_id = session.id.clone();
+    let task = tokio::spawn(async move {
+        let _turn = turn;
+        let result = stream_response_agentic(
+            provider_id,
+            model_id,
+            session_id,
+            request.prompt,
+            options,
+            event_tx.clone(),
+        )
+        .await;
+        // Errors met before the turn started have not been reported yet
+        if let Err(e) = result {
+            let _ = event_tx
+                .send(AppEvent::StreamError(format!("{:#}", e)))
+                .await;
+        }
+    });
+
+    let guard = TurnGuard {
+        abort: abort_tx,
+        task: task.abort_handle(),
+        finished: false,
+    };
+    Sse::new(turn_events(session.id, event_rx, guard))
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}
+
+/// Ends a turn when its event stream is dropped before the turn finished,
+/// i.e. when the client disconnected
+struct TurnGuard {
+    abort: watch::Sender<bool>,
+    task: tokio::task::AbortHandle,
+    finished: bool,
+}
+
+impl Drop for TurnGuard {
+    fn drop(&mut self) {
+        if !self.finished {
+            let _ = self.abort.send(true);
+            self.task.abort();
+        }
+    }
+}
+
+/// SSE events of a turn, ending after `done` or the first `error`
+///
+/// Dropping the stream before then aborts the turn through `guard`.
+fn turn_events(
+    session_id: String,
+    events: mpsc::Receiver<AppEvent>,
+    guard: TurnGuard,
+) -> impl Stream<Item = Result<Event, Infallible>> {
+    let first = sse_event("session", json!({ "session_id": session_id }));
+    let rest = futures::stream::unfold(Some((events, guard)), |state| async move {
+        let (mut events, mut guard) = state?;
+        loop {
+            let event = events.recv().await?;
+            let last = matches!(
+                event,
+                AppEvent::StreamDone | AppEvent::StreamError(_) | AppEvent::AuthError { .. }
+            );
+            if let Some(event) = to_sse(event).await {
+                guard.finished = last;
+                return Some((Ok(event), (!last).then_some((events, guard))));
+            }
+        }
+    });
+    futures::stream::once(async { Ok(first) }).chain(rest)
+}
+
+/// The SSE event for a turn event, answering prompts nobody can answer
+async fn to_sse(event: AppEvent) -> Option<Event> {
+    let event = match event {
+        AppEvent::StreamDelta(text) => sse_event("delta", json!({ "text": text })),
+        AppEvent::ReasoningDelta(text) => sse_event("reasoning", json!({ "text": text })),
+        AppEvent::ToolCall(name, id) => sse_event("tool_call", json!({ "id": id, "name": name })),
+        AppEvent::ToolInput { id, args } => {
+            sse_event("tool_input", json!({ "id": id, "args": args }))
+        }
+        AppEvent::ToolOutput { id, text } => {
+            sse_event("tool_output", json!({ "id": id, "text": text }))
+        }
+        AppEvent::ToolResult {
+            id,
+            output,
+            is_error,
+        } => sse_event(
+            "tool_result",
+            json!({ "id": id, "output": output, "is_error": is_error }),
+        ),
+        AppEvent::StepFinished { tokens, cost } => {
+            sse_event("step", json!({ "tokens": tokens, "cost": cost }))
+        }
+        AppEvent::ModelFallback {
+            provider_id,
+            model_id,
+            reason,
+        } => sse_event(
+            "fallback",
+            json!({ "model": format!("{}/{}", provider_id, model_id), "reason": reason }),
+        ),
+        AppEvent::PermissionRequested(request) => {
+            let event = sse_event(
+                "permission_denied",
+                json!({ "permission": request.permission, "patterns": request.patterns }),
+            );
+            if request.calls.is_empty() {
+                crate::permission_state::send_permission_response(
+                    request.id,
+                    false,
+                    PermissionScope::Once,
+                )
+                .await;
+            } else {
+                crate::permission_state::send_batch_response(request.id, BatchDecision::DenyAll)
+                    .await;
+            }
+            event
+        }
+        AppEvent::QuestionRequested(request) => {
+            crate::question_state::send_question_response(request.id, Vec::new()).await;
+            return None;
+        }
+        AppEvent::AuthError {
+            provider_id,
+            message,
+        } => sse_event(
+            "error",
+            json!({ "message": format!("{}: {}", provider_id, message) }),
+        ),
+        AppEvent::StreamError(message) => sse_event("error", json!({ "message": message })),
+        AppEvent::StreamDone => sse_event("done", json!({})),
+        _ => return None,
+    };
+    Some(event)
+}
+
+fn sse_event(name: &str, data: serde_json::Value) -> Event {
+    Event::default().event(name).data(data.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::sync::oneshot;
+
+    /// A turn that streams a delta and then waits on the provider forever
+    fn pending_turn() -> (
+        mpsc::Receiver<AppEvent>,
+        oneshot::Receiver<()>,
+        tokio::task::JoinHandle<()>,
+    ) {
+        let (event_tx, event_rx) = mpsc::channel(10);
+        let (request_tx, request_rx) = oneshot::channel::<()>();
+        let task = tokio::spawn(async move {
+            // Dropped, closing `request_rx`, when the task is
+            let _request = request_tx;
+            let _ = event_tx
+                .send(AppEvent::StreamDelta("Hel".to_string()))
+                .await;
+            let _ = event_tx.send(AppEvent::StreamDone).await;
+            std::future::pending::<()>().await;
+        });
+        (event_rx, request_rx, task)
+    }
+
+    #[tokio::test]
+    async fn test_disconnect_aborts_turn() {
+        let (event_rx, request_rx, task) = pending_turn();
+        let (abort_tx, abort_rx) = watch::channel(false);
+        let guard = TurnGuard {
+            abort: abort_tx,
+            task: task.abort_handle(),
+            finished: false,
+        };
+        let mut events = Box::pin(turn_events("s".to_string(), event_rx, guard));
+        // The session, then the delta
+        assert!(events.next().await.is_some());
+        assert!(events.next().await.is_some());
+
+        // The client goes away mid-turn
+        drop(events);
+        assert!(*abort_rx.borrow());
+        assert!(task.await.unwrap_err().is_cancelled());
+        assert!(request_rx.await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_finished_turn_is_not_aborted() {
+        let (event_rx, _request_rx, task) = pending_turn();
+        let (abort_tx, abort_rx) = watch::channel(false);
+        let guard = TurnGuard {
+            abort: abort_tx,
+            task: task.abort_handle(),
+            finished: false,
+        };
+        let events: Vec<_> = turn_events("s".to_string(), event_rx, guard)
+            .collect()
+            .await;
+        assert_eq!(events.len(), 3);
+        assert!(!*abort_rx.borrow());
+        assert!(!task.is_finished());
+        task.abort();
+    }
+}
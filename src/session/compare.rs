@@ -0,0 +1,138 @@
+//! Comparison of two sessions.
+//!
+//! `opencode session diff` A/B compares two runs of the same task: the
+//! assistant replies are aligned by position and diffed line by line, and the
+//! files each session changed are listed side by side.
+
+use anyhow::Result;
+use similar::TextDiff;
+
+use super::message::Message;
+use super::parts::Part;
+use super::FileDiff;
+
+/// Number of unchanged lines shown around each change of a reply
+const CONTEXT_LINES: usize = 3;
+
+/// Text of each assistant reply of a session, in order
+///
+/// Replies without text, such as steps that only call tools, are left out so
+/// the text replies of two sessions line up.
+pub async fn replies(session_id: &str) -> Result<Vec<String>> {
+    let mut replies = Vec::new();
+    for message in Message::list(session_id).await? {
+        if !matches!(message, Message::Assistant(_)) {
+            continue;
+        }
+        let parts = Part::list(message.id()).await?;
+        let text = parts
+            .iter()
+            .filter_map(|part| match part {
+                Part::Text(text) => Some(text.text.trim_end()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        if !text.trim().is_empty() {
+            replies.push(text);
+        }
+    }
+    Ok(replies)
+}
+
+/// Unified diff of two replies, or `None` when they are the same
+pub fn reply_diff(old: &str, new: &str, old_label: &str, new_label: &str) -> Option<String> {
+    let old = format!("{}\n", old.trim_end());
+    let new = format!("{}\n", new.trim_end());
+    if old == new {
+        return None;
+    }
+
+    let diff = TextDiff::from_lines(&old, &new);
+    let text = diff
+        .unified_diff()
+        .context_radius(CONTEXT_LINES)
+        .header(old_label, new_label)
+        .to_string();
+    Some(text)
+}
+
+/// A file changed by either session, with its added and deleted line
+/// counts in each
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileChange {
+    pub path: String,
+    pub left: Option<(u32, u32)>,
+    pub right: Option<(u32, u32)>,
+}
+
+/// Files changed by either session, in order of first change (left first)
+pub fn file_changes(left: &[FileDiff], right: &[FileDiff]) -> Vec<FileChange> {
+    let counts = |diffs: &[FileDiff], path: &str| {
+        diffs
+            .iter()
+            .find(|d| d.path == path)
+            .map(|d| (d.additions, d.deletions))
+    };
+
+    let mut changes: Vec<FileChange> = Vec::new();
+    for diff in left.iter().chain(right) {
+        if changes.iter().any(|c| c.path == diff.path) {
+            continue;
+        }
+        changes.push(FileChange {
+            path: diff.path.clone(),
+            left: counts(left, &diff.path),
+            right: counts(right, &diff.path),
+        });
+    }
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reply_diff() {
+        assert_eq!(reply_diff("same\n", "same", "a", "b"), None);
+
+        let diff = reply_diff("one\ntwo", "one\nthree", "a", "b").unwrap();
+        assert!(diff.starts_with("--- a\n+++ b\n"));
+        assert!(diff.contains("-two\n"));
+        assert!(diff.contains("+three\n"));
+    }
+
+    #[test]
+    fn test_file_changes() {
+        let left = vec![
+            FileDiff::compute("a.rs", "1\n", "2\n"),
+            FileDiff::compute("b.rs", "", "x\n"),
+        ];
+        let right = vec![
+            FileDiff::compute("c.rs", "", "y\n"),
+            FileDiff::compute("a.rs", "1\n", "1\n2\n"),
+        ];
+
+        assert_eq!(
+            file_changes(&left, &right),
+            vec![
+                FileChange {
+                    path: "a.rs".to_string(),
+                    left: Some((1, 1)),
+                    right: Some((1, 0)),
+                },
+                FileChange {
+                    path: "b.rs".to_string(),
+                    left: Some((1, 0)),
+                    right: None,
+                },
+                FileChange {
+                    path: "c.rs".to_string(),
+                    left: None,
+                    right: Some((1, 0)),
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,154 @@
+//! File diffs for a session.
+//!
+//! The edit and write tools attach a unified diff to their result metadata.
+//! This module computes those diffs and collects them from the stored tool
+//! parts of a session, merged per file.
+
+use anyhow::Result;
+use similar::{ChangeTag, TextDiff};
+
+use super::message::Message;
+use super::parts::{Part, ToolState};
+use super::{FileDiff, SessionSummary};
+
+/// Number of unchanged lines shown around each change
+const CONTEXT_LINES: usize = 3;
+
+impl FileDiff {
+    /// Compute a unified diff between two versions of a file
+    pub fn compute(path: &str, old: &str, new: &str) -> Self {
+        let diff = TextDiff::from_lines(old, new);
+
+        let mut additions = 0;
+        let mut deletions = 0;
+        for change in diff.iter_all_changes() {
+            match change.tag() {
+                ChangeTag::Insert => additions += 1,
+                ChangeTag::Delete => deletions += 1,
+                ChangeTag::Equal => {}
+            }
+        }
+
+        let header_path = path.trim_start_matches('/');
+        let text = diff
+            .unified_diff()
+            .context_radius(CONTEXT_LINES)
+            .header(&format!("a/{}", header_path), &format!("b/{}", header_path))
+            .to_string();
+
+        Self {
+            path: path.to_string(),
+            additions,
+            deletions,
+            diff: Some(text),
+        }
+    }
+}
+
+impl SessionSummary {
+    /// Summarize a set of file diffs
+    pub fn from_diffs(diffs: Vec<FileDiff>) -> Self {
+        Self {
+            additions: diffs.iter().map(|d| d.additions).sum(),
+            deletions: diffs.iter().map(|d| d.deletions).sum(),
+            files: diffs.len() as u32,
+            diffs: Some(diffs),
+        }
+    }
+}
+
+/// Merge diffs for the same file, keeping the order of first change
+fn merge(diffs: impl IntoIterator<Item = FileDiff>) -> Vec<FileDiff> {
+    let mut merged: Vec<FileDiff> = Vec::new();
+
+    for diff in diffs {
+        match merged.iter_mut().find(|d| d.path == diff.path) {
+            Some(existing) => {
+                existing.additions += diff.additions;
+                existing.deletions += diff.deletions;
+                existing.diff = match (existing.diff.take(), diff.diff) {
+                    (Some(a), Some(b)) => Some(format!("{}{}", a, b)),
+                    (a, b) => a.or(b),
+                };
+            }
+            None => merged.push(diff),
+        }
+    }
+
+    merged
+}
+
+/// Extract the diff recorded by a completed edit/write tool part
+fn from_tool_state(state: &ToolState) -> Option<FileDiff> {
+    let ToolState::Completed(completed) = state else {
+        return None;
+    };
+    let metadata = &completed.metadata;
+    let count = |key: &str| metadata.get(key).and_then(|v| v.as_u64()).unwrap_or(0) as u32;
+
+    Some(FileDiff {
+        path: metadata.get("path")?.as_str()?.to_string(),
+        additions: count("additions"),
+        deletions: count("deletions"),
+        diff: Some(metadata.get("diff")?.as_str()?.to_string()),
+    })
+}
+
+/// List files changed in a session, merged per file
+pub async fn list(session_id: &str) -> Result<Vec<FileDiff>> {
+    let mut diffs = Vec::new();
+
+    for message in Message::list(session_id).await? {
+        for part in Part::list(message.id()).await? {
+            if let Part::Tool(tool_part) = part {
+                diffs.extend(from_tool_state(&tool_part.state));
+            }
+        }
+    }
+
+    Ok(merge(diffs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compute_counts_lines() {
+        let diff = FileDiff::compute("src/lib.rs", "a\nb\nc\n", "a\nB\nc\nd\n");
+
+        assert_eq!(diff.additions, 2);
+        assert_eq!(diff.deletions, 1);
+        let text = diff.diff.unwrap();
+        assert!(text.starts_with("--- a/src/lib.rs\n+++ b/src/lib.rs\n"));
+        assert!(text.contains("-b\n"));
+        assert!(text.contains("+B\n"));
+    }
+
+    #[test]
+    fn test_compute_new_file() {
+        let diff = FileDiff::compute("new.txt", "", "hello\n");
+
+        assert_eq!(diff.additions, 1);
+        assert_eq!(diff.deletions, 0);
+    }
+
+    #[test]
+    fn test_merge_same_file() {
+        let merged = merge(vec![
+            FileDiff::compute("a.rs", "1\n", "2\n"),
+            FileDiff::compute("b.rs", "", "x\n"),
+            FileDiff::compute("a.rs", "2\n", "2\n3\n"),
+        ]);
+
+        assert_eq!(merged.len(), 2);
+        assert_eq!(merged[0].path, "a.rs");
+        assert_eq!(merged[0].additions, 2);
+        assert_eq!(merged[0].deletions, 1);
+        assert_eq!(merged[1].path, "b.rs");
+
+        let summary = SessionSummary::from_diffs(merged);
+        assert_eq!(summary.files, 2);
+        assert_eq!(summary.additions, 3);
+    }
+}
@@ -0,0 +1,280 @@
+//! Prompt macros expanded before a prompt is sent.
+//!
+//! - `!file:<path>` inlines a file
+//! - `!sh:<command>` inlines a command's output; the command runs to the end
+//!   of the line. Off unless listed in `macros.enabled`, since it runs
+//!   whatever follows it in the prompt
+//! - `!clip` inlines the clipboard text
+//!
+//! A directive starts a line or follows whitespace. Unknown directives, and
+//! ones left out of `macros.enabled`, pass through untouched.
+
+use std::path::Path;
+use std::process::Stdio;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+
+use crate::config::Config;
+use crate::tool::Shell;
+
+/// Directives expanded when `macros.enabled` is unset
+pub const DEFAULT_MACROS: &[&str] = &["file", "clip"];
+
+/// Seconds a `!sh:` command may run when `macros.sh_timeout` is unset
+const DEFAULT_SH_TIMEOUT_SECS: u64 = 30;
+
+/// Bytes of a `!sh:` command's output inlined; the rest is cut off
+const MAX_SH_OUTPUT: usize = 64 * 1024;
+
+/// A directive found in a prompt
+#[derive(Debug, Clone, PartialEq)]
+enum Directive {
+    File(String),
+    Sh(String),
+    Clip,
+}
+
+impl Directive {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::File(_) => "file",
+            Self::Sh(_) => "sh",
+            Self::Clip => "clip",
+        }
+    }
+}
+
+/// A piece of a prompt: text kept as typed, or a directive to expand
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Text(String),
+    Directive(Directive),
+}
+
+/// Expand the enabled directives in a prompt
+///
+/// Paths and commands are relative to `cwd`. Fails if a directive can't be
+/// expanded, e.g. a missing file, so the prompt isn't sent half-expanded.
+pub async fn expand(prompt: &str, config: &Config, cwd: &Path) -> Result<String> {
+    let enabled: Vec<&str> = match config.macros.as_ref().and_then(|m| m.enabled.as_ref()) {
+        Some(enabled) => enabled.iter().map(String::as_str).collect(),
+        None => DEFAULT_MACROS.to_vec(),
+    };
+
+    let mut expanded = String::new();
+    for segment in parse(prompt, &enabled) {
+        match segment {
+            Segment::Text(text) => expanded.push_str(&text),
+            Segment::Directive(directive) => {
+                expanded.push_str(&expand_directive(&directive, config, cwd).await?)
+            }
+        }
+    }
+    Ok(expanded)
+}
+
+async fn expand_directive(directive: &Directive, config: &Config, cwd: &Path) -> Result<String> {
+    match directive {
+        Directive::File(path) => {
+            let full_path = cwd.join(path);
+            let size = tokio::fs::metadata(&full_path)
+                .await
+                .with_context(|| format!("!file:{}: failed to read the file", path))?
+                .len();
+            let max_bytes = config
+                .max_read_bytes
+                .unwrap_or(crate::tool::DEFAULT_MAX_READ_BYTES);
+            if size > max_bytes {
+                anyhow::bail!(
+                    "!file:{}: file is {} bytes, over the limit of {} bytes (max_read_bytes)",
+                    path,
+                    size,
+                    max_bytes
+                );
+            }
+            let content = tokio::fs::read_to_string(&full_path)
+                .await
+                .with_context(|| format!("!file:{}: failed to read the file", path))?;
+            Ok(format!("{}:\n```\n{}\n```", path, content.trim_end()))
+        }
+        Directive::Sh(command) => {
+            let secs = config
+                .macros
+                .as_ref()
+                .and_then(|m| m.sh_timeout)
+                .unwrap_or(DEFAULT_SH_TIMEOUT_SECS);
+            // Without stdin a command waiting for input ends instead of
+            // blocking on the terminal; dropping it on timeout kills it
+            let mut process = Shell::resolve(config.shell.as_deref()).command(command);
+            process
+                .current_dir(cwd)
+                .stdin(Stdio::null())
+                .kill_on_drop(true);
+            let output = tokio::time::timeout(Duration::from_secs(secs), process.output())
+                .await
+                .map_err(|_| anyhow::anyhow!("!sh:{}: timed out after {}s", command, secs))?
+                .with_context(|| format!("!sh:{}: failed to run the command", command))?;
+            let mut text = String::from_utf8_lossy(&output.stdout).to_string();
+            text.push_str(&String::from_utf8_lossy(&output.stderr));
+            Ok(format!(
+                "$ {}\n```\n{}\n```",
+                command,
+                cap_output(text.trim_end())
+            ))
+        }
+        Directive::Clip => arboard::Clipboard::new()
+            .and_then(|mut clipboard| clipboard.get_text())
+            .context("!clip: failed to read the clipboard"),
+    }
+}
+
+/// Output cut to `MAX_SH_OUTPUT` bytes on a char boundary, with a note
+fn cap_output(text: &str) -> String {
+    if text.len() <= MAX_SH_OUTPUT {
+        return text.to_string();
+    }
+    let mut end = MAX_SH_OUTPUT;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!(
+        "{}\n[output cut off at {} of {} bytes]",
+        &text[..end],
+        end,
+        text.len()
+    )
+}
+
+/// Split a prompt into text and the enabled directives
+fn parse(prompt: &str, enabled: &[&str]) -> Vec<Segment> {
+    let mut segments = Vec::new();
+    let mut text = String::new();
+
+    for line in prompt.split_inclusive('\n') {
+        let mut rest = line;
+        while let Some((start, end, directive)) = next_directive(rest, enabled) {
+            text.push_str(&rest[..start]);
+            if !text.is_empty() {
+                segments.push(Segment::Text(std::mem::take(&mut text)));
+            }
+            segments.push(Segment::Directive(directive));
+            rest = &rest[end..];
+        }
+        text.push_str(rest);
+    }
+    if !text.is_empty() {
+        segments.push(Segment::Text(text));
+    }
+    segments
+}
+
+/// The first enabled directive in a line, with its byte range
+fn next_directive(line: &str, enabled: &[&str]) -> Option<(usize, usize, Directive)> {
+    let mut prev = None;
+    for (start, c) in line.char_indices() {
+        let at_word_start = prev.is_none_or(char::is_whitespace);
+        prev = Some(c);
+        if c != '!' || !at_word_start {
+            continue;
+        }
+
+        let body = &line[start + 1..];
+        let word_end = body.find(char::is_whitespace).unwrap_or(body.len());
+        let (directive, len) = if let Some(path) = body[..word_end].strip_prefix("file:") {
+            (Directive::File(path.to_string()), word_end)
+        } else if let Some(command) = body.strip_prefix("sh:") {
+            let command = command.trim_end_matches(['\r', '\n']);
+            (Directive::Sh(command.trim().to_string()), 3 + command.len())
+        } else if &body[..word_end] == "clip" {
+            (Directive::Clip, word_end)
+        } else {
+            continue;
+        };
+
+        let empty = matches!(&directive, Directive::File(s) | Directive::Sh(s) if s.is_empty());
+        if !empty && enabled.contains(&directive.name()) {
+            return Some((start, start + 1 + len, directive));
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(s: &str) -> Segment {
+        Segment::Text(s.to_string())
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            parse(
+                "Review !file:src/main.rs please\n!sh:git log -1\nand !clip.",
+                &["file", "sh", "clip"]
+            ),
+            vec![
+                text("Review "),
+                Segment::Directive(Directive::File("src/main.rs".to_string())),
+                text(" please\n"),
+                Segment::Directive(Directive::Sh("git log -1".to_string())),
+                text("\nand !clip."),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_leaves_unknown_and_disabled() {
+        let prompt = "Hi! !todo x!file:a !clip\n!sh:";
+        assert_eq!(parse(prompt, &["file", "sh"]), vec![text(prompt)]);
+        assert_eq!(
+            parse("!clip", DEFAULT_MACROS),
+            vec![Segment::Directive(Directive::Clip)]
+        );
+        // Shell commands are opt-in
+        assert_eq!(parse("!sh:ls", DEFAULT_MACROS), vec![text("!sh:ls")]);
+    }
+
+    #[tokio::test]
+    async fn test_sh_has_no_stdin() {
+        let config = Config {
+            macros: Some(crate::config::MacroConfig {
+                enabled: Some(vec!["sh".to_string()]),
+                sh_timeout: Some(5),
+            }),
+            ..Default::default()
+        };
+        let cwd = std::env::current_dir().unwrap();
+        let expanded = expand("!sh:cat", &config, &cwd).await.unwrap();
+        assert_eq!(expanded, "$ cat\n```\n\n```");
+    }
+
+    #[tokio::test]
+    async fn test_file_over_max_read_bytes() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("big.txt"), "x".repeat(100)).unwrap();
+        let config = Config {
+            max_read_bytes: Some(10),
+            ..Default::default()
+        };
+        let err = expand("!file:big.txt", &config, dir.path())
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("over the limit of 10 bytes"));
+    }
+
+    #[test]
+    fn test_cap_output() {
+        assert_eq!(cap_output("short"), "short");
+        let long = "é".repeat(MAX_SH_OUTPUT);
+        let capped = cap_output(&long);
+        assert!(capped.starts_with(&"é".repeat(MAX_SH_OUTPUT / 2)));
+        assert!(capped.ends_with(&format!(
+            "[output cut off at {} of {} bytes]",
+            MAX_SH_OUTPUT,
+            long.len()
+        )));
+    }
+}
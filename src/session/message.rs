@@ -4,7 +4,6 @@
 //! and assistant messages. Part types are defined in parts.rs.
 
 use crate::bus::{self, Event};
-#[cfg(test)]
 use crate::id::{self, IdPrefix};
 use crate::storage;
 use anyhow::{Context, Result};
@@ -23,6 +22,16 @@ pub enum Message {
     Assistant(AssistantMessage),
 }
 
+/// A window of a session's messages, from `Message::list_paginated`
+#[derive(Debug, Default)]
+pub struct MessagePage {
+    /// Messages in chronological order
+    pub messages: Vec<Message>,
+    /// Messages in the window that could not be read: corrupt, or removed
+    /// since the listing
+    pub skipped: usize,
+}
+
 impl Message {
     pub fn id(&self) -> &str {
         match self {
@@ -40,22 +49,41 @@ impl Message {
 
     /// List all messages for a session
     pub async fn list(session_id: &str) -> Result<Vec<Message>> {
+        let mut messages: Vec<Message> = storage::global()
+            .list_values(&["message", session_id])
+            .await?;
+
+        // Sort by ID (chronological order)
+        messages.sort_by(|a, b| a.id().cmp(b.id()));
+
+        Ok(messages)
+    }
+
+    /// List a bounded window of messages for a session
+    ///
+    /// Returns up to `limit` messages older than `before_id` (or the newest
+    /// messages when `None`) in chronological order. Message IDs are
+    /// ascending, so the window is chosen from the key listing and only the
+    /// messages inside it are read.
+    pub async fn list_paginated(
+        session_id: &str,
+        before_id: Option<&str>,
+        limit: usize,
+    ) -> Result<MessagePage> {
         let keys = storage::global().list(&["message", session_id]).await?;
-        let mut messages = Vec::new();
+        let mut page = MessagePage::default();
 
-        for key in keys {
-            if let Some(message) = storage::global()
-                .read::<Message>(&key.iter().map(|s| s.as_str()).collect::<Vec<_>>())
+        for key in page_window(&keys, before_id, limit) {
+            match storage::global()
+                .read_listed::<Message>(&key.iter().map(|s| s.as_str()).collect::<Vec<_>>())
                 .await?
             {
-                messages.push(message);
+                Some(message) => page.messages.push(message),
+                None => page.skipped += 1,
             }
         }
 
-        // Sort by ID (chronological order)
-        messages.sort_by(|a, b| a.id().cmp(b.id()));
-
-        Ok(messages)
+        Ok(page)
     }
 
     /// Save the message
@@ -69,6 +97,130 @@ impl Message {
 
         Ok(())
     }
+
+    /// Delete a message and every later message of the session, with their parts
+    ///
+    /// Message IDs are ascending, so later messages sort after `from_id`.
+    /// Returns the number of deleted messages.
+    pub async fn truncate(session_id: &str, from_id: &str) -> Result<usize> {
+        let keys = storage::global().list(&["message", session_id]).await?;
+        let mut removed = 0;
+
+        for key in keys {
+            let Some(message_id) = key.last().filter(|id| id.as_str() >= from_id) else {
+                continue;
+            };
+            for part_key in storage::global().list(&["part", message_id]).await? {
+                storage::global()
+                    .remove(&part_key.iter().map(|s| s.as_str()).collect::<Vec<_>>())
+                    .await?;
+            }
+            storage::global()
+                .remove(&key.iter().map(|s| s.as_str()).collect::<Vec<_>>())
+                .await?;
+            removed += 1;
+        }
+
+        if removed > 0 {
+            bus::publish(MessageRemoved {}).await;
+        }
+        Ok(removed)
+    }
+
+    /// Delete every message of the session, with their parts
+    ///
+    /// The session itself is kept. Returns the number of deleted messages.
+    pub async fn clear(session_id: &str) -> Result<usize> {
+        Self::truncate(session_id, "").await
+    }
+
+    /// Save the user prompt and the assistant message that tool calls attach to
+    ///
+    /// Returns the assistant message ID.
+    pub async fn save_prompt(
+        session_id: &str,
+        prompt: &str,
+        provider_id: &str,
+        model_id: &str,
+        cwd: &str,
+    ) -> Result<String> {
+        let now = chrono::Utc::now().timestamp_millis();
+        let user_id = id::ascending(IdPrefix::Message);
+
+        Message::User(UserMessage {
+            id: user_id.clone(),
+            session_id: session_id.to_string(),
+            time: MessageTime { created: now },
+            agent: "default".to_string(),
+            model: ModelRef {
+                provider_id: provider_id.to_string(),
+                model_id: model_id.to_string(),
+            },
+            summary: None,
+            system: None,
+            tools: None,
+            variant: None,
+        })
+        .save()
+        .await?;
+
+        Part::Text(TextPart {
+            base: PartBase {
+                id: id::ascending(IdPrefix::Part),
+                session_id: session_id.to_string(),
+                message_id: user_id.clone(),
+            },
+            text: prompt.to_string(),
+            synthetic: None,
+            ignored: None,
+            time: None,
+            metadata: None,
+        })
+        .save()
+        .await?;
+
+        Self::save_reply(session_id, &user_id, provider_id, model_id, cwd).await
+    }
+
+    /// Save an empty assistant message answering the user message `parent_id`
+    ///
+    /// Replies to the same prompt are variants of that turn. Returns the
+    /// assistant message ID.
+    pub async fn save_reply(
+        session_id: &str,
+        parent_id: &str,
+        provider_id: &str,
+        model_id: &str,
+        cwd: &str,
+    ) -> Result<String> {
+        let now = chrono::Utc::now().timestamp_millis();
+        let assistant_id = id::ascending(IdPrefix::Message);
+        Message::Assistant(AssistantMessage {
+            id: assistant_id.clone(),
+            session_id: session_id.to_string(),
+            parent_id: parent_id.to_string(),
+            time: AssistantMessageTime {
+                created: now,
+                completed: None,
+            },
+            agent: "default".to_string(),
+            provider_id: provider_id.to_string(),
+            model_id: model_id.to_string(),
+            path: MessagePath {
+                cwd: cwd.to_string(),
+                root: cwd.to_string(),
+            },
+            error: None,
+            summary: None,
+            cost: 0.0,
+            tokens: TokenUsage::default(),
+            finish: None,
+        })
+        .save()
+        .await?;
+
+        Ok(assistant_id)
+    }
 }
 
 /// User message
@@ -168,6 +320,9 @@ pub enum MessageError {
     OutputLength {},
     #[serde(rename = "MessageAbortedError")]
     Aborted { message: String },
+    /// The provider refused the request or filtered the response
+    #[serde(rename = "MessageRefusedError")]
+    Refused { message: String },
 }
 
 /// Token usage statistics
@@ -185,12 +340,42 @@ pub struct CacheUsage {
     pub write: u64,
 }
 
+impl TokenUsage {
+    /// Add another usage to this one
+    pub fn add(&mut self, other: &TokenUsage) {
+        self.input += other.input;
+        self.output += other.output;
+        self.reasoning += other.reasoning;
+        self.cache.read += other.cache.read;
+        self.cache.write += other.cache.write;
+    }
+}
+
 /// Message events
 #[derive(Debug, Clone)]
 pub struct MessageUpdated {}
 
 impl Event for MessageUpdated {}
 
+/// Published when messages are deleted
+#[derive(Debug, Clone)]
+pub struct MessageRemoved {}
+
+impl Event for MessageRemoved {}
+
+/// Select the last `limit` sorted keys whose ID sorts before `before_id`
+fn page_window<'a>(
+    keys: &'a [Vec<String>],
+    before_id: Option<&str>,
+    limit: usize,
+) -> &'a [Vec<String>] {
+    let end = match before_id {
+        Some(before) => keys.partition_point(|k| k.last().is_some_and(|id| id.as_str() < before)),
+        None => keys.len(),
+    };
+    &keys[end.saturating_sub(limit)..end]
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -405,4 +590,36 @@ mod tests {
             assert_eq!(parsed.cache.read, 5);
         }
     }
+
+    mod page_window {
+        use super::*;
+
+        fn keys(ids: &[&str]) -> Vec<Vec<String>> {
+            ids.iter()
+                .map(|id| vec!["message".to_string(), "ses".to_string(), id.to_string()])
+                .collect()
+        }
+
+        fn ids(window: &[Vec<String>]) -> Vec<&str> {
+            window.iter().map(|k| k[2].as_str()).collect()
+        }
+
+        #[test]
+        fn test_latest_window() {
+            let keys = keys(&["msg_1", "msg_2", "msg_3", "msg_4"]);
+            assert_eq!(ids(page_window(&keys, None, 2)), vec!["msg_3", "msg_4"]);
+            assert_eq!(ids(page_window(&keys, None, 10)).len(), 4);
+        }
+
+        #[test]
+        fn test_window_before_id() {
+            let keys = keys(&["msg_1", "msg_2", "msg_3", "msg_4"]);
+            assert_eq!(
+                ids(page_window(&keys, Some("msg_3"), 2)),
+                vec!["msg_1", "msg_2"]
+            );
+            assert_eq!(ids(page_window(&keys, Some("msg_2"), 2)), vec!["msg_1"]);
+            assert!(page_window(&keys, Some("msg_1"), 2).is_empty());
+        }
+    }
 }
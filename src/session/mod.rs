@@ -3,12 +3,24 @@
 //! This module handles chat sessions, including creation, persistence,
 //! message management, and session lifecycle.
 
+pub mod compare;
+pub mod diff;
+pub mod macros;
 mod message;
 mod parts;
+mod share;
+mod steps;
 pub mod system;
+pub mod title;
+pub mod tool_log;
+mod trash;
 mod types;
+mod writer;
 
 pub use message::*;
+pub use steps::*;
+pub use trash::*;
+pub use writer::*;
 
 use crate::bus::{self, Event};
 use crate::id::{self, IdPrefix};
@@ -16,6 +28,7 @@ use crate::storage;
 use anyhow::{Context, Result};
 use chrono::Utc;
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 
 /// Session information
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -60,6 +73,14 @@ pub struct Session {
     /// Current model for this session (cached, source of truth is message metadata)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub model: Option<ModelRef>,
+
+    /// Text appended to the system prompt of this session's turns
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub system: Option<String>,
+
+    /// Files whose current contents are added to every turn (set with `/pin`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pinned: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -82,6 +103,12 @@ pub struct SessionTime {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ShareInfo {
     pub url: String,
+    /// Share ID on the server, used to delete it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// Secret authorizing deletion
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -129,11 +156,11 @@ impl Session {
 
         let title = options.title.unwrap_or_else(|| {
             let prefix = if options.parent_id.is_some() {
-                "Child session"
+                CHILD_TITLE_PREFIX
             } else {
-                "New session"
+                NEW_TITLE_PREFIX
             };
-            format!("{} - {}", prefix, Utc::now().to_rfc3339())
+            format!("{}{}", prefix, Utc::now().to_rfc3339())
         });
 
         let session = Session {
@@ -154,6 +181,8 @@ impl Session {
             summary: None,
             permission: options.permission,
             model: None, // Will be set when first prompt is sent
+            system: None,
+            pinned: Vec::new(),
         };
 
         // Persist to storage
@@ -232,17 +261,9 @@ impl Session {
 
     /// List all sessions for a project
     pub async fn list(project_id: &str) -> Result<Vec<Session>> {
-        let keys = storage::global().list(&["session", project_id]).await?;
-        let mut sessions = Vec::new();
-
-        for key in keys {
-            if let Some(session) = storage::global()
-                .read::<Session>(&key.iter().map(|s| s.as_str()).collect::<Vec<_>>())
-                .await?
-            {
-                sessions.push(session);
-            }
-        }
+        let mut sessions: Vec<Session> = storage::global()
+            .list_values(&["session", project_id])
+            .await?;
 
         // Sort by creation time (newest first due to descending IDs)
         sessions.sort_by(|a, b| a.id.cmp(&b.id));
@@ -250,11 +271,46 @@ impl Session {
         Ok(sessions)
     }
 
+    /// Order sessions as a parent/child tree, paired with their depth
+    ///
+    /// Each child follows its parent; siblings keep their input order. A
+    /// session whose parent no longer exists is treated as a root.
+    pub fn tree(sessions: &[Session]) -> Vec<(usize, &Session)> {
+        let ids: HashSet<&str> = sessions.iter().map(|s| s.id.as_str()).collect();
+        let mut children: HashMap<&str, Vec<&Session>> = HashMap::new();
+        let mut roots = Vec::new();
+
+        for session in sessions {
+            match session.parent_id.as_deref() {
+                Some(parent) if ids.contains(parent) => {
+                    children.entry(parent).or_default().push(session)
+                }
+                _ => roots.push(session),
+            }
+        }
+
+        let mut ordered = Vec::with_capacity(sessions.len());
+        let mut stack: Vec<(usize, &Session)> = roots.into_iter().rev().map(|s| (0, s)).collect();
+        while let Some((depth, session)) = stack.pop() {
+            ordered.push((depth, session));
+            if let Some(kids) = children.get(session.id.as_str()) {
+                stack.extend(kids.iter().rev().map(|s| (depth + 1, *s)));
+            }
+        }
+
+        ordered
+    }
+
     /// Get all messages for this session
     pub async fn messages(&self) -> Result<Vec<Message>> {
         Message::list(&self.id).await
     }
 
+    /// Count the messages in this session without loading them
+    pub async fn message_count(&self) -> Result<usize> {
+        storage::global().count(&["message", &self.id]).await
+    }
+
     /// Get the current model for this session
     /// Priority: session.model > last message model
     pub async fn get_model(&self) -> Option<ModelRef> {
@@ -289,8 +345,45 @@ impl Session {
         self.model = Some(model);
         self.update(project_id, |_| {}).await
     }
+
+    /// Set the system prompt additions for this session and persist
+    pub async fn set_system(&mut self, project_id: &str, system: Option<String>) -> Result<()> {
+        self.system = system;
+        self.update(project_id, |_| {}).await
+    }
+
+    /// Set the session's pinned files and persist
+    pub async fn set_pinned(&mut self, project_id: &str, pinned: Vec<String>) -> Result<()> {
+        self.pinned = pinned;
+        self.update(project_id, |_| {}).await
+    }
+
+    /// Whether the session still has the title it was created with
+    pub fn is_default_title(&self) -> bool {
+        [NEW_TITLE_PREFIX, CHILD_TITLE_PREFIX].iter().any(|prefix| {
+            self.title
+                .strip_prefix(prefix)
+                .is_some_and(|time| chrono::DateTime::parse_from_rfc3339(time).is_ok())
+        })
+    }
+
+    /// Set the session's permission rules and persist
+    pub async fn set_permission(
+        &mut self,
+        project_id: &str,
+        permission: Option<serde_json::Value>,
+    ) -> Result<()> {
+        self.permission = permission;
+        self.update(project_id, |_| {}).await
+    }
 }
 
+/// Start of the title of a session created without one
+const NEW_TITLE_PREFIX: &str = "New session - ";
+
+/// Start of the title of a child session created without one
+const CHILD_TITLE_PREFIX: &str = "Child session - ";
+
 /// Options for creating a new session
 #[derive(Debug, Default)]
 pub struct CreateSessionOptions {
@@ -331,5 +424,70 @@ mod tests {
 
         assert!(session.id.starts_with("ses_"));
         assert!(!session.slug.is_empty());
+        assert!(session.is_default_title());
+    }
+
+    #[test]
+    fn test_is_default_title() {
+        let mut session = session_with_parent("a", None);
+        session.title = "Child session - 2025-01-02T03:04:05.678+00:00".to_string();
+        assert!(session.is_default_title());
+        session.title = "New session - ideas".to_string();
+        assert!(!session.is_default_title());
+        session.title = "Fork of New session - 2025-01-02T03:04:05+00:00".to_string();
+        assert!(!session.is_default_title());
+    }
+
+    fn session_with_parent(id: &str, parent_id: Option<&str>) -> Session {
+        Session {
+            id: id.to_string(),
+            slug: id.to_string(),
+            project_id: "default".to_string(),
+            directory: ".".to_string(),
+            parent_id: parent_id.map(String::from),
+            title: id.to_string(),
+            version: "test".to_string(),
+            time: SessionTime {
+                created: 0,
+                updated: 0,
+                compacting: None,
+                archived: None,
+            },
+            share: None,
+            summary: None,
+            permission: None,
+            model: None,
+            system: None,
+            pinned: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_tree_orders_children_under_parents() {
+        let sessions = vec![
+            session_with_parent("a", None),
+            session_with_parent("b", None),
+            session_with_parent("a1", Some("a")),
+            session_with_parent("a1x", Some("a1")),
+            session_with_parent("a2", Some("a")),
+            session_with_parent("orphan", Some("deleted")),
+        ];
+
+        let tree: Vec<(usize, &str)> = Session::tree(&sessions)
+            .into_iter()
+            .map(|(depth, s)| (depth, s.id.as_str()))
+            .collect();
+
+        assert_eq!(
+            tree,
+            vec![
+                (0, "a"),
+                (1, "a1"),
+                (2, "a1x"),
+                (1, "a2"),
+                (0, "b"),
+                (0, "orphan"),
+            ]
+        );
     }
 }
@@ -72,22 +72,30 @@ impl Part {
 
     /// List all parts for a message
     pub async fn list(message_id: &str) -> Result<Vec<Part>> {
+        Ok(Self::list_counting_skipped(message_id).await?.0)
+    }
+
+    /// List all parts for a message, with the number of parts that could not
+    /// be read: corrupt, or removed since the listing
+    pub async fn list_counting_skipped(message_id: &str) -> Result<(Vec<Part>, usize)> {
         let keys = storage::global().list(&["part", message_id]).await?;
         let mut parts = Vec::new();
+        let mut skipped = 0;
 
         for key in keys {
-            if let Some(part) = storage::global()
-                .read::<Part>(&key.iter().map(|s| s.as_str()).collect::<Vec<_>>())
+            match storage::global()
+                .read_listed::<Part>(&key.iter().map(|s| s.as_str()).collect::<Vec<_>>())
                 .await?
             {
-                parts.push(part);
+                Some(part) => parts.push(part),
+                None => skipped += 1,
             }
         }
 
         // Sort by ID (chronological order)
         parts.sort_by(|a, b| a.id().cmp(b.id()));
 
-        Ok(parts)
+        Ok((parts, skipped))
     }
 
     /// Save the part
@@ -252,8 +260,6 @@ pub struct ToolTimeComplete {
 pub struct StepStartPart {
     #[serde(flatten)]
     pub base: PartBase,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub snapshot: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -263,8 +269,6 @@ pub struct StepFinishPart {
     pub reason: String,
     pub cost: f64,
     pub tokens: TokenUsage,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub snapshot: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -0,0 +1,185 @@
+//! Session sharing.
+//!
+//! `/share` uploads a copy of a session to a share server and stores the
+//! returned URL on the session; `/unshare` deletes it again. Secrets are
+//! redacted from the upload (see [`crate::redact`]). The protocol is small so
+//! it can be self-hosted:
+//!
+//! - `POST {url}/api/share` with the payload, answering with
+//!   `{"id": ..., "url": ..., "secret": ...}`
+//! - `DELETE {url}/api/share/{id}` with `{"secret": ...}`
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::time::Duration;
+
+use super::{Message, Part, Session, ShareInfo};
+use crate::config::{Config, ShareFormat, ShareMode};
+use crate::redact::Redactor;
+
+#[derive(Debug, Deserialize)]
+struct ShareResponse {
+    id: String,
+    url: String,
+    #[serde(default)]
+    secret: Option<String>,
+}
+
+impl Session {
+    /// Upload this session to the configured share server
+    ///
+    /// Returns the share info, which the caller stores on the session.
+    pub async fn share(&self, config: &Config) -> Result<ShareInfo> {
+        let base_url = share_url(config)?;
+        let format = config
+            .share_server
+            .as_ref()
+            .and_then(|s| s.format.clone())
+            .unwrap_or_default();
+
+        let mut payload = self.share_payload(&format).await?;
+        let (redactor, warnings) = Redactor::from_config(config);
+        for warning in warnings {
+            tracing::warn!("{}", warning);
+        }
+        redactor.redact_value(&mut payload);
+
+        let response = http_client()?
+            .post(format!("{}/api/share", base_url))
+            .json(&payload)
+            .send()
+            .await
+            .context("Failed to reach share server")?;
+        if !response.status().is_success() {
+            anyhow::bail!("Share server returned HTTP {}", response.status());
+        }
+
+        let shared: ShareResponse = response
+            .json()
+            .await
+            .context("Invalid response from share server")?;
+        Ok(ShareInfo {
+            url: shared.url,
+            id: Some(shared.id),
+            secret: shared.secret,
+        })
+    }
+
+    /// Delete this session from the share server
+    pub async fn unshare(&self, config: &Config) -> Result<()> {
+        let share = self.share.as_ref().context("Session is not shared")?;
+        let id = share
+            .id
+            .as_deref()
+            .context("Share was not created by this client")?;
+        let base_url = share_url(config)?;
+
+        let response = http_client()?
+            .delete(format!("{}/api/share/{}", base_url, id))
+            .json(&json!({ "secret": share.secret }))
+            .send()
+            .await
+            .context("Failed to reach share server")?;
+        // Already gone on the server is as good as deleted
+        if !response.status().is_success() && response.status() != reqwest::StatusCode::NOT_FOUND {
+            anyhow::bail!("Share server returned HTTP {}", response.status());
+        }
+
+        Ok(())
+    }
+
+    /// Build the upload payload for this session, before redaction
+    async fn share_payload(&self, format: &ShareFormat) -> Result<Value> {
+        let mut messages = Vec::new();
+        for message in Message::list(&self.id).await? {
+            let parts = Part::list(message.id()).await?;
+            messages.push((message, parts));
+        }
+
+        let session = json!({
+            "id": self.id,
+            "title": self.title,
+            "created": self.time.created,
+            "updated": self.time.updated,
+        });
+        let payload = match format {
+            ShareFormat::Json => json!({
+                "format": "json",
+                "session": session,
+                "messages": messages
+                    .iter()
+                    .map(|(info, parts)| json!({ "info": info, "parts": parts }))
+                    .collect::<Vec<_>>(),
+            }),
+            ShareFormat::Markdown => json!({
+                "format": "markdown",
+                "session": session,
+                "markdown": render_markdown(&self.title, &messages),
+            }),
+        };
+        Ok(payload)
+    }
+}
+
+/// Base URL of the share server, checking that sharing is enabled
+fn share_url(config: &Config) -> Result<String> {
+    if matches!(config.share, Some(ShareMode::Disabled)) {
+        anyhow::bail!("Sharing is disabled in the config (\"share\": \"disabled\")");
+    }
+    config
+        .share_server
+        .as_ref()
+        .and_then(|s| s.url.as_deref())
+        .filter(|url| !url.is_empty())
+        .map(|url| url.trim_end_matches('/').to_string())
+        .context("No share server configured; set \"share_server.url\" in the config")
+}
+
+fn http_client() -> Result<reqwest::Client> {
+    Ok(reqwest::Client::builder()
+        .timeout(Duration::from_secs(30))
+        .user_agent("opencode-rs/0.1.0")
+        .build()?)
+}
+
+/// Render a session as a markdown transcript of text and tool calls
+fn render_markdown(title: &str, messages: &[(Message, Vec<Part>)]) -> String {
+    let mut output = format!("# {}\n\n", title);
+    for (message, parts) in messages {
+        let role = match message {
+            Message::User(_) => "User",
+            Message::Assistant(_) => "Assistant",
+        };
+        output.push_str(&format!("## {}\n\n", role));
+        for part in parts {
+            match part {
+                Part::Text(text) => output.push_str(&format!("{}\n\n", text.text.trim_end())),
+                Part::Tool(tool) => output.push_str(&format!("*Tool: {}*\n\n", tool.tool)),
+                _ => {}
+            }
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_share_requires_configured_server() {
+        let config = Config::default();
+        assert!(share_url(&config).is_err());
+
+        let config = Config {
+            share: Some(ShareMode::Manual),
+            share_server: Some(crate::config::ShareServerConfig {
+                url: Some("https://share.example.com/".to_string()),
+                format: None,
+            }),
+            ..Default::default()
+        };
+        assert_eq!(share_url(&config).unwrap(), "https://share.example.com");
+    }
+}
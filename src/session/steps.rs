@@ -0,0 +1,124 @@
+//! Step boundaries of an agentic turn.
+//!
+//! Each provider request in a turn is a step. A `StepStartPart` is saved
+//! before the request and a `StepFinishPart` with the step's finish reason,
+//! tokens and cost after it; the finish is also added to the assistant
+//! message totals, so cost accrues across every step of the turn.
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+
+use super::message::{AssistantMessage, Message, MessageError, TokenUsage};
+use super::parts::{Part, PartBase, StepFinishPart, StepStartPart};
+use crate::id::{self, IdPrefix};
+use crate::storage;
+
+/// Save the start of a step
+pub async fn record_step_start(session_id: &str, message_id: &str) -> Result<()> {
+    Part::StepStart(StepStartPart {
+        base: part_base(session_id, message_id),
+    })
+    .save()
+    .await
+}
+
+/// Save the finish of a step and add its usage to the assistant message
+pub async fn record_step_finish(
+    session_id: &str,
+    message_id: &str,
+    reason: &str,
+    tokens: TokenUsage,
+    cost: f64,
+) -> Result<()> {
+    let finish = StepFinishPart {
+        base: part_base(session_id, message_id),
+        reason: reason.to_string(),
+        cost,
+        tokens,
+    };
+    Part::StepFinish(finish.clone()).save().await?;
+
+    let message = storage::global()
+        .read::<Message>(&["message", session_id, message_id])
+        .await?
+        .context("Assistant message not found")?;
+    let Message::Assistant(mut assistant) = message else {
+        anyhow::bail!("Message {} is not an assistant message", message_id);
+    };
+    apply_step(&mut assistant, &finish, Utc::now().timestamp_millis());
+    Message::Assistant(assistant).save().await
+}
+
+/// Save an error on the assistant message, e.g. a refusal by the provider
+pub async fn record_message_error(
+    session_id: &str,
+    message_id: &str,
+    error: MessageError,
+) -> Result<()> {
+    let message = storage::global()
+        .read::<Message>(&["message", session_id, message_id])
+        .await?
+        .context("Assistant message not found")?;
+    let Message::Assistant(mut assistant) = message else {
+        anyhow::bail!("Message {} is not an assistant message", message_id);
+    };
+    assistant.error = Some(error);
+    Message::Assistant(assistant).save().await
+}
+
+fn part_base(session_id: &str, message_id: &str) -> PartBase {
+    PartBase {
+        id: id::ascending(IdPrefix::Part),
+        session_id: session_id.to_string(),
+        message_id: message_id.to_string(),
+    }
+}
+
+/// Add a finished step to the message totals
+fn apply_step(assistant: &mut AssistantMessage, step: &StepFinishPart, now: i64) {
+    assistant.cost += step.cost;
+    assistant.tokens.add(&step.tokens);
+    assistant.finish = Some(step.reason.clone());
+    assistant.time.completed = Some(now);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_steps_accumulate_on_message() {
+        let mut assistant: AssistantMessage = serde_json::from_value(serde_json::json!({
+            "id": "msg_2",
+            "session_id": "ses_1",
+            "parent_id": "msg_1",
+            "time": {"created": 0},
+            "agent": "default",
+            "provider_id": "anthropic",
+            "model_id": "claude",
+            "path": {"cwd": ".", "root": "."},
+            "cost": 0.0,
+            "tokens": {"input": 0, "output": 0, "reasoning": 0, "cache": {"read": 0, "write": 0}}
+        }))
+        .unwrap();
+
+        let step = |reason: &str, input, output, cost| StepFinishPart {
+            base: part_base("ses_1", "msg_2"),
+            reason: reason.to_string(),
+            cost,
+            tokens: TokenUsage {
+                input,
+                output,
+                ..Default::default()
+            },
+        };
+        apply_step(&mut assistant, &step("tool_use", 100, 20, 0.5), 1);
+        apply_step(&mut assistant, &step("end_turn", 150, 30, 0.25), 2);
+
+        assert_eq!(assistant.tokens.input, 250);
+        assert_eq!(assistant.tokens.output, 50);
+        assert_eq!(assistant.cost, 0.75);
+        assert_eq!(assistant.finish.as_deref(), Some("end_turn"));
+        assert_eq!(assistant.time.completed, Some(2));
+    }
+}
@@ -3,7 +3,24 @@
 //! This module generates the system prompt that provides context to the LLM
 //! about the environment, available tools, and instructions.
 
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use anyhow::{Context, Result};
+
+use crate::config::{AgentConfig, Config};
+
+/// Largest part of a pinned file quoted in the system prompt
+const MAX_PINNED_BYTES: usize = 64 * 1024;
+
+/// Set by `--system-file`
+static FILE_OVERRIDE: OnceLock<PathBuf> = OnceLock::new();
+
+/// Load the system prompt from `path` for the rest of the process, in place
+/// of the configured `system_file`
+pub fn set_file_override(path: PathBuf) {
+    let _ = FILE_OVERRIDE.set(path);
+}
 
 /// Generate the environment information section of the system prompt.
 ///
@@ -54,6 +71,143 @@ pub fn generate(cwd: &str, _provider_id: &str, _model_id: &str) -> String {
     environment(cwd)
 }
 
+/// The system prompt for a turn: the contents of `--system-file` or the
+/// configured `system_file` with variables interpolated, or the generated one
+///
+/// A relative `system_file` is resolved against `cwd`. A file that cannot be
+/// read is an error rather than a silent fallback.
+pub fn load(config: &Config, cwd: &str, provider_id: &str, model_id: &str) -> Result<String> {
+    let path = match (FILE_OVERRIDE.get(), &config.system_file) {
+        (Some(path), _) => path.clone(),
+        (None, Some(file)) => Path::new(cwd).join(file),
+        (None, None) => return Ok(generate(cwd, provider_id, model_id)),
+    };
+    let template = read_prompt_file(&path)?;
+    Ok(interpolate(&template, cwd, provider_id, model_id))
+}
+
+/// An agent's prompt, from its `system_file` or `prompt`, with variables
+/// interpolated
+pub fn agent_prompt(
+    agent: &AgentConfig,
+    cwd: &str,
+    provider_id: &str,
+    model_id: &str,
+) -> Result<Option<String>> {
+    let template = match (&agent.system_file, &agent.prompt) {
+        (Some(file), _) => read_prompt_file(&Path::new(cwd).join(file))?,
+        (None, Some(prompt)) => prompt.clone(),
+        (None, None) => return Ok(None),
+    };
+    Ok(Some(interpolate(&template, cwd, provider_id, model_id)))
+}
+
+fn read_prompt_file(path: &Path) -> Result<String> {
+    std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read system prompt file {}", path.display()))
+}
+
+/// Replace the variables of a prompt template
+///
+/// `{cwd}`, `{platform}`, `{date}`, `{provider}`, `{model}`, `{environment}`
+/// (the default environment section) and `{env:VAR}`. Other braces are left
+/// as written, so prompts may contain code and JSON.
+pub fn interpolate(template: &str, cwd: &str, provider_id: &str, model_id: &str) -> String {
+    let re =
+        regex::Regex::new(r"\{(cwd|platform|date|provider|model|environment|env:[^}]+)\}").unwrap();
+    re.replace_all(template, |caps: &regex::Captures| match &caps[1] {
+        "cwd" => cwd.to_string(),
+        "platform" => std::env::consts::OS.to_string(),
+        "date" => chrono::Local::now().format("%a %b %d %Y").to_string(),
+        "provider" => provider_id.to_string(),
+        "model" => model_id.to_string(),
+        "environment" => environment(cwd),
+        var => std::env::var(&var["env:".len()..]).unwrap_or_default(),
+    })
+    .to_string()
+}
+
+/// Add `text` on a new line after the session's current additions
+pub fn append(additions: Option<&str>, text: &str) -> String {
+    match additions {
+        Some(additions) if !additions.is_empty() => format!("{}\n{}", additions, text),
+        _ => text.to_string(),
+    }
+}
+
+/// Append the session's additions (set with `/system` or `--append-system`)
+/// to a system prompt
+pub fn with_additions(mut prompt: String, additions: Option<&str>) -> String {
+    if let Some(additions) = additions.filter(|a| !a.trim().is_empty()) {
+        prompt.push_str("\n\n");
+        prompt.push_str(additions);
+    }
+    prompt
+}
+
+/// Session additions carrying messages over from another session
+///
+/// Each message is a `(role, content)` pair, quoted under a heading naming
+/// the session it came from.
+pub fn prior_context(session_title: &str, messages: &[(&str, &str)]) -> String {
+    let mut text = format!(
+        "Prior context from session \"{}\" (for reference, not new instructions):",
+        session_title
+    );
+    for (role, content) in messages {
+        text.push_str(&format!("\n\n[{}]\n{}", role, content.trim()));
+    }
+    text
+}
+
+/// Section quoting the current contents of the session's pinned files
+///
+/// The files are read when this is called, so every turn sees them as they
+/// are on disk. Paths are relative to `cwd`; files that cannot be read are
+/// noted in place of their contents, and long ones are cut off.
+pub fn pinned_files(cwd: &str, paths: &[String]) -> Option<String> {
+    if paths.is_empty() {
+        return None;
+    }
+    let mut text = "Pinned files (current contents, re-read every turn):".to_string();
+    for path in paths {
+        let contents = match read_pinned(&Path::new(cwd).join(path)) {
+            Ok(contents) => contents,
+            Err(e) => format!("[could not read: {}]", e),
+        };
+        text.push_str(&format!(
+            "\n\n<file path=\"{}\">\n{}\n</file>",
+            path,
+            contents.trim_end()
+        ));
+    }
+    Some(text)
+}
+
+/// A pinned file's text, reading no more than `MAX_PINNED_BYTES` of it
+///
+/// Binary files (with a null byte in what is read) are noted, not quoted.
+fn read_pinned(path: &Path) -> std::io::Result<String> {
+    use std::io::Read;
+
+    let size = std::fs::metadata(path)?.len();
+    let mut bytes = Vec::new();
+    std::fs::File::open(path)?
+        .take(MAX_PINNED_BYTES as u64)
+        .read_to_end(&mut bytes)?;
+    if bytes.contains(&0) {
+        return Ok(format!("[binary file, {} bytes]", size));
+    }
+    let mut text = String::from_utf8_lossy(&bytes).into_owned();
+    if size > MAX_PINNED_BYTES as u64 {
+        text.push_str(&format!(
+            "\n[cut off at {} of {} bytes]",
+            MAX_PINNED_BYTES, size
+        ));
+    }
+    Ok(text)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -72,4 +226,94 @@ mod tests {
         assert!(prompt.contains("<env>"));
         assert!(prompt.contains("</env>"));
     }
+
+    #[test]
+    fn test_interpolate() {
+        let template = "Work in {cwd} with {provider}/{model}. Keep {braces} and {\"json\": 1}.";
+        assert_eq!(
+            interpolate(template, "/repo", "anthropic", "claude-sonnet-4"),
+            "Work in /repo with anthropic/claude-sonnet-4. Keep {braces} and {\"json\": 1}."
+        );
+        assert!(interpolate("{environment}", "/repo", "", "").contains("<env>"));
+        assert_eq!(
+            interpolate("[{env:OPENCODE_SYSTEM_TEST_UNSET}]", "", "", ""),
+            "[]"
+        );
+    }
+
+    #[test]
+    fn test_load_system_file() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("system.md"), "You review {model} output.").unwrap();
+        let cwd = dir.path().to_str().unwrap();
+
+        let config: Config =
+            serde_json::from_value(serde_json::json!({"system_file": "system.md"})).unwrap();
+        assert_eq!(
+            load(&config, cwd, "openai", "gpt-4o").unwrap(),
+            "You review gpt-4o output."
+        );
+        assert!(load(&Config::default(), cwd, "openai", "gpt-4o")
+            .unwrap()
+            .contains("<env>"));
+
+        let missing: Config =
+            serde_json::from_value(serde_json::json!({"system_file": "missing.md"})).unwrap();
+        assert!(load(&missing, cwd, "openai", "gpt-4o").is_err());
+
+        let agent: AgentConfig = serde_json::from_value(serde_json::json!({
+            "prompt": "Ignored",
+            "system_file": "system.md"
+        }))
+        .unwrap();
+        assert_eq!(
+            agent_prompt(&agent, cwd, "openai", "o3")
+                .unwrap()
+                .as_deref(),
+            Some("You review o3 output.")
+        );
+    }
+
+    #[test]
+    fn test_additions_are_appended() {
+        let additions = append(None, "Always respond in French");
+        let additions = append(Some(&additions), "Be brief");
+        assert_eq!(additions, "Always respond in French\nBe brief");
+
+        assert_eq!(
+            with_additions("<env></env>".to_string(), Some(&additions)),
+            "<env></env>\n\nAlways respond in French\nBe brief"
+        );
+        assert_eq!(with_additions("base".to_string(), None), "base");
+    }
+
+    #[test]
+    fn test_pinned_files() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("lib.rs"), "pub fn a() {}\n").unwrap();
+        std::fs::write(dir.path().join("logo.png"), b"\x89PNG\0\0").unwrap();
+        let cwd = dir.path().to_str().unwrap();
+
+        assert_eq!(pinned_files(cwd, &[]), None);
+        let paths = ["lib.rs", "gone.rs", "logo.png"].map(String::from);
+        let text = pinned_files(cwd, &paths).unwrap();
+        assert!(text.starts_with("Pinned files"));
+        assert!(text.contains("<file path=\"lib.rs\">\npub fn a() {}\n</file>"));
+        assert!(text.contains("<file path=\"gone.rs\">\n[could not read: "));
+        assert!(text.contains("<file path=\"logo.png\">\n[binary file, 6 bytes]\n</file>"));
+
+        // Changes on disk show up the next time
+        std::fs::write(dir.path().join("lib.rs"), "pub fn b() {}").unwrap();
+        let text = pinned_files(cwd, &["lib.rs".to_string()]).unwrap();
+        assert!(text.contains("pub fn b() {}"));
+    }
+
+    #[test]
+    fn test_prior_context() {
+        let text = prior_context("Fix login", &[("user", "Why?\n"), ("assistant", "Because")]);
+        assert_eq!(
+            text,
+            "Prior context from session \"Fix login\" (for reference, not new instructions):\n\n[user]\nWhy?\n\n[assistant]\nBecause"
+        );
+    }
 }
@@ -0,0 +1,170 @@
+//! Generated session titles.
+//!
+//! New sessions are named after their creation time. After the first turn of
+//! such a session, a cheap model is asked for a short title summing up the
+//! first prompt and reply, which replaces the placeholder.
+
+use crate::provider::{Model, ModelStatus, Provider};
+
+/// Instructions for the title request
+pub const SYSTEM_PROMPT: &str = "You name chat sessions. Reply with a title of at most six \
+words that says what the user wants, in the language of their message. Reply with the title \
+only: no quotes, no trailing punctuation, no explanation.";
+
+/// Max output tokens of the title request
+pub const MAX_TOKENS: u64 = 64;
+
+/// Longest title kept, in characters
+const MAX_TITLE_CHARS: usize = 80;
+
+/// Characters of the prompt and of the reply sent for titling
+const EXCERPT_CHARS: usize = 2000;
+
+/// The message asking for a title of a first exchange
+pub fn request(prompt: &str, reply: &str) -> String {
+    format!(
+        "User:\n{}\n\nAssistant:\n{}",
+        excerpt(prompt),
+        excerpt(reply)
+    )
+}
+
+fn excerpt(text: &str) -> &str {
+    match text.char_indices().nth(EXCERPT_CHARS) {
+        Some((end, _)) => &text[..end],
+        None => text,
+    }
+}
+
+/// A model's reply cut down to a title, or `None` if nothing is left
+///
+/// Models tend to add quotes, a `Title:` label or a final period despite
+/// being asked not to.
+pub fn clean(reply: &str) -> Option<String> {
+    let line = reply.lines().map(str::trim).find(|line| !line.is_empty())?;
+    let line = line.trim_start_matches('#').trim_start();
+    let line = ["Title:", "title:"]
+        .iter()
+        .find_map(|label| line.strip_prefix(label))
+        .unwrap_or(line);
+    let title = line
+        .trim()
+        .trim_matches(['"', '\'', '`', '*'])
+        .trim_end_matches('.')
+        .trim();
+    if title.is_empty() {
+        return None;
+    }
+    Some(match title.char_indices().nth(MAX_TITLE_CHARS) {
+        Some((end, _)) => format!("{}…", title[..end].trim_end()),
+        None => title.to_string(),
+    })
+}
+
+/// The cheapest model of a provider that is not deprecated
+///
+/// Prices are per token of input plus output. Models without a price are
+/// skipped, since a zero price usually means it is unknown; `None` if no
+/// model has one. `current` wins ties.
+pub fn cheapest_model<'a>(provider: &'a Provider, current: &str) -> Option<&'a str> {
+    let price = |model: &Model| model.cost.input + model.cost.output;
+    provider
+        .models
+        .values()
+        .filter(|model| !matches!(model.status, ModelStatus::Deprecated) && price(model) > 0.0)
+        .min_by(|a, b| {
+            price(a)
+                .total_cmp(&price(b))
+                .then_with(|| (a.id != current).cmp(&(b.id != current)))
+                .then_with(|| a.id.cmp(&b.id))
+        })
+        .map(|model| model.id.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::{ModelApi, ModelCapabilities, ModelCost, ModelLimit, ProviderSource};
+    use std::collections::HashMap;
+
+    fn model(id: &str, input: f64, output: f64) -> Model {
+        Model {
+            id: id.to_string(),
+            provider_id: "test".to_string(),
+            name: id.to_string(),
+            family: None,
+            api: ModelApi {
+                id: id.to_string(),
+                url: None,
+                npm: None,
+            },
+            capabilities: ModelCapabilities::default(),
+            cost: ModelCost {
+                input,
+                output,
+                ..Default::default()
+            },
+            limit: ModelLimit {
+                context: 200_000,
+                input: None,
+                output: 8192,
+            },
+            status: ModelStatus::Active,
+            options: HashMap::new(),
+            headers: HashMap::new(),
+            release_date: None,
+            variants: HashMap::new(),
+        }
+    }
+
+    fn provider(models: Vec<Model>) -> Provider {
+        Provider {
+            id: "test".to_string(),
+            name: "Test".to_string(),
+            source: ProviderSource::Config,
+            env: Vec::new(),
+            key: None,
+            options: HashMap::new(),
+            insecure_skip_verify: false,
+            models: models.into_iter().map(|m| (m.id.clone(), m)).collect(),
+        }
+    }
+
+    #[test]
+    fn test_cheapest_model_skips_unpriced() {
+        let priced = provider(vec![
+            model("big", 15.0, 75.0),
+            model("small", 0.8, 4.0),
+            model("unknown", 0.0, 0.0),
+        ]);
+        assert_eq!(cheapest_model(&priced, "big"), Some("small"));
+
+        let unpriced = provider(vec![model("a", 0.0, 0.0), model("b", 0.0, 0.0)]);
+        assert_eq!(cheapest_model(&unpriced, "a"), None);
+    }
+
+    #[test]
+    fn test_clean() {
+        assert_eq!(
+            clean("\"Fix the login redirect.\"").as_deref(),
+            Some("Fix the login redirect")
+        );
+        assert_eq!(
+            clean("\n# Title: Rust lifetimes explained\nMore text").as_deref(),
+            Some("Rust lifetimes explained")
+        );
+        assert_eq!(clean("  \n\"\"\n"), None);
+
+        let long = clean(&"word ".repeat(40)).unwrap();
+        assert_eq!(long.chars().count(), MAX_TITLE_CHARS);
+        assert!(long.ends_with("word…"));
+    }
+
+    #[test]
+    fn test_request_excerpts() {
+        let long = "x".repeat(EXCERPT_CHARS + 10);
+        let text = request(&long, "ok");
+        assert!(text.starts_with(&format!("User:\n{}\n\n", "x".repeat(EXCERPT_CHARS))));
+        assert!(text.ends_with("Assistant:\nok"));
+    }
+}
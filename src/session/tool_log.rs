@@ -0,0 +1,342 @@
+//! Tool execution log.
+//!
+//! This module derives an audit view of tool calls from the `ToolPart`s stored
+//! for a session, so users can see what the agent actually ran.
+
+use anyhow::Result;
+use serde::Serialize;
+use serde_json::Value;
+
+use super::message::Message;
+use super::parts::{Part, ToolPart, ToolState};
+use crate::slash_command::parser::sh_quote;
+
+/// Input keys that carry the path of a file the tool touched
+const FILE_KEYS: [&str; 2] = ["filePath", "file_path"];
+
+/// A single tool execution derived from a stored tool part
+#[derive(Debug, Clone, Serialize)]
+pub struct ToolExecution {
+    pub part_id: String,
+    pub message_id: String,
+    pub call_id: String,
+    pub tool: String,
+    /// pending, running, completed or error
+    pub status: String,
+    /// Start timestamp (milliseconds), if the call has started
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub started: Option<i64>,
+    /// Duration in milliseconds, if the call has finished
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<i64>,
+    pub input: Value,
+    /// Exit code reported by the tool (e.g. bash)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub files: Vec<String>,
+}
+
+impl ToolExecution {
+    /// Build a log entry from a tool part
+    pub fn from_part(part: &ToolPart) -> Self {
+        let mut entry = Self {
+            part_id: part.base.id.clone(),
+            message_id: part.base.message_id.clone(),
+            call_id: part.call_id.clone(),
+            tool: part.tool.clone(),
+            status: String::new(),
+            started: None,
+            duration_ms: None,
+            input: Value::Null,
+            exit_code: None,
+            title: None,
+            error: None,
+            files: Vec::new(),
+        };
+
+        match &part.state {
+            ToolState::Pending(s) => {
+                entry.status = "pending".to_string();
+                entry.input = s.input.clone();
+            }
+            ToolState::Running(s) => {
+                entry.status = "running".to_string();
+                entry.input = s.input.clone();
+                entry.started = Some(s.time.start);
+                entry.title = s.title.clone();
+            }
+            ToolState::Completed(s) => {
+                entry.status = "completed".to_string();
+                entry.input = s.input.clone();
+                entry.started = Some(s.time.start);
+                entry.duration_ms = Some(s.time.end - s.time.start);
+                entry.title = Some(s.title.clone());
+                entry.exit_code = s.metadata.get("exitCode").and_then(|v| v.as_i64());
+            }
+            ToolState::Error(s) => {
+                entry.status = "error".to_string();
+                entry.input = s.input.clone();
+                entry.started = Some(s.time.start);
+                entry.duration_ms = Some(s.time.end - s.time.start);
+                entry.error = Some(s.error.clone());
+            }
+        }
+
+        entry.files = collect_files(&entry.input);
+        entry
+    }
+}
+
+/// Collect file paths referenced by a tool's input
+fn collect_files(input: &Value) -> Vec<String> {
+    let mut files: Vec<String> = FILE_KEYS
+        .iter()
+        .filter_map(|key| input.get(key).and_then(|v| v.as_str()))
+        .map(String::from)
+        .collect();
+    files.dedup();
+    files
+}
+
+/// Render tool executions as a shell script that repeats what the agent ran
+///
+/// `bash` calls become commands, run in a subshell when they set a working
+/// directory. Other calls are listed as comments, with the files they touched
+/// or else their arguments. Every line of a comment starts with `#`, so a
+/// title or path with a line break can't inject a command.
+pub fn to_script(session_id: &str, executions: &[ToolExecution]) -> String {
+    let mut script = format!(
+        "#!/bin/sh\n# Tool calls of session {}, in order. Review before running.\n",
+        session_id
+    );
+
+    for exec in executions {
+        let mut heading = exec.tool.clone();
+        if exec.status != "completed" {
+            heading.push_str(&format!(" ({})", exec.status));
+        }
+        if let Some(code) = exec.exit_code.filter(|code| *code != 0) {
+            heading.push_str(&format!(" (exit {})", code));
+        }
+
+        let command = exec.input.get("command").and_then(|v| v.as_str());
+        match command.filter(|_| exec.tool == "bash") {
+            Some(command) => {
+                if let Some(title) = &exec.title {
+                    heading.push_str(&format!(": {}", title));
+                }
+                script.push('\n');
+                script.push_str(&comment(&heading));
+                match exec.input.get("workdir").and_then(|v| v.as_str()) {
+                    Some(workdir) => script.push_str(&format!(
+                        "(\ncd {} || exit 1\n{}\n)\n",
+                        sh_quote(workdir),
+                        command.trim_end()
+                    )),
+                    None => script.push_str(&format!("{}\n", command.trim_end())),
+                }
+            }
+            None => {
+                let detail = if exec.files.is_empty() {
+                    exec.input.to_string()
+                } else {
+                    exec.files.join(" ")
+                };
+                script.push('\n');
+                script.push_str(&comment(&format!("{} {}", heading, detail)));
+            }
+        }
+    }
+
+    script
+}
+
+/// Render text as shell comment lines, so line breaks can't end the comment
+fn comment(text: &str) -> String {
+    text.split(['\r', '\n'])
+        .filter(|line| !line.is_empty())
+        .map(|line| format!("# {}\n", line))
+        .collect()
+}
+
+/// List tool executions for a session in chronological order
+///
+/// When `filter` is set, only calls to that tool are returned.
+pub async fn list(session_id: &str, filter: Option<&str>) -> Result<Vec<ToolExecution>> {
+    let mut executions = Vec::new();
+
+    for message in Message::list(session_id).await? {
+        for part in Part::list(message.id()).await? {
+            let Part::Tool(tool_part) = part else {
+                continue;
+            };
+            if filter.is_some_and(|f| f != tool_part.tool) {
+                continue;
+            }
+            executions.push(ToolExecution::from_part(&tool_part));
+        }
+    }
+
+    Ok(executions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session::parts::{
+        PartBase, ToolStateCompleted, ToolStateError, ToolStatePending, ToolTimeComplete,
+    };
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    fn tool_part(tool: &str, state: ToolState) -> ToolPart {
+        ToolPart {
+            base: PartBase {
+                id: "prt_1".to_string(),
+                session_id: "ses_1".to_string(),
+                message_id: "msg_1".to_string(),
+            },
+            tool: tool.to_string(),
+            call_id: "call_1".to_string(),
+            state,
+            metadata: None,
+        }
+    }
+
+    #[test]
+    fn test_completed_bash_execution() {
+        let part = tool_part(
+            "bash",
+            ToolState::Completed(ToolStateCompleted {
+                input: json!({"command": "ls"}),
+                output: "file.txt".to_string(),
+                title: "List files".to_string(),
+                metadata: HashMap::from([("exitCode".to_string(), json!(0))]),
+                time: ToolTimeComplete {
+                    start: 1000,
+                    end: 1250,
+                    compacted: None,
+                },
+                attachments: None,
+            }),
+        );
+
+        let entry = ToolExecution::from_part(&part);
+        assert_eq!(entry.status, "completed");
+        assert_eq!(entry.started, Some(1000));
+        assert_eq!(entry.duration_ms, Some(250));
+        assert_eq!(entry.exit_code, Some(0));
+        assert!(entry.files.is_empty());
+    }
+
+    #[test]
+    fn test_error_execution_collects_files() {
+        let part = tool_part(
+            "write",
+            ToolState::Error(ToolStateError {
+                input: json!({"filePath": "src/main.rs", "content": ""}),
+                error: "Permission denied".to_string(),
+                metadata: None,
+                time: ToolTimeComplete {
+                    start: 1000,
+                    end: 1010,
+                    compacted: None,
+                },
+            }),
+        );
+
+        let entry = ToolExecution::from_part(&part);
+        assert_eq!(entry.status, "error");
+        assert_eq!(entry.error.as_deref(), Some("Permission denied"));
+        assert_eq!(entry.files, vec!["src/main.rs".to_string()]);
+    }
+
+    #[test]
+    fn test_pending_execution_has_no_timing() {
+        let part = tool_part(
+            "read",
+            ToolState::Pending(ToolStatePending {
+                input: json!({"filePath": "README.md"}),
+                raw: String::new(),
+            }),
+        );
+
+        let entry = ToolExecution::from_part(&part);
+        assert_eq!(entry.status, "pending");
+        assert!(entry.started.is_none());
+        assert!(entry.duration_ms.is_none());
+        assert_eq!(entry.files, vec!["README.md".to_string()]);
+    }
+
+    #[test]
+    fn test_to_script() {
+        let completed = |tool: &str, input: Value, title: &str, exit: i64| {
+            tool_part(
+                tool,
+                ToolState::Completed(ToolStateCompleted {
+                    input,
+                    output: String::new(),
+                    title: title.to_string(),
+                    metadata: HashMap::from([("exitCode".to_string(), json!(exit))]),
+                    time: ToolTimeComplete {
+                        start: 0,
+                        end: 0,
+                        compacted: None,
+                    },
+                    attachments: None,
+                }),
+            )
+        };
+        let executions: Vec<ToolExecution> = [
+            completed("bash", json!({"command": "ls"}), "List files", 0),
+            completed(
+                "edit",
+                json!({"filePath": "src/main.rs", "oldString": "a"}),
+                "src/main.rs",
+                0,
+            ),
+            completed(
+                "bash",
+                json!({"command": "cargo test\n", "workdir": "/tmp/it's"}),
+                "Run tests",
+                101,
+            ),
+            completed("glob", json!({"pattern": "*.rs"}), "*.rs", 0),
+            completed("bash", json!({"command": "true"}), "Fake\r\nrm -rf ~", 0),
+            completed("read", json!({"filePath": "a\nrm -rf ~"}), "", 0),
+        ]
+        .iter()
+        .map(ToolExecution::from_part)
+        .collect();
+
+        assert_eq!(
+            to_script("ses_1", &executions),
+            "#!/bin/sh\n\
+             # Tool calls of session ses_1, in order. Review before running.\n\
+             \n\
+             # bash: List files\n\
+             ls\n\
+             \n\
+             # edit src/main.rs\n\
+             \n\
+             # bash (exit 101): Run tests\n\
+             (\n\
+             cd '/tmp/it'\\''s' || exit 1\n\
+             cargo test\n\
+             )\n\
+             \n\
+             # glob {\"pattern\":\"*.rs\"}\n\
+             \n\
+             # bash: Fake\n\
+             # rm -rf ~\n\
+             true\n\
+             \n\
+             # read a\n\
+             # rm -rf ~\n"
+        );
+    }
+}
@@ -0,0 +1,190 @@
+//! Session trash (soft delete).
+//!
+//! Deleted sessions are moved into a trash area together with their messages
+//! and parts, so they can be restored until the retention period expires.
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use super::{Message, Part, Session, SessionDeleted, SessionUpdated};
+use crate::bus;
+use crate::storage;
+
+/// Default number of days a trashed session is kept before it is purged
+pub const DEFAULT_TRASH_RETENTION_DAYS: u32 = 30;
+
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+/// A session in the trash, bundled with everything needed to restore it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrashedSession {
+    pub session: Session,
+    pub messages: Vec<Message>,
+    pub parts: Vec<Part>,
+    /// Time the session was moved to the trash (milliseconds)
+    pub deleted_at: i64,
+}
+
+impl TrashedSession {
+    /// Check whether this entry is older than the retention period
+    pub fn is_expired(&self, retention_days: u32, now: i64) -> bool {
+        now - self.deleted_at > i64::from(retention_days) * DAY_MS
+    }
+}
+
+impl Session {
+    /// Move a session and its messages/parts to the trash
+    pub async fn trash(project_id: &str, session_id: &str) -> Result<()> {
+        let session = Session::get(project_id, session_id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Session not found: {}", session_id))?;
+
+        let messages = Message::list(session_id).await?;
+        let mut parts = Vec::new();
+        for message in &messages {
+            parts.extend(Part::list(message.id()).await?);
+        }
+
+        let trashed = TrashedSession {
+            session,
+            messages,
+            parts,
+            deleted_at: Utc::now().timestamp_millis(),
+        };
+
+        storage::global()
+            .write(&["trash", project_id, session_id], &trashed)
+            .await
+            .context("Failed to move session to trash")?;
+
+        Session::delete(project_id, session_id).await
+    }
+
+    /// Restore a session from the trash
+    pub async fn restore(project_id: &str, session_id: &str) -> Result<Session> {
+        let trashed: TrashedSession = storage::global()
+            .read(&["trash", project_id, session_id])
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Session not found in trash: {}", session_id))?;
+
+        storage::global()
+            .write(&["session", project_id, session_id], &trashed.session)
+            .await
+            .context("Failed to restore session")?;
+
+        for message in &trashed.messages {
+            message.save().await?;
+        }
+        for part in &trashed.parts {
+            part.save().await?;
+        }
+
+        storage::global()
+            .remove(&["trash", project_id, session_id])
+            .await?;
+
+        bus::publish(SessionUpdated {}).await;
+
+        Ok(trashed.session)
+    }
+
+    /// List sessions in the trash
+    pub async fn list_trash(project_id: &str) -> Result<Vec<TrashedSession>> {
+        let keys = storage::global().list(&["trash", project_id]).await?;
+        let mut trashed = Vec::new();
+
+        for key in keys {
+            if let Some(entry) = storage::global()
+                .read::<TrashedSession>(&key.iter().map(|s| s.as_str()).collect::<Vec<_>>())
+                .await?
+            {
+                trashed.push(entry);
+            }
+        }
+
+        Ok(trashed)
+    }
+
+    /// Permanently remove a session from the trash
+    pub async fn purge(project_id: &str, session_id: &str) -> Result<()> {
+        storage::global()
+            .remove(&["trash", project_id, session_id])
+            .await
+            .context("Failed to purge session")?;
+
+        bus::publish(SessionDeleted {}).await;
+
+        Ok(())
+    }
+
+    /// Purge trashed sessions older than the retention period
+    ///
+    /// Returns the number of purged sessions.
+    pub async fn purge_expired(project_id: &str, retention_days: u32) -> Result<usize> {
+        let now = Utc::now().timestamp_millis();
+        let mut purged = 0;
+
+        for entry in Session::list_trash(project_id).await? {
+            if entry.is_expired(retention_days, now) {
+                Session::purge(project_id, &entry.session.id).await?;
+                purged += 1;
+            }
+        }
+
+        Ok(purged)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session::SessionTime;
+
+    fn trashed_at(deleted_at: i64) -> TrashedSession {
+        TrashedSession {
+            session: Session {
+                id: "ses_1".to_string(),
+                slug: "quick-fox-100".to_string(),
+                project_id: "default".to_string(),
+                directory: "/tmp".to_string(),
+                parent_id: None,
+                title: "Test".to_string(),
+                version: "0.1.0".to_string(),
+                time: SessionTime {
+                    created: 0,
+                    updated: 0,
+                    compacting: None,
+                    archived: None,
+                },
+                share: None,
+                summary: None,
+                permission: None,
+                model: None,
+                system: None,
+                pinned: Vec::new(),
+            },
+            messages: Vec::new(),
+            parts: Vec::new(),
+            deleted_at,
+        }
+    }
+
+    #[test]
+    fn test_not_expired_within_retention() {
+        let entry = trashed_at(0);
+        assert!(!entry.is_expired(30, 29 * DAY_MS));
+    }
+
+    #[test]
+    fn test_expired_after_retention() {
+        let entry = trashed_at(0);
+        assert!(entry.is_expired(30, 31 * DAY_MS));
+    }
+
+    #[test]
+    fn test_zero_retention_expires_immediately() {
+        let entry = trashed_at(0);
+        assert!(entry.is_expired(0, 1));
+    }
+}
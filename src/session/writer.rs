@@ -0,0 +1,204 @@
+//! Incremental persistence of streamed assistant text.
+//!
+//! Deltas are buffered and written to a single `TextPart` (or `ReasoningPart`
+//! for thinking) at most once per debounce interval, so a crashed or remotely
+//! viewed session shows the partial response.
+
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use chrono::Utc;
+
+use super::parts::{Part, PartBase, PartTime, ReasoningPart, TextPart};
+use crate::id::{self, IdPrefix};
+
+/// Minimum interval between writes of a streaming text part
+pub const TEXT_FLUSH_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Part being streamed: the response text or the model's reasoning
+enum StreamedPart {
+    Text(TextPart),
+    Reasoning(ReasoningPart),
+}
+
+impl StreamedPart {
+    fn base_mut(&mut self) -> &mut PartBase {
+        match self {
+            Self::Text(part) => &mut part.base,
+            Self::Reasoning(part) => &mut part.base,
+        }
+    }
+
+    fn text(&self) -> &str {
+        match self {
+            Self::Text(part) => &part.text,
+            Self::Reasoning(part) => &part.text,
+        }
+    }
+
+    fn text_mut(&mut self) -> &mut String {
+        match self {
+            Self::Text(part) => &mut part.text,
+            Self::Reasoning(part) => &mut part.text,
+        }
+    }
+
+    fn time_mut(&mut self) -> Option<&mut PartTime> {
+        match self {
+            Self::Text(part) => part.time.as_mut(),
+            Self::Reasoning(part) => Some(&mut part.time),
+        }
+    }
+
+    fn to_part(&self) -> Part {
+        match self {
+            Self::Text(part) => Part::Text(part.clone()),
+            Self::Reasoning(part) => Part::Reasoning(part.clone()),
+        }
+    }
+}
+
+/// Writes streamed text into a persisted text or reasoning part
+///
+/// The part's ID and start time are taken at the first delta, so parts of a
+/// step sort in the order they started streaming.
+pub struct TextPartWriter {
+    part: StreamedPart,
+    interval: Duration,
+    last_flush: Instant,
+    dirty: bool,
+}
+
+impl TextPartWriter {
+    pub fn new(session_id: &str, message_id: &str) -> Self {
+        Self::with_part(StreamedPart::Text(TextPart {
+            base: part_base(session_id, message_id),
+            text: String::new(),
+            synthetic: None,
+            ignored: None,
+            time: Some(part_time()),
+            metadata: None,
+        }))
+    }
+
+    /// Create a writer for the model's reasoning (thinking) deltas
+    pub fn reasoning(session_id: &str, message_id: &str) -> Self {
+        Self::with_part(StreamedPart::Reasoning(ReasoningPart {
+            base: part_base(session_id, message_id),
+            text: String::new(),
+            time: part_time(),
+            metadata: None,
+        }))
+    }
+
+    fn with_part(part: StreamedPart) -> Self {
+        Self {
+            part,
+            interval: TEXT_FLUSH_INTERVAL,
+            last_flush: Instant::now(),
+            dirty: false,
+        }
+    }
+
+    /// Buffer a delta, returning whether a flush is due
+    pub fn push(&mut self, delta: &str) -> bool {
+        if delta.is_empty() {
+            return false;
+        }
+        if self.part.text().is_empty() {
+            self.part.base_mut().id = id::ascending(IdPrefix::Part);
+            if let Some(time) = self.part.time_mut() {
+                time.start = Utc::now().timestamp_millis();
+            }
+        }
+        self.part.text_mut().push_str(delta);
+        self.dirty = true;
+        self.last_flush.elapsed() >= self.interval
+    }
+
+    /// Buffer a delta and write the part if the debounce interval has passed
+    pub async fn append(&mut self, delta: &str) -> Result<()> {
+        if self.push(delta) {
+            self.flush().await?;
+        }
+        Ok(())
+    }
+
+    /// Write buffered text, if any
+    pub async fn flush(&mut self) -> Result<()> {
+        if !self.dirty {
+            return Ok(());
+        }
+        self.part.to_part().save().await?;
+        self.dirty = false;
+        self.last_flush = Instant::now();
+        Ok(())
+    }
+
+    /// Mark the part as complete and write it
+    pub async fn finish(mut self) -> Result<()> {
+        if self.part.text().is_empty() {
+            return Ok(());
+        }
+        if let Some(time) = self.part.time_mut() {
+            time.end = Some(Utc::now().timestamp_millis());
+        }
+        self.dirty = true;
+        self.flush().await
+    }
+}
+
+fn part_base(session_id: &str, message_id: &str) -> PartBase {
+    PartBase {
+        id: id::ascending(IdPrefix::Part),
+        session_id: session_id.to_string(),
+        message_id: message_id.to_string(),
+    }
+}
+
+fn part_time() -> PartTime {
+    PartTime {
+        start: Utc::now().timestamp_millis(),
+        end: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push_debounces() {
+        let mut writer = TextPartWriter::new("ses_1", "msg_1");
+        writer.interval = Duration::MAX;
+
+        assert!(!writer.push("Hello"));
+        assert!(!writer.push(", world"));
+        assert_eq!(writer.part.text(), "Hello, world");
+    }
+
+    #[test]
+    fn test_push_due_after_interval() {
+        let mut writer = TextPartWriter::new("ses_1", "msg_1");
+        writer.interval = Duration::ZERO;
+
+        assert!(writer.push("Hello"));
+        assert!(!writer.push(""));
+    }
+
+    #[test]
+    fn test_reasoning_part() {
+        let mut writer = TextPartWriter::reasoning("ses_1", "msg_1");
+        writer.interval = Duration::MAX;
+        writer.push("Let me think");
+
+        match writer.part.to_part() {
+            Part::Reasoning(part) => {
+                assert_eq!(part.text, "Let me think");
+                assert_eq!(part.base.message_id, "msg_1");
+                assert!(part.time.end.is_none());
+            }
+            other => panic!("expected a reasoning part, got {:?}", other),
+        }
+    }
+}
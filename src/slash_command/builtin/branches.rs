@@ -0,0 +1,25 @@
+use crate::slash_command::{CommandAction, CommandContext, CommandOutput, SlashCommand};
+use anyhow::Result;
+use async_trait::async_trait;
+
+/// Branches command - navigate between parent and forked sessions
+pub struct BranchesCommand;
+
+#[async_trait]
+impl SlashCommand for BranchesCommand {
+    fn name(&self) -> &str {
+        "branches"
+    }
+
+    fn description(&self) -> &str {
+        "Show the session fork tree and switch branches"
+    }
+
+    fn usage(&self) -> &str {
+        "/branches"
+    }
+
+    async fn execute(&self, _args: &str, _ctx: &CommandContext) -> Result<CommandOutput> {
+        Ok(CommandOutput::action(CommandAction::OpenBranchList))
+    }
+}
@@ -0,0 +1,30 @@
+use crate::slash_command::{CommandAction, CommandContext, CommandOutput, SlashCommand};
+use anyhow::Result;
+use async_trait::async_trait;
+
+/// Copy code command - copy a code block from the last response
+pub struct CopyCodeCommand;
+
+#[async_trait]
+impl SlashCommand for CopyCodeCommand {
+    fn name(&self) -> &str {
+        "copy-code"
+    }
+
+    fn description(&self) -> &str {
+        "Copy a code block from the last response"
+    }
+
+    fn usage(&self) -> &str {
+        "/copy-code [fenced]"
+    }
+
+    async fn execute(&self, args: &str, _ctx: &CommandContext) -> Result<CommandOutput> {
+        let fenced = match args.trim() {
+            "" => false,
+            "fenced" => true,
+            _ => return Ok(CommandOutput::text(format!("Usage: {}", self.usage()))),
+        };
+        Ok(CommandOutput::action(CommandAction::CopyCode { fenced }))
+    }
+}
@@ -0,0 +1,25 @@
+use crate::slash_command::{CommandAction, CommandContext, CommandOutput, SlashCommand};
+use anyhow::Result;
+use async_trait::async_trait;
+
+/// Diff command - review file changes made in the session
+pub struct DiffCommand;
+
+#[async_trait]
+impl SlashCommand for DiffCommand {
+    fn name(&self) -> &str {
+        "diff"
+    }
+
+    fn description(&self) -> &str {
+        "Review file changes in this session"
+    }
+
+    fn usage(&self) -> &str {
+        "/diff"
+    }
+
+    async fn execute(&self, _args: &str, _ctx: &CommandContext) -> Result<CommandOutput> {
+        Ok(CommandOutput::action(CommandAction::OpenDiffViewer))
+    }
+}
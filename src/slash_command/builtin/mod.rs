@@ -1,9 +1,12 @@
 pub mod agent;
+pub mod branches;
 pub mod clear;
 pub mod commands;
 pub mod compact;
 pub mod connect;
 pub mod copy;
+pub mod copy_code;
+pub mod diff;
 pub mod editor;
 pub mod exit;
 pub mod export;
@@ -12,24 +15,37 @@ pub mod help;
 pub mod init;
 pub mod mcp;
 pub mod model;
+pub mod permission;
+pub mod pin;
+pub mod prefill;
 pub mod redo;
+pub mod regenerate;
 pub mod rename;
+pub mod reset;
 pub mod review;
 pub mod session;
+pub mod set;
 pub mod share;
 pub mod status;
+pub mod system;
 pub mod theme;
 pub mod thinking;
 pub mod timeline;
+pub mod toggle;
 pub mod undo;
+pub mod unpin;
 pub mod unshare;
+pub mod yank_context;
 
 pub use agent::AgentCommand;
+pub use branches::BranchesCommand;
 pub use clear::ClearCommand;
 pub use commands::CommandsCommand;
 pub use compact::CompactCommand;
 pub use connect::ConnectCommand;
 pub use copy::CopyCommand;
+pub use copy_code::CopyCodeCommand;
+pub use diff::DiffCommand;
 pub use editor::EditorCommand;
 pub use exit::ExitCommand;
 pub use export::ExportCommand;
@@ -38,14 +54,24 @@ pub use help::HelpCommand;
 pub use init::InitCommand;
 pub use mcp::McpCommand;
 pub use model::ModelCommand;
+pub use permission::PermissionCommand;
+pub use pin::PinCommand;
+pub use prefill::PrefillCommand;
 pub use redo::RedoCommand;
+pub use regenerate::RegenerateCommand;
 pub use rename::RenameCommand;
+pub use reset::ResetCommand;
 pub use review::ReviewCommand;
 pub use session::SessionCommand;
+pub use set::SetCommand;
 pub use share::ShareCommand;
 pub use status::StatusCommand;
+pub use system::SystemCommand;
 pub use theme::ThemeCommand;
 pub use thinking::ThinkingCommand;
 pub use timeline::TimelineCommand;
+pub use toggle::ToggleCommand;
 pub use undo::UndoCommand;
+pub use unpin::UnpinCommand;
 pub use unshare::UnshareCommand;
+pub use yank_context::YankContextCommand;
@@ -1,3 +1,4 @@
+use crate::provider::Capability;
 use crate::slash_command::{CommandAction, CommandContext, CommandOutput, SlashCommand};
 use anyhow::Result;
 use async_trait::async_trait;
@@ -16,7 +17,7 @@ impl SlashCommand for ModelCommand {
     }
 
     fn usage(&self) -> &str {
-        "/model [provider/model]"
+        "/model [provider/model | alias | capability...]"
     }
 
     fn aliases(&self) -> Vec<&str> {
@@ -29,6 +30,15 @@ impl SlashCommand for ModelCommand {
         if args.is_empty() {
             // Open model selector dialog
             Ok(CommandOutput::action(CommandAction::OpenModelSelector))
+        } else if let Some(capabilities) = args
+            .split_whitespace()
+            .map(Capability::parse)
+            .collect::<Option<Vec<_>>>()
+        {
+            // e.g. `/model toolcall input.image`
+            Ok(CommandOutput::action(CommandAction::FilterModels(
+                capabilities,
+            )))
         } else {
             // This will be handled by the TUI to actually switch models
             Ok(CommandOutput {
@@ -0,0 +1,42 @@
+use crate::config::PermissionAction;
+use crate::slash_command::{CommandAction, CommandContext, CommandOutput, SlashCommand};
+use anyhow::Result;
+use async_trait::async_trait;
+
+/// Permission command - set the session's own permission rules
+pub struct PermissionCommand;
+
+#[async_trait]
+impl SlashCommand for PermissionCommand {
+    fn name(&self) -> &str {
+        "permission"
+    }
+
+    fn description(&self) -> &str {
+        "Allow, ask or deny a tool for this session only"
+    }
+
+    fn usage(&self) -> &str {
+        "/permission <tool|*> <allow|ask|deny> | /permission clear"
+    }
+
+    async fn execute(&self, args: &str, _ctx: &CommandContext) -> Result<CommandOutput> {
+        let parts: Vec<&str> = args.split_whitespace().collect();
+        let rule = match parts.as_slice() {
+            ["clear"] => None,
+            [tool, action] => {
+                let action = match *action {
+                    "allow" => PermissionAction::Allow,
+                    "ask" => PermissionAction::Ask,
+                    "deny" => PermissionAction::Deny,
+                    _ => return Ok(CommandOutput::text(format!("Usage: {}", self.usage()))),
+                };
+                Some((tool.to_string(), action))
+            }
+            _ => return Ok(CommandOutput::text(format!("Usage: {}", self.usage()))),
+        };
+        Ok(CommandOutput::action(CommandAction::SetSessionPermission(
+            rule,
+        )))
+    }
+}
@@ -0,0 +1,28 @@
+use crate::slash_command::{CommandAction, CommandContext, CommandOutput, SlashCommand};
+use anyhow::Result;
+use async_trait::async_trait;
+
+/// Pin command - keep a file's current contents in every turn
+pub struct PinCommand;
+
+#[async_trait]
+impl SlashCommand for PinCommand {
+    fn name(&self) -> &str {
+        "pin"
+    }
+
+    fn description(&self) -> &str {
+        "Add a file's current contents to every turn, or list pinned files"
+    }
+
+    fn usage(&self) -> &str {
+        "/pin [path]"
+    }
+
+    async fn execute(&self, args: &str, _ctx: &CommandContext) -> Result<CommandOutput> {
+        let path = Some(args.trim()).filter(|path| !path.is_empty());
+        Ok(CommandOutput::action(CommandAction::PinFile(
+            path.map(String::from),
+        )))
+    }
+}
@@ -0,0 +1,29 @@
+use crate::slash_command::{CommandAction, CommandContext, CommandOutput, SlashCommand};
+use anyhow::Result;
+use async_trait::async_trait;
+
+/// Prefill command - seed assistant replies with fixed text
+pub struct PrefillCommand;
+
+#[async_trait]
+impl SlashCommand for PrefillCommand {
+    fn name(&self) -> &str {
+        "prefill"
+    }
+
+    fn description(&self) -> &str {
+        "Start assistant replies with the given text (Anthropic only)"
+    }
+
+    fn usage(&self) -> &str {
+        "/prefill <text|off>"
+    }
+
+    async fn execute(&self, args: &str, _ctx: &CommandContext) -> Result<CommandOutput> {
+        let prefill = match args.trim() {
+            "" | "off" => None,
+            _ => Some(args.trim_start().to_string()),
+        };
+        Ok(CommandOutput::action(CommandAction::SetPrefill(prefill)))
+    }
+}
@@ -0,0 +1,47 @@
+use crate::slash_command::{CommandAction, CommandContext, CommandOutput, SlashCommand};
+use anyhow::Result;
+use async_trait::async_trait;
+
+/// Regenerate command - answer the last prompt again, keeping both replies
+pub struct RegenerateCommand;
+
+#[async_trait]
+impl SlashCommand for RegenerateCommand {
+    fn name(&self) -> &str {
+        "regenerate"
+    }
+
+    fn description(&self) -> &str {
+        "Generate another reply to the last prompt, optionally with another model or temperature"
+    }
+
+    fn usage(&self) -> &str {
+        "/regenerate [provider/model] [temperature]"
+    }
+
+    async fn execute(&self, args: &str, _ctx: &CommandContext) -> Result<CommandOutput> {
+        let mut model = None;
+        let mut temperature = None;
+        // A number is the temperature, checked against the provider's range
+        // once the model is known; anything else is a model or an alias
+        for arg in args.split_whitespace() {
+            match arg.parse::<f64>() {
+                Ok(value) => temperature = Some(value),
+                Err(_) if crate::provider::parse_model_string(arg).is_some() => {
+                    model = Some(arg.to_string())
+                }
+                Err(_) => {
+                    return Ok(CommandOutput::text(format!(
+                        "Unknown model or invalid temperature: {}\nUsage: {}",
+                        arg,
+                        self.usage()
+                    )))
+                }
+            }
+        }
+        Ok(CommandOutput::action(CommandAction::Regenerate {
+            model,
+            temperature,
+        }))
+    }
+}
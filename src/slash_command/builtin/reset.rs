@@ -0,0 +1,25 @@
+use crate::slash_command::{CommandAction, CommandContext, CommandOutput, SlashCommand};
+use anyhow::Result;
+use async_trait::async_trait;
+
+/// Reset command - deletes the current session's messages but keeps the session
+pub struct ResetCommand;
+
+#[async_trait]
+impl SlashCommand for ResetCommand {
+    fn name(&self) -> &str {
+        "reset"
+    }
+
+    fn description(&self) -> &str {
+        "Delete this session's messages, keeping its settings"
+    }
+
+    fn usage(&self) -> &str {
+        "/reset"
+    }
+
+    async fn execute(&self, _args: &str, _ctx: &CommandContext) -> Result<CommandOutput> {
+        Ok(CommandOutput::action(CommandAction::ResetSession))
+    }
+}
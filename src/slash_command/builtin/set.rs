@@ -0,0 +1,61 @@
+use crate::provider::structured;
+use crate::slash_command::{CommandAction, CommandContext, CommandOutput, SlashCommand};
+use anyhow::Result;
+use async_trait::async_trait;
+use std::path::Path;
+
+/// Set command - change a session setting
+pub struct SetCommand;
+
+#[async_trait]
+impl SlashCommand for SetCommand {
+    fn name(&self) -> &str {
+        "set"
+    }
+
+    fn description(&self) -> &str {
+        "Change a session setting"
+    }
+
+    fn usage(&self) -> &str {
+        "/set max_output <tokens|default> | /set json_schema <file|off>"
+    }
+
+    async fn execute(&self, args: &str, _ctx: &CommandContext) -> Result<CommandOutput> {
+        let mut parts = args.split_whitespace();
+        let (Some(key), Some(value)) = (parts.next(), parts.next()) else {
+            return Ok(CommandOutput::text(format!("Usage: {}", self.usage())));
+        };
+
+        match key {
+            "max_output" => {
+                let max_output = match value {
+                    "default" | "off" => None,
+                    _ => match value.parse::<u64>() {
+                        Ok(tokens) if tokens > 0 => Some(tokens),
+                        _ => {
+                            return Ok(CommandOutput::text(format!(
+                                "Invalid token count: {}",
+                                value
+                            )))
+                        }
+                    },
+                };
+                Ok(CommandOutput::action(CommandAction::SetMaxOutput(
+                    max_output,
+                )))
+            }
+            "json_schema" => {
+                let schema = match value {
+                    "off" => None,
+                    path => match structured::load_schema(Path::new(path)).await {
+                        Ok(schema) => Some(schema),
+                        Err(e) => return Ok(CommandOutput::text(format!("{:#}", e))),
+                    },
+                };
+                Ok(CommandOutput::action(CommandAction::SetJsonSchema(schema)))
+            }
+            _ => Ok(CommandOutput::text(format!("Unknown setting: {}", key))),
+        }
+    }
+}
@@ -0,0 +1,30 @@
+use crate::slash_command::{CommandAction, CommandContext, CommandOutput, SlashCommand};
+use anyhow::Result;
+use async_trait::async_trait;
+
+/// System command - add instructions to the session's system prompt
+pub struct SystemCommand;
+
+#[async_trait]
+impl SlashCommand for SystemCommand {
+    fn name(&self) -> &str {
+        "system"
+    }
+
+    fn description(&self) -> &str {
+        "Append text to the session's system prompt"
+    }
+
+    fn usage(&self) -> &str {
+        "/system <text|clear>"
+    }
+
+    async fn execute(&self, args: &str, _ctx: &CommandContext) -> Result<CommandOutput> {
+        let text = match args.trim() {
+            "" => return Ok(CommandOutput::text(format!("Usage: {}", self.usage()))),
+            "clear" => None,
+            text => Some(text.to_string()),
+        };
+        Ok(CommandOutput::action(CommandAction::AppendSystem(text)))
+    }
+}
@@ -0,0 +1,33 @@
+use crate::slash_command::{
+    CommandAction, CommandContext, CommandOutput, DisplayToggle, SlashCommand,
+};
+use anyhow::Result;
+use async_trait::async_trait;
+
+/// Toggle command - show or hide tool details, reply metadata and system messages
+pub struct ToggleCommand;
+
+#[async_trait]
+impl SlashCommand for ToggleCommand {
+    fn name(&self) -> &str {
+        "toggle"
+    }
+
+    fn description(&self) -> &str {
+        "Show or hide tool details, reply metadata or system messages"
+    }
+
+    fn usage(&self) -> &str {
+        "/toggle <tools|metadata|system>"
+    }
+
+    async fn execute(&self, args: &str, _ctx: &CommandContext) -> Result<CommandOutput> {
+        let toggle = match args.trim() {
+            "tools" => DisplayToggle::Tools,
+            "metadata" => DisplayToggle::Metadata,
+            "system" => DisplayToggle::System,
+            _ => return Ok(CommandOutput::text(format!("Usage: {}", self.usage()))),
+        };
+        Ok(CommandOutput::action(CommandAction::ToggleDisplay(toggle)))
+    }
+}
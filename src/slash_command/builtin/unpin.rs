@@ -0,0 +1,28 @@
+use crate::slash_command::{CommandAction, CommandContext, CommandOutput, SlashCommand};
+use anyhow::Result;
+use async_trait::async_trait;
+
+/// Unpin command - stop adding a pinned file to every turn
+pub struct UnpinCommand;
+
+#[async_trait]
+impl SlashCommand for UnpinCommand {
+    fn name(&self) -> &str {
+        "unpin"
+    }
+
+    fn description(&self) -> &str {
+        "Unpin a file, or all files"
+    }
+
+    fn usage(&self) -> &str {
+        "/unpin [path]"
+    }
+
+    async fn execute(&self, args: &str, _ctx: &CommandContext) -> Result<CommandOutput> {
+        let path = Some(args.trim()).filter(|path| !path.is_empty());
+        Ok(CommandOutput::action(CommandAction::UnpinFile(
+            path.map(String::from),
+        )))
+    }
+}
@@ -0,0 +1,26 @@
+use crate::slash_command::{CommandAction, CommandContext, CommandOutput, SlashCommand};
+use anyhow::Result;
+use async_trait::async_trait;
+
+/// Yank context command - starts a new session seeded with the messages
+/// marked in the timeline
+pub struct YankContextCommand;
+
+#[async_trait]
+impl SlashCommand for YankContextCommand {
+    fn name(&self) -> &str {
+        "yank-context"
+    }
+
+    fn description(&self) -> &str {
+        "Start a new session with the messages marked in /timeline as context"
+    }
+
+    fn usage(&self) -> &str {
+        "/yank-context"
+    }
+
+    async fn execute(&self, _args: &str, _ctx: &CommandContext) -> Result<CommandOutput> {
+        Ok(CommandOutput::action(CommandAction::YankContext))
+    }
+}
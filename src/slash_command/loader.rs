@@ -12,6 +12,27 @@ use std::sync::Arc;
 pub async fn load_commands_from_directory(base_path: &Path) -> Result<Vec<Arc<dyn SlashCommand>>> {
     let mut commands = Vec::new();
 
+    for (dir, path) in command_files(base_path) {
+        match read_command_file(&dir, &path).await {
+            Ok((name, config)) => {
+                tracing::debug!("Loaded command: {}", name);
+                commands
+                    .push(Arc::new(TemplateCommand::new(name, config)) as Arc<dyn SlashCommand>);
+            }
+            Err(e) => {
+                tracing::warn!("Failed to load command from {:?}: {}", path, e);
+            }
+        }
+    }
+
+    Ok(commands)
+}
+
+/// Markdown command files under a directory's .opencode/command(s)
+/// directories, each with the command directory it is in
+pub fn command_files(base_path: &Path) -> Vec<(PathBuf, PathBuf)> {
+    let mut files = Vec::new();
+
     // Check for .opencode/command and .opencode/commands directories
     let possible_dirs = vec![
         base_path.join(".opencode/command"),
@@ -39,30 +60,20 @@ pub async fn load_commands_from_directory(base_path: &Path) -> Result<Vec<Arc<dy
             let path = entry.path();
 
             // Only process .md files
-            if !path.is_file() || path.extension().and_then(|s| s.to_str()) != Some("md") {
-                continue;
-            }
-
-            match load_command_from_file(&dir, path).await {
-                Ok(cmd) => {
-                    tracing::debug!("Loaded command: {}", cmd.name());
-                    commands.push(cmd);
-                }
-                Err(e) => {
-                    tracing::warn!("Failed to load command from {:?}: {}", path, e);
-                }
+            if path.is_file() && path.extension().and_then(|s| s.to_str()) == Some("md") {
+                files.push((dir.clone(), path.to_path_buf()));
             }
         }
     }
 
-    Ok(commands)
+    files
 }
 
-/// Load a single command from a markdown file
-async fn load_command_from_file(
+/// Read a command's name and definition from a markdown file
+pub async fn read_command_file(
     base_dir: &Path,
     file_path: &Path,
-) -> Result<Arc<dyn SlashCommand>> {
+) -> Result<(String, CommandConfig)> {
     // Parse the markdown file
     let markdown = parse_markdown_file(file_path)
         .await
@@ -73,7 +84,17 @@ async fn load_command_from_file(
         .strip_prefix(base_dir)
         .with_context(|| format!("Path {:?} is not under {:?}", file_path, base_dir))?;
 
-    let command_name = calculate_command_name(relative_path)?;
+    // A `name` in the frontmatter takes precedence over the file path
+    let command_name = match markdown
+        .frontmatter
+        .name
+        .as_deref()
+        .map(|n| n.trim().trim_start_matches('/'))
+        .filter(|n| !n.is_empty())
+    {
+        Some(name) => name.to_string(),
+        None => calculate_command_name(relative_path)?,
+    };
 
     // Create CommandConfig from frontmatter
     let config = CommandConfig {
@@ -84,10 +105,7 @@ async fn load_command_from_file(
         subtask: markdown.frontmatter.subtask,
     };
 
-    // Create TemplateCommand
-    let cmd = TemplateCommand::new(command_name, config);
-
-    Ok(Arc::new(cmd) as Arc<dyn SlashCommand>)
+    Ok((command_name, config))
 }
 
 /// Calculate command name from relative file path
@@ -117,6 +135,9 @@ fn calculate_command_name(relative_path: &Path) -> Result<String> {
 }
 
 /// Find all .opencode directories from current path up to root
+///
+/// Directories are ordered nearest first, followed by `~/.opencode` and the
+/// global config directory.
 pub async fn find_opencode_directories() -> Result<Vec<PathBuf>> {
     let mut dirs = Vec::new();
     let mut current = std::env::current_dir()?;
@@ -135,10 +156,11 @@ pub async fn find_opencode_directories() -> Result<Vec<PathBuf>> {
         }
     }
 
-    // Also check global config directory
-    if let Some(global_dir) = crate::config::Config::global_config_dir() {
+    // Also check the home and global config directories
+    let global_dirs = [dirs::home_dir(), crate::config::Config::global_config_dir()];
+    for global_dir in global_dirs.into_iter().flatten() {
         let global_opencode = global_dir.join(".opencode");
-        if global_opencode.exists() && global_opencode.is_dir() {
+        if global_opencode.is_dir() && !dirs.contains(&global_opencode) {
             dirs.push(global_opencode);
         }
     }
@@ -154,9 +176,13 @@ pub async fn load_all_commands() -> Result<Vec<Arc<dyn SlashCommand>>> {
     // Find all .opencode directories
     let dirs = find_opencode_directories().await?;
 
-    // Load commands from each directory (project configs override global)
-    for dir in dirs.iter().rev() {
-        let commands = load_commands_from_directory(dir).await?;
+    // Load commands from each directory; the nearest directory wins, so
+    // project commands override global ones
+    for dir in &dirs {
+        let Some(base_path) = dir.parent() else {
+            continue;
+        };
+        let commands = load_commands_from_directory(base_path).await?;
 
         for cmd in commands {
             let name = cmd.name().to_string();
@@ -195,7 +221,7 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_load_command_from_file() {
+    async fn test_read_command_file() {
         use tempfile::TempDir;
         use tokio::fs;
 
@@ -213,11 +239,31 @@ This is a test command with $1 argument.
 "#;
         fs::write(&file_path, content).await.unwrap();
 
-        let cmd = load_command_from_file(&command_dir, &file_path)
-            .await
-            .unwrap();
+        let (name, config) = read_command_file(&command_dir, &file_path).await.unwrap();
+
+        assert_eq!(name, "test");
+        assert_eq!(config.description.as_deref(), Some("Test command"));
+    }
+
+    #[tokio::test]
+    async fn test_frontmatter_name_overrides_file_name() {
+        use tempfile::TempDir;
+        use tokio::fs;
+
+        let temp_dir = TempDir::new().unwrap();
+        let command_dir = temp_dir.path().join(".opencode/commands");
+        fs::create_dir_all(&command_dir).await.unwrap();
+        fs::write(
+            command_dir.join("code-review.md"),
+            "---\nname: review\ndescription: Review a file\n---\n\nReview $1\n",
+        )
+        .await
+        .unwrap();
+
+        let commands = load_commands_from_directory(temp_dir.path()).await.unwrap();
 
-        assert_eq!(cmd.name(), "test");
-        assert_eq!(cmd.description(), "Test command");
+        assert_eq!(commands.len(), 1);
+        assert_eq!(commands[0].name(), "review");
+        assert_eq!(commands[0].description(), "Review a file");
     }
 }
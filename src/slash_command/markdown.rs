@@ -16,6 +16,9 @@ pub struct MarkdownFile {
 /// Frontmatter metadata from markdown files
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Frontmatter {
+    /// Command name, overriding the one derived from the file path
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -87,6 +90,9 @@ fn parse_frontmatter(yaml_str: &str) -> Result<Frontmatter> {
         for (key, value) in hash {
             if let Some(key_str) = key.as_str() {
                 match key_str {
+                    "name" => {
+                        frontmatter.name = value.as_str().map(|s| s.to_string());
+                    }
                     "description" => {
                         frontmatter.description = value.as_str().map(|s| s.to_string());
                     }
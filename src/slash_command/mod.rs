@@ -18,12 +18,18 @@ pub struct CommandContext {}
 pub enum CommandAction {
     /// Open model selector dialog
     OpenModelSelector,
+    /// Open model selector dialog listing only models with these capabilities
+    FilterModels(Vec<crate::provider::Capability>),
     /// Open agent selector dialog
     OpenAgentSelector,
     /// Open session list
     OpenSessionList,
+    /// Open the parent/child session tree
+    OpenBranchList,
     /// Create new session
     NewSession,
+    /// Delete the current session's messages, keeping the session
+    ResetSession,
     /// Exit the application
     Exit,
     /// Open provider connection dialog
@@ -32,6 +38,11 @@ pub enum CommandAction {
     Undo,
     /// Redo last message
     Redo,
+    /// Answer the last prompt again as a new variant of its reply
+    Regenerate {
+        model: Option<String>,
+        temperature: Option<f64>,
+    },
     /// Compact/summarize session
     Compact,
     /// Unshare session
@@ -40,14 +51,20 @@ pub enum CommandAction {
     Rename,
     /// Copy session transcript to clipboard
     Copy,
+    /// Copy a code block from the last assistant message
+    CopyCode { fenced: bool },
     /// Export session transcript to file
     Export,
     /// Jump to message (timeline)
     Timeline,
     /// Fork from message
     Fork,
+    /// Start a new session seeded with the messages marked in the timeline
+    YankContext,
     /// Toggle thinking visibility
     ToggleThinking,
+    /// Show or hide a kind of transcript content
+    ToggleDisplay(DisplayToggle),
     /// Share session
     Share,
     /// Show status
@@ -60,6 +77,33 @@ pub enum CommandAction {
     OpenEditor,
     /// Show all commands
     ShowCommands,
+    /// Open the file diff viewer
+    OpenDiffViewer,
+    /// Set max output tokens per request (None resets to the model limit)
+    SetMaxOutput(Option<u64>),
+    /// Set the assistant prefill (None clears it)
+    SetPrefill(Option<String>),
+    /// Append to the session's system prompt (None clears the additions)
+    AppendSystem(Option<String>),
+    /// Pin a file to the session (None lists the pinned files)
+    PinFile(Option<String>),
+    /// Unpin a file from the session (None unpins all)
+    UnpinFile(Option<String>),
+    /// Set a session permission rule (None clears the session's rules)
+    SetSessionPermission(Option<(String, crate::config::PermissionAction)>),
+    /// Set the JSON schema replies must conform to (None clears it)
+    SetJsonSchema(Option<serde_json::Value>),
+}
+
+/// Transcript content that can be shown or hidden with `/toggle`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DisplayToggle {
+    /// Tool results and live tool output
+    Tools,
+    /// Model, tokens and cost under assistant replies
+    Metadata,
+    /// System messages
+    System,
 }
 
 /// Output from a slash command
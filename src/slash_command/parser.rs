@@ -1,5 +1,7 @@
 use anyhow::Result;
 use regex::Regex;
+use std::process::Stdio;
+use std::time::Duration;
 use tokio::process::Command;
 
 /// Parsed slash command with name and arguments
@@ -84,77 +86,176 @@ pub fn parse_quoted_args(input: &str) -> Vec<String> {
     args
 }
 
-/// Expand template placeholders with arguments
-///
-/// Supports:
-/// - $1, $2, ..., $N for positional args (1-indexed, not 0-indexed like bash $0)
-/// - $ARGUMENTS for all args joined by spaces
-/// - Last numbered placeholder gets all remaining arguments
-pub fn expand_template(template: &str, args: &[String]) -> String {
-    let mut result = template.to_string();
+/// Time allowed for a shell substitution in a template
+const SHELL_TIMEOUT: Duration = Duration::from_secs(30);
 
-    // Replace $ARGUMENTS with all arguments joined by space
-    let all_args = args.join(" ");
-    result = result.replace("$ARGUMENTS", &all_args);
+/// A piece of a command template
+#[derive(Debug, Clone, PartialEq)]
+enum Segment<'a> {
+    Text(&'a str),
+    /// `$ARGUMENTS`
+    Arguments,
+    /// `$1`, `$2`, ... (1-indexed)
+    Positional(usize),
+    /// `$(command)` or `` !`command` ``, with the source text it was parsed from
+    Shell {
+        command: &'a str,
+        source: &'a str,
+    },
+}
 
-    // Find the last placeholder number to implement "swallow remaining args"
-    // behavior for the last placeholder
-    let re = Regex::new(r"\$(\d+)").unwrap();
-    let last_placeholder_num = re
-        .captures_iter(template)
-        .filter_map(|cap| cap.get(1))
-        .filter_map(|m| m.as_str().parse::<usize>().ok())
-        .max();
-
-    // Replace positional arguments $1, $2, etc.
-    for (i, _) in args.iter().enumerate() {
-        let placeholder_num = i + 1;
-        let placeholder = format!("${}", placeholder_num);
-
-        // For the last placeholder, swallow all remaining args
-        if Some(placeholder_num) == last_placeholder_num && placeholder_num <= args.len() {
-            let remaining_args: Vec<_> = args.iter().skip(i).collect();
-            let remaining = remaining_args
-                .iter()
-                .map(|s| s.as_str())
-                .collect::<Vec<_>>()
-                .join(" ");
-            result = result.replace(&placeholder, &remaining);
-        } else if placeholder_num <= args.len() {
-            result = result.replace(&placeholder, &args[i]);
+/// Split a template into text, placeholders and shell substitutions
+fn parse_segments(template: &str) -> Vec<Segment<'_>> {
+    let mut segments = Vec::new();
+    let mut text_start = 0;
+    let mut i = 0;
+
+    while i < template.len() {
+        match segment_at(&template[i..]) {
+            Some((segment, len)) => {
+                if text_start < i {
+                    segments.push(Segment::Text(&template[text_start..i]));
+                }
+                segments.push(segment);
+                i += len;
+                text_start = i;
+            }
+            None => {
+                i += template[i..].chars().next().map_or(1, char::len_utf8);
+            }
         }
     }
+    if text_start < template.len() {
+        segments.push(Segment::Text(&template[text_start..]));
+    }
 
-    result
+    segments
+}
+
+/// Parse a placeholder or shell substitution at the start of `s`
+fn segment_at(s: &str) -> Option<(Segment<'_>, usize)> {
+    if s.starts_with("$ARGUMENTS") {
+        return Some((Segment::Arguments, "$ARGUMENTS".len()));
+    }
+
+    if let Some(body) = s.strip_prefix("$(") {
+        // Balance parentheses so `$(echo $(date))` is one substitution
+        let mut depth = 1;
+        for (j, c) in body.char_indices() {
+            match c {
+                '(' => depth += 1,
+                ')' => depth -= 1,
+                _ => {}
+            }
+            if depth == 0 {
+                let len = 2 + j + 1;
+                let segment = Segment::Shell {
+                    command: &body[..j],
+                    source: &s[..len],
+                };
+                return Some((segment, len));
+            }
+        }
+        return None;
+    }
+
+    if let Some(digits) = s.strip_prefix('$') {
+        let len = digits.bytes().take_while(u8::is_ascii_digit).count();
+        let n: usize = digits[..len].parse().ok()?;
+        return (n > 0).then_some((Segment::Positional(n), 1 + len));
+    }
+
+    let body = s.strip_prefix("!`")?;
+    let end = body.find('`').filter(|&end| end > 0)?;
+    let len = 2 + end + 1;
+    let segment = Segment::Shell {
+        command: &body[..end],
+        source: &s[..len],
+    };
+    Some((segment, len))
+}
+
+/// Highest positional placeholder, including those inside shell substitutions
+fn last_placeholder(segments: &[Segment]) -> Option<usize> {
+    segments
+        .iter()
+        .filter_map(|segment| match segment {
+            Segment::Positional(n) => Some(*n),
+            Segment::Shell { command, .. } => last_placeholder(&parse_segments(command)),
+            _ => None,
+        })
+        .max()
+}
+
+/// Render a segment, passing each argument through `quote`
+///
+/// Shell substitutions are kept as written.
+fn render_segment(
+    segment: &Segment,
+    args: &[String],
+    last: Option<usize>,
+    quote: fn(&str) -> String,
+) -> String {
+    let join = |args: &[String]| args.iter().map(|a| quote(a)).collect::<Vec<_>>().join(" ");
+
+    match segment {
+        Segment::Text(text) => text.to_string(),
+        Segment::Arguments => join(args),
+        // The last placeholder swallows all remaining arguments
+        Segment::Positional(n) if Some(*n) == last => join(args.get(n - 1..).unwrap_or(&[])),
+        Segment::Positional(n) => args.get(n - 1).map(|a| quote(a)).unwrap_or_default(),
+        Segment::Shell { source, .. } => source.to_string(),
+    }
 }
 
 /// Expand template with shell command execution and file references
-/// This is async version that supports:
-/// - !`command` - Execute shell command and substitute output
+///
+/// Supports:
+/// - $1, $2, ..., $N for positional args (1-indexed, not 0-indexed like bash $0)
+/// - $ARGUMENTS for all args joined by spaces
+/// - Last numbered placeholder gets all remaining arguments
+/// - $(command) or !`command` - Execute shell command and substitute output
 /// - @filepath - Mark file for inclusion (caller should handle)
+///
+/// Placeholders without a matching argument expand to nothing. The template
+/// is expanded in a single pass, so text from arguments or command output is
+/// never expanded again. Arguments used inside a shell command are quoted, so
+/// they cannot inject further commands.
 pub async fn expand_template_async(template: &str, args: &[String]) -> Result<String> {
-    // First expand basic placeholders
-    let mut result = expand_template(template, args);
-
-    // Execute shell commands: !`command`
-    let shell_re = Regex::new(r"!\`([^`]+)\`").unwrap();
-
-    // Collect all shell commands first
-    let shell_commands: Vec<_> = shell_re
-        .captures_iter(&result.clone())
-        .map(|cap| cap.get(1).unwrap().as_str().to_string())
-        .collect();
-
-    // Execute commands and replace
-    for cmd_str in shell_commands {
-        let output = execute_shell_command(&cmd_str).await?;
-        let pattern = format!("!`{}`", cmd_str);
-        result = result.replace(&pattern, &output);
+    let segments = parse_segments(template);
+    let last = last_placeholder(&segments);
+
+    let mut result = String::new();
+    for segment in &segments {
+        match segment {
+            Segment::Shell { command, .. } => {
+                let command: String = parse_segments(command)
+                    .iter()
+                    .map(|s| render_segment(s, args, last, shell_quote))
+                    .collect();
+                result.push_str(&execute_shell_command(&command).await?);
+            }
+            _ => result.push_str(&render_segment(segment, args, last, str::to_string)),
+        }
     }
 
     Ok(result)
 }
 
+/// Quote an argument as a single word for the platform shell
+pub fn shell_quote(arg: &str) -> String {
+    if cfg!(target_os = "windows") {
+        format!("\"{}\"", arg.replace('"', "\"\""))
+    } else {
+        sh_quote(arg)
+    }
+}
+
+/// Quote an argument as a single word for a POSIX shell
+pub fn sh_quote(arg: &str) -> String {
+    format!("'{}'", arg.replace('\'', "'\\''"))
+}
+
 /// Execute a shell command and return its output
 ///
 /// # Security Warning
@@ -163,14 +264,23 @@ pub async fn expand_template_async(template: &str, args: &[String]) -> Result<St
 /// Only use templates from trusted sources. Malicious templates could execute
 /// dangerous commands like `rm -rf /` or exfiltrate data.
 ///
-/// Template authors should validate and sanitize any user input before passing
-/// it to shell commands.
+/// Arguments substituted into the command are quoted by the caller, and the
+/// command is killed if it runs longer than [`SHELL_TIMEOUT`].
 async fn execute_shell_command(cmd: &str) -> Result<String> {
-    let output = if cfg!(target_os = "windows") {
-        Command::new("cmd").args(["/C", cmd]).output().await?
+    let mut command = if cfg!(target_os = "windows") {
+        let mut command = Command::new("cmd");
+        command.args(["/C", cmd]);
+        command
     } else {
-        Command::new("sh").arg("-c").arg(cmd).output().await?
+        let mut command = Command::new("sh");
+        command.arg("-c").arg(cmd);
+        command
     };
+    command.stdin(Stdio::null()).kill_on_drop(true);
+
+    let output = tokio::time::timeout(SHELL_TIMEOUT, command.output())
+        .await
+        .map_err(|_| anyhow::anyhow!("Command timed out: {}", cmd))??;
 
     if output.status.success() {
         Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
@@ -180,6 +290,54 @@ async fn execute_shell_command(cmd: &str) -> Result<String> {
     }
 }
 
+/// Problems in a template: unknown placeholders and unclosed substitutions
+///
+/// Text that looks like a placeholder but is not one is sent to the model as
+/// written, which is rarely what the author meant. `$NAME` inside a shell
+/// substitution is a shell variable and is not reported.
+pub fn template_problems(template: &str) -> Vec<String> {
+    let mut problems = Vec::new();
+    for segment in parse_segments(template) {
+        let Segment::Text(text) = segment else {
+            continue;
+        };
+        for (i, _) in text.match_indices('$') {
+            let rest = &text[i + 1..];
+            let name_len = rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+            let problem = if rest.starts_with('(') {
+                "unclosed `$(` substitution".to_string()
+            } else if rest.starts_with('{') {
+                let end = rest.find('}').map_or(rest.len(), |end| end + 1);
+                format!(
+                    "unknown placeholder `${}`; use `$1`, `$2`, ... or `$ARGUMENTS`",
+                    &rest[..end]
+                )
+            } else if rest.starts_with('0') && name_len == 1 {
+                "`$0` is not a placeholder; arguments start at `$1`".to_string()
+            } else if rest.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+                format!(
+                    "unknown placeholder `${}`; use `$1`, `$2`, ... or `$ARGUMENTS`",
+                    &rest[..name_len]
+                )
+            } else {
+                continue;
+            };
+            if !problems.contains(&problem) {
+                problems.push(problem);
+            }
+        }
+        if text.contains("!`") {
+            let problem = "unclosed !` substitution".to_string();
+            if !problems.contains(&problem) {
+                problems.push(problem);
+            }
+        }
+    }
+    problems
+}
+
 /// Extract file references from template (@filepath)
 ///
 /// Matches patterns like @file.txt, @./path/file.js, @~/Documents/file.md
@@ -200,6 +358,16 @@ pub fn extract_file_references(template: &str) -> Vec<String> {
 mod tests {
     use super::*;
 
+    /// Expand placeholders only, leaving shell substitutions as written
+    fn expand_template(template: &str, args: &[String]) -> String {
+        let segments = parse_segments(template);
+        let last = last_placeholder(&segments);
+        segments
+            .iter()
+            .map(|segment| render_segment(segment, args, last, str::to_string))
+            .collect()
+    }
+
     #[test]
     fn test_parse_command() {
         assert_eq!(
@@ -298,6 +466,39 @@ mod tests {
         assert!(result.contains("hello"));
     }
 
+    #[test]
+    fn test_expand_template_is_single_pass() {
+        let args = vec!["$2".to_string(), "x".to_string()];
+        assert_eq!(expand_template("$1 and $2", &args), "$2 and x");
+
+        // $10 is not $1 followed by "0"; missing arguments expand to nothing
+        assert_eq!(expand_template("[$10][$2]", &["a".to_string()]), "[][]");
+    }
+
+    #[tokio::test]
+    async fn test_expand_template_async_with_dollar_paren_shell() {
+        let args = vec!["hello world".to_string()];
+        let result = expand_template_async("Said: $(echo $1)", &args)
+            .await
+            .unwrap();
+        assert_eq!(result, "Said: hello world");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_expand_template_async_quotes_arguments_in_shell() {
+        let args = vec!["a; echo injected".to_string()];
+        let result = expand_template_async("$(printf '%s' $1)", &args)
+            .await
+            .unwrap();
+        assert_eq!(result, "a; echo injected");
+
+        // Arguments are not parsed for shell substitutions
+        let args = vec!["$(echo injected)".to_string()];
+        let result = expand_template_async("Arg: $1", &args).await.unwrap();
+        assert_eq!(result, "Arg: $(echo injected)");
+    }
+
     #[test]
     fn test_extract_file_references() {
         let template = "Check @README.md and @src/main.rs for details";
@@ -314,4 +515,22 @@ mod tests {
         assert_eq!(files.len(), 1);
         assert_eq!(files[0], "~/.config/opencode.json");
     }
+
+    #[test]
+    fn test_template_problems() {
+        assert!(template_problems("Explain $1 with $ARGUMENTS $(echo $HOME)").is_empty());
+        assert_eq!(
+            template_problems("Use $ARGS and ${1}, not $0 or $(date"),
+            vec![
+                "unknown placeholder `$ARGS`; use `$1`, `$2`, ... or `$ARGUMENTS`",
+                "unknown placeholder `${1}`; use `$1`, `$2`, ... or `$ARGUMENTS`",
+                "`$0` is not a placeholder; arguments start at `$1`",
+                "unclosed `$(` substitution",
+            ]
+        );
+        assert_eq!(
+            template_problems("Run !`git status"),
+            vec!["unclosed !` substitution"]
+        );
+    }
 }
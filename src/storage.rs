@@ -3,12 +3,25 @@
 //! This module provides persistent storage using JSON files, similar to
 //! opencode-ts's Storage module. Data is stored in a hierarchical directory
 //! structure based on keys.
+//!
+//! The storage root defaults to the user data directory. It can be moved with
+//! the `OPENCODE_DATA_DIR` environment variable or the `storage.path` config,
+//! or kept inside the project with `storage.project`.
+//!
+//! Listings skip records that fail to deserialize, so one corrupt file does
+//! not make a whole session unreadable; `storage.strict` turns this off.
+//! Session records are versioned and upgraded on read (see `migrate`).
 
+pub mod migrate;
+
+use crate::config::Config;
 use anyhow::{Context, Result};
+use migrate::RecordKind;
 use serde::{de::DeserializeOwned, Serialize};
 use std::collections::HashMap;
-use std::path::PathBuf;
-use std::sync::Arc;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock};
 use tokio::fs;
 use tokio::sync::RwLock;
 
@@ -17,15 +30,76 @@ use tokio::sync::RwLock;
 pub struct StorageConfig {
     /// Base directory for storage
     pub base_path: PathBuf,
+    /// Fail listings on a corrupt record instead of skipping it
+    pub strict: bool,
 }
 
+/// Environment variable overriding the storage root
+pub const DATA_DIR_ENV: &str = "OPENCODE_DATA_DIR";
+
 impl Default for StorageConfig {
     fn default() -> Self {
-        let base = dirs::data_dir()
-            .unwrap_or_else(|| PathBuf::from("."))
-            .join("opencode-rs");
-        Self { base_path: base }
+        let base = std::env::var_os(DATA_DIR_ENV)
+            .map(PathBuf::from)
+            .unwrap_or_else(default_data_dir);
+        Self {
+            base_path: base,
+            strict: false,
+        }
+    }
+}
+
+impl StorageConfig {
+    /// Resolve the storage root from the config, relative to a working directory
+    ///
+    /// Precedence: `OPENCODE_DATA_DIR`, then `storage.path`, then the project's
+    /// `.opencode/storage` when `storage.project` is set, then the user data
+    /// directory.
+    pub fn from_config(config: &Config, cwd: &Path) -> Self {
+        let env_dir = std::env::var_os(DATA_DIR_ENV).map(PathBuf::from);
+        Self {
+            base_path: resolve_base_path(env_dir, config.storage.as_ref(), cwd),
+            strict: config
+                .storage
+                .as_ref()
+                .and_then(|s| s.strict)
+                .unwrap_or(false),
+        }
+    }
+}
+
+fn default_data_dir() -> PathBuf {
+    dirs::data_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("opencode-rs")
+}
+
+fn resolve_base_path(
+    env_dir: Option<PathBuf>,
+    settings: Option<&crate::config::StorageConfig>,
+    cwd: &Path,
+) -> PathBuf {
+    if let Some(dir) = env_dir.filter(|d| !d.as_os_str().is_empty()) {
+        return cwd.join(dir);
     }
+    let Some(settings) = settings else {
+        return default_data_dir();
+    };
+    if let Some(path) = settings.path.as_deref().filter(|p| !p.is_empty()) {
+        return cwd.join(path);
+    }
+    if settings.project.unwrap_or(false) {
+        return project_root(cwd).join(".opencode").join("storage");
+    }
+    default_data_dir()
+}
+
+/// Nearest ancestor with a `.opencode` or `.git` entry, or `cwd` itself
+fn project_root(cwd: &Path) -> PathBuf {
+    cwd.ancestors()
+        .find(|dir| [".opencode", ".git"].iter().any(|m| dir.join(m).exists()))
+        .unwrap_or(cwd)
+        .to_path_buf()
 }
 
 /// Main storage struct
@@ -33,6 +107,8 @@ pub struct Storage {
     config: StorageConfig,
     /// Simple in-memory cache for frequently accessed data
     cache: Arc<RwLock<HashMap<String, String>>>,
+    /// Corrupt records skipped by listings
+    skipped: AtomicUsize,
 }
 
 impl Storage {
@@ -40,6 +116,7 @@ impl Storage {
         Self {
             config,
             cache: Arc::new(RwLock::new(HashMap::new())),
+            skipped: AtomicUsize::new(0),
         }
     }
 
@@ -48,6 +125,11 @@ impl Storage {
         Self::new(StorageConfig::default())
     }
 
+    /// Root directory of this storage
+    pub fn base_path(&self) -> &Path {
+        &self.config.base_path
+    }
+
     /// Convert a key path to a file path
     fn key_to_path(&self, key: &[&str]) -> PathBuf {
         let mut path = self.config.base_path.clone();
@@ -74,9 +156,15 @@ impl Storage {
                 .with_context(|| format!("Failed to create directory: {:?}", parent))?;
         }
 
-        // Serialize data
-        let json =
-            serde_json::to_string_pretty(data).with_context(|| "Failed to serialize data")?;
+        // Serialize data, stamping versioned records with the schema version
+        let json = match RecordKind::of_key(key) {
+            Some(_) => serde_json::to_value(data).and_then(|mut value| {
+                migrate::stamp(&mut value);
+                serde_json::to_string_pretty(&value)
+            }),
+            None => serde_json::to_string_pretty(data),
+        }
+        .with_context(|| "Failed to serialize data")?;
 
         // Write to file
         fs::write(&path, &json)
@@ -99,8 +187,8 @@ impl Storage {
         {
             let cache = self.cache.read().await;
             if let Some(json) = cache.get(&cache_key) {
-                let data: T = serde_json::from_str(json)
-                    .with_context(|| "Failed to deserialize cached data")?;
+                let data: T =
+                    Self::parse(key, json).with_context(|| "Failed to deserialize cached data")?;
                 return Ok(Some(data));
             }
         }
@@ -120,12 +208,39 @@ impl Storage {
             cache.insert(cache_key, json.clone());
         }
 
-        let data: T = serde_json::from_str(&json)
+        let data: T = Self::parse(key, &json)
             .with_context(|| format!("Failed to deserialize data from {:?}", path))?;
 
         Ok(Some(data))
     }
 
+    /// Read a record as stored, without schema upgrades or caching
+    pub async fn read_raw(&self, key: &[&str]) -> Result<Option<serde_json::Value>> {
+        let path = self.key_to_path(key);
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let json = fs::read_to_string(&path)
+            .await
+            .with_context(|| format!("Failed to read from {:?}", path))?;
+        let value = serde_json::from_str(&json)
+            .with_context(|| format!("Failed to deserialize data from {:?}", path))?;
+        Ok(Some(value))
+    }
+
+    /// Deserialize a stored record, upgrading versioned records first
+    fn parse<T: DeserializeOwned>(key: &[&str], json: &str) -> serde_json::Result<T> {
+        match RecordKind::of_key(key) {
+            Some(kind) => {
+                let mut value: serde_json::Value = serde_json::from_str(json)?;
+                migrate::upgrade(kind, &mut value);
+                serde_json::from_value(value)
+            }
+            None => serde_json::from_str(json),
+        }
+    }
+
     /// Remove data from storage
     pub async fn remove(&self, key: &[&str]) -> Result<()> {
         let path = self.key_to_path(key);
@@ -185,15 +300,184 @@ impl Storage {
 
         Ok(items)
     }
+
+    /// Read every value stored directly under a key prefix
+    ///
+    /// Values are returned in key order. Unlike `list` followed by `read` for
+    /// each key, this walks the directory once and deserializes each file
+    /// without per-key existence checks or cache updates.
+    pub async fn list_values<T: DeserializeOwned>(&self, prefix: &[&str]) -> Result<Vec<T>> {
+        let mut values = Vec::new();
+
+        for path in self.value_paths(prefix).await? {
+            let json = match fs::read_to_string(&path).await {
+                Ok(json) => json,
+                // Removed between listing and reading
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e).with_context(|| format!("Failed to read from {:?}", path)),
+            };
+            match Self::parse(prefix, &json)
+                .with_context(|| format!("Failed to deserialize data from {:?}", path))
+            {
+                Ok(value) => values.push(value),
+                Err(e) => self.skip_corrupt(e)?,
+            }
+        }
+
+        Ok(values)
+    }
+
+    /// Read a value found by `list`
+    ///
+    /// Like `read`, but a record that fails to deserialize is skipped
+    /// (yielding `None`) unless storage is strict.
+    pub async fn read_listed<T: DeserializeOwned>(&self, key: &[&str]) -> Result<Option<T>> {
+        match self.read(key).await {
+            Err(e) if e.downcast_ref::<serde_json::Error>().is_some() => {
+                self.skip_corrupt(e)?;
+                Ok(None)
+            }
+            result => result,
+        }
+    }
+
+    /// Upgrade every stored session, message and part record to the current
+    /// schema, rewriting the records that changed
+    ///
+    /// Records that are not valid JSON are left alone and counted as skipped.
+    pub async fn migrate_all(&self) -> Result<MigrationReport> {
+        let mut report = MigrationReport::default();
+
+        for kind in RecordKind::ALL {
+            for path in json_files(self.config.base_path.join(kind.prefix())).await? {
+                let json = fs::read_to_string(&path)
+                    .await
+                    .with_context(|| format!("Failed to read from {:?}", path))?;
+                report.checked += 1;
+
+                let mut value: serde_json::Value = match serde_json::from_str(&json) {
+                    Ok(value) => value,
+                    Err(e) => {
+                        tracing::warn!("Skipping corrupt record {:?}: {}", path, e);
+                        report.skipped += 1;
+                        continue;
+                    }
+                };
+                if migrate::upgrade(kind, &mut value) {
+                    fs::write(&path, serde_json::to_string_pretty(&value)?)
+                        .await
+                        .with_context(|| format!("Failed to write to {:?}", path))?;
+                    report.upgraded += 1;
+                }
+            }
+        }
+
+        // Cached copies may predate the upgrade
+        self.cache.write().await.clear();
+        Ok(report)
+    }
+
+    /// Number of corrupt records skipped by listings so far
+    pub fn skipped(&self) -> usize {
+        self.skipped.load(Ordering::Relaxed)
+    }
+
+    /// Log and count a corrupt record, or return its error when strict
+    fn skip_corrupt(&self, error: anyhow::Error) -> Result<()> {
+        if self.config.strict {
+            return Err(error);
+        }
+        tracing::warn!("Skipping corrupt record: {:#}", error);
+        self.skipped.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Count the values stored directly under a key prefix
+    pub async fn count(&self, prefix: &[&str]) -> Result<usize> {
+        Ok(self.value_paths(prefix).await?.len())
+    }
+
+    /// Paths of the value files directly under a key prefix, in key order
+    async fn value_paths(&self, prefix: &[&str]) -> Result<Vec<PathBuf>> {
+        let mut dir = self.config.base_path.clone();
+        for part in prefix {
+            dir = dir.join(part);
+        }
+
+        let mut paths = Vec::new();
+
+        if !dir.exists() {
+            return Ok(paths);
+        }
+
+        let mut entries = fs::read_dir(&dir)
+            .await
+            .with_context(|| format!("Failed to read directory {:?}", dir))?;
+
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                paths.push(path);
+            }
+        }
+
+        paths.sort();
+
+        Ok(paths)
+    }
+}
+
+/// Outcome of `Storage::migrate_all`
+#[derive(Debug, Default)]
+pub struct MigrationReport {
+    /// Records looked at
+    pub checked: usize,
+    /// Records rewritten in the current schema
+    pub upgraded: usize,
+    /// Records that are not valid JSON
+    pub skipped: usize,
+}
+
+/// JSON files under a directory, at any depth
+async fn json_files(dir: PathBuf) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut dirs = vec![dir];
+
+    while let Some(dir) = dirs.pop() {
+        if !dir.exists() {
+            continue;
+        }
+        let mut entries = fs::read_dir(&dir)
+            .await
+            .with_context(|| format!("Failed to read directory {:?}", dir))?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if entry.file_type().await?.is_dir() {
+                dirs.push(path);
+            } else if path.extension().is_some_and(|ext| ext == "json") {
+                files.push(path);
+            }
+        }
+    }
+
+    files.sort();
+    Ok(files)
 }
 
 // Global storage instance
-static GLOBAL_STORAGE: std::sync::LazyLock<Storage> =
-    std::sync::LazyLock::new(Storage::with_defaults);
+static GLOBAL_STORAGE: OnceLock<Storage> = OnceLock::new();
+
+/// Configure the global storage root
+///
+/// Must run before the first call to `global()`; returns false if storage was
+/// already initialized.
+pub fn init(config: StorageConfig) -> bool {
+    GLOBAL_STORAGE.set(Storage::new(config)).is_ok()
+}
 
 /// Get the global storage instance
 pub fn global() -> &'static Storage {
-    &GLOBAL_STORAGE
+    GLOBAL_STORAGE.get_or_init(Storage::with_defaults)
 }
 
 #[cfg(test)]
@@ -213,6 +497,7 @@ mod tests {
         let dir = tempdir().unwrap();
         let storage = Storage::new(StorageConfig {
             base_path: dir.path().to_path_buf(),
+            strict: false,
         });
 
         let data = TestData {
@@ -231,6 +516,7 @@ mod tests {
         let dir = tempdir().unwrap();
         let storage = Storage::new(StorageConfig {
             base_path: dir.path().to_path_buf(),
+            strict: false,
         });
 
         let data = TestData {
@@ -246,11 +532,147 @@ mod tests {
         assert_eq!(items.len(), 3);
     }
 
+    #[tokio::test]
+    async fn test_list_values_and_count() {
+        let dir = tempdir().unwrap();
+        let storage = Storage::new(StorageConfig {
+            base_path: dir.path().to_path_buf(),
+            strict: false,
+        });
+
+        for (key, value) in [("b", 2), ("a", 1), ("c", 3)] {
+            let data = TestData {
+                name: key.to_string(),
+                value,
+            };
+            storage.write(&["items", key], &data).await.unwrap();
+        }
+        // Nested keys are not part of the prefix's values
+        storage
+            .write(&["items", "nested", "d"], &TestData::default())
+            .await
+            .unwrap();
+
+        let values: Vec<TestData> = storage.list_values(&["items"]).await.unwrap();
+        let names: Vec<&str> = values.iter().map(|v| v.name.as_str()).collect();
+        assert_eq!(names, vec!["a", "b", "c"]);
+
+        assert_eq!(storage.count(&["items"]).await.unwrap(), 3);
+        assert_eq!(storage.count(&["missing"]).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_corrupt_records_are_skipped_unless_strict() {
+        let dir = tempdir().unwrap();
+        let storage = Storage::new(StorageConfig {
+            base_path: dir.path().to_path_buf(),
+            strict: false,
+        });
+        storage
+            .write(&["items", "a"], &TestData::default())
+            .await
+            .unwrap();
+        std::fs::write(dir.path().join("items").join("b.json"), "{not json").unwrap();
+
+        let values: Vec<TestData> = storage.list_values(&["items"]).await.unwrap();
+        assert_eq!(values.len(), 1);
+        let read: Option<TestData> = storage.read_listed(&["items", "b"]).await.unwrap();
+        assert!(read.is_none());
+        assert_eq!(storage.skipped(), 2);
+
+        let strict = Storage::new(StorageConfig {
+            base_path: dir.path().to_path_buf(),
+            strict: true,
+        });
+        assert!(strict.list_values::<TestData>(&["items"]).await.is_err());
+        assert!(strict
+            .read_listed::<TestData>(&["items", "b"])
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_records_are_versioned() {
+        let dir = tempdir().unwrap();
+        let storage = Storage::new(StorageConfig {
+            base_path: dir.path().to_path_buf(),
+            strict: false,
+        });
+        let old = dir.path().join("message").join("ses_1");
+        std::fs::create_dir_all(&old).unwrap();
+        std::fs::write(
+            old.join("msg_1.json"),
+            r#"{"role":"assistant","mode":"build"}"#,
+        )
+        .unwrap();
+
+        // Upgraded on read
+        let read: Option<serde_json::Value> =
+            storage.read(&["message", "ses_1", "msg_1"]).await.unwrap();
+        assert_eq!(read.unwrap()["agent"], "build");
+
+        // Rewritten by a bulk migration, once
+        let report = storage.migrate_all().await.unwrap();
+        assert_eq!((report.checked, report.upgraded), (1, 1));
+        assert_eq!(storage.migrate_all().await.unwrap().upgraded, 0);
+
+        // Stamped on write
+        storage
+            .write(&["part", "msg_1", "prt_1"], &TestData::default())
+            .await
+            .unwrap();
+        let json = std::fs::read_to_string(dir.path().join("part/msg_1/prt_1.json")).unwrap();
+        assert!(json.contains(r#""schema_version": 1"#));
+    }
+
+    #[test]
+    fn test_resolve_base_path_precedence() {
+        use crate::config::StorageConfig as Settings;
+
+        let dir = tempdir().unwrap();
+        let cwd = dir.path().join("project").join("src");
+        std::fs::create_dir_all(&cwd).unwrap();
+        std::fs::create_dir_all(dir.path().join("project").join(".git")).unwrap();
+
+        let settings = Settings {
+            path: Some("data".to_string()),
+            project: Some(true),
+            strict: None,
+        };
+        assert_eq!(
+            resolve_base_path(Some(PathBuf::from("/env")), Some(&settings), &cwd),
+            PathBuf::from("/env")
+        );
+        assert_eq!(
+            resolve_base_path(None, Some(&settings), &cwd),
+            cwd.join("data")
+        );
+
+        let project_only = Settings {
+            path: None,
+            project: Some(true),
+            strict: None,
+        };
+        assert_eq!(
+            resolve_base_path(None, Some(&project_only), &cwd),
+            dir.path().join("project").join(".opencode").join("storage")
+        );
+
+        assert_eq!(resolve_base_path(None, None, &cwd), default_data_dir());
+
+        // A nested repository wins over a `.opencode` directory further up
+        std::fs::create_dir_all(dir.path().join(".opencode")).unwrap();
+        assert_eq!(project_root(&cwd), dir.path().join("project"));
+        std::fs::create_dir_all(cwd.join(".opencode")).unwrap();
+        assert_eq!(project_root(&cwd), cwd);
+    }
+
     #[tokio::test]
     async fn test_remove() {
         let dir = tempdir().unwrap();
         let storage = Storage::new(StorageConfig {
             base_path: dir.path().to_path_buf(),
+            strict: false,
         });
 
         let data = TestData {
@@ -0,0 +1,122 @@
+//! Schema versions of stored session records.
+//!
+//! Session, message and part records carry a `schema_version`. Records are
+//! upgraded to the current version as they are read and stamped with it as
+//! they are written, so an old record is rewritten in the new shape on its
+//! next save. `opencode storage migrate` upgrades every record at once.
+
+use serde_json::Value;
+
+/// Current schema version of stored records
+pub const SCHEMA_VERSION: u64 = 1;
+
+/// Field holding a record's schema version; records without it are version 0
+pub const VERSION_FIELD: &str = "schema_version";
+
+/// Kind of a versioned record
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordKind {
+    Session,
+    Message,
+    Part,
+}
+
+impl RecordKind {
+    pub const ALL: [RecordKind; 3] = [RecordKind::Session, RecordKind::Message, RecordKind::Part];
+
+    /// Storage key prefix of the records
+    pub fn prefix(self) -> &'static str {
+        match self {
+            RecordKind::Session => "session",
+            RecordKind::Message => "message",
+            RecordKind::Part => "part",
+        }
+    }
+
+    /// Kind of the record stored under a key, if it is versioned
+    pub fn of_key(key: &[&str]) -> Option<Self> {
+        let prefix = key.first()?;
+        Self::ALL.into_iter().find(|kind| kind.prefix() == *prefix)
+    }
+}
+
+/// A migration from one schema version to the next
+type Migration = fn(RecordKind, &mut serde_json::Map<String, Value>);
+
+/// Migrations in order; entry `n` upgrades version `n` to `n + 1`
+const MIGRATIONS: [Migration; SCHEMA_VERSION as usize] = [rename_mode_to_agent];
+
+/// Upgrade a record to the current schema in place
+///
+/// Returns whether the record changed. Records from a newer version are left
+/// as they are.
+pub fn upgrade(kind: RecordKind, value: &mut Value) -> bool {
+    let Some(record) = value.as_object_mut() else {
+        return false;
+    };
+    let version = record
+        .get(VERSION_FIELD)
+        .and_then(Value::as_u64)
+        .unwrap_or(0);
+    if version >= SCHEMA_VERSION {
+        return false;
+    }
+
+    for migration in &MIGRATIONS[version as usize..] {
+        migration(kind, record);
+    }
+    record.insert(VERSION_FIELD.to_string(), SCHEMA_VERSION.into());
+    true
+}
+
+/// Stamp a record being written with the current schema version
+pub fn stamp(value: &mut Value) {
+    if let Some(record) = value.as_object_mut() {
+        record.insert(VERSION_FIELD.to_string(), SCHEMA_VERSION.into());
+    }
+}
+
+/// Version 0 to 1: assistant messages named their agent `mode`
+fn rename_mode_to_agent(kind: RecordKind, record: &mut serde_json::Map<String, Value>) {
+    if kind != RecordKind::Message || record.contains_key("agent") {
+        return;
+    }
+    if let Some(mode) = record.remove("mode") {
+        record.insert("agent".to_string(), mode);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_upgrade_renames_mode() {
+        let mut message = json!({"role": "assistant", "id": "msg_1", "mode": "build"});
+        assert!(upgrade(RecordKind::Message, &mut message));
+        assert_eq!(
+            message,
+            json!({"role": "assistant", "id": "msg_1", "agent": "build", "schema_version": 1})
+        );
+
+        // Already current
+        assert!(!upgrade(RecordKind::Message, &mut message));
+    }
+
+    #[test]
+    fn test_upgrade_keeps_newer_records() {
+        let mut part = json!({"id": "prt_1", "schema_version": 99, "mode": "x"});
+        assert!(!upgrade(RecordKind::Part, &mut part));
+        assert_eq!(part["mode"], "x");
+    }
+
+    #[test]
+    fn test_record_kind_of_key() {
+        assert_eq!(
+            RecordKind::of_key(&["message", "ses_1", "msg_1"]),
+            Some(RecordKind::Message)
+        );
+        assert_eq!(RecordKind::of_key(&["state", "last_model"]), None);
+    }
+}
@@ -1,4 +1,7 @@
 //! Bash tool for executing shell commands.
+//!
+//! Commands run in the shell picked by [`Shell::resolve`]. Output lines are
+//! sent to the output sink as they arrive.
 
 use super::*;
 use anyhow::Result;
@@ -6,7 +9,6 @@ use serde_json::{json, Value};
 use std::process::{ExitStatus, Stdio};
 use std::time::Duration;
 use tokio::io::{AsyncBufReadExt, BufReader};
-use tokio::process::Command;
 
 /// Default command timeout in milliseconds
 const DEFAULT_TIMEOUT_MS: u64 = 120_000; // 2 minutes
@@ -22,6 +24,7 @@ impl BashTool {
 
 /// Arguments parsed from the tool input
 struct BashArgs {
+    shell: Shell,
     command: String,
     workdir: String,
     timeout_ms: u64,
@@ -73,7 +76,21 @@ impl Tool for BashTool {
     }
 
     async fn execute(&self, args: Value, ctx: &ToolContext) -> Result<ToolResult> {
-        let args = parse_args(args, ctx)?;
+        self.execute_streaming(args, ctx, &ToolOutputSink::default())
+            .await
+    }
+
+    async fn execute_streaming(
+        &self,
+        args: Value,
+        ctx: &ToolContext,
+        sink: &ToolOutputSink,
+    ) -> Result<ToolResult> {
+        let configured_shell = crate::config::Config::load()
+            .await
+            .ok()
+            .and_then(|c| c.shell);
+        let args = parse_args(args, ctx, Shell::resolve(configured_shell.as_deref()))?;
 
         // Check for abort
         if ctx.is_aborted() {
@@ -93,6 +110,7 @@ impl Tool for BashTool {
             ("command".to_string(), json!(args.command)),
             ("workdir".to_string(), json!(args.workdir)),
             ("timeout".to_string(), json!(args.timeout_ms)),
+            ("shell".to_string(), json!(args.shell.program)),
         ]);
 
         if let Some(denied) = ctx
@@ -106,7 +124,7 @@ impl Tool for BashTool {
         let start = std::time::Instant::now();
         let timeout = Duration::from_millis(args.timeout_ms);
 
-        match run_command(&args.command, &args.workdir, timeout).await {
+        match run_command(&args.shell, &args.command, &args.workdir, timeout, sink).await {
             Ok(output) => {
                 let duration = start.elapsed();
                 Ok(build_success_result(&args, output, duration))
@@ -127,7 +145,7 @@ impl Tool for BashTool {
 }
 
 /// Parse arguments from the tool input
-fn parse_args(args: Value, ctx: &ToolContext) -> Result<BashArgs> {
+fn parse_args(args: Value, ctx: &ToolContext, shell: Shell) -> Result<BashArgs> {
     let command = args
         .get("command")
         .and_then(|v| v.as_str())
@@ -154,6 +172,7 @@ fn parse_args(args: Value, ctx: &ToolContext) -> Result<BashArgs> {
         .to_string();
 
     Ok(BashArgs {
+        shell,
         command,
         workdir,
         timeout_ms,
@@ -180,15 +199,16 @@ enum CommandError {
     Execution(anyhow::Error),
 }
 
-/// Run the bash command with timeout
+/// Run the command in the shell with timeout
 async fn run_command(
+    shell: &Shell,
     command: &str,
     workdir: &str,
     timeout: Duration,
+    sink: &ToolOutputSink,
 ) -> std::result::Result<CommandOutput, CommandError> {
-    let mut child = Command::new("bash")
-        .arg("-c")
-        .arg(command)
+    let mut child = shell
+        .command(command)
         .current_dir(workdir)
         .stdout(Stdio::piped())
         .stderr(Stdio::piped())
@@ -199,7 +219,7 @@ async fn run_command(
     let stderr = child.stderr.take().unwrap();
 
     let result = tokio::time::timeout(timeout, async {
-        let (stdout_lines, stderr_lines) = read_output(stdout, stderr).await;
+        let (stdout_lines, stderr_lines) = read_output(stdout, stderr, sink).await;
         let status = child.wait().await?;
         Ok::<_, anyhow::Error>(CommandOutput {
             stdout_lines,
@@ -220,10 +240,11 @@ async fn run_command(
     }
 }
 
-/// Read stdout and stderr from the process
+/// Read stdout and stderr from the process, reporting each line as it arrives
 async fn read_output(
     stdout: tokio::process::ChildStdout,
     stderr: tokio::process::ChildStderr,
+    sink: &ToolOutputSink,
 ) -> (Vec<String>, Vec<String>) {
     let mut stdout_reader = BufReader::new(stdout).lines();
     let mut stderr_reader = BufReader::new(stderr).lines();
@@ -235,7 +256,10 @@ async fn read_output(
         tokio::select! {
             line = stdout_reader.next_line() => {
                 match line {
-                    Ok(Some(l)) => stdout_lines.push(l),
+                    Ok(Some(l)) => {
+                        sink.chunk(&l);
+                        stdout_lines.push(l);
+                    }
                     Ok(None) => break,
                     Err(e) => {
                         stderr_lines.push(format!("Error reading stdout: {}", e));
@@ -245,7 +269,10 @@ async fn read_output(
             }
             line = stderr_reader.next_line() => {
                 match line {
-                    Ok(Some(l)) => stderr_lines.push(l),
+                    Ok(Some(l)) => {
+                        sink.chunk(&l);
+                        stderr_lines.push(l);
+                    }
                     Ok(None) => {},
                     Err(e) => {
                         stderr_lines.push(format!("Error reading stderr: {}", e));
@@ -257,6 +284,7 @@ async fn read_output(
 
     // Drain remaining stderr
     while let Ok(Some(line)) = stderr_reader.next_line().await {
+        sink.chunk(&line);
         stderr_lines.push(line);
     }
 
@@ -297,6 +325,7 @@ fn build_success_result(
             m.insert("duration".to_string(), json!(duration.as_millis()));
             m.insert("workdir".to_string(), json!(&args.workdir));
             m.insert("command".to_string(), json!(&args.command));
+            m.insert("shell".to_string(), json!(&args.shell.program));
             m
         },
         truncated,
@@ -154,6 +154,8 @@ impl Tool for BatchTool {
 
             for call in batch {
                 let mut ctx = ctx.clone();
+                // The batch call's approval does not extend to the calls it makes
+                ctx.preapproved = false;
                 // Set is_in_batch flag for child tools (e.g., webfetch can use this to increase output limit)
                 ctx.extra
                     .insert("is_in_batch".to_string(), serde_json::json!(true));
@@ -0,0 +1,194 @@
+//! Per-turn cache of read-only tool results.
+//!
+//! A model often reads the same file or runs the same search more than once in
+//! a turn. Results of the read-only tools are kept, keyed by tool name and
+//! arguments, for as long as the turn's `ToolContext`, and repeated calls get
+//! the kept result with `cached: true` in its metadata. Any other tool may
+//! change files, so running one empties the cache.
+//!
+//! A kept result remembers the permission requests its call made, and is only
+//! reused while the rules allow them without asking; otherwise the call runs
+//! again and prompts as usual.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use anyhow::Result;
+use serde_json::Value;
+
+use super::{PermissionRequest, ToolResult};
+
+/// Tools whose results depend only on their arguments and the files they read
+pub const CACHEABLE_TOOLS: &[&str] = &["read", "glob", "grep", "ls"];
+
+/// Metadata key marking a result served from the cache
+pub const CACHED_KEY: &str = "cached";
+
+/// A kept result and the permission requests of the call that produced it
+#[derive(Debug, Clone)]
+pub struct CachedCall {
+    pub result: ToolResult,
+    pub permissions: Vec<PermissionRequest>,
+}
+
+/// Results of read-only tool calls, shared by the clones of a tool context
+#[derive(Debug, Clone, Default)]
+pub struct ToolResultCache {
+    results: Arc<Mutex<HashMap<(String, String), CachedCall>>>,
+    /// Bumped whenever the cache is emptied
+    generation: Arc<AtomicU64>,
+}
+
+impl ToolResultCache {
+    /// The kept result of an identical earlier call, marked as cached
+    pub fn get(&self, tool: &str, args: &Value) -> Option<CachedCall> {
+        if !CACHEABLE_TOOLS.contains(&tool) {
+            return None;
+        }
+        let results = self.results.lock().unwrap_or_else(|e| e.into_inner());
+        results
+            .get(&(tool.to_string(), normalize(args)))
+            .map(|call| CachedCall {
+                result: call
+                    .result
+                    .clone()
+                    .with_metadata(CACHED_KEY, Value::Bool(true)),
+                permissions: call.permissions.clone(),
+            })
+    }
+
+    /// Current generation, taken before a call runs and passed to `record`
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::SeqCst)
+    }
+
+    /// Keep a successful read-only result, or forget everything after any
+    /// other tool ran
+    ///
+    /// Errors, including denied permissions, are not kept. Neither is a result
+    /// whose call overlapped another tool that emptied the cache since
+    /// `generation` was taken, as it may have read files mid-change.
+    pub fn record(
+        &self,
+        tool: &str,
+        args: &Value,
+        generation: u64,
+        permissions: Vec<PermissionRequest>,
+        result: &Result<ToolResult>,
+    ) {
+        let mut results = self.results.lock().unwrap_or_else(|e| e.into_inner());
+        if !CACHEABLE_TOOLS.contains(&tool) {
+            results.clear();
+            self.generation.fetch_add(1, Ordering::SeqCst);
+            return;
+        }
+        if self.generation() != generation {
+            return;
+        }
+        if let Ok(result) = result {
+            if result.metadata.get("error") != Some(&Value::Bool(true)) {
+                let call = CachedCall {
+                    result: result.clone(),
+                    permissions,
+                };
+                results.insert((tool.to_string(), normalize(args)), call);
+            }
+        }
+    }
+}
+
+/// Arguments as JSON with object keys sorted, so key order does not matter
+fn normalize(args: &Value) -> String {
+    fn sorted(value: &Value) -> Value {
+        match value {
+            Value::Object(map) => {
+                let mut keys: Vec<_> = map.keys().collect();
+                keys.sort();
+                Value::Object(
+                    keys.into_iter()
+                        .map(|key| (key.clone(), sorted(&map[key])))
+                        .collect(),
+                )
+            }
+            Value::Array(items) => Value::Array(items.iter().map(sorted).collect()),
+            other => other.clone(),
+        }
+    }
+    sorted(args).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_repeated_read_is_cached() {
+        let cache = ToolResultCache::default();
+        let args = json!({"filePath": "src/main.rs", "limit": 10});
+        assert!(cache.get("read", &args).is_none());
+
+        cache.record(
+            "read",
+            &args,
+            cache.generation(),
+            Vec::new(),
+            &Ok(ToolResult::success("main.rs", "fn main() {}")),
+        );
+        let reordered = json!({"limit": 10, "filePath": "src/main.rs"});
+        let cached = cache.get("read", &reordered).unwrap().result;
+        assert_eq!(cached.output, "fn main() {}");
+        assert_eq!(cached.metadata.get(CACHED_KEY), Some(&Value::Bool(true)));
+
+        // Other arguments are a different call
+        assert!(cache
+            .get("read", &json!({"filePath": "src/lib.rs"}))
+            .is_none());
+    }
+
+    #[test]
+    fn test_mutating_tools_clear_and_errors_are_not_kept() {
+        let cache = ToolResultCache::default();
+        let args = json!({"pattern": "TODO"});
+        let ok = |title: &str| Ok(ToolResult::success(title, ""));
+        cache.record("grep", &args, cache.generation(), Vec::new(), &ok("grep"));
+
+        let edit = json!({"filePath": "a.rs"});
+        cache.record("edit", &edit, cache.generation(), Vec::new(), &ok("a.rs"));
+        assert!(cache.get("grep", &args).is_none());
+        assert!(cache.get("edit", &edit).is_none());
+
+        let denied = ToolResult::error("Permission Denied", "User denied permission");
+        let generation = cache.generation();
+        cache.record("grep", &args, generation, Vec::new(), &Ok(denied));
+        let missing = Err(anyhow::anyhow!("no such directory"));
+        cache.record("ls", &json!({}), generation, Vec::new(), &missing);
+        assert!(cache.get("grep", &args).is_none());
+        assert!(cache.get("ls", &json!({})).is_none());
+    }
+
+    #[test]
+    fn test_read_overlapping_a_write_is_not_kept() {
+        let cache = ToolResultCache::default();
+        let args = json!({"filePath": "a.rs"});
+
+        // The read starts, an edit finishes meanwhile, then the read finishes
+        let generation = cache.generation();
+        cache.record(
+            "edit",
+            &args,
+            cache.generation(),
+            Vec::new(),
+            &Ok(ToolResult::success("a.rs", "")),
+        );
+        cache.record(
+            "read",
+            &args,
+            generation,
+            Vec::new(),
+            &Ok(ToolResult::success("a.rs", "old")),
+        );
+        assert!(cache.get("read", &args).is_none());
+    }
+}
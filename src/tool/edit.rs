@@ -1,6 +1,7 @@
 //! Edit tool for making string replacements in files.
 
 use super::*;
+use crate::session::FileDiff;
 use anyhow::Result;
 use serde_json::{json, Value};
 use tokio::fs;
@@ -142,6 +143,7 @@ impl Tool for EditTool {
         let old_lines = content.lines().count();
         let new_lines = new_content.lines().count();
         let line_diff = new_lines as i64 - old_lines as i64;
+        let file_diff = FileDiff::compute(&display_path, &content, &new_content);
 
         let title = if replace_all && occurrences > 1 {
             format!("Edited {} ({} replacements)", display_path, occurrences)
@@ -169,6 +171,9 @@ impl Tool for EditTool {
                     json!(if replace_all { occurrences } else { 1 }),
                 );
                 m.insert("lineDiff".to_string(), json!(line_diff));
+                m.insert("additions".to_string(), json!(file_diff.additions));
+                m.insert("deletions".to_string(), json!(file_diff.deletions));
+                m.insert("diff".to_string(), json!(file_diff.diff));
                 m
             },
             truncated: false,
@@ -4,9 +4,11 @@
 //! the conversation state with tool results.
 
 use super::*;
+use crate::config::{Config, PermissionAction};
+use crate::permission::PermissionChecker;
 use crate::provider::{ChatContent, ChatMessage, ContentPart};
 use anyhow::Result;
-use std::collections::{HashMap, VecDeque};
+use std::collections::{HashMap, HashSet, VecDeque};
 
 /// Doom loop detection threshold - number of identical consecutive tool calls
 pub const DOOM_LOOP_THRESHOLD: usize = 3;
@@ -14,6 +16,18 @@ pub const DOOM_LOOP_THRESHOLD: usize = 3;
 /// Maximum number of tools that can be executed in parallel (matches TypeScript implementation)
 pub const MAX_PARALLEL_TOOLS: usize = 10;
 
+/// Error recorded for tool calls cut short by an aborted turn
+const CANCELLED_MESSAGE: &str = "Cancelled: the turn was aborted before the tool finished";
+
+/// Calls in a step that would ask for permission before one summary is shown
+const MIN_BATCH_CONFIRM: usize = 2;
+
+/// Tools that never ask for permission, left out of batch summaries
+const NO_PERMISSION_TOOLS: &[&str] = &["question", "invalid", "git"];
+
+/// Maximum length of an argument preview in a batch summary
+const PREVIEW_MAX_CHARS: usize = 80;
+
 /// Represents a pending tool call that needs to be executed
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct PendingToolCall {
@@ -29,20 +43,60 @@ impl PendingToolCall {
     }
 }
 
+/// Parse a call's arguments; empty arguments are an empty object
+///
+/// Malformed JSON, such as arguments cut off mid-stream, is an error naming
+/// the problem and the start of what was sent, so the model can correct the
+/// call on its next step instead of the tool running with no input.
+pub fn parse_arguments(arguments: &str) -> Result<serde_json::Value> {
+    if arguments.trim().is_empty() {
+        return Ok(serde_json::json!({}));
+    }
+    serde_json::from_str(arguments).map_err(|e| {
+        anyhow::anyhow!(
+            "model produced invalid JSON arguments: {} (arguments start with: {}); \
+            call the tool again with its arguments as one complete JSON object",
+            e,
+            truncate_preview(arguments)
+        )
+    })
+}
+
 /// Execute a single tool call and return the result
+///
+/// Fails without running the tool when the arguments are not valid JSON.
+/// A read-only call repeating one made earlier with the same context gets the
+/// earlier result from `ctx.result_cache`, if the permission rules still
+/// allow it without asking.
 pub async fn execute_tool(
     tool_name: &str,
     arguments: &str,
-    _tool_id: &str,
+    tool_id: &str,
     ctx: &ToolContext,
 ) -> Result<ToolResult> {
-    // Parse arguments
-    let args: serde_json::Value =
-        serde_json::from_str(arguments).unwrap_or_else(|_| serde_json::json!({}));
+    let args = parse_arguments(arguments)?;
+    if let Some(call) = ctx.result_cache.get(tool_name, &args) {
+        if ctx.allowed_by_rules(&call.permissions).await {
+            return Ok(call.result);
+        }
+    }
 
-    // Get tool from registry and execute
+    // Record the call's permission requests along with its result
+    let generation = ctx.result_cache.generation();
+    let log = PermissionLog::default();
+    let mut call_ctx = ctx.clone();
+    call_ctx.permission_log = Some(log.clone());
+
+    // Get tool from registry and execute, streaming output to the context's handler
     let registry = registry::registry();
-    registry.execute(tool_name, args, ctx).await
+    let sink = ToolOutputSink::new(tool_id, ctx.progress_handler.clone());
+    let result = registry
+        .execute_streaming(tool_name, args.clone(), &call_ctx, &sink)
+        .await;
+    let permissions = std::mem::take(&mut *log.lock().unwrap_or_else(|e| e.into_inner()));
+    ctx.result_cache
+        .record(tool_name, &args, generation, permissions, &result);
+    result
 }
 
 /// Process all pending tool calls in parallel with concurrency limit
@@ -62,6 +116,18 @@ pub async fn execute_tool(
 /// - The permission_state module auto-approves all pending requests that match
 /// - This means only the FIRST call in a group asks for permission
 /// - Remaining calls are auto-approved if they match the saved rule
+///
+/// When at least two calls would ask for permission, the user is first asked
+/// once about all of them: approving runs them without further prompts,
+/// denying skips them, and deciding individually falls back to the prompts
+/// above.
+///
+/// When `ctx.abort` is set, queued and running calls are cancelled instead of
+/// completed. Their results are errors marked as cancelled, so every call
+/// still gets a result.
+///
+/// Image attachments of the results follow all tool results as image parts,
+/// so the model sees them on the next request.
 pub async fn execute_all_tools_parallel(
     pending_calls: Vec<PendingToolCall>,
     ctx: &ToolContext,
@@ -71,6 +137,8 @@ pub async fn execute_all_tools_parallel(
     use tokio::sync::Semaphore;
 
     let total_calls = pending_calls.len();
+    let mut results = Vec::with_capacity(total_calls);
+    let (batch, decision) = confirm_batch(&pending_calls, ctx).await;
 
     // Create a semaphore to limit concurrent execution to MAX_PARALLEL_TOOLS
     let semaphore = Arc::new(Semaphore::new(MAX_PARALLEL_TOOLS));
@@ -80,33 +148,66 @@ pub async fn execute_all_tools_parallel(
     let mut futures = FuturesUnordered::new();
 
     for call in pending_calls {
-        let ctx = ctx.clone();
+        let in_batch = batch.contains(&call.id);
+        if in_batch && decision == BatchDecision::DenyAll {
+            results.push(vec![denied_result(&call)]);
+            continue;
+        }
+        let mut ctx = ctx.clone();
+        ctx.preapproved = in_batch && decision == BatchDecision::ApproveAll;
         let semaphore = semaphore.clone();
 
         let future = async move {
             // Acquire semaphore permit (blocks if MAX_PARALLEL_TOOLS already running)
             // As soon as one task completes and releases its permit, this will proceed
-            let _permit = semaphore.acquire().await.expect("Semaphore closed");
+            let _permit = tokio::select! {
+                permit = semaphore.acquire() => permit.expect("Semaphore closed"),
+                _ = ctx.aborted() => return vec![cancelled_result(&call)],
+            };
+            if ctx.is_aborted() {
+                return vec![cancelled_result(&call)];
+            }
 
-            // Execute the tool
-            let result = execute_tool(&call.name, &call.arguments, &call.id, &ctx).await;
+            // Execute the tool, dropping it if the turn is aborted meanwhile
+            let start_time = chrono::Utc::now().timestamp_millis();
+            let part_id = record_tool_start(&call, &ctx, start_time).await;
+            let (result, cancelled) = tokio::select! {
+                result = execute_tool(&call.name, &call.arguments, &call.id, &ctx) => (result, false),
+                _ = ctx.aborted() => (Err(anyhow::anyhow!(CANCELLED_MESSAGE)), true),
+            };
+            if let Some(part_id) = part_id {
+                record_tool_part(&call, &ctx, part_id, start_time, &result).await;
+            }
+            if cancelled {
+                return vec![cancelled_result(&call)];
+            }
 
-            // Convert to content part
+            // Convert to content parts
             match result {
                 Ok(tool_result) => {
                     // Format output as JSON with title and output
-                    let output_json = serde_json::json!({
+                    let mut output_json = serde_json::json!({
                         "title": tool_result.title,
                         "output": tool_result.output,
                         "metadata": tool_result.metadata,
                         "truncated": tool_result.truncated,
                     });
+                    let images = image_parts(&tool_result.attachments);
+                    if !tool_result.attachments.is_empty() {
+                        output_json["attachments"] = tool_result
+                            .attachments
+                            .iter()
+                            .map(|attachment| attachment.path.clone())
+                            .collect();
+                    }
 
-                    ContentPart::ToolResult {
+                    let mut parts = vec![ContentPart::ToolResult {
                         tool_use_id: call.id.clone(),
                         content: serde_json::to_string(&output_json).unwrap_or(tool_result.output),
                         is_error: Some(false),
-                    }
+                    }];
+                    parts.extend(images);
+                    parts
                 }
                 Err(e) => {
                     // Error result
@@ -115,12 +216,12 @@ pub async fn execute_all_tools_parallel(
                         "error": e.to_string(),
                     });
 
-                    ContentPart::ToolResult {
+                    vec![ContentPart::ToolResult {
                         tool_use_id: call.id.clone(),
                         content: serde_json::to_string(&error_json)
                             .unwrap_or_else(|_| e.to_string()),
                         is_error: Some(true),
-                    }
+                    }]
                 }
             }
         };
@@ -133,12 +234,211 @@ pub async fn execute_all_tools_parallel(
     // - At most MAX_PARALLEL_TOOLS run concurrently
     // - New tasks start immediately when a slot becomes available
     // - Results are collected in completion order (may differ from submission order)
-    let mut results = Vec::with_capacity(total_calls);
     while let Some(result) = futures.next().await {
         results.push(result);
     }
 
-    results
+    // Providers want tool results ahead of other content of the message
+    let (mut parts, images): (Vec<_>, Vec<_>) = results
+        .into_iter()
+        .flatten()
+        .partition(|part| matches!(part, ContentPart::ToolResult { .. }));
+    parts.extend(images);
+    parts
+}
+
+/// Image parts for the image attachments of a tool result
+fn image_parts(attachments: &[FileAttachment]) -> Vec<ContentPart> {
+    attachments
+        .iter()
+        .filter(|attachment| attachment.mime_type.starts_with("image/"))
+        .map(|attachment| ContentPart::ImageUrl {
+            image_url: crate::provider::ImageUrl {
+                url: attachment.url.clone(),
+                detail: None,
+            },
+        })
+        .collect()
+}
+
+/// Ask once about the calls that would each ask for permission
+///
+/// Returns the IDs of the calls covered and the decision on them. Fewer than
+/// `MIN_BATCH_CONFIRM` such calls are decided individually.
+async fn confirm_batch(
+    calls: &[PendingToolCall],
+    ctx: &ToolContext,
+) -> (HashSet<String>, BatchDecision) {
+    let checker = match &ctx.permission_checker {
+        Some(checker) => checker.clone(),
+        None => PermissionChecker::from_config(&Config::load().await.unwrap_or_default()),
+    };
+    let batch: Vec<BatchCall> = calls
+        .iter()
+        .filter(|call| !NO_PERMISSION_TOOLS.contains(&call.name.as_str()))
+        .filter(|call| matches!(checker.action(&call.name), PermissionAction::Ask))
+        .map(|call| BatchCall {
+            id: call.id.clone(),
+            tool: call.name.clone(),
+            preview: call_preview(&call.arguments),
+        })
+        .collect();
+    if batch.len() < MIN_BATCH_CONFIRM || ctx.is_aborted() {
+        return (HashSet::new(), BatchDecision::Individually);
+    }
+
+    let ids = batch.iter().map(|call| call.id.clone()).collect();
+    // On abort the calls are cancelled, so they need no decision
+    let decision = tokio::select! {
+        decision = ctx.ask_batch_permission(batch) => decision.unwrap_or(BatchDecision::DenyAll),
+        _ = ctx.aborted() => BatchDecision::Individually,
+    };
+    (ids, decision)
+}
+
+/// Short preview of a call's arguments: its main argument, else the JSON
+fn call_preview(arguments: &str) -> String {
+    let args = parse_tool_input(arguments);
+    let preview = ["command", "filePath", "path", "url", "pattern"]
+        .iter()
+        .find_map(|key| args.get(key).and_then(|v| v.as_str()))
+        .map(|value| value.lines().next().unwrap_or_default().to_string())
+        .unwrap_or_else(|| args.to_string());
+    truncate_preview(&preview)
+}
+
+/// A preview cut to `PREVIEW_MAX_CHARS`
+fn truncate_preview(preview: &str) -> String {
+    if preview.chars().count() > PREVIEW_MAX_CHARS {
+        let truncated: String = preview.chars().take(PREVIEW_MAX_CHARS - 3).collect();
+        format!("{}...", truncated)
+    } else {
+        preview.to_string()
+    }
+}
+
+/// Result for a call skipped because the user denied its batch
+fn denied_result(call: &PendingToolCall) -> ContentPart {
+    let error_json = serde_json::json!({
+        "title": "Permission Denied",
+        "error": format!("User denied this batch of tool calls, including {}", call.name),
+    });
+
+    ContentPart::ToolResult {
+        tool_use_id: call.id.clone(),
+        content: error_json.to_string(),
+        is_error: Some(true),
+    }
+}
+
+/// Result for a call that was cancelled before it finished
+fn cancelled_result(call: &PendingToolCall) -> ContentPart {
+    let error_json = serde_json::json!({
+        "title": "Cancelled",
+        "error": CANCELLED_MESSAGE,
+        "cancelled": true,
+    });
+
+    ContentPart::ToolResult {
+        tool_use_id: call.id.clone(),
+        content: error_json.to_string(),
+        is_error: Some(true),
+    }
+}
+
+/// Persist a running tool call so progress is visible before it finishes
+///
+/// Returns the part ID to update when the call completes, or `None` when the
+/// call is not part of a persisted session.
+async fn record_tool_start(
+    call: &PendingToolCall,
+    ctx: &ToolContext,
+    start_time: i64,
+) -> Option<String> {
+    use crate::id::{self, IdPrefix};
+    use crate::session::{ToolState, ToolStateRunning, ToolTimeStart};
+
+    // Tool calls outside of a persisted session are not logged
+    if ctx.session_id.is_empty() || ctx.message_id.is_empty() {
+        return None;
+    }
+
+    let part_id = id::ascending(IdPrefix::Part);
+    let state = ToolState::Running(ToolStateRunning {
+        input: parse_tool_input(&call.arguments),
+        title: None,
+        metadata: None,
+        time: ToolTimeStart { start: start_time },
+    });
+    save_tool_part(call, ctx, &part_id, state).await;
+
+    Some(part_id)
+}
+
+/// Persist a finished tool call so it appears in the session's tool log
+async fn record_tool_part(
+    call: &PendingToolCall,
+    ctx: &ToolContext,
+    part_id: String,
+    start_time: i64,
+    result: &Result<ToolResult>,
+) {
+    use crate::session::{ToolState, ToolStateCompleted, ToolStateError, ToolTimeComplete};
+
+    let input = parse_tool_input(&call.arguments);
+    let time = ToolTimeComplete {
+        start: start_time,
+        end: chrono::Utc::now().timestamp_millis(),
+        compacted: None,
+    };
+
+    let state = match result {
+        Ok(tool_result) => ToolState::Completed(ToolStateCompleted {
+            input,
+            output: tool_result.output.clone(),
+            title: tool_result.title.clone(),
+            metadata: tool_result.metadata.clone(),
+            time,
+            attachments: None,
+        }),
+        Err(e) => ToolState::Error(ToolStateError {
+            input,
+            error: e.to_string(),
+            metadata: None,
+            time,
+        }),
+    };
+
+    save_tool_part(call, ctx, &part_id, state).await;
+}
+
+fn parse_tool_input(arguments: &str) -> serde_json::Value {
+    serde_json::from_str(arguments).unwrap_or_else(|_| serde_json::json!({}))
+}
+
+async fn save_tool_part(
+    call: &PendingToolCall,
+    ctx: &ToolContext,
+    part_id: &str,
+    state: crate::session::ToolState,
+) {
+    use crate::session::{Part, PartBase, ToolPart};
+
+    let part = Part::Tool(ToolPart {
+        base: PartBase {
+            id: part_id.to_string(),
+            session_id: ctx.session_id.clone(),
+            message_id: ctx.message_id.clone(),
+        },
+        tool: call.name.clone(),
+        call_id: call.id.clone(),
+        state,
+        metadata: None,
+    });
+
+    if let Err(e) = part.save().await {
+        tracing::warn!("Failed to record tool part for {}: {}", call.name, e);
+    }
 }
 
 /// Build a tool result message to send back to the LLM
@@ -149,6 +449,91 @@ pub fn build_tool_result_message(tool_results: Vec<ContentPart>) -> ChatMessage
     }
 }
 
+/// Make sure every tool use has a result in the following user message
+///
+/// Providers reject a request in which a tool use of an assistant message has
+/// no matching result in the next message, e.g. when the call was never run.
+/// Missing results are added as errors, with a warning.
+pub fn pair_tool_results(messages: &mut Vec<ChatMessage>) {
+    let mut idx = 0;
+    while idx < messages.len() {
+        let missing = unanswered_tool_uses(messages, idx);
+        if !missing.is_empty() {
+            let mut results = Vec::with_capacity(missing.len());
+            for (id, name) in &missing {
+                tracing::warn!("Tool call {} ({}) has no result, adding an error", id, name);
+                results.push(skipped_result(id, name));
+            }
+
+            match messages.get_mut(idx + 1).filter(|next| next.role == "user") {
+                // Results go first, ahead of any text of the message
+                Some(next) => {
+                    match std::mem::replace(&mut next.content, ChatContent::Text(String::new())) {
+                        ChatContent::Parts(parts) => results.extend(parts),
+                        ChatContent::Text(text) if !text.is_empty() => {
+                            results.push(ContentPart::Text { text })
+                        }
+                        ChatContent::Text(_) => {}
+                    }
+                    next.content = ChatContent::Parts(results);
+                }
+                None => messages.insert(idx + 1, build_tool_result_message(results)),
+            }
+        }
+        idx += 1;
+    }
+}
+
+/// IDs and names of the tool uses of an assistant message that the next
+/// message has no result for
+fn unanswered_tool_uses(messages: &[ChatMessage], idx: usize) -> Vec<(String, String)> {
+    let message = &messages[idx];
+    let ChatContent::Parts(parts) = &message.content else {
+        return Vec::new();
+    };
+    if message.role != "assistant" {
+        return Vec::new();
+    }
+
+    let answered: HashSet<&str> = match messages.get(idx + 1) {
+        Some(ChatMessage {
+            role,
+            content: ChatContent::Parts(next_parts),
+        }) if role == "user" => next_parts
+            .iter()
+            .filter_map(|part| match part {
+                ContentPart::ToolResult { tool_use_id, .. } => Some(tool_use_id.as_str()),
+                _ => None,
+            })
+            .collect(),
+        _ => HashSet::new(),
+    };
+
+    parts
+        .iter()
+        .filter_map(|part| match part {
+            ContentPart::ToolUse { id, name, .. } if !answered.contains(id.as_str()) => {
+                Some((id.clone(), name.clone()))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Result for a tool use that was never run
+fn skipped_result(id: &str, name: &str) -> ContentPart {
+    let error_json = serde_json::json!({
+        "title": "Tool Skipped",
+        "error": format!("{} was skipped or denied and did not run", name),
+    });
+
+    ContentPart::ToolResult {
+        tool_use_id: id.to_string(),
+        content: error_json.to_string(),
+        is_error: Some(true),
+    }
+}
+
 /// Track tool calls during streaming
 #[derive(Debug, Default)]
 pub struct ToolCallTracker {
@@ -241,3 +626,169 @@ impl Default for DoomLoopDetector {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tool_use(id: &str) -> ContentPart {
+        ContentPart::ToolUse {
+            id: id.to_string(),
+            name: "bash".to_string(),
+            input: serde_json::json!({}),
+        }
+    }
+
+    fn result_ids(message: &ChatMessage) -> Vec<&str> {
+        let ChatContent::Parts(parts) = &message.content else {
+            return Vec::new();
+        };
+        parts
+            .iter()
+            .filter_map(|part| match part {
+                ContentPart::ToolResult { tool_use_id, .. } => Some(tool_use_id.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_pair_tool_results_adds_missing_results() {
+        let mut messages = vec![
+            ChatMessage {
+                role: "assistant".to_string(),
+                content: ChatContent::Parts(vec![tool_use("call_1"), tool_use("call_2")]),
+            },
+            build_tool_result_message(vec![skipped_result("call_2", "bash")]),
+            ChatMessage {
+                role: "assistant".to_string(),
+                content: ChatContent::Parts(vec![tool_use("call_3")]),
+            },
+            ChatMessage {
+                role: "user".to_string(),
+                content: ChatContent::Text("go on".to_string()),
+            },
+            ChatMessage {
+                role: "assistant".to_string(),
+                content: ChatContent::Parts(vec![tool_use("call_4")]),
+            },
+        ];
+
+        pair_tool_results(&mut messages);
+
+        assert_eq!(messages.len(), 6);
+        assert_eq!(result_ids(&messages[1]), vec!["call_1", "call_2"]);
+        assert_eq!(result_ids(&messages[3]), vec!["call_3"]);
+        assert!(matches!(
+            &messages[3].content,
+            ChatContent::Parts(parts) if matches!(&parts[1], ContentPart::Text { text } if text == "go on")
+        ));
+        assert_eq!(messages[5].role, "user");
+        assert_eq!(result_ids(&messages[5]), vec!["call_4"]);
+    }
+
+    #[tokio::test]
+    async fn test_aborted_turn_cancels_every_call() {
+        let (abort_tx, abort_rx) = tokio::sync::watch::channel(false);
+        let ctx = ToolContext::new("", "").with_abort(abort_rx);
+        abort_tx.send(true).unwrap();
+
+        let calls = (1..=3)
+            .map(|i| PendingToolCall {
+                id: format!("call_{}", i),
+                name: "read".to_string(),
+                arguments: r#"{"filePath": "Cargo.toml"}"#.to_string(),
+            })
+            .collect();
+        let results = execute_all_tools_parallel(calls, &ctx).await;
+
+        assert_eq!(results.len(), 3);
+        for result in results {
+            let ContentPart::ToolResult {
+                content, is_error, ..
+            } = result
+            else {
+                panic!("expected a tool result");
+            };
+            assert_eq!(is_error, Some(true));
+            assert!(content.contains(r#""cancelled":true"#));
+        }
+    }
+
+    #[test]
+    fn test_image_parts_keep_only_images() {
+        let attachments = vec![
+            FileAttachment {
+                path: "shot.png".to_string(),
+                mime_type: "image/png".to_string(),
+                url: "data:image/png;base64,abc".to_string(),
+            },
+            FileAttachment {
+                path: "report.pdf".to_string(),
+                mime_type: "application/pdf".to_string(),
+                url: "file:///tmp/report.pdf".to_string(),
+            },
+        ];
+
+        let parts = image_parts(&attachments);
+        assert_eq!(parts.len(), 1);
+        assert!(matches!(
+            &parts[0],
+            ContentPart::ImageUrl { image_url } if image_url.url == "data:image/png;base64,abc"
+        ));
+    }
+
+    #[test]
+    fn test_call_preview() {
+        assert_eq!(
+            call_preview(r#"{"command": "cargo test\necho done", "timeout": 5}"#),
+            "cargo test"
+        );
+        assert_eq!(
+            call_preview(r#"{"filePath": "src/main.rs", "content": "fn main() {}"}"#),
+            "src/main.rs"
+        );
+        assert_eq!(call_preview(r#"{"todos": []}"#), r#"{"todos":[]}"#);
+
+        let long = format!(r#"{{"command": "{}"}}"#, "x".repeat(200));
+        assert_eq!(call_preview(&long).chars().count(), PREVIEW_MAX_CHARS);
+    }
+
+    #[test]
+    fn test_parse_arguments() {
+        assert_eq!(
+            parse_arguments(r#"{"filePath": "a.rs"}"#).unwrap(),
+            serde_json::json!({"filePath": "a.rs"})
+        );
+        assert_eq!(parse_arguments(" ").unwrap(), serde_json::json!({}));
+
+        let err = parse_arguments(r#"{"filePath": "a.r"#).unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("model produced invalid JSON arguments: EOF while parsing a string"));
+        assert!(err
+            .to_string()
+            .contains(r#"start with: {"filePath": "a.r)"#));
+    }
+
+    #[tokio::test]
+    async fn test_invalid_arguments_do_not_run_the_tool() {
+        let ctx = ToolContext::new("", "");
+        let calls = vec![PendingToolCall {
+            id: "call_1".to_string(),
+            name: "write".to_string(),
+            arguments: r#"{"filePath": "out.txt", "content": "unterminated"#.to_string(),
+        }];
+        let results = execute_all_tools_parallel(calls, &ctx).await;
+
+        let [ContentPart::ToolResult {
+            content, is_error, ..
+        }] = results.as_slice()
+        else {
+            panic!("expected one tool result");
+        };
+        assert_eq!(*is_error, Some(true));
+        assert!(content.contains("model produced invalid JSON arguments"));
+        assert!(!std::path::Path::new("out.txt").exists());
+    }
+}
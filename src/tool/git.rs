@@ -0,0 +1,358 @@
+//! Git tool for read-only repository inspection.
+//!
+//! Only `status`, `diff`, `log`, `blame` and `show` are exposed, and git is
+//! run directly rather than through a shell, so the tool needs no permission
+//! prompt. Anything that changes the repository goes through `bash`.
+//!
+//! Repository config can name programs for git to run (an fsmonitor hook,
+//! external diff drivers, textconv filters); those are all turned off, so
+//! inspecting an untrusted checkout runs nothing from it.
+
+use super::*;
+use anyhow::Result;
+use serde_json::{json, Value};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::process::Command;
+
+/// Git command timeout
+const GIT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Default number of commits shown by `log`
+const DEFAULT_LOG_COUNT: u64 = 20;
+
+/// Upper bound for the `log` count
+const MAX_LOG_COUNT: u64 = 200;
+
+/// Tool for read-only git commands
+pub struct GitTool;
+
+/// Subcommands the tool accepts
+const SUBCOMMANDS: &[&str] = &["status", "diff", "log", "blame", "show"];
+
+#[async_trait::async_trait]
+impl Tool for GitTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "git".to_string(),
+            description: r#"Read-only git commands for inspecting the repository.
+- status: branch and changed files, grouped into staged, unstaged and untracked
+- diff: changes in the working tree, or the index with `staged`, or against `ref`
+- log: recent commits (`count`, default 20), optionally for `path`
+- blame: line authorship for `path`, optionally limited to `start_line`..`end_line`
+- show: a commit (`ref`, default HEAD) with its stat and patch
+Use bash for commands that change the repository (commit, checkout, reset, ...)."#
+                .to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "command": {
+                        "type": "string",
+                        "enum": SUBCOMMANDS,
+                        "description": "Git subcommand to run"
+                    },
+                    "path": {
+                        "type": "string",
+                        "description": "File or directory to limit the command to (required for blame)"
+                    },
+                    "ref": {
+                        "type": "string",
+                        "description": "Commit, branch or tag (diff and show)"
+                    },
+                    "staged": {
+                        "type": "boolean",
+                        "description": "Diff the index instead of the working tree"
+                    },
+                    "count": {
+                        "type": "number",
+                        "description": "Number of commits for log (default: 20)"
+                    },
+                    "start_line": {
+                        "type": "number",
+                        "description": "First line for blame"
+                    },
+                    "end_line": {
+                        "type": "number",
+                        "description": "Last line for blame"
+                    }
+                },
+                "required": ["command"]
+            }),
+        }
+    }
+
+    async fn execute(&self, args: Value, ctx: &ToolContext) -> Result<ToolResult> {
+        let subcommand = args
+            .get("command")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow::anyhow!("command is required"))?;
+        let git_args = match build_args(subcommand, &args, ctx) {
+            Ok(git_args) => git_args,
+            Err(e) => return Ok(ToolResult::error("Invalid git command", e.to_string())),
+        };
+
+        let output = match run_git(&git_args, &ctx.cwd).await {
+            Ok(output) => output,
+            Err(e) => return Ok(ToolResult::error("git failed", format!("{:#}", e))),
+        };
+
+        let text = if subcommand == "status" {
+            format_status(&output)
+        } else if subcommand == "log" {
+            format_log(&output)
+        } else {
+            output
+        };
+        let text = if text.trim().is_empty() {
+            "(no output)".to_string()
+        } else {
+            text
+        };
+        let (text, truncated) = truncate_output(&text);
+
+        let mut result = ToolResult::success(format!("git {}", subcommand), text)
+            .with_metadata("command", json!(git_args.join(" ")));
+        result.truncated = truncated;
+        Ok(result)
+    }
+}
+
+/// Build git arguments for a subcommand, rejecting anything not read-only
+fn build_args(subcommand: &str, args: &Value, ctx: &ToolContext) -> Result<Vec<String>> {
+    let str_arg = |name: &str| {
+        args.get(name)
+            .and_then(|v| v.as_str())
+            .filter(|s| !s.is_empty())
+    };
+    let path = str_arg("path").map(|p| ctx.resolve_path(p).to_string_lossy().to_string());
+    let git_ref = str_arg("ref");
+    if let Some(git_ref) = git_ref {
+        // A ref starting with `-` would be parsed as an option
+        if git_ref.starts_with('-') || git_ref.chars().any(char::is_whitespace) {
+            anyhow::bail!("Invalid ref: {}", git_ref);
+        }
+    }
+
+    let mut git_args: Vec<String> = match subcommand {
+        "status" => vec!["status", "--porcelain=v1", "--branch"],
+        "diff" => {
+            let mut diff = vec![
+                "diff",
+                "--no-ext-diff",
+                "--no-textconv",
+                "--stat",
+                "--patch",
+            ];
+            if args.get("staged").and_then(|v| v.as_bool()) == Some(true) {
+                diff.push("--staged");
+            }
+            diff.extend(git_ref);
+            diff
+        }
+        "log" => vec!["log", "--date=short", "--format=%h%x09%ad%x09%an%x09%s"],
+        "blame" => vec!["blame", "--no-textconv", "--date=short"],
+        "show" => vec![
+            "show",
+            "--no-ext-diff",
+            "--no-textconv",
+            "--stat",
+            "--patch",
+            git_ref.unwrap_or("HEAD"),
+        ],
+        other => anyhow::bail!(
+            "Unsupported git command '{}'; expected one of: {}. Use bash for other git commands.",
+            other,
+            SUBCOMMANDS.join(", ")
+        ),
+    }
+    .into_iter()
+    .map(str::to_string)
+    .collect();
+
+    if subcommand == "log" {
+        let count = args
+            .get("count")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(DEFAULT_LOG_COUNT)
+            .clamp(1, MAX_LOG_COUNT);
+        git_args.push(format!("-n{}", count));
+    }
+    if subcommand == "blame" {
+        let start = args.get("start_line").and_then(|v| v.as_u64());
+        let end = args.get("end_line").and_then(|v| v.as_u64());
+        match (start, end) {
+            (Some(start), Some(end)) => git_args.push(format!("-L{},{}", start, end)),
+            (Some(start), None) => git_args.push(format!("-L{},", start)),
+            (None, Some(end)) => git_args.push(format!("-L1,{}", end)),
+            (None, None) => {}
+        }
+        if path.is_none() {
+            anyhow::bail!("blame requires a path");
+        }
+    }
+
+    if let Some(path) = path {
+        git_args.push("--".to_string());
+        git_args.push(path);
+    }
+    Ok(git_args)
+}
+
+/// Run git without a pager, colors or fsmonitor hook, failing with its stderr
+async fn run_git(args: &[String], workdir: &str) -> Result<String> {
+    let child = Command::new("git")
+        .args([
+            "--no-pager",
+            "-c",
+            "color.ui=false",
+            "-c",
+            "core.quotepath=false",
+            "-c",
+            "core.fsmonitor=false",
+        ])
+        .args(args)
+        .current_dir(workdir)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()?;
+
+    let output = tokio::time::timeout(GIT_TIMEOUT, child.wait_with_output())
+        .await
+        .map_err(|_| anyhow::anyhow!("git timed out after {}s", GIT_TIMEOUT.as_secs()))??;
+    if !output.status.success() {
+        anyhow::bail!("{}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Group `status --porcelain --branch` output into sections
+fn format_status(porcelain: &str) -> String {
+    let mut branch = None;
+    let (mut staged, mut unstaged, mut untracked, mut conflicts) =
+        (Vec::new(), Vec::new(), Vec::new(), Vec::new());
+
+    for line in porcelain.lines() {
+        if let Some(rest) = line.strip_prefix("## ") {
+            branch = Some(rest.to_string());
+            continue;
+        }
+        if line.len() < 4 {
+            continue;
+        }
+        let (code, file) = line.split_at(3);
+        let mut code = code.chars();
+        let (index, worktree) = (code.next().unwrap_or(' '), code.next().unwrap_or(' '));
+        match (index, worktree) {
+            ('?', '?') => untracked.push(file.to_string()),
+            ('U', _) | (_, 'U') | ('A', 'A') | ('D', 'D') => conflicts.push(file.to_string()),
+            _ => {
+                if index != ' ' {
+                    staged.push(format!("{} {}", status_name(index), file));
+                }
+                if worktree != ' ' {
+                    unstaged.push(format!("{} {}", status_name(worktree), file));
+                }
+            }
+        }
+    }
+
+    let mut output = format!("Branch: {}\n", branch.as_deref().unwrap_or("(unknown)"));
+    if staged.is_empty() && unstaged.is_empty() && untracked.is_empty() && conflicts.is_empty() {
+        output.push_str("Working tree clean\n");
+    }
+    for (title, files) in [
+        ("Conflicts", conflicts),
+        ("Staged", staged),
+        ("Unstaged", unstaged),
+        ("Untracked", untracked),
+    ] {
+        if !files.is_empty() {
+            output.push_str(&format!("\n{} ({}):\n", title, files.len()));
+            for file in files {
+                output.push_str(&format!("  {}\n", file));
+            }
+        }
+    }
+    output
+}
+
+fn status_name(code: char) -> &'static str {
+    match code {
+        'M' => "modified:",
+        'A' => "added:",
+        'D' => "deleted:",
+        'R' => "renamed:",
+        'C' => "copied:",
+        'T' => "typechange:",
+        _ => "changed:",
+    }
+}
+
+/// Align the tab-separated `log` format into columns
+fn format_log(log: &str) -> String {
+    log.lines()
+        .map(|line| {
+            let fields: Vec<&str> = line.splitn(4, '\t').collect();
+            match fields.as_slice() {
+                [hash, date, author, subject] => {
+                    format!("{} {} {:<20} {}", hash, date, author, subject)
+                }
+                _ => line.to_string(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_is_grouped() {
+        let porcelain = "\
+## main...origin/main [ahead 1]
+M  src/lib.rs
+ M README.md
+MM src/main.rs
+R  old.rs -> new.rs
+UU src/conflict.rs
+?? notes.txt
+";
+        let status = format_status(porcelain);
+        assert!(status.starts_with("Branch: main...origin/main [ahead 1]\n"));
+        assert!(status.contains(
+            "Staged (3):\n  modified: src/lib.rs\n  modified: src/main.rs\n  renamed: old.rs -> new.rs\n"
+        ));
+        assert!(status.contains("Unstaged (2):\n  modified: README.md\n  modified: src/main.rs\n"));
+        assert!(status.contains("Conflicts (1):\n  src/conflict.rs\n"));
+        assert!(status.contains("Untracked (1):\n  notes.txt\n"));
+
+        assert!(format_status("## main\n").contains("Working tree clean"));
+    }
+
+    #[test]
+    fn test_only_read_only_commands_are_built() {
+        let ctx = ToolContext::new("ses_1", "msg_1").with_cwd("/repo".to_string());
+
+        let args = build_args("log", &json!({"count": 5000, "path": "src"}), &ctx).unwrap();
+        assert_eq!(args[args.len() - 3..], ["-n200", "--", "/repo/src"]);
+
+        let args = build_args(
+            "blame",
+            &json!({"path": "a.rs", "start_line": 3, "end_line": 9}),
+            &ctx,
+        )
+        .unwrap();
+        assert!(args.contains(&"-L3,9".to_string()));
+
+        assert!(build_args("blame", &json!({}), &ctx).is_err());
+        let args = build_args("diff", &json!({}), &ctx).unwrap();
+        assert!(args.contains(&"--no-ext-diff".to_string()));
+        assert!(args.contains(&"--no-textconv".to_string()));
+        assert!(build_args("show", &json!({"ref": "--output=x"}), &ctx).is_err());
+        assert!(build_args("commit", &json!({}), &ctx).is_err());
+    }
+}
@@ -108,7 +108,7 @@ impl Tool for GlobTool {
         }
 
         // Sort by modification time (newest first)
-        files.sort_by(|a, b| b.1.cmp(&a.1));
+        files.sort_by_key(|f| std::cmp::Reverse(f.1));
 
         let total_count = files.len();
 
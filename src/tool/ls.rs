@@ -0,0 +1,197 @@
+//! Ls tool for listing directory entries with their metadata.
+
+use super::*;
+use anyhow::Result;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::path::Path;
+
+/// Default depth when `recursive` is set
+const DEFAULT_RECURSIVE_DEPTH: u64 = 3;
+
+/// Upper bound for the recursion depth
+const MAX_DEPTH: u64 = 10;
+
+/// Maximum number of entries returned
+const MAX_ENTRIES: usize = 1000;
+
+/// Directories listed but never descended into
+const SKIPPED_DIRS: &[&str] = &[".git"];
+
+/// Tool for listing a directory
+pub struct LsTool;
+
+/// A directory entry as returned to the model
+#[derive(Debug, Serialize)]
+struct Entry {
+    /// Path relative to the listed directory, with `/` separators
+    path: String,
+    #[serde(rename = "type")]
+    kind: &'static str,
+    size: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    modified: Option<String>,
+}
+
+#[async_trait::async_trait]
+impl Tool for LsTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "ls".to_string(),
+            description: r#"Lists a directory's entries as JSON with type (file, dir, symlink), size in bytes and modification time.
+- Set recursive to include subdirectories, down to depth (default 3)
+- Directories come before files; symlinks are not followed
+- Use this to decide which files to read; use glob to find files by pattern"#
+                .to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "path": {
+                        "type": "string",
+                        "description": "The directory to list (defaults to current directory)"
+                    },
+                    "recursive": {
+                        "type": "boolean",
+                        "description": "Include entries of subdirectories"
+                    },
+                    "depth": {
+                        "type": "number",
+                        "description": "Maximum depth when recursive (default: 3, max: 10)"
+                    }
+                }
+            }),
+        }
+    }
+
+    async fn execute(&self, args: Value, ctx: &ToolContext) -> Result<ToolResult> {
+        let path_arg = args
+            .get("path")
+            .and_then(|v| v.as_str())
+            .unwrap_or(&ctx.cwd);
+        let resolved_path = ctx.resolve_path(path_arg);
+
+        // Validate path is within project root
+        let path = validate_path(resolved_path.to_string_lossy().as_ref(), &ctx.root)?;
+        let display_path = path.display().to_string();
+
+        let metadata = HashMap::from([("path".to_string(), json!(display_path))]);
+        if let Some(denied) = ctx
+            .require_permission("ls", vec![display_path.clone()], metadata)
+            .await?
+        {
+            return Ok(denied);
+        }
+
+        if !path.is_dir() {
+            return Ok(ToolResult::error(
+                format!("Not a directory: {}", display_path),
+                format!("'{}' does not exist or is not a directory", display_path),
+            ));
+        }
+
+        let depth = if args.get("recursive").and_then(|v| v.as_bool()) == Some(true) {
+            args.get("depth")
+                .and_then(|v| v.as_u64())
+                .unwrap_or(DEFAULT_RECURSIVE_DEPTH)
+                .clamp(1, MAX_DEPTH)
+        } else {
+            1
+        };
+
+        let mut entries = Vec::new();
+        let truncated = !list_dir(&path, "", depth, &mut entries);
+
+        let output = serde_json::to_string_pretty(&json!({
+            "path": display_path,
+            "entries": entries,
+            "truncated": truncated,
+        }))?;
+
+        let mut result =
+            ToolResult::success(display_path, output).with_metadata("count", json!(entries.len()));
+        result.truncated = truncated;
+        Ok(result)
+    }
+}
+
+/// Append the entries of `dir` and, below `depth`, its subdirectories
+///
+/// Returns false once `MAX_ENTRIES` is reached.
+fn list_dir(dir: &Path, prefix: &str, depth: u64, entries: &mut Vec<Entry>) -> bool {
+    let Ok(read_dir) = std::fs::read_dir(dir) else {
+        return true;
+    };
+    let mut children: Vec<(String, std::fs::Metadata)> = read_dir
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let metadata = entry.path().symlink_metadata().ok()?;
+            Some((entry.file_name().to_string_lossy().to_string(), metadata))
+        })
+        .collect();
+    children.sort_by(|(a_name, a), (b_name, b)| {
+        b.is_dir().cmp(&a.is_dir()).then_with(|| a_name.cmp(b_name))
+    });
+
+    for (name, metadata) in children {
+        if entries.len() >= MAX_ENTRIES {
+            return false;
+        }
+        let path = format!("{}{}", prefix, name);
+        let entry = to_entry(path.clone(), &metadata);
+        let descend = depth > 1 && entry.kind == "dir" && !SKIPPED_DIRS.contains(&name.as_str());
+        entries.push(entry);
+
+        if descend && !list_dir(&dir.join(&name), &format!("{}/", path), depth - 1, entries) {
+            return false;
+        }
+    }
+    true
+}
+
+fn to_entry(path: String, metadata: &std::fs::Metadata) -> Entry {
+    let kind = if metadata.file_type().is_symlink() {
+        "symlink"
+    } else if metadata.is_dir() {
+        "dir"
+    } else {
+        "file"
+    };
+    Entry {
+        path,
+        kind,
+        size: if metadata.is_file() {
+            metadata.len()
+        } else {
+            0
+        },
+        modified: metadata
+            .modified()
+            .ok()
+            .map(|time| chrono::DateTime::<chrono::Utc>::from(time).to_rfc3339()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_list_dir_respects_depth() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("src/nested")).unwrap();
+        std::fs::write(dir.path().join("README.md"), "hello").unwrap();
+        std::fs::write(dir.path().join("src/main.rs"), "fn main() {}").unwrap();
+        std::fs::write(dir.path().join("src/nested/deep.rs"), "").unwrap();
+
+        let mut entries = Vec::new();
+        assert!(list_dir(dir.path(), "", 1, &mut entries));
+        let listed: Vec<(&str, &str)> = entries.iter().map(|e| (e.path.as_str(), e.kind)).collect();
+        assert_eq!(listed, vec![("src", "dir"), ("README.md", "file")]);
+        assert_eq!(entries[1].size, 5);
+
+        let mut entries = Vec::new();
+        assert!(list_dir(dir.path(), "", 2, &mut entries));
+        let paths: Vec<&str> = entries.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(paths, vec!["src", "src/nested", "src/main.rs", "README.md"]);
+    }
+}
@@ -6,28 +6,38 @@
 
 mod bash;
 mod batch;
+mod cache;
 mod edit;
 mod executor;
+mod git;
 mod glob;
 mod grep;
+mod ls;
 mod model_utils;
 mod question;
 mod read;
 mod registry;
+mod shell;
+mod test_runner;
 mod todo;
 mod webfetch;
 mod write;
 
 pub use bash::BashTool;
 pub use batch::BatchTool;
+pub use cache::ToolResultCache;
 pub use edit::EditTool;
 pub use executor::*;
+pub use git::GitTool;
 pub use glob::GlobTool;
 pub use grep::GrepTool;
+pub use ls::LsTool;
 pub use model_utils::*;
 pub use question::QuestionTool;
-pub use read::ReadTool;
+pub use read::{ReadTool, DEFAULT_MAX_READ_BYTES};
 pub use registry::*;
+pub use shell::Shell;
+pub use test_runner::TestTool;
 pub use todo::{TodoReadTool, TodoWriteTool};
 pub use webfetch::WebFetchTool;
 pub use write::WriteTool;
@@ -37,6 +47,9 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::HashMap;
 
+/// Permission name of requests that cover a batch of tool calls
+pub const BATCH_PERMISSION: &str = "tool_batch";
+
 /// Permission request for tool execution
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PermissionRequest {
@@ -45,6 +58,18 @@ pub struct PermissionRequest {
     pub patterns: Vec<String>,
     pub always: Vec<String>,
     pub metadata: HashMap<String, Value>,
+    /// Tool calls covered by a batch request (`BATCH_PERMISSION`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub calls: Vec<BatchCall>,
+}
+
+/// A tool call summarized in a batch permission request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchCall {
+    pub id: String,
+    pub tool: String,
+    /// Short preview of the arguments, e.g. the command or file path
+    pub preview: String,
 }
 
 /// Permission response from user
@@ -53,6 +78,18 @@ pub struct PermissionResponse {
     pub id: String,
     pub allow: bool,
     pub scope: PermissionScope,
+    /// For batch requests: ask for each call separately instead
+    #[serde(default)]
+    pub individually: bool,
+}
+
+/// Decision on a batch permission request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BatchDecision {
+    ApproveAll,
+    DenyAll,
+    /// Ask for each call separately
+    Individually,
 }
 
 /// Permission scope - how long the permission is valid
@@ -113,6 +150,48 @@ pub type QuestionHandler = std::sync::Arc<
     dyn Fn(QuestionRequest) -> tokio::sync::oneshot::Receiver<QuestionResponse> + Send + Sync,
 >;
 
+/// A chunk of output from a running tool call
+#[derive(Debug, Clone)]
+pub struct ToolOutputChunk {
+    pub call_id: String,
+    pub text: String,
+}
+
+/// Progress handler type, called for each chunk of live tool output
+pub type ProgressHandler = std::sync::Arc<dyn Fn(ToolOutputChunk) + Send + Sync>;
+
+/// Permission requests made during one tool call
+pub type PermissionLog = std::sync::Arc<std::sync::Mutex<Vec<PermissionRequest>>>;
+
+/// Receives the output of one tool call while it runs
+///
+/// A default sink discards everything, so tools can report output
+/// unconditionally.
+#[derive(Clone, Default)]
+pub struct ToolOutputSink {
+    call_id: String,
+    handler: Option<ProgressHandler>,
+}
+
+impl ToolOutputSink {
+    pub fn new(call_id: &str, handler: Option<ProgressHandler>) -> Self {
+        Self {
+            call_id: call_id.to_string(),
+            handler,
+        }
+    }
+
+    /// Report a chunk of output, usually a single line
+    pub fn chunk(&self, text: &str) {
+        if let Some(handler) = &self.handler {
+            handler(ToolOutputChunk {
+                call_id: self.call_id.clone(),
+                text: text.to_string(),
+            });
+        }
+    }
+}
+
 /// Tool definition that can be sent to the LLM
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ToolDefinition {
@@ -143,8 +222,21 @@ pub struct ToolContext {
     pub extra: HashMap<String, Value>,
     /// Permission handler
     pub permission_handler: Option<PermissionHandler>,
+    /// Config and session permission rules the handler applies; the config
+    /// alone is used when unset
+    pub permission_checker: Option<crate::permission::PermissionChecker>,
     /// Question handler
     pub question_handler: Option<QuestionHandler>,
+    /// Receives live output of tools that stream it
+    pub progress_handler: Option<ProgressHandler>,
+    /// Set when the call was approved as part of a batch; its permission
+    /// requests then skip the prompt, though rules still apply
+    pub preapproved: bool,
+    /// Results of read-only calls made with this context (i.e. in this turn)
+    pub result_cache: ToolResultCache,
+    /// Collects the permission requests of one call, so a cached result is
+    /// only reused while the rules still allow them
+    pub permission_log: Option<PermissionLog>,
 }
 
 impl ToolContext {
@@ -162,7 +254,12 @@ impl ToolContext {
                 .unwrap_or_else(|_| ".".to_string()),
             extra: HashMap::new(),
             permission_handler: None,
+            permission_checker: None,
             question_handler: None,
+            progress_handler: None,
+            preapproved: false,
+            result_cache: ToolResultCache::default(),
+            permission_log: None,
         }
     }
 
@@ -181,16 +278,49 @@ impl ToolContext {
         self
     }
 
+    pub fn with_permission_checker(
+        mut self,
+        checker: crate::permission::PermissionChecker,
+    ) -> Self {
+        self.permission_checker = Some(checker);
+        self
+    }
+
     pub fn with_question_handler(mut self, handler: QuestionHandler) -> Self {
         self.question_handler = Some(handler);
         self
     }
 
+    pub fn with_progress_handler(mut self, handler: ProgressHandler) -> Self {
+        self.progress_handler = Some(handler);
+        self
+    }
+
+    pub fn with_abort(mut self, abort: tokio::sync::watch::Receiver<bool>) -> Self {
+        self.abort = Some(abort);
+        self
+    }
+
     /// Check if execution should be aborted
     pub fn is_aborted(&self) -> bool {
         self.abort.as_ref().map(|rx| *rx.borrow()).unwrap_or(false)
     }
 
+    /// Resolve once execution should be aborted; never without an abort signal
+    pub async fn aborted(&self) {
+        if let Some(mut abort) = self.abort.clone() {
+            loop {
+                if *abort.borrow_and_update() {
+                    return;
+                }
+                if abort.changed().await.is_err() {
+                    break;
+                }
+            }
+        }
+        std::future::pending::<()>().await
+    }
+
     /// Resolve a path argument to an absolute path.
     /// If the path is relative, it will be joined with the current working directory.
     pub fn resolve_path(&self, path: &str) -> std::path::PathBuf {
@@ -211,15 +341,27 @@ impl ToolContext {
         always: Vec<String>,
         metadata: HashMap<String, Value>,
     ) -> Result<bool> {
+        let request = PermissionRequest {
+            id: uuid::Uuid::new_v4().to_string(),
+            permission,
+            patterns,
+            always,
+            metadata,
+            calls: Vec::new(),
+        };
+        if let Some(log) = &self.permission_log {
+            log.lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .push(request.clone());
+        }
+        if self.preapproved {
+            // The batch approval stands in for the prompt only; deny and
+            // session rules still decide first
+            let checker = self.checker().await;
+            let decided = crate::permission_state::decided_response(&checker, &request).await;
+            return Ok(decided.is_none_or(|response| response.allow));
+        }
         if let Some(handler) = &self.permission_handler {
-            let request = PermissionRequest {
-                id: uuid::Uuid::new_v4().to_string(),
-                permission,
-                patterns,
-                always,
-                metadata,
-            };
-
             let rx = handler(request);
             match rx.await {
                 Ok(response) => Ok(response.allow),
@@ -231,6 +373,55 @@ impl ToolContext {
         }
     }
 
+    /// Whether the rules allow every request without prompting
+    pub async fn allowed_by_rules(&self, requests: &[PermissionRequest]) -> bool {
+        let checker = self.checker().await;
+        for request in requests {
+            let decided = crate::permission_state::decided_response(&checker, request).await;
+            if !decided.is_some_and(|response| response.allow) {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// The context's permission rules, or the config's when unset
+    async fn checker(&self) -> crate::permission::PermissionChecker {
+        match &self.permission_checker {
+            Some(checker) => checker.clone(),
+            None => crate::permission::PermissionChecker::from_config(
+                &crate::config::Config::load().await.unwrap_or_default(),
+            ),
+        }
+    }
+
+    /// Ask once about a batch of tool calls
+    ///
+    /// Without a permission handler each call asks (and is denied) on its own.
+    pub async fn ask_batch_permission(&self, calls: Vec<BatchCall>) -> Result<BatchDecision> {
+        let Some(handler) = &self.permission_handler else {
+            return Ok(BatchDecision::Individually);
+        };
+        let request = PermissionRequest {
+            id: uuid::Uuid::new_v4().to_string(),
+            permission: BATCH_PERMISSION.to_string(),
+            patterns: calls
+                .iter()
+                .map(|call| format!("{} {}", call.tool, call.preview))
+                .collect(),
+            always: Vec::new(),
+            metadata: HashMap::new(),
+            calls,
+        };
+
+        match handler(request).await {
+            Ok(response) if response.individually => Ok(BatchDecision::Individually),
+            Ok(response) if response.allow => Ok(BatchDecision::ApproveAll),
+            Ok(_) => Ok(BatchDecision::DenyAll),
+            Err(_) => anyhow::bail!("Permission request cancelled"),
+        }
+    }
+
     /// Request permission and return an error result if denied.
     /// This is a convenience method that combines permission check and denial handling.
     pub async fn require_permission(
@@ -239,13 +430,13 @@ impl ToolContext {
         patterns: Vec<String>,
         metadata: HashMap<String, Value>,
     ) -> Result<Option<ToolResult>> {
+        let always = patterns
+            .iter()
+            .map(|p| crate::permission_state::always_pattern(tool_name, p))
+            .collect();
+
         let allowed = self
-            .ask_permission(
-                tool_name.to_string(),
-                patterns.clone(),
-                vec!["*".to_string()],
-                metadata,
-            )
+            .ask_permission(tool_name.to_string(), patterns.clone(), always, metadata)
             .await?;
 
         if allowed {
@@ -344,6 +535,20 @@ pub trait Tool: Send + Sync {
 
     /// Execute the tool with the given arguments
     async fn execute(&self, args: Value, ctx: &ToolContext) -> Result<ToolResult>;
+
+    /// Execute the tool, sending incremental output to `sink` while it runs
+    ///
+    /// The executor always calls this; the result must still carry the full
+    /// output. Tools without incremental output keep this default, which
+    /// calls `execute`.
+    async fn execute_streaming(
+        &self,
+        args: Value,
+        ctx: &ToolContext,
+        _sink: &ToolOutputSink,
+    ) -> Result<ToolResult> {
+        self.execute(args, ctx).await
+    }
 }
 
 /// Maximum output size before truncation (in bytes)
@@ -396,9 +601,14 @@ pub fn truncate_output(output: &str) -> (String, bool) {
 }
 
 /// Validate file path is safe (within project root)
+///
+/// Symlinks are resolved through the longest existing ancestor, so new files
+/// are checked too. On Windows, verbatim (`\\?\`) prefixes are removed and
+/// paths compare case-insensitively; on Unix the comparison is exact.
 pub fn validate_path(path: &str, root: &str) -> Result<std::path::PathBuf> {
     use std::path::Path;
 
+    let windows = cfg!(windows);
     let path = Path::new(path);
     let root = Path::new(root);
 
@@ -409,18 +619,174 @@ pub fn validate_path(path: &str, root: &str) -> Result<std::path::PathBuf> {
         root.join(path)
     };
 
-    // Canonicalize to resolve symlinks and ..
-    let canonical = absolute.canonicalize().unwrap_or(absolute);
-    let root_canonical = root.canonicalize().unwrap_or(root.to_path_buf());
+    let resolved = resolve_existing(&absolute, windows);
+    let root_resolved = resolve_existing(root, windows);
 
     // Check if path is within root
-    if !canonical.starts_with(&root_canonical) {
+    if !is_within(&resolved, &root_resolved, windows) {
         anyhow::bail!(
             "Path '{}' is outside project root '{}'",
-            canonical.display(),
-            root_canonical.display()
+            resolved,
+            root_resolved
         );
     }
 
-    Ok(canonical)
+    Ok(std::path::PathBuf::from(resolved))
+}
+
+/// Normalized absolute form of a path, following symlinks where it exists
+fn resolve_existing(path: &std::path::Path, windows: bool) -> String {
+    if let Ok(canonical) = path.canonicalize() {
+        return normalize_path(&canonical.to_string_lossy(), windows);
+    }
+
+    // Canonicalize the nearest existing ancestor and re-append the rest
+    let normalized = std::path::PathBuf::from(normalize_path(&path.to_string_lossy(), windows));
+    let mut missing = Vec::new();
+    let mut current = normalized.as_path();
+    while let (Some(parent), Some(name)) = (current.parent(), current.file_name()) {
+        missing.push(name.to_os_string());
+        if let Ok(canonical) = parent.canonicalize() {
+            let full = missing
+                .iter()
+                .rev()
+                .fold(canonical, |acc, name| acc.join(name));
+            return normalize_path(&full.to_string_lossy(), windows);
+        }
+        current = parent;
+    }
+    normalized.to_string_lossy().into_owned()
+}
+
+/// Lexically normalize a path: resolve `.` and `..`, and on Windows unify
+/// separators and drop verbatim prefixes (`\\?\C:\` and `\\?\UNC\`)
+fn normalize_path(path: &str, windows: bool) -> String {
+    let sep = if windows { '\\' } else { '/' };
+    let path = if windows {
+        let path = path.replace('/', "\\");
+        if let Some(rest) = path.strip_prefix(r"\\?\UNC\") {
+            format!(r"\\{}", rest)
+        } else if let Some(rest) = path.strip_prefix(r"\\?\") {
+            rest.to_string()
+        } else {
+            path
+        }
+    } else {
+        path.to_string()
+    };
+
+    let segments: Vec<&str> = path.split(sep).collect();
+    // Leading segments that `..` cannot remove: UNC server and share, a
+    // drive letter, or the root
+    let prefix_len = if windows && path.starts_with(r"\\") {
+        4.min(segments.len())
+    } else if windows && segments[0].ends_with(':') {
+        1
+    } else {
+        usize::from(segments[0].is_empty())
+    };
+
+    let mut result: Vec<&str> = segments[..prefix_len].to_vec();
+    for segment in &segments[prefix_len..] {
+        match *segment {
+            "" | "." => {}
+            ".." if result.len() > prefix_len => {
+                result.pop();
+            }
+            ".." if prefix_len > 0 => {}
+            other => result.push(other),
+        }
+    }
+
+    let mut normalized = result.join(&sep.to_string());
+    if result.len() == prefix_len && prefix_len > 0 && !normalized.ends_with(sep) {
+        normalized.push(sep);
+    }
+    normalized
+}
+
+/// Whether a normalized path is the root or inside it
+fn is_within(path: &str, root: &str, windows: bool) -> bool {
+    let sep = if windows { '\\' } else { '/' };
+    let (path, root) = if windows {
+        (path.to_lowercase(), root.to_lowercase())
+    } else {
+        (path.to_string(), root.to_string())
+    };
+    let root = root.trim_end_matches(sep);
+    path == root || path.starts_with(&format!("{}{}", root, sep))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_preapproved_keeps_deny_rules() {
+        let checker = crate::permission::PermissionChecker::default()
+            .with_session(Some(&serde_json::json!({"bash": "deny"})));
+        let mut ctx = ToolContext::new("ses_1", "msg_1").with_permission_checker(checker);
+        ctx.preapproved = true;
+
+        let ask = |permission: &str| {
+            ctx.ask_permission(
+                permission.to_string(),
+                vec!["*".to_string()],
+                Vec::new(),
+                HashMap::new(),
+            )
+        };
+        assert!(!ask("bash").await.unwrap());
+        assert!(ask("edit").await.unwrap());
+    }
+
+    #[test]
+    fn test_normalize_windows_paths() {
+        assert_eq!(
+            normalize_path(r"C:\proj\..\proj\file", true),
+            r"C:\proj\file"
+        );
+        assert_eq!(
+            normalize_path(r"C:/proj\src/./main.rs", true),
+            r"C:\proj\src\main.rs"
+        );
+        assert_eq!(normalize_path(r"\\?\C:\proj\file", true), r"C:\proj\file");
+        assert_eq!(
+            normalize_path(r"\\?\UNC\server\share\proj\..\..\x", true),
+            r"\\server\share\x"
+        );
+        assert_eq!(normalize_path(r"C:\..\..", true), r"C:\");
+    }
+
+    #[test]
+    fn test_normalize_unix_paths() {
+        assert_eq!(normalize_path("/proj/../proj/./file", false), "/proj/file");
+        assert_eq!(normalize_path("/../etc", false), "/etc");
+        assert_eq!(normalize_path("/", false), "/");
+        // Backslashes are ordinary file name characters on Unix
+        assert_eq!(normalize_path(r"/proj/a\b", false), r"/proj/a\b");
+    }
+
+    #[test]
+    fn test_is_within_root() {
+        assert!(is_within(r"c:\PROJ\file", r"C:\proj", true));
+        assert!(is_within(r"C:\proj", r"C:\proj\", true));
+        assert!(!is_within(r"C:\project2\file", r"C:\proj", true));
+
+        assert!(is_within("/proj/file", "/proj", false));
+        assert!(!is_within("/Proj/file", "/proj", false));
+        assert!(!is_within("/project2/file", "/proj", false));
+        assert!(is_within("/etc/passwd", "/", false));
+    }
+
+    #[test]
+    fn test_validate_path_checks_new_and_parent_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().to_string_lossy().to_string();
+
+        let created = validate_path("src/new.rs", &root).unwrap();
+        assert!(created.ends_with("src/new.rs"));
+        assert!(validate_path("src/../new.rs", &root).is_ok());
+        assert!(validate_path("../outside.rs", &root).is_err());
+    }
 }
@@ -5,6 +5,16 @@ use anyhow::Result;
 use serde_json::{json, Value};
 use std::{collections::HashMap, path::Path};
 use tokio::fs;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt};
+
+/// Largest file read whole unless `max_read_bytes` is set
+pub const DEFAULT_MAX_READ_BYTES: u64 = 1024 * 1024;
+
+/// Bytes at the start of a file checked for null bytes to detect binaries
+const BINARY_CHECK_BYTES: u64 = 8192;
+
+/// Bytes of a line shown; longer lines are cut off
+const MAX_LINE_BYTES: usize = 2000;
 
 /// Tool for reading files
 pub struct ReadTool;
@@ -47,6 +57,7 @@ impl Tool for ReadTool {
 - The filePath parameter can be an absolute path or a relative path from the working directory
 - By default, it reads up to 2000 lines starting from the beginning of the file
 - You can optionally specify a line offset and limit (especially handy for long files)
+- Files over the size limit (1 MB by default) can only be read with an offset or limit
 - Results are returned with line numbers starting at 1
 - You can read image files using this tool"#
                 .to_string(),
@@ -129,15 +140,19 @@ impl Tool for ReadTool {
             ));
         }
 
-        // Try to detect if it's a binary file
-        let content = fs::read(&path).await?;
-
-        // Check for binary content (null bytes in first 8KB)
-        let is_binary = content.iter().take(8192).any(|&b| b == 0);
+        // Detect binary files from their first bytes (null bytes in the first
+        // 8KB), before looking at the size
+        let mut head = Vec::new();
+        fs::File::open(&path)
+            .await?
+            .take(BINARY_CHECK_BYTES)
+            .read_to_end(&mut head)
+            .await?;
+        let is_binary = head.contains(&0);
+        let metadata = fs::metadata(&path).await?;
 
         if is_binary {
             // For binary files, return metadata
-            let metadata = fs::metadata(&path).await?;
             let mime_type = mime_guess::from_path(&path)
                 .first()
                 .map(|m| m.to_string())
@@ -156,26 +171,40 @@ impl Tool for ReadTool {
             .with_metadata("mimeType", json!(mime_type)));
         }
 
-        // Convert to string
-        let text = String::from_utf8_lossy(&content);
-        let lines: Vec<&str> = text.lines().collect();
-        let total_lines = lines.len();
-
-        // Apply offset and limit
-        let end = (offset + limit).min(total_lines);
-        let selected_lines = if offset < total_lines {
-            &lines[offset..end]
+        // Files over the limit are only read by line range, a line at a time
+        let max_bytes = crate::config::Config::load()
+            .await
+            .unwrap_or_default()
+            .max_read_bytes
+            .unwrap_or(DEFAULT_MAX_READ_BYTES);
+        let (selected_lines, total_lines) = if metadata.len() > max_bytes {
+            if args.get("offset").is_none() && args.get("limit").is_none() {
+                anyhow::bail!(
+                    "File too large: {} is {} bytes, over the limit of {} bytes \
+                    (max_read_bytes); specify a line range with offset and limit",
+                    path.display(),
+                    metadata.len(),
+                    max_bytes
+                );
+            }
+            read_line_range(&path, offset, limit).await?
         } else {
-            &[]
+            let content = fs::read(&path).await?;
+            let text = String::from_utf8_lossy(&content);
+            let lines: Vec<String> = text.lines().map(String::from).collect();
+            let total_lines = lines.len();
+            let selected = lines.into_iter().skip(offset).take(limit).collect();
+            (selected, total_lines)
         };
+        let end = (offset + limit).min(total_lines);
 
         // Format with line numbers (cat -n style)
         let mut output = String::new();
         for (i, line) in selected_lines.iter().enumerate() {
             let line_num = offset + i + 1;
             // Truncate very long lines
-            let line_content = if line.len() > 2000 {
-                format!("{}...[truncated]", &line[..2000])
+            let line_content = if line.len() > MAX_LINE_BYTES {
+                format!("{}...[truncated]", &line[..MAX_LINE_BYTES])
             } else {
                 line.to_string()
             };
@@ -219,3 +248,77 @@ impl Default for ReadTool {
         Self::new()
     }
 }
+
+/// Lines `offset..offset + limit` of a file and its number of lines, read a
+/// line at a time so the whole file is never in memory
+///
+/// Only the first bytes of each line are kept, enough to show it cut off, so
+/// a file that is one huge line isn't loaded whole either.
+async fn read_line_range(path: &Path, offset: usize, limit: usize) -> Result<(Vec<String>, usize)> {
+    // A few bytes past the shown part so a char cut there stays whole
+    const KEEP: usize = MAX_LINE_BYTES + 4;
+
+    let mut reader = tokio::io::BufReader::new(fs::File::open(path).await?);
+    let mut lines = Vec::new();
+    let mut total_lines = 0;
+    let mut line = Vec::new();
+    let mut in_line = false;
+    loop {
+        let chunk = reader.fill_buf().await?;
+        if chunk.is_empty() {
+            break;
+        }
+        let newline = chunk.iter().position(|&b| b == b'\n');
+        let part = &chunk[..newline.unwrap_or(chunk.len())];
+        let keep = total_lines >= offset && lines.len() < limit;
+        if keep && line.len() < KEEP {
+            line.extend_from_slice(&part[..part.len().min(KEEP - line.len())]);
+        }
+        let used = newline.map_or(chunk.len(), |i| i + 1);
+        reader.consume(used);
+        in_line = newline.is_none();
+        if newline.is_some() {
+            if keep {
+                let text = line.strip_suffix(b"\r").unwrap_or(&line);
+                lines.push(String::from_utf8_lossy(text).into_owned());
+            }
+            line.clear();
+            total_lines += 1;
+        }
+    }
+    // A last line without a newline
+    if in_line {
+        if total_lines >= offset && lines.len() < limit {
+            lines.push(String::from_utf8_lossy(&line).into_owned());
+        }
+        total_lines += 1;
+    }
+    Ok((lines, total_lines))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_read_line_range() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("big.txt");
+        std::fs::write(&path, "one\ntwo\r\nthree\nfour").unwrap();
+
+        let (lines, total) = read_line_range(&path, 1, 2).await.unwrap();
+        assert_eq!(lines, vec!["two", "three"]);
+        assert_eq!(total, 4);
+
+        let (lines, total) = read_line_range(&path, 10, 5).await.unwrap();
+        assert!(lines.is_empty());
+        assert_eq!(total, 4);
+
+        // A huge line is kept only up to a little past what is shown
+        std::fs::write(&path, format!("{}\nlast\n", "x".repeat(100_000))).unwrap();
+        let (lines, total) = read_line_range(&path, 0, 2).await.unwrap();
+        assert!(lines[0].len() > MAX_LINE_BYTES && lines[0].len() < 3000);
+        assert_eq!(lines[1], "last");
+        assert_eq!(total, 2);
+    }
+}
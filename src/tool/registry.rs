@@ -1,6 +1,7 @@
 //! Tool registry for managing available tools.
 
 use super::*;
+use crate::config::Config;
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::RwLock;
@@ -19,9 +20,27 @@ impl ToolRegistry {
 
     /// Create a registry with all default tools
     pub fn with_defaults() -> Self {
-        let _registry = Self::new();
+        Self {
+            tools: RwLock::new(Self::default_tools()),
+        }
+    }
+
+    /// Reset to the default tools, minus those in `disabled_tools` or set to
+    /// `false` in `tools`
+    pub async fn initialize(&self, config: &Config) {
+        let mut tools = Self::default_tools();
+        for id in config.disabled_tools.iter().flatten() {
+            if tools.remove(id).is_none() {
+                tracing::warn!("Unknown tool in disabled_tools: {}", id);
+            }
+        }
+        for (id, _) in config.tools.iter().flatten().filter(|(_, on)| !**on) {
+            tools.remove(id);
+        }
+        *self.tools.write().await = tools;
+    }
 
-        // Register default tools synchronously since we're in a sync context
+    fn default_tools() -> HashMap<String, Arc<dyn Tool>> {
         let mut tools = HashMap::new();
 
         tools.insert(
@@ -65,10 +84,10 @@ impl ToolRegistry {
             Arc::new(WebFetchTool) as Arc<dyn Tool>,
         );
         tools.insert("batch".to_string(), Arc::new(BatchTool) as Arc<dyn Tool>);
-
-        Self {
-            tools: RwLock::new(tools),
-        }
+        tools.insert("git".to_string(), Arc::new(GitTool) as Arc<dyn Tool>);
+        tools.insert("ls".to_string(), Arc::new(LsTool) as Arc<dyn Tool>);
+        tools.insert("test".to_string(), Arc::new(TestTool) as Arc<dyn Tool>);
+        tools
     }
 
     /// Get a tool by ID
@@ -97,6 +116,9 @@ impl ToolRegistry {
                 "todoread".to_string(),
                 "webfetch".to_string(),
                 "batch".to_string(),
+                "git".to_string(),
+                "ls".to_string(),
+                "test".to_string(),
             ]
         }
     }
@@ -121,6 +143,22 @@ impl ToolRegistry {
 
         tool.execute(args, ctx).await
     }
+
+    /// Execute a tool by ID, streaming its output to `sink`
+    pub async fn execute_streaming(
+        &self,
+        tool_id: &str,
+        args: serde_json::Value,
+        ctx: &ToolContext,
+        sink: &ToolOutputSink,
+    ) -> Result<ToolResult> {
+        let tool = self
+            .get(tool_id)
+            .await
+            .ok_or_else(|| anyhow::anyhow!("Tool '{}' not found", tool_id))?;
+
+        tool.execute_streaming(args, ctx, sink).await
+    }
 }
 
 impl Default for ToolRegistry {
@@ -137,3 +175,37 @@ static GLOBAL_REGISTRY: std::sync::LazyLock<Arc<ToolRegistry>> =
 pub fn registry() -> Arc<ToolRegistry> {
     GLOBAL_REGISTRY.clone()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_initialize_disabled_tools() {
+        let registry = ToolRegistry::with_defaults();
+        let config = Config {
+            disabled_tools: Some(vec!["bash".to_string(), "webfetch".to_string()]),
+            tools: Some(HashMap::from([
+                ("grep".to_string(), false),
+                ("glob".to_string(), true),
+            ])),
+            ..Default::default()
+        };
+        registry.initialize(&config).await;
+
+        assert!(registry.get("bash").await.is_none());
+        assert!(registry.get("grep").await.is_none());
+        assert!(registry.get("glob").await.is_some());
+        let names: Vec<String> = registry
+            .definitions()
+            .await
+            .into_iter()
+            .map(|d| d.name)
+            .collect();
+        assert!(!names.contains(&"webfetch".to_string()));
+        assert!(names.contains(&"read".to_string()));
+
+        registry.initialize(&Config::default()).await;
+        assert!(registry.get("bash").await.is_some());
+    }
+}
@@ -0,0 +1,133 @@
+//! Shell selection for tools that run commands.
+//!
+//! Commands run in `shell` from the config, else the user's `$SHELL` (`cmd` on
+//! Windows). Shells that don't take POSIX syntax, such as fish, fall back to
+//! `bash` unless configured explicitly, since the model writes bash. The
+//! command is passed to the shell as a single argument, so quotes and
+//! newlines reach it unchanged. For `cmd`, which takes the rest of its own
+//! command line, the command is wrapped in one more pair of quotes that
+//! `/s /c` removes.
+
+use std::path::Path;
+use tokio::process::Command;
+
+/// Shell used when nothing else is configured or usable
+#[cfg(not(windows))]
+const DEFAULT_SHELL: &str = "bash";
+#[cfg(windows)]
+const DEFAULT_SHELL: &str = "cmd";
+
+/// Login shells whose syntax differs too much from POSIX to run bash commands
+const NON_POSIX_SHELLS: &[&str] = &["fish", "nu", "xonsh", "elvish", "ion"];
+
+/// A shell and the flag that makes it run a command string
+#[derive(Debug, Clone, PartialEq)]
+pub struct Shell {
+    pub program: String,
+    flags: Vec<&'static str>,
+}
+
+impl Shell {
+    /// Resolve the shell from the config value and the environment
+    pub fn resolve(configured: Option<&str>) -> Self {
+        Self::pick(configured, std::env::var("SHELL").ok().as_deref())
+    }
+
+    fn pick(configured: Option<&str>, env_shell: Option<&str>) -> Self {
+        let configured = configured.map(str::trim).filter(|s| !s.is_empty());
+        let program = match configured {
+            Some(program) => program,
+            None => env_shell
+                .filter(|s| !cfg!(windows) && !s.is_empty())
+                .filter(|s| !NON_POSIX_SHELLS.contains(&shell_name(s).as_str()))
+                .unwrap_or(DEFAULT_SHELL),
+        };
+        Self {
+            program: program.to_string(),
+            flags: command_flags(&shell_name(program)),
+        }
+    }
+
+    /// Build a process that runs `command` in this shell
+    pub fn command(&self, command: &str) -> Command {
+        let mut process = Command::new(&self.program);
+        process.args(&self.flags);
+        push_command(&mut process, &self.program, command);
+        process
+    }
+
+    /// Quote an argument as a single word for this shell
+    pub fn quote(&self, arg: &str) -> String {
+        match shell_name(&self.program).as_str() {
+            "cmd" => format!("\"{}\"", arg.replace('"', "\"\"")),
+            "powershell" | "pwsh" => format!("'{}'", arg.replace('\'', "''")),
+            _ => crate::slash_command::parser::sh_quote(arg),
+        }
+    }
+}
+
+#[cfg(not(windows))]
+fn push_command(process: &mut Command, _program: &str, command: &str) {
+    process.arg(command);
+}
+
+#[cfg(windows)]
+fn push_command(process: &mut Command, program: &str, command: &str) {
+    if shell_name(program) == "cmd" {
+        // cmd does its own parsing, so the argument is not escaped; `/s`
+        // strips the outer quotes, keeping those inside the command intact
+        process.raw_arg(format!("\"{}\"", command));
+    } else {
+        process.arg(command);
+    }
+}
+
+/// Lowercase file name of a shell path without extension, e.g. `pwsh`
+fn shell_name(program: &str) -> String {
+    Path::new(program)
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_lowercase())
+        .unwrap_or_default()
+}
+
+fn command_flags(name: &str) -> Vec<&'static str> {
+    match name {
+        "cmd" => vec!["/d", "/s", "/c"],
+        "powershell" | "pwsh" => vec!["-NoProfile", "-NonInteractive", "-Command"],
+        _ => vec!["-c"],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_configured_shell_wins() {
+        let shell = Shell::pick(Some("/usr/bin/fish"), Some("/bin/zsh"));
+        assert_eq!(shell.program, "/usr/bin/fish");
+        assert_eq!(shell.flags, vec!["-c"]);
+
+        let shell = Shell::pick(Some("pwsh.exe"), None);
+        assert_eq!(
+            shell.flags,
+            vec!["-NoProfile", "-NonInteractive", "-Command"]
+        );
+    }
+
+    #[test]
+    fn test_quote_per_shell() {
+        let quote = |program: &str, arg: &str| Shell::pick(Some(program), None).quote(arg);
+        assert_eq!(quote("bash", "it's"), r"'it'\''s'");
+        assert_eq!(quote("pwsh", "it's"), "'it''s'");
+        assert_eq!(quote("cmd.exe", r#"say "hi""#), r#""say ""hi""""#);
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn test_env_shell_unless_not_posix() {
+        assert_eq!(Shell::pick(None, Some("/bin/zsh")).program, "/bin/zsh");
+        assert_eq!(Shell::pick(None, Some("/usr/bin/fish")).program, "bash");
+        assert_eq!(Shell::pick(Some(" "), None).program, "bash");
+    }
+}
@@ -0,0 +1,578 @@
+//! Test tool for running the project's tests and summarizing the results.
+//!
+//! The command comes from `test.command` in the config or is detected from
+//! the project files. Output from cargo, pytest, go test and jest/vitest is
+//! parsed into pass/fail counts and failing tests, so the model gets a short
+//! summary instead of the full log. Output lines are sent to the output sink
+//! while the tests run.
+
+use super::*;
+use anyhow::Result;
+use serde_json::{json, Value};
+use std::path::Path;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+
+/// Default test timeout in milliseconds
+const DEFAULT_TIMEOUT_MS: u64 = 600_000; // 10 minutes
+
+/// Lines of raw output kept from the end of the run
+const RAW_TAIL_LINES: usize = 200;
+
+/// Failing tests listed in the summary
+const MAX_FAILURES: usize = 20;
+
+/// Message lines kept per failing test
+const MAX_MESSAGE_LINES: usize = 10;
+
+/// Tool for running the project's tests
+pub struct TestTool;
+
+/// Counts and failures parsed from test output
+#[derive(Debug, Default, PartialEq)]
+struct TestSummary {
+    passed: u64,
+    failed: u64,
+    ignored: u64,
+    failures: Vec<TestFailure>,
+}
+
+#[derive(Debug, PartialEq)]
+struct TestFailure {
+    name: String,
+    message: String,
+}
+
+/// Reasons a test run produced no output
+enum RunError {
+    Timeout,
+    Aborted,
+    Execution(anyhow::Error),
+}
+
+#[async_trait::async_trait]
+impl Tool for TestTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "test".to_string(),
+            description: r#"Runs the project's tests and returns a summary.
+- Uses the configured test command, or detects one (cargo test, npm test, go test, pytest)
+- Reports pass/fail counts and the names and messages of failing tests
+- Pass `filter` to run only matching tests
+- Prefer this over bash for running tests"#
+                .to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "filter": {
+                        "type": "string",
+                        "description": "Only run tests matching this name or path"
+                    },
+                    "command": {
+                        "type": "string",
+                        "description": "Test command to run instead of the configured or detected one"
+                    },
+                    "timeout": {
+                        "type": "number",
+                        "description": "Timeout in milliseconds (default: 600000)"
+                    }
+                }
+            }),
+        }
+    }
+
+    async fn execute(&self, args: Value, ctx: &ToolContext) -> Result<ToolResult> {
+        self.execute_streaming(args, ctx, &ToolOutputSink::default())
+            .await
+    }
+
+    async fn execute_streaming(
+        &self,
+        args: Value,
+        ctx: &ToolContext,
+        sink: &ToolOutputSink,
+    ) -> Result<ToolResult> {
+        if ctx.is_aborted() {
+            return Ok(ToolResult::error(
+                "Tests aborted",
+                "The test run was aborted before it started.",
+            ));
+        }
+
+        let config = crate::config::Config::load().await.unwrap_or_default();
+        let shell = Shell::resolve(config.shell.as_deref());
+        let config = config.test.unwrap_or_default();
+
+        let base = match args.get("command").and_then(|v| v.as_str()) {
+            Some(command) => command.to_string(),
+            None => match config.command.or_else(|| detect_command(Path::new(&ctx.cwd))) {
+                Some(command) => command,
+                None => {
+                    return Ok(ToolResult::error(
+                        "No test command",
+                        "Could not detect how to run this project's tests. Set \"test.command\" in the config or pass `command`.",
+                    ))
+                }
+            },
+        };
+        let filter = args.get("filter").and_then(|v| v.as_str());
+        let command = with_filter(&base, filter, &shell);
+        let timeout_ms = args
+            .get("timeout")
+            .and_then(|v| v.as_u64())
+            .or(config.timeout)
+            .unwrap_or(DEFAULT_TIMEOUT_MS);
+
+        // Tests run arbitrary code, so they share the bash permission
+        let metadata = HashMap::from([
+            ("command".to_string(), json!(command)),
+            ("workdir".to_string(), json!(ctx.cwd)),
+            ("timeout".to_string(), json!(timeout_ms)),
+        ]);
+        if let Some(denied) = ctx
+            .require_permission("bash", vec![command.clone()], metadata)
+            .await?
+        {
+            return Ok(denied);
+        }
+
+        let start = std::time::Instant::now();
+        let timeout = Duration::from_millis(timeout_ms);
+        match run_tests(&shell, &command, &ctx.cwd, timeout, ctx, sink).await {
+            Ok((output, exit_code)) => {
+                Ok(build_result(&command, &output, exit_code, start.elapsed()))
+            }
+            Err(RunError::Timeout) => Ok(ToolResult::error(
+                "Tests timed out",
+                format!(
+                    "Tests timed out after {}ms\nCommand: {}",
+                    timeout_ms, command
+                ),
+            )),
+            Err(RunError::Aborted) => Ok(ToolResult::error(
+                "Tests aborted",
+                format!("The test run was aborted.\nCommand: {}", command),
+            )),
+            Err(RunError::Execution(e)) => Ok(ToolResult::error(
+                "Tests failed to run",
+                format!("Failed to execute '{}': {}", command, e),
+            )),
+        }
+    }
+}
+
+/// Detect the test command from the files in the project directory
+fn detect_command(dir: &Path) -> Option<String> {
+    if dir.join("Cargo.toml").exists() {
+        return Some("cargo test".to_string());
+    }
+    if let Ok(package) = std::fs::read_to_string(dir.join("package.json")) {
+        let package: Value = serde_json::from_str(&package).unwrap_or_default();
+        if package.pointer("/scripts/test").is_some() {
+            return Some("npm test --".to_string());
+        }
+    }
+    if dir.join("go.mod").exists() {
+        return Some("go test ./...".to_string());
+    }
+    ["pyproject.toml", "pytest.ini", "setup.py", "tox.ini"]
+        .iter()
+        .any(|file| dir.join(file).exists())
+        .then(|| "pytest".to_string())
+}
+
+/// Append a test filter to the command in the form its runner expects,
+/// quoted for the shell that runs it
+fn with_filter(command: &str, filter: Option<&str>, shell: &Shell) -> String {
+    let Some(filter) = filter.filter(|f| !f.trim().is_empty()) else {
+        return command.to_string();
+    };
+    let quoted = shell.quote(filter);
+    if command.starts_with("go test") {
+        format!("{} -run {}", command, quoted)
+    } else if command.starts_with("pytest") {
+        format!("{} -k {}", command, quoted)
+    } else {
+        format!("{} {}", command, quoted)
+    }
+}
+
+/// Run the test command, returning its combined output and exit code
+///
+/// Each output line is also sent to `sink` as it arrives.
+async fn run_tests(
+    shell: &Shell,
+    command: &str,
+    workdir: &str,
+    timeout: Duration,
+    ctx: &ToolContext,
+    sink: &ToolOutputSink,
+) -> std::result::Result<(String, i32), RunError> {
+    let mut child = shell
+        .command(command)
+        .current_dir(workdir)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| RunError::Execution(e.into()))?;
+    let (stdout, stderr) = (child.stdout.take(), child.stderr.take());
+
+    let run = async {
+        let (stdout, stderr) = tokio::join!(read_lines(stdout, sink), read_lines(stderr, sink));
+        let status = child.wait().await?;
+        Ok::<_, std::io::Error>((stdout, stderr, status))
+    };
+
+    // Dropping the child on timeout or abort kills it
+    let (mut combined, stderr, status) = tokio::select! {
+        result = tokio::time::timeout(timeout, run) => match result {
+            Ok(output) => output.map_err(|e| RunError::Execution(e.into()))?,
+            Err(_) => return Err(RunError::Timeout),
+        },
+        _ = ctx.aborted() => return Err(RunError::Aborted),
+    };
+
+    if !stderr.trim().is_empty() {
+        if !combined.is_empty() && !combined.ends_with('\n') {
+            combined.push('\n');
+        }
+        combined.push_str(&stderr);
+    }
+    Ok((combined, status.code().unwrap_or(-1)))
+}
+
+/// Read a child's output stream to the end, sending each line to `sink`
+///
+/// Invalid UTF-8 is replaced rather than ending the read, so the child never
+/// blocks on a full pipe.
+async fn read_lines<R: AsyncRead + Unpin>(reader: Option<R>, sink: &ToolOutputSink) -> String {
+    let mut output = String::new();
+    let Some(reader) = reader else {
+        return output;
+    };
+    let mut reader = BufReader::new(reader);
+    let mut buf = Vec::new();
+    while matches!(reader.read_until(b'\n', &mut buf).await, Ok(n) if n > 0) {
+        let line = String::from_utf8_lossy(&buf);
+        sink.chunk(line.trim_end_matches(['\n', '\r']));
+        output.push_str(&line);
+        buf.clear();
+    }
+    output
+}
+
+/// Build the tool result from the summary and the tail of the raw output
+fn build_result(command: &str, output: &str, exit_code: i32, duration: Duration) -> ToolResult {
+    let summary = parse_summary(output);
+    let status = if exit_code == 0 { "passed" } else { "failed" };
+
+    let title = match &summary {
+        Some(s) if s.failed > 0 => format!("{} failed, {} passed", s.failed, s.passed),
+        Some(s) => format!("{} passed", s.passed),
+        None => format!("Tests {} (exit code {})", status, exit_code),
+    };
+
+    let mut text = format!(
+        "Command: {}\nResult: {} (exit code {})\n",
+        command, status, exit_code
+    );
+    match &summary {
+        Some(s) => {
+            text.push_str(&format!(
+                "Passed: {}, Failed: {}, Ignored: {}\n",
+                s.passed, s.failed, s.ignored
+            ));
+            if !s.failures.is_empty() {
+                text.push_str("\nFailures:\n");
+                for failure in s.failures.iter().take(MAX_FAILURES) {
+                    text.push_str(&format!("- {}\n", failure.name));
+                    for line in failure.message.lines().take(MAX_MESSAGE_LINES) {
+                        text.push_str(&format!("    {}\n", line));
+                    }
+                }
+                if s.failures.len() > MAX_FAILURES {
+                    text.push_str(&format!(
+                        "... and {} more\n",
+                        s.failures.len() - MAX_FAILURES
+                    ));
+                }
+            }
+        }
+        None => text.push_str("Could not parse test results; see the output below.\n"),
+    }
+
+    let lines: Vec<&str> = output.lines().collect();
+    let tail = &lines[lines.len().saturating_sub(RAW_TAIL_LINES)..];
+    text.push_str(&format!(
+        "\n--- Output (last {} of {} lines) ---\n",
+        tail.len(),
+        lines.len()
+    ));
+    text.push_str(&tail.join("\n"));
+    let (text, truncated) = truncate_output(&text);
+
+    let mut result = ToolResult::success(title, text)
+        .with_metadata("exitCode", json!(exit_code))
+        .with_metadata("duration", json!(duration.as_millis()))
+        .with_metadata("command", json!(command));
+    if let Some(s) = &summary {
+        result = result
+            .with_metadata("passed", json!(s.passed))
+            .with_metadata("failed", json!(s.failed));
+    }
+    result.truncated = truncated || tail.len() < lines.len();
+    result
+}
+
+/// Parse test output from any of the supported runners
+fn parse_summary(output: &str) -> Option<TestSummary> {
+    parse_cargo(output)
+        .or_else(|| parse_pytest(output))
+        .or_else(|| parse_go(output))
+        .or_else(|| parse_jest(output))
+}
+
+/// `cargo test`: one `test result:` line per test binary
+fn parse_cargo(output: &str) -> Option<TestSummary> {
+    let mut summary = TestSummary::default();
+    let mut found = false;
+    for line in output.lines() {
+        if let Some(rest) = line.strip_prefix("test result: ") {
+            found = true;
+            summary.passed += count_before(rest, " passed");
+            summary.failed += count_before(rest, " failed");
+            summary.ignored += count_before(rest, " ignored");
+        } else if let Some(name) = line
+            .strip_prefix("test ")
+            .and_then(|l| l.strip_suffix(" ... FAILED"))
+        {
+            summary.failures.push(TestFailure {
+                name: name.to_string(),
+                message: cargo_failure_message(output, name),
+            });
+        }
+    }
+    found.then_some(summary)
+}
+
+/// The captured output printed for a failed cargo test
+fn cargo_failure_message(output: &str, name: &str) -> String {
+    let header = format!("---- {} stdout ----", name);
+    output
+        .lines()
+        .skip_while(|line| line.trim() != header)
+        .skip(1)
+        .take_while(|line| !line.starts_with("---- ") && line.trim() != "failures:")
+        .filter(|line| !line.trim().is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// pytest: `FAILED path::test - message` lines and a `N passed, M failed` footer
+fn parse_pytest(output: &str) -> Option<TestSummary> {
+    let mut summary = TestSummary::default();
+    let mut found = false;
+    for line in output.lines() {
+        let trimmed = line.trim().trim_matches('=').trim();
+        if let Some(rest) = line.strip_prefix("FAILED ") {
+            let (name, message) = rest.split_once(" - ").unwrap_or((rest, ""));
+            summary.failures.push(TestFailure {
+                name: name.to_string(),
+                message: message.to_string(),
+            });
+        } else if line.starts_with('=')
+            && (trimmed.contains(" passed") || trimmed.contains(" failed"))
+            && trimmed.contains(" in ")
+        {
+            found = true;
+            summary.passed = count_before(trimmed, " passed");
+            summary.failed = count_before(trimmed, " failed") + count_before(trimmed, " error");
+            summary.ignored = count_before(trimmed, " skipped");
+        }
+    }
+    found.then_some(summary)
+}
+
+/// go test: `--- PASS:` / `--- FAIL:` lines per test
+fn parse_go(output: &str) -> Option<TestSummary> {
+    let mut summary = TestSummary::default();
+    let mut found = false;
+    let lines: Vec<&str> = output.lines().collect();
+    for (i, line) in lines.iter().enumerate() {
+        let line = line.trim_start();
+        if line.starts_with("--- PASS: ") {
+            found = true;
+            summary.passed += 1;
+        } else if line.starts_with("--- SKIP: ") {
+            found = true;
+            summary.ignored += 1;
+        } else if let Some(rest) = line.strip_prefix("--- FAIL: ") {
+            found = true;
+            summary.failed += 1;
+            let name = rest.split_whitespace().next().unwrap_or(rest);
+            let message = lines[i + 1..]
+                .iter()
+                .take_while(|l| l.starts_with("    ") && !l.trim_start().starts_with("--- "))
+                .map(|l| l.trim())
+                .collect::<Vec<_>>()
+                .join("\n");
+            summary.failures.push(TestFailure {
+                name: name.to_string(),
+                message,
+            });
+        }
+    }
+    found.then_some(summary)
+}
+
+/// jest and vitest: a `Tests:` footer line and `●` headers for failures
+fn parse_jest(output: &str) -> Option<TestSummary> {
+    let mut summary = TestSummary::default();
+    let mut found = false;
+    for line in output.lines() {
+        let trimmed = line.trim();
+        if let Some(rest) = trimmed.strip_prefix("Tests:") {
+            found = true;
+            summary.passed = count_before(rest, " passed");
+            summary.failed = count_before(rest, " failed");
+            summary.ignored = count_before(rest, " skipped") + count_before(rest, " todo");
+        } else if let Some(name) = trimmed.strip_prefix("● ") {
+            if !summary.failures.iter().any(|f| f.name == name) {
+                summary.failures.push(TestFailure {
+                    name: name.to_string(),
+                    message: String::new(),
+                });
+            }
+        }
+    }
+    found.then_some(summary)
+}
+
+/// The number right before `label`, e.g. 3 in `3 passed; 0 failed`
+fn count_before(text: &str, label: &str) -> u64 {
+    text.find(label)
+        .and_then(|end| text[..end].split(|c: char| !c.is_ascii_digit()).last())
+        .and_then(|digits| digits.parse().ok())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cargo_output() {
+        let output = "\
+running 3 tests
+test a::works ... ok
+test a::breaks ... FAILED
+test a::slow ... ignored
+
+failures:
+
+---- a::breaks stdout ----
+thread 'a::breaks' panicked at src/a.rs:10:5:
+assertion `left == right` failed
+
+failures:
+    a::breaks
+
+test result: FAILED. 1 passed; 1 failed; 1 ignored; 0 measured; 0 filtered out
+
+running 2 tests
+test result: ok. 2 passed; 0 failed; 0 ignored; 0 measured; 0 filtered out
+";
+        let summary = parse_summary(output).unwrap();
+        assert_eq!((summary.passed, summary.failed, summary.ignored), (3, 1, 1));
+        assert_eq!(summary.failures.len(), 1);
+        assert_eq!(summary.failures[0].name, "a::breaks");
+        assert!(summary.failures[0]
+            .message
+            .contains("panicked at src/a.rs:10:5"));
+    }
+
+    #[test]
+    fn test_parse_pytest_go_and_jest_output() {
+        let pytest = "\
+FAILED tests/test_x.py::test_y - AssertionError: 1 != 2
+=========== 1 failed, 4 passed, 1 skipped in 0.12s ===========";
+        let summary = parse_summary(pytest).unwrap();
+        assert_eq!((summary.passed, summary.failed, summary.ignored), (4, 1, 1));
+        assert_eq!(summary.failures[0].name, "tests/test_x.py::test_y");
+        assert_eq!(summary.failures[0].message, "AssertionError: 1 != 2");
+
+        let go = "\
+=== RUN   TestAdd
+--- PASS: TestAdd (0.00s)
+=== RUN   TestSub
+--- FAIL: TestSub (0.00s)
+    math_test.go:12: got 1, want 2
+FAIL";
+        let summary = parse_summary(go).unwrap();
+        assert_eq!((summary.passed, summary.failed), (1, 1));
+        assert_eq!(summary.failures[0].name, "TestSub");
+        assert_eq!(
+            summary.failures[0].message,
+            "math_test.go:12: got 1, want 2"
+        );
+
+        let jest = "\
+  ● math › subtracts
+
+Tests:       1 failed, 1 skipped, 5 passed, 7 total";
+        let summary = parse_summary(jest).unwrap();
+        assert_eq!((summary.passed, summary.failed, summary.ignored), (5, 1, 1));
+        assert_eq!(summary.failures[0].name, "math › subtracts");
+    }
+
+    #[test]
+    fn test_unrecognized_output() {
+        assert_eq!(parse_summary("make: *** [test] Error 2"), None);
+    }
+
+    #[test]
+    fn test_filter_is_passed_per_runner() {
+        let sh = Shell::resolve(Some("bash"));
+        assert_eq!(
+            with_filter("cargo test", Some("parse"), &sh),
+            "cargo test 'parse'"
+        );
+        assert_eq!(
+            with_filter("go test ./...", Some("TestA"), &sh),
+            "go test ./... -run 'TestA'"
+        );
+        assert_eq!(
+            with_filter("pytest", Some("it's"), &sh),
+            r"pytest -k 'it'\''s'"
+        );
+        assert_eq!(with_filter("npm test --", Some(" "), &sh), "npm test --");
+
+        let cmd = Shell::resolve(Some("cmd"));
+        assert_eq!(
+            with_filter("cargo test", Some("a b"), &cmd),
+            r#"cargo test "a b""#
+        );
+    }
+
+    #[tokio::test]
+    async fn test_output_lines_reach_the_sink() {
+        let chunks = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let collected = chunks.clone();
+        let sink = ToolOutputSink::new(
+            "call_1",
+            Some(std::sync::Arc::new(move |chunk: ToolOutputChunk| {
+                collected.lock().unwrap().push(chunk.text)
+            })),
+        );
+
+        let output = read_lines(Some(&b"running 2 tests\r\nok \xff\nend"[..]), &sink).await;
+        assert_eq!(output, "running 2 tests\r\nok \u{fffd}\nend");
+        assert_eq!(
+            *chunks.lock().unwrap(),
+            vec!["running 2 tests", "ok \u{fffd}", "end"]
+        );
+    }
+}
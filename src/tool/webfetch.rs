@@ -28,6 +28,7 @@ Usage notes:
 const MAX_RESPONSE_SIZE: usize = 5 * 1024 * 1024; // 5MB
 const DEFAULT_TIMEOUT: u64 = 30; // 30 seconds
 const MAX_TIMEOUT: u64 = 120; // 2 minutes
+const PROGRESS_INTERVAL: usize = 256 * 1024; // Report download progress every 256KB
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WebFetchParams {
@@ -74,6 +75,16 @@ impl Tool for WebFetchTool {
     }
 
     async fn execute(&self, args: Value, ctx: &ToolContext) -> Result<ToolResult> {
+        self.execute_streaming(args, ctx, &ToolOutputSink::default())
+            .await
+    }
+
+    async fn execute_streaming(
+        &self,
+        args: Value,
+        ctx: &ToolContext,
+        sink: &ToolOutputSink,
+    ) -> Result<ToolResult> {
         let params: WebFetchParams = serde_json::from_value(args)?;
 
         // Validate URL
@@ -107,7 +118,7 @@ impl Tool for WebFetchTool {
             .ask_permission(
                 "webfetch".to_string(),
                 vec![url.clone()],
-                vec![domain_pattern, "*".to_string()],
+                vec![domain_pattern],
                 metadata,
             )
             .await?;
@@ -149,7 +160,7 @@ impl Tool for WebFetchTool {
         }
 
         // Fetch the URL
-        let response = client
+        let mut response = client
             .get(&url)
             .header("Accept", accept_header)
             .header("Accept-Language", "en-US,en;q=0.9")
@@ -181,17 +192,26 @@ impl Tool for WebFetchTool {
             .unwrap_or("")
             .to_string();
 
-        // Read response body
-        let bytes = response
-            .bytes()
+        // Read response body, reporting progress as it downloads
+        let total = response.content_length();
+        let mut bytes = Vec::new();
+        let mut next_report = PROGRESS_INTERVAL;
+        while let Some(chunk) = response
+            .chunk()
             .await
-            .map_err(|e| anyhow::anyhow!("Failed to read response: {}", e))?;
-
-        if bytes.len() > MAX_RESPONSE_SIZE {
-            return Ok(ToolResult::error(
-                "Response too large",
-                "Response exceeds 5MB limit",
-            ));
+            .map_err(|e| anyhow::anyhow!("Failed to read response: {}", e))?
+        {
+            bytes.extend_from_slice(&chunk);
+            if bytes.len() > MAX_RESPONSE_SIZE {
+                return Ok(ToolResult::error(
+                    "Response too large",
+                    "Response exceeds 5MB limit",
+                ));
+            }
+            if bytes.len() >= next_report {
+                sink.chunk(&download_progress(bytes.len(), total));
+                next_report = bytes.len() + PROGRESS_INTERVAL;
+            }
         }
 
         // Convert to string
@@ -244,6 +264,14 @@ impl Tool for WebFetchTool {
     }
 }
 
+/// Describe download progress, e.g. `Downloaded 512KB of 2048KB`
+fn download_progress(received: usize, total: Option<u64>) -> String {
+    match total {
+        Some(total) => format!("Downloaded {}KB of {}KB", received / 1024, total / 1024),
+        None => format!("Downloaded {}KB", received / 1024),
+    }
+}
+
 /// Convert HTML to Markdown (simplified version)
 fn convert_html_to_markdown(html: &str) -> String {
     // This is a very basic implementation
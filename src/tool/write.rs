@@ -1,6 +1,7 @@
 //! Write tool for creating/overwriting files.
 
 use super::*;
+use crate::session::FileDiff;
 use anyhow::Result;
 use serde_json::{json, Value};
 use tokio::fs;
@@ -71,6 +72,12 @@ impl Tool for WriteTool {
         } else {
             0
         };
+        // Previous content for the diff (binary files are treated as empty)
+        let old_content = if existed {
+            fs::read_to_string(&path).await.unwrap_or_default()
+        } else {
+            String::new()
+        };
 
         let display_path = path.display().to_string();
 
@@ -93,6 +100,7 @@ impl Tool for WriteTool {
 
         let lines = content.lines().count();
         let bytes = content.len();
+        let file_diff = FileDiff::compute(&display_path, &old_content, content);
 
         let title = if existed {
             format!("Updated {} ({} lines)", display_path, lines)
@@ -119,6 +127,9 @@ impl Tool for WriteTool {
                 m.insert("created".to_string(), json!(!existed));
                 m.insert("lines".to_string(), json!(lines));
                 m.insert("bytes".to_string(), json!(bytes));
+                m.insert("additions".to_string(), json!(file_diff.additions));
+                m.insert("deletions".to_string(), json!(file_diff.deletions));
+                m.insert("diff".to_string(), json!(file_diff.diff));
                 m
             },
             truncated: false,
@@ -4,33 +4,38 @@
 //! The App state is defined in state.rs.
 
 use anyhow::Result;
-use crossterm::{
-    event::{self, Event, KeyCode, KeyEvent, KeyModifiers},
-    execute,
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
-};
-use ratatui::{backend::CrosstermBackend, Terminal};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, MouseButton, MouseEvent, MouseEventKind};
+use ratatui::{backend::CrosstermBackend, layout::Rect, Terminal};
 use std::io;
-use std::time::Duration;
 use tokio::sync::mpsc;
 
 use super::command_handler::handle_command_output;
 use super::dialog::handle_dialog_input;
+use super::dialog_render::select_item_at;
+use super::frame::{self, FrameStats};
 use super::input::{key_to_action, Action};
-use super::llm_streaming::stream_response_agentic;
-use super::ui;
+use super::keymap::KeyAction;
+use super::llm_streaming::{stream_response_agentic, StreamOptions};
+use super::notify::Attention;
+use super::phase::TurnPhase;
+use super::{terminal, ui};
 
 // Re-export App for backward compatibility
 pub use super::state::App;
 
 // Re-export types for backward compatibility
+use super::types::DialogType;
 pub use super::types::{AppEvent, AutocompleteState};
-use crate::config::Config;
 use crate::provider;
-use crate::slash_command::{parser::ParsedCommand, CommandContext};
+use crate::slash_command::{parser::ParsedCommand, CommandContext, DisplayToggle};
+use crate::tool::BatchDecision;
 
 /// Run the TUI application
-pub async fn run(initial_prompt: Option<String>, model: Option<String>) -> Result<()> {
+pub async fn run(
+    initial_prompt: Option<String>,
+    model: Option<String>,
+    append_system: Option<String>,
+) -> Result<()> {
     // Check if we're running in a TTY
     use std::io::IsTerminal;
     if !std::io::stdout().is_terminal() {
@@ -41,18 +46,22 @@ pub async fn run(initial_prompt: Option<String>, model: Option<String>) -> Resul
         );
     }
 
+    // Create app before taking over the terminal, so errors print normally
+    let mut app = App::new(model).await?;
+    if append_system.is_some() {
+        app.append_system(append_system).await?;
+    }
+
     // Setup terminal
-    enable_raw_mode()?;
-    let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen)?;
-    let backend = CrosstermBackend::new(stdout);
+    terminal::setup(app.mouse_capture)?;
+    let backend = CrosstermBackend::new(io::stdout());
     let mut terminal = Terminal::new(backend)?;
 
-    // Create app
-    let mut app = App::new(model).await?;
-
-    // If no model configured, open provider/model selector
-    if !app.model_configured {
+    // On first run guide through setup; else if no model is configured,
+    // open the provider/model selector
+    if app.onboarding {
+        app.start_onboarding();
+    } else if !app.model_configured {
         if app.available_providers.is_empty() {
             app.open_provider_selector();
         } else {
@@ -60,10 +69,20 @@ pub async fn run(initial_prompt: Option<String>, model: Option<String>) -> Resul
         }
     }
 
-    // If there's an initial prompt, set it as input
+    // Reopen questions the session's last run left unanswered
+    if app.dialog.is_none() {
+        if let Some(session_id) = app.session.as_ref().map(|s| s.id.clone()) {
+            app.restore_questions(&session_id).await;
+        }
+    }
+
+    // If there's an initial prompt, set it as input; else offer the unsent
+    // input of a crashed run
     if let Some(prompt) = initial_prompt {
         app.input = prompt;
         app.cursor_position = app.input.len();
+    } else if let Some(draft) = app.draft.recover() {
+        app.offer_draft_recovery(draft);
     }
 
     // Event channel for async processing
@@ -73,9 +92,12 @@ pub async fn run(initial_prompt: Option<String>, model: Option<String>) -> Resul
     let result = run_app(&mut terminal, &mut app, event_tx, &mut event_rx).await;
 
     // Restore terminal
-    disable_raw_mode()?;
-    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
-    terminal.show_cursor()?;
+    terminal::restore();
+
+    // A clean exit leaves nothing to recover
+    if result.is_ok() {
+        app.draft.clear();
+    }
 
     result
 }
@@ -164,21 +186,73 @@ async fn execute_slash_command(
     Ok(())
 }
 
+/// Expand prompt macros such as `!file:` in a submitted prompt
+async fn expand_macros(input: &str) -> Result<String> {
+    let config = crate::config::Config::load().await.unwrap_or_default();
+    let cwd = std::env::current_dir()?;
+    crate::session::macros::expand(input, &config, &cwd).await
+}
+
 /// Start streaming response from LLM
 fn start_llm_stream(app: &mut App, input: &str, event_tx: &mpsc::Sender<AppEvent>) {
+    if let Err(e) = provider::offline::ensure_online() {
+        app.add_message("system", &e.to_string());
+        app.recall_history(input.to_string());
+        return;
+    }
     app.add_message("user", input);
     app.is_processing = true;
-    app.status = "Processing".to_string();
+    app.begin_turn_phase();
     app.add_message("assistant", "");
 
+    let options = turn_options(app);
+    let (provider_id, model_id) = (app.provider_id.clone(), app.model_id.clone());
+    spawn_turn(app, provider_id, model_id, input, options, event_tx);
+}
+
+/// Stream options of a turn from the session settings
+///
+/// Also arms `turn_abort`, so the turn can be cancelled.
+pub(super) fn turn_options(app: &mut App) -> StreamOptions {
+    let (abort_tx, abort_rx) = tokio::sync::watch::channel(false);
+    app.turn_abort = Some(abort_tx);
+    StreamOptions {
+        max_output: app.max_output_tokens,
+        prefill: app.prefill.clone(),
+        json_schema: app.json_schema.clone(),
+        abort: Some(abort_rx),
+        system: app.session.as_ref().and_then(|s| s.system.clone()),
+        pinned: app
+            .session
+            .as_ref()
+            .map(|s| s.pinned.clone())
+            .unwrap_or_default(),
+        permission: app.session.as_ref().and_then(|s| s.permission.clone()),
+        ..Default::default()
+    }
+}
+
+/// Run an agentic turn in the background, streaming into the last message
+pub(super) fn spawn_turn(
+    app: &App,
+    provider_id: String,
+    model_id: String,
+    prompt: &str,
+    options: StreamOptions,
+    event_tx: &mpsc::Sender<AppEvent>,
+) {
     let tx = event_tx.clone();
-    let provider_id = app.provider_id.clone();
-    let model_id = app.model_id.clone();
-    let prompt = input.to_string();
+    let session_id = app
+        .session
+        .as_ref()
+        .map(|s| s.id.clone())
+        .unwrap_or_default();
+    let prompt = prompt.to_string();
 
     tokio::spawn(async move {
         // Error is already handled inside stream_response_agentic via the event_tx
-        let _ = stream_response_agentic(provider_id, model_id, prompt, tx).await;
+        let _ =
+            stream_response_agentic(provider_id, model_id, session_id, prompt, options, tx).await;
     });
 }
 
@@ -192,37 +266,168 @@ async fn handle_submit(app: &mut App, event_tx: &mpsc::Sender<AppEvent>) -> Resu
     if let Some(input) = app.take_input() {
         // Add to input history (before processing)
         app.add_input_to_history(&input);
+        app.draft.clear();
 
         if input.trim() == "/" {
             show_slash_command_help(app).await;
         } else if let Some(parsed) = ParsedCommand::parse(&input) {
             execute_slash_command(app, &parsed, event_tx).await?;
         } else {
+            let input = match expand_macros(&input).await {
+                Ok(input) => input,
+                Err(e) => {
+                    app.add_message("system", &format!("Error: {:#}", e));
+                    app.recall_history(input);
+                    return Ok(());
+                }
+            };
+            if let Some(edit) = app.editing.take() {
+                if let Err(e) = app.truncate_for_edit(edit).await {
+                    app.add_message("system", &format!("Failed to replace message: {}", e));
+                    return Ok(());
+                }
+            }
             start_llm_stream(app, &input, event_tx);
         }
     }
     Ok(())
 }
 
-/// Handle keyboard shortcuts (Ctrl+M, Ctrl+P)
-fn handle_keyboard_shortcuts(app: &mut App, key: &KeyEvent) -> bool {
-    if key.modifiers != KeyModifiers::CONTROL {
-        return false;
-    }
-
-    match key.code {
-        KeyCode::Char('m') => {
+/// Resolve a key event through the keymap
+///
+/// Bindable actions come only from the keymap, so remapped defaults no longer
+/// trigger. Dialog shortcuts are handled here and resolve to `Action::None`.
+fn resolve_key_action(app: &mut App, key: KeyEvent) -> Action {
+    let bound = app.keymap.lookup(&key).map(|a| app.input_key_action(a));
+    match bound {
+        Some(KeyAction::InputModeToggle) => {
+            app.multiline_input = !app.multiline_input;
+            Action::None
+        }
+        Some(KeyAction::AppExit) => Action::Quit,
+        Some(KeyAction::InputSubmit) => Action::Submit,
+        Some(KeyAction::InputNewline) => Action::Newline,
+        Some(KeyAction::InputClear) => Action::ClearInput,
+        Some(KeyAction::SessionInterrupt) => Action::Cancel,
+        Some(KeyAction::ModelList) => {
             app.open_model_selector();
-            true
+            Action::None
         }
-        KeyCode::Char('p') => {
+        Some(KeyAction::ProviderList) => {
             app.open_provider_selector();
-            true
+            Action::None
+        }
+        Some(KeyAction::CopyCode) => {
+            app.copy_code_block(false);
+            Action::None
+        }
+        Some(KeyAction::EditorOpen) => {
+            app.open_external_editor();
+            Action::None
+        }
+        Some(KeyAction::HistorySearch) => {
+            app.open_history_search();
+            Action::None
+        }
+        Some(KeyAction::ToggleTools) => {
+            app.toggle_display(DisplayToggle::Tools);
+            Action::None
+        }
+        Some(KeyAction::ToggleMetadata) => {
+            app.toggle_display(DisplayToggle::Metadata);
+            Action::None
+        }
+        Some(KeyAction::ToggleSystem) => {
+            app.toggle_display(DisplayToggle::System);
+            Action::None
+        }
+        Some(KeyAction::ToolSelectPrev) => {
+            app.select_tool(-1);
+            Action::None
+        }
+        Some(KeyAction::ToolSelectNext) => {
+            app.select_tool(1);
+            Action::None
+        }
+        Some(KeyAction::ToolExpand) => {
+            app.toggle_tool_expanded();
+            Action::None
+        }
+        Some(KeyAction::VariantPrev) => {
+            app.cycle_reply_variant(-1);
+            Action::None
         }
-        _ => false,
+        Some(KeyAction::VariantNext) => {
+            app.cycle_reply_variant(1);
+            Action::None
+        }
+        Some(KeyAction::MessageNext) => {
+            app.focus_message(1);
+            Action::None
+        }
+        Some(KeyAction::MessageCopy) => {
+            app.copy_focused_message();
+            Action::None
+        }
+        // Handled in handle_key_input, since scrolling may load older messages
+        // and editing looks up the stored message
+        Some(
+            KeyAction::MessagesPageUp
+            | KeyAction::MessagesPageDown
+            | KeyAction::MessagePrev
+            | KeyAction::MessageEdit,
+        ) => Action::None,
+        None => match key_to_action(key) {
+            Action::Quit
+            | Action::Submit
+            | Action::Newline
+            | Action::ClearInput
+            | Action::Cancel => Action::None,
+            action => action,
+        },
     }
 }
 
+/// Handle mouse input: the wheel scrolls, a click picks a dialog item
+///
+/// Over a list dialog the wheel moves the selection and a click selects the
+/// item under the pointer; clicking the selected item opens it like Enter.
+/// Otherwise the wheel scrolls the transcript.
+async fn handle_mouse_input(
+    app: &mut App,
+    mouse: MouseEvent,
+    screen: Rect,
+    event_tx: &mpsc::Sender<AppEvent>,
+) -> Result<()> {
+    let Some(dialog) = &mut app.dialog else {
+        match mouse.kind {
+            MouseEventKind::ScrollUp => app.scroll_messages_up().await,
+            MouseEventKind::ScrollDown => app.scroll_messages_down(),
+            _ => {}
+        }
+        return Ok(());
+    };
+
+    let scrollable =
+        dialog.dialog_type.uses_select_list() || dialog.dialog_type == DialogType::DiffView;
+    let key = match mouse.kind {
+        MouseEventKind::ScrollUp if scrollable => KeyCode::Up,
+        MouseEventKind::ScrollDown if scrollable => KeyCode::Down,
+        MouseEventKind::Down(MouseButton::Left) if dialog.dialog_type.uses_select_list() => {
+            match select_item_at(dialog, screen, mouse.column, mouse.row) {
+                Some(position) if position == dialog.selected_index => KeyCode::Enter,
+                Some(position) => {
+                    dialog.selected_index = position;
+                    return Ok(());
+                }
+                None => return Ok(()),
+            }
+        }
+        _ => return Ok(()),
+    };
+    handle_dialog_input(app, KeyEvent::from(key), event_tx).await
+}
+
 /// Handle keyboard input in the main loop
 async fn handle_key_input(
     app: &mut App,
@@ -240,20 +445,37 @@ async fn handle_key_input(
         return Ok(());
     }
 
-    // Check for keyboard shortcuts
-    if handle_keyboard_shortcuts(app, &key) {
-        return Ok(());
+    match app.keymap.lookup(&key) {
+        Some(KeyAction::MessagesPageUp) => {
+            app.scroll_messages_up().await;
+            return Ok(());
+        }
+        Some(KeyAction::MessagesPageDown) => {
+            app.scroll_messages_down();
+            return Ok(());
+        }
+        Some(KeyAction::MessagePrev) => {
+            app.focus_older_message().await;
+            return Ok(());
+        }
+        Some(KeyAction::MessageEdit) => {
+            app.edit_focused_message().await;
+            return Ok(());
+        }
+        _ => {}
     }
 
-    let action = key_to_action(key);
+    let action = resolve_key_action(app, key);
 
     match action {
         Action::Submit if !app.is_processing => {
             handle_submit(app, event_tx).await?;
         }
         Action::Cancel if app.is_processing => {
-            app.is_processing = false;
-            app.status = "Ready".to_string();
+            app.abort_turn();
+        }
+        Action::Cancel if app.editing.is_some() => {
+            app.cancel_message_edit();
         }
         _ => {
             // Reset input history navigation when user starts typing
@@ -274,25 +496,89 @@ async fn handle_key_input(
 }
 
 /// Handle a single async event
-async fn handle_single_event(app: &mut App, event: AppEvent) -> Result<()> {
+async fn handle_single_event(
+    app: &mut App,
+    event: AppEvent,
+    event_tx: &mpsc::Sender<AppEvent>,
+) -> Result<()> {
     match event {
         AppEvent::StreamDelta(text) => {
+            app.set_turn_phase(TurnPhase::Streaming);
             app.append_to_assistant(&text);
         }
+        AppEvent::ReasoningDelta(text) => {
+            app.set_turn_phase(TurnPhase::Thinking);
+            app.append_reasoning(&text);
+        }
         AppEvent::StreamDone => {
+            app.finish_reasoning();
             app.is_processing = false;
             app.status = "Ready".to_string();
             app.clear_tool_batch();
+            app.notify(Attention::TurnComplete);
+            app.request_auto_title(event_tx);
         }
         AppEvent::StreamError(err) => {
+            app.finish_reasoning();
             app.is_processing = false;
             app.status = "Error".to_string();
             app.clear_tool_batch();
             app.add_message("system", &format!("Error: {}", err));
+            app.notify(Attention::TurnComplete);
+        }
+        AppEvent::SessionTitled {
+            session_id,
+            title,
+            tokens,
+            cost,
+        } => {
+            app.apply_auto_title(&session_id, title, tokens, cost).await;
+        }
+        AppEvent::StepFinished { tokens, cost } => {
+            app.total_tokens += tokens;
+            app.total_cost += cost;
+            app.record_reply_usage(tokens, cost);
+        }
+        AppEvent::ModelFallback {
+            provider_id,
+            model_id,
+            reason,
+        } => {
+            let model = format!("{}/{}", provider_id, model_id);
+            app.status = format!("Using fallback {}", model);
+            // Keep the reply last so the rest of the stream still lands in it
+            let reply = match app.messages.last() {
+                Some(last) if last.role == "assistant" => app.messages.pop(),
+                _ => None,
+            };
+            app.add_message(
+                "system",
+                &format!(
+                    "{}. Continuing with fallback model {}; run /model {} to keep using it.",
+                    reason, model, model
+                ),
+            );
+            app.messages.extend(reply);
+        }
+        AppEvent::AuthError {
+            provider_id,
+            message,
+        } => {
+            app.is_processing = false;
+            app.status = "Authentication failed".to_string();
+            app.clear_tool_batch();
+            app.add_message("system", &format!("Error: {}", message));
+            // Offer to reconnect right away; Esc dismisses the dialog
+            app.open_auth_method_selector(&provider_id);
         }
         AppEvent::ToolCall(name, id) => {
+            app.set_turn_phase(TurnPhase::Streaming);
             app.handle_tool_call(&id, &name);
         }
+        AppEvent::ToolInput { id, args } => {
+            app.set_tool_args(&id, &args);
+            app.tool_started(&id);
+        }
         AppEvent::DeviceCodeReceived {
             user_code,
             verification_uri,
@@ -301,8 +587,7 @@ async fn handle_single_event(app: &mut App, event: AppEvent) -> Result<()> {
             let _ = open::that(&verification_uri);
         }
         AppEvent::OAuthSuccess { provider_id } => {
-            let config = Config::load().await?;
-            provider::registry().initialize(&config).await?;
+            provider::registry().reload_credentials().await;
             app.all_providers = provider::registry().list().await;
             app.available_providers = provider::registry().list_available().await;
             app.close_dialog();
@@ -317,23 +602,38 @@ async fn handle_single_event(app: &mut App, event: AppEvent) -> Result<()> {
                 dialog.message = Some(format!("Error: {}", err));
             }
         }
+        AppEvent::ToolOutput { id, text } => {
+            app.push_live_output(&id, &text);
+        }
         AppEvent::ToolResult {
             id,
             output,
             is_error,
         } => {
             app.handle_tool_result_grouped(&id, &output, is_error);
+            app.tool_finished(&id);
         }
+        AppEvent::ToolImage { mime } => app.add_image(&mime),
         AppEvent::PermissionRequested(request) => {
+            app.set_turn_phase(TurnPhase::AwaitingPermission);
             app.show_permission_request(request);
+            app.notify(Attention::Permission);
         }
         AppEvent::PermissionResponse { id, allow, scope } => {
+            app.resume_turn_phase();
             handle_permission_response(app, &id, allow, scope);
         }
+        AppEvent::BatchPermissionResponse { id, decision } => {
+            app.resume_turn_phase();
+            handle_batch_permission_response(app, id, decision);
+        }
         AppEvent::QuestionRequested(request) => {
+            app.set_turn_phase(TurnPhase::AwaitingAnswer);
             app.open_question_dialog(request);
+            app.notify(Attention::Question);
         }
         AppEvent::QuestionReplied { id, answers } => {
+            app.resume_turn_phase();
             handle_question_reply(app, &id, answers);
         }
     }
@@ -369,6 +669,19 @@ fn handle_permission_response(
     };
 }
 
+/// Handle the decision on a batch of tool calls
+fn handle_batch_permission_response(app: &mut App, id: String, decision: BatchDecision) {
+    tokio::spawn(async move {
+        super::llm_streaming::send_batch_permission_response(id, decision).await;
+    });
+
+    app.status = match decision {
+        BatchDecision::ApproveAll => "Tool calls approved".to_string(),
+        BatchDecision::DenyAll => "Tool calls denied".to_string(),
+        BatchDecision::Individually => "Deciding each tool call".to_string(),
+    };
+}
+
 /// Handle question reply event
 fn handle_question_reply(app: &mut App, id: &str, answers: Vec<Vec<String>>) {
     // Format answers for display first, before moving answers
@@ -384,6 +697,23 @@ fn handle_question_reply(app: &mut App, id: &str, answers: Vec<Vec<String>>) {
         format!("Question answered: {}", formatted_answers.join(" | "))
     };
 
+    // A restored question's tool is gone: offer the answers as the next prompt
+    if let Some(pos) = app.restored_questions.iter().position(|r| r.id == id) {
+        let request = app.restored_questions.remove(pos);
+        if !formatted_answers.is_empty() {
+            if !app.input.is_empty() {
+                app.input.push_str("\n\n");
+            }
+            app.input
+                .push_str(&crate::question_state::answers_prompt(&request, &answers));
+            app.cursor_position = app.input.len();
+            app.status = "Answers added to the input, press Enter to send".to_string();
+        }
+        if let Some(next) = app.restored_questions.first().cloned() {
+            app.open_question_dialog(next);
+        }
+    }
+
     // Send response to waiting tool - move answers into the spawn
     let id_owned = id.to_string();
     tokio::spawn(async move {
@@ -391,10 +721,22 @@ fn handle_question_reply(app: &mut App, id: &str, answers: Vec<Vec<String>>) {
     });
 }
 
-/// Process all pending async events
-async fn handle_async_events(app: &mut App, event_rx: &mut mpsc::Receiver<AppEvent>) -> Result<()> {
+/// Process all pending async events, merging stream deltas so each run of
+/// them updates the transcript once per frame
+async fn handle_async_events(
+    app: &mut App,
+    event_tx: &mpsc::Sender<AppEvent>,
+    event_rx: &mut mpsc::Receiver<AppEvent>,
+    stats: &mut FrameStats,
+) -> Result<()> {
+    let mut events = Vec::new();
     while let Ok(event) = event_rx.try_recv() {
-        handle_single_event(app, event).await?;
+        events.push(event);
+    }
+    stats.events += events.len() as u64;
+    for event in frame::coalesce(events) {
+        stats.applied += 1;
+        handle_single_event(app, event, event_tx).await?;
     }
     Ok(())
 }
@@ -406,21 +748,38 @@ async fn run_app(
     event_tx: mpsc::Sender<AppEvent>,
     event_rx: &mut mpsc::Receiver<AppEvent>,
 ) -> Result<()> {
-    let tick_rate = Duration::from_millis(100);
+    let tick_rate = app.tick_rate;
     let mut last_tick = std::time::Instant::now();
+    let mut stats = FrameStats::default();
 
     loop {
+        if app.redraw_requested {
+            terminal.clear()?;
+            app.redraw_requested = false;
+        }
         terminal.draw(|f| ui::render(f, app))?;
+        stats.frames += 1;
 
         let timeout = tick_rate.saturating_sub(last_tick.elapsed());
 
         if event::poll(timeout)? {
-            if let Event::Key(key) = event::read()? {
-                handle_key_input(app, key, &event_tx).await?;
+            match event::read()? {
+                Event::Key(key) => handle_key_input(app, key, &event_tx).await?,
+                Event::Mouse(mouse) => {
+                    let size = terminal.size()?;
+                    let screen = Rect::new(0, 0, size.width, size.height);
+                    handle_mouse_input(app, mouse, screen, &event_tx).await?;
+                }
+                Event::FocusGained => app.focused = Some(true),
+                Event::FocusLost => app.focused = Some(false),
+                // Clear what the old size left behind; messages reflow on the next draw
+                Event::Resize(_, _) => app.redraw_requested = true,
+                _ => {}
             }
         }
 
-        handle_async_events(app, event_rx).await?;
+        handle_async_events(app, &event_tx, event_rx, &mut stats).await?;
+        app.autosave_draft();
 
         if last_tick.elapsed() >= tick_rate {
             app.spinner_frame = app.spinner_frame.wrapping_add(1);
@@ -432,5 +791,6 @@ async fn run_app(
         }
     }
 
+    stats.log(tick_rate);
     Ok(())
 }
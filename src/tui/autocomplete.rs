@@ -34,7 +34,7 @@ impl App {
                 .collect();
 
             // Sort by score (descending)
-            scored_items.sort_by(|a, b| b.0.cmp(&a.0));
+            scored_items.sort_by_key(|item| std::cmp::Reverse(item.0));
             items = scored_items.into_iter().map(|(_, item)| item).collect();
         }
 
@@ -0,0 +1,118 @@
+//! Fenced code block extraction from message text.
+
+/// A fenced code block
+#[derive(Debug, Clone, PartialEq)]
+pub struct CodeBlock {
+    /// Language tag from the info string, if any
+    pub language: Option<String>,
+    pub code: String,
+}
+
+impl CodeBlock {
+    /// Render the block with its fence and language tag
+    pub fn fenced(&self) -> String {
+        format!(
+            "```{}\n{}\n```\n",
+            self.language.as_deref().unwrap_or(""),
+            self.code
+        )
+    }
+
+    /// First non-empty line, for previews
+    pub fn first_line(&self) -> &str {
+        self.code
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty())
+            .unwrap_or("")
+    }
+}
+
+/// Opening fence: the fence character and its length
+fn opening_fence(line: &str) -> Option<(char, usize, &str)> {
+    let trimmed = line.trim_start();
+    let fence_char = trimmed.chars().next().filter(|c| *c == '`' || *c == '~')?;
+    let len = trimmed.chars().take_while(|c| *c == fence_char).count();
+    if len < 3 {
+        return None;
+    }
+    let info = trimmed[len..].trim();
+    // Backtick fences cannot contain backticks in the info string
+    if fence_char == '`' && info.contains('`') {
+        return None;
+    }
+    Some((fence_char, len, info))
+}
+
+fn is_closing_fence(line: &str, fence_char: char, fence_len: usize) -> bool {
+    let trimmed = line.trim();
+    trimmed.len() >= fence_len && trimmed.chars().all(|c| c == fence_char)
+}
+
+/// Extract fenced code blocks in order of appearance
+///
+/// An unterminated block (e.g. from a truncated response) runs to the end of
+/// the text.
+pub fn extract_code_blocks(text: &str) -> Vec<CodeBlock> {
+    let mut blocks = Vec::new();
+    let mut lines = text.lines();
+
+    while let Some(line) = lines.next() {
+        let Some((fence_char, fence_len, info)) = opening_fence(line) else {
+            continue;
+        };
+        let language = info.split_whitespace().next().map(|lang| lang.to_string());
+
+        let mut code = Vec::new();
+        for line in lines.by_ref() {
+            if is_closing_fence(line, fence_char, fence_len) {
+                break;
+            }
+            code.push(line);
+        }
+
+        blocks.push(CodeBlock {
+            language,
+            code: code.join("\n"),
+        });
+    }
+
+    blocks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_blocks_with_language() {
+        let text = "Here:\n\n```rust\nfn main() {}\n```\n\nAnd:\n\n```\nls -la\n```\n";
+        let blocks = extract_code_blocks(text);
+
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[0].language.as_deref(), Some("rust"));
+        assert_eq!(blocks[0].code, "fn main() {}");
+        assert_eq!(blocks[1].language, None);
+        assert_eq!(blocks[1].fenced(), "```\nls -la\n```\n");
+    }
+
+    #[test]
+    fn test_nested_fences() {
+        let text = "````markdown\n```python\nprint(1)\n```\n````\n~~~sh title=x\necho\n~~~";
+        let blocks = extract_code_blocks(text);
+
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[0].code, "```python\nprint(1)\n```");
+        assert_eq!(blocks[1].language.as_deref(), Some("sh"));
+        assert_eq!(blocks[1].code, "echo");
+    }
+
+    #[test]
+    fn test_unterminated_block() {
+        let blocks = extract_code_blocks("text ``inline``\n```js\nconst a = 1;\n");
+
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0].code, "const a = 1;");
+        assert_eq!(blocks[0].first_line(), "const a = 1;");
+    }
+}
@@ -5,11 +5,13 @@
 use anyhow::Result;
 use tokio::sync::mpsc;
 
-use super::llm_streaming::stream_response;
+use super::app::{spawn_turn, turn_options};
+use super::llm_streaming::{get_current_dir, stream_response};
 use super::state::App;
 use super::types::AppEvent;
+use crate::config::{AgentConfig, Config, PermissionAction};
 use crate::provider::{self, StreamEvent};
-use crate::session::{CreateSessionOptions, Session};
+use crate::session::{CreateSessionOptions, Message, Session};
 use crate::slash_command::{CommandAction, CommandOutput};
 
 /// Handle command output
@@ -20,6 +22,9 @@ pub async fn handle_command_output(
     event_tx: &mpsc::Sender<AppEvent>,
 ) -> Result<()> {
     // Handle special actions first
+    if let Some(CommandAction::Regenerate { model, temperature }) = &output.action {
+        return regenerate(app, model.as_deref(), *temperature, event_tx).await;
+    }
     if let Some(action) = &output.action {
         return handle_action(app, action).await;
     }
@@ -30,16 +35,19 @@ pub async fn handle_command_output(
         return Ok(());
     }
 
-    // Handle model switch
-    if let Some(model) = &output.model {
-        handle_model_switch(app, model);
-        return Ok(());
-    }
+    // A model or agent on a prompt only applies to that turn (see `resolve_turn`)
+    if !output.submit_to_llm {
+        // Handle model switch
+        if let Some(model) = &output.model {
+            handle_model_switch(app, model).await;
+            return Ok(());
+        }
 
-    // Handle agent switch
-    if let Some(agent_name) = &output.agent {
-        handle_agent_switch(app, agent_name);
-        return Ok(());
+        // Handle agent switch
+        if let Some(agent_name) = &output.agent {
+            handle_agent_switch(app, agent_name);
+            return Ok(());
+        }
     }
 
     // Display command output if not empty
@@ -49,29 +57,108 @@ pub async fn handle_command_output(
 
     // Submit to LLM if requested
     if output.submit_to_llm {
-        start_llm_response(app, &output.text, event_tx);
+        let turn = resolve_turn(app, &output).await;
+        start_llm_response(app, &output.text, turn, event_tx);
     }
 
     Ok(())
 }
 
+/// Model and agent prompt used for a single command prompt
+struct Turn {
+    provider_id: String,
+    model_id: String,
+    agent_prompt: Option<String>,
+}
+
+/// Resolve the model for a command prompt without switching the session's model
+///
+/// Uses the command's model, then the model of its agent, then the current
+/// model. The agent's prompt is added to the system prompt for this turn only.
+async fn resolve_turn(app: &mut App, output: &CommandOutput) -> Turn {
+    let agent = match &output.agent {
+        Some(name) => load_agent(app, name).await,
+        None => None,
+    };
+
+    let model = output
+        .model
+        .clone()
+        .or_else(|| agent.as_ref().and_then(|a| a.model.clone()));
+    let parsed = model.as_deref().and_then(provider::parse_model_string);
+    if let (Some(model), None) = (&model, &parsed) {
+        app.add_message(
+            "system",
+            &format!("Invalid model '{}', using the current model", model),
+        );
+    }
+    let (provider_id, model_id) =
+        parsed.unwrap_or_else(|| (app.provider_id.clone(), app.model_id.clone()));
+
+    let cwd = get_current_dir();
+    let agent_prompt = match agent {
+        Some(agent) => crate::session::system::agent_prompt(&agent, &cwd, &provider_id, &model_id)
+            .unwrap_or_else(|e| {
+                app.add_message("system", &format!("{:#}; using no agent prompt", e));
+                None
+            }),
+        None => None,
+    };
+
+    Turn {
+        provider_id,
+        model_id,
+        agent_prompt,
+    }
+}
+
+/// Look up an agent in the config, warning if it is not defined
+async fn load_agent(app: &mut App, name: &str) -> Option<AgentConfig> {
+    let agent = Config::load()
+        .await
+        .ok()
+        .and_then(|config| config.agent)
+        .and_then(|mut agents| agents.remove(name));
+    if agent.is_none() {
+        app.add_message(
+            "system",
+            &format!("Unknown agent '{}', using the default prompt", name),
+        );
+    }
+    agent
+}
+
 /// Handle special command actions
 async fn handle_action(app: &mut App, action: &CommandAction) -> Result<()> {
     match action {
         // UI actions
         CommandAction::OpenModelSelector => app.open_model_selector(),
+        CommandAction::FilterModels(capabilities) => app.open_model_selector_with(capabilities),
         CommandAction::OpenProviderConnection => app.open_provider_connection(),
         CommandAction::Exit => app.should_quit = true,
         CommandAction::ToggleTheme => handle_toggle_theme(app),
         CommandAction::ToggleThinking => handle_toggle_thinking(app),
+        CommandAction::ToggleDisplay(toggle) => app.toggle_display(*toggle),
 
         // Session actions
         CommandAction::NewSession => create_new_session(app).await,
-        CommandAction::Status => handle_status(app),
+        CommandAction::ResetSession => reset_session(app).await,
+        CommandAction::Status => handle_status(app).await,
+        CommandAction::SetMaxOutput(max_output) => handle_set_max_output(app, *max_output),
+        CommandAction::SetPrefill(prefill) => handle_set_prefill(app, prefill.clone()),
+        CommandAction::AppendSystem(text) => handle_append_system(app, text.clone()).await,
+        CommandAction::PinFile(path) => handle_pin(app, path.clone()).await,
+        CommandAction::UnpinFile(path) => handle_unpin(app, path.clone()).await,
+        CommandAction::SetSessionPermission(rule) => {
+            handle_set_session_permission(app, rule.clone()).await
+        }
+        CommandAction::SetJsonSchema(schema) => handle_set_json_schema(app, schema.clone()),
 
         // Transcript actions
         CommandAction::Copy => handle_copy_transcript(app),
+        CommandAction::CopyCode { fenced } => app.copy_code_block(*fenced),
         CommandAction::Export => handle_export_transcript(app),
+        CommandAction::OpenDiffViewer => app.open_diff_viewer().await?,
 
         // Unimplemented actions with messages
         CommandAction::OpenAgentSelector => {
@@ -80,37 +167,33 @@ async fn handle_action(app: &mut App, action: &CommandAction) -> Result<()> {
         CommandAction::OpenSessionList => {
             app.open_session_list().await?;
         }
+        CommandAction::OpenBranchList => {
+            app.open_branch_list().await?;
+        }
         CommandAction::Undo => handle_undo(app),
         CommandAction::Redo => handle_redo(app),
+        // Needs the event channel, handled in `handle_command_output`
+        CommandAction::Regenerate { .. } => {}
         CommandAction::Compact => {
             // Note: /compact command already works by sending a prompt to LLM
             // This action is not used by the current implementation
             app.add_message("system", "Use /compact command to summarize the session");
         }
-        CommandAction::Unshare => {
-            app.add_message(
-                "system",
-                "Session sharing features are under development. This requires server-side API implementation.",
-            );
-        }
+        CommandAction::Unshare => handle_unshare(app).await,
         CommandAction::Rename => app.open_session_rename(),
         CommandAction::Timeline => app.open_timeline(),
         CommandAction::Fork => {
             handle_fork_session(app).await?;
         }
-        CommandAction::Share => {
-            app.add_message(
-                "system",
-                "Session sharing features are under development. This requires server-side API implementation.",
-            );
-        }
+        CommandAction::YankContext => yank_context(app).await?,
+        CommandAction::Share => handle_share(app).await,
         CommandAction::ToggleMcp => {
             app.add_message(
                 "system",
                 "MCP server management dialog is under development. For now, configure MCPs in your opencode.json file.",
             );
         }
-        CommandAction::OpenEditor => handle_open_editor(app)?,
+        CommandAction::OpenEditor => app.open_external_editor(),
         CommandAction::ShowCommands => {
             app.add_message("system", "Use /help to see all available commands")
         }
@@ -118,6 +201,154 @@ async fn handle_action(app: &mut App, action: &CommandAction) -> Result<()> {
     Ok(())
 }
 
+/// Answer the last prompt again as a new variant of its reply
+///
+/// The model defaults to the current one; the earlier replies stay available
+/// through `variant_prev` and `variant_next`.
+async fn regenerate(
+    app: &mut App,
+    model: Option<&str>,
+    temperature: Option<f64>,
+    event_tx: &mpsc::Sender<AppEvent>,
+) -> Result<()> {
+    if app.is_processing {
+        app.status = "Wait for the reply to finish".to_string();
+        return Ok(());
+    }
+    let Some(prompt) = app
+        .messages
+        .iter()
+        .rfind(|m| m.role == "user")
+        .map(|m| m.content.clone())
+    else {
+        app.add_message("system", "Nothing to regenerate");
+        return Ok(());
+    };
+    let (provider_id, model_id) = match model {
+        Some(model) => match provider::parse_model_string(model) {
+            Some(parsed) => parsed,
+            None => {
+                app.add_message("system", &format!("Invalid model: {}", model));
+                return Ok(());
+            }
+        },
+        None => (app.provider_id.clone(), app.model_id.clone()),
+    };
+    if let Some(temperature) = temperature {
+        let max = provider::max_temperature(&provider_id);
+        if !(0.0..=max).contains(&temperature) {
+            app.add_message(
+                "system",
+                &format!(
+                    "Invalid temperature: {} ({} accepts 0 to {})",
+                    temperature, provider_id, max
+                ),
+            );
+            return Ok(());
+        }
+    }
+
+    // Answer the stored prompt, so the reply is saved as its sibling
+    let reply_to = match &app.session {
+        Some(session) => Message::list(&session.id)
+            .await?
+            .iter()
+            .rev()
+            .find_map(|m| match m {
+                Message::User(user) => Some(user.id.clone()),
+                _ => None,
+            }),
+        None => None,
+    };
+    if app.session.is_some() && reply_to.is_none() {
+        app.add_message("system", "Nothing to regenerate");
+        return Ok(());
+    }
+
+    let model_name = format!("{}/{}", provider_id, model_id);
+    if !app.start_reply_variant(&model_name) {
+        app.add_message("system", "Nothing to regenerate");
+        return Ok(());
+    }
+    app.is_processing = true;
+    app.begin_turn_phase();
+    app.status = format!("Regenerating with {}", model_name);
+
+    let mut options = turn_options(app);
+    options.temperature = temperature;
+    options.reply_to = reply_to;
+    spawn_turn(app, provider_id, model_id, &prompt, options, event_tx);
+    Ok(())
+}
+
+/// Upload the current session and copy its share URL
+async fn handle_share(app: &mut App) {
+    let Some(mut session) = app.session.clone() else {
+        app.add_message("system", "No active session to share");
+        return;
+    };
+    if let Some(share) = &session.share {
+        let message = format!("Session is already shared: {}", share.url);
+        app.add_message("system", &message);
+        return;
+    }
+
+    app.status = "Sharing session...".to_string();
+    let result = match Config::load().await {
+        Ok(config) => session.share(&config).await,
+        Err(e) => Err(e),
+    };
+    let share = match result {
+        Ok(share) => share,
+        Err(e) => {
+            app.status = "Share failed".to_string();
+            app.add_message("system", &format!("Failed to share session: {}", e));
+            return;
+        }
+    };
+
+    let url = share.url.clone();
+    if let Err(e) = session.update("default", |s| s.share = Some(share)).await {
+        tracing::warn!("Failed to save share URL: {}", e);
+    }
+    app.session = Some(session);
+
+    let message = match crate::tui::copy_to_clipboard(&url) {
+        Ok(_) => format!("Session shared: {} (copied to clipboard)", url),
+        Err(_) => format!("Session shared: {}", url),
+    };
+    app.status = "Session shared".to_string();
+    app.add_message("system", &message);
+}
+
+/// Delete the current session's share
+async fn handle_unshare(app: &mut App) {
+    let Some(mut session) = app.session.clone() else {
+        app.add_message("system", "No active session to unshare");
+        return;
+    };
+    if session.share.is_none() {
+        app.add_message("system", "Session is not shared");
+        return;
+    }
+
+    let result = match Config::load().await {
+        Ok(config) => session.unshare(&config).await,
+        Err(e) => Err(e),
+    };
+    if let Err(e) = result {
+        app.add_message("system", &format!("Failed to unshare session: {}", e));
+        return;
+    }
+
+    if let Err(e) = session.update("default", |s| s.share = None).await {
+        tracing::warn!("Failed to clear share URL: {}", e);
+    }
+    app.session = Some(session);
+    app.status = "Session unshared".to_string();
+    app.add_message("system", "Session is no longer shared");
+}
+
 /// Handle undo action
 fn handle_undo(app: &mut App) {
     if app.can_undo() {
@@ -174,14 +405,196 @@ fn handle_toggle_thinking(app: &mut App) {
 }
 
 /// Handle status display action
-fn handle_status(app: &mut App) {
-    let status_msg = format!(
-        "Session: {}\nModel: {}\nProvider: {}\nTokens: {}\nCost: ${:.4}",
-        app.session_title, app.model_display, app.provider_id, app.total_tokens, app.total_cost
+async fn handle_status(app: &mut App) {
+    let mut status_msg = format!(
+        "Session: {}\nModel: {}\nProvider: {}\nMax output: {}\nTokens: {}\nCost: ${:.4}",
+        app.session_title,
+        app.model_display,
+        app.provider_id,
+        describe_max_output(app),
+        app.total_tokens,
+        app.total_cost
     );
+    if let Some(prefill) = &app.prefill {
+        status_msg.push_str(&format!("\nPrefill: {:?}", prefill));
+    }
+    if app.json_schema.is_some() {
+        status_msg.push_str("\nJSON schema: on");
+    }
+    let tokenizer = crate::provider::tokenizer::for_model(&app.provider_id, &app.model_id);
+    status_msg.push_str(&format!("\nTokenizer: {}", tokenizer.name()));
+    let config = Config::load().await.unwrap_or_default();
+    let base =
+        crate::session::system::load(&config, &get_current_dir(), &app.provider_id, &app.model_id);
+    match base {
+        Ok(base) => {
+            let system_prompt = crate::session::system::with_additions(
+                base,
+                app.session.as_ref().and_then(|s| s.system.as_deref()),
+            );
+            status_msg.push_str(&format!("\n\nSystem prompt:\n{}", system_prompt));
+        }
+        Err(e) => status_msg.push_str(&format!("\n\nSystem prompt: {:#}", e)),
+    }
     app.add_message("system", &status_msg);
 }
 
+/// Handle max output tokens change
+fn handle_set_max_output(app: &mut App, max_output: Option<u64>) {
+    app.max_output_tokens = max_output;
+    let msg = format!("Max output set to {}", describe_max_output(app));
+    app.add_message("system", &msg);
+}
+
+/// Handle assistant prefill change
+fn handle_set_prefill(app: &mut App, prefill: Option<String>) {
+    let msg = match prefill {
+        Some(_) if app.provider_id != "anthropic" => format!(
+            "Prefill is only supported by Anthropic models (current provider: {})",
+            app.provider_id
+        ),
+        Some(text) => {
+            let msg = format!("Replies will start with {:?}", text);
+            app.prefill = Some(text);
+            msg
+        }
+        None => {
+            app.prefill = None;
+            "Prefill cleared".to_string()
+        }
+    };
+    app.add_message("system", &msg);
+}
+
+/// Handle `/system`: append to or clear the session's system prompt
+async fn handle_append_system(app: &mut App, text: Option<String>) {
+    let msg = match &text {
+        Some(text) => format!("Added to the system prompt: {:?}", text),
+        None => "System prompt additions cleared".to_string(),
+    };
+    match app.append_system(text).await {
+        Ok(()) => app.add_message("system", &msg),
+        Err(e) => app.add_message("system", &format!("Failed to update system prompt: {}", e)),
+    }
+}
+
+/// Handle pinning a file, or listing the pinned files
+async fn handle_pin(app: &mut App, path: Option<String>) {
+    let Some(session) = &app.session else {
+        app.add_message("system", "No active session to pin files to");
+        return;
+    };
+    let mut pinned = session.pinned.clone();
+    let msg = match path {
+        None if pinned.is_empty() => "No pinned files; pin one with /pin <path>".to_string(),
+        None => format!("Pinned files:\n{}", pinned.join("\n")),
+        Some(path) if pinned.contains(&path) => format!("{} is already pinned", path),
+        Some(path)
+            if !std::path::Path::new(&get_current_dir())
+                .join(&path)
+                .is_file() =>
+        {
+            format!("Not a file: {}", path)
+        }
+        Some(path) => {
+            pinned.push(path.clone());
+            match app.set_pinned(pinned).await {
+                Ok(()) => format!("Pinned {}; its current contents go with every turn", path),
+                Err(e) => format!("Failed to pin {}: {}", path, e),
+            }
+        }
+    };
+    app.add_message("system", &msg);
+}
+
+/// Handle unpinning a file, or all files
+async fn handle_unpin(app: &mut App, path: Option<String>) {
+    let Some(session) = &app.session else {
+        app.add_message("system", "No active session");
+        return;
+    };
+    let mut pinned = session.pinned.clone();
+    let msg = match &path {
+        Some(path) if !pinned.contains(path) => {
+            app.add_message("system", &format!("{} is not pinned", path));
+            return;
+        }
+        Some(path) => {
+            pinned.retain(|p| p != path);
+            format!("Unpinned {}", path)
+        }
+        None => {
+            pinned.clear();
+            "Unpinned all files".to_string()
+        }
+    };
+    match app.set_pinned(pinned).await {
+        Ok(()) => app.add_message("system", &msg),
+        Err(e) => app.add_message("system", &format!("Failed to unpin: {}", e)),
+    }
+}
+
+/// Handle a session permission rule change
+async fn handle_set_session_permission(app: &mut App, rule: Option<(String, PermissionAction)>) {
+    let msg = match &rule {
+        Some((tool, action)) => {
+            let action = match action {
+                PermissionAction::Allow => "allow",
+                PermissionAction::Ask => "ask",
+                PermissionAction::Deny => "deny",
+            };
+            format!("Session permission for {}: {}", tool, action)
+        }
+        None => "Session permission rules cleared".to_string(),
+    };
+    match app.set_session_permission(rule).await {
+        Ok(()) => app.add_message("system", &msg),
+        Err(e) => app.add_message(
+            "system",
+            &format!("Failed to update session permissions: {}", e),
+        ),
+    }
+}
+
+/// Handle JSON schema change
+fn handle_set_json_schema(app: &mut App, schema: Option<serde_json::Value>) {
+    let msg = match schema {
+        Some(_) if !provider::structured::is_supported(&app.provider_id) => format!(
+            "Structured output is not supported by the current provider: {}",
+            app.provider_id
+        ),
+        Some(schema) => {
+            app.json_schema = Some(schema);
+            "Replies will be validated against the JSON schema".to_string()
+        }
+        None => {
+            app.json_schema = None;
+            "JSON schema cleared".to_string()
+        }
+    };
+    app.add_message("system", &msg);
+}
+
+/// Describe the effective max output tokens for the current model
+fn describe_max_output(app: &App) -> String {
+    let model = app
+        .all_providers
+        .iter()
+        .find(|p| p.id == app.provider_id)
+        .and_then(|p| p.models.get(&app.model_id));
+
+    match (model, app.max_output_tokens) {
+        (Some(model), Some(_)) => format!(
+            "{} tokens (model limit {})",
+            provider::max_output_tokens(model, app.max_output_tokens),
+            model.limit.output
+        ),
+        (Some(model), None) => format!("{} tokens (model limit)", model.limit.output),
+        (None, Some(max)) => format!("{} tokens", max),
+        (None, None) => "model limit".to_string(),
+    }
+}
+
 /// Create transcript options from app state
 fn create_transcript_options(app: &App) -> crate::tui::TranscriptOptions {
     crate::tui::TranscriptOptions {
@@ -197,6 +610,7 @@ fn handle_copy_transcript(app: &mut App) {
         &app.messages,
         &create_transcript_options(app),
     );
+    let transcript = app.redactor.redact(&transcript);
 
     match crate::tui::copy_to_clipboard(&transcript) {
         Ok(_) => app.add_message("system", "Transcript copied to clipboard"),
@@ -214,6 +628,7 @@ fn handle_export_transcript(app: &mut App) {
         &app.messages,
         &create_transcript_options(app),
     );
+    let transcript = app.redactor.redact(&transcript);
 
     let filename = generate_export_filename(&app.session_slug);
 
@@ -240,7 +655,7 @@ async fn create_new_session(app: &mut App) {
             app.session_title = session.title.clone();
             app.session_slug = session.slug.clone();
             app.session = Some(session);
-            app.messages.clear();
+            app.clear_transcript();
             app.total_cost = 0.0;
             app.total_tokens = 0;
             app.status = "Session cleared".to_string();
@@ -251,14 +666,78 @@ async fn create_new_session(app: &mut App) {
     }
 }
 
+/// Start a new session whose system prompt quotes the marked messages
+async fn yank_context(app: &mut App) -> Result<()> {
+    let mut marked = app.marked_messages.clone();
+    marked.sort_unstable();
+    let messages: Vec<(&str, &str)> = marked
+        .iter()
+        .filter_map(|&index| app.messages.get(index))
+        .map(|msg| (msg.role.as_str(), msg.content.as_str()))
+        .collect();
+    if messages.is_empty() {
+        app.add_message(
+            "system",
+            "No messages marked. Open /timeline and press Tab on the messages to carry over.",
+        );
+        return Ok(());
+    }
+
+    let count = messages.len();
+    let context = crate::session::system::prior_context(&app.session_title, &messages);
+    let previous_id = app.session.as_ref().map(|s| s.id.clone());
+    create_new_session(app).await;
+    if app.session.as_ref().map(|s| s.id.clone()) == previous_id {
+        return Ok(());
+    }
+
+    app.append_system(Some(context)).await?;
+    app.add_message(
+        "system",
+        &format!(
+            "New session started with {} message(s) as prior context",
+            count
+        ),
+    );
+    app.status = "Context yanked".to_string();
+    Ok(())
+}
+
+/// Delete the current session's messages, keeping its ID and settings
+async fn reset_session(app: &mut App) {
+    let Some(session_id) = app.session.as_ref().map(|s| s.id.clone()) else {
+        app.status = "No active session".to_string();
+        return;
+    };
+    match Message::clear(&session_id).await {
+        Ok(removed) => {
+            app.clear_transcript();
+            app.message_history.clear();
+            app.history_position = 0;
+            app.editing = None;
+            app.selected_tool = None;
+            app.total_cost = 0.0;
+            app.total_tokens = 0;
+            app.status = format!("Session reset ({} messages deleted)", removed);
+        }
+        Err(e) => {
+            app.status = format!("Error resetting session: {}", e);
+        }
+    }
+}
+
 /// Handle model switch command
-fn handle_model_switch(app: &mut App, model: &str) {
-    if let Some((provider_id, model_id)) = provider::parse_model_string(model) {
-        app.model_display = format!("{}/{}", provider_id, model_id);
-        app.provider_id = provider_id;
-        app.model_id = model_id;
-        app.model_configured = true;
-        app.status = format!("Switched to model: {}", model);
+///
+/// The model is saved on the session, so it is used again when the session
+/// is resumed.
+async fn handle_model_switch(app: &mut App, model: &str) {
+    let Some((provider_id, model_id)) = provider::parse_model_string(model) else {
+        app.status = format!("Invalid model: {}", model);
+        return;
+    };
+    match app.set_model(&provider_id, &model_id).await {
+        Ok(()) => app.status = format!("Switched to model: {}", model),
+        Err(e) => app.status = format!("Error switching model: {}", e),
     }
 }
 
@@ -280,21 +759,46 @@ fn handle_agent_switch(app: &mut App, agent_name: &str) {
 }
 
 /// Start streaming LLM response
-fn start_llm_response(app: &mut App, prompt: &str, event_tx: &mpsc::Sender<AppEvent>) {
+fn start_llm_response(app: &mut App, prompt: &str, turn: Turn, event_tx: &mpsc::Sender<AppEvent>) {
     app.is_processing = true;
-    app.status = "Processing".to_string();
+    app.begin_turn_phase();
+    if turn.provider_id != app.provider_id || turn.model_id != app.model_id {
+        app.status = format!("Processing with {}/{}", turn.provider_id, turn.model_id);
+    }
 
     // Add empty assistant message
     app.add_message("assistant", "");
 
     // Start streaming
-    let provider_id = app.provider_id.clone();
-    let model_id = app.model_id.clone();
+    let max_output = app.max_output_tokens;
+    let session_system = app.session.as_ref().and_then(|s| s.system.clone());
+    let pinned = app
+        .session
+        .as_ref()
+        .map(|s| s.pinned.clone())
+        .unwrap_or_default();
     let prompt = prompt.to_string();
     let tx = event_tx.clone();
 
     tokio::spawn(async move {
-        match stream_response(&provider_id, &model_id, &prompt).await {
+        let Turn {
+            provider_id,
+            model_id,
+            agent_prompt,
+        } = turn;
+        let pinned = crate::session::system::pinned_files(&get_current_dir(), &pinned);
+        let session_system: Vec<String> = [session_system, pinned].into_iter().flatten().collect();
+        let session_system = (!session_system.is_empty()).then(|| session_system.join("\n\n"));
+        match stream_response(
+            &provider_id,
+            &model_id,
+            &prompt,
+            max_output,
+            session_system.as_deref(),
+            agent_prompt.as_deref(),
+        )
+        .await
+        {
             Ok(rx) => process_stream_events(rx, tx).await,
             Err(e) => {
                 let _ = tx.send(AppEvent::StreamError(e.to_string())).await;
@@ -311,8 +815,21 @@ async fn process_stream_events(
     while let Some(event) = rx.recv().await {
         let app_event = match event {
             StreamEvent::TextDelta(text) => Some(AppEvent::StreamDelta(text)),
+            StreamEvent::ReasoningDelta(text) => Some(AppEvent::ReasoningDelta(text)),
             StreamEvent::Done { .. } => Some(AppEvent::StreamDone),
-            StreamEvent::Error(err) => Some(AppEvent::StreamError(err)),
+            StreamEvent::Error(err) | StreamEvent::Overloaded(err) => {
+                Some(AppEvent::StreamError(err))
+            }
+            StreamEvent::Refused(reason) => Some(AppEvent::StreamError(
+                provider::RequestRefused(reason).to_string(),
+            )),
+            StreamEvent::AuthError {
+                provider_id,
+                message,
+            } => Some(AppEvent::AuthError {
+                provider_id,
+                message,
+            }),
             StreamEvent::ToolCallStart { name, .. } => {
                 Some(AppEvent::ToolCall(name, String::new()))
             }
@@ -325,69 +842,10 @@ async fn process_stream_events(
     }
 }
 
-/// Handle opening external editor
-fn handle_open_editor(app: &mut App) -> Result<()> {
-    use crossterm::terminal::{disable_raw_mode, enable_raw_mode};
-    use std::io::Write;
-
-    // Get editor from environment
-    let editor = std::env::var("EDITOR")
-        .or_else(|_| std::env::var("VISUAL"))
-        .unwrap_or_else(|_| "vi".to_string());
-
-    // Create temp file with current input
-    let temp_dir = std::env::temp_dir();
-    let temp_file = temp_dir.join(format!("opencode-edit-{}.txt", std::process::id()));
-
-    {
-        let mut file = std::fs::File::create(&temp_file)?;
-        file.write_all(app.input.as_bytes())?;
-    }
-
-    // Disable raw mode temporarily
-    disable_raw_mode()?;
-
-    // Open editor
-    let status = std::process::Command::new(&editor).arg(&temp_file).status();
-
-    // Re-enable raw mode
-    enable_raw_mode()?;
-
-    match status {
-        Ok(exit_status) if exit_status.success() => {
-            // Read file content
-            match std::fs::read_to_string(&temp_file) {
-                Ok(content) => {
-                    app.input = content;
-                    app.cursor_position = app.input.len();
-                    app.add_message("system", "Editor content loaded");
-                }
-                Err(e) => {
-                    app.add_message("system", &format!("Failed to read editor file: {}", e));
-                }
-            }
-        }
-        Ok(_) => {
-            app.add_message("system", "Editor exited with error");
-        }
-        Err(e) => {
-            app.add_message(
-                "system",
-                &format!("Failed to open editor '{}': {}", editor, e),
-            );
-        }
-    }
-
-    // Clean up temp file
-    let _ = std::fs::remove_file(&temp_file);
-
-    Ok(())
-}
-
 /// Handle session forking
 async fn handle_fork_session(app: &mut App) -> Result<()> {
     use crate::id::{self, IdPrefix};
-    use crate::session::{Message, Part};
+    use crate::session::Part;
     use std::collections::HashMap;
 
     // Extract needed info from current session without full clone
@@ -455,7 +913,7 @@ async fn handle_fork_session(app: &mut App) -> Result<()> {
     app.session_slug = new_session.slug.clone();
     let session_title = new_session.title.clone();
     app.session = Some(new_session);
-    app.messages.clear();
+    app.clear_transcript();
     app.total_cost = 0.0;
     app.total_tokens = 0;
 
@@ -9,6 +9,7 @@ use ratatui::{
 };
 
 use super::theme::Theme;
+use super::types::DisplayLine;
 
 /// Spinner animation frames (braille pattern)
 pub const SPINNER_FRAMES: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
@@ -18,6 +19,8 @@ pub struct Header<'a> {
     pub title: &'a str,
     pub model: &'a str,
     pub status: &'a str,
+    /// Whether a turn is running, which highlights the status
+    pub busy: bool,
     pub theme: &'a Theme,
 }
 
@@ -47,8 +50,8 @@ impl<'a> Widget for Header<'a> {
         // Status
         let status_style = match self.status {
             "Ready" => self.theme.text().fg(self.theme.success),
-            "Processing" => self.theme.text().fg(self.theme.warning),
             "Error" => self.theme.text().fg(self.theme.error),
+            _ if self.busy => self.theme.text().fg(self.theme.warning),
             _ => self.theme.text_dim(),
         };
         let status = Paragraph::new(self.status)
@@ -61,8 +64,10 @@ impl<'a> Widget for Header<'a> {
 /// Message component for displaying a single message
 pub struct MessageWidget<'a> {
     pub role: &'a str,
-    pub content: &'a str,
+    pub lines: &'a [DisplayLine],
     pub theme: &'a Theme,
+    /// Whether the message has the `message_prev`/`message_next` focus
+    pub focused: bool,
 }
 
 impl<'a> Widget for MessageWidget<'a> {
@@ -76,15 +81,28 @@ impl<'a> Widget for MessageWidget<'a> {
         Block::default().style(bg_style).render(area, buf);
 
         let content_lines: Vec<Line> = self
-            .content
-            .trim()
-            .lines()
-            .filter(|line| !line.trim().is_empty())
-            .map(|line| Line::from(Span::styled(format!(" {} ", line), self.theme.text())))
+            .lines
+            .iter()
+            .map(|line| {
+                let style = if line.selected {
+                    Style::default()
+                        .fg(self.theme.background)
+                        .bg(self.theme.accent)
+                } else {
+                    self.theme.text()
+                };
+                // A focused message is marked in its left padding column
+                let margin = if self.focused {
+                    Span::styled("▌", Style::default().fg(self.theme.accent))
+                } else {
+                    Span::styled(" ", style)
+                };
+                Line::from(vec![margin, Span::styled(format!("{} ", line.text), style)])
+            })
             .collect();
 
+        // Lines come wrapped to the width already, see `wrap::wrap_lines`
         Paragraph::new(content_lines)
-            .wrap(Wrap { trim: false })
             .style(bg_style)
             .render(area, buf);
     }
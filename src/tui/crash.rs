@@ -0,0 +1,63 @@
+//! Crash reports for panics in the TUI.
+//!
+//! The panic message and backtrace are written to a log file under the data
+//! directory, since they would otherwise be lost behind the alternate screen.
+
+use std::backtrace::Backtrace;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Subdirectory of the data directory holding crash logs
+const CRASH_DIR: &str = "crash";
+
+/// Directory crash logs are written to
+pub fn crash_dir() -> PathBuf {
+    crate::storage::global().base_path().join(CRASH_DIR)
+}
+
+/// Write a crash log for a panic, returning its path
+pub fn write_report(dir: &Path, panic: &str, backtrace: &Backtrace) -> io::Result<PathBuf> {
+    let now = chrono::Local::now();
+    std::fs::create_dir_all(dir)?;
+    let path = dir.join(format!("crash-{}.log", now.format("%Y%m%d-%H%M%S")));
+    std::fs::write(&path, format_report(&now.to_rfc3339(), panic, backtrace))?;
+    Ok(path)
+}
+
+fn format_report(time: &str, panic: &str, backtrace: &Backtrace) -> String {
+    format!(
+        "opencode-rs {} crash report\n\
+        time: {}\n\
+        os: {} {}\n\n\
+        {}\n\n\
+        backtrace:\n{}\n",
+        env!("CARGO_PKG_VERSION"),
+        time,
+        std::env::consts::OS,
+        std::env::consts::ARCH,
+        panic,
+        backtrace
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_report_is_written_with_panic_message() {
+        let dir = tempdir().unwrap();
+        let path = write_report(
+            &dir.path().join(CRASH_DIR),
+            "panicked at src/main.rs:1:1:\nboom",
+            &Backtrace::disabled(),
+        )
+        .unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert!(path.starts_with(dir.path()));
+        assert!(content.contains("boom"));
+        assert!(content.contains("backtrace:"));
+    }
+}
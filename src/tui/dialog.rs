@@ -8,34 +8,56 @@ use anyhow::Result;
 use crossterm::event::KeyCode;
 use tokio::sync::mpsc;
 
+use super::clipboard::copy_to_clipboard;
+use super::code_block::{extract_code_blocks, CodeBlock};
+use super::keymap::KeyAction;
 use super::oauth_flow::{start_copilot_oauth_flow, start_openai_oauth_flow};
 use super::state::App;
 use super::types::{AppEvent, DialogState, DialogType, SelectItem};
 use crate::config::Config;
-use crate::provider;
+use crate::provider::{self, Capability};
 
 /// Dialog-related methods for App
 impl App {
     /// Open the model selector dialog
     pub fn open_model_selector(&mut self) {
-        let items = self.collect_available_models();
+        self.open_model_selector_with(&[]);
+    }
 
-        if items.is_empty() {
+    /// Open the model selector dialog, listing only models with `filter`
+    ///
+    /// Models lacking a capability the session requires stay listed but are
+    /// flagged, e.g. `[no toolcall]`.
+    pub fn open_model_selector_with(&mut self, filter: &[Capability]) {
+        let items = self.collect_available_models(filter);
+
+        if items.is_empty() && filter.is_empty() {
             self.open_provider_selector();
             return;
         }
 
-        let dialog = DialogState::new(
-            DialogType::ModelSelector,
-            "Select Model (deprecated models hidden)",
-        )
-        .with_items(items);
+        let title = if filter.is_empty() {
+            "Select Model (deprecated models hidden)".to_string()
+        } else {
+            format!("Select Model (with {})", capability_names(filter))
+        };
+        let mut dialog = DialogState::new(DialogType::ModelSelector, &title).with_items(items);
+        if dialog.items.is_empty() {
+            dialog.message = Some("No model has all of these capabilities".to_string());
+        } else if self.onboarding {
+            dialog.message = Some("Step 2/3: choose a model".to_string());
+        }
         self.dialog = Some(dialog);
     }
 
     /// Collect available models from providers, excluding deprecated ones
-    fn collect_available_models(&self) -> Vec<SelectItem> {
-        self.available_providers
+    /// and those lacking a capability in `filter`
+    ///
+    /// Configured aliases of the listed models come first.
+    fn collect_available_models(&self, filter: &[Capability]) -> Vec<SelectItem> {
+        let required = &self.required_capabilities();
+        let models: Vec<SelectItem> = self
+            .available_providers
             .iter()
             .flat_map(|provider| {
                 provider
@@ -43,15 +65,25 @@ impl App {
                     .iter()
                     .filter(|(_, model)| {
                         !matches!(model.status, crate::provider::ModelStatus::Deprecated)
+                            && model.capabilities.missing(filter).is_empty()
                     })
                     .map(move |(model_id, model)| SelectItem {
                         id: format!("{}/{}", provider.id, model_id),
-                        label: format!("{}{}", model.name, model_status_badge(model.status)),
+                        label: format!(
+                            "{}{}{}",
+                            model.name,
+                            model_status_badge(model.status),
+                            missing_capability_badge(&model.capabilities.missing(required))
+                        ),
                         description: Some(format!("{} - {}", provider.name, model_id)),
                         provider_id: Some(provider.id.clone()),
                     })
             })
-            .collect()
+            .collect();
+
+        let mut items = alias_items(&provider::aliases(), &models);
+        items.extend(models);
+        items
     }
 
     /// Open the provider selector dialog
@@ -145,6 +177,205 @@ impl App {
         Ok(())
     }
 
+    /// Open the branch dialog showing the parent/child tree of sessions
+    pub async fn open_branch_list(&mut self) -> Result<()> {
+        use crate::session::Session;
+
+        let project_id = self
+            .session
+            .as_ref()
+            .map_or("default", |s| s.project_id.as_str())
+            .to_string();
+        let current_id = self.session.as_ref().map(|s| s.id.clone());
+        let sessions = Session::list(&project_id).await?;
+
+        let items: Vec<SelectItem> = Session::tree(&sessions)
+            .into_iter()
+            .map(|(depth, s)| {
+                let marker = if current_id.as_deref() == Some(s.id.as_str()) {
+                    "● "
+                } else {
+                    ""
+                };
+                let branch = if depth == 0 { "" } else { "└ " };
+
+                SelectItem {
+                    id: s.id.clone(),
+                    label: format!("{}{}{}{}", "  ".repeat(depth), branch, marker, s.title),
+                    description: Some(format!("Slug: {}", s.slug)),
+                    provider_id: None,
+                }
+            })
+            .collect();
+
+        let mut dialog = DialogState::new(DialogType::BranchList, "Session Branches")
+            .with_items(items)
+            .with_message("Select a branch to switch to");
+        if let Some(idx) = current_id
+            .as_deref()
+            .and_then(|id| dialog.items.iter().position(|item| item.id == id))
+        {
+            dialog.selected_index = idx;
+        }
+        self.dialog = Some(dialog);
+
+        Ok(())
+    }
+
+    /// Switch to a stored session, resetting the transcript
+    pub async fn switch_session(&mut self, session_id: &str) {
+        use crate::session::Session;
+
+        let project_id = self
+            .session
+            .as_ref()
+            .map_or("default", |s| s.project_id.as_str())
+            .to_string();
+        if let Ok(Some(session)) = Session::get(&project_id, session_id).await {
+            self.session_title = session.title.clone();
+            self.session_slug = session.slug.clone();
+            let session_title = session.title.clone();
+            self.session = Some(session);
+            self.restore_session_model().await;
+            self.load_recent_messages().await;
+            self.total_cost = 0.0;
+            self.total_tokens = 0;
+            self.add_message("system", &format!("Switched to session: {}", session_title));
+            self.restore_questions(session_id).await;
+        }
+    }
+
+    /// Reopen a question left unanswered in a session, e.g. by a restart
+    ///
+    /// The tool that asked it is gone, so the answers are put into the input
+    /// to send instead (see `handle_question_reply`).
+    pub async fn restore_questions(&mut self, session_id: &str) {
+        self.restored_questions = crate::question_state::restore(session_id).await;
+        if let Some(request) = self.restored_questions.first().cloned() {
+            self.add_message(
+                "system",
+                "A question from before the restart is still waiting for an answer",
+            );
+            self.open_question_dialog(request);
+        }
+    }
+
+    /// Open the diff viewer for files changed in the current session
+    pub async fn open_diff_viewer(&mut self) -> Result<()> {
+        use crate::session::{diff, SessionSummary};
+
+        let Some(session) = &self.session else {
+            return Ok(());
+        };
+
+        let diffs = diff::list(&session.id).await?;
+        if diffs.is_empty() {
+            self.add_message("system", "No file changes in this session");
+            return Ok(());
+        }
+
+        let items: Vec<SelectItem> = diffs
+            .iter()
+            .enumerate()
+            .map(|(idx, d)| SelectItem {
+                id: idx.to_string(),
+                label: d.path.clone(),
+                description: Some(format!("+{} -{}", d.additions, d.deletions)),
+                provider_id: None,
+            })
+            .collect();
+
+        let summary = SessionSummary::from_diffs(diffs);
+        let mut dialog = DialogState::new(DialogType::DiffList, "File Changes")
+            .with_items(items)
+            .with_message(&format!(
+                "{} files changed, +{} -{}",
+                summary.files, summary.additions, summary.deletions
+            ));
+        dialog.file_diffs = summary.diffs.unwrap_or_default();
+        self.dialog = Some(dialog);
+
+        Ok(())
+    }
+
+    /// Copy a code block from the last assistant message
+    ///
+    /// A single block is copied directly; several open a picker.
+    pub fn copy_code_block(&mut self, fenced: bool) {
+        let blocks = self
+            .messages
+            .iter()
+            .rev()
+            .find(|m| m.role == "assistant")
+            .map(|m| extract_code_blocks(&m.content))
+            .unwrap_or_default();
+
+        let text = |block: &CodeBlock| {
+            if fenced {
+                block.fenced()
+            } else {
+                block.code.clone()
+            }
+        };
+
+        match blocks.as_slice() {
+            [] => self.add_message("system", "No code blocks in the last response"),
+            [block] => self.copy_code_text(&text(block)),
+            _ => {
+                let items: Vec<SelectItem> = blocks
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, block)| SelectItem {
+                        id: idx.to_string(),
+                        label: format!(
+                            "{}. {}",
+                            idx + 1,
+                            block.language.as_deref().unwrap_or("text")
+                        ),
+                        description: Some(block.first_line().to_string()),
+                        provider_id: None,
+                    })
+                    .collect();
+
+                let mut dialog = DialogState::new(DialogType::CodeBlockList, "Copy Code Block")
+                    .with_items(items);
+                dialog.code_blocks = blocks.iter().map(text).collect();
+                self.dialog = Some(dialog);
+            }
+        }
+    }
+
+    /// Open a fuzzy search over past prompts (most recent first)
+    pub fn open_history_search(&mut self) {
+        if self.input_history.is_empty() {
+            self.status = "No prompt history".to_string();
+            return;
+        }
+
+        let items: Vec<SelectItem> = self
+            .input_history
+            .iter()
+            .map(|entry| SelectItem {
+                id: entry.clone(),
+                label: entry.split_whitespace().collect::<Vec<_>>().join(" "),
+                description: None,
+                provider_id: None,
+            })
+            .collect();
+
+        let dialog =
+            DialogState::new(DialogType::HistorySearch, "Search Prompt History").with_items(items);
+        self.dialog = Some(dialog);
+    }
+
+    /// Copy code to the clipboard and report the result
+    fn copy_code_text(&mut self, text: &str) {
+        match copy_to_clipboard(&self.redactor.redact(text)) {
+            Ok(_) => self.add_message("system", "Code block copied to clipboard"),
+            Err(e) => self.add_message("system", &format!("Failed to copy to clipboard: {}", e)),
+        }
+    }
+
     /// Open agent selector dialog
     pub async fn open_agent_selector(&mut self) -> Result<()> {
         // Load config to get agent definitions
@@ -221,9 +452,10 @@ impl App {
             })
             .collect();
 
-        let dialog = DialogState::new(DialogType::Timeline, "Message Timeline")
+        let mut dialog = DialogState::new(DialogType::Timeline, "Message Timeline")
             .with_items(items)
-            .with_message("Select a message to view");
+            .with_message("Select a message to view it, or one of yours to edit and resend");
+        dialog.marked = self.marked_messages.clone();
         self.dialog = Some(dialog);
     }
 
@@ -329,6 +561,10 @@ async fn handle_selector_input(app: &mut App, key_code: KeyCode) -> Result<()> {
         }
         KeyCode::Up => dialog.move_up(),
         KeyCode::Down => dialog.move_down(),
+        KeyCode::Tab if dialog.dialog_type == DialogType::Timeline => {
+            dialog.toggle_mark();
+            app.marked_messages = dialog.marked.clone();
+        }
         KeyCode::Char(c) => {
             dialog.search_query.push(c);
             dialog.update_filter();
@@ -357,8 +593,15 @@ async fn handle_selector_enter(app: &mut App) -> Result<()> {
         DialogType::ModelSelector => {
             if let Some((provider_id, model_id)) = provider::parse_model_string(&item_id) {
                 app.set_model(&provider_id, &model_id).await?;
+                if app.onboarding {
+                    app.offer_starter_config();
+                }
             }
         }
+        DialogType::StarterConfig => {
+            app.finish_onboarding(&item_id).await;
+        }
+        DialogType::DraftRecovery => app.finish_draft_recovery(&item_id),
         DialogType::ProviderSelector => {
             let has_key = app
                 .all_providers
@@ -372,18 +615,8 @@ async fn handle_selector_enter(app: &mut App) -> Result<()> {
                 app.open_auth_method_selector(&item_id);
             }
         }
-        DialogType::SessionList => {
-            use crate::session::Session;
-            if let Ok(Some(session)) = Session::get("default", &item_id).await {
-                app.session_title = session.title.clone();
-                app.session_slug = session.slug.clone();
-                let session_title = session.title.clone();
-                app.session = Some(session);
-                app.messages.clear();
-                app.total_cost = 0.0;
-                app.total_tokens = 0;
-                app.add_message("system", &format!("Switched to session: {}", session_title));
-            }
+        DialogType::SessionList | DialogType::BranchList => {
+            app.switch_session(&item_id).await;
             app.close_dialog();
         }
         DialogType::AgentSelector => {
@@ -400,9 +633,43 @@ async fn handle_selector_enter(app: &mut App) -> Result<()> {
             );
             app.close_dialog();
         }
+        DialogType::DiffList => {
+            if let Some(dialog) = &mut app.dialog {
+                if let Some(diff) = item_id
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|idx| dialog.file_diffs.get(idx))
+                {
+                    dialog.title = diff.path.clone();
+                    dialog.dialog_type = DialogType::DiffView;
+                    dialog.diff_scroll = 0;
+                }
+            }
+        }
+        DialogType::HistorySearch => {
+            app.close_dialog();
+            app.recall_history(item_id);
+        }
+        DialogType::CodeBlockList => {
+            let text = item_id
+                .parse::<usize>()
+                .ok()
+                .and_then(|idx| dialog.code_blocks.get(idx).cloned());
+            app.close_dialog();
+            if let Some(text) = text {
+                app.copy_code_text(&text);
+            }
+        }
         DialogType::Timeline => {
+            app.close_dialog();
             if let Ok(msg_index) = item_id.parse::<usize>() {
-                if let Some(msg) = app.messages.get(msg_index) {
+                if app
+                    .messages
+                    .get(msg_index)
+                    .is_some_and(|m| m.role == "user")
+                {
+                    app.begin_message_edit(msg_index).await;
+                } else if let Some(msg) = app.messages.get(msg_index) {
                     app.add_message(
                         "system",
                         &format!(
@@ -415,13 +682,43 @@ async fn handle_selector_enter(app: &mut App) -> Result<()> {
                     );
                 }
             }
-            app.close_dialog();
         }
         _ => {}
     }
     Ok(())
 }
 
+/// Handle input for the diff view (scrolling and going back to the file list)
+fn handle_diff_view_input(dialog: &mut DialogState, key_code: KeyCode) {
+    const PAGE: usize = 10;
+
+    let line_count = dialog
+        .selected_item()
+        .and_then(|item| item.id.parse::<usize>().ok())
+        .and_then(|idx| dialog.file_diffs.get(idx))
+        .and_then(|d| d.diff.as_ref())
+        .map(|text| text.lines().count())
+        .unwrap_or(0);
+    let max_scroll = line_count.saturating_sub(1);
+
+    match key_code {
+        KeyCode::Esc | KeyCode::Backspace | KeyCode::Char('q') => {
+            dialog.title = "File Changes".to_string();
+            dialog.dialog_type = DialogType::DiffList;
+        }
+        KeyCode::Up | KeyCode::Char('k') => {
+            dialog.diff_scroll = dialog.diff_scroll.saturating_sub(1);
+        }
+        KeyCode::Down | KeyCode::Char('j') => {
+            dialog.diff_scroll = (dialog.diff_scroll + 1).min(max_scroll);
+        }
+        KeyCode::PageUp => dialog.diff_scroll = dialog.diff_scroll.saturating_sub(PAGE),
+        KeyCode::PageDown => dialog.diff_scroll = (dialog.diff_scroll + PAGE).min(max_scroll),
+        KeyCode::Home => dialog.diff_scroll = 0,
+        _ => {}
+    }
+}
+
 /// Handle character input for text dialogs
 fn handle_text_char(dialog: &mut DialogState, key_code: KeyCode) {
     match key_code {
@@ -517,9 +814,8 @@ async fn handle_api_key_submit(app: &mut App) -> Result<()> {
     // Set environment variable for current session
     std::env::set_var(&env_var, &api_key);
 
-    // Re-initialize registry
-    let config = Config::load().await?;
-    provider::registry().initialize(&config).await?;
+    // Pick up the new key without reloading the model catalog
+    provider::registry().reload_credentials().await;
 
     // Update cached providers
     app.all_providers = provider::registry().list().await;
@@ -618,6 +914,9 @@ async fn handle_permission_input(
         return Ok(());
     };
     let id = permission_request.id.clone();
+    if dialog.is_batch_permission() {
+        return handle_batch_permission_input(app, id, key_code, event_tx).await;
+    }
 
     // Arrow key navigation
     match key_code {
@@ -652,6 +951,54 @@ async fn handle_permission_input(
     Ok(())
 }
 
+/// Map batch permission option index to a decision
+fn batch_option_to_decision(index: usize) -> Option<crate::tool::BatchDecision> {
+    use crate::tool::BatchDecision;
+    match index {
+        0 => Some(BatchDecision::ApproveAll),
+        1 => Some(BatchDecision::Individually),
+        2 => Some(BatchDecision::DenyAll),
+        _ => None,
+    }
+}
+
+/// Handle input for a permission request covering a batch of tool calls
+async fn handle_batch_permission_input(
+    app: &mut App,
+    id: String,
+    key_code: KeyCode,
+    event_tx: &mpsc::Sender<AppEvent>,
+) -> Result<()> {
+    use crate::tool::BatchDecision;
+
+    let Some(dialog) = &mut app.dialog else {
+        return Ok(());
+    };
+    let decision = match key_code {
+        KeyCode::Left => {
+            dialog.move_permission_left();
+            None
+        }
+        KeyCode::Right => {
+            dialog.move_permission_right();
+            None
+        }
+        KeyCode::Char('a' | 'A' | 'y' | 'Y') => Some(BatchDecision::ApproveAll),
+        KeyCode::Char('e' | 'E') => Some(BatchDecision::Individually),
+        KeyCode::Char('n' | 'N') | KeyCode::Esc => Some(BatchDecision::DenyAll),
+        KeyCode::Enter => batch_option_to_decision(dialog.selected_permission_option),
+        _ => None,
+    };
+
+    if let Some(decision) = decision {
+        app.close_dialog();
+        let _ = event_tx
+            .send(AppEvent::BatchPermissionResponse { id, decision })
+            .await;
+    }
+    Ok(())
+}
+
 /// Handle custom answer editing in question dialog
 fn handle_custom_answer_edit(dialog: &mut DialogState, key_code: KeyCode) {
     let current_q_idx = dialog.current_question_index;
@@ -701,14 +1048,21 @@ async fn handle_question_input(
     let max_options = current_question.options.len() + usize::from(current_question.custom);
 
     match key_code {
-        KeyCode::Esc => app.close_dialog(),
+        // Cancel: the tool gets no answers and the stored question is removed
+        KeyCode::Esc => {
+            app.close_dialog();
+            let _ = event_tx
+                .send(AppEvent::QuestionReplied {
+                    id: question_request.id,
+                    answers: Vec::new(),
+                })
+                .await;
+        }
         KeyCode::Up | KeyCode::Char('k') => {
             dialog.current_option_index = dialog.current_option_index.saturating_sub(1);
         }
-        KeyCode::Down | KeyCode::Char('j') => {
-            if dialog.current_option_index + 1 < max_options {
-                dialog.current_option_index += 1;
-            }
+        KeyCode::Down | KeyCode::Char('j') if dialog.current_option_index + 1 < max_options => {
+            dialog.current_option_index += 1;
         }
         KeyCode::Tab if current_q_idx + 1 < question_count => {
             dialog.current_question_index += 1;
@@ -818,9 +1172,29 @@ pub async fn handle_dialog_input(
         | DialogType::ProviderSelector
         | DialogType::SessionList
         | DialogType::Timeline
-        | DialogType::AgentSelector => {
+        | DialogType::AgentSelector
+        | DialogType::DiffList
+        | DialogType::CodeBlockList
+        | DialogType::BranchList
+        | DialogType::StarterConfig
+        | DialogType::DraftRecovery => {
             handle_selector_input(app, key.code).await?;
         }
+        DialogType::HistorySearch => {
+            // Pressing the search key again steps to the next older match
+            if app.keymap.lookup(&key) == Some(KeyAction::HistorySearch) {
+                if let Some(dialog) = &mut app.dialog {
+                    dialog.move_down();
+                }
+            } else {
+                handle_selector_input(app, key.code).await?;
+            }
+        }
+        DialogType::DiffView => {
+            if let Some(dialog) = &mut app.dialog {
+                handle_diff_view_input(dialog, key.code);
+            }
+        }
         DialogType::ApiKeyInput => {
             handle_api_key_input(app, key.code).await?;
         }
@@ -846,6 +1220,23 @@ pub async fn handle_dialog_input(
     Ok(())
 }
 
+/// Selector entries for the aliases of listed models, marked as aliases
+///
+/// An entry selects the model the alias stands for.
+fn alias_items(aliases: &[(String, String)], models: &[SelectItem]) -> Vec<SelectItem> {
+    aliases
+        .iter()
+        .filter_map(|(alias, target)| {
+            let model = models.iter().find(|item| &item.id == target)?;
+            Some(SelectItem {
+                label: format!("{} [alias]", alias),
+                description: Some(format!("alias for {} ({})", target, model.label)),
+                ..model.clone()
+            })
+        })
+        .collect()
+}
+
 /// Get status badge text for a model status
 fn model_status_badge(status: crate::provider::ModelStatus) -> &'static str {
     match status {
@@ -856,6 +1247,23 @@ fn model_status_badge(status: crate::provider::ModelStatus) -> &'static str {
     }
 }
 
+/// Badge flagging capabilities a model lacks, e.g. ` [no toolcall]`
+fn missing_capability_badge(missing: &[Capability]) -> String {
+    missing
+        .iter()
+        .map(|capability| format!(" [no {}]", capability.name()))
+        .collect()
+}
+
+/// Comma-separated capability names
+pub fn capability_names(capabilities: &[Capability]) -> String {
+    capabilities
+        .iter()
+        .map(|capability| capability.name())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
 /// Get auth method items for a provider, if OAuth is supported
 fn get_auth_method_items(provider_id: &str) -> Option<Vec<SelectItem>> {
     let (oauth_label, oauth_desc, key_label, key_desc) = match provider_id {
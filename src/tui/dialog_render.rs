@@ -3,11 +3,16 @@
 //! This module contains the rendering logic for various dialog types,
 //! extracted from ui.rs for better organization and maintainability.
 
+use std::rc::Rc;
+
 use ratatui::{
-    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    layout::{Alignment, Constraint, Direction, Layout, Position, Rect},
     style::{Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, Clear, List, ListItem, Paragraph, Wrap},
+    widgets::{
+        Block, Borders, Clear, List, ListItem, Paragraph, Scrollbar, ScrollbarOrientation,
+        ScrollbarState, Wrap,
+    },
     Frame,
 };
 
@@ -27,6 +32,15 @@ fn calculate_dialog_area(area: Rect) -> Rect {
     Rect::new(x, y, width, height)
 }
 
+/// Calculate a large centered dialog area (for content such as diffs)
+fn calculate_large_dialog_area(area: Rect) -> Rect {
+    let width = (area.width * 9 / 10).max(40).min(area.width);
+    let height = (area.height * 9 / 10).max(10).min(area.height);
+    let x = (area.width.saturating_sub(width)) / 2;
+    let y = (area.height.saturating_sub(height)) / 2;
+    Rect::new(x, y, width, height)
+}
+
 /// Render a help text at the bottom of a dialog
 fn render_help_text(frame: &mut Frame, theme: &Theme, area: Rect, text: &str) {
     let help = Paragraph::new(text)
@@ -83,7 +97,11 @@ fn input_dialog_constraints() -> [Constraint; 5] {
 
 /// Render a dialog overlay
 pub fn render_dialog(frame: &mut Frame, dialog: &DialogState, theme: &Theme, area: Rect) {
-    let dialog_area = calculate_dialog_area(area);
+    let dialog_area = if dialog.dialog_type == DialogType::DiffView {
+        calculate_large_dialog_area(area)
+    } else {
+        calculate_dialog_area(area)
+    };
 
     frame.render_widget(Clear, dialog_area);
 
@@ -97,18 +115,18 @@ pub fn render_dialog(frame: &mut Frame, dialog: &DialogState, theme: &Theme, are
     frame.render_widget(block, dialog_area);
 
     match dialog.dialog_type {
-        DialogType::ModelSelector
-        | DialogType::ProviderSelector
-        | DialogType::AuthMethodSelector
-        | DialogType::SessionList
-        | DialogType::Timeline
-        | DialogType::AgentSelector => render_select_dialog(frame, dialog, theme, inner),
+        _ if dialog.dialog_type.uses_select_list() => {
+            render_select_dialog(frame, dialog, theme, inner)
+        }
+        DialogType::DiffView => render_diff_dialog(frame, dialog, theme, inner),
         DialogType::ApiKeyInput => render_input_dialog(frame, dialog, theme, inner, true),
         DialogType::SessionRename => render_input_dialog(frame, dialog, theme, inner, false),
         DialogType::OAuthDeviceCode => render_device_code_dialog(frame, dialog, theme, inner),
         DialogType::OAuthWaiting => render_waiting_dialog(frame, dialog, theme, inner),
         DialogType::PermissionRequest => render_permission_dialog(frame, dialog, theme, inner),
         DialogType::Question => render_question_dialog(frame, dialog, theme, inner),
+        // The remaining dialogs are lists, handled by the first arm
+        _ => render_select_dialog(frame, dialog, theme, inner),
     }
 }
 
@@ -116,9 +134,9 @@ pub fn render_dialog(frame: &mut Frame, dialog: &DialogState, theme: &Theme, are
 // Dialog Type Renderers
 // ============================================================================
 
-/// Render a selection dialog (model, provider, session selector, etc.)
-fn render_select_dialog(frame: &mut Frame, dialog: &DialogState, theme: &Theme, area: Rect) {
-    let chunks = Layout::default()
+/// Split a selection dialog into message, search, divider, list and help
+fn select_dialog_layout(area: Rect) -> Rc<[Rect]> {
+    Layout::default()
         .direction(Direction::Vertical)
         .constraints([
             Constraint::Length(1), // Message
@@ -127,7 +145,51 @@ fn render_select_dialog(frame: &mut Frame, dialog: &DialogState, theme: &Theme,
             Constraint::Min(3),    // List
             Constraint::Length(1), // Help
         ])
-        .split(area);
+        .split(area)
+}
+
+/// Position of the first shown item, keeping the selection mid-list
+///
+/// Near the end of the list the window stops at the last item, so it stays
+/// full and the selection is always in view.
+fn select_list_start(dialog: &DialogState, visible_count: usize) -> usize {
+    let last_start = dialog.filtered_indices.len().saturating_sub(visible_count);
+    dialog
+        .selected_index
+        .saturating_sub(visible_count / 2)
+        .min(last_start)
+}
+
+/// Split the list area into items and, when they do not all fit, a
+/// scrollbar column on the right
+fn select_list_areas(dialog: &DialogState, list: Rect) -> (Rect, Option<Rect>) {
+    if dialog.filtered_indices.len() <= list.height as usize || list.width < 2 {
+        return (list, None);
+    }
+    let [items, scrollbar] =
+        Layout::horizontal([Constraint::Min(1), Constraint::Length(1)]).areas(list);
+    (items, Some(scrollbar))
+}
+
+/// Position in the filtered list of the item shown at a screen cell
+///
+/// Mirrors the layout of `render_dialog` for selection dialogs, so mouse
+/// clicks can be mapped to items.
+pub fn select_item_at(dialog: &DialogState, screen: Rect, column: u16, row: u16) -> Option<usize> {
+    let inner = Block::default()
+        .borders(Borders::ALL)
+        .inner(calculate_dialog_area(screen));
+    let (list, _) = select_list_areas(dialog, select_dialog_layout(inner)[3]);
+    if !list.contains(Position::new(column, row)) {
+        return None;
+    }
+    let position = select_list_start(dialog, list.height as usize) + (row - list.y) as usize;
+    (position < dialog.filtered_indices.len()).then_some(position)
+}
+
+/// Render a selection dialog (model, provider, session selector, etc.)
+fn render_select_dialog(frame: &mut Frame, dialog: &DialogState, theme: &Theme, area: Rect) {
+    let chunks = select_dialog_layout(area);
 
     // Message (dimmed for selector dialogs)
     if let Some(message) = &dialog.message {
@@ -151,9 +213,10 @@ fn render_select_dialog(frame: &mut Frame, dialog: &DialogState, theme: &Theme,
     };
     frame.render_widget(Paragraph::new(Line::from(search_spans)), chunks[1]);
 
-    // List items
-    let visible_count = chunks[3].height as usize;
-    let start_index = dialog.selected_index.saturating_sub(visible_count / 2);
+    // List items, windowed to the visible rows
+    let (list_area, scrollbar_area) = select_list_areas(dialog, chunks[3]);
+    let visible_count = list_area.height as usize;
+    let start_index = select_list_start(dialog, visible_count);
 
     let items: Vec<ListItem> = dialog
         .filtered_indices
@@ -165,9 +228,14 @@ fn render_select_dialog(frame: &mut Frame, dialog: &DialogState, theme: &Theme,
             let item = &dialog.items[item_idx];
             let is_selected = start_index + i == dialog.selected_index;
 
+            let mark = if dialog.marked.contains(&item_idx) {
+                "* "
+            } else {
+                "  "
+            };
             let content = match &item.description {
-                Some(desc) => format!("  {} - {}", item.label, desc),
-                None => format!("  {}", item.label),
+                Some(desc) => format!("{}{} - {}", mark, item.label, desc),
+                None => format!("{}{}", mark, item.label),
             };
 
             ListItem::new(content).style(selection_style(theme, is_selected))
@@ -180,15 +248,26 @@ fn render_select_dialog(frame: &mut Frame, dialog: &DialogState, theme: &Theme,
             .alignment(Alignment::Center);
         frame.render_widget(empty, chunks[3]);
     } else {
-        frame.render_widget(List::new(items), chunks[3]);
+        frame.render_widget(List::new(items), list_area);
+    }
+    if let Some(area) = scrollbar_area {
+        let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
+            .begin_symbol(None)
+            .end_symbol(None)
+            .track_symbol(Some("│"))
+            .style(theme.text_dim());
+        let mut state = ScrollbarState::new(dialog.filtered_indices.len())
+            .viewport_content_length(visible_count)
+            .position(dialog.selected_index);
+        frame.render_stateful_widget(scrollbar, area, &mut state);
     }
 
-    render_help_text(
-        frame,
-        theme,
-        chunks[4],
-        "Up/Down: Navigate | Enter: Select | Esc: Cancel",
-    );
+    let help = if dialog.dialog_type == DialogType::Timeline {
+        "Up/Down: Navigate | Enter: Select | Tab: Mark for /yank-context | Esc: Cancel"
+    } else {
+        "Up/Down: Navigate | Enter: Select | Esc: Cancel"
+    };
+    render_help_text(frame, theme, chunks[4], help);
 }
 
 /// Render an input dialog (API key input or session rename)
@@ -311,6 +390,11 @@ fn render_waiting_dialog(frame: &mut Frame, dialog: &DialogState, theme: &Theme,
 
 /// Render permission request dialog
 fn render_permission_dialog(frame: &mut Frame, dialog: &DialogState, theme: &Theme, area: Rect) {
+    if dialog.is_batch_permission() {
+        render_batch_permission_dialog(frame, dialog, theme, area);
+        return;
+    }
+
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
@@ -348,15 +432,12 @@ fn render_permission_dialog(frame: &mut Frame, dialog: &DialogState, theme: &The
     // Patterns and metadata
     let metadata_text =
         serde_json::to_string_pretty(&req.metadata).unwrap_or_else(|_| "{}".to_string());
-    let truncated_metadata = if metadata_text.len() > 300 {
-        format!("{}...", &metadata_text[..300])
-    } else {
-        metadata_text
-    };
+    let truncated_metadata = super::tool_block::truncate_chars(&metadata_text, 300);
 
     let details = Paragraph::new(format!(
-        "Patterns: {}\n\nMetadata:\n{}",
+        "Patterns: {}\nAlways allows: {}\n\nMetadata:\n{}",
         req.patterns.join(", "),
+        req.always.join(", "),
         truncated_metadata
     ))
     .style(theme.text())
@@ -364,19 +445,101 @@ fn render_permission_dialog(frame: &mut Frame, dialog: &DialogState, theme: &The
     frame.render_widget(details, chunks[4]);
 
     // Permission options
-    let selected = dialog.selected_permission_option;
-    let options_config: [(usize, &str, &str, ratatui::style::Color); 5] = [
-        (0, "[Y]", " Once    ", theme.success),
-        (1, "[S]", " Session    ", theme.accent),
-        (2, "[W]", " Workspace    ", theme.accent),
-        (3, "[G]", " Global    ", theme.accent),
-        (4, "[N]", " Reject", theme.error),
-    ];
-
-    let option_spans: Vec<Span> = options_config
+    let options_widget = permission_options(
+        &[
+            ("[Y]", " Once    ", theme.success),
+            ("[S]", " Session    ", theme.accent),
+            ("[W]", " Workspace    ", theme.accent),
+            ("[G]", " Global    ", theme.accent),
+            ("[N]", " Reject", theme.error),
+        ],
+        dialog.selected_permission_option,
+        theme,
+    );
+    frame.render_widget(options_widget, chunks[6]);
+
+    render_help_text(
+        frame,
+        theme,
+        chunks[7],
+        "Left/Right: Navigate | Enter: Confirm | Y/S/W/G/N: Direct select | Esc: Cancel",
+    );
+}
+
+/// Render the summary of a batch of tool calls awaiting permission
+fn render_batch_permission_dialog(
+    frame: &mut Frame,
+    dialog: &DialogState,
+    theme: &Theme,
+    area: Rect,
+) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(2), // Title
+            Constraint::Length(1), // Spacer
+            Constraint::Min(3),    // Tool calls
+            Constraint::Length(1), // Spacer
+            Constraint::Length(3), // Options
+            Constraint::Length(1), // Help
+        ])
+        .split(area);
+
+    let Some(req) = &dialog.permission_request else {
+        return;
+    };
+
+    let title = Paragraph::new(format!("Run {} Tool Calls?", req.calls.len()))
+        .style(
+            Style::default()
+                .fg(theme.warning)
+                .add_modifier(Modifier::BOLD),
+        )
+        .alignment(Alignment::Center);
+    frame.render_widget(title, chunks[0]);
+
+    let lines: Vec<Line> = req
+        .calls
         .iter()
-        .flat_map(|(idx, key, label, color)| {
-            let is_selected = selected == *idx;
+        .map(|call| {
+            Line::from(vec![
+                Span::styled(format!("{:<10} ", call.tool), theme.text_accent()),
+                Span::styled(call.preview.clone(), theme.text()),
+            ])
+        })
+        .collect();
+    frame.render_widget(Paragraph::new(lines), chunks[2]);
+
+    let options_widget = permission_options(
+        &[
+            ("[A]", " Approve all    ", theme.success),
+            ("[E]", " Decide each    ", theme.accent),
+            ("[N]", " Deny all", theme.error),
+        ],
+        dialog.selected_permission_option,
+        theme,
+    );
+    frame.render_widget(options_widget, chunks[4]);
+
+    render_help_text(
+        frame,
+        theme,
+        chunks[5],
+        "Left/Right: Navigate | Enter: Confirm | A/E/N: Direct select | Esc: Deny all",
+    );
+}
+
+/// Row of permission options as `(key, label, color)`, highlighting `selected`
+fn permission_options<'a>(
+    options: &[(&'a str, &'a str, ratatui::style::Color)],
+    selected: usize,
+    theme: &Theme,
+) -> Paragraph<'a> {
+    let option_spans: Vec<Span> = options
+        .iter()
+        .enumerate()
+        .flat_map(|(idx, (key, label, color))| {
+            let is_selected = selected == idx;
             let key_style = if is_selected {
                 Style::default()
                     .fg(theme.background)
@@ -397,17 +560,57 @@ fn render_permission_dialog(frame: &mut Frame, dialog: &DialogState, theme: &The
         })
         .collect();
 
-    let options_widget = Paragraph::new(vec![Line::from(option_spans)])
+    Paragraph::new(vec![Line::from(option_spans)])
         .alignment(Alignment::Center)
-        .style(theme.text());
-    frame.render_widget(options_widget, chunks[6]);
+        .style(theme.text())
+}
 
-    render_help_text(
-        frame,
-        theme,
-        chunks[7],
-        "Left/Right: Navigate | Enter: Confirm | Y/S/W/G/N: Direct select | Esc: Cancel",
-    );
+/// Render a unified diff with added/removed lines colorized
+fn render_diff_dialog(frame: &mut Frame, dialog: &DialogState, theme: &Theme, area: Rect) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(3),    // Diff
+            Constraint::Length(1), // Help
+        ])
+        .split(area);
+
+    let diff = dialog
+        .selected_item()
+        .and_then(|item| item.id.parse::<usize>().ok())
+        .and_then(|idx| dialog.file_diffs.get(idx));
+
+    let lines: Vec<Line> = diff
+        .and_then(|d| d.diff.as_deref())
+        .unwrap_or("")
+        .lines()
+        .map(|line| {
+            let style = if line.starts_with("+++") || line.starts_with("---") {
+                theme.text_dim().add_modifier(Modifier::BOLD)
+            } else if line.starts_with('+') {
+                Style::default().fg(theme.success)
+            } else if line.starts_with('-') {
+                Style::default().fg(theme.error)
+            } else if line.starts_with("@@") {
+                theme.text_accent()
+            } else {
+                theme.text()
+            };
+            Line::from(Span::styled(line.to_string(), style))
+        })
+        .collect();
+
+    let paragraph = Paragraph::new(lines).scroll((dialog.diff_scroll as u16, 0));
+    frame.render_widget(paragraph, chunks[0]);
+
+    let help = match diff {
+        Some(d) => format!(
+            "+{} -{} | Up/Down: Scroll | PgUp/PgDn: Page | Esc: Back",
+            d.additions, d.deletions
+        ),
+        None => "Esc: Back".to_string(),
+    };
+    render_help_text(frame, theme, chunks[1], &help);
 }
 
 /// Render question dialog
@@ -556,3 +759,58 @@ fn render_question_dialog(frame: &mut Frame, dialog: &DialogState, theme: &Theme
 
     render_help_text(frame, theme, chunks[6], help_text);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tui::types::SelectItem;
+
+    #[test]
+    fn test_select_item_at() {
+        let items = (0..3)
+            .map(|i| SelectItem {
+                id: i.to_string(),
+                label: format!("Item {}", i),
+                description: None,
+                provider_id: None,
+            })
+            .collect();
+        let dialog = DialogState::new(DialogType::SessionList, "Sessions").with_items(items);
+        // An 80x24 screen puts the dialog at (10, 2) and its list at row 6
+        let screen = Rect::new(0, 0, 80, 24);
+
+        assert_eq!(select_item_at(&dialog, screen, 20, 6), Some(0));
+        assert_eq!(select_item_at(&dialog, screen, 20, 8), Some(2));
+        // Below the last item, on the search line and outside the dialog
+        assert_eq!(select_item_at(&dialog, screen, 20, 9), None);
+        assert_eq!(select_item_at(&dialog, screen, 20, 4), None);
+        assert_eq!(select_item_at(&dialog, screen, 5, 6), None);
+    }
+
+    #[test]
+    fn test_long_list_window_stays_full() {
+        let items = (0..30)
+            .map(|i| SelectItem {
+                id: i.to_string(),
+                label: format!("Item {}", i),
+                description: None,
+                provider_id: None,
+            })
+            .collect();
+        let mut dialog = DialogState::new(DialogType::ModelSelector, "Models").with_items(items);
+        assert_eq!(select_list_start(&dialog, 10), 0);
+        dialog.selected_index = 15;
+        assert_eq!(select_list_start(&dialog, 10), 10);
+        dialog.selected_index = 29;
+        assert_eq!(select_list_start(&dialog, 10), 20);
+
+        // The list gets a scrollbar column, which does not select items
+        let list = Rect::new(11, 6, 58, 10);
+        let (items, scrollbar) = select_list_areas(&dialog, list);
+        assert_eq!(items.width, 57);
+        assert_eq!(scrollbar, Some(Rect::new(68, 6, 1, 10)));
+        let screen = Rect::new(0, 0, 80, 24);
+        assert_eq!(select_item_at(&dialog, screen, 68, 6), None);
+        assert_eq!(select_item_at(&dialog, screen, 67, 19), Some(29));
+    }
+}
@@ -0,0 +1,342 @@
+//! Autosave and recovery of the unsent input.
+//!
+//! The input is written to a recovery file under the data directory at a
+//! configurable interval while it changes. The file is removed when the input
+//! is submitted or the TUI exits cleanly, so one left behind means the last
+//! run crashed; the next launch offers to restore it.
+//!
+//! Each project directory has its own recovery file, guarded by a lock file.
+//! While one TUI holds the lock, another started in the same directory
+//! neither autosaves nor offers the file for recovery.
+
+use std::fs::File;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use super::state::App;
+use super::tool_block::truncate_chars;
+use super::types::{DialogState, DialogType, SelectItem};
+use crate::config::TuiConfig;
+
+/// Directory of the recovery files under the data directory
+const DRAFT_DIR: &str = "drafts";
+
+/// Default seconds between autosaves
+pub const DEFAULT_INTERVAL_SECS: u64 = 5;
+
+/// Item ID of the recovery choice that restores the draft
+const RESTORE: &str = "restore";
+
+/// Max characters of the draft preview in the recovery dialog
+const PREVIEW_CHARS: usize = 60;
+
+/// Path of the recovery file for a project directory
+pub fn draft_path(project: &Path) -> PathBuf {
+    let digest = Sha256::digest(project.to_string_lossy().as_bytes());
+    let key: String = digest[..8].iter().map(|b| format!("{:02x}", b)).collect();
+    crate::storage::global()
+        .base_path()
+        .join(DRAFT_DIR)
+        .join(format!("{}.json", key))
+}
+
+/// Take the lock guarding a recovery file, None while another TUI holds it
+fn lock(path: &Path) -> io::Result<Option<File>> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let file = std::fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(path.with_extension("lock"))?;
+    match file.try_lock() {
+        Ok(()) => Ok(Some(file)),
+        Err(std::fs::TryLockError::WouldBlock) => Ok(None),
+        Err(std::fs::TryLockError::Error(e)) => Err(e),
+    }
+}
+
+/// An unsent input saved for recovery
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Draft {
+    pub input: String,
+    /// Session the input was typed in
+    pub session_id: Option<String>,
+    /// Unix time in milliseconds
+    pub saved_at: i64,
+}
+
+/// Read a draft left by an earlier run, ignoring empty or unreadable files
+pub fn load(path: &Path) -> Option<Draft> {
+    let content = std::fs::read_to_string(path).ok()?;
+    match serde_json::from_str::<Draft>(&content) {
+        Ok(draft) if !draft.input.trim().is_empty() => Some(draft),
+        Ok(_) => None,
+        Err(e) => {
+            tracing::warn!("Ignoring unreadable draft {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+/// Periodic writer of the recovery file
+#[derive(Debug, Clone)]
+pub struct DraftAutosave {
+    /// Recovery file, None when autosave is off or another TUI has the lock
+    path: Option<PathBuf>,
+    /// Lock on the recovery file, released when the last clone is dropped
+    _lock: Option<Arc<File>>,
+    interval: Duration,
+    last_save: Option<Instant>,
+    /// Input as last written
+    saved: String,
+}
+
+impl Default for DraftAutosave {
+    fn default() -> Self {
+        Self::new(None, Duration::from_secs(DEFAULT_INTERVAL_SECS))
+    }
+}
+
+impl DraftAutosave {
+    pub fn new(path: Option<PathBuf>, interval: Duration) -> Self {
+        Self {
+            path,
+            _lock: None,
+            interval,
+            last_save: None,
+            saved: String::new(),
+        }
+    }
+
+    /// Autosave to the project's recovery file, unless
+    /// `tui.autosave_interval` is 0 or another TUI holds its lock
+    pub fn from_config(config: Option<&TuiConfig>, project: &Path) -> Self {
+        let secs = config
+            .and_then(|tui| tui.autosave_interval)
+            .unwrap_or(DEFAULT_INTERVAL_SECS);
+        let interval = Duration::from_secs(secs);
+        if secs == 0 {
+            return Self::new(None, interval);
+        }
+
+        let path = draft_path(project);
+        match lock(&path) {
+            Ok(Some(file)) => Self {
+                _lock: Some(Arc::new(file)),
+                ..Self::new(Some(path), interval)
+            },
+            Ok(None) => {
+                tracing::info!("Another TUI is autosaving this project's input");
+                Self::new(None, interval)
+            }
+            Err(e) => {
+                tracing::warn!("Failed to lock {}: {}", path.display(), e);
+                Self::new(None, interval)
+            }
+        }
+    }
+
+    /// The draft left by a crashed run in this project, if any
+    pub fn recover(&self) -> Option<Draft> {
+        self.path.as_deref().and_then(load)
+    }
+
+    /// Write the input if it changed and the interval has passed
+    ///
+    /// An emptied input removes the file.
+    pub fn tick(&mut self, input: &str, session_id: Option<&str>) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        if input == self.saved || self.last_save.is_some_and(|t| t.elapsed() < self.interval) {
+            return;
+        }
+        self.last_save = Some(Instant::now());
+
+        let result = if input.trim().is_empty() {
+            remove(path)
+        } else {
+            write(
+                path,
+                &Draft {
+                    input: input.to_string(),
+                    session_id: session_id.map(String::from),
+                    saved_at: chrono::Utc::now().timestamp_millis(),
+                },
+            )
+        };
+        match result {
+            Ok(()) => self.saved = input.to_string(),
+            Err(e) => tracing::warn!("Failed to autosave draft: {}", e),
+        }
+    }
+
+    /// Remove the recovery file, after a submit or on a clean exit
+    pub fn clear(&mut self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        if let Err(e) = remove(path) {
+            tracing::warn!("Failed to remove draft: {}", e);
+        }
+        self.saved.clear();
+    }
+}
+
+/// Write through a temporary file, so a crash never leaves half a draft
+fn write(path: &Path, draft: &Draft) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, serde_json::to_string(draft)?)?;
+    std::fs::rename(&tmp, path)
+}
+
+fn remove(path: &Path) -> io::Result<()> {
+    match std::fs::remove_file(path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+/// Draft recovery methods for App
+impl App {
+    /// Save the input for crash recovery when due
+    pub fn autosave_draft(&mut self) {
+        let session_id = self.session.as_ref().map(|s| s.id.as_str());
+        self.draft.tick(&self.input, session_id);
+    }
+
+    /// Offer to restore a draft left by a crashed run
+    ///
+    /// When another dialog is already open the draft is put into the input
+    /// directly, since the input is empty at startup anyway.
+    pub fn offer_draft_recovery(&mut self, draft: Draft) {
+        if self.dialog.is_some() {
+            self.add_message("system", "Restored the unsent input of the last run");
+            self.restore_draft(draft);
+            return;
+        }
+
+        let saved = chrono::DateTime::from_timestamp_millis(draft.saved_at)
+            .map(|time| {
+                time.with_timezone(&chrono::Local)
+                    .format("%Y-%m-%d %H:%M")
+                    .to_string()
+            })
+            .unwrap_or_default();
+        let first_line = draft.input.lines().next().unwrap_or_default();
+        let dialog = DialogState::new(DialogType::DraftRecovery, "Recover Unsent Input")
+            .with_items(vec![
+                SelectItem {
+                    id: RESTORE.to_string(),
+                    label: "Restore".to_string(),
+                    description: Some(truncate_chars(first_line, PREVIEW_CHARS)),
+                    provider_id: None,
+                },
+                SelectItem {
+                    id: "discard".to_string(),
+                    label: "Discard".to_string(),
+                    description: None,
+                    provider_id: None,
+                },
+            ])
+            .with_message(&format!(
+                "The last run exited with an unsent input ({} lines, saved {})",
+                draft.input.lines().count(),
+                saved
+            ));
+        self.recovered_draft = Some(draft);
+        self.dialog = Some(dialog);
+    }
+
+    /// Handle the recovery choice
+    pub fn finish_draft_recovery(&mut self, choice: &str) {
+        self.close_dialog();
+        match self.recovered_draft.take() {
+            Some(draft) if choice == RESTORE => self.restore_draft(draft),
+            _ => self.draft.clear(),
+        }
+    }
+
+    fn restore_draft(&mut self, draft: Draft) {
+        self.cursor_position = draft.input.len();
+        self.input = draft.input;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_autosave_and_clear() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("draft.json");
+        let mut autosave = DraftAutosave::new(Some(path.clone()), Duration::ZERO);
+
+        autosave.tick("half-written prompt", Some("ses_1"));
+        let draft = load(&path).unwrap();
+        assert_eq!(draft.input, "half-written prompt");
+        assert_eq!(draft.session_id.as_deref(), Some("ses_1"));
+
+        // Emptying the input removes the file
+        autosave.tick("", None);
+        assert!(!path.exists());
+
+        autosave.tick("again", None);
+        autosave.clear();
+        assert!(load(&path).is_none());
+    }
+
+    #[test]
+    fn test_autosave_waits_for_interval() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("draft.json");
+        let mut autosave = DraftAutosave::new(Some(path.clone()), Duration::from_secs(3600));
+
+        autosave.tick("first", None);
+        autosave.tick("second", None);
+        assert_eq!(load(&path).unwrap().input, "first");
+    }
+
+    #[test]
+    fn test_lock_is_exclusive() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("draft.json");
+        let held = lock(&path).unwrap();
+        assert!(held.is_some());
+        assert!(lock(&path).unwrap().is_none());
+        drop(held);
+        assert!(lock(&path).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_recovery_choice() {
+        let draft = Draft {
+            input: "unsent".to_string(),
+            session_id: None,
+            saved_at: 0,
+        };
+        let mut app = App::default();
+        app.offer_draft_recovery(draft);
+        assert_eq!(
+            app.dialog.as_ref().unwrap().dialog_type,
+            DialogType::DraftRecovery
+        );
+
+        app.finish_draft_recovery(RESTORE);
+        assert!(app.dialog.is_none());
+        assert_eq!(app.input, "unsent");
+        assert_eq!(app.cursor_position, 6);
+    }
+}
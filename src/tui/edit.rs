@@ -0,0 +1,141 @@
+//! Editing and resending an earlier prompt.
+//!
+//! Selecting a user message in the timeline loads its text into the input.
+//! Submitting it deletes that message and everything after it from the session
+//! and sends the edited prompt in its place.
+
+use anyhow::{Context, Result};
+
+use super::state::App;
+use super::types::DisplayMessage;
+use crate::session::{Message, Part};
+
+/// A stored user message being edited for resending
+#[derive(Debug, Clone)]
+pub struct MessageEdit {
+    /// Stored ID of the message being replaced
+    message_id: String,
+    /// Number of user messages after it in the transcript
+    ///
+    /// Counted from the end, since loading older history shifts indices.
+    later_users: usize,
+}
+
+impl App {
+    /// Load the user message at `index` in the transcript into the input
+    pub async fn begin_message_edit(&mut self, index: usize) {
+        if self.is_processing {
+            self.status = "Wait for the response to finish before editing".to_string();
+            return;
+        }
+        let Some(text) = self
+            .messages
+            .get(index)
+            .filter(|m| m.role == "user")
+            .map(|m| m.content.clone())
+        else {
+            return;
+        };
+        let later_users = self.messages[index + 1..]
+            .iter()
+            .filter(|m| m.role == "user")
+            .count();
+
+        match self.stored_user_message_id(later_users, &text).await {
+            Some(message_id) => {
+                self.cursor_position = text.len();
+                self.input = text;
+                self.editing = Some(MessageEdit {
+                    message_id,
+                    later_users,
+                });
+                self.status = "Editing message: submit to resend, Esc to cancel".to_string();
+            }
+            None => self.add_message(
+                "system",
+                "This message is not stored in the session and cannot be edited",
+            ),
+        }
+    }
+
+    /// Stop editing; the input is kept as a normal prompt
+    pub fn cancel_message_edit(&mut self) {
+        self.editing = None;
+        self.status = "Edit cancelled".to_string();
+    }
+
+    /// Delete the edited message and everything after it, before resending
+    pub async fn truncate_for_edit(&mut self, edit: MessageEdit) -> Result<()> {
+        let session_id = self
+            .session
+            .as_ref()
+            .map(|s| s.id.clone())
+            .context("No active session")?;
+        Message::truncate(&session_id, &edit.message_id).await?;
+
+        if let Some(index) = user_index_from_end(&self.messages, edit.later_users) {
+            self.messages.truncate(index);
+        }
+        self.message_scroll = 0;
+        Ok(())
+    }
+
+    /// ID of the stored user message matching a transcript entry
+    ///
+    /// Every user message in the transcript is stored, so the n-th last one
+    /// in the transcript is the n-th last one in storage. Its text is checked
+    /// to be sure.
+    async fn stored_user_message_id(&self, later_users: usize, text: &str) -> Option<String> {
+        let session_id = &self.session.as_ref()?.id;
+        let messages = Message::list(session_id).await.ok()?;
+        let message = messages
+            .iter()
+            .filter(|m| matches!(m, Message::User(_)))
+            .nth_back(later_users)?;
+
+        let stored: String = Part::list(message.id())
+            .await
+            .ok()?
+            .iter()
+            .filter_map(|part| match part {
+                Part::Text(part) => Some(part.text.as_str()),
+                _ => None,
+            })
+            .collect();
+        (stored == text).then(|| message.id().to_string())
+    }
+}
+
+/// Index of the user message with `later_users` user messages after it
+fn user_index_from_end(messages: &[DisplayMessage], later_users: usize) -> Option<usize> {
+    messages
+        .iter()
+        .enumerate()
+        .rev()
+        .filter(|(_, m)| m.role == "user")
+        .nth(later_users)
+        .map(|(index, _)| index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_user_index_counts_from_the_end() {
+        let mut app = App::default();
+        for (role, text) in [
+            ("user", "first"),
+            ("assistant", "a"),
+            ("user", "second"),
+            ("system", "note"),
+            ("assistant", "b"),
+        ] {
+            app.add_message(role, text);
+        }
+
+        assert_eq!(user_index_from_end(&app.messages, 0), Some(2));
+        assert_eq!(user_index_from_end(&app.messages, 1), Some(0));
+        assert_eq!(user_index_from_end(&app.messages, 2), None);
+    }
+}
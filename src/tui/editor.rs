@@ -0,0 +1,122 @@
+//! Composing the input in an external editor.
+//!
+//! The current input is written to a temp file and opened in `$VISUAL` or
+//! `$EDITOR`. The TUI leaves the alternate screen while the editor runs and
+//! redraws from scratch afterwards.
+
+use std::io::Write;
+use std::process::Command;
+
+use anyhow::{Context, Result};
+
+use super::state::App;
+use super::terminal;
+
+/// Editor used when neither `$VISUAL` nor `$EDITOR` is set
+const DEFAULT_EDITOR: &str = "vi";
+
+impl App {
+    /// Edit the input in the user's editor, loading the result back on save
+    pub fn open_external_editor(&mut self) {
+        let command = editor_command(
+            std::env::var("VISUAL").ok().as_deref(),
+            std::env::var("EDITOR").ok().as_deref(),
+        );
+
+        terminal::restore();
+        let result = edit_in(&command, &self.input);
+        if let Err(e) = terminal::resume() {
+            tracing::warn!("Failed to resume terminal: {}", e);
+        }
+        self.redraw_requested = true;
+
+        match result {
+            Ok(Some(text)) => {
+                self.cursor_position = text.len();
+                self.input = text;
+                self.input_history_position = None;
+                self.status = "Ready".to_string();
+            }
+            Ok(None) => {
+                self.status = "Editor exited with an error; input unchanged".to_string();
+            }
+            Err(e) => {
+                self.add_message("system", &format!("Failed to open editor: {:#}", e));
+            }
+        }
+    }
+}
+
+/// Editor program and arguments, e.g. `code --wait`
+fn editor_command(visual: Option<&str>, editor: Option<&str>) -> Vec<String> {
+    [visual, editor]
+        .into_iter()
+        .flatten()
+        .map(|value| {
+            value
+                .split_whitespace()
+                .map(str::to_string)
+                .collect::<Vec<_>>()
+        })
+        .find(|parts| !parts.is_empty())
+        .unwrap_or_else(|| vec![DEFAULT_EDITOR.to_string()])
+}
+
+/// Open `text` in the editor and return the saved text
+///
+/// Returns `None` when the editor exits unsuccessfully.
+fn edit_in(command: &[String], text: &str) -> Result<Option<String>> {
+    let mut file = tempfile::Builder::new()
+        .prefix("opencode-prompt-")
+        .suffix(".md")
+        .tempfile()
+        .context("Failed to create temp file")?;
+    file.write_all(text.as_bytes())?;
+    file.flush()?;
+
+    let (program, args) = command.split_first().context("No editor configured")?;
+    let status = Command::new(program)
+        .args(args)
+        .arg(file.path())
+        .status()
+        .with_context(|| format!("Failed to run '{}'", program))?;
+    if !status.success() {
+        return Ok(None);
+    }
+
+    let edited = std::fs::read_to_string(file.path()).context("Failed to read edited file")?;
+    Ok(Some(strip_final_newline(edited)))
+}
+
+/// Drop the newline most editors add at the end of the file
+fn strip_final_newline(mut text: String) -> String {
+    if text.ends_with('\n') {
+        text.pop();
+        if text.ends_with('\r') {
+            text.pop();
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_editor_command_prefers_visual() {
+        assert_eq!(
+            editor_command(Some("code --wait"), Some("vim")),
+            vec!["code", "--wait"]
+        );
+        assert_eq!(editor_command(Some(" "), Some("nano")), vec!["nano"]);
+        assert_eq!(editor_command(None, None), vec![DEFAULT_EDITOR]);
+    }
+
+    #[test]
+    fn test_only_final_newline_is_stripped() {
+        assert_eq!(strip_final_newline("a\n\nb\n".to_string()), "a\n\nb");
+        assert_eq!(strip_final_newline("a\r\n".to_string()), "a");
+        assert_eq!(strip_final_newline("a".to_string()), "a");
+    }
+}
@@ -0,0 +1,125 @@
+//! Focusing a message of the transcript.
+//!
+//! `message_prev` and `message_next` move a focus through the shown messages,
+//! scrolling the focused one into view and marking it. The focused message
+//! can then be copied with `message_copy`, or, if it is one of the user's,
+//! edited and resent with `message_edit`.
+
+use super::clipboard::copy_to_clipboard;
+use super::state::App;
+
+impl App {
+    /// Transcript indices of the messages the display toggles leave shown
+    fn shown_message_indices(&self) -> Vec<usize> {
+        self.messages
+            .iter()
+            .enumerate()
+            .filter(|(_, msg)| self.displayed_lines(msg).is_some())
+            .map(|(idx, _)| idx)
+            .collect()
+    }
+
+    /// Move the focus to an older (`-1`) or newer (`1`) message
+    ///
+    /// With nothing focused, the newest message is focused. Moving past the
+    /// newest message clears the focus. The focused message is scrolled to
+    /// the bottom of the transcript.
+    pub fn focus_message(&mut self, step: isize) {
+        let shown = self.shown_message_indices();
+        let current = self
+            .focused_message
+            .and_then(|focused| shown.iter().position(|&idx| idx == focused));
+        let next = match current {
+            None => shown.len().checked_sub(1),
+            Some(pos) => match pos.checked_add_signed(step) {
+                Some(next) if next < shown.len() => Some(next),
+                // Past the newest message
+                _ if step > 0 => None,
+                // Already at the oldest message
+                _ => Some(pos),
+            },
+        };
+        self.focused_message = next.map(|pos| shown[pos]);
+        self.message_scroll = match self.focused_message {
+            Some(idx) => self.messages.len() - 1 - idx,
+            None => 0,
+        };
+    }
+
+    /// Focus an older message, loading a page of history first when the
+    /// oldest loaded one is focused
+    pub async fn focus_older_message(&mut self) {
+        let at_oldest = self.focused_message.is_some_and(|focused| {
+            self.shown_message_indices()
+                .first()
+                .is_some_and(|&idx| idx == focused)
+        });
+        if at_oldest {
+            if let Some(before_id) = self.history_before.clone() {
+                self.load_history_page(Some(&before_id)).await;
+            }
+        }
+        self.focus_message(-1);
+    }
+
+    /// Copy the focused message's text to the clipboard
+    pub fn copy_focused_message(&mut self) {
+        let Some(text) = self
+            .focused_message
+            .and_then(|idx| self.messages.get(idx))
+            .map(|msg| msg.content.clone())
+        else {
+            self.status = "No message focused".to_string();
+            return;
+        };
+        match copy_to_clipboard(&self.redactor.redact(&text)) {
+            Ok(_) => self.status = "Message copied to clipboard".to_string(),
+            Err(e) => self.add_message("system", &format!("Failed to copy to clipboard: {}", e)),
+        }
+    }
+
+    /// Load the focused message into the input to edit and resend it
+    pub async fn edit_focused_message(&mut self) {
+        let Some(idx) = self.focused_message else {
+            self.status = "No message focused".to_string();
+            return;
+        };
+        if self.messages.get(idx).is_some_and(|msg| msg.role == "user") {
+            self.begin_message_edit(idx).await;
+        } else {
+            self.status = "Only your own messages can be edited".to_string();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_focus_message() {
+        let mut app = App::default();
+        app.add_message("user", "first");
+        app.add_message("assistant", "answer");
+        app.add_message("system", "note");
+        app.add_message("user", "second");
+        app.show_system_messages = false;
+
+        app.focus_message(-1);
+        assert_eq!(app.focused_message, Some(3));
+        assert_eq!(app.message_scroll, 0);
+        // The hidden system message is skipped
+        app.focus_message(-1);
+        assert_eq!(app.focused_message, Some(1));
+        assert_eq!(app.message_scroll, 2);
+        app.focus_message(-1);
+        app.focus_message(-1);
+        assert_eq!(app.focused_message, Some(0));
+
+        app.focus_message(1);
+        app.focus_message(1);
+        app.focus_message(1);
+        assert_eq!(app.focused_message, None);
+        assert_eq!(app.message_scroll, 0);
+    }
+}
@@ -0,0 +1,108 @@
+//! Frame pacing of the event loop.
+//!
+//! The loop redraws once per tick or input event. Stream deltas that arrive
+//! in between are merged before they are applied, so a fast model costs one
+//! transcript update per frame rather than one per token.
+
+use std::time::Duration;
+
+use super::types::AppEvent;
+use crate::config::TuiConfig;
+
+/// Default milliseconds between ticks
+const DEFAULT_TICK_MS: u64 = 100;
+
+/// Shortest tick allowed, about 60 frames per second
+const MIN_TICK_MS: u64 = 16;
+
+/// Longest tick allowed, beyond which the spinner stalls visibly
+const MAX_TICK_MS: u64 = 1000;
+
+/// Time between ticks, from `tui.tick_interval`
+pub fn tick_rate(config: Option<&TuiConfig>) -> Duration {
+    let ms = config
+        .and_then(|tui| tui.tick_interval)
+        .unwrap_or(DEFAULT_TICK_MS)
+        .clamp(MIN_TICK_MS, MAX_TICK_MS);
+    Duration::from_millis(ms)
+}
+
+/// Merge runs of reply and reasoning deltas, keeping the order of events
+pub fn coalesce(events: Vec<AppEvent>) -> Vec<AppEvent> {
+    let mut merged: Vec<AppEvent> = Vec::with_capacity(events.len());
+    for event in events {
+        match (merged.last_mut(), event) {
+            (Some(AppEvent::StreamDelta(text)), AppEvent::StreamDelta(more))
+            | (Some(AppEvent::ReasoningDelta(text)), AppEvent::ReasoningDelta(more)) => {
+                text.push_str(&more)
+            }
+            (_, event) => merged.push(event),
+        }
+    }
+    merged
+}
+
+/// Counters logged when the TUI exits, to compare redraw costs
+#[derive(Debug, Default)]
+pub struct FrameStats {
+    /// Frames drawn
+    pub frames: u64,
+    /// Events received from the turn
+    pub events: u64,
+    /// Events applied after coalescing
+    pub applied: u64,
+}
+
+impl FrameStats {
+    pub fn log(&self, tick_rate: Duration) {
+        tracing::debug!(
+            "Drew {} frames at a {:?} tick; applied {} of {} events after coalescing",
+            self.frames,
+            tick_rate,
+            self.applied,
+            self.events
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_coalesce() {
+        let events = vec![
+            AppEvent::ReasoningDelta("Let me ".to_string()),
+            AppEvent::ReasoningDelta("think".to_string()),
+            AppEvent::StreamDelta("Hel".to_string()),
+            AppEvent::StreamDelta("lo".to_string()),
+            AppEvent::ToolCall("bash".to_string(), "call_1".to_string()),
+            AppEvent::StreamDelta("!".to_string()),
+            AppEvent::StreamDone,
+        ];
+        let merged = coalesce(events);
+        assert_eq!(merged.len(), 5);
+        assert!(matches!(&merged[0], AppEvent::ReasoningDelta(t) if t == "Let me think"));
+        assert!(matches!(&merged[1], AppEvent::StreamDelta(t) if t == "Hello"));
+        assert!(
+            matches!(&merged[2], AppEvent::ToolCall(name, id) if name == "bash" && id == "call_1")
+        );
+        assert!(matches!(&merged[3], AppEvent::StreamDelta(t) if t == "!"));
+        assert!(matches!(merged[4], AppEvent::StreamDone));
+    }
+
+    #[test]
+    fn test_tick_rate() {
+        assert_eq!(tick_rate(None), Duration::from_millis(100));
+        let config = |ms| TuiConfig {
+            tick_interval: Some(ms),
+            ..Default::default()
+        };
+        assert_eq!(tick_rate(Some(&config(33))), Duration::from_millis(33));
+        assert_eq!(tick_rate(Some(&config(0))), Duration::from_millis(16));
+        assert_eq!(
+            tick_rate(Some(&config(60_000))),
+            Duration::from_millis(1000)
+        );
+    }
+}
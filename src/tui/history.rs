@@ -0,0 +1,345 @@
+//! Windowed loading of stored session messages into the transcript.
+//!
+//! Switching sessions loads only the newest page of messages; older pages are
+//! fetched when the transcript is scrolled up to the oldest loaded message.
+
+use super::state::App;
+use super::types::{DisplayMessage, MessagePart, ReplyMetadata};
+use crate::session::{Message, MessagePage, Part, ToolState};
+
+/// Number of stored messages loaded per page
+const HISTORY_PAGE_SIZE: usize = 50;
+
+/// Number of messages scrolled per key press
+const SCROLL_STEP: usize = 5;
+
+impl App {
+    /// Clear the transcript and any paging state
+    pub fn clear_transcript(&mut self) {
+        self.messages.clear();
+        self.message_scroll = 0;
+        self.history_before = None;
+        self.marked_messages.clear();
+        self.focused_message = None;
+    }
+
+    /// Load the newest page of the current session's stored messages
+    pub async fn load_recent_messages(&mut self) {
+        self.clear_transcript();
+        self.load_history_page(None).await;
+    }
+
+    /// Scroll the transcript towards older messages, fetching a page if needed
+    pub async fn scroll_messages_up(&mut self) {
+        let max_scroll = self.messages.len().saturating_sub(1);
+        self.message_scroll = (self.message_scroll + SCROLL_STEP).min(max_scroll);
+
+        if self.message_scroll + SCROLL_STEP > max_scroll {
+            if let Some(before_id) = self.history_before.clone() {
+                self.load_history_page(Some(&before_id)).await;
+            }
+        }
+    }
+
+    /// Scroll the transcript towards newer messages
+    pub fn scroll_messages_down(&mut self) {
+        self.message_scroll = self.message_scroll.saturating_sub(SCROLL_STEP);
+    }
+
+    /// Prepend one page of stored messages older than `before_id`
+    pub(super) async fn load_history_page(&mut self, before_id: Option<&str>) {
+        let Some(session_id) = self.session.as_ref().map(|s| s.id.clone()) else {
+            return;
+        };
+
+        let MessagePage {
+            messages: page,
+            skipped: skipped_messages,
+        } = match Message::list_paginated(&session_id, before_id, HISTORY_PAGE_SIZE).await {
+            Ok(page) => page,
+            Err(e) => {
+                tracing::warn!("Failed to load session messages: {}", e);
+                self.history_before = None;
+                return;
+            }
+        };
+
+        // A short page means the start of the session has been reached;
+        // skipped messages still count towards the page
+        self.history_before = page
+            .first()
+            .filter(|_| page.len() + skipped_messages == HISTORY_PAGE_SIZE)
+            .map(|m| m.id().to_string());
+
+        let mut older: Vec<DisplayMessage> = Vec::with_capacity(page.len());
+        let mut last_parent = None;
+        let mut skipped_parts = 0;
+        for message in &page {
+            let (parts, skipped) = Part::list_counting_skipped(message.id())
+                .await
+                .unwrap_or_default();
+            skipped_parts += skipped;
+            let Some(display) = to_display_message(message, &parts) else {
+                continue;
+            };
+            // Replies to the same prompt are variants of one reply
+            let parent = match message {
+                Message::Assistant(reply) => Some(reply.parent_id.as_str()),
+                Message::User(_) => None,
+            };
+            match older.last_mut() {
+                Some(previous) if parent.is_some() && parent == last_parent => {
+                    previous.push_variant(display.to_variant())
+                }
+                _ => older.push(display),
+            }
+            last_parent = parent;
+        }
+        // Older messages go in front, shifting the marked and focused indices
+        for index in self
+            .marked_messages
+            .iter_mut()
+            .chain(&mut self.focused_message)
+        {
+            *index += older.len();
+        }
+        self.messages.splice(0..0, older);
+
+        let skipped = skipped_messages + skipped_parts;
+        if skipped > 0 {
+            self.status = format!("Skipped {} corrupt record(s) in this session", skipped);
+        }
+    }
+}
+
+/// Convert a stored message and its parts into a transcript entry
+///
+/// Returns `None` for messages with nothing to show.
+fn to_display_message(message: &Message, parts: &[Part]) -> Option<DisplayMessage> {
+    let (role, metadata) = match message {
+        Message::User(_) => ("user", None),
+        Message::Assistant(reply) => (
+            "assistant",
+            Some(ReplyMetadata {
+                model: format!("{}/{}", reply.provider_id, reply.model_id),
+                tokens: reply.tokens.input + reply.tokens.output,
+                cost: reply.cost,
+            }),
+        ),
+    };
+    // Reasoning is counted with the tokenizer of the model that wrote it
+    let tokenizer = match message {
+        Message::Assistant(reply) => Some(crate::provider::tokenizer::for_model(
+            &reply.provider_id,
+            &reply.model_id,
+        )),
+        Message::User(_) => None,
+    };
+
+    let mut content = String::new();
+    let mut display_parts = Vec::new();
+    for part in parts {
+        match part {
+            Part::Text(text) => {
+                content.push_str(&text.text);
+                display_parts.push(MessagePart::Text {
+                    text: text.text.clone(),
+                });
+            }
+            Part::Reasoning(reasoning) => display_parts.push(MessagePart::Reasoning {
+                text: reasoning.text.clone(),
+                tokens: tokenizer.map(|tokenizer| tokenizer.count(&reasoning.text)),
+            }),
+            Part::Tool(tool) => {
+                content.push_str(&format!("[Calling tool: {}]\n", tool.tool));
+                let (input, result) = match &tool.state {
+                    ToolState::Pending(state) => (&state.input, None),
+                    ToolState::Running(state) => (&state.input, None),
+                    // Shaped like the results sent to the model, for the same titles
+                    ToolState::Completed(state) => (
+                        &state.input,
+                        Some((
+                            serde_json::json!({"title": state.title, "output": state.output}),
+                            false,
+                        )),
+                    ),
+                    ToolState::Error(state) => (
+                        &state.input,
+                        Some((
+                            serde_json::json!({
+                                "title": "Tool Execution Error",
+                                "error": state.error,
+                            }),
+                            true,
+                        )),
+                    ),
+                };
+                display_parts.push(MessagePart::ToolCall {
+                    id: tool.call_id.clone(),
+                    name: tool.tool.clone(),
+                    args: input.to_string(),
+                    expanded: false,
+                });
+                if let Some((output, is_error)) = result {
+                    display_parts.push(MessagePart::ToolResult {
+                        id: tool.call_id.clone(),
+                        output: output.to_string(),
+                        is_error,
+                    });
+                }
+                if let ToolState::Completed(state) = &tool.state {
+                    let images = state.attachments.iter().flatten();
+                    display_parts.extend(
+                        images
+                            .filter(|file| file.mime.starts_with("image/"))
+                            .map(|file| MessagePart::Image {
+                                mime: file.mime.clone(),
+                            }),
+                    );
+                }
+            }
+            Part::File(file) if file.mime.starts_with("image/") => {
+                display_parts.push(MessagePart::Image {
+                    mime: file.mime.clone(),
+                })
+            }
+            _ => {}
+        }
+    }
+
+    if display_parts.is_empty() {
+        return None;
+    }
+
+    Some(DisplayMessage {
+        role: role.to_string(),
+        content,
+        parts: display_parts,
+        metadata,
+        variants: Vec::new(),
+        variant: 0,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::Capability;
+
+    fn user_message() -> Message {
+        serde_json::from_value(serde_json::json!({
+            "role": "user",
+            "id": "msg_1",
+            "session_id": "ses_1",
+            "time": {"created": 0},
+            "agent": "default",
+            "model": {"provider_id": "anthropic", "model_id": "claude"}
+        }))
+        .unwrap()
+    }
+
+    fn part(value: serde_json::Value) -> Part {
+        let mut base = serde_json::json!({
+            "id": "prt_1",
+            "session_id": "ses_1",
+            "message_id": "msg_1"
+        });
+        base.as_object_mut()
+            .unwrap()
+            .extend(value.as_object().unwrap().clone());
+        serde_json::from_value(base).unwrap()
+    }
+
+    #[test]
+    fn test_text_and_tool_parts_are_shown() {
+        let parts = vec![
+            part(serde_json::json!({"type": "text", "text": "hello\n"})),
+            part(serde_json::json!({
+                "type": "tool",
+                "tool": "bash",
+                "call_id": "call_1",
+                "state": {"status": "pending", "input": {}, "raw": ""}
+            })),
+        ];
+
+        let display = to_display_message(&user_message(), &parts).unwrap();
+        assert_eq!(display.role, "user");
+        assert_eq!(display.content, "hello\n[Calling tool: bash]\n");
+        assert_eq!(display.parts.len(), 2);
+    }
+
+    #[test]
+    fn test_completed_tool_part_keeps_its_result() {
+        let parts = vec![part(serde_json::json!({
+            "type": "tool",
+            "tool": "bash",
+            "call_id": "call_1",
+            "state": {
+                "status": "completed",
+                "input": {"command": "ls"},
+                "output": "a.rs",
+                "title": "ls",
+                "metadata": {},
+                "time": {"start": 0, "end": 1}
+            }
+        }))];
+
+        let display = to_display_message(&user_message(), &parts).unwrap();
+        let app = App::default();
+        let lines: Vec<String> = app
+            .displayed_lines(&display)
+            .unwrap()
+            .into_iter()
+            .map(|line| line.text)
+            .collect();
+        assert_eq!(lines, vec!["▸ bash ✓ ls"]);
+        assert!(matches!(
+            &display.parts[0],
+            MessagePart::ToolCall { args, .. } if args == r#"{"command":"ls"}"#
+        ));
+    }
+
+    #[test]
+    fn test_image_requires_image_input() {
+        let parts = vec![part(serde_json::json!({
+            "type": "file",
+            "mime": "image/png",
+            "url": "data:image/png;base64,abc"
+        }))];
+
+        let mut app = App::default();
+        assert_eq!(app.required_capabilities(), vec![Capability::ToolCall]);
+        app.messages
+            .push(to_display_message(&user_message(), &parts).unwrap());
+        let lines: Vec<String> = app
+            .displayed_lines(&app.messages[0])
+            .unwrap()
+            .into_iter()
+            .map(|line| line.text)
+            .collect();
+        assert_eq!(lines, vec!["[image: image/png]"]);
+        assert_eq!(
+            app.required_capabilities(),
+            vec![Capability::ToolCall, Capability::ImageInput]
+        );
+    }
+
+    #[test]
+    fn test_message_without_visible_parts_is_skipped() {
+        assert!(to_display_message(&user_message(), &[]).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_scroll_is_clamped_to_loaded_messages() {
+        let mut app = App::default();
+        for i in 0..3 {
+            app.add_message("user", &i.to_string());
+        }
+
+        app.scroll_messages_up().await;
+        assert_eq!(app.message_scroll, 2);
+
+        app.scroll_messages_down();
+        assert_eq!(app.message_scroll, 0);
+    }
+}
@@ -0,0 +1,593 @@
+//! Configurable keybindings.
+//!
+//! Maps key chords such as `ctrl+enter` to bindable actions. Defaults can be
+//! overridden per action through the `keybinds` config map; actions that are
+//! not configured keep their default chords.
+//!
+//! Configs written for the TypeScript opencode use some other action names
+//! and `<leader>` sequences. Names with an equivalent here are mapped; the
+//! others, and leader chords, are skipped without a warning.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use anyhow::{bail, Result};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/// Action that can be bound to key chords
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyAction {
+    AppExit,
+    InputSubmit,
+    InputNewline,
+    InputClear,
+    SessionInterrupt,
+    ModelList,
+    ProviderList,
+    InputModeToggle,
+    CopyCode,
+    EditorOpen,
+    MessagesPageUp,
+    MessagesPageDown,
+    HistorySearch,
+    ToggleTools,
+    ToggleMetadata,
+    ToggleSystem,
+    ToolSelectPrev,
+    ToolSelectNext,
+    ToolExpand,
+    VariantPrev,
+    VariantNext,
+    MessagePrev,
+    MessageNext,
+    MessageCopy,
+    MessageEdit,
+}
+
+impl KeyAction {
+    /// All bindable actions, in precedence order for conflicts
+    pub const ALL: [KeyAction; 25] = [
+        KeyAction::AppExit,
+        KeyAction::InputSubmit,
+        KeyAction::InputNewline,
+        KeyAction::InputClear,
+        KeyAction::SessionInterrupt,
+        KeyAction::ModelList,
+        KeyAction::ProviderList,
+        KeyAction::InputModeToggle,
+        KeyAction::CopyCode,
+        KeyAction::EditorOpen,
+        KeyAction::MessagesPageUp,
+        KeyAction::MessagesPageDown,
+        KeyAction::HistorySearch,
+        KeyAction::ToggleTools,
+        KeyAction::ToggleMetadata,
+        KeyAction::ToggleSystem,
+        KeyAction::ToolSelectPrev,
+        KeyAction::ToolSelectNext,
+        KeyAction::ToolExpand,
+        KeyAction::VariantPrev,
+        KeyAction::VariantNext,
+        KeyAction::MessagePrev,
+        KeyAction::MessageNext,
+        KeyAction::MessageCopy,
+        KeyAction::MessageEdit,
+    ];
+
+    /// Config name of the action
+    pub fn name(self) -> &'static str {
+        match self {
+            KeyAction::AppExit => "app_exit",
+            KeyAction::InputSubmit => "input_submit",
+            KeyAction::InputNewline => "input_newline",
+            KeyAction::InputClear => "input_clear",
+            KeyAction::SessionInterrupt => "session_interrupt",
+            KeyAction::ModelList => "model_list",
+            KeyAction::ProviderList => "provider_list",
+            KeyAction::InputModeToggle => "input_mode_toggle",
+            KeyAction::CopyCode => "copy_code",
+            KeyAction::EditorOpen => "editor_open",
+            KeyAction::MessagesPageUp => "messages_page_up",
+            KeyAction::MessagesPageDown => "messages_page_down",
+            KeyAction::HistorySearch => "history_search",
+            KeyAction::ToggleTools => "toggle_tools",
+            KeyAction::ToggleMetadata => "toggle_metadata",
+            KeyAction::ToggleSystem => "toggle_system",
+            KeyAction::ToolSelectPrev => "tool_select_prev",
+            KeyAction::ToolSelectNext => "tool_select_next",
+            KeyAction::ToolExpand => "tool_expand",
+            KeyAction::VariantPrev => "variant_prev",
+            KeyAction::VariantNext => "variant_next",
+            KeyAction::MessagePrev => "message_prev",
+            KeyAction::MessageNext => "message_next",
+            KeyAction::MessageCopy => "message_copy",
+            KeyAction::MessageEdit => "message_edit",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|action| action.name() == name)
+    }
+
+    /// Default chords for the action
+    fn default_chords(self) -> &'static str {
+        match self {
+            KeyAction::AppExit => "ctrl+c,ctrl+d",
+            KeyAction::InputSubmit => "enter",
+            KeyAction::InputNewline => "shift+enter,ctrl+enter,alt+enter",
+            KeyAction::InputClear => "ctrl+u",
+            KeyAction::SessionInterrupt => "esc",
+            KeyAction::ModelList => "ctrl+m",
+            KeyAction::ProviderList => "ctrl+p",
+            KeyAction::InputModeToggle => "ctrl+t",
+            KeyAction::CopyCode => "ctrl+y",
+            KeyAction::EditorOpen => "ctrl+o",
+            KeyAction::MessagesPageUp => "shift+pageup",
+            KeyAction::MessagesPageDown => "shift+pagedown",
+            KeyAction::HistorySearch => "ctrl+r",
+            KeyAction::ToggleTools => "alt+t",
+            KeyAction::ToggleMetadata => "alt+m",
+            KeyAction::ToggleSystem => "alt+s",
+            KeyAction::ToolSelectPrev => "alt+up",
+            KeyAction::ToolSelectNext => "alt+down",
+            KeyAction::ToolExpand => "alt+e",
+            KeyAction::VariantPrev => "alt+left",
+            KeyAction::VariantNext => "alt+right",
+            KeyAction::MessagePrev => "ctrl+up",
+            KeyAction::MessageNext => "ctrl+down",
+            KeyAction::MessageCopy => "alt+y",
+            KeyAction::MessageEdit => "alt+r",
+        }
+    }
+}
+
+/// TypeScript opencode action names with an equivalent action here
+const LEGACY_ALIASES: &[(&str, KeyAction)] = &[
+    ("messages_previous", KeyAction::MessagePrev),
+    ("messages_next", KeyAction::MessageNext),
+    ("messages_copy", KeyAction::MessageCopy),
+    ("tool_details", KeyAction::ToggleTools),
+];
+
+/// TypeScript opencode action names without an equivalent here
+const LEGACY_ACTIONS: &[&str] = &[
+    "leader",
+    "app_help",
+    "theme_list",
+    "sidebar_toggle",
+    "scrollbar_toggle",
+    "username_toggle",
+    "status_view",
+    "session_export",
+    "session_new",
+    "session_list",
+    "session_timeline",
+    "session_fork",
+    "session_rename",
+    "session_share",
+    "session_unshare",
+    "session_compact",
+    "session_child_cycle",
+    "session_child_cycle_reverse",
+    "session_parent",
+    "messages_half_page_up",
+    "messages_half_page_down",
+    "messages_first",
+    "messages_last",
+    "messages_last_user",
+    "messages_undo",
+    "messages_redo",
+    "messages_toggle_conceal",
+    "model_cycle_recent",
+    "model_cycle_recent_reverse",
+    "model_cycle_favorite",
+    "model_cycle_favorite_reverse",
+    "command_list",
+    "agent_list",
+    "agent_cycle",
+    "agent_cycle_reverse",
+    "variant_cycle",
+    "input_paste",
+    "history_previous",
+    "history_next",
+    "terminal_suspend",
+    "terminal_title_toggle",
+    "tips_toggle",
+    "display_thinking",
+];
+
+/// Chords of a config value without TypeScript-style `<leader>` sequences
+///
+/// None when the value had only leader sequences, so the defaults stay.
+fn without_leader_chords(value: &str) -> Option<String> {
+    let chords: Vec<&str> = value
+        .split(',')
+        .filter(|chord| !chord.contains("<leader>"))
+        .collect();
+    let had_leader = chords.len() < value.split(',').count();
+    let chords = chords.join(",");
+    (!(had_leader && chords.trim().is_empty())).then_some(chords)
+}
+
+/// A key with modifiers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyChord {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl KeyChord {
+    /// Parse a chord such as `ctrl+enter`, `alt+m` or `f2`
+    pub fn parse(chord: &str) -> Result<Self> {
+        let chord = chord.trim().to_lowercase();
+        let mut parts: Vec<&str> = chord.split('+').map(str::trim).collect();
+        // "ctrl++" binds the plus key
+        if chord.ends_with("++") {
+            parts.truncate(parts.len() - 2);
+            parts.push("+");
+        }
+        let Some(key) = parts.pop().filter(|k| !k.is_empty()) else {
+            bail!("empty key chord");
+        };
+
+        let mut modifiers = KeyModifiers::NONE;
+        for modifier in parts {
+            modifiers |= match modifier {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" | "meta" | "option" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                other => bail!("unknown modifier '{}'", other),
+            };
+        }
+
+        let code = match key {
+            "enter" | "return" => KeyCode::Enter,
+            "esc" | "escape" => KeyCode::Esc,
+            "tab" => KeyCode::Tab,
+            "backspace" => KeyCode::Backspace,
+            "delete" | "del" => KeyCode::Delete,
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            "space" => KeyCode::Char(' '),
+            _ => {
+                let mut chars = key.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => KeyCode::Char(c),
+                    _ => match key.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+                        Some(n) if (1..=12).contains(&n) => KeyCode::F(n),
+                        _ => bail!("unknown key '{}'", key),
+                    },
+                }
+            }
+        };
+
+        Ok(Self { code, modifiers })
+    }
+
+    /// Parse a comma-separated list of chords
+    fn parse_list(chords: &str) -> Result<Vec<Self>> {
+        chords
+            .split(',')
+            .filter(|c| !c.trim().is_empty())
+            .map(Self::parse)
+            .collect()
+    }
+
+    /// Check whether a key event triggers this chord
+    fn matches(&self, key: &KeyEvent) -> bool {
+        let code = match key.code {
+            KeyCode::Char(c) => KeyCode::Char(c.to_ascii_lowercase()),
+            code => code,
+        };
+        code == self.code && key.modifiers == self.modifiers
+    }
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (modifier, name) in [
+            (KeyModifiers::CONTROL, "Ctrl"),
+            (KeyModifiers::ALT, "Alt"),
+            (KeyModifiers::SHIFT, "Shift"),
+        ] {
+            if self.modifiers.contains(modifier) {
+                write!(f, "{}+", name)?;
+            }
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "Space"),
+            KeyCode::Char(c) => write!(f, "{}", c.to_ascii_uppercase()),
+            KeyCode::F(n) => write!(f, "F{}", n),
+            code => write!(f, "{:?}", code),
+        }
+    }
+}
+
+/// Resolved keybindings
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    bindings: Vec<(KeyChord, KeyAction)>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self::from_config(&HashMap::new()).0
+    }
+}
+
+impl Keymap {
+    /// Build a keymap from the `keybinds` config map
+    ///
+    /// Returns the keymap and a list of warnings for unknown actions, invalid
+    /// chords and conflicts. Invalid entries fall back to the defaults, and a
+    /// chord bound to several actions keeps the first one. Use `none` to
+    /// unbind an action.
+    pub fn from_config(overrides: &HashMap<String, String>) -> (Self, Vec<String>) {
+        let mut warnings = Vec::new();
+
+        let mut names: Vec<&String> = overrides.keys().collect();
+        names.sort();
+        for name in names {
+            if KeyAction::from_name(name).is_some()
+                || LEGACY_ALIASES.iter().any(|(legacy, _)| legacy == name)
+            {
+                continue;
+            }
+            if LEGACY_ACTIONS.contains(&name.as_str()) {
+                tracing::debug!("Ignoring keybind for unsupported action '{}'", name);
+            } else {
+                warnings.push(format!("Unknown keybind action '{}'", name));
+            }
+        }
+
+        // Configured chords first, so they take precedence over defaults
+        let mut configured = Vec::new();
+        let mut defaults = Vec::new();
+        for action in KeyAction::ALL {
+            // The action's own name wins over a legacy alias
+            let value = overrides.get(action.name()).or_else(|| {
+                LEGACY_ALIASES
+                    .iter()
+                    .find(|(_, a)| *a == action)
+                    .and_then(|(legacy, _)| overrides.get(*legacy))
+            });
+            let chords = value.map(String::as_str).and_then(without_leader_chords);
+            match chords.as_deref().map(str::trim) {
+                Some("none") => {}
+                Some(chords) => match KeyChord::parse_list(chords) {
+                    Ok(chords) => configured.extend(chords.into_iter().map(|c| (c, action))),
+                    Err(e) => {
+                        warnings.push(format!(
+                            "Invalid keybind for {}: {} (using default)",
+                            action.name(),
+                            e
+                        ));
+                        defaults.extend(Self::defaults_for(action));
+                    }
+                },
+                None => defaults.extend(Self::defaults_for(action)),
+            }
+        }
+
+        let mut bindings: Vec<(KeyChord, KeyAction)> = Vec::new();
+        for (chord, action) in configured {
+            match bindings.iter().find(|(c, _)| *c == chord) {
+                Some((_, existing)) if *existing != action => warnings.push(format!(
+                    "Keybind conflict: {} and {} share a chord; keeping {}",
+                    existing.name(),
+                    action.name(),
+                    existing.name()
+                )),
+                Some(_) => {}
+                None => bindings.push((chord, action)),
+            }
+        }
+        // Defaults silently yield to configured chords
+        for (chord, action) in defaults {
+            if !bindings.iter().any(|(c, _)| *c == chord) {
+                bindings.push((chord, action));
+            }
+        }
+
+        (Self { bindings }, warnings)
+    }
+
+    fn defaults_for(action: KeyAction) -> Vec<(KeyChord, KeyAction)> {
+        KeyChord::parse_list(action.default_chords())
+            .expect("default keybinds are valid")
+            .into_iter()
+            .map(|chord| (chord, action))
+            .collect()
+    }
+
+    /// First chord bound to an action
+    pub fn chord_for(&self, action: KeyAction) -> Option<KeyChord> {
+        self.bindings
+            .iter()
+            .find(|(_, a)| *a == action)
+            .map(|(chord, _)| *chord)
+    }
+
+    /// Look up the action bound to a key event
+    pub fn lookup(&self, key: &KeyEvent) -> Option<KeyAction> {
+        self.bindings
+            .iter()
+            .find(|(chord, _)| chord.matches(key))
+            .map(|(_, action)| *action)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    fn config(entries: &[(&str, &str)]) -> HashMap<String, String> {
+        entries
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_chords() {
+        let chord = KeyChord::parse("Ctrl+Enter").unwrap();
+        assert_eq!(chord.code, KeyCode::Enter);
+        assert_eq!(chord.modifiers, KeyModifiers::CONTROL);
+
+        let chord = KeyChord::parse("ctrl+shift+k").unwrap();
+        assert_eq!(chord.code, KeyCode::Char('k'));
+        assert_eq!(chord.modifiers, KeyModifiers::CONTROL | KeyModifiers::SHIFT);
+
+        assert_eq!(KeyChord::parse("f2").unwrap().code, KeyCode::F(2));
+        assert_eq!(KeyChord::parse("ctrl++").unwrap().code, KeyCode::Char('+'));
+        assert!(KeyChord::parse("hyper+x").is_err());
+        assert!(KeyChord::parse("ctrl+").is_err());
+        assert!(KeyChord::parse("f13").is_err());
+    }
+
+    #[test]
+    fn test_display_chords() {
+        assert_eq!(
+            KeyChord::parse("ctrl+enter").unwrap().to_string(),
+            "Ctrl+Enter"
+        );
+        assert_eq!(
+            KeyChord::parse("alt+shift+m").unwrap().to_string(),
+            "Alt+Shift+M"
+        );
+        assert_eq!(KeyChord::parse("esc").unwrap().to_string(), "Esc");
+        assert_eq!(KeyChord::parse("f5").unwrap().to_string(), "F5");
+    }
+
+    #[test]
+    fn test_defaults() {
+        let keymap = Keymap::default();
+        assert_eq!(
+            keymap.lookup(&key(KeyCode::Enter, KeyModifiers::NONE)),
+            Some(KeyAction::InputSubmit)
+        );
+        assert_eq!(
+            keymap.lookup(&key(KeyCode::Enter, KeyModifiers::SHIFT)),
+            Some(KeyAction::InputNewline)
+        );
+        assert_eq!(
+            keymap.lookup(&key(KeyCode::Char('m'), KeyModifiers::CONTROL)),
+            Some(KeyAction::ModelList)
+        );
+        assert_eq!(
+            keymap.lookup(&key(KeyCode::Char('r'), KeyModifiers::CONTROL)),
+            Some(KeyAction::HistorySearch)
+        );
+        assert_eq!(
+            keymap.lookup(&key(KeyCode::Char('x'), KeyModifiers::NONE)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_swap_submit_and_newline() {
+        let (keymap, warnings) = Keymap::from_config(&config(&[
+            ("input_submit", "ctrl+enter"),
+            ("input_newline", "enter"),
+        ]));
+
+        assert!(warnings.is_empty());
+        assert_eq!(
+            keymap.lookup(&key(KeyCode::Enter, KeyModifiers::CONTROL)),
+            Some(KeyAction::InputSubmit)
+        );
+        assert_eq!(
+            keymap.lookup(&key(KeyCode::Enter, KeyModifiers::NONE)),
+            Some(KeyAction::InputNewline)
+        );
+    }
+
+    #[test]
+    fn test_configured_chord_overrides_default() {
+        // ctrl+enter is a default newline chord, but the configured submit wins
+        let (keymap, warnings) = Keymap::from_config(&config(&[("input_submit", "ctrl+enter")]));
+
+        assert!(warnings.is_empty());
+        assert_eq!(
+            keymap.lookup(&key(KeyCode::Enter, KeyModifiers::CONTROL)),
+            Some(KeyAction::InputSubmit)
+        );
+        assert_eq!(
+            keymap.lookup(&key(KeyCode::Enter, KeyModifiers::SHIFT)),
+            Some(KeyAction::InputNewline)
+        );
+        assert_eq!(
+            keymap.lookup(&key(KeyCode::Enter, KeyModifiers::NONE)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_warnings() {
+        let (keymap, warnings) = Keymap::from_config(&config(&[
+            ("model_list", "ctrl+o"),
+            ("provider_list", "ctrl+o"),
+            ("input_clear", "hyper+u"),
+            ("frobnicate", "ctrl+t"),
+        ]));
+
+        assert_eq!(warnings.len(), 3);
+        assert!(warnings[0].contains("frobnicate"));
+        assert!(warnings[1].contains("input_clear"));
+        assert!(warnings[2].contains("conflict"));
+        assert_eq!(
+            keymap.lookup(&key(KeyCode::Char('o'), KeyModifiers::CONTROL)),
+            Some(KeyAction::ModelList)
+        );
+        // Invalid chord falls back to the default
+        assert_eq!(
+            keymap.lookup(&key(KeyCode::Char('u'), KeyModifiers::CONTROL)),
+            Some(KeyAction::InputClear)
+        );
+    }
+
+    #[test]
+    fn test_legacy_config() {
+        let (keymap, warnings) = Keymap::from_config(&config(&[
+            ("leader", "ctrl+x"),
+            ("session_new", "<leader>n"),
+            ("messages_copy", "<leader>y,alt+c"),
+            ("app_exit", "ctrl+c,<leader>q"),
+            ("editor_open", "<leader>e"),
+        ]));
+
+        assert!(warnings.is_empty());
+        assert_eq!(
+            keymap.lookup(&key(KeyCode::Char('c'), KeyModifiers::ALT)),
+            Some(KeyAction::MessageCopy)
+        );
+        assert_eq!(
+            keymap.lookup(&key(KeyCode::Char('d'), KeyModifiers::CONTROL)),
+            None
+        );
+        // Only leader chords: the default stays
+        assert_eq!(
+            keymap.lookup(&key(KeyCode::Char('o'), KeyModifiers::CONTROL)),
+            Some(KeyAction::EditorOpen)
+        );
+    }
+
+    #[test]
+    fn test_unbind() {
+        let (keymap, _) = Keymap::from_config(&config(&[("app_exit", "none")]));
+        assert_eq!(
+            keymap.lookup(&key(KeyCode::Char('c'), KeyModifiers::CONTROL)),
+            None
+        );
+    }
+}
@@ -8,10 +8,12 @@ use std::sync::Arc;
 use tokio::sync::mpsc;
 
 use super::types::AppEvent;
+use crate::permission::PermissionChecker;
 use crate::provider::{
-    self, ChatContent, ChatMessage, ContentPart, Model, OpenAIRequest, StreamEvent,
-    StreamingClient, ToolDefinition,
+    self, AnthropicRequest, Capability, ChatContent, ChatMessage, ContentPart, FinishReason, Model,
+    ModelOverloaded, OpenAIRequest, RequestRefused, StreamEvent, StreamingClient, ToolDefinition,
 };
+use crate::session::{self, Message, MessageError, TextPartWriter, TokenUsage};
 use crate::tool::{self, DoomLoopDetector, PendingToolCall, ToolCallTracker, ToolContext};
 
 const MAX_AGENTIC_STEPS: i32 = 10;
@@ -22,20 +24,63 @@ pub async fn send_permission_response(id: String, allow: bool, scope: tool::Perm
     crate::permission_state::send_permission_response(id, allow, scope).await;
 }
 
+/// Send the decision on a batch of tool calls to the waiting executor
+pub async fn send_batch_permission_response(id: String, decision: tool::BatchDecision) {
+    crate::permission_state::send_batch_response(id, decision).await;
+}
+
 /// Send question response to waiting tool
 pub async fn send_question_response(id: String, answers: Vec<Vec<String>>) {
     crate::question_state::send_question_response(id, answers).await;
 }
 
-/// Context for streaming operations
-struct StreamContext {
+/// Provider, credentials and request limits for a model
+struct ModelTarget {
     provider_id: String,
     api_key: String,
     model: Model,
+    max_tokens: u64,
+    /// Sampling temperature, sent only to models that support it
+    temperature: Option<f64>,
+    /// Request options of the provider and model config
+    options: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Context for streaming operations
+struct StreamContext {
+    target: ModelTarget,
+    /// Max output tokens override, reapplied when falling back to another model
+    max_output: Option<u64>,
     tool_defs: Vec<ToolDefinition>,
     tool_ctx: Arc<ToolContext>,
     event_tx: mpsc::Sender<AppEvent>,
     system_prompt: String,
+    /// JSON schema the final reply must conform to
+    json_schema: Option<serde_json::Value>,
+}
+
+/// Per-request options taken from the session settings
+#[derive(Debug, Clone, Default)]
+pub struct StreamOptions {
+    /// Max output tokens override
+    pub max_output: Option<u64>,
+    /// Assistant prefill for the first step (Anthropic only)
+    pub prefill: Option<String>,
+    /// JSON schema the final reply must conform to
+    pub json_schema: Option<serde_json::Value>,
+    /// Signal that aborts the turn and cancels its pending tool calls
+    pub abort: Option<tokio::sync::watch::Receiver<bool>>,
+    /// Text appended to the system prompt (set with `/system`)
+    pub system: Option<String>,
+    /// Files quoted in the system prompt as they are now (set with `/pin`)
+    pub pinned: Vec<String>,
+    /// Session permission rules, layered over the config
+    pub permission: Option<serde_json::Value>,
+    /// Sampling temperature override (None = provider default)
+    pub temperature: Option<f64>,
+    /// Stored user message to answer again instead of saving the prompt,
+    /// for a regenerated reply
+    pub reply_to: Option<String>,
 }
 
 /// Result of processing a stream
@@ -43,30 +88,109 @@ struct StreamResult {
     response_text: String,
     pending_calls: Vec<PendingToolCall>,
     finish_reason: String,
+    input_tokens: u64,
+    output_tokens: u64,
+    /// Reason the provider refused the request or filtered the response
+    refusal: Option<String>,
 }
 
 /// Stream a response from the LLM with agentic loop
 pub async fn stream_response_agentic(
     provider_id: String,
     model_id: String,
+    session_id: String,
     initial_prompt: String,
+    mut options: StreamOptions,
     event_tx: mpsc::Sender<AppEvent>,
 ) -> Result<()> {
-    let ctx = StreamContext::new(&provider_id, &model_id, event_tx).await?;
+    let mut ctx = StreamContext::new(
+        &provider_id,
+        &model_id,
+        &session_id,
+        &initial_prompt,
+        &mut options,
+        event_tx,
+    )
+    .await?;
+    ctx.json_schema = options.json_schema;
+    ctx.system_prompt = crate::session::system::with_additions(
+        std::mem::take(&mut ctx.system_prompt),
+        options.system.as_deref(),
+    );
+    let pinned = crate::session::system::pinned_files(&get_current_dir(), &options.pinned);
+    ctx.system_prompt = crate::session::system::with_additions(
+        std::mem::take(&mut ctx.system_prompt),
+        pinned.as_deref(),
+    );
     let mut messages = vec![ChatMessage {
         role: "user".to_string(),
         content: ChatContent::Text(initial_prompt),
     }];
-    let client = StreamingClient::new();
+    let mut client = create_client(&provider_id).await?;
+    let mut fallbacks =
+        provider::fallback_chain(&configured_fallbacks().await, &provider_id, &model_id)
+            .into_iter();
     let mut doom_detector = DoomLoopDetector::new();
+    // Only the first reply is seeded; later steps continue after tool results
+    let mut prefill = options.prefill;
+    let mut json_retries = 0;
 
     for step in 1..=MAX_AGENTIC_STEPS {
-        let rx = ctx.create_stream(&client, &messages).await?;
-        let result = process_stream(rx, &ctx.event_tx).await?;
+        // The TUI has already ended an aborted turn, so stop without reporting
+        if ctx.tool_ctx.is_aborted() {
+            return Ok(());
+        }
+        tool::pair_tool_results(&mut messages);
+        ctx.start_step().await;
+        let result = loop {
+            let rx = ctx
+                .create_stream(&client, &messages, prefill.as_deref())
+                .await?;
+            match process_stream(rx, &ctx.event_tx, ctx.step_writers()).await {
+                Ok(result) => break result,
+                Err(e) => match e.downcast::<ModelOverloaded>() {
+                    Ok(overloaded) => client = ctx.fall_back(&mut fallbacks, overloaded).await?,
+                    Err(e) => return Err(e),
+                },
+            }
+        };
+        prefill = None;
+        ctx.finish_step(&result).await;
+
+        // Reported after the step, so the refused response's usage is counted
+        if let Some(reason) = result.refusal {
+            let error = anyhow::Error::from(RequestRefused(reason));
+            let _ = ctx
+                .event_tx
+                .send(AppEvent::StreamError(error.to_string()))
+                .await;
+            ctx.record_refusal(&error).await;
+            return Err(error);
+        }
 
-        if !handle_stream_result(&ctx, &mut messages, result, &mut doom_detector, step).await? {
+        if handle_stream_result(&ctx, &mut messages, result, &mut doom_detector, step).await? {
+            continue;
+        }
+
+        let Some(schema) = &ctx.json_schema else {
+            break;
+        };
+        let Err(e) = provider::structured::check_output(&messages, schema) else {
+            break;
+        };
+        if json_retries >= provider::structured::MAX_RETRIES {
+            let _ = ctx
+                .event_tx
+                .send(AppEvent::StreamError(format!(
+                    "Reply does not match the JSON schema: {}",
+                    e
+                )))
+                .await;
             break;
         }
+        json_retries += 1;
+        messages.push(provider::structured::correction_message(&e));
+        prefill = provider::structured::retry_prefill(&ctx.target.provider_id);
     }
 
     if messages.len() > MAX_AGENTIC_STEPS as usize * 2 {
@@ -82,72 +206,297 @@ pub async fn stream_response_agentic(
     Ok(())
 }
 
+/// Fallback models from the config, in provider/model format
+async fn configured_fallbacks() -> Vec<String> {
+    match crate::config::Config::load().await {
+        Ok(config) => config.model_fallbacks.unwrap_or_default(),
+        Err(e) => {
+            tracing::warn!("Failed to load config for model fallbacks: {}", e);
+            Vec::new()
+        }
+    }
+}
+
 impl StreamContext {
+    /// Set up a turn, taking the abort signal out of `options`
     async fn new(
         provider_id: &str,
         model_id: &str,
+        session_id: &str,
+        prompt: &str,
+        options: &mut StreamOptions,
         event_tx: mpsc::Sender<AppEvent>,
     ) -> Result<Self> {
-        let (api_key, model) = get_provider_credentials(provider_id, model_id).await?;
+        let max_output = options.max_output;
+        let mut target = ModelTarget::resolve(provider_id, model_id, max_output).await?;
+        target.temperature = options.temperature;
         let tool_defs = get_tool_definitions().await;
         let cwd = get_current_dir();
-        let system_prompt = crate::session::system::generate(&cwd, provider_id, model_id);
-
-        let permission_handler =
-            crate::permission_state::create_tui_permission_handler(event_tx.clone());
-        let question_handler = crate::question_state::create_tui_question_handler(event_tx.clone());
-
-        let tool_ctx = Arc::new(
-            ToolContext::new("", "")
-                .with_cwd(cwd.clone())
-                .with_root(cwd)
-                .with_permission_handler(permission_handler)
-                .with_question_handler(question_handler),
+        let config = crate::config::Config::load().await.unwrap_or_default();
+        let system_prompt = crate::session::system::load(&config, &cwd, provider_id, model_id)?;
+
+        let checker =
+            PermissionChecker::from_config(&config).with_session(options.permission.as_ref());
+        let permission_handler = crate::permission_state::create_tui_permission_handler(
+            checker.clone(),
+            event_tx.clone(),
         );
+        let question_handler = crate::question_state::create_tui_question_handler(
+            session_id.to_string(),
+            event_tx.clone(),
+        );
+
+        // Persist the prompt so tool calls (and the file diffs they carry) are recorded;
+        // a regenerated reply is a sibling answering the stored prompt
+        let message_id = match &options.reply_to {
+            _ if session_id.is_empty() => String::new(),
+            Some(parent_id) => {
+                Message::save_reply(session_id, parent_id, provider_id, model_id, &cwd).await?
+            }
+            None => Message::save_prompt(session_id, prompt, provider_id, model_id, &cwd).await?,
+        };
+
+        let mut tool_ctx = ToolContext::new(session_id, &message_id)
+            .with_cwd(cwd.clone())
+            .with_root(cwd)
+            .with_permission_handler(permission_handler)
+            .with_permission_checker(checker)
+            .with_question_handler(question_handler)
+            .with_progress_handler(create_progress_handler(event_tx.clone()));
+        if let Some(abort) = options.abort.take() {
+            tool_ctx = tool_ctx.with_abort(abort);
+        }
+        let tool_ctx = Arc::new(tool_ctx);
 
         Ok(Self {
-            provider_id: provider_id.to_string(),
-            api_key,
-            model,
+            target,
+            max_output,
             tool_defs,
             tool_ctx,
             event_tx,
             system_prompt,
+            json_schema: None,
+        })
+    }
+
+    /// Create writers that persist this step's text and reasoning, if the
+    /// session is persisted
+    fn step_writers(&self) -> Option<StepWriters> {
+        let (session_id, message_id) = self.persisted_message()?;
+        Some(StepWriters {
+            text: TextPartWriter::new(session_id, message_id),
+            reasoning: TextPartWriter::reasoning(session_id, message_id),
         })
     }
 
+    /// Session and assistant message IDs, if the session is persisted
+    fn persisted_message(&self) -> Option<(&str, &str)> {
+        let (session_id, message_id) = (&self.tool_ctx.session_id, &self.tool_ctx.message_id);
+        if session_id.is_empty() || message_id.is_empty() {
+            return None;
+        }
+        Some((session_id, message_id))
+    }
+
+    /// Record the start of a step
+    async fn start_step(&self) {
+        let Some((session_id, message_id)) = self.persisted_message() else {
+            return;
+        };
+        if let Err(e) = session::record_step_start(session_id, message_id).await {
+            tracing::warn!("Failed to persist step start: {}", e);
+        }
+    }
+
+    /// Record the finish of a step and report its usage
+    ///
+    /// Cost is priced with the model that served the step, which differs from
+    /// the session model after a fallback.
+    async fn finish_step(&self, result: &StreamResult) {
+        let cost = provider::usage_cost(
+            &self.target.model.cost,
+            result.input_tokens,
+            result.output_tokens,
+        );
+        let _ = self
+            .event_tx
+            .send(AppEvent::StepFinished {
+                tokens: result.input_tokens + result.output_tokens,
+                cost,
+            })
+            .await;
+
+        let Some((session_id, message_id)) = self.persisted_message() else {
+            return;
+        };
+        let tokens = TokenUsage {
+            input: result.input_tokens,
+            output: result.output_tokens,
+            ..Default::default()
+        };
+        if let Err(e) =
+            session::record_step_finish(session_id, message_id, &result.finish_reason, tokens, cost)
+                .await
+        {
+            tracing::warn!("Failed to persist step finish: {}", e);
+        }
+    }
+
+    /// Save a refusal on the assistant message, so it is not taken for an
+    /// empty answer
+    async fn record_refusal(&self, error: &anyhow::Error) {
+        let Some(refused) = error.downcast_ref::<RequestRefused>() else {
+            return;
+        };
+        let Some((session_id, message_id)) = self.persisted_message() else {
+            return;
+        };
+        let error = MessageError::Refused {
+            message: refused.0.clone(),
+        };
+        if let Err(e) = session::record_message_error(session_id, message_id, error).await {
+            tracing::warn!("Failed to persist refusal: {}", e);
+        }
+    }
+
+    /// Switch to the next usable fallback model after an overload
+    ///
+    /// The session keeps its model; only the rest of this turn uses the
+    /// fallback. Fails with the overload error once the chain is exhausted.
+    async fn fall_back(
+        &mut self,
+        fallbacks: &mut impl Iterator<Item = (String, String)>,
+        overloaded: ModelOverloaded,
+    ) -> Result<StreamingClient> {
+        for (provider_id, model_id) in fallbacks {
+            let resolved = async {
+                let target = ModelTarget::resolve(&provider_id, &model_id, self.max_output).await?;
+                Ok::<_, anyhow::Error>((target, create_client(&provider_id).await?))
+            }
+            .await;
+            let (target, client) = match resolved {
+                Ok(resolved) => resolved,
+                Err(e) => {
+                    tracing::warn!(
+                        "Skipping fallback model {}/{}: {}",
+                        provider_id,
+                        model_id,
+                        e
+                    );
+                    continue;
+                }
+            };
+
+            let _ = self
+                .event_tx
+                .send(AppEvent::ModelFallback {
+                    provider_id,
+                    model_id,
+                    reason: overloaded.0.clone(),
+                })
+                .await;
+            self.target = ModelTarget {
+                temperature: self.target.temperature,
+                ..target
+            };
+            return Ok(client);
+        }
+
+        let _ = self
+            .event_tx
+            .send(AppEvent::StreamError(overloaded.0.clone()))
+            .await;
+        Err(overloaded.into())
+    }
+
     async fn create_stream(
         &self,
         client: &StreamingClient,
         messages: &[ChatMessage],
+        prefill: Option<&str>,
     ) -> Result<mpsc::Receiver<StreamEvent>> {
         dispatch_to_provider(
             client,
-            &self.provider_id,
-            &self.api_key,
-            &self.model,
+            &self.target,
             messages.to_vec(),
             &self.system_prompt,
             &self.tool_defs,
+            prefill,
+            self.json_schema.as_ref(),
         )
         .await
     }
 }
 
+/// Forward live tool output to the TUI
+///
+/// Chunks are dropped rather than waited for when the event queue is full;
+/// the tool result still carries the full output.
+fn create_progress_handler(event_tx: mpsc::Sender<AppEvent>) -> tool::ProgressHandler {
+    Arc::new(move |output: tool::ToolOutputChunk| {
+        let _ = event_tx.try_send(AppEvent::ToolOutput {
+            id: output.call_id,
+            text: output.text,
+        });
+    })
+}
+
+/// Writers persisting a step's streamed response text and reasoning
+struct StepWriters {
+    text: TextPartWriter,
+    reasoning: TextPartWriter,
+}
+
+impl StepWriters {
+    /// Write buffered deltas, e.g. before the stream ends with an error
+    async fn flush(&mut self) {
+        let _ = self.reasoning.flush().await;
+        let _ = self.text.flush().await;
+    }
+
+    /// Mark the parts as complete and write them
+    async fn finish(self) {
+        for writer in [self.reasoning, self.text] {
+            if let Err(e) = writer.finish().await {
+                tracing::warn!("Failed to persist streamed text: {}", e);
+            }
+        }
+    }
+}
+
 /// Process stream events and collect results
+///
+/// When writers are given, the response text and reasoning are persisted as
+/// they stream.
 async fn process_stream(
     mut rx: mpsc::Receiver<StreamEvent>,
     event_tx: &mpsc::Sender<AppEvent>,
+    mut writers: Option<StepWriters>,
 ) -> Result<StreamResult> {
     let mut response_text = String::new();
     let mut tool_tracker = ToolCallTracker::new();
     let mut finish_reason = String::new();
+    let (mut input_tokens, mut output_tokens) = (0, 0);
+    let mut refusal = None;
 
     while let Some(event) = rx.recv().await {
         match event {
             StreamEvent::TextDelta(text) => {
                 let _ = event_tx.send(AppEvent::StreamDelta(text.clone())).await;
                 response_text.push_str(&text);
+                if let Some(writers) = &mut writers {
+                    if let Err(e) = writers.text.append(&text).await {
+                        tracing::warn!("Failed to persist streamed text: {}", e);
+                    }
+                }
+            }
+            StreamEvent::ReasoningDelta(text) => {
+                if let Some(writers) = &mut writers {
+                    if let Err(e) = writers.reasoning.append(&text).await {
+                        tracing::warn!("Failed to persist streamed reasoning: {}", e);
+                    }
+                }
+                let _ = event_tx.send(AppEvent::ReasoningDelta(text)).await;
             }
             StreamEvent::ToolCallStart { id, name } => {
                 let _ = event_tx
@@ -164,20 +513,66 @@ async fn process_stream(
             StreamEvent::Done {
                 finish_reason: reason,
             } => {
-                finish_reason = reason;
+                // OpenAI's `[DONE]` and Anthropic's `message_stop` follow the
+                // actual reason as a plain "stop"
+                if finish_reason.is_empty() {
+                    finish_reason = reason;
+                }
+            }
+            StreamEvent::Usage {
+                input_tokens: input,
+                output_tokens: output,
+            } => {
+                // Some providers report usage more than once per response
+                input_tokens = input_tokens.max(input);
+                output_tokens = output_tokens.max(output);
             }
             StreamEvent::Error(err) => {
                 let _ = event_tx.send(AppEvent::StreamError(err.clone())).await;
+                if let Some(writers) = &mut writers {
+                    writers.flush().await;
+                }
                 return Err(anyhow::anyhow!(err));
             }
+            // Usage may still follow, so the stream is read to its end
+            StreamEvent::Refused(reason) => refusal = Some(reason),
+            StreamEvent::Overloaded(message) => {
+                // Reported by the caller, which may fall back to another model
+                if let Some(writers) = &mut writers {
+                    writers.flush().await;
+                }
+                return Err(ModelOverloaded(message).into());
+            }
+            StreamEvent::AuthError {
+                provider_id,
+                message,
+            } => {
+                let _ = event_tx
+                    .send(AppEvent::AuthError {
+                        provider_id,
+                        message: message.clone(),
+                    })
+                    .await;
+                if let Some(writers) = &mut writers {
+                    writers.flush().await;
+                }
+                return Err(anyhow::anyhow!(message));
+            }
             _ => {}
         }
     }
 
+    if let Some(writers) = writers {
+        writers.finish().await;
+    }
+
     Ok(StreamResult {
         response_text,
         pending_calls: tool_tracker.get_all_calls(),
         finish_reason,
+        input_tokens,
+        output_tokens,
+        refusal,
     })
 }
 
@@ -191,9 +586,26 @@ async fn handle_stream_result(
 ) -> Result<bool> {
     let mut assistant_parts = build_text_parts(&result.response_text);
 
+    let finish = FinishReason::parse(&result.finish_reason);
+    if let Some(reason) = finish.refusal() {
+        add_assistant_message(messages, assistant_parts);
+        let error = anyhow::Error::from(RequestRefused(reason.to_string()));
+        ctx.record_refusal(&error).await;
+        let _ = ctx
+            .event_tx
+            .send(AppEvent::StreamError(error.to_string()))
+            .await;
+        return Ok(false);
+    }
+
+    // Tool calls continue the loop whatever the reason says; without any,
+    // there is nothing to send back, so the turn ends
     if result.pending_calls.is_empty() {
+        if finish == FinishReason::ToolCalls {
+            tracing::warn!("Model finished with tool calls but none were received");
+        }
         add_assistant_message(messages, assistant_parts);
-        return Ok(!is_terminal_finish_reason(&result.finish_reason));
+        return Ok(false);
     }
 
     // Check for doom loop
@@ -262,71 +674,201 @@ fn add_assistant_message(messages: &mut Vec<ChatMessage>, parts: Vec<ContentPart
 
 /// Execute approved tools
 async fn execute_tools(ctx: &StreamContext, calls: Vec<PendingToolCall>) -> Vec<ContentPart> {
+    for call in &calls {
+        let _ = ctx
+            .event_tx
+            .send(AppEvent::ToolInput {
+                id: call.id.clone(),
+                args: call.arguments.clone(),
+            })
+            .await;
+    }
+
     let tool_results = tool::execute_all_tools_parallel(calls, &ctx.tool_ctx).await;
 
     for result in &tool_results {
-        if let ContentPart::ToolResult {
-            tool_use_id,
-            content,
-            is_error,
-        } = result
-        {
-            let _ = ctx
-                .event_tx
-                .send(AppEvent::ToolResult {
-                    id: tool_use_id.clone(),
-                    output: content.clone(),
-                    is_error: is_error.unwrap_or(false),
-                })
-                .await;
-        }
+        let event = match result {
+            ContentPart::ToolResult {
+                tool_use_id,
+                content,
+                is_error,
+            } => AppEvent::ToolResult {
+                id: tool_use_id.clone(),
+                output: content.clone(),
+                is_error: is_error.unwrap_or(false),
+            },
+            ContentPart::ImageUrl { image_url } => AppEvent::ToolImage {
+                mime: image_mime(&image_url.url).to_string(),
+            },
+            _ => continue,
+        };
+        let _ = ctx.event_tx.send(event).await;
     }
 
     tool_results
 }
 
-fn is_terminal_finish_reason(reason: &str) -> bool {
-    matches!(reason, "stop" | "end_turn" | "length")
+/// Media type of an image URL, known only for data URLs
+fn image_mime(url: &str) -> &str {
+    url.strip_prefix("data:")
+        .and_then(|rest| rest.split([';', ',']).next())
+        .filter(|mime| !mime.is_empty())
+        .unwrap_or("image")
 }
 
 /// Stream a response from the LLM (simple, non-agentic)
+///
+/// `session_system` and then `agent_prompt` are appended to the system prompt.
 pub async fn stream_response(
     provider_id: &str,
     model_id: &str,
     prompt: &str,
+    max_output: Option<u64>,
+    session_system: Option<&str>,
+    agent_prompt: Option<&str>,
 ) -> Result<mpsc::Receiver<StreamEvent>> {
-    let (api_key, model) = get_provider_credentials(provider_id, model_id).await?;
+    let target = ModelTarget::resolve(provider_id, model_id, max_output).await?;
     let messages = vec![ChatMessage {
         role: "user".to_string(),
         content: ChatContent::Text(prompt.to_string()),
     }];
     let tool_defs = get_tool_definitions().await;
     let cwd = get_current_dir();
-    let system_prompt = crate::session::system::generate(&cwd, provider_id, model_id);
+    let config = crate::config::Config::load().await.unwrap_or_default();
+    let mut system_prompt = crate::session::system::with_additions(
+        crate::session::system::load(&config, &cwd, provider_id, model_id)?,
+        session_system,
+    );
+    if let Some(agent_prompt) = agent_prompt {
+        system_prompt.push_str("\n\n");
+        system_prompt.push_str(agent_prompt);
+    }
 
-    let client = StreamingClient::new();
+    let client = create_client(provider_id).await?;
     dispatch_to_provider(
         &client,
-        provider_id,
-        &api_key,
-        &model,
+        &target,
         messages,
         &system_prompt,
         &tool_defs,
+        None,
+        None,
     )
     .await
 }
 
+/// A generated session title and what the request for it used
+pub struct GeneratedTitle {
+    /// `None` if the model's reply held no title
+    pub title: Option<String>,
+    pub tokens: u64,
+    pub cost: f64,
+}
+
+/// Ask a cheap model for a title of a session's first exchange
+///
+/// Uses `small_model` if configured, otherwise the cheapest model of the
+/// session's provider.
+pub async fn generate_title(
+    provider_id: &str,
+    model_id: &str,
+    prompt: &str,
+    reply: &str,
+) -> Result<GeneratedTitle> {
+    let config = crate::config::Config::load().await.unwrap_or_default();
+    let configured = config
+        .small_model
+        .as_deref()
+        .and_then(provider::parse_model_string);
+    let (provider_id, model_id) = match configured {
+        Some(small_model) => small_model,
+        None => {
+            let cheapest = provider::registry()
+                .get(provider_id)
+                .await
+                .and_then(|p| session::title::cheapest_model(&p, model_id).map(String::from));
+            (
+                provider_id.to_string(),
+                cheapest.unwrap_or_else(|| model_id.to_string()),
+            )
+        }
+    };
+
+    let target =
+        ModelTarget::resolve(&provider_id, &model_id, Some(session::title::MAX_TOKENS)).await?;
+    let messages = vec![ChatMessage {
+        role: "user".to_string(),
+        content: ChatContent::Text(session::title::request(prompt, reply)),
+    }];
+    let client = create_client(&provider_id).await?;
+    let mut rx = dispatch_to_provider(
+        &client,
+        &target,
+        messages,
+        session::title::SYSTEM_PROMPT,
+        &[],
+        None,
+        None,
+    )
+    .await?;
+
+    let mut text = String::new();
+    let (mut input_tokens, mut output_tokens) = (0, 0);
+    while let Some(event) = rx.recv().await {
+        match event {
+            StreamEvent::TextDelta(delta) => text.push_str(&delta),
+            StreamEvent::Usage {
+                input_tokens: input,
+                output_tokens: output,
+            } => {
+                input_tokens = input_tokens.max(input);
+                output_tokens = output_tokens.max(output);
+            }
+            StreamEvent::Error(message)
+            | StreamEvent::Refused(message)
+            | StreamEvent::Overloaded(message)
+            | StreamEvent::AuthError { message, .. } => anyhow::bail!(message),
+            _ => {}
+        }
+    }
+    Ok(GeneratedTitle {
+        title: session::title::clean(&text),
+        tokens: input_tokens + output_tokens,
+        cost: provider::usage_cost(&target.model.cost, input_tokens, output_tokens),
+    })
+}
+
 // --- Shared utility functions ---
 
-fn get_current_dir() -> String {
+pub(super) fn get_current_dir() -> String {
     std::env::current_dir()
         .ok()
         .and_then(|p| p.to_str().map(String::from))
         .unwrap_or_else(|| ".".to_string())
 }
 
-async fn get_provider_credentials(provider_id: &str, model_id: &str) -> Result<(String, Model)> {
+impl ModelTarget {
+    /// Look up the provider and model, applying the max output override
+    async fn resolve(provider_id: &str, model_id: &str, max_output: Option<u64>) -> Result<Self> {
+        let (api_key, model, options) = get_provider_credentials(provider_id, model_id).await?;
+        let max_tokens = provider::max_output_tokens(&model, max_output);
+
+        Ok(Self {
+            provider_id: provider_id.to_string(),
+            api_key,
+            model,
+            max_tokens,
+            temperature: None,
+            options,
+        })
+    }
+}
+
+/// API key, model and merged request options of a provider's model
+async fn get_provider_credentials(
+    provider_id: &str,
+    model_id: &str,
+) -> Result<(String, Model, serde_json::Map<String, serde_json::Value>)> {
     let provider = provider::registry()
         .get(provider_id)
         .await
@@ -338,11 +880,21 @@ async fn get_provider_credentials(provider_id: &str, model_id: &str) -> Result<(
         .ok_or_else(|| anyhow::anyhow!("Model not found: {}", model_id))?
         .clone();
 
+    let options = provider::options::merged(&provider.options, &model.options);
     let api_key = provider
         .key
         .ok_or_else(|| anyhow::anyhow!("No API key for provider: {}", provider_id))?;
 
-    Ok((api_key, model))
+    Ok((api_key, model, options))
+}
+
+/// Create a streaming client configured for the provider
+async fn create_client(provider_id: &str) -> Result<StreamingClient> {
+    let provider = provider::registry()
+        .get(provider_id)
+        .await
+        .ok_or_else(|| anyhow::anyhow!("Provider not found: {}", provider_id))?;
+    StreamingClient::for_provider(&provider)
 }
 
 async fn get_tool_definitions() -> Vec<ToolDefinition> {
@@ -360,36 +912,41 @@ async fn get_tool_definitions() -> Vec<ToolDefinition> {
 
 async fn dispatch_to_provider(
     client: &StreamingClient,
-    provider_id: &str,
-    api_key: &str,
-    model: &Model,
-    messages: Vec<ChatMessage>,
+    target: &ModelTarget,
+    mut messages: Vec<ChatMessage>,
     system_prompt: &str,
     tool_defs: &[ToolDefinition],
+    prefill: Option<&str>,
+    json_schema: Option<&serde_json::Value>,
 ) -> Result<mpsc::Receiver<StreamEvent>> {
-    match provider_id {
+    let ModelTarget {
+        provider_id,
+        api_key,
+        model,
+        max_tokens,
+        temperature,
+        options,
+    } = target;
+    let max_tokens = *max_tokens;
+    let temperature = temperature.filter(|_| model.capabilities.temperature);
+    if !model.capabilities.supports(Capability::ImageInput) {
+        provider::replace_images(&mut messages);
+    }
+
+    match provider_id.as_str() {
         "anthropic" => {
-            client
-                .stream_anthropic(
-                    api_key,
-                    &model.api.id,
-                    messages,
-                    Some(system_prompt.to_string()),
-                    tool_defs.to_vec(),
-                    model.limit.output,
-                )
-                .await
-        }
-        "openai" => {
-            let base_url = model.api.url.as_deref().unwrap_or(DEFAULT_OPENAI_URL);
-            let request = OpenAIRequest {
+            let request = AnthropicRequest {
                 messages,
                 system: Some(system_prompt.to_string()),
                 tools: tool_defs.to_vec(),
-                max_tokens: model.limit.output,
+                max_tokens,
+                prefill: prefill.map(String::from),
+                json_schema: json_schema.cloned(),
+                temperature,
+                options: options.clone(),
             };
             client
-                .stream_openai(api_key, base_url, &model.api.id, request)
+                .stream_anthropic(api_key, &model.api.id, request)
                 .await
         }
         "copilot" => {
@@ -400,10 +957,26 @@ async fn dispatch_to_provider(
                     messages,
                     Some(system_prompt.to_string()),
                     tool_defs.to_vec(),
-                    model.limit.output,
+                    max_tokens,
                 )
                 .await
         }
+        _ if provider::is_openai_compatible(provider_id, model) => {
+            let base_url = model.api.url.as_deref().unwrap_or(DEFAULT_OPENAI_URL);
+            let request = OpenAIRequest {
+                messages,
+                system: Some(system_prompt.to_string()),
+                tools: tool_defs.to_vec(),
+                max_tokens,
+                max_tokens_field: provider::openai_max_tokens_field(model),
+                json_schema: json_schema.cloned(),
+                temperature,
+                options: options.clone(),
+            };
+            client
+                .stream_openai(provider_id, api_key, base_url, &model.api.id, request)
+                .await
+        }
         _ => Err(anyhow::anyhow!("Unsupported provider: {}", provider_id)),
     }
 }
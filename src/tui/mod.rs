@@ -5,21 +5,39 @@
 mod app;
 mod autocomplete;
 mod clipboard;
+mod code_block;
 mod command_handler;
 mod components;
+mod crash;
 mod dialog;
 mod dialog_render;
+mod draft;
+mod edit;
+mod editor;
+mod focus;
+mod frame;
+mod history;
 mod input;
+mod keymap;
 mod llm_streaming;
 mod model;
+mod notify;
 mod oauth_flow;
+mod onboarding;
+mod phase;
 mod state;
+mod terminal;
 mod theme;
+mod title;
+mod tool_block;
 mod transcript;
 mod types;
 mod ui;
+mod variants;
+mod wrap;
 
 pub use app::run;
 pub use clipboard::copy_to_clipboard;
+pub use llm_streaming::{stream_response_agentic, StreamOptions};
 pub use transcript::{format_transcript, TranscriptOptions};
 pub use types::*;
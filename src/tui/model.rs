@@ -5,8 +5,10 @@
 
 use anyhow::Result;
 
+use super::dialog::capability_names;
 use super::state::App;
-use crate::provider;
+use super::types::MessagePart;
+use crate::provider::{self, Capability};
 
 /// Model-related methods for App
 impl App {
@@ -24,6 +26,18 @@ impl App {
         self.model_configured = true;
         self.close_dialog();
 
+        let missing = model.capabilities.missing(&self.required_capabilities());
+        if !missing.is_empty() {
+            self.add_message(
+                "system",
+                &format!(
+                    "Warning: {} does not support {}, which this session requires",
+                    self.model_display,
+                    capability_names(&missing)
+                ),
+            );
+        }
+
         // Save to session
         if let Some(session) = &mut self.session {
             let model_ref = crate::session::ModelRef {
@@ -49,4 +63,44 @@ impl App {
 
         Ok(())
     }
+
+    /// Use the model the current session last used, if it is still available
+    ///
+    /// Called when switching sessions; the session already records the model,
+    /// so nothing is saved.
+    pub async fn restore_session_model(&mut self) {
+        let Some(session) = &self.session else {
+            return;
+        };
+        let Some(model_ref) = session.get_model().await else {
+            return;
+        };
+        let Some(model) = provider::registry()
+            .get_model(&model_ref.provider_id, &model_ref.model_id)
+            .await
+        else {
+            return;
+        };
+        self.model_display = format!("{}/{}", model_ref.provider_id, model.name);
+        self.provider_id = model_ref.provider_id;
+        self.model_id = model_ref.model_id;
+        self.model_configured = true;
+    }
+
+    /// Capabilities the session requires of its model
+    ///
+    /// Tools are always offered, so the model must support tool calling; a
+    /// transcript with images needs a model that accepts them.
+    pub fn required_capabilities(&self) -> Vec<Capability> {
+        let mut required = vec![Capability::ToolCall];
+        let has_images = self
+            .messages
+            .iter()
+            .flat_map(|msg| &msg.parts)
+            .any(|part| matches!(part, MessagePart::Image { .. }));
+        if has_images {
+            required.push(Capability::ImageInput);
+        }
+        required
+    }
 }
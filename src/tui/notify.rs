@@ -0,0 +1,128 @@
+//! Notifications when a turn needs attention.
+//!
+//! When a turn finishes, or the agent asks a question or for permission, the
+//! TUI can ring the terminal bell and show a desktop notification. Both are
+//! off unless enabled under `tui.notify`. Terminals that report focus changes
+//! only get notified while they are in the background; others always do.
+
+use super::state::App;
+use crate::config::NotifyConfig;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// Something the user may want to be told about
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Attention {
+    TurnComplete,
+    Question,
+    Permission,
+}
+
+impl Attention {
+    /// Body of the desktop notification
+    fn message(self) -> &'static str {
+        match self {
+            Attention::TurnComplete => "The turn is complete",
+            Attention::Question => "The agent has a question",
+            Attention::Permission => "The agent is asking for permission",
+        }
+    }
+}
+
+/// Resolved `tui.notify` settings
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Notifier {
+    pub bell: bool,
+    pub desktop: bool,
+}
+
+impl Notifier {
+    pub fn from_config(config: Option<&NotifyConfig>) -> Self {
+        Self {
+            bell: config.and_then(|c| c.bell).unwrap_or(false),
+            desktop: config.and_then(|c| c.desktop).unwrap_or(false),
+        }
+    }
+
+    /// Whether anything fires for a terminal with the given focus
+    ///
+    /// Unknown focus (None) counts as in the background, since a terminal that
+    /// never reports focus would otherwise never be notified.
+    pub fn is_active(&self, focused: Option<bool>) -> bool {
+        (self.bell || self.desktop) && focused != Some(true)
+    }
+
+    /// Ring the bell and show a desktop notification, as configured
+    pub fn notify(&self, attention: Attention, focused: Option<bool>) {
+        if !self.is_active(focused) {
+            return;
+        }
+        if self.bell {
+            let mut stdout = std::io::stdout();
+            let _ = stdout.write_all(b"\x07");
+            let _ = stdout.flush();
+        }
+        if self.desktop {
+            show_desktop_notification(attention.message());
+        }
+    }
+}
+
+/// Notification methods for App
+impl App {
+    /// Notify the user that the turn needs attention, as configured
+    pub fn notify(&self, attention: Attention) {
+        self.notifier.notify(attention, self.focused);
+    }
+}
+
+/// Show a desktop notification through the platform's notifier, if any
+///
+/// Failures are ignored: a missing `notify-send` should not interrupt a turn.
+/// The notifier runs detached from the terminal and is reaped on a background
+/// thread, so it leaves no zombie process.
+fn show_desktop_notification(message: &str) {
+    let mut command = if cfg!(target_os = "macos") {
+        let script = format!("display notification {:?} with title \"opencode\"", message);
+        let mut command = Command::new("osascript");
+        command.args(["-e", &script]);
+        command
+    } else if cfg!(windows) {
+        return;
+    } else {
+        let mut command = Command::new("notify-send");
+        command.args(["--app-name=opencode", "opencode", message]);
+        command
+    };
+    let spawned = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn();
+    match spawned {
+        Ok(mut child) => {
+            std::thread::spawn(move || child.wait());
+        }
+        Err(e) => tracing::debug!("Failed to show desktop notification: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_notifier_fires_only_in_background() {
+        let off = Notifier::from_config(None);
+        assert!(!off.is_active(Some(false)));
+
+        let bell = Notifier::from_config(Some(&NotifyConfig {
+            bell: Some(true),
+            desktop: None,
+        }));
+        assert!(bell.is_active(Some(false)));
+        assert!(!bell.is_active(Some(true)));
+        // A terminal that never reported focus is notified
+        assert!(bell.is_active(None));
+    }
+}
@@ -0,0 +1,101 @@
+//! First-run onboarding.
+//!
+//! When no provider has credentials and no config file exists, the TUI walks
+//! a new user through connecting a provider, picking a model and optionally
+//! writing a starter config. Connecting reuses the provider selector and its
+//! API key and OAuth flows.
+
+use super::state::App;
+use super::types::{DialogState, DialogType, SelectItem};
+use crate::config::Config;
+
+/// Shown in the transcript when onboarding starts
+const WELCOME: &str = "Welcome to opencode! No provider credentials were found.
+
+To get started, pick a provider below and either enter its API key or sign in \
+(GitHub Copilot and ChatGPT support OAuth). Alternatively, quit, set the \
+provider's API key environment variable (e.g. ANTHROPIC_API_KEY) and restart, \
+or add a custom OpenAI-compatible provider to opencode.json.
+
+After connecting, choose a model; it can be saved to a starter config.";
+
+/// Item ID of the starter config choice that writes the file
+const WRITE_CONFIG: &str = "write";
+
+/// Onboarding methods for App
+impl App {
+    /// Explain the setup options and open the provider selector
+    pub fn start_onboarding(&mut self) {
+        self.add_message("system", WELCOME);
+        self.open_provider_selector();
+        if let Some(dialog) = &mut self.dialog {
+            dialog.message = Some("Step 1/3: connect a provider".to_string());
+        }
+    }
+
+    /// Offer to write a starter config with the chosen model, ending onboarding
+    pub fn offer_starter_config(&mut self) {
+        self.onboarding = false;
+        let path = Config::global_config_path()
+            .map(|path| path.display().to_string())
+            .unwrap_or_else(|| "opencode.json".to_string());
+
+        let dialog = DialogState::new(DialogType::StarterConfig, "Save Starter Config")
+            .with_items(vec![
+                SelectItem {
+                    id: WRITE_CONFIG.to_string(),
+                    label: "Write starter config".to_string(),
+                    description: Some(path),
+                    provider_id: None,
+                },
+                SelectItem {
+                    id: "skip".to_string(),
+                    label: "Skip".to_string(),
+                    description: Some("Nothing is written".to_string()),
+                    provider_id: None,
+                },
+            ])
+            .with_message(&format!(
+                "Step 3/3: save {} as the default model?",
+                self.model_display
+            ));
+        self.dialog = Some(dialog);
+    }
+
+    /// Handle the starter config choice
+    pub async fn finish_onboarding(&mut self, choice: &str) {
+        self.close_dialog();
+        if choice != WRITE_CONFIG {
+            return;
+        }
+
+        let model = format!("{}/{}", self.provider_id, self.model_id);
+        match Config::init_with_model(&model).await {
+            Ok(path) => self.add_message(
+                "system",
+                &format!("Wrote starter config to {}", path.display()),
+            ),
+            Err(e) => self.add_message("system", &format!("Failed to write config: {}", e)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_offer_starter_config_ends_onboarding() {
+        let mut app = App {
+            onboarding: true,
+            model_display: "anthropic/Claude Sonnet".to_string(),
+            ..Default::default()
+        };
+        app.offer_starter_config();
+
+        assert!(!app.onboarding);
+        let dialog = app.dialog.as_ref().unwrap();
+        assert_eq!(dialog.dialog_type, DialogType::StarterConfig);
+        assert_eq!(dialog.items[0].id, WRITE_CONFIG);
+    }
+}
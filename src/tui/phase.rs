@@ -0,0 +1,143 @@
+//! What a running turn is doing.
+//!
+//! The status shows whether the model is thinking, streaming its reply, or
+//! waiting on tools, on a permission decision or on an answer. The phase
+//! follows the turn's events; tool calls count as running from the moment
+//! their arguments are complete until their results arrive.
+
+use super::state::App;
+use super::types::MessagePart;
+
+/// Phase of a running turn
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TurnPhase {
+    /// Waiting for the model, or receiving its reasoning
+    #[default]
+    Thinking,
+    /// Receiving the reply text or tool calls
+    Streaming,
+    /// Executing the tool calls in `App::running_tools`
+    RunningTools,
+    /// A tool waits for a permission decision
+    AwaitingPermission,
+    /// A tool waits for an answer to its question
+    AwaitingAnswer,
+}
+
+impl App {
+    /// Move the running turn to another phase and show it as the status
+    ///
+    /// Events arriving after the turn ended, e.g. from an aborted turn, leave
+    /// the status alone.
+    pub fn set_turn_phase(&mut self, phase: TurnPhase) {
+        if !self.is_processing {
+            return;
+        }
+        self.turn_phase = phase;
+        self.status = self.turn_phase_label();
+    }
+
+    /// Status text of the current phase
+    pub fn turn_phase_label(&self) -> String {
+        match self.turn_phase {
+            TurnPhase::Thinking => "Thinking…".to_string(),
+            TurnPhase::Streaming => "Streaming…".to_string(),
+            TurnPhase::RunningTools => {
+                let mut names: Vec<&str> = self
+                    .running_tools
+                    .iter()
+                    .map(|(_, name)| name.as_str())
+                    .collect();
+                names.dedup();
+                match names.as_slice() {
+                    [name] => format!("Running {}…", name),
+                    _ => format!("Running {} tools…", self.running_tools.len()),
+                }
+            }
+            TurnPhase::AwaitingPermission => "Waiting for permission…".to_string(),
+            TurnPhase::AwaitingAnswer => "Waiting for your answer…".to_string(),
+        }
+    }
+
+    /// Start a turn in the thinking phase
+    pub fn begin_turn_phase(&mut self) {
+        self.running_tools.clear();
+        self.set_turn_phase(TurnPhase::Thinking);
+    }
+
+    /// Record a tool call whose execution starts
+    pub fn tool_started(&mut self, id: &str) {
+        let name = self
+            .messages
+            .last()
+            .into_iter()
+            .flat_map(|msg| &msg.parts)
+            .find_map(|part| match part {
+                MessagePart::ToolCall {
+                    id: call_id, name, ..
+                } if call_id == id => Some(name.clone()),
+                _ => None,
+            })
+            .unwrap_or_else(|| "tool".to_string());
+        self.running_tools.push((id.to_string(), name));
+        self.set_turn_phase(TurnPhase::RunningTools);
+    }
+
+    /// Record a finished tool call; once none runs, the model is up again
+    pub fn tool_finished(&mut self, id: &str) {
+        self.running_tools.retain(|(call_id, _)| call_id != id);
+        if self.turn_phase == TurnPhase::RunningTools {
+            self.resume_turn_phase();
+        }
+    }
+
+    /// Return from waiting on the user to the tools or the model
+    pub fn resume_turn_phase(&mut self) {
+        if self.running_tools.is_empty() {
+            self.set_turn_phase(TurnPhase::Thinking);
+        } else {
+            self.set_turn_phase(TurnPhase::RunningTools);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_turn_phase() {
+        let mut app = App::default();
+        app.add_message("assistant", "");
+        app.is_processing = true;
+        app.begin_turn_phase();
+        assert_eq!(app.status, "Thinking…");
+
+        app.set_turn_phase(TurnPhase::Streaming);
+        app.handle_tool_call("call_1", "bash");
+        app.handle_tool_call("call_2", "bash");
+        app.tool_started("call_1");
+        app.tool_started("call_2");
+        assert_eq!(app.status, "Running bash…");
+        app.handle_tool_call("call_3", "read");
+        app.tool_started("call_3");
+        assert_eq!(app.status, "Running 3 tools…");
+
+        app.set_turn_phase(TurnPhase::AwaitingPermission);
+        assert_eq!(app.status, "Waiting for permission…");
+        app.resume_turn_phase();
+        assert_eq!(app.status, "Running 3 tools…");
+
+        app.tool_finished("call_3");
+        assert_eq!(app.status, "Running bash…");
+        app.tool_finished("call_1");
+        app.tool_finished("call_2");
+        assert_eq!(app.status, "Thinking…");
+
+        // Late events of an ended turn
+        app.is_processing = false;
+        app.status = "Ready".to_string();
+        app.set_turn_phase(TurnPhase::Streaming);
+        assert_eq!(app.status, "Ready");
+    }
+}
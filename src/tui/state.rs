@@ -4,17 +4,37 @@
 //! the TUI application state. Similar to context/local.tsx in the TS version.
 
 use anyhow::Result;
+use std::collections::VecDeque;
 use std::sync::Arc;
+use std::time::Duration;
 
+use super::draft::{Draft, DraftAutosave};
+use super::edit::MessageEdit;
+use super::frame;
 use super::input::Action;
+use super::keymap::{KeyAction, Keymap};
+use super::notify::Notifier;
+use super::phase::TurnPhase;
 use super::theme::Theme;
+use super::tool_block;
 use super::types::{
-    AutocompleteState, DialogState, DialogType, DisplayMessage, MessagePart, PermissionRequest,
+    AutocompleteState, DialogState, DialogType, DisplayLine, DisplayMessage, MessagePart,
+    PermissionRequest, QuestionRequest, ReplyMetadata,
 };
-use crate::config::Config;
+use crate::config::{Config, PermissionAction};
 use crate::provider::{self, Provider};
+use crate::redact::Redactor;
 use crate::session::{CreateSessionOptions, Session};
-use crate::slash_command::{builtin::*, registry::CommandRegistry, template::TemplateCommand};
+use crate::slash_command::{
+    builtin::*, registry::CommandRegistry, template::TemplateCommand, DisplayToggle,
+};
+use crate::tool;
+
+/// Number of live tool output lines kept for display
+pub const LIVE_OUTPUT_LINES: usize = 6;
+
+/// Prefix of shown reasoning lines, setting them apart from the reply
+const REASONING_PREFIX: &str = "│ ";
 
 /// Application state
 pub struct App {
@@ -42,6 +62,8 @@ pub struct App {
     pub is_processing: bool,
     /// Spinner animation frame
     pub spinner_frame: usize,
+    /// Time between redraws of the event loop
+    pub tick_rate: Duration,
     /// Total cost
     pub total_cost: f64,
     /// Total tokens used
@@ -50,6 +72,8 @@ pub struct App {
     pub theme: Theme,
     /// Should quit
     pub should_quit: bool,
+    /// Clear the terminal before the next draw, e.g. after the editor ran
+    pub redraw_requested: bool,
     /// Whether model is configured
     pub model_configured: bool,
     /// Current dialog state
@@ -66,6 +90,12 @@ pub struct App {
     pub show_thinking: bool,
     /// Show tool details in messages
     pub show_tool_details: bool,
+    /// Show model, tokens and cost under assistant replies
+    pub show_assistant_metadata: bool,
+    /// Show system messages in the transcript
+    pub show_system_messages: bool,
+    /// Call ID of the tool call selected in the transcript
+    pub selected_tool: Option<String>,
     /// Message history for undo/redo
     pub message_history: Vec<Vec<DisplayMessage>>,
     /// Current position in history
@@ -78,6 +108,56 @@ pub struct App {
     pub input_history_buffer: String,
     /// Current tool batch for grouping consecutive tool calls
     pub tool_batch: Option<ToolBatch>,
+    /// Latest lines of live output from running tools, with their call IDs
+    pub live_output: VecDeque<(String, String)>,
+    /// Abort signal of the running turn; sending `true` cancels its tool calls
+    pub turn_abort: Option<tokio::sync::watch::Sender<bool>>,
+    /// Max output tokens override for requests (None = model limit)
+    pub max_output_tokens: Option<u64>,
+    /// Resolved keybindings
+    pub keymap: Keymap,
+    /// Secret redaction applied to copies and exports
+    pub redactor: Redactor,
+    /// Whether Enter inserts a newline instead of submitting
+    pub multiline_input: bool,
+    /// Whether the TUI captures the mouse (`tui.mouse`)
+    pub mouse_capture: bool,
+    /// First run: no credentials and no config file
+    pub onboarding: bool,
+    /// Unanswered questions restored from storage, whose tool no longer waits
+    pub restored_questions: Vec<QuestionRequest>,
+    /// Transcript indices of messages marked in the timeline for `/yank-context`
+    pub marked_messages: Vec<usize>,
+    /// Transcript index of the message focused with `message_prev`/`message_next`
+    pub focused_message: Option<usize>,
+    /// Max characters of a collapsed tool result preview
+    pub tool_preview_chars: usize,
+    /// Bell and desktop notifications when a turn needs attention
+    pub notifier: Notifier,
+    /// Whether the terminal has focus, None until it reports a change
+    pub focused: Option<bool>,
+    /// Autosave of the unsent input for crash recovery
+    pub draft: DraftAutosave,
+    /// Draft of a crashed run awaiting the recovery choice
+    pub recovered_draft: Option<Draft>,
+    /// Text to seed assistant replies with (Anthropic only)
+    pub prefill: Option<String>,
+    /// JSON schema assistant replies must conform to
+    pub json_schema: Option<serde_json::Value>,
+    /// Number of messages scrolled up from the bottom of the transcript
+    pub message_scroll: usize,
+    /// ID of the oldest loaded stored message, while older ones remain
+    pub history_before: Option<String>,
+    /// Earlier prompt being edited; submitting replaces it and what follows
+    pub editing: Option<MessageEdit>,
+    /// What the running turn is doing
+    pub turn_phase: TurnPhase,
+    /// Tool calls of the running turn being executed, as (ID, tool name)
+    pub running_tools: Vec<(String, String)>,
+    /// Whether sessions are titled after their first exchange
+    pub auto_title: bool,
+    /// Session a title has been requested for in this run
+    pub auto_title_session: Option<String>,
 }
 
 /// Tracks a batch of consecutive tool calls of the same type
@@ -108,10 +188,12 @@ impl Default for App {
             status: "Ready".to_string(),
             is_processing: false,
             spinner_frame: 0,
+            tick_rate: frame::tick_rate(None),
             total_cost: 0.0,
             total_tokens: 0,
             theme: Theme::dark(),
             should_quit: false,
+            redraw_requested: false,
             model_configured: false,
             dialog: None,
             available_providers: Vec::new(),
@@ -120,12 +202,40 @@ impl Default for App {
             autocomplete: None,
             show_thinking: true,
             show_tool_details: true,
+            show_assistant_metadata: false,
+            show_system_messages: true,
+            selected_tool: None,
             message_history: Vec::new(),
             history_position: 0,
             input_history: Vec::new(),
             input_history_position: None,
             input_history_buffer: String::new(),
             tool_batch: None,
+            live_output: VecDeque::new(),
+            turn_abort: None,
+            max_output_tokens: None,
+            keymap: Keymap::default(),
+            redactor: Redactor::default(),
+            multiline_input: false,
+            mouse_capture: false,
+            onboarding: false,
+            restored_questions: Vec::new(),
+            marked_messages: Vec::new(),
+            focused_message: None,
+            tool_preview_chars: tool_block::DEFAULT_PREVIEW_CHARS,
+            notifier: Notifier::default(),
+            focused: None,
+            draft: DraftAutosave::default(),
+            recovered_draft: None,
+            prefill: None,
+            json_schema: None,
+            message_scroll: 0,
+            history_before: None,
+            editing: None,
+            turn_phase: TurnPhase::default(),
+            running_tools: Vec::new(),
+            auto_title: true,
+            auto_title_session: None,
         }
     }
 }
@@ -138,10 +248,12 @@ impl App {
 
         // Initialize provider registry
         provider::registry().initialize(&config).await?;
+        tool::registry().initialize(&config).await;
 
         // Cache providers
         app.all_providers = provider::registry().list().await;
         app.available_providers = provider::registry().list_available().await;
+        app.onboarding = app.available_providers.is_empty() && !Config::file_exists().await;
 
         // Create session first
         let session = Session::create(CreateSessionOptions::default()).await?;
@@ -191,6 +303,47 @@ impl App {
         }
 
         app.session = Some(session);
+        app.max_output_tokens = config.max_output_tokens;
+        app.auto_title = config.auto_title.unwrap_or(true);
+
+        app.multiline_input = config
+            .tui
+            .as_ref()
+            .and_then(|tui| tui.multiline_input)
+            .unwrap_or(false);
+        app.mouse_capture = config
+            .tui
+            .as_ref()
+            .and_then(|tui| tui.mouse)
+            .unwrap_or(false);
+        app.tool_preview_chars = config
+            .tui
+            .as_ref()
+            .and_then(|tui| tui.tool_preview_chars)
+            .unwrap_or(tool_block::DEFAULT_PREVIEW_CHARS);
+        app.notifier =
+            Notifier::from_config(config.tui.as_ref().and_then(|tui| tui.notify.as_ref()));
+        let project = std::env::current_dir().unwrap_or_default();
+        app.draft = DraftAutosave::from_config(config.tui.as_ref(), &project);
+        app.tick_rate = frame::tick_rate(config.tui.as_ref());
+
+        // Resolve keybindings, reporting invalid entries
+        if let Some(keybinds) = &config.keybinds {
+            let (keymap, warnings) = Keymap::from_config(keybinds);
+            app.keymap = keymap;
+            for warning in warnings {
+                tracing::warn!("{}", warning);
+                app.add_message("system", &warning);
+            }
+        }
+
+        // Build the secret redactor, reporting invalid patterns
+        let (redactor, warnings) = Redactor::from_config(&config);
+        app.redactor = redactor;
+        for warning in warnings {
+            tracing::warn!("{}", warning);
+            app.add_message("system", &warning);
+        }
 
         // Apply theme from config
         if let Some(theme_name) = &config.theme {
@@ -206,6 +359,22 @@ impl App {
         Ok(app)
     }
 
+    /// Apply the input mode: in multiline mode submit and newline trade keys
+    pub fn input_key_action(&self, action: KeyAction) -> KeyAction {
+        match (self.multiline_input, action) {
+            (true, KeyAction::InputSubmit) => KeyAction::InputNewline,
+            (true, KeyAction::InputNewline) => KeyAction::InputSubmit,
+            _ => action,
+        }
+    }
+
+    /// Display name of the key that currently triggers an action
+    pub fn key_hint(&self, action: KeyAction) -> String {
+        self.keymap
+            .chord_for(self.input_key_action(action))
+            .map_or_else(|| "unbound".to_string(), |chord| chord.to_string())
+    }
+
     /// Check if undo is possible
     pub fn can_undo(&self) -> bool {
         self.history_position > 0 && !self.message_history.is_empty()
@@ -309,6 +478,13 @@ impl App {
         }
     }
 
+    /// Replace the input with a prompt picked in the history search
+    pub fn recall_history(&mut self, entry: String) {
+        self.input_history_position = None;
+        self.input_history_buffer.clear();
+        self.set_input_and_cursor(entry);
+    }
+
     /// Check if a model is configured and ready to use
     pub fn is_ready(&self) -> bool {
         self.model_configured && !self.provider_id.is_empty() && !self.model_id.is_empty()
@@ -400,39 +576,311 @@ impl App {
 
     /// Add a message to display
     pub fn add_message(&mut self, role: &str, content: &str) {
+        self.message_scroll = 0;
         self.messages.push(DisplayMessage {
             role: role.to_string(),
             content: content.to_string(),
             parts: vec![MessagePart::Text {
                 text: content.to_string(),
             }],
+            metadata: None,
+            variants: Vec::new(),
+            variant: 0,
         });
     }
 
     /// Add a tool call to the last message
-    pub fn add_tool_call(&mut self, _id: &str, name: &str, args: &str) {
+    pub fn add_tool_call(&mut self, id: &str, name: &str, args: &str) {
+        self.finish_reasoning();
         if let Some(msg) = self.messages.last_mut() {
             msg.parts.push(MessagePart::ToolCall {
+                id: id.to_string(),
                 name: name.to_string(),
                 args: args.to_string(),
+                expanded: false,
             });
         }
     }
 
     /// Add a tool result to the messages
-    pub fn add_tool_result(&mut self, _id: &str, output: &str, is_error: bool) {
+    pub fn add_tool_result(&mut self, id: &str, output: &str, is_error: bool) {
         if let Some(msg) = self.messages.last_mut() {
             msg.parts.push(MessagePart::ToolResult {
+                id: id.to_string(),
                 output: output.to_string(),
                 is_error,
             });
         }
     }
 
+    /// Note an image sent to the model in the last message
+    pub fn add_image(&mut self, mime: &str) {
+        if let Some(msg) = self.messages.last_mut() {
+            msg.parts.push(MessagePart::Image {
+                mime: mime.to_string(),
+            });
+        }
+    }
+
+    /// Set the arguments of a tool call once they are fully streamed
+    pub fn set_tool_args(&mut self, id: &str, arguments: &str) {
+        if let Some(args) = self.tool_call_parts_mut().find_map(|part| match part {
+            MessagePart::ToolCall {
+                id: call_id, args, ..
+            } if *call_id == id => Some(args),
+            _ => None,
+        }) {
+            *args = arguments.to_string();
+        }
+    }
+
+    /// Tool call parts of the transcript, newest first
+    fn tool_call_parts_mut(&mut self) -> impl Iterator<Item = &mut MessagePart> {
+        self.messages
+            .iter_mut()
+            .rev()
+            .flat_map(|msg| msg.parts.iter_mut().rev())
+            .filter(|part| matches!(part, MessagePart::ToolCall { .. }))
+    }
+
+    /// Move the tool call selection to an older (`-1`) or newer (`1`) call
+    ///
+    /// With nothing selected, the newest call is selected. Moving past the
+    /// newest call clears the selection.
+    pub fn select_tool(&mut self, step: isize) {
+        let ids: Vec<String> = self
+            .messages
+            .iter()
+            .flat_map(|msg| &msg.parts)
+            .filter_map(|part| match part {
+                MessagePart::ToolCall { id, .. } => Some(id.clone()),
+                _ => None,
+            })
+            .collect();
+
+        let current = self
+            .selected_tool
+            .as_ref()
+            .and_then(|selected| ids.iter().position(|id| id == selected));
+        let next = match current {
+            None => ids.len().checked_sub(1),
+            Some(pos) => match pos.checked_add_signed(step) {
+                Some(next) if next < ids.len() => Some(next),
+                // Past the newest call
+                _ if step > 0 => None,
+                // Already at the oldest call
+                _ => Some(pos),
+            },
+        };
+        self.selected_tool = next.and_then(|pos| ids.get(pos).cloned());
+        self.scroll_to_selected_tool();
+    }
+
+    /// Expand or collapse the selected tool call (the newest one if none is selected)
+    pub fn toggle_tool_expanded(&mut self) {
+        if self.selected_tool.is_none() {
+            self.select_tool(0);
+        }
+        let Some(selected) = self.selected_tool.clone() else {
+            return;
+        };
+        if let Some(expanded) = self.tool_call_parts_mut().find_map(|part| match part {
+            MessagePart::ToolCall { id, expanded, .. } if *id == selected => Some(expanded),
+            _ => None,
+        }) {
+            *expanded = !*expanded;
+        }
+    }
+
+    /// Scroll the transcript so the message of the selected tool call is the newest shown
+    fn scroll_to_selected_tool(&mut self) {
+        let Some(selected) = &self.selected_tool else {
+            self.message_scroll = 0;
+            return;
+        };
+        if let Some(idx) = self.messages.iter().position(|msg| {
+            msg.parts
+                .iter()
+                .any(|part| matches!(part, MessagePart::ToolCall { id, .. } if id == selected))
+        }) {
+            self.message_scroll = self.messages.len() - 1 - idx;
+        }
+    }
+
+    /// Add a finished step's usage to the last assistant reply
+    pub fn record_reply_usage(&mut self, tokens: u64, cost: f64) {
+        let model = self.model_display.clone();
+        if let Some(msg) = self.messages.last_mut().filter(|m| m.role == "assistant") {
+            let metadata = msg.metadata.get_or_insert_with(|| ReplyMetadata {
+                model,
+                ..Default::default()
+            });
+            metadata.tokens += tokens;
+            metadata.cost += cost;
+        }
+    }
+
+    /// Show or hide a kind of transcript content
+    pub fn toggle_display(&mut self, toggle: DisplayToggle) {
+        let (flag, what) = match toggle {
+            DisplayToggle::Tools => (&mut self.show_tool_details, "Tool details"),
+            DisplayToggle::Metadata => (&mut self.show_assistant_metadata, "Reply metadata"),
+            DisplayToggle::System => (&mut self.show_system_messages, "System messages"),
+        };
+        *flag = !*flag;
+        let state = if *flag { "shown" } else { "hidden" };
+        // The status bar, since a system message may itself be hidden
+        self.status = format!("{} {}", what, state);
+    }
+
+    /// Non-empty lines of a message as shown under the display toggles, or
+    /// None when the message is hidden
+    ///
+    /// Tool calls render as collapsible blocks (see `tool_block`). Hiding tool
+    /// details keeps their one-line summaries, without titles, collapsed.
+    pub fn displayed_lines(&self, msg: &DisplayMessage) -> Option<Vec<DisplayLine>> {
+        if msg.role == "system" && !self.show_system_messages {
+            return None;
+        }
+
+        let has_structured_parts = msg.parts.iter().any(|part| {
+            matches!(
+                part,
+                MessagePart::ToolCall { .. }
+                    | MessagePart::Reasoning { .. }
+                    | MessagePart::Image { .. }
+            )
+        });
+        let mut lines = if has_structured_parts {
+            self.part_lines(&msg.parts)
+        } else {
+            msg.content.lines().map(DisplayLine::new).collect()
+        };
+        if let Some(metadata) = msg
+            .metadata
+            .as_ref()
+            .filter(|_| self.show_assistant_metadata)
+        {
+            lines.push(DisplayLine::new(format!("· {}", metadata)));
+        }
+        if let Some(label) = msg.variant_label() {
+            lines.push(DisplayLine::new(format!("· {}", label)));
+        }
+        lines.retain(|line| !line.text.trim().is_empty());
+        Some(lines)
+    }
+
+    /// Lines of a message's text, reasoning, tool call and image parts, in order
+    fn part_lines(&self, parts: &[MessagePart]) -> Vec<DisplayLine> {
+        let mut lines = Vec::new();
+        for part in parts {
+            match part {
+                MessagePart::Text { text } => lines.extend(text.lines().map(DisplayLine::new)),
+                MessagePart::Reasoning { text, .. } if self.show_thinking => lines.extend(
+                    text.lines()
+                        .map(|line| DisplayLine::new(format!("{}{}", REASONING_PREFIX, line))),
+                ),
+                MessagePart::Reasoning {
+                    tokens: Some(tokens),
+                    ..
+                } => lines.push(DisplayLine::new(format!(
+                    "reasoning (hidden, {} tokens)",
+                    tokens
+                ))),
+                MessagePart::Reasoning { tokens: None, .. } => {
+                    lines.push(DisplayLine::new("reasoning (hidden)"))
+                }
+                MessagePart::ToolCall {
+                    id,
+                    name,
+                    args,
+                    expanded,
+                } => {
+                    let result = tool_block::find_result(parts, id);
+                    let expanded = *expanded && self.show_tool_details;
+                    lines.push(DisplayLine {
+                        text: tool_block::summary(
+                            name,
+                            result,
+                            expanded,
+                            self.show_tool_details,
+                            self.tool_preview_chars,
+                        ),
+                        selected: self.selected_tool.as_ref() == Some(id),
+                    });
+                    if expanded {
+                        lines.extend(
+                            tool_block::details(args, result)
+                                .into_iter()
+                                .map(DisplayLine::new),
+                        );
+                    }
+                }
+                MessagePart::ToolResult { .. } => {}
+                MessagePart::Image { mime } => {
+                    lines.push(DisplayLine::new(format!("[image: {}]", mime)))
+                }
+            }
+        }
+        lines
+    }
+
     /// Append to the last assistant message
     pub fn append_to_assistant(&mut self, delta: &str) {
+        self.finish_reasoning();
         if let Some(msg) = self.messages.last_mut().filter(|m| m.role == "assistant") {
             msg.content.push_str(delta);
+            match msg.parts.last_mut() {
+                Some(MessagePart::Text { text }) => text.push_str(delta),
+                _ => msg.parts.push(MessagePart::Text {
+                    text: delta.to_string(),
+                }),
+            }
+        }
+    }
+
+    /// Append a reasoning delta to the last assistant message
+    ///
+    /// Reasoning is kept as its own part, out of the reply text.
+    pub fn append_reasoning(&mut self, delta: &str) {
+        if let Some(msg) = self.messages.last_mut().filter(|m| m.role == "assistant") {
+            match msg.parts.last_mut() {
+                Some(MessagePart::Reasoning { text, .. }) => text.push_str(delta),
+                _ => msg.parts.push(MessagePart::Reasoning {
+                    text: delta.to_string(),
+                    tokens: None,
+                }),
+            }
+        }
+    }
+
+    /// Count the tokens of the last assistant message's finished reasoning
+    ///
+    /// Called when a reply moves past its reasoning, so hidden reasoning shows
+    /// its size without tokenizing it on every frame.
+    pub fn finish_reasoning(&mut self) {
+        let Some(msg) = self.messages.last_mut().filter(|m| m.role == "assistant") else {
+            return;
+        };
+        for part in &mut msg.parts {
+            if let MessagePart::Reasoning {
+                text,
+                tokens: tokens @ None,
+            } = part
+            {
+                let tokenizer = provider::tokenizer::for_model(&self.provider_id, &self.model_id);
+                *tokens = Some(tokenizer.count(text));
+            }
+        }
+    }
+
+    /// Append a tool progress line to the last assistant message's text
+    ///
+    /// Only the plain-text content gets the line; the transcript renders the
+    /// tool parts instead.
+    fn append_tool_line(&mut self, line: &str) {
+        if let Some(msg) = self.messages.last_mut().filter(|m| m.role == "assistant") {
+            msg.content.push_str(line);
         }
     }
 
@@ -457,7 +905,7 @@ impl App {
                 completed_count: 0,
                 call_ids: vec![id.to_string()],
             });
-            self.append_to_assistant(&format!("[Calling tool: {}]\n", name));
+            self.append_tool_line(&format!("[Calling tool: {}]\n", name));
         }
 
         self.add_tool_call(id, name, "");
@@ -486,10 +934,11 @@ impl App {
             }
         } else {
             let status = if is_error { "ERROR" } else { "OK" };
-            let display = Self::extract_display_output(output);
-            self.append_to_assistant(&format!("[Tool {} result: {}] {}\n", id, status, display));
+            let display = Self::extract_display_output(output, self.tool_preview_chars);
+            self.append_tool_line(&format!("[Tool {} result: {}] {}\n", id, status, display));
         }
 
+        self.live_output.retain(|(call_id, _)| call_id != id);
         self.add_tool_result(id, output, is_error);
     }
 
@@ -534,26 +983,109 @@ impl App {
         }
     }
 
+    /// Abort the running turn, cancelling its pending tool calls
+    ///
+    /// Questions the turn asked are dropped too, and their dialog closed.
+    pub fn abort_turn(&mut self) {
+        if let Some(abort) = self.turn_abort.take() {
+            let _ = abort.send(true);
+        }
+        let asked_by_turn = self
+            .dialog
+            .as_ref()
+            .and_then(|dialog| dialog.question_request.as_ref())
+            .is_some_and(|request| !self.restored_questions.iter().any(|r| r.id == request.id));
+        if asked_by_turn {
+            self.close_dialog();
+        }
+        if let Some(session) = &self.session {
+            let session_id = session.id.clone();
+            tokio::spawn(async move {
+                crate::question_state::cancel_waiting(&session_id).await;
+            });
+        }
+        self.is_processing = false;
+        self.status = "Cancelled".to_string();
+        self.clear_tool_batch();
+    }
+
     /// Clear tool batch (call when stream completes or switching tools)
     pub fn clear_tool_batch(&mut self) {
         self.tool_batch = None;
+        self.live_output.clear();
+    }
+
+    /// Add a chunk of live output from a running tool call
+    pub fn push_live_output(&mut self, id: &str, text: &str) {
+        for line in text.lines() {
+            if self.live_output.len() >= LIVE_OUTPUT_LINES {
+                self.live_output.pop_front();
+            }
+            self.live_output
+                .push_back((id.to_string(), line.to_string()));
+        }
     }
 
     /// Extract display text from tool output (JSON title or truncated output)
-    fn extract_display_output(output: &str) -> String {
+    ///
+    /// The text is kept on one line, so hiding tool details can drop it.
+    fn extract_display_output(output: &str, max_chars: usize) -> String {
         // Try to parse as JSON and extract title
         if let Ok(parsed) = serde_json::from_str::<serde_json::Value>(output) {
             if let Some(title) = parsed.get("title").and_then(|v| v.as_str()) {
-                return title.to_string();
+                let title = title.split_whitespace().collect::<Vec<_>>().join(" ");
+                return tool_block::truncate_chars(&title, max_chars);
             }
         }
 
         // Truncate if too long
-        if output.len() > 200 {
-            format!("{}...", &output[..200])
-        } else {
-            output.to_string()
-        }
+        let output = output.split_whitespace().collect::<Vec<_>>().join(" ");
+        tool_block::truncate_chars(&output, max_chars)
+    }
+
+    /// Append text to the session's system prompt (None clears the additions)
+    pub async fn append_system(&mut self, text: Option<String>) -> Result<()> {
+        let Some(session) = self.session.as_mut() else {
+            return Ok(());
+        };
+        let system =
+            text.map(|text| crate::session::system::append(session.system.as_deref(), &text));
+        let project_id = session.project_id.clone();
+        session.set_system(&project_id, system).await
+    }
+
+    /// Replace the session's pinned files
+    pub async fn set_pinned(&mut self, pinned: Vec<String>) -> Result<()> {
+        let Some(session) = self.session.as_mut() else {
+            return Ok(());
+        };
+        let project_id = session.project_id.clone();
+        session.set_pinned(&project_id, pinned).await
+    }
+
+    /// Set one of the session's permission rules, or clear them all
+    ///
+    /// Rules are layered over the config from the next turn on.
+    pub async fn set_session_permission(
+        &mut self,
+        rule: Option<(String, PermissionAction)>,
+    ) -> Result<()> {
+        let Some(session) = self.session.as_mut() else {
+            return Ok(());
+        };
+        let permission = match rule {
+            Some((tool, action)) => {
+                let mut rules = match session.permission.take() {
+                    Some(serde_json::Value::Object(rules)) => rules,
+                    _ => serde_json::Map::new(),
+                };
+                rules.insert(tool, serde_json::to_value(action)?);
+                Some(serde_json::Value::Object(rules))
+            }
+            None => None,
+        };
+        let project_id = session.project_id.clone();
+        session.set_permission(&project_id, permission).await
     }
 
     /// Initialize slash commands
@@ -564,22 +1096,35 @@ impl App {
         let commands: Vec<Arc<dyn SlashCommand>> = vec![
             Arc::new(HelpCommand),
             Arc::new(ClearCommand),
+            Arc::new(ResetCommand),
             Arc::new(ModelCommand),
+            Arc::new(PrefillCommand),
+            Arc::new(SystemCommand),
+            Arc::new(PinCommand),
+            Arc::new(UnpinCommand),
+            Arc::new(PermissionCommand),
             Arc::new(AgentCommand),
             Arc::new(ExitCommand),
             Arc::new(ConnectCommand),
             Arc::new(UndoCommand),
             Arc::new(RedoCommand),
+            Arc::new(RegenerateCommand),
             Arc::new(CompactCommand),
             Arc::new(UnshareCommand),
             Arc::new(RenameCommand),
             Arc::new(CopyCommand),
+            Arc::new(CopyCodeCommand),
             Arc::new(ExportCommand),
+            Arc::new(DiffCommand),
             Arc::new(TimelineCommand),
+            Arc::new(YankContextCommand),
             Arc::new(ForkCommand),
+            Arc::new(BranchesCommand),
             Arc::new(ThinkingCommand),
+            Arc::new(ToggleCommand),
             Arc::new(ShareCommand),
             Arc::new(SessionCommand),
+            Arc::new(SetCommand),
             Arc::new(StatusCommand),
             Arc::new(McpCommand),
             Arc::new(ThemeCommand),
@@ -654,6 +1199,131 @@ mod tests {
             assert!(app.dialog.is_none());
         }
 
+        fn shown(app: &App, idx: usize) -> Option<Vec<String>> {
+            app.displayed_lines(&app.messages[idx])
+                .map(|lines| lines.into_iter().map(|line| line.text).collect())
+        }
+
+        #[test]
+        fn test_display_toggles() {
+            let mut app = App {
+                model_display: "anthropic/claude".to_string(),
+                ..Default::default()
+            };
+            app.add_message("system", "Theme switched");
+            app.add_message("assistant", "Let me look.\n");
+            app.handle_tool_call("call_1", "read");
+            app.set_tool_args("call_1", r#"{"filePath":"a.rs"}"#);
+            app.handle_tool_result_grouped(
+                "call_1",
+                r#"{"title":"a.rs","output":"fn main() {}"}"#,
+                false,
+            );
+            app.append_to_assistant("Done.");
+            app.record_reply_usage(120, 0.5);
+
+            assert_eq!(
+                shown(&app, 1).unwrap(),
+                vec!["Let me look.", "▸ read ✓ a.rs", "Done."]
+            );
+
+            app.toggle_tool_expanded();
+            assert_eq!(
+                shown(&app, 1).unwrap(),
+                vec![
+                    "Let me look.",
+                    "▾ read ✓ a.rs",
+                    "    {",
+                    "      \"filePath\": \"a.rs\"",
+                    "    }",
+                    "    ──",
+                    "    fn main() {}",
+                    "Done.",
+                ]
+            );
+            assert!(app.displayed_lines(&app.messages[1]).unwrap()[1].selected);
+
+            app.toggle_display(DisplayToggle::Tools);
+            app.toggle_display(DisplayToggle::Metadata);
+            app.toggle_display(DisplayToggle::System);
+            assert_eq!(
+                shown(&app, 1).unwrap(),
+                vec![
+                    "Let me look.",
+                    "▸ read ✓",
+                    "Done.",
+                    "· anthropic/claude · 120 tokens · $0.5000",
+                ]
+            );
+            assert!(shown(&app, 0).is_none());
+            assert_eq!(app.status, "System messages hidden");
+        }
+
+        #[test]
+        fn test_reasoning_toggle() {
+            let mut app = App::default();
+            app.add_message("assistant", "");
+            app.append_reasoning("Check the ");
+            app.append_reasoning("config first.");
+            app.append_to_assistant("It is set.");
+
+            assert_eq!(
+                shown(&app, 0).unwrap(),
+                vec!["│ Check the config first.", "It is set."]
+            );
+            assert_eq!(app.messages[0].content, "It is set.");
+
+            app.show_thinking = false;
+            assert_eq!(
+                shown(&app, 0).unwrap(),
+                vec!["reasoning (hidden, 6 tokens)", "It is set."]
+            );
+        }
+
+        #[test]
+        fn test_select_tool() {
+            let mut app = App::default();
+            app.add_message("assistant", "");
+            app.handle_tool_call("call_1", "read");
+            app.add_message("user", "next");
+            app.add_message("assistant", "");
+            app.handle_tool_call("call_2", "bash");
+
+            app.select_tool(-1);
+            assert_eq!(app.selected_tool.as_deref(), Some("call_2"));
+            app.select_tool(-1);
+            assert_eq!(app.selected_tool.as_deref(), Some("call_1"));
+            assert_eq!(app.message_scroll, 2);
+            app.select_tool(-1);
+            assert_eq!(app.selected_tool.as_deref(), Some("call_1"));
+            app.select_tool(1);
+            app.select_tool(1);
+            assert_eq!(app.selected_tool, None);
+            assert_eq!(app.message_scroll, 0);
+        }
+
+        #[test]
+        fn test_history_search_recalls_prompt() {
+            let mut app = App::default();
+            app.add_input_to_history("fix the build");
+            app.add_input_to_history("write tests\nfor the parser");
+            app.add_input_to_history("explain this code");
+
+            app.open_history_search();
+            let dialog = app.dialog.as_mut().unwrap();
+            assert_eq!(dialog.dialog_type, DialogType::HistorySearch);
+            assert_eq!(dialog.items[0].label, "explain this code");
+
+            dialog.search_query = "tsts".to_string();
+            dialog.update_filter();
+            let entry = dialog.selected_item().unwrap().id.clone();
+            assert_eq!(entry, "write tests\nfor the parser");
+
+            app.recall_history(entry);
+            assert_eq!(app.input, "write tests\nfor the parser");
+            assert_eq!(app.cursor_position, app.input.len());
+        }
+
         #[test]
         fn test_hide_autocomplete() {
             let mut app = App {
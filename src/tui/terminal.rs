@@ -0,0 +1,115 @@
+//! Terminal setup and teardown.
+//!
+//! The TUI puts the terminal in raw mode on the alternate screen. Teardown
+//! runs on normal exit, on panic (through a panic hook that also writes a crash
+//! report) and on SIGINT/SIGTERM, so the shell is not left in a broken state.
+
+use super::crash;
+use anyhow::Result;
+use crossterm::{
+    cursor::Show,
+    event::{DisableFocusChange, DisableMouseCapture, EnableFocusChange, EnableMouseCapture},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use std::backtrace::Backtrace;
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Whether the TUI captures the mouse, set by `setup`
+static MOUSE_CAPTURE: AtomicBool = AtomicBool::new(false);
+
+/// Enter raw mode and the alternate screen, and install the restore handlers
+///
+/// Capturing the mouse enables wheel scrolling and clicks, but takes text
+/// selection away from the terminal, so it is opt-in (`tui.mouse`).
+pub fn setup(mouse_capture: bool) -> Result<()> {
+    install_panic_hook();
+    spawn_signal_handler();
+    MOUSE_CAPTURE.store(mouse_capture, Ordering::Relaxed);
+    resume()
+}
+
+/// Re-enter raw mode and the alternate screen after `restore`
+///
+/// Used when the TUI hands the terminal to another program, such as the
+/// external editor.
+pub fn resume() -> Result<()> {
+    enable_raw_mode()?;
+    execute!(io::stdout(), EnterAlternateScreen, EnableFocusChange)?;
+    if MOUSE_CAPTURE.load(Ordering::Relaxed) {
+        execute!(io::stdout(), EnableMouseCapture)?;
+    }
+    Ok(())
+}
+
+/// Return the terminal to its normal state
+///
+/// Safe to call more than once; errors are ignored since this also runs while
+/// the process is going down.
+pub fn restore() {
+    let _ = disable_raw_mode();
+    let _ = execute!(
+        io::stdout(),
+        LeaveAlternateScreen,
+        DisableMouseCapture,
+        DisableFocusChange,
+        Show
+    );
+}
+
+/// Write a crash report when a panic occurs, restoring the terminal first if
+/// the panic takes down the TUI
+///
+/// Only a panic on the thread that set up the terminal ends the TUI. One in a
+/// task on another thread fails just that task while the TUI keeps running,
+/// so the terminal is left as it is and only the report is written.
+fn install_panic_hook() {
+    let crash_dir = crash::crash_dir();
+    let tui_thread = std::thread::current().id();
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let backtrace = Backtrace::force_capture();
+        let report = crash::write_report(&crash_dir, &info.to_string(), &backtrace);
+        if std::thread::current().id() != tui_thread {
+            return;
+        }
+
+        restore();
+        previous(info);
+        match report {
+            Ok(path) => eprintln!("Crash report written to {}", path.display()),
+            Err(e) => eprintln!("Failed to write crash report: {}", e),
+        }
+    }));
+}
+
+/// Restore the terminal and exit when the process is interrupted or terminated
+fn spawn_signal_handler() {
+    tokio::spawn(async {
+        let code = wait_for_signal().await;
+        restore();
+        std::process::exit(code);
+    });
+}
+
+/// Wait for SIGINT or SIGTERM, returning the conventional exit code
+#[cfg(unix)]
+async fn wait_for_signal() -> i32 {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let Ok(mut terminate) = signal(SignalKind::terminate()) else {
+        let _ = tokio::signal::ctrl_c().await;
+        return 130;
+    };
+    tokio::select! {
+        _ = tokio::signal::ctrl_c() => 130,
+        _ = terminate.recv() => 143,
+    }
+}
+
+#[cfg(not(unix))]
+async fn wait_for_signal() -> i32 {
+    let _ = tokio::signal::ctrl_c().await;
+    130
+}
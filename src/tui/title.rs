@@ -0,0 +1,98 @@
+//! Automatic titles for new sessions.
+//!
+//! When a turn completes in a session still named after its creation time, a
+//! title for its first exchange is generated in the background; the TUI stays
+//! usable meanwhile and picks the title up as an event.
+
+use tokio::sync::mpsc;
+
+use super::llm_streaming::generate_title;
+use super::state::App;
+use super::types::AppEvent;
+
+impl App {
+    /// Start generating a title for the current session if it still has its
+    /// default one
+    ///
+    /// A session gets one attempt per run, so a failing model is not asked
+    /// again after every turn.
+    pub fn request_auto_title(&mut self, event_tx: &mpsc::Sender<AppEvent>) {
+        if !self.auto_title {
+            return;
+        }
+        let Some(session) = self.session.as_ref().filter(|s| s.is_default_title()) else {
+            return;
+        };
+        if self.auto_title_session.as_deref() == Some(session.id.as_str()) {
+            return;
+        }
+        let Some(prompt_idx) = self.messages.iter().position(|m| m.role == "user") else {
+            return;
+        };
+        let Some(reply) = self.messages[prompt_idx + 1..]
+            .iter()
+            .find(|m| m.role == "assistant" && !m.content.trim().is_empty())
+        else {
+            return;
+        };
+
+        let session_id = session.id.clone();
+        self.auto_title_session = Some(session_id.clone());
+        let (provider_id, model_id) = (self.provider_id.clone(), self.model_id.clone());
+        let prompt = self.messages[prompt_idx].content.clone();
+        let reply = reply.content.clone();
+        let tx = event_tx.clone();
+        tokio::spawn(async move {
+            match generate_title(&provider_id, &model_id, &prompt, &reply).await {
+                Ok(generated) => {
+                    if generated.title.is_none() {
+                        tracing::warn!("Failed to generate a session title: no title in reply");
+                    }
+                    let _ = tx
+                        .send(AppEvent::SessionTitled {
+                            session_id,
+                            title: generated.title,
+                            tokens: generated.tokens,
+                            cost: generated.cost,
+                        })
+                        .await;
+                }
+                Err(e) => tracing::warn!("Failed to generate a session title: {:#}", e),
+            }
+        });
+    }
+
+    /// Count the usage of a title request in the session totals and save the
+    /// title, unless the session was renamed or left meanwhile
+    pub async fn apply_auto_title(
+        &mut self,
+        session_id: &str,
+        title: Option<String>,
+        tokens: u64,
+        cost: f64,
+    ) {
+        if self.session.as_ref().is_some_and(|s| s.id == session_id) {
+            self.total_tokens += tokens;
+            self.total_cost += cost;
+        }
+        let Some(title) = title else {
+            return;
+        };
+        let Some(session) = self
+            .session
+            .as_mut()
+            .filter(|s| s.id == session_id && s.is_default_title())
+        else {
+            return;
+        };
+        let project_id = session.project_id.clone();
+        if let Err(e) = session
+            .update(&project_id, |s| s.title = title.clone())
+            .await
+        {
+            tracing::warn!("Failed to save the session title: {}", e);
+            return;
+        }
+        self.session_title = title;
+    }
+}
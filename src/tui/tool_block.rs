@@ -0,0 +1,185 @@
+//! Collapsible tool call blocks in the transcript.
+//!
+//! A tool call renders as one summary line with the tool name, status and
+//! title. Expanding it shows the full arguments and output below that line.
+
+use super::types::MessagePart;
+
+/// Default max characters of the title on a summary line
+pub const DEFAULT_PREVIEW_CHARS: usize = 80;
+
+/// Indent of the arguments and output of an expanded call
+const DETAIL_INDENT: &str = "    ";
+
+/// Find the result part of a tool call: its output and error flag
+pub fn find_result<'a>(parts: &'a [MessagePart], call_id: &str) -> Option<(&'a str, bool)> {
+    parts.iter().find_map(|part| match part {
+        MessagePart::ToolResult {
+            id,
+            output,
+            is_error,
+        } if id == call_id => Some((output.as_str(), *is_error)),
+        _ => None,
+    })
+}
+
+/// Summary line of a tool call, e.g. `▸ bash ✓ List files`
+///
+/// The title is cut to `title_chars` characters and left out when
+/// `with_title` is false.
+pub fn summary(
+    name: &str,
+    result: Option<(&str, bool)>,
+    expanded: bool,
+    with_title: bool,
+    title_chars: usize,
+) -> String {
+    let marker = if expanded { "▾" } else { "▸" };
+    let status = match result {
+        None => "…",
+        Some((_, false)) => "✓",
+        Some((_, true)) => "✗",
+    };
+    let mut line = format!("{} {} {}", marker, name, status);
+
+    if let Some(title) = result
+        .filter(|_| with_title)
+        .and_then(|(output, _)| title(output))
+    {
+        line.push(' ');
+        line.push_str(&truncate_chars(&title, title_chars));
+    }
+    line
+}
+
+/// Full arguments and output of an expanded tool call, one entry per line
+pub fn details(args: &str, result: Option<(&str, bool)>) -> Vec<String> {
+    let args = match serde_json::from_str::<serde_json::Value>(args) {
+        Ok(value) => serde_json::to_string_pretty(&value).unwrap_or_else(|_| args.to_string()),
+        Err(_) => args.to_string(),
+    };
+    let mut lines: Vec<String> = args
+        .lines()
+        .map(|line| format!("{}{}", DETAIL_INDENT, line))
+        .collect();
+
+    if let Some((output, _)) = result {
+        lines.push(format!("{}──", DETAIL_INDENT));
+        lines.extend(
+            body(output)
+                .lines()
+                .map(|line| format!("{}{}", DETAIL_INDENT, line)),
+        );
+    }
+    lines
+}
+
+/// Title of a tool result: its JSON `title`, else its first non-empty line
+fn title(output: &str) -> Option<String> {
+    let title = match serde_json::from_str::<serde_json::Value>(output) {
+        Ok(value) => value.get("title")?.as_str()?.to_string(),
+        Err(_) => output
+            .lines()
+            .find(|line| !line.trim().is_empty())?
+            .to_string(),
+    };
+    Some(title.split_whitespace().collect::<Vec<_>>().join(" "))
+}
+
+/// Cut text to at most `max_chars` characters, marking the cut with `...`
+///
+/// Cuts on a char boundary, so multi-byte text never splits mid-character.
+pub fn truncate_chars(text: &str, max_chars: usize) -> String {
+    match text.char_indices().nth(max_chars) {
+        Some((end, _)) => format!("{}...", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+/// Body of a tool result: its JSON `output` or `error`, else the raw text
+fn body(output: &str) -> String {
+    serde_json::from_str::<serde_json::Value>(output)
+        .ok()
+        .and_then(|value| {
+            ["output", "error"]
+                .into_iter()
+                .find_map(|key| value.get(key)?.as_str().map(String::from))
+        })
+        .unwrap_or_else(|| output.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summary() {
+        let result = r#"{"title":"List files","output":"a\nb"}"#;
+        assert_eq!(summary("bash", None, false, true, 80), "▸ bash …");
+        assert_eq!(
+            summary("bash", Some((result, false)), false, true, 80),
+            "▸ bash ✓ List files"
+        );
+        assert_eq!(
+            summary("bash", Some((result, true)), true, false, 80),
+            "▾ bash ✗"
+        );
+        assert_eq!(
+            summary(
+                "read",
+                Some(("\nplain output\nmore", false)),
+                false,
+                true,
+                80
+            ),
+            "▸ read ✓ plain output"
+        );
+        assert_eq!(
+            summary("bash", Some((result, false)), false, true, 4),
+            "▸ bash ✓ List..."
+        );
+    }
+
+    #[test]
+    fn test_truncate_chars() {
+        assert_eq!(truncate_chars("short", 10), "short");
+        assert_eq!(truncate_chars("héllo wörld", 4), "héll...");
+        assert_eq!(truncate_chars("日本語テキスト", 3), "日本語...");
+    }
+
+    #[test]
+    fn test_details() {
+        let result = r#"{"title":"List files","output":"a\nb"}"#;
+        assert_eq!(
+            details(r#"{"command":"ls"}"#, Some((result, false))),
+            vec![
+                "    {",
+                "      \"command\": \"ls\"",
+                "    }",
+                "    ──",
+                "    a",
+                "    b",
+            ]
+        );
+        assert_eq!(details("", None), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_find_result() {
+        let parts = vec![
+            MessagePart::ToolCall {
+                id: "call_1".to_string(),
+                name: "bash".to_string(),
+                args: String::new(),
+                expanded: false,
+            },
+            MessagePart::ToolResult {
+                id: "call_1".to_string(),
+                output: "done".to_string(),
+                is_error: true,
+            },
+        ];
+        assert_eq!(find_result(&parts, "call_1"), Some(("done", true)));
+        assert_eq!(find_result(&parts, "call_2"), None);
+    }
+}
@@ -75,7 +75,8 @@ fn format_message_content(
             MessagePart::Text { text } => {
                 content.push_str(text);
             }
-            MessagePart::ToolCall { name, args } => {
+            MessagePart::Reasoning { .. } => {}
+            MessagePart::ToolCall { name, args, .. } => {
                 if options.include_tool_details {
                     content.push_str(&format!(
                         "\n**Tool Call: {}**\n```json\n{}\n```\n",
@@ -83,7 +84,9 @@ fn format_message_content(
                     ));
                 }
             }
-            MessagePart::ToolResult { output, is_error } => {
+            MessagePart::ToolResult {
+                output, is_error, ..
+            } => {
                 if options.include_tool_details {
                     let status = if *is_error { "Error" } else { "Success" };
                     content.push_str(&format!(
@@ -92,6 +95,9 @@ fn format_message_content(
                     ));
                 }
             }
+            MessagePart::Image { mime } => {
+                content.push_str(&format!("\n[Image: {}]\n", mime));
+            }
         }
     }
 
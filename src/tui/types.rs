@@ -8,14 +8,88 @@ pub struct DisplayMessage {
     pub role: String,
     pub content: String,
     pub parts: Vec<MessagePart>,
+    /// Model and usage of an assistant reply
+    pub metadata: Option<ReplyMetadata>,
+    /// Every variant of a regenerated reply, empty for a single reply
+    ///
+    /// The shown variant lives in the fields above; its entry here is only
+    /// refreshed when switching away from it.
+    pub variants: Vec<ReplyVariant>,
+    /// Index of the shown variant
+    pub variant: usize,
 }
 
-/// Message part - can be text, tool call, or tool result
+/// One reply to a prompt, kept while another variant of the turn is shown
+#[derive(Debug, Clone, Default)]
+pub struct ReplyVariant {
+    pub content: String,
+    pub parts: Vec<MessagePart>,
+    pub metadata: Option<ReplyMetadata>,
+}
+
+/// Model, token usage and cost of an assistant reply
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ReplyMetadata {
+    pub model: String,
+    pub tokens: u64,
+    pub cost: f64,
+}
+
+impl std::fmt::Display for ReplyMetadata {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} · {} tokens · ${:.4}",
+            self.model, self.tokens, self.cost
+        )
+    }
+}
+
+/// Message part - can be text, reasoning, tool call, or tool result
 #[derive(Debug, Clone)]
 pub enum MessagePart {
-    Text { text: String },
-    ToolCall { name: String, args: String },
-    ToolResult { output: String, is_error: bool },
+    Text {
+        text: String,
+    },
+    /// The model's reasoning (thinking), shown or hidden with `/thinking`
+    Reasoning {
+        text: String,
+        /// Token count, taken once the reasoning is complete
+        tokens: Option<usize>,
+    },
+    ToolCall {
+        id: String,
+        name: String,
+        args: String,
+        /// Whether the transcript shows the full arguments and output
+        expanded: bool,
+    },
+    ToolResult {
+        id: String,
+        output: String,
+        is_error: bool,
+    },
+    /// An image in the conversation, e.g. returned by a tool call
+    Image {
+        mime: String,
+    },
+}
+
+/// A line of a message as shown in the transcript
+#[derive(Debug, Clone, PartialEq)]
+pub struct DisplayLine {
+    pub text: String,
+    /// Whether the line is the summary of the selected tool call
+    pub selected: bool,
+}
+
+impl DisplayLine {
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            selected: false,
+        }
+    }
 }
 
 /// Active dialog type
@@ -33,6 +107,34 @@ pub enum DialogType {
     Timeline,
     AgentSelector,
     Question,
+    DiffList,
+    DiffView,
+    CodeBlockList,
+    BranchList,
+    HistorySearch,
+    StarterConfig,
+    DraftRecovery,
+}
+
+impl DialogType {
+    /// Whether the dialog is rendered as a searchable list of items
+    pub fn uses_select_list(&self) -> bool {
+        matches!(
+            self,
+            DialogType::ModelSelector
+                | DialogType::ProviderSelector
+                | DialogType::AuthMethodSelector
+                | DialogType::SessionList
+                | DialogType::Timeline
+                | DialogType::AgentSelector
+                | DialogType::DiffList
+                | DialogType::CodeBlockList
+                | DialogType::BranchList
+                | DialogType::HistorySearch
+                | DialogType::StarterConfig
+                | DialogType::DraftRecovery
+        )
+    }
 }
 
 /// Autocomplete state for slash commands
@@ -99,7 +201,11 @@ pub struct PermissionRequest {
     pub id: String,
     pub permission: String,
     pub patterns: Vec<String>,
+    /// Patterns remembered when allowed for a session/workspace/globally
+    pub always: Vec<String>,
     pub metadata: std::collections::HashMap<String, serde_json::Value>,
+    /// Tool calls covered by a batch request; empty for single requests
+    pub calls: Vec<crate::tool::BatchCall>,
 }
 
 // Re-export question types from tool module to avoid duplication
@@ -135,6 +241,14 @@ pub struct DialogState {
     pub custom_answer_input: String,
     /// Whether we're editing custom answer
     pub is_editing_custom: bool,
+    /// File diffs for the diff viewer
+    pub file_diffs: Vec<crate::session::FileDiff>,
+    /// Scroll offset (in lines) of the diff view
+    pub diff_scroll: usize,
+    /// Clipboard text for each entry of the code block picker
+    pub code_blocks: Vec<String>,
+    /// Indices into `items` of marked entries (timeline multi-select)
+    pub marked: Vec<usize>,
 }
 
 impl DialogState {
@@ -158,6 +272,10 @@ impl DialogState {
             current_option_index: 0,
             custom_answer_input: String::new(),
             is_editing_custom: false,
+            file_diffs: Vec::new(),
+            diff_scroll: 0,
+            code_blocks: Vec::new(),
+            marked: Vec::new(),
         }
     }
 
@@ -212,7 +330,7 @@ impl DialogState {
                 .collect();
 
             // Sort by score (descending)
-            scored_items.sort_by(|a, b| b.1.cmp(&a.1));
+            scored_items.sort_by_key(|item| std::cmp::Reverse(item.1));
 
             self.filtered_indices = scored_items.into_iter().map(|(idx, _)| idx).collect();
         }
@@ -231,6 +349,19 @@ impl DialogState {
         }
     }
 
+    /// Mark or unmark the selected item
+    pub fn toggle_mark(&mut self) {
+        let Some(&index) = self.filtered_indices.get(self.selected_index) else {
+            return;
+        };
+        match self.marked.iter().position(|&i| i == index) {
+            Some(pos) => {
+                self.marked.remove(pos);
+            }
+            None => self.marked.push(index),
+        }
+    }
+
     pub fn move_down(&mut self) {
         if self.selected_index + 1 < self.filtered_indices.len() {
             self.selected_index += 1;
@@ -247,30 +378,84 @@ impl DialogState {
     /// Move right in permission options
     pub fn move_permission_right(&mut self) {
         // 0=Once, 1=Session, 2=Workspace, 3=Global, 4=Reject
-        if self.selected_permission_option < 4 {
+        // Batch requests: 0=Approve all, 1=Decide each, 2=Deny all
+        let last = if self.is_batch_permission() { 2 } else { 4 };
+        if self.selected_permission_option < last {
             self.selected_permission_option += 1;
         }
     }
+
+    /// Whether the permission request covers a batch of tool calls
+    pub fn is_batch_permission(&self) -> bool {
+        self.permission_request
+            .as_ref()
+            .is_some_and(|request| !request.calls.is_empty())
+    }
 }
 
 /// Application events for the TUI event loop
 #[derive(Debug)]
 pub enum AppEvent {
     StreamDelta(String),
+    /// Reasoning (thinking) text streamed before or between replies
+    ReasoningDelta(String),
     StreamDone,
     StreamError(String),
+    /// A title was requested for a session that still had its default one,
+    /// with the usage of the request; `title` is `None` if the reply held none
+    SessionTitled {
+        session_id: String,
+        title: Option<String>,
+        tokens: u64,
+        cost: f64,
+    },
+    /// An agentic step finished, with its token usage and cost
+    StepFinished {
+        tokens: u64,
+        cost: f64,
+    },
+    /// The model was overloaded and the turn continues on a fallback model
+    ModelFallback {
+        provider_id: String,
+        model_id: String,
+        reason: String,
+    },
+    /// The provider rejected the credentials
+    AuthError {
+        provider_id: String,
+        message: String,
+    },
     ToolCall(String, String),
+    /// Arguments of a tool call, once fully streamed
+    ToolInput {
+        id: String,
+        args: String,
+    },
+    /// A chunk of live output from a running tool call
+    ToolOutput {
+        id: String,
+        text: String,
+    },
     ToolResult {
         id: String,
         output: String,
         is_error: bool,
     },
+    /// An image a tool call returned to the model
+    ToolImage {
+        mime: String,
+    },
     PermissionRequested(PermissionRequest),
     PermissionResponse {
         id: String,
         allow: bool,
         scope: crate::tool::PermissionScope,
     },
+    /// Decision on a batch of tool calls
+    BatchPermissionResponse {
+        id: String,
+        decision: crate::tool::BatchDecision,
+    },
     QuestionRequested(QuestionRequest),
     QuestionReplied {
         id: String,
@@ -492,5 +677,27 @@ mod tests {
 
             assert!(dialog.filtered_indices.is_empty());
         }
+
+        #[test]
+        fn test_toggle_mark() {
+            let items = create_items();
+            let mut dialog =
+                DialogState::new(DialogType::Timeline, "Message Timeline").with_items(items);
+
+            dialog.move_down();
+            dialog.toggle_mark();
+            assert_eq!(dialog.marked, vec![1]);
+
+            // Marks refer to items, not filtered positions
+            dialog.filtered_indices = vec![2];
+            dialog.selected_index = 0;
+            dialog.toggle_mark();
+            assert_eq!(dialog.marked, vec![1, 2]);
+
+            dialog.update_filter();
+            dialog.move_down();
+            dialog.toggle_mark();
+            assert_eq!(dialog.marked, vec![2]);
+        }
     }
 }
@@ -10,6 +10,9 @@ use ratatui::{
 use super::app::{App, AutocompleteState};
 use super::components::{Header, InputBox, MessageWidget, StatusBar, SPINNER_FRAMES};
 use super::dialog_render::render_dialog;
+use super::keymap::KeyAction;
+use super::types::DisplayLine;
+use super::wrap;
 
 /// Main UI rendering function
 pub fn render(frame: &mut Frame, app: &App) {
@@ -20,12 +23,20 @@ pub fn render(frame: &mut Frame, app: &App) {
     let input_lines = app.input.lines().count().max(1);
     let input_height = (input_lines as u16 + 1).clamp(2, 10);
 
-    // Main layout: Header, Messages, Input, Status
+    // Live tool output gets a bordered pane while a tool is running
+    let live_height = if app.live_output.is_empty() || !app.show_tool_details {
+        0
+    } else {
+        app.live_output.len() as u16 + 1
+    };
+
+    // Main layout: Header, Messages, Live output, Input, Status
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
             Constraint::Length(1),            // Header
             Constraint::Min(10),              // Messages
+            Constraint::Length(live_height),  // Live tool output
             Constraint::Length(input_height), // Input (dynamic)
             Constraint::Length(1),            // Status bar
         ])
@@ -36,27 +47,47 @@ pub fn render(frame: &mut Frame, app: &App) {
         title: &app.session_title,
         model: &app.model_display,
         status: &app.status,
+        busy: app.is_processing,
         theme,
     };
     frame.render_widget(header, chunks[0]);
 
     // Render messages
     render_messages(frame, app, chunks[1]);
+    if live_height > 0 {
+        render_live_output(frame, app, chunks[2]);
+    }
 
     // Render input
+    let submit_key = app.key_hint(KeyAction::InputSubmit);
+    let placeholder = format!(
+        "Type a message... ({} to send, {} for newline)",
+        submit_key,
+        app.key_hint(KeyAction::InputNewline)
+    );
     let input = InputBox {
         content: &app.input,
-        placeholder: "Type a message... (Enter to send, Shift+Enter for newline)",
+        placeholder: &placeholder,
         theme,
     };
-    frame.render_widget(input, chunks[2]);
+    frame.render_widget(input, chunks[3]);
 
     // Render status bar
-    let left = format!("Session: {}", app.session_slug);
+    let mut left = format!("Session: {}", app.session_slug);
+    let pinned = app.session.as_ref().map(|s| s.pinned.as_slice());
+    if let Some(pinned) = pinned.filter(|pinned| !pinned.is_empty()) {
+        let names: Vec<&str> = pinned
+            .iter()
+            .map(|path| path.rsplit('/').next().unwrap_or(path))
+            .collect();
+        left.push_str(&format!(" | Pinned: {}", names.join(", ")));
+    }
     let center = if app.is_processing {
-        "Processing...".to_string()
+        app.turn_phase_label()
+    } else if app.multiline_input {
+        format!("Multiline | {} to send", submit_key)
     } else {
-        "Ready".to_string()
+        format!("{} to send", submit_key)
     };
     let right = format!(
         "Cost: ${:.4} | Tokens: {}",
@@ -69,7 +100,7 @@ pub fn render(frame: &mut Frame, app: &App) {
         right: &right,
         theme,
     };
-    frame.render_widget(status, chunks[3]);
+    frame.render_widget(status, chunks[4]);
 
     // Render dialog if open
     if let Some(dialog) = &app.dialog {
@@ -78,18 +109,27 @@ pub fn render(frame: &mut Frame, app: &App) {
 
     // Render autocomplete if open
     if let Some(autocomplete) = &app.autocomplete {
-        render_autocomplete(frame, autocomplete, theme, chunks[2]);
+        render_autocomplete(frame, autocomplete, theme, chunks[3]);
     }
 }
 
-/// Calculate visible line count for message content
-fn calculate_message_height(content: &str) -> u16 {
-    content
-        .trim()
-        .lines()
-        .filter(|line| !line.trim().is_empty())
-        .count()
-        .max(1) as u16
+/// Render the latest lines of output from running tools
+fn render_live_output(frame: &mut Frame, app: &App, area: Rect) {
+    let lines: Vec<&str> = app
+        .live_output
+        .iter()
+        .map(|(_, line)| line.as_str())
+        .collect();
+    frame.render_widget(
+        Paragraph::new(lines.join("\n"))
+            .style(app.theme.text_dim())
+            .block(
+                Block::default()
+                    .borders(Borders::TOP)
+                    .title(" Tool output "),
+            ),
+        area,
+    );
 }
 
 /// Render messages area
@@ -99,36 +139,52 @@ fn render_messages(frame: &mut Frame, app: &App, area: Rect) {
         return;
     }
 
-    let messages = &app.messages;
-    let mut visible_messages: Vec<(&str, &str, u16, bool)> = Vec::new();
+    // Messages scrolled past stay hidden below the visible area
+    let end = app.messages.len().saturating_sub(app.message_scroll);
+    // Apply the display toggles (hidden system messages, tool details, metadata)
+    let messages: Vec<(&str, Vec<DisplayLine>, bool)> = app.messages[..end]
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, msg)| {
+            let focused = app.focused_message == Some(idx);
+            Some((msg.role.as_str(), app.displayed_lines(msg)?, focused))
+        })
+        .collect();
+    let mut visible_messages: Vec<(Vec<DisplayLine>, &str, u16, bool, bool)> = Vec::new();
     let mut total_height = 0u16;
 
-    // Collect messages from newest to oldest until we fill the area
-    for (idx, msg) in messages.iter().enumerate().rev() {
-        let msg_height = calculate_message_height(&msg.content);
-        let needs_separator = idx + 1 < messages.len() && msg.role != messages[idx + 1].role;
+    // Collect messages from newest to oldest until we fill the area, measuring
+    // each in rows wrapped at the current width
+    for (idx, (role, lines, focused)) in messages.iter().enumerate().rev() {
+        let rows = wrap::wrap_lines(lines, area.width);
+        let msg_height = rows.len().max(1) as u16;
+        let needs_separator = idx + 1 < messages.len() && *role != messages[idx + 1].0;
         let separator_height = if needs_separator { 1 } else { 0 };
         let item_height = msg_height + separator_height;
 
-        if total_height + item_height > area.height {
+        // The newest message is shown even when taller than the area (e.g. an
+        // expanded tool call), cut off at the bottom
+        if total_height + item_height > area.height && !visible_messages.is_empty() {
             break;
         }
+        let msg_height = msg_height.min(area.height - total_height);
 
         total_height += item_height;
-        visible_messages.push((&msg.content, &msg.role, msg_height, needs_separator));
+        visible_messages.push((rows, *role, msg_height, needs_separator, *focused));
     }
 
     // Render from oldest to newest (top to bottom)
     visible_messages.reverse();
 
     let mut current_y = area.y;
-    for (content, role, msg_height, needs_separator) in visible_messages {
+    for (lines, role, msg_height, needs_separator, focused) in visible_messages {
         let msg_area = Rect::new(area.x, current_y, area.width, msg_height);
         frame.render_widget(
             MessageWidget {
                 role,
-                content,
+                lines: &lines,
                 theme: &app.theme,
+                focused,
             },
             msg_area,
         );
@@ -147,7 +203,7 @@ fn render_messages(frame: &mut Frame, app: &App, area: Rect) {
     if app.is_processing && current_y < area.y + area.height {
         let spinner_area = Rect::new(area.x, current_y, area.width, 1);
         let spinner_char = SPINNER_FRAMES[app.spinner_frame % SPINNER_FRAMES.len()];
-        let spinner = Paragraph::new(format!(" {} Thinking...", spinner_char))
+        let spinner = Paragraph::new(format!(" {} {}", spinner_char, app.turn_phase_label()))
             .style(app.theme.text_accent().add_modifier(Modifier::BOLD));
         frame.render_widget(spinner, spinner_area);
     }
@@ -159,10 +215,14 @@ fn render_welcome_message(frame: &mut Frame, app: &App, area: Rect) {
         "Welcome to opencode!\n\n\
          Model: {}\n\n\
          Tips:\n\
-         • Type your message and press Enter to send\n\
-         • Use Shift+Enter for multi-line input\n\
-         • Press Ctrl+C to quit",
-        app.model_display
+         • Type your message and press {} to send\n\
+         • Use {} for multi-line input ({} toggles)\n\
+         • Press {} to quit",
+        app.model_display,
+        app.key_hint(KeyAction::InputSubmit),
+        app.key_hint(KeyAction::InputNewline),
+        app.key_hint(KeyAction::InputModeToggle),
+        app.key_hint(KeyAction::AppExit)
     );
     frame.render_widget(
         Paragraph::new(welcome)
@@ -0,0 +1,164 @@
+//! Variants of an assistant reply.
+//!
+//! `/regenerate` answers the last prompt again, optionally with another model
+//! or temperature, and keeps every reply as a variant of the turn. The
+//! transcript shows one variant at a time; `variant_prev` and `variant_next`
+//! cycle through them.
+
+use super::state::App;
+use super::types::{DisplayMessage, ReplyMetadata, ReplyVariant};
+
+impl DisplayMessage {
+    /// Number of variants of the reply
+    pub fn variant_count(&self) -> usize {
+        self.variants.len().max(1)
+    }
+
+    /// Position of the shown variant, e.g. `variant 2/3`, for replies with
+    /// more than one
+    pub fn variant_label(&self) -> Option<String> {
+        let count = self.variant_count();
+        (count > 1).then(|| format!("variant {}/{}", self.variant + 1, count))
+    }
+
+    /// Add a variant and show it
+    pub fn push_variant(&mut self, variant: ReplyVariant) {
+        self.store_shown();
+        self.variants.push(variant.clone());
+        self.variant = self.variants.len() - 1;
+        self.show(variant);
+    }
+
+    /// Show the variant `step` places away, wrapping around
+    ///
+    /// Returns false when the reply has a single variant.
+    pub fn cycle_variant(&mut self, step: isize) -> bool {
+        let count = self.variant_count() as isize;
+        if count < 2 {
+            return false;
+        }
+        self.store_shown();
+        self.variant = (self.variant as isize + step).rem_euclid(count) as usize;
+        self.show(self.variants[self.variant].clone());
+        true
+    }
+
+    /// The shown reply as a variant
+    pub fn to_variant(&self) -> ReplyVariant {
+        ReplyVariant {
+            content: self.content.clone(),
+            parts: self.parts.clone(),
+            metadata: self.metadata.clone(),
+        }
+    }
+
+    /// Save the shown reply into its variant slot
+    fn store_shown(&mut self) {
+        let shown = self.to_variant();
+        match self.variants.get_mut(self.variant) {
+            Some(slot) => *slot = shown,
+            None => self.variants.push(shown),
+        }
+    }
+
+    fn show(&mut self, variant: ReplyVariant) {
+        self.content = variant.content;
+        self.parts = variant.parts;
+        self.metadata = variant.metadata;
+    }
+}
+
+/// Variant methods for App
+impl App {
+    /// Start an empty variant of the last reply for a regenerated answer
+    ///
+    /// The reply moves to the end of the transcript, below any later system
+    /// messages, so the streamed answer lands in it. Returns false when there
+    /// is no reply to the last prompt.
+    pub fn start_reply_variant(&mut self, model: &str) -> bool {
+        let Some(index) = self.messages.iter().rposition(|m| m.role != "system") else {
+            return false;
+        };
+        if self.messages[index].role != "assistant" {
+            return false;
+        }
+
+        let mut reply = self.messages.remove(index);
+        reply.push_variant(ReplyVariant {
+            metadata: Some(ReplyMetadata {
+                model: model.to_string(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        });
+        self.messages.push(reply);
+        self.message_scroll = 0;
+        true
+    }
+
+    /// Show another variant of the last reply
+    pub fn cycle_reply_variant(&mut self, step: isize) {
+        if self.is_processing {
+            self.status = "Wait for the reply to finish".to_string();
+            return;
+        }
+        let Some(reply) = self.messages.iter_mut().rfind(|m| m.role == "assistant") else {
+            return;
+        };
+        if reply.cycle_variant(step) {
+            self.status = format!("Showing {}", reply.variant_label().unwrap_or_default());
+        } else {
+            self.status = "The last reply has no other variants; use /regenerate".to_string();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reply(text: &str) -> DisplayMessage {
+        DisplayMessage {
+            role: "assistant".to_string(),
+            content: text.to_string(),
+            parts: Vec::new(),
+            metadata: None,
+            variants: Vec::new(),
+            variant: 0,
+        }
+    }
+
+    #[test]
+    fn test_cycle_variants() {
+        let mut message = reply("first");
+        assert!(!message.cycle_variant(1));
+        assert_eq!(message.variant_label(), None);
+
+        message.push_variant(reply("second").to_variant());
+        assert_eq!(message.content, "second");
+        assert_eq!(message.variant_label().as_deref(), Some("variant 2/2"));
+
+        // Text streamed into the shown variant is kept when switching away
+        message.content.push_str(" (done)");
+        assert!(message.cycle_variant(1));
+        assert_eq!(message.content, "first");
+        assert!(message.cycle_variant(-1));
+        assert_eq!(message.content, "second (done)");
+        assert_eq!(message.variant_count(), 2);
+    }
+
+    #[test]
+    fn test_start_reply_variant_moves_reply_last() {
+        let mut app = App::default();
+        app.add_message("user", "question");
+        app.messages.push(reply("answer"));
+        app.add_message("system", "note");
+
+        assert!(app.start_reply_variant("openai/gpt-4o"));
+        let last = app.messages.last().unwrap();
+        assert_eq!(last.role, "assistant");
+        assert_eq!(last.content, "");
+        assert_eq!(last.variant_count(), 2);
+        assert_eq!(app.messages[1].role, "system");
+    }
+}
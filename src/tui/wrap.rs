@@ -0,0 +1,104 @@
+//! Wrapping of transcript lines to the terminal width.
+//!
+//! Messages are wrapped into rows when they are rendered, at the width of the
+//! frame being drawn, so their heights always match what is on screen and the
+//! transcript reflows when the terminal is resized.
+
+use unicode_width::UnicodeWidthChar;
+
+use super::types::DisplayLine;
+
+/// Columns of padding around each row: one on either side
+pub const ROW_PADDING: usize = 2;
+
+/// Wrap message lines into rows that fit `width` columns with padding
+pub fn wrap_lines(lines: &[DisplayLine], width: u16) -> Vec<DisplayLine> {
+    let width = (width as usize).saturating_sub(ROW_PADDING);
+    lines
+        .iter()
+        .flat_map(|line| {
+            wrap(&line.text, width).into_iter().map(|text| DisplayLine {
+                text,
+                selected: line.selected,
+            })
+        })
+        .collect()
+}
+
+/// Split a line into rows of at most `width` columns
+///
+/// Breaks after the last space that fits, or within a word longer than the
+/// row. A width of 0 leaves the line as it is.
+pub fn wrap(text: &str, width: usize) -> Vec<String> {
+    if width == 0 {
+        return vec![text.to_string()];
+    }
+
+    let mut rows = Vec::new();
+    let mut row = String::new();
+    let mut row_width = 0;
+    // Byte offset and width of the row up to its last space
+    let mut last_break: Option<(usize, usize)> = None;
+
+    for c in text.chars() {
+        let char_width = c.width().unwrap_or(0);
+        while row_width + char_width > width && !row.is_empty() {
+            match last_break.take() {
+                Some((offset, break_width)) => {
+                    let rest = row.split_off(offset);
+                    rows.push(std::mem::replace(&mut row, rest));
+                    row_width -= break_width;
+                }
+                None => {
+                    rows.push(std::mem::take(&mut row));
+                    row_width = 0;
+                }
+            }
+        }
+        row.push(c);
+        row_width += char_width;
+        if c == ' ' {
+            last_break = Some((row.len(), row_width));
+        }
+    }
+    rows.push(row);
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wrap_at_spaces() {
+        assert_eq!(wrap("short", 10), vec!["short"]);
+        assert_eq!(
+            wrap("the quick brown fox jumps", 10),
+            vec!["the quick ", "brown fox ", "jumps"]
+        );
+        assert_eq!(wrap("", 10), vec![""]);
+    }
+
+    #[test]
+    fn test_wrap_long_words_and_wide_chars() {
+        assert_eq!(wrap("abcdefghij", 4), vec!["abcd", "efgh", "ij"]);
+        // Double-width characters never straddle a row
+        assert_eq!(
+            wrap("日本語テキスト", 5),
+            vec!["日本", "語テ", "キス", "ト"]
+        );
+    }
+
+    #[test]
+    fn test_wrap_lines_reflows_with_width() {
+        let lines = vec![DisplayLine {
+            text: "one two three".to_string(),
+            selected: true,
+        }];
+        assert_eq!(wrap_lines(&lines, 40).len(), 1);
+
+        let narrow = wrap_lines(&lines, 10);
+        assert_eq!(narrow.len(), 2);
+        assert!(narrow.iter().all(|row| row.selected));
+    }
+}